@@ -0,0 +1,282 @@
+/// Words read out in full.
+const ABBREVIATIONS: &[(&str, &str)] = &[
+    ("e.g.", "for example"),
+    ("i.e.", "that is"),
+    ("etc.", "et cetera"),
+    ("vs.", "versus"),
+    ("vs", "versus"),
+    ("approx.", "approximately"),
+    ("Dr.", "Doctor"),
+    ("Mr.", "Mister"),
+    ("Mrs.", "Missus"),
+    ("&", "and"),
+];
+
+/// Units read out after a number, as (symbol, singular, plural). Matching is
+/// case-sensitive, so "5 GB" expands but "5 gb" is left alone.
+const UNITS: &[(&str, &str, &str)] = &[
+    ("km", "kilometer", "kilometers"),
+    ("m", "meter", "meters"),
+    ("cm", "centimeter", "centimeters"),
+    ("mm", "millimeter", "millimeters"),
+    ("mi", "mile", "miles"),
+    ("ft", "foot", "feet"),
+    ("kg", "kilogram", "kilograms"),
+    ("g", "gram", "grams"),
+    ("mg", "milligram", "milligrams"),
+    ("lb", "pound", "pounds"),
+    ("lbs", "pound", "pounds"),
+    ("L", "liter", "liters"),
+    ("ml", "milliliter", "milliliters"),
+    ("mph", "mile per hour", "miles per hour"),
+    ("km/h", "kilometer per hour", "kilometers per hour"),
+    ("°C", "degree Celsius", "degrees Celsius"),
+    ("°F", "degree Fahrenheit", "degrees Fahrenheit"),
+    ("%", "percent", "percent"),
+    ("ms", "millisecond", "milliseconds"),
+    ("sec", "second", "seconds"),
+    ("min", "minute", "minutes"),
+    ("hr", "hour", "hours"),
+    ("hrs", "hour", "hours"),
+    ("KB", "kilobyte", "kilobytes"),
+    ("MB", "megabyte", "megabytes"),
+    ("GB", "gigabyte", "gigabytes"),
+    ("TB", "terabyte", "terabytes"),
+    ("Hz", "hertz", "hertz"),
+    ("kHz", "kilohertz", "kilohertz"),
+    ("MHz", "megahertz", "megahertz"),
+    ("GHz", "gigahertz", "gigahertz"),
+    ("W", "watt", "watts"),
+    ("kW", "kilowatt", "kilowatts"),
+    ("kWh", "kilowatt hour", "kilowatt hours"),
+];
+
+/// Turns model output into something worth hearing: drops `<think>` blocks
+/// and code, strips markdown, and reads abbreviations and units in full.
+pub fn for_speech(text: &str) -> String {
+    let text = strip_think(text);
+    let mut lines = Vec::new();
+    let mut in_code = false;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code || line.is_empty() {
+            continue;
+        }
+        let line = strip_markdown(line);
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+    // List items and headings become sentences instead of running together.
+    let count = lines.len();
+    let joined = lines
+        .into_iter()
+        .enumerate()
+        .map(|(index, mut line)| {
+            if index + 1 < count && line.ends_with(|c: char| c.is_alphanumeric()) {
+                line.push('.');
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    expand(&joined)
+}
+
+/// Splits after sentence-ending punctuation, the same way streamed answers
+/// are split while they arrive.
+pub fn sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    for c in text.chars() {
+        if c.is_whitespace() && current.ends_with(['.', '!', '?']) {
+            sentences.push(current.trim().to_string());
+            current.clear();
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.trim().is_empty() {
+        sentences.push(current.trim().to_string());
+    }
+    sentences
+}
+
+/// Splits `text` into what to say now (whole sentences, at most `max_chars`)
+/// and the rest. A single overlong sentence is cut at a word boundary.
+/// `max_chars` of 0 means no limit.
+pub fn split_spoken(text: &str, max_chars: usize) -> (String, Option<String>) {
+    if max_chars == 0 || text.chars().count() <= max_chars {
+        return (text.to_string(), None);
+    }
+    let all = sentences(text);
+    let mut now = String::new();
+    let mut taken = 0;
+    for sentence in &all {
+        let len = sentence.chars().count() + usize::from(!now.is_empty());
+        if now.chars().count() + len > max_chars {
+            break;
+        }
+        if !now.is_empty() {
+            now.push(' ');
+        }
+        now.push_str(sentence);
+        taken += 1;
+    }
+    if taken == 0 {
+        let mut words = all[0].split_whitespace();
+        for word in words.by_ref() {
+            if !now.is_empty() && now.chars().count() + 1 + word.chars().count() > max_chars {
+                let rest: Vec<&str> = std::iter::once(word).chain(words).collect();
+                let mut rest = rest.join(" ");
+                for sentence in &all[1..] {
+                    rest.push(' ');
+                    rest.push_str(sentence);
+                }
+                return (now, Some(rest));
+            }
+            if !now.is_empty() {
+                now.push(' ');
+            }
+            now.push_str(word);
+        }
+        taken = 1;
+    }
+    let rest = all[taken..].join(" ");
+    (now, (!rest.is_empty()).then_some(rest))
+}
+
+/// The last `count` lines of a program's `output` that have something in
+/// them, as sentences to read out.
+pub fn last_lines(output: &str, count: usize) -> String {
+    let lines: Vec<&str> = output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let skip = lines.len().saturating_sub(count.max(1));
+    lines[skip..]
+        .iter()
+        .map(|line| {
+            if line.ends_with(['.', '!', '?']) {
+                line.to_string()
+            } else {
+                format!("{}.", line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Removes `<think>...</think>`; an unclosed block runs to the end. Some
+/// chat templates swallow the opening tag, so text before a lone `</think>`
+/// goes too.
+pub fn strip_think(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = match (text.find("<think>"), text.find("</think>")) {
+        (None, Some(end)) => &text[end + "</think>".len()..],
+        (Some(start), Some(end)) if end < start => &text[end + "</think>".len()..],
+        _ => text,
+    };
+    while let Some(start) = rest.find("<think>") {
+        out.push_str(&rest[..start]);
+        match rest[start..].find("</think>") {
+            Some(end) => rest = &rest[start + end + "</think>".len()..],
+            None => return out,
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn strip_markdown(line: &str) -> String {
+    let line = line.trim_start_matches('#').trim_start_matches('>').trim_start();
+    let line = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| line.strip_prefix(bullet))
+        .unwrap_or(line);
+    let mut out = String::new();
+    let mut rest = line;
+    // [text](url) keeps just the text.
+    while let Some(open) = rest.find('[') {
+        let Some(close) = rest[open..].find("](").map(|offset| open + offset) else {
+            break;
+        };
+        let Some(end) = rest[close..].find(')').map(|offset| close + offset) else {
+            break;
+        };
+        out.push_str(&rest[..open]);
+        out.push_str(&rest[open + 1..close]);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    out.chars()
+        .filter(|c| !matches!(c, '*' | '`'))
+        .map(|c| if c == '_' { ' ' } else { c })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn expand(text: &str) -> String {
+    let mut words = Vec::new();
+    // The number just before this word, if it stood on its own ("5 km").
+    let mut number: Option<String> = None;
+    for token in text.split_whitespace() {
+        if let Some((_, full)) = ABBREVIATIONS.iter().find(|(short, _)| *short == token) {
+            words.push(full.to_string());
+            number = None;
+            continue;
+        }
+        let core = token.trim_end_matches([',', ';', ':', '.', '!', '?', ')']);
+        let trailing = &token[core.len()..];
+        let body = core.trim_start_matches('(');
+        let lead = &core[..core.len() - body.len()];
+        let (own_number, unit) = split_number(body);
+        let spoken = match (&own_number, &number) {
+            // "5km", "20°C"
+            (Some(value), _) if !unit.is_empty() => {
+                unit_name(unit, value).map(|name| format!("{}{} {}{}", lead, value, name, trailing))
+            }
+            // "5 km"
+            (None, Some(value)) if lead.is_empty() => {
+                unit_name(body, value).map(|name| format!("{}{}", name, trailing))
+            }
+            _ => None,
+        };
+        number = match &spoken {
+            None if unit.is_empty() && trailing.is_empty() => own_number,
+            _ => None,
+        };
+        words.push(spoken.unwrap_or_else(|| token.to_string()));
+    }
+    words.join(" ")
+}
+
+/// Splits a leading number ("1.5", "1,000", "-3") from whatever follows it.
+fn split_number(token: &str) -> (Option<String>, &str) {
+    let digits_end = token
+        .char_indices()
+        .find(|&(index, c)| {
+            !(c.is_ascii_digit() || ((c == '.' || c == ',') && index > 0) || (c == '-' && index == 0))
+        })
+        .map_or(token.len(), |(index, _)| index);
+    let number = token[..digits_end].trim_end_matches(['.', ',']);
+    if number.chars().any(|c| c.is_ascii_digit()) {
+        (Some(number.to_string()), &token[number.len()..])
+    } else {
+        (None, token)
+    }
+}
+
+fn unit_name(unit: &str, number: &str) -> Option<&'static str> {
+    UNITS
+        .iter()
+        .find(|(symbol, _, _)| *symbol == unit)
+        .map(|(_, singular, plural)| if number == "1" { *singular } else { *plural })
+}
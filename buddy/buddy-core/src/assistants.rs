@@ -0,0 +1,48 @@
+//! "Ask chatgpt what a monad is": questions for an `[assistants]` entry by
+//! name, recognized without the intent model so that it doesn't answer them
+//! itself.
+
+use crate::{config::Config, intent::Intent, rules::EXACT_CONFIDENCE};
+
+/// Longest name tried, in words ("my cloud model").
+const MAX_NAME_WORDS: usize = 4;
+
+/// Recognizes "ask `name` `question`" and "ask the `name` `question`".
+/// Names are matched ignoring case, spaces and punctuation, so "Chat GPT"
+/// finds `chatgpt` and "cloud model" finds `cloud_model`.
+pub fn recognize(transcript: &str, config: &Config) -> Option<Intent> {
+    if config.assistants.is_empty() {
+        return None;
+    }
+    let words: Vec<&str> = transcript.split_whitespace().collect();
+    let (first, rest) = words.split_first()?;
+    if squash(first) != "ask" {
+        return None;
+    }
+    let rest = match rest {
+        [the, rest @ ..] if squash(the) == "the" => rest,
+        _ => rest,
+    };
+    // The longest name wins: "chat gpt four" over "chat gpt".
+    (1..=rest.len().min(MAX_NAME_WORDS)).rev().find_map(|len| {
+        let spoken = squash(&rest[..len].concat());
+        let name = config.assistants.keys().find(|key| squash(key) == spoken)?;
+        let question = rest[len..]
+            .join(" ")
+            .trim_start_matches([',', ':', '-', ' '])
+            .to_string();
+        (!question.is_empty()).then(|| Intent::Ask {
+            assistant: name.clone(),
+            question,
+            confidence: EXACT_CONFIDENCE,
+        })
+    })
+}
+
+/// Lowercase letters and digits only.
+fn squash(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
@@ -0,0 +1,992 @@
+use crate::{
+    config::{AudioConfig, AudioSource},
+    windows_api,
+};
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    Device, Sample, SampleFormat, SampleRate, SizedSample, StreamConfig,
+};
+use rodio::{Decoder, Source};
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::BufReader,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicI16, AtomicU64, AtomicUsize, Ordering},
+        mpsc::Sender,
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+pub struct AudioCapturer {
+    /// `audio.device_name`; `None` follows the system default.
+    preferred: Option<String>,
+    requested_rate: u32,
+    input: Mutex<Input>,
+    /// Set when the recording device changes, until main reports it.
+    device_change: Mutex<Option<DeviceChange>>,
+    silence_stop_secs: u64,
+    min_speech_secs: u64,
+    silence_threshold: i16,
+    noise_floor_secs: u64,
+    silence_floor_multiplier: f32,
+    silence_floor_offset: i16,
+    levels: Option<Mutex<LevelMonitor>>,
+    agc: Option<Agc>,
+    /// Samples lost to capture-buffer overflow since startup.
+    dropped_samples: AtomicU64,
+    /// Set (e.g. by the cancel hotkey) to stop the recording in progress.
+    cancel: Arc<AtomicBool>,
+    debug: bool,
+}
+
+/// The device Buddy records from and the stream settings chosen for it.
+/// Replaced when the device disappears or the preferred one comes back.
+struct Input {
+    device: Device,
+    name: String,
+    /// Opened from `audio.device_name` rather than as a fallback.
+    preferred: bool,
+    config: StreamConfig,
+    sample_format: SampleFormat,
+    channels: usize,
+    sample_rate: u32,
+}
+
+/// A switch between input devices, reported once after the capture that
+/// noticed it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceChange {
+    /// The configured microphone is gone; recording from this one instead.
+    Fallback(String),
+    /// The configured microphone is back.
+    Reconnected(String),
+}
+
+impl DeviceChange {
+    pub fn message(&self) -> String {
+        match self {
+            Self::Fallback(name) => format!("Microphone disconnected. Using {} instead.", name),
+            Self::Reconnected(name) => format!("Microphone {} reconnected.", name),
+        }
+    }
+}
+
+/// Automatic gain control: lifts quiet speech toward `target_rms` (a fraction
+/// of full scale), never by more than `max_gain`, and pulls hot recordings
+/// back below `AGC_PEAK_LIMIT` so the gain itself never clips.
+#[derive(Debug, Clone, Copy)]
+struct Agc {
+    target_rms: f32,
+    max_gain: f32,
+}
+
+/// Highest peak (fraction of full scale) AGC leaves in a recording.
+const AGC_PEAK_LIMIT: f32 = 0.8;
+/// Speech RMS below this is treated as silence and left alone.
+const AGC_MIN_RMS: f64 = 30.0;
+/// AGC measures loudness over frames of this length (ms) ...
+const AGC_FRAME_MS: u32 = 20;
+/// ... and only over the loudest fraction of them, so pauses between words
+/// do not count as quiet speech.
+const AGC_SPEECH_FRACTION: f64 = 0.5;
+
+impl Agc {
+    /// Scales `samples` in place and returns the gain applied.
+    fn apply(&self, samples: &mut [i16], sample_rate: u32) -> f32 {
+        let (peak, _) = peak_rms(samples);
+        let rms = speech_rms(samples, sample_rate);
+        if peak == 0 || rms < AGC_MIN_RMS {
+            return 1.0;
+        }
+        let wanted =
+            (self.target_rms * i16::MAX as f32 / rms as f32).clamp(1.0, self.max_gain.max(1.0));
+        let gain = wanted.min(AGC_PEAK_LIMIT * i16::MAX as f32 / peak as f32);
+        if (gain - 1.0).abs() > f32::EPSILON {
+            for sample in samples.iter_mut() {
+                *sample = (*sample as f32 * gain).clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+            }
+        }
+        gain
+    }
+}
+
+/// RMS over the loudest frames of a recording.
+fn speech_rms(samples: &[i16], sample_rate: u32) -> f64 {
+    let frame = (sample_rate * AGC_FRAME_MS / 1000).max(1) as usize;
+    let mut energies: Vec<f64> = samples
+        .chunks(frame)
+        .map(|chunk| {
+            chunk.iter().map(|&s| (s as f64) * (s as f64)).sum::<f64>() / chunk.len() as f64
+        })
+        .collect();
+    if energies.is_empty() {
+        return 0.0;
+    }
+    energies.sort_by(|a, b| b.total_cmp(a));
+    let loud = ((energies.len() as f64 * AGC_SPEECH_FRACTION).ceil() as usize).max(1);
+    (energies[..loud].iter().sum::<f64>() / loud as f64).sqrt()
+}
+
+/// Input level problem spotted across recent utterances.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelWarning {
+    Clipping,
+    TooQuiet,
+}
+
+impl LevelWarning {
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::Clipping => {
+                "Your microphone seems too loud and is clipping. Try lowering the input gain."
+            }
+            Self::TooQuiet => {
+                "Your microphone seems too quiet. Try raising the input gain or moving closer."
+            }
+        }
+    }
+}
+
+/// Samples at or beyond this magnitude are counted as clipped.
+const CLIP_LEVEL: u16 = 32_700;
+/// An utterance with more than this fraction of clipped samples is "clipped".
+const CLIP_RATIO: f64 = 0.001;
+/// An utterance whose peak stays under this (~5% of full scale) is "quiet".
+const QUIET_PEAK: u16 = 1_600;
+/// Number of recent utterances kept for the running statistics.
+const LEVEL_HISTORY: usize = 5;
+/// Clipped utterances within the history needed before warning.
+const CLIPPED_TO_WARN: usize = 2;
+/// Consecutive quiet utterances needed before warning.
+const QUIET_TO_WARN: usize = 3;
+
+#[derive(Debug, Clone, Copy)]
+struct UtteranceLevel {
+    clipped: bool,
+    quiet: bool,
+}
+
+/// Running per-utterance statistics used to spot a mic that is persistently
+/// too hot or too quiet, rather than reacting to a single odd capture.
+#[derive(Debug, Default)]
+struct LevelMonitor {
+    history: VecDeque<UtteranceLevel>,
+    pending: Option<LevelWarning>,
+}
+
+impl LevelMonitor {
+    fn observe(&mut self, samples: &[i16]) {
+        if samples.is_empty() {
+            return;
+        }
+        let clipped = samples
+            .iter()
+            .filter(|sample| sample.unsigned_abs() >= CLIP_LEVEL)
+            .count();
+        let peak = samples
+            .iter()
+            .map(|sample| sample.unsigned_abs())
+            .max()
+            .unwrap_or(0);
+        if self.history.len() == LEVEL_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(UtteranceLevel {
+            clipped: clipped as f64 / samples.len() as f64 > CLIP_RATIO,
+            quiet: peak < QUIET_PEAK,
+        });
+
+        let clipped_count = self.history.iter().filter(|level| level.clipped).count();
+        let quiet_run = self
+            .history
+            .iter()
+            .rev()
+            .take_while(|level| level.quiet)
+            .count();
+        let warning = if clipped_count >= CLIPPED_TO_WARN {
+            Some(LevelWarning::Clipping)
+        } else if quiet_run >= QUIET_TO_WARN {
+            Some(LevelWarning::TooQuiet)
+        } else {
+            None
+        };
+        if warning.is_some() {
+            // Start over so the same problem is not reported after every command.
+            self.history.clear();
+            self.pending = warning;
+        }
+    }
+}
+
+pub fn print_input_devices() -> Result<(), AudioError> {
+    let host = cpal::default_host();
+    let mut devices = host.input_devices().map_err(AudioError::Devices)?;
+    let mut index = 0;
+    while let Some(device) = devices.next() {
+        let name = device
+            .name()
+            .unwrap_or_else(|_| format!("Input Device {}", index));
+        println!("Input {}: {}", index, name);
+
+        match device.default_input_config() {
+            Ok(default_cfg) => {
+                let cfg = default_cfg.config();
+                println!(
+                    "  Default: {} ch, {:?}, {} Hz",
+                    cfg.channels, default_cfg.sample_format(), cfg.sample_rate.0
+                );
+            }
+            Err(err) => {
+                eprintln!("  Default config error: {}", err);
+            }
+        }
+
+        match device.supported_input_configs() {
+            Ok(configs) => {
+                for cfg in configs {
+                    println!(
+                        "  Supported: {} ch, {:?}, {}-{} Hz",
+                        cfg.channels(),
+                        cfg.sample_format(),
+                        cfg.min_sample_rate().0,
+                        cfg.max_sample_rate().0
+                    );
+                }
+            }
+            Err(err) => {
+                eprintln!("  Supported config error: {}", err);
+            }
+        }
+        index += 1;
+    }
+    Ok(())
+}
+
+impl AudioCapturer {
+    pub fn new(cfg: &AudioConfig, debug: bool) -> Result<Self, AudioError> {
+        let preferred = cfg.device_name.clone();
+        let mut device_change = None;
+        let input = match open_input(preferred.as_deref(), cfg.sample_rate, debug) {
+            Err(AudioError::DeviceNotFound(name)) => {
+                let input = open_input(None, cfg.sample_rate, debug)?;
+                eprintln!("Input device '{}' not found; using {}", name, input.name);
+                device_change = Some(DeviceChange::Fallback(input.name.clone()));
+                input
+            }
+            other => other?,
+        };
+        Ok(Self::with_input(cfg, preferred, input, device_change, debug))
+    }
+
+    /// Opens `source` for `--transcribe`: the configured microphone, or a
+    /// loopback of what the PC is playing.
+    pub fn for_source(
+        cfg: &AudioConfig,
+        source: AudioSource,
+        debug: bool,
+    ) -> Result<Self, AudioError> {
+        match source {
+            AudioSource::Microphone => Self::new(cfg, debug),
+            AudioSource::Loopback => {
+                let input = open_loopback(debug)?;
+                Ok(Self::with_input(cfg, None, input, None, debug))
+            }
+        }
+    }
+
+    fn with_input(
+        cfg: &AudioConfig,
+        preferred: Option<String>,
+        input: Input,
+        device_change: Option<DeviceChange>,
+        debug: bool,
+    ) -> Self {
+        Self {
+            preferred,
+            requested_rate: cfg.sample_rate,
+            input: Mutex::new(input),
+            device_change: Mutex::new(device_change),
+            silence_stop_secs: cfg.silence_stop_secs,
+            min_speech_secs: cfg.min_speech_secs,
+            silence_threshold: cfg.silence_threshold,
+            noise_floor_secs: cfg.noise_floor_secs,
+            silence_floor_multiplier: cfg.silence_floor_multiplier,
+            silence_floor_offset: cfg.silence_floor_offset,
+            levels: cfg
+                .level_warnings
+                .then(|| Mutex::new(LevelMonitor::default())),
+            agc: cfg.agc.then_some(Agc {
+                target_rms: cfg.agc_target_rms,
+                max_gain: cfg.agc_max_gain,
+            }),
+            dropped_samples: AtomicU64::new(0),
+            cancel: Arc::new(AtomicBool::new(false)),
+            debug,
+        }
+    }
+
+    /// Records one utterance at 16 kHz. If the device can't be opened, the
+    /// devices are enumerated again and the recording retried on whatever is
+    /// available; a device lost mid-recording is replaced for next time and
+    /// reported as `AudioError::DeviceLost`.
+    pub fn capture(&self, max_duration: Option<Duration>) -> Result<Vec<i16>, AudioError> {
+        if windows_api::mic_muted_by_buddy() {
+            return Err(AudioError::MicMuted);
+        }
+        self.reconnect_preferred();
+        let mut input = self.input();
+        let mut data = match self.record(&input, max_duration) {
+            Err(err @ (AudioError::BuildStream(_) | AudioError::PlayStream(_))) => {
+                eprintln!("Recording from {} failed: {}", input.name, err);
+                self.switch_device(&mut input)?;
+                self.record(&input, max_duration)?
+            }
+            Err(AudioError::DeviceLost) => {
+                self.switch_device(&mut input)?;
+                return Err(AudioError::DeviceLost);
+            }
+            other => other?,
+        };
+        let sample_rate = input.sample_rate;
+        drop(input);
+
+        if let Some(levels) = &self.levels {
+            if let Ok(mut levels) = levels.lock() {
+                levels.observe(&data);
+            }
+        }
+
+        let gain = match &self.agc {
+            Some(agc) if !data.is_empty() => agc.apply(&mut data, sample_rate),
+            _ => 1.0,
+        };
+        if self.debug && !data.is_empty() {
+            let (peak, rms) = peak_rms(&data);
+            let peak_pct = (peak as f64 / i16::MAX as f64) * 100.0;
+            let rms_pct = (rms / i16::MAX as f64) * 100.0;
+            if (gain - 1.0).abs() > f32::EPSILON {
+                println!(
+                    "Audio level: peak {:.1}%, rms {:.1}% (gain {:.2}x)",
+                    peak_pct, rms_pct, gain
+                );
+            } else {
+                println!("Audio level: peak {:.1}%, rms {:.1}%", peak_pct, rms_pct);
+            }
+        }
+
+        if sample_rate != 16_000 && data.len() > 1 {
+            data = resample_linear(&data, sample_rate, 16_000);
+        }
+
+        Ok(data)
+    }
+
+    /// Returns (and clears) the device switch made by recent captures.
+    pub fn take_device_change(&self) -> Option<DeviceChange> {
+        self.device_change
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+    }
+
+    fn input(&self) -> MutexGuard<'_, Input> {
+        self.input.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn report(&self, change: DeviceChange) {
+        println!("{}", change.message());
+        *self
+            .device_change
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(change);
+    }
+
+    /// Moves back to `audio.device_name` once it shows up again.
+    fn reconnect_preferred(&self) {
+        let Some(preferred) = &self.preferred else {
+            return;
+        };
+        let mut input = self.input();
+        if input.preferred {
+            return;
+        }
+        if let Ok(reconnected) = open_input(Some(preferred), self.requested_rate, self.debug) {
+            self.report(DeviceChange::Reconnected(reconnected.name.clone()));
+            *input = reconnected;
+        }
+    }
+
+    /// Re-enumerates after `input` failed: the preferred device if it is
+    /// still there, otherwise the current default.
+    fn switch_device(&self, input: &mut Input) -> Result<(), AudioError> {
+        let preferred = self.preferred.as_deref();
+        let next = open_input(preferred, self.requested_rate, self.debug)
+            .or_else(|_| open_input(None, self.requested_rate, self.debug))?;
+        // The same name means the device blinked out and came back.
+        if next.name != input.name {
+            self.report(DeviceChange::Fallback(next.name.clone()));
+        }
+        *input = next;
+        Ok(())
+    }
+
+    /// Records until `stop` is set, sending 16 kHz chunks to `chunks` as they
+    /// fill. A chunk ends at the first pause after `CHUNK_MIN_SECS`, or at
+    /// `CHUNK_MAX_SECS` if nobody pauses, so words are rarely split.
+    pub fn record_chunks(&self, stop: &AtomicBool, chunks: &Sender<Vec<i16>>) -> Result<(), AudioError> {
+        let input = self.input();
+        let ring = Arc::new(SampleRing::new(input.sample_rate as usize * RING_SECS));
+        let lost = Arc::new(AtomicBool::new(false));
+        let stream = open_stream(&input, Arc::clone(&ring), Arc::clone(&lost))?;
+        stream.play().map_err(AudioError::PlayStream)?;
+        let rate = input.sample_rate as usize;
+        let window = (rate / 20).max(1);
+        let send = |chunk: Vec<i16>| {
+            let chunk = if rate != 16_000 && chunk.len() > 1 {
+                resample_linear(&chunk, input.sample_rate, 16_000)
+            } else {
+                chunk
+            };
+            chunks.send(chunk).is_ok()
+        };
+        let mut chunk = Vec::new();
+        while !stop.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(50));
+            ring.drain_into(&mut chunk);
+            if lost.load(Ordering::Relaxed) {
+                send(chunk);
+                return Err(AudioError::DeviceLost);
+            }
+            let secs = chunk.len() / rate;
+            let paused = chunk.len() >= window
+                && window_level(&chunk[chunk.len() - window..]) < self.silence_threshold.max(1);
+            if (secs >= CHUNK_MAX_SECS || (secs >= CHUNK_MIN_SECS && paused))
+                && !send(std::mem::take(&mut chunk))
+            {
+                return Ok(());
+            }
+        }
+        drop(stream);
+        ring.drain_into(&mut chunk);
+        if !chunk.is_empty() {
+            send(chunk);
+        }
+        Ok(())
+    }
+
+    /// Records `length` at 16 kHz, or less if `stop` is set first, without
+    /// waiting for a pause. The device is only locked while the stream opens,
+    /// so a command can start recording while a window is still open.
+    pub fn capture_window(
+        &self,
+        length: Duration,
+        stop: &AtomicBool,
+    ) -> Result<Vec<i16>, AudioError> {
+        let (stream, ring, lost, sample_rate) = {
+            let input = self.input();
+            let ring = Arc::new(SampleRing::new(input.sample_rate as usize * RING_SECS));
+            let lost = Arc::new(AtomicBool::new(false));
+            let stream = open_stream(&input, Arc::clone(&ring), Arc::clone(&lost))?;
+            (stream, ring, lost, input.sample_rate)
+        };
+        stream.play().map_err(AudioError::PlayStream)?;
+        let start = Instant::now();
+        let mut buf = Vec::new();
+        while start.elapsed() < length && !stop.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(50));
+            ring.drain_into(&mut buf);
+            if lost.load(Ordering::Relaxed) {
+                return Err(AudioError::DeviceLost);
+            }
+        }
+        drop(stream);
+        ring.drain_into(&mut buf);
+        if sample_rate != 16_000 && buf.len() > 1 {
+            buf = resample_linear(&buf, sample_rate, 16_000);
+        }
+        Ok(buf)
+    }
+
+    /// Flag that aborts the current capture with `AudioError::Cancelled`.
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.cancel)
+    }
+
+    /// Returns (and clears) a level warning raised by recent captures.
+    pub fn take_level_warning(&self) -> Option<LevelWarning> {
+        self.levels
+            .as_ref()
+            .and_then(|levels| levels.lock().ok())
+            .and_then(|mut levels| levels.pending.take())
+    }
+
+    fn record(&self, input: &Input, max_duration: Option<Duration>) -> Result<Vec<i16>, AudioError> {
+        // A cancel pressed while idle must not cut off this recording.
+        self.cancel.store(false, Ordering::Relaxed);
+        let ring = Arc::new(SampleRing::new(input.sample_rate as usize * RING_SECS));
+        let lost = Arc::new(AtomicBool::new(false));
+        let stream = open_stream(input, Arc::clone(&ring), Arc::clone(&lost))?;
+
+        let expected_secs =
+            max_duration.map_or(UNBOUNDED_CAPTURE_SECS, |limit| limit.as_secs_f64());
+        let mut buf = Vec::with_capacity((input.sample_rate as f64 * expected_secs) as usize);
+        stream.play().map_err(AudioError::PlayStream)?;
+        let start = Instant::now();
+        let min_duration = Duration::from_secs(self.min_speech_secs);
+        let silence_duration = Duration::from_secs(self.silence_stop_secs);
+        let noise_floor_duration = Duration::from_secs(self.noise_floor_secs.max(1));
+        let poll_interval = Duration::from_millis(50);
+        let silence_threshold = self.silence_threshold.max(1);
+        let window_samples = ((input.sample_rate as f64) * poll_interval.as_secs_f64()) as usize;
+        let mut last_sound = start;
+        let mut heard_sound = false;
+        let mut noise_floor: i16 = 0;
+        let mut cancelled = false;
+        loop {
+            thread::sleep(poll_interval);
+            if self.cancel.swap(false, Ordering::Relaxed) {
+                cancelled = true;
+                break;
+            }
+            if lost.load(Ordering::Relaxed) {
+                return Err(AudioError::DeviceLost);
+            }
+            let elapsed = start.elapsed();
+            if let Some(limit) = max_duration {
+                if elapsed >= limit {
+                    break;
+                }
+            }
+            ring.drain_into(&mut buf);
+            if !buf.is_empty() {
+                let start_idx = buf.len().saturating_sub(window_samples.max(1));
+                let level = window_level(&buf[start_idx..]);
+                if elapsed <= noise_floor_duration {
+                    noise_floor = noise_floor.max(level);
+                }
+                let dynamic_threshold = (noise_floor as f32 * self.silence_floor_multiplier)
+                    .round() as i16
+                    + self.silence_floor_offset;
+                let active_threshold = silence_threshold.max(dynamic_threshold);
+                let has_sound = level >= active_threshold;
+                if has_sound {
+                    last_sound = Instant::now();
+                    heard_sound = true;
+                }
+            }
+            if !heard_sound && elapsed >= silence_duration && silence_duration.as_secs() > 0 {
+                break;
+            }
+            if heard_sound && elapsed >= min_duration && last_sound.elapsed() >= silence_duration {
+                break;
+            }
+        }
+        drop(stream);
+        if cancelled {
+            return Err(AudioError::Cancelled);
+        }
+        ring.drain_into(&mut buf);
+
+        let dropped = ring.dropped();
+        self.dropped_samples.fetch_add(dropped as u64, Ordering::Relaxed);
+        if self.debug {
+            println!(
+                "Capture buffer: {} samples, {} dropped ({} dropped this session)",
+                buf.len(),
+                dropped,
+                self.dropped_samples.load(Ordering::Relaxed)
+            );
+        }
+        Ok(buf)
+    }
+}
+
+/// Opens the input device picked by `name` (see `find_device`; the system
+/// default for `None`) at the rate closest to `requested_rate` it supports.
+fn open_input(name: Option<&str>, requested_rate: u32, debug: bool) -> Result<Input, AudioError> {
+    let host = cpal::default_host();
+    let device = if let Some(name) = name {
+        find_device(&host, name)?
+    } else {
+        host.default_input_device()
+            .ok_or(AudioError::NoDefaultDevice)?
+    };
+
+    let supported = device
+        .default_input_config()
+        .map_err(AudioError::DefaultConfig)?;
+    let sample_format = supported.sample_format();
+    let mut stream_config: StreamConfig = supported.config().clone();
+    let selected_rate = select_sample_rate(&device, sample_format, stream_config.channels, requested_rate)
+        .unwrap_or(stream_config.sample_rate.0);
+    stream_config.sample_rate = SampleRate(selected_rate);
+    let channels = stream_config.channels as usize;
+    let device_name = device
+        .name()
+        .unwrap_or_else(|_| "Unknown input device".to_string());
+    if debug {
+        if selected_rate != requested_rate {
+            println!(
+                "Requested {} Hz not supported; using {} Hz",
+                requested_rate, selected_rate
+            );
+        }
+        println!(
+            "Using input device: {} ({} ch @ {} Hz, {:?})",
+            device_name,
+            channels,
+            stream_config.sample_rate.0,
+            supported.sample_format()
+        );
+    }
+    Ok(Input {
+        device,
+        name: device_name,
+        preferred: name.is_some(),
+        sample_rate: stream_config.sample_rate.0,
+        config: stream_config,
+        sample_format,
+        channels,
+    })
+}
+
+/// The default output device, recorded through WASAPI loopback: cpal opens
+/// a loopback stream when an input stream is built on an output device.
+#[cfg(windows)]
+fn open_loopback(debug: bool) -> Result<Input, AudioError> {
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or_else(|| AudioError::Loopback("no default output device".to_string()))?;
+    // Loopback runs at the shared mix format; there's no rate to pick.
+    let supported = device
+        .default_output_config()
+        .map_err(AudioError::DefaultConfig)?;
+    let config: StreamConfig = supported.config();
+    let name = device
+        .name()
+        .unwrap_or_else(|_| "Unknown output device".to_string());
+    if debug {
+        println!(
+            "Recording output of: {} ({} ch @ {} Hz, {:?})",
+            name,
+            config.channels,
+            config.sample_rate.0,
+            supported.sample_format()
+        );
+    }
+    Ok(Input {
+        device,
+        name,
+        preferred: false,
+        sample_format: supported.sample_format(),
+        channels: config.channels as usize,
+        sample_rate: config.sample_rate.0,
+        config,
+    })
+}
+
+#[cfg(not(windows))]
+fn open_loopback(_debug: bool) -> Result<Input, AudioError> {
+    Err(AudioError::Loopback(
+        "needs Windows; elsewhere set device_name to a monitor source (e.g. \"Monitor of ...\")"
+            .to_string(),
+    ))
+}
+
+/// Builds a (paused) stream that mixes `input` down to mono i16 into `ring`
+/// and sets `lost` if the device goes away.
+fn open_stream(
+    input: &Input,
+    ring: Arc<SampleRing>,
+    lost: Arc<AtomicBool>,
+) -> Result<cpal::Stream, AudioError> {
+    match input.sample_format {
+        SampleFormat::I16 => open_stream_with_type::<i16, _>(input, ring, lost, |sample| sample),
+        SampleFormat::U16 => open_stream_with_type::<u16, _>(input, ring, lost, |sample| {
+            let centered = sample as i32 - i16::MAX as i32 - 1;
+            centered as i16
+        }),
+        SampleFormat::F32 => open_stream_with_type::<f32, _>(input, ring, lost, |sample| {
+            let clamped = sample.max(-1.0).min(1.0);
+            (clamped * i16::MAX as f32) as i16
+        }),
+        _ => Err(AudioError::UnsupportedFormat(input.sample_format)),
+    }
+}
+
+fn open_stream_with_type<T, F>(
+    input: &Input,
+    writer: Arc<SampleRing>,
+    lost: Arc<AtomicBool>,
+    convert: F,
+) -> Result<cpal::Stream, AudioError>
+where
+    T: Sample + SizedSample + Send + 'static,
+    F: Fn(T) -> i16 + Send + Sync + 'static,
+{
+    let err_fn = move |err| match err {
+        cpal::StreamError::DeviceNotAvailable => lost.store(true, Ordering::Relaxed),
+        err => eprintln!("audio stream error: {}", err),
+    };
+    let channels = input.channels.max(1);
+    input
+        .device
+        .build_input_stream(
+            &input.config,
+            move |data: &[T], _| {
+                if channels == 1 {
+                    writer.push(data.iter().map(|sample| convert(*sample)));
+                } else {
+                    writer.push(data.chunks_exact(channels).map(|frame| {
+                        let sum: i32 = frame.iter().map(|sample| convert(*sample) as i32).sum();
+                        (sum / channels as i32) as i16
+                    }));
+                }
+            },
+            err_fn,
+            None,
+        )
+        .map_err(AudioError::BuildStream)
+}
+
+/// Finds an input device by its exact name, its index in `--list-audio`, or
+/// a case-insensitive part of its name, in that order, so "realtek" picks
+/// "Mikrofonarray (Realtek(R) Audio)".
+fn find_device(host: &cpal::Host, spec: &str) -> Result<Device, AudioError> {
+    let mut devices: Vec<(String, Device)> = host
+        .input_devices()
+        .map_err(AudioError::Devices)?
+        .enumerate()
+        .map(|(index, device)| {
+            let name = device
+                .name()
+                .unwrap_or_else(|_| format!("Input Device {}", index));
+            (name, device)
+        })
+        .collect();
+    let spec = spec.trim();
+    let lower = spec.to_lowercase();
+    let position = devices
+        .iter()
+        .position(|(name, _)| name == spec)
+        .or_else(|| spec.parse::<usize>().ok().filter(|index| *index < devices.len()))
+        .or_else(|| {
+            devices
+                .iter()
+                .position(|(name, _)| name.to_lowercase().contains(&lower))
+        })
+        .ok_or_else(|| AudioError::DeviceNotFound(spec.to_string()))?;
+    Ok(devices.swap_remove(position).1)
+}
+
+/// Seconds of audio the capture ring holds between polls. The poll loop
+/// drains it every 50 ms, so this only overflows if that thread stalls.
+const RING_SECS: usize = 2;
+/// `record_chunks` ends a chunk at the first pause after this many seconds...
+const CHUNK_MIN_SECS: usize = 15;
+/// ... or here, whether or not there was one.
+const CHUNK_MAX_SECS: usize = 30;
+/// Capacity reserved for the recording when no capture limit is configured.
+const UNBOUNDED_CAPTURE_SECS: f64 = 30.0;
+
+/// Fixed-size single-producer/single-consumer queue between the audio
+/// callback and the capture loop. The callback never locks or allocates;
+/// when the ring is full, new samples are dropped and counted instead.
+struct SampleRing {
+    slots: Box<[AtomicI16]>,
+    /// Total samples written; only the producer stores it.
+    head: AtomicUsize,
+    /// Total samples read; only the consumer stores it.
+    tail: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+impl SampleRing {
+    fn new(capacity: usize) -> Self {
+        Self {
+            slots: (0..capacity.max(1)).map(|_| AtomicI16::new(0)).collect(),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0),
+        }
+    }
+
+    /// Producer side, called from the audio callback.
+    fn push(&self, samples: impl Iterator<Item = i16>) {
+        let capacity = self.slots.len();
+        let head = self.head.load(Ordering::Relaxed);
+        let free = capacity - head.wrapping_sub(self.tail.load(Ordering::Acquire));
+        let mut written = 0;
+        let mut dropped = 0;
+        for sample in samples {
+            if written < free {
+                let slot = head.wrapping_add(written) % capacity;
+                self.slots[slot].store(sample, Ordering::Relaxed);
+                written += 1;
+            } else {
+                dropped += 1;
+            }
+        }
+        self.head.store(head.wrapping_add(written), Ordering::Release);
+        if dropped > 0 {
+            self.dropped.fetch_add(dropped, Ordering::Relaxed);
+        }
+    }
+
+    /// Consumer side: moves everything written so far into `out`.
+    fn drain_into(&self, out: &mut Vec<i16>) {
+        let capacity = self.slots.len();
+        let tail = self.tail.load(Ordering::Relaxed);
+        let head = self.head.load(Ordering::Acquire);
+        let available = head.wrapping_sub(tail);
+        out.extend((0..available).map(|offset| {
+            self.slots[tail.wrapping_add(offset) % capacity].load(Ordering::Relaxed)
+        }));
+        self.tail.store(head, Ordering::Release);
+    }
+
+    fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+fn window_level(samples: &[i16]) -> i16 {
+    if samples.is_empty() {
+        return 0;
+    }
+    let sum: i64 = samples.iter().map(|s| i64::from(s.abs())).sum();
+    (sum / samples.len() as i64).min(i16::MAX as i64) as i16
+}
+
+fn select_sample_rate(
+    device: &Device,
+    sample_format: SampleFormat,
+    channels: u16,
+    requested: u32,
+) -> Option<u32> {
+    let configs = device.supported_input_configs().ok()?;
+    for cfg in configs {
+        if cfg.sample_format() != sample_format || cfg.channels() != channels {
+            continue;
+        }
+        if requested >= cfg.min_sample_rate().0 && requested <= cfg.max_sample_rate().0 {
+            return Some(requested);
+        }
+    }
+    None
+}
+
+pub fn peak_rms(samples: &[i16]) -> (i16, f64) {
+    let (peak, sum_sq) = samples
+        .iter()
+        .fold((0i16, 0u64), |(peak, sum_sq), &sample| {
+            let abs = sample.abs();
+            let peak = peak.max(abs);
+            let sum_sq = sum_sq + (sample as i32).saturating_mul(sample as i32) as u64;
+            (peak, sum_sq)
+        });
+    let rms = (sum_sq as f64 / samples.len() as f64).sqrt();
+    (peak, rms)
+}
+
+/// Decodes an audio file into the 16 kHz mono samples whisper expects.
+/// `.pcm`/`.raw` files are taken as headerless 16 kHz mono 16-bit little-endian.
+pub fn load_clip(path: &Path) -> Result<Vec<i16>, AudioError> {
+    let raw = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pcm") || ext.eq_ignore_ascii_case("raw"));
+    if raw {
+        let bytes = std::fs::read(path).map_err(|err| AudioError::Clip(err.to_string()))?;
+        return Ok(bytes
+            .chunks_exact(2)
+            .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+            .collect());
+    }
+    let file = File::open(path).map_err(|err| AudioError::Clip(err.to_string()))?;
+    let decoder =
+        Decoder::new(BufReader::new(file)).map_err(|err| AudioError::Clip(err.to_string()))?;
+    let channels = usize::from(decoder.channels().max(1));
+    let sample_rate = decoder.sample_rate();
+    let samples: Vec<i16> = decoder.collect();
+    let mono: Vec<i16> = samples
+        .chunks(channels)
+        .map(|frame| (frame.iter().map(|&s| s as i32).sum::<i32>() / frame.len() as i32) as i16)
+        .collect();
+    Ok(resample_linear(&mono, sample_rate, 16_000))
+}
+
+fn resample_linear(samples: &[i16], src_rate: u32, dst_rate: u32) -> Vec<i16> {
+    if src_rate == dst_rate || samples.len() < 2 {
+        return samples.to_vec();
+    }
+    if src_rate % dst_rate == 0 {
+        let factor = (src_rate / dst_rate) as usize;
+        if factor > 1 {
+            let mut out = Vec::with_capacity(samples.len() / factor);
+            for chunk in samples.chunks_exact(factor) {
+                let sum: i32 = chunk.iter().map(|&s| s as i32).sum();
+                out.push((sum / factor as i32) as i16);
+            }
+            return out;
+        }
+    }
+    let ratio = dst_rate as f64 / src_rate as f64;
+    let out_len = ((samples.len() as f64) * ratio).max(1.0) as usize;
+    let mut out = Vec::with_capacity(out_len);
+    for i in 0..out_len {
+        let pos = i as f64 / ratio;
+        let idx = pos.floor() as usize;
+        let frac = (pos - idx as f64) as f32;
+        let s0 = samples[idx] as f32;
+        let s1 = samples.get(idx + 1).copied().unwrap_or(samples[idx]) as f32;
+        let sample = s0 + (s1 - s0) * frac;
+        out.push(sample as i16);
+    }
+    out
+}
+
+#[derive(Debug)]
+pub enum AudioError {
+    Devices(cpal::DevicesError),
+    DeviceNotFound(String),
+    NoDefaultDevice,
+    DefaultConfig(cpal::DefaultStreamConfigError),
+    UnsupportedFormat(SampleFormat),
+    BuildStream(cpal::BuildStreamError),
+    PlayStream(cpal::PlayStreamError),
+    Clip(String),
+    Cancelled,
+    /// The device went away mid-recording.
+    DeviceLost,
+    Loopback(String),
+    /// "Mute my mic" muted the microphone, so there is nothing to record.
+    MicMuted,
+}
+
+impl std::fmt::Display for AudioError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Devices(err) => write!(f, "device query error: {}", err),
+            Self::DeviceNotFound(name) => write!(f, "input device '{}' not found", name),
+            Self::NoDefaultDevice => write!(f, "no default input device available"),
+            Self::DefaultConfig(err) => write!(f, "failed to read default config: {}", err),
+            Self::UnsupportedFormat(fmt) => write!(f, "unsupported sample format: {:?}", fmt),
+            Self::BuildStream(err) => write!(f, "failed building stream: {}", err),
+            Self::PlayStream(err) => write!(f, "failed starting stream: {}", err),
+            Self::Clip(err) => write!(f, "failed reading audio clip: {}", err),
+            Self::Cancelled => write!(f, "recording cancelled"),
+            Self::DeviceLost => write!(f, "input device disconnected"),
+            Self::Loopback(reason) => write!(f, "loopback capture unavailable: {}", reason),
+            Self::MicMuted => write!(
+                f,
+                "the microphone is muted; unmute it in Windows or type ':text unmute my mic'"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AudioError {}
@@ -0,0 +1,375 @@
+use crate::{
+    config::{Config, LoggingConfig},
+    executor::{ExecutionError, ExecutionResult},
+    intent::Intent,
+};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Bytes in a key made by `record`.
+const KEY_BYTES: usize = 32;
+
+/// `prev` of the first entry.
+const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// What set a command in motion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Hotkey,
+    Ipc,
+    Http,
+    Console,
+    /// `--from-wav --execute`.
+    Replay,
+    /// `Pipeline::execute` in an app embedding `buddy_core`.
+    Library,
+    /// A command run at a set time rather than when asked, by an app
+    /// embedding `buddy_core` that executes through
+    /// `Actions::with_source(Source::Schedule)`.
+    Schedule,
+}
+
+impl Source {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Hotkey => "hotkey",
+            Self::Ipc => "ipc",
+            Self::Http => "http",
+            Self::Console => "console",
+            Self::Replay => "replay",
+            Self::Library => "library",
+            Self::Schedule => "schedule",
+        }
+    }
+}
+
+/// One line of the audit log. `hash` is an HMAC-SHA256, under the key at
+/// `logging.audit_key`, of every other field, including the previous entry's
+/// hash, so editing, removing, or reordering lines breaks the chain from that
+/// point on, and only someone holding the key can write a chain that checks.
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    seq: u64,
+    at: u64,
+    source: String,
+    action: String,
+    result: String,
+    prev: String,
+    hash: String,
+}
+
+impl Entry {
+    fn digest(&self, key: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+        for field in [
+            self.seq.to_string().as_str(),
+            self.at.to_string().as_str(),
+            &self.source,
+            &self.action,
+            &self.result,
+            &self.prev,
+        ] {
+            mac.update(field.as_bytes());
+            mac.update(&[0]);
+        }
+        hex(&mac.finalize().into_bytes())
+    }
+}
+
+/// Appends `intent` to `logging.audit_log` when it is a system action,
+/// force-ends a program, deletes files, changes a smart-home device or runs
+/// a shell command, with what `source` set it off and how `result` went: a
+/// command's exit status, or why it was refused. Dry runs change
+/// nothing, so there is nothing to audit.
+pub fn record_executed(
+    config: &Config,
+    source: Source,
+    intent: &Intent,
+    result: &Result<ExecutionResult, ExecutionError>,
+) {
+    let Some(path) = &config.logging.audit_log else {
+        return;
+    };
+    if config.policy.dry_run {
+        return;
+    }
+    let action = match intent {
+        Intent::System { target, .. } => target.clone(),
+        Intent::KillApp { target, .. } => format!("kill_app:{}", target),
+        Intent::Files { target, .. } if target != "new_folder" => format!("files:{}", target),
+        Intent::SmartHome {
+            target, command, ..
+        } if command != "state" => format!("smart_home:{}:{}", command, target),
+        Intent::Shell {
+            command: Some(command),
+            ..
+        } => format!("shell:{}", command),
+        _ => return,
+    };
+    let outcome = match result {
+        Ok(ExecutionResult::Ran {
+            status: Some(0), ..
+        }) => Ok(()),
+        Ok(ExecutionResult::Ran {
+            status: Some(code), ..
+        }) => Err(format!("exit status {}", code)),
+        Ok(ExecutionResult::Ran { status: None, .. }) => Err("no exit status".to_string()),
+        // `shell::refused` kept the command from running.
+        Ok(ExecutionResult::Answer(reason)) if matches!(intent, Intent::Shell { .. }) => {
+            Err(reason.clone())
+        }
+        Ok(_) => Ok(()),
+        Err(err) => Err(err.to_string()),
+    };
+    let recorded = load_or_create_key(&key_path(&config.logging, path))
+        .and_then(|key| record(path, &key, source, &action, outcome));
+    if let Err(err) = recorded {
+        eprintln!("Failed to write audit log {}: {}", path.display(), err);
+    }
+}
+
+/// `logging.audit_key`, or the log's path with a `.key` extension.
+pub fn key_path(logging: &LoggingConfig, log: &Path) -> PathBuf {
+    logging
+        .audit_key
+        .clone()
+        .unwrap_or_else(|| log.with_extension("key"))
+}
+
+/// Reads the hex key at `path`.
+pub fn load_key(path: &Path) -> Result<Vec<u8>, AuditError> {
+    let text = fs::read_to_string(path).map_err(AuditError::Io)?;
+    let text = text.trim();
+    let key = (0..text.len())
+        .step_by(2)
+        .map(|at| text.get(at..at + 2).and_then(|pair| u8::from_str_radix(pair, 16).ok()))
+        .collect::<Option<Vec<u8>>>()
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| AuditError::BadKey(path.to_path_buf()))?;
+    Ok(key)
+}
+
+/// The key at `path`, made from random bytes the first time. On Unix only
+/// its owner can read it.
+fn load_or_create_key(path: &Path) -> Result<Vec<u8>, AuditError> {
+    match load_key(path) {
+        Err(AuditError::Io(err)) if err.kind() == io::ErrorKind::NotFound => {}
+        loaded => return loaded,
+    }
+    let mut key = vec![0; KEY_BYTES];
+    getrandom::getrandom(&mut key).map_err(|err| AuditError::Io(err.into()))?;
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", hex(&key)))
+        .map_err(AuditError::Io)?;
+    Ok(key)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Appends an action to the log at `path`, chained to the last entry
+/// already there and keyed with `key`.
+pub fn record(
+    path: &Path,
+    key: &[u8],
+    source: Source,
+    action: &str,
+    outcome: Result<(), String>,
+) -> Result<(), AuditError> {
+    let last = match fs::read_to_string(path) {
+        Ok(contents) => match contents.lines().rfind(|line| !line.trim().is_empty()) {
+            Some(line) => Some(parse(line, 0)?),
+            None => None,
+        },
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(AuditError::Io(err)),
+    };
+    let mut entry = Entry {
+        seq: last.as_ref().map_or(1, |last| last.seq + 1),
+        at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default(),
+        source: source.name().to_string(),
+        action: action.to_string(),
+        result: match outcome {
+            Ok(()) => "ok".to_string(),
+            Err(err) => format!("error: {}", err),
+        },
+        prev: last.map_or_else(|| GENESIS.to_string(), |last| last.hash),
+        hash: String::new(),
+    };
+    entry.hash = entry.digest(key);
+    let line =
+        serde_json::to_string(&entry).map_err(|err| AuditError::Io(io::Error::other(err)))?;
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", line))
+        .map_err(AuditError::Io)
+}
+
+/// Walks the whole chain, checking each hash with `key`, and returns the
+/// number of entries, or the first line that does not follow from the one
+/// before it.
+pub fn verify(path: &Path, key: &[u8]) -> Result<u64, AuditError> {
+    let contents = fs::read_to_string(path).map_err(AuditError::Io)?;
+    let mut prev = GENESIS.to_string();
+    let mut count = 0;
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let number = index + 1;
+        let entry = parse(line, number)?;
+        if entry.prev != prev || entry.seq != count + 1 || entry.digest(key) != entry.hash {
+            return Err(AuditError::Broken(number));
+        }
+        prev = entry.hash;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn parse(line: &str, number: usize) -> Result<Entry, AuditError> {
+    serde_json::from_str(line).map_err(|err| AuditError::Malformed(number, err.to_string()))
+}
+
+#[derive(Debug)]
+pub enum AuditError {
+    Io(io::Error),
+    /// Line number (0 when unknown) and parse error.
+    Malformed(usize, String),
+    /// Line number where the chain stops matching.
+    Broken(usize),
+    /// A key file that isn't hex.
+    BadKey(PathBuf),
+}
+
+impl std::fmt::Display for AuditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "audit log I/O error: {}", err),
+            Self::Malformed(0, err) => write!(f, "malformed audit entry: {}", err),
+            Self::Malformed(line, err) => {
+                write!(f, "malformed audit entry on line {}: {}", line, err)
+            }
+            Self::Broken(line) => write!(f, "audit chain broken at line {}", line),
+            Self::BadKey(path) => write!(f, "audit key {} is not a hex key", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for AuditError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh path in the temp directory, removed first if a run left it.
+    fn temp_log(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("buddy-{}-{}.log", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn write_entries(path: &Path, key: &[u8]) {
+        record(path, key, Source::Hotkey, "lock", Ok(())).unwrap();
+        record(path, key, Source::Http, "mute", Err("no device".to_string())).unwrap();
+        record(path, key, Source::Schedule, "shutdown", Ok(())).unwrap();
+    }
+
+    #[test]
+    fn verifies_an_untouched_log() {
+        let path = temp_log("audit-untouched");
+        write_entries(&path, b"secret");
+        assert_eq!(verify(&path, b"secret").unwrap(), 3);
+        assert!(matches!(verify(&path, b"guess"), Err(AuditError::Broken(1))));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_an_edited_line() {
+        let path = temp_log("audit-edited");
+        write_entries(&path, b"secret");
+        let edited = fs::read_to_string(&path).unwrap().replace("\"mute\"", "\"unmute\"");
+        fs::write(&path, edited).unwrap();
+        assert!(matches!(verify(&path, b"secret"), Err(AuditError::Broken(2))));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rejects_a_log_rewritten_and_rehashed_without_the_key() {
+        let path = temp_log("audit-rehashed");
+        write_entries(&path, b"secret");
+        // Everything an attacker can do without the key: rebuild the whole
+        // chain with the same fields under a key of their own.
+        let entries: Vec<Entry> = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| parse(line, 0).unwrap())
+            .collect();
+        fs::remove_file(&path).unwrap();
+        for entry in entries.iter().filter(|entry| entry.action != "shutdown") {
+            let outcome = match entry.result.as_str() {
+                "ok" => Ok(()),
+                result => Err(result.to_string()),
+            };
+            record(&path, b"guess", Source::Console, &entry.action, outcome).unwrap();
+        }
+        assert!(matches!(verify(&path, b"secret"), Err(AuditError::Broken(1))));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn records_a_shell_command_with_its_exit_status() {
+        let path = temp_log("audit-shell");
+        let key = temp_log("audit-shell-key");
+        let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/config.toml");
+        let mut config = Config::load(fixture, None).unwrap();
+        config.logging.audit_log = Some(path.clone());
+        config.logging.audit_key = Some(key.clone());
+        let intent = Intent::Shell {
+            request: "list the files".to_string(),
+            command: Some("ls -la".to_string()),
+            confidence: 1.0,
+        };
+        let ran = Ok(ExecutionResult::Ran {
+            summary: "The command failed. No such file".to_string(),
+            status: Some(2),
+        });
+        record_executed(&config, Source::Hotkey, &intent, &ran);
+        let log = fs::read_to_string(&path).unwrap();
+        assert!(log.contains("\"action\":\"shell:ls -la\""), "{}", log);
+        assert!(log.contains("\"result\":\"error: exit status 2\""), "{}", log);
+        assert_eq!(verify(&path, &load_key(&key).unwrap()).unwrap(), 1);
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&key).unwrap();
+    }
+
+    #[test]
+    fn makes_a_key_once_and_reads_it_back() {
+        let path = temp_log("audit-key");
+        let key = load_or_create_key(&path).unwrap();
+        assert_eq!(key.len(), KEY_BYTES);
+        assert_eq!(load_or_create_key(&path).unwrap(), key);
+        assert_eq!(load_key(&path).unwrap(), key);
+        fs::write(&path, "not hex").unwrap();
+        assert!(matches!(load_key(&path), Err(AuditError::BadKey(_))));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,98 @@
+//! "Stop" while Buddy is talking. With `feedback.listen_for_stop`, the
+//! microphone is recorded in short windows whenever feedback is playing, and
+//! a window heard as "stop" raises the same flag as the stop hotkey. Any
+//! other speech in a window ducks the feedback until a quiet window follows.
+
+use crate::{audio::AudioCapturer, rules, transcription::Transcriber};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Long enough for "stop talking", short enough to stop soon after it.
+const WINDOW: Duration = Duration::from_millis(1500);
+/// How often an idle listener checks whether feedback started playing.
+const IDLE_POLL: Duration = Duration::from_millis(50);
+/// Pause after the microphone fails, giving the next command a chance to
+/// switch devices.
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+pub struct StopListener {
+    done: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl StopListener {
+    /// Listens while `playing` is up, raising `stop` on "stop", "be quiet"
+    /// and the like, and holding `ducking` up while the user is talking.
+    pub fn start(
+        capturer: Arc<AudioCapturer>,
+        transcriber: Arc<Transcriber>,
+        playing: Arc<AtomicBool>,
+        ducking: Arc<AtomicBool>,
+        stop: Arc<AtomicBool>,
+    ) -> Self {
+        let done = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let done = Arc::clone(&done);
+            thread::Builder::new()
+                .name("barge-in".into())
+                .spawn(move || {
+                    listen(&capturer, &transcriber, &playing, &ducking, &stop, &done);
+                    ducking.store(false, Ordering::Relaxed);
+                })
+                .map_err(|err| eprintln!("failed to start listening for \"stop\": {}", err))
+                .ok()
+        };
+        Self { done, thread }
+    }
+}
+
+impl Drop for StopListener {
+    fn drop(&mut self) {
+        self.done.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn listen(
+    capturer: &AudioCapturer,
+    transcriber: &Transcriber,
+    playing: &AtomicBool,
+    ducking: &AtomicBool,
+    stop: &AtomicBool,
+    done: &AtomicBool,
+) {
+    while !done.load(Ordering::Relaxed) {
+        if !playing.load(Ordering::Relaxed) {
+            ducking.store(false, Ordering::Relaxed);
+            thread::sleep(IDLE_POLL);
+            continue;
+        }
+        let audio = match capturer.capture_window(WINDOW, done) {
+            Ok(audio) => audio,
+            Err(err) => {
+                eprintln!("Cannot listen for \"stop\": {}", err);
+                let failed = Instant::now();
+                while failed.elapsed() < RETRY_DELAY && !done.load(Ordering::Relaxed) {
+                    thread::sleep(IDLE_POLL);
+                }
+                continue;
+            }
+        };
+        match transcriber.transcribe(&audio) {
+            Ok(text) if rules::wants_silence(&text) => {
+                println!("Heard: {}", text.trim());
+                stop.store(true, Ordering::Relaxed);
+            }
+            Ok(text) => ducking.store(!text.trim().is_empty(), Ordering::Relaxed),
+            Err(err) => eprintln!("Transcribing a \"stop\" window failed: {}", err),
+        }
+    }
+}
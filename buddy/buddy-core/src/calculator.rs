@@ -0,0 +1,653 @@
+/// Phrases dropped from the front of a question before it is read as math.
+const LEAD_PHRASES: &[&str] = &[
+    "what is ",
+    "what's ",
+    "whats ",
+    "how much is ",
+    "calculate ",
+    "compute ",
+    "convert ",
+    "please ",
+];
+
+/// Words that separate the two units in "5 miles to km".
+const CONVERSION_WORDS: &[&str] = &["to", "into", "in", "as"];
+
+/// Unit families; a unit only converts into another of the same family.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dimension {
+    Length,
+    Mass,
+    Volume,
+    Time,
+    Speed,
+    Data,
+    Temperature,
+}
+
+struct Unit {
+    /// Lowercase spellings, including plurals and abbreviations.
+    names: &'static [&'static str],
+    dimension: Dimension,
+    /// The unit in meters, kilograms, liters, seconds, meters per second,
+    /// bytes, or kelvin, after adding `offset`.
+    factor: f64,
+    offset: f64,
+    singular: &'static str,
+    plural: &'static str,
+}
+
+const fn unit(
+    names: &'static [&'static str],
+    dimension: Dimension,
+    factor: f64,
+    singular: &'static str,
+    plural: &'static str,
+) -> Unit {
+    Unit {
+        names,
+        dimension,
+        factor,
+        offset: 0.0,
+        singular,
+        plural,
+    }
+}
+
+const UNITS: &[Unit] = &[
+    unit(&["m", "meter", "meters", "metre", "metres"], Dimension::Length, 1.0, "meter", "meters"),
+    unit(
+        &["km", "kilometer", "kilometers", "kilometre", "kilometres"],
+        Dimension::Length,
+        1000.0,
+        "kilometer",
+        "kilometers",
+    ),
+    unit(
+        &["cm", "centimeter", "centimeters", "centimetre", "centimetres"],
+        Dimension::Length,
+        0.01,
+        "centimeter",
+        "centimeters",
+    ),
+    unit(
+        &["mm", "millimeter", "millimeters", "millimetre", "millimetres"],
+        Dimension::Length,
+        0.001,
+        "millimeter",
+        "millimeters",
+    ),
+    unit(&["mi", "mile", "miles"], Dimension::Length, 1609.344, "mile", "miles"),
+    unit(&["yd", "yard", "yards"], Dimension::Length, 0.9144, "yard", "yards"),
+    unit(&["ft", "foot", "feet"], Dimension::Length, 0.3048, "foot", "feet"),
+    unit(&["inch", "inches"], Dimension::Length, 0.0254, "inch", "inches"),
+    unit(
+        &["kg", "kilo", "kilos", "kilogram", "kilograms"],
+        Dimension::Mass,
+        1.0,
+        "kilogram",
+        "kilograms",
+    ),
+    unit(&["g", "gram", "grams"], Dimension::Mass, 0.001, "gram", "grams"),
+    unit(&["mg", "milligram", "milligrams"], Dimension::Mass, 1e-6, "milligram", "milligrams"),
+    unit(&["lb", "lbs", "pound", "pounds"], Dimension::Mass, 0.453_592_37, "pound", "pounds"),
+    unit(&["oz", "ounce", "ounces"], Dimension::Mass, 0.028_349_523_125, "ounce", "ounces"),
+    unit(&["stone", "stones"], Dimension::Mass, 6.350_293_18, "stone", "stone"),
+    unit(
+        &["t", "tonne", "tonnes", "metric ton", "metric tons"],
+        Dimension::Mass,
+        1000.0,
+        "tonne",
+        "tonnes",
+    ),
+    unit(&["l", "liter", "liters", "litre", "litres"], Dimension::Volume, 1.0, "liter", "liters"),
+    unit(
+        &["ml", "milliliter", "milliliters", "millilitre", "millilitres"],
+        Dimension::Volume,
+        0.001,
+        "milliliter",
+        "milliliters",
+    ),
+    unit(&["gal", "gallon", "gallons"], Dimension::Volume, 3.785_411_784, "gallon", "gallons"),
+    unit(&["qt", "quart", "quarts"], Dimension::Volume, 0.946_352_946, "quart", "quarts"),
+    unit(&["pt", "pint", "pints"], Dimension::Volume, 0.473_176_473, "pint", "pints"),
+    unit(&["cup", "cups"], Dimension::Volume, 0.236_588_236_5, "cup", "cups"),
+    unit(
+        &["fl oz", "fluid ounce", "fluid ounces"],
+        Dimension::Volume,
+        0.029_573_529_562_5,
+        "fluid ounce",
+        "fluid ounces",
+    ),
+    unit(
+        &["tbsp", "tablespoon", "tablespoons"],
+        Dimension::Volume,
+        0.014_786_764_781_25,
+        "tablespoon",
+        "tablespoons",
+    ),
+    unit(
+        &["tsp", "teaspoon", "teaspoons"],
+        Dimension::Volume,
+        0.004_928_921_593_75,
+        "teaspoon",
+        "teaspoons",
+    ),
+    unit(
+        &["ms", "millisecond", "milliseconds"],
+        Dimension::Time,
+        0.001,
+        "millisecond",
+        "milliseconds",
+    ),
+    unit(&["s", "sec", "secs", "second", "seconds"], Dimension::Time, 1.0, "second", "seconds"),
+    unit(&["min", "mins", "minute", "minutes"], Dimension::Time, 60.0, "minute", "minutes"),
+    unit(&["h", "hr", "hrs", "hour", "hours"], Dimension::Time, 3600.0, "hour", "hours"),
+    unit(&["day", "days"], Dimension::Time, 86_400.0, "day", "days"),
+    unit(&["week", "weeks"], Dimension::Time, 604_800.0, "week", "weeks"),
+    unit(&["year", "years"], Dimension::Time, 31_557_600.0, "year", "years"),
+    unit(
+        &["m/s", "meter per second", "meters per second"],
+        Dimension::Speed,
+        1.0,
+        "meter per second",
+        "meters per second",
+    ),
+    unit(
+        &["km/h", "kmh", "kph", "kilometer per hour", "kilometers per hour"],
+        Dimension::Speed,
+        1.0 / 3.6,
+        "kilometer per hour",
+        "kilometers per hour",
+    ),
+    unit(
+        &["mph", "mile per hour", "miles per hour"],
+        Dimension::Speed,
+        0.447_04,
+        "mile per hour",
+        "miles per hour",
+    ),
+    unit(&["knot", "knots"], Dimension::Speed, 1852.0 / 3600.0, "knot", "knots"),
+    unit(&["bit", "bits"], Dimension::Data, 0.125, "bit", "bits"),
+    unit(&["b", "byte", "bytes"], Dimension::Data, 1.0, "byte", "bytes"),
+    unit(&["kb", "kilobyte", "kilobytes"], Dimension::Data, 1e3, "kilobyte", "kilobytes"),
+    unit(&["mb", "megabyte", "megabytes"], Dimension::Data, 1e6, "megabyte", "megabytes"),
+    unit(&["gb", "gigabyte", "gigabytes"], Dimension::Data, 1e9, "gigabyte", "gigabytes"),
+    unit(&["tb", "terabyte", "terabytes"], Dimension::Data, 1e12, "terabyte", "terabytes"),
+    Unit {
+        names: &["c", "°c", "celsius", "centigrade"],
+        dimension: Dimension::Temperature,
+        factor: 1.0,
+        offset: 273.15,
+        singular: "degree Celsius",
+        plural: "degrees Celsius",
+    },
+    Unit {
+        names: &["f", "°f", "fahrenheit"],
+        dimension: Dimension::Temperature,
+        factor: 5.0 / 9.0,
+        offset: 459.67,
+        singular: "degree Fahrenheit",
+        plural: "degrees Fahrenheit",
+    },
+    unit(&["k", "kelvin", "kelvins"], Dimension::Temperature, 1.0, "kelvin", "kelvins"),
+];
+
+/// Answers arithmetic ("what is 15% of 230") and unit conversions ("convert
+/// 5 miles to km") without the intent model. `None` when the transcript
+/// isn't one of those, so it goes on to the model.
+pub fn answer(transcript: &str) -> Option<String> {
+    let mut text = transcript
+        .trim()
+        .trim_end_matches(['.', '!', '?'])
+        .to_lowercase()
+        .replace('×', "*")
+        .replace('÷', "/");
+    while let Some(rest) = LEAD_PHRASES
+        .iter()
+        .find_map(|phrase| text.strip_prefix(phrase))
+    {
+        text = rest.trim_start().to_string();
+    }
+    if let Some(answer) = conversion(&text) {
+        return Some(answer);
+    }
+    let value = evaluate(&text)?;
+    Some(if value.is_finite() {
+        format_number(value)
+    } else {
+        "That's undefined".to_string()
+    })
+}
+
+/// Evaluates an arithmetic expression in symbols or words ("2 + 3 * 4",
+/// "10 divided by 4", "3 squared", "15% of 230"). Bare numbers aren't
+/// expressions, so "what is 2001" is left for the model.
+pub fn evaluate(expression: &str) -> Option<f64> {
+    let tokens = tokenize(expression)?;
+    if !tokens.iter().any(Token::is_operator) {
+        return None;
+    }
+    let mut parser = Parser { tokens, pos: 0 };
+    let value = parser.expr()?;
+    (parser.pos == parser.tokens.len()).then_some(value)
+}
+
+/// Converts `value` between two named units of the same kind.
+pub fn convert(value: f64, from: &str, to: &str) -> Option<f64> {
+    let (from, to) = (find_unit(from)?, find_unit(to)?);
+    if from.dimension != to.dimension {
+        return None;
+    }
+    Some((value + from.offset) * from.factor / to.factor - to.offset)
+}
+
+/// "5 miles to km", "a mile in feet", "how many feet are in 3 miles".
+fn conversion(text: &str) -> Option<String> {
+    let words = unit_words(text);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let (amount, from, to) = match words.as_slice() {
+        ["how", "many" | "much", rest @ ..] => {
+            let split = rest
+                .iter()
+                .position(|word| matches!(*word, "are" | "is" | "in"))?;
+            let mut from = &rest[split..];
+            while let [first, tail @ ..] = from {
+                if !matches!(*first, "are" | "is" | "in" | "there") {
+                    break;
+                }
+                from = tail;
+            }
+            let (amount, from) = from.split_first()?;
+            (amount_of(amount)?, from.join(" "), rest[..split].join(" "))
+        }
+        [amount, rest @ ..] => {
+            let amount = amount_of(amount)?;
+            let (from, to) = (1..rest.len())
+                .filter(|&split| CONVERSION_WORDS.contains(&rest[split]))
+                .map(|split| (rest[..split].join(" "), rest[split + 1..].join(" ")))
+                .find(|(from, to)| convert(amount, from, to).is_some())?;
+            (amount, from, to)
+        }
+        [] => return None,
+    };
+    let value = convert(amount, &from, &to)?;
+    let (from, to) = (find_unit(&from)?, find_unit(&to)?);
+    Some(format!(
+        "{} {} is {} {}",
+        format_number(amount),
+        if amount == 1.0 { from.singular } else { from.plural },
+        format_number(value),
+        if format_number(value) == "1" { to.singular } else { to.plural }
+    ))
+}
+
+/// Lowercase words with a number split from a unit written against it
+/// ("5km" is "5 km").
+fn unit_words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for word in text.split_whitespace() {
+        let word = word.trim_matches([',', '?', '!']);
+        let number_end = word
+            .char_indices()
+            .find(|&(index, c)| {
+                !(c.is_ascii_digit() || c == '.' || c == ',' || (c == '-' && index == 0))
+            })
+            .map_or(word.len(), |(index, _)| index);
+        if number_end > 0 && number_end < word.len() {
+            words.push(word[..number_end].to_string());
+            words.push(word[number_end..].to_string());
+        } else if !word.is_empty() {
+            words.push(word.to_string());
+        }
+    }
+    words
+}
+
+fn amount_of(word: &str) -> Option<f64> {
+    match word {
+        "a" | "an" | "one" => Some(1.0),
+        _ => word.replace(',', "").parse().ok(),
+    }
+}
+
+/// A unit by any of its names; "degrees celsius" is "celsius".
+fn find_unit(name: &str) -> Option<&'static Unit> {
+    let name = name.trim();
+    let name = ["degrees ", "degree "]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .unwrap_or(name);
+    UNITS.iter().find(|unit| unit.names.contains(&name))
+}
+
+/// Rounded to three significant digits but at least two decimals, with
+/// trailing zeros dropped ("8.05", "0.00311", "5280").
+fn format_number(value: f64) -> String {
+    let magnitude = value.abs().log10().floor();
+    let decimals = if magnitude.is_finite() {
+        (2 - magnitude as i32).clamp(2, 10) as usize
+    } else {
+        2
+    };
+    let text = format!("{:.*}", decimals, value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    Number(f64),
+    Plus,
+    Minus,
+    Times,
+    Divide,
+    Power,
+    Open,
+    Close,
+    Percent,
+    Of,
+    Squared,
+    Cubed,
+    SquareRoot,
+}
+
+impl Token {
+    fn is_operator(&self) -> bool {
+        !matches!(self, Self::Number(_) | Self::Open | Self::Close)
+    }
+}
+
+/// `None` as soon as anything isn't part of an expression.
+fn tokenize(text: &str) -> Option<Vec<Token>> {
+    let mut words = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() || c == ',' {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut number = String::new();
+            while let Some(&c) = chars.peek() {
+                match c {
+                    '0'..='9' | '.' => number.push(c),
+                    // Thousands separators: "1,000".
+                    ',' => {}
+                    _ => break,
+                }
+                chars.next();
+            }
+            words.push(Word::Number(number.parse().ok()?));
+        } else if c.is_alphabetic() {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_alphabetic()) {
+                word.push(c);
+                chars.next();
+            }
+            words.push(Word::Text(word));
+        } else {
+            words.push(Word::Symbol(c));
+            chars.next();
+        }
+    }
+
+    let mut tokens = Vec::new();
+    let mut rest = words.as_slice();
+    while !rest.is_empty() {
+        let (token, used) = match rest {
+            [Word::Number(number), ..] => (Token::Number(*number), 1),
+            [Word::Symbol(symbol), ..] => (
+                match symbol {
+                    '+' => Token::Plus,
+                    '-' => Token::Minus,
+                    '*' => Token::Times,
+                    '/' => Token::Divide,
+                    '^' => Token::Power,
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    '%' => Token::Percent,
+                    _ => return None,
+                },
+                1,
+            ),
+            [Word::Text(first), tail @ ..] => {
+                let next: Vec<&str> = tail
+                    .iter()
+                    .take(3)
+                    .map_while(|word| match word {
+                        Word::Text(text) => Some(text.as_str()),
+                        _ => None,
+                    })
+                    .collect();
+                match (first.as_str(), next.as_slice()) {
+                    ("multiplied", ["by", ..]) => (Token::Times, 2),
+                    ("divided", ["by", ..]) => (Token::Divide, 2),
+                    ("to", ["the", "power", "of", ..]) => (Token::Power, 4),
+                    ("square", ["root", "of", ..]) => (Token::SquareRoot, 3),
+                    ("plus", _) => (Token::Plus, 1),
+                    ("minus" | "negative", _) => (Token::Minus, 1),
+                    ("times" | "x", _) => (Token::Times, 1),
+                    ("over", _) => (Token::Divide, 1),
+                    ("percent", _) => (Token::Percent, 1),
+                    ("of", _) => (Token::Of, 1),
+                    ("squared", _) => (Token::Squared, 1),
+                    ("cubed", _) => (Token::Cubed, 1),
+                    ("sqrt", _) => (Token::SquareRoot, 1),
+                    _ => return None,
+                }
+            }
+            [] => unreachable!("loop stops on an empty slice"),
+        };
+        tokens.push(token);
+        rest = &rest[used..];
+    }
+    Some(tokens)
+}
+
+enum Word {
+    Number(f64),
+    Symbol(char),
+    Text(String),
+}
+
+/// Recursive descent, loosest binding first:
+///
+/// ```text
+/// expr    = term (("+" | "-") term)*
+/// term    = unary (("*" | "/") unary)*
+/// unary   = "-" unary | power
+/// power   = postfix ("^" unary)?
+/// postfix = primary ("squared" | "cubed" | "%" ("of" unary)?)*
+/// primary = number | "(" expr ")" | "square root of" unary
+/// ```
+///
+/// Values carry whether they are a bare percentage, so "200 plus 15%" adds
+/// 15% of 200 the way a pocket calculator does.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, token: Token) -> bool {
+        let found = self.peek() == Some(token);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expr(&mut self) -> Option<f64> {
+        let (mut value, _) = self.term()?;
+        loop {
+            let sign = match self.peek() {
+                Some(Token::Plus) => 1.0,
+                Some(Token::Minus) => -1.0,
+                _ => return Some(value),
+            };
+            self.pos += 1;
+            let (rhs, percent) = self.term()?;
+            value = if percent {
+                value * (1.0 + sign * rhs)
+            } else {
+                value + sign * rhs
+            };
+        }
+    }
+
+    fn term(&mut self) -> Option<(f64, bool)> {
+        let (mut value, mut percent) = self.unary()?;
+        loop {
+            if self.eat(Token::Times) {
+                value *= self.unary()?.0;
+            } else if self.eat(Token::Divide) {
+                value /= self.unary()?.0;
+            } else {
+                return Some((value, percent));
+            }
+            percent = false;
+        }
+    }
+
+    fn unary(&mut self) -> Option<(f64, bool)> {
+        if self.eat(Token::Minus) {
+            let (value, percent) = self.unary()?;
+            Some((-value, percent))
+        } else {
+            self.eat(Token::Plus);
+            self.power()
+        }
+    }
+
+    fn power(&mut self) -> Option<(f64, bool)> {
+        let (base, percent) = self.postfix()?;
+        if self.eat(Token::Power) {
+            let (exponent, _) = self.unary()?;
+            Some((base.powf(exponent), false))
+        } else {
+            Some((base, percent))
+        }
+    }
+
+    fn postfix(&mut self) -> Option<(f64, bool)> {
+        let (mut value, mut percent) = (self.primary()?, false);
+        loop {
+            if self.eat(Token::Squared) {
+                value = value.powi(2);
+            } else if self.eat(Token::Cubed) {
+                value = value.powi(3);
+            } else if self.eat(Token::Percent) {
+                value /= 100.0;
+                percent = !self.eat(Token::Of);
+                if !percent {
+                    value *= self.unary()?.0;
+                }
+                continue;
+            } else {
+                return Some((value, percent));
+            }
+            percent = false;
+        }
+    }
+
+    fn primary(&mut self) -> Option<f64> {
+        match self.peek()? {
+            Token::Number(number) => {
+                self.pos += 1;
+                Some(number)
+            }
+            Token::Open => {
+                self.pos += 1;
+                let value = self.expr()?;
+                self.eat(Token::Close).then_some(value)
+            }
+            Token::SquareRoot => {
+                self.pos += 1;
+                Some(self.unary()?.0.sqrt())
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_precedence() {
+        assert_eq!(evaluate("2 + 3 * 4"), Some(14.0));
+        assert_eq!(evaluate("(2 + 3) * 4"), Some(20.0));
+        assert_eq!(evaluate("2 ^ 3 ^ 2"), Some(512.0));
+        assert_eq!(evaluate("-2 ^ 2"), Some(-4.0));
+        assert_eq!(evaluate("10 - 4 - 3"), Some(3.0));
+    }
+
+    #[test]
+    fn reads_operators_in_words() {
+        assert_eq!(evaluate("10 divided by 4"), Some(2.5));
+        assert_eq!(evaluate("6 times 7"), Some(42.0));
+        assert_eq!(evaluate("3 squared plus 4 squared"), Some(25.0));
+        assert_eq!(evaluate("2 to the power of 10"), Some(1024.0));
+        assert_eq!(evaluate("square root of 81"), Some(9.0));
+        assert_eq!(evaluate("1,000 minus 1"), Some(999.0));
+    }
+
+    #[test]
+    fn handles_percentages() {
+        assert_eq!(answer("What is 15% of 230?").as_deref(), Some("34.5"));
+        assert_eq!(answer("what's 20 percent of 50").as_deref(), Some("10"));
+        assert_eq!(answer("200 plus 15%").as_deref(), Some("230"));
+        assert_eq!(answer("80 - 25%").as_deref(), Some("60"));
+    }
+
+    #[test]
+    fn formats_answers_for_speech() {
+        assert_eq!(answer("what is 1 / 3").as_deref(), Some("0.333"));
+        assert_eq!(answer("what is 0.1 + 0.2").as_deref(), Some("0.3"));
+        assert_eq!(answer("calculate 1 / 0").as_deref(), Some("That's undefined"));
+    }
+
+    #[test]
+    fn converts_units() {
+        assert_eq!(
+            answer("Convert 5 miles to km.").as_deref(),
+            Some("5 miles is 8.05 kilometers")
+        );
+        assert_eq!(
+            answer("what is 100 degrees celsius in fahrenheit").as_deref(),
+            Some("100 degrees Celsius is 212 degrees Fahrenheit")
+        );
+        assert_eq!(
+            answer("how many feet are in a mile").as_deref(),
+            Some("1 mile is 5280 feet")
+        );
+        assert_eq!(answer("convert 2kg to pounds").as_deref(), Some("2 kilograms is 4.41 pounds"));
+        assert_eq!(
+            answer("60 mph in km/h").as_deref(),
+            Some("60 miles per hour is 96.56 kilometers per hour")
+        );
+    }
+
+    #[test]
+    fn refuses_mismatched_units() {
+        assert_eq!(convert(5.0, "miles", "kg"), None);
+        assert_eq!(answer("convert 5 miles to kilograms"), None);
+    }
+
+    #[test]
+    fn leaves_other_questions_to_the_model() {
+        assert_eq!(answer("what is the capital of France"), None);
+        assert_eq!(answer("what is 2001 a space odyssey about"), None);
+        assert_eq!(answer("what is 42"), None);
+        assert_eq!(answer("open my resume"), None);
+        assert_eq!(answer("set volume to 50"), None);
+    }
+}
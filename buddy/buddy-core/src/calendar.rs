@@ -0,0 +1,481 @@
+use crate::clock::{self, Date};
+use std::{fs, io, path::Path};
+
+/// One `VEVENT` from an ICS file.
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub summary: String,
+    pub start: Date,
+    /// Minutes after local midnight; `None` for all-day events.
+    pub time: Option<u32>,
+    /// The first day after the event; all-day events can span several.
+    end: Date,
+    repeat: Option<Recurrence>,
+    /// Occurrences removed from the series (`EXDATE`).
+    skipped: Vec<Date>,
+}
+
+/// The subset of `RRULE` calendar apps write for everyday repeats.
+#[derive(Debug, Clone)]
+struct Recurrence {
+    frequency: Frequency,
+    interval: i64,
+    until: Option<Date>,
+    count: Option<usize>,
+    /// `BYDAY` weekdays (0 is Sunday), each with the ordinal a monthly or
+    /// yearly rule gives it: 2 for "2TU", the second Tuesday, -1 for "-1FR",
+    /// the last Friday, and 0 for every one. Empty repeats on the start's day.
+    weekdays: Vec<(i32, u32)>,
+    /// `BYMONTH` of a yearly rule; empty repeats in the start's month.
+    months: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Event {
+    /// Whether the event (or one of its repeats) takes place on `day`.
+    pub fn occurs_on(&self, day: Date) -> bool {
+        if day < self.start || self.skipped.contains(&day) {
+            return false;
+        }
+        let Some(repeat) = &self.repeat else {
+            return day < self.end.max(self.start.add_days(1));
+        };
+        if repeat.until.is_some_and(|until| day > until) || !self.repeats_on(repeat, day) {
+            return false;
+        }
+        match repeat.count {
+            Some(count) => {
+                let earlier = (self.start.days()..day.days())
+                    .map(Date::from_days)
+                    .filter(|date| self.repeats_on(repeat, *date))
+                    .count();
+                earlier < count
+            }
+            None => true,
+        }
+    }
+
+    fn repeats_on(&self, repeat: &Recurrence, day: Date) -> bool {
+        let start = self.start;
+        let weekday = day.weekday();
+        let listed = repeat.weekdays.iter().any(|(_, listed)| *listed == weekday);
+        match repeat.frequency {
+            Frequency::Daily => {
+                (day.days() - start.days()) % repeat.interval == 0
+                    && (repeat.weekdays.is_empty() || listed)
+            }
+            Frequency::Weekly => {
+                // Weeks start on Monday, as RRULE's default WKST does.
+                let monday = |date: Date| date.days() - (i64::from(date.weekday()) + 6) % 7;
+                let weeks = (monday(day) - monday(start)) / 7;
+                weeks % repeat.interval == 0
+                    && if repeat.weekdays.is_empty() {
+                        weekday == start.weekday()
+                    } else {
+                        listed
+                    }
+            }
+            Frequency::Monthly => {
+                let months = i64::from(day.year - start.year) * 12 + i64::from(day.month)
+                    - i64::from(start.month);
+                months % repeat.interval == 0
+                    && if repeat.weekdays.is_empty() {
+                        day.day == start.day
+                    } else {
+                        nth_weekday(&repeat.weekdays, day, month_of(day))
+                    }
+            }
+            Frequency::Yearly => {
+                let in_month = if repeat.months.is_empty() {
+                    day.month == start.month
+                } else {
+                    repeat.months.contains(&day.month)
+                };
+                let on_day = if repeat.weekdays.is_empty() {
+                    in_month && day.day == start.day
+                } else if repeat.months.is_empty() {
+                    // Without `BYMONTH`, "20MO" is counted within the year.
+                    let first = Date { month: 1, day: 1, ..day };
+                    nth_weekday(&repeat.weekdays, day, (first, Date { month: 12, day: 31, ..day }))
+                } else {
+                    in_month && nth_weekday(&repeat.weekdays, day, month_of(day))
+                };
+                on_day && i64::from(day.year - start.year) % repeat.interval == 0
+            }
+        }
+    }
+}
+
+/// The first and last day of `day`'s month.
+fn month_of(day: Date) -> (Date, Date) {
+    let last = clock::days_in_month(day.year, day.month);
+    (Date { day: 1, ..day }, Date { day: last, ..day })
+}
+
+/// Whether `day` is one of `weekdays` in the period from `first` to `last`,
+/// counting an ordinal from the period's start, or from its end when negative.
+fn nth_weekday(weekdays: &[(i32, u32)], day: Date, (first, last): (Date, Date)) -> bool {
+    let from_start = (day.days() - first.days()) / 7 + 1;
+    let from_end = (last.days() - day.days()) / 7 + 1;
+    weekdays.iter().any(|&(nth, weekday)| {
+        weekday == day.weekday()
+            && match nth {
+                0 => true,
+                nth if nth > 0 => i64::from(nth) == from_start,
+                nth => i64::from(-nth) == from_end,
+            }
+    })
+}
+
+#[derive(Debug)]
+pub enum CalendarError {
+    Io(io::Error),
+}
+
+impl std::fmt::Display for CalendarError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read calendar: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for CalendarError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+        }
+    }
+}
+
+/// Reads the events from an ICS file. The file is read on every question,
+/// so a calendar app exporting to it stays current.
+pub fn load(path: &Path) -> Result<Vec<Event>, CalendarError> {
+    fs::read_to_string(path)
+        .map(|text| parse(&text))
+        .map_err(CalendarError::Io)
+}
+
+/// Events on `day`: all-day ones first, then by start time.
+pub fn events_on(events: &[Event], day: Date) -> Vec<&Event> {
+    let mut found: Vec<&Event> = events.iter().filter(|event| event.occurs_on(day)).collect();
+    found.sort_by_key(|event| event.time.map_or(0, |time| time + 1));
+    found
+}
+
+/// "You have 2 events tomorrow: Standup at 9:30 AM and Lunch at 12 PM".
+pub fn spoken_agenda(events: &[&Event], day: Date, today: Date) -> String {
+    let when = clock::spoken_day(day, today);
+    let items: Vec<String> = events
+        .iter()
+        .map(|event| match event.time {
+            Some(time) => format!("{} at {}", event.summary, clock::spoken_time(time)),
+            None => format!("{} all day", event.summary),
+        })
+        .collect();
+    match items.as_slice() {
+        [] => format!("Nothing on your calendar {}", when),
+        [only] => format!("You have one event {}: {}", when, only),
+        [first @ .., last] => format!(
+            "You have {} events {}: {} and {}",
+            items.len(),
+            when,
+            first.join(", "),
+            last
+        ),
+    }
+}
+
+/// Parses the `VEVENT`s of an ICS file, skipping any without a start or
+/// with one it can't read. Times in UTC are converted to local time; times
+/// with a `TZID` are taken as local.
+pub fn parse(text: &str) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut current: Option<Vec<(String, String)>> = None;
+    // Components inside the event, such as a `VALARM` with its own summary.
+    let mut nested = 0;
+    for line in unfold(text) {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Parameters such as `TZID=...` or `VALUE=DATE` aren't needed.
+        let name = key.split(';').next().unwrap_or(key).to_ascii_uppercase();
+        match (name.as_str(), value) {
+            ("BEGIN", "VEVENT") => current = Some(Vec::new()),
+            ("BEGIN", _) if current.is_some() => nested += 1,
+            ("END", "VEVENT") => {
+                nested = 0;
+                if let Some(event) = current.take().and_then(|fields| event(&fields)) {
+                    events.push(event);
+                }
+            }
+            ("END", _) if nested > 0 => nested -= 1,
+            (name, _) => {
+                if let Some(fields) = current.as_mut().filter(|_| nested == 0) {
+                    fields.push((name.to_string(), value.to_string()));
+                }
+            }
+        }
+    }
+    events
+}
+
+/// Joins folded lines (continuations start with a space or tab).
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn event(fields: &[(String, String)]) -> Option<Event> {
+    let field = |name: &str| {
+        fields
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let (start, time) = date_time(field("DTSTART")?)?;
+    let end = field("DTEND")
+        .and_then(date_time)
+        .map_or(start, |(end, _)| end);
+    let skipped = fields
+        .iter()
+        .filter(|(key, _)| key == "EXDATE")
+        .flat_map(|(_, value)| value.split(','))
+        .filter_map(|value| date_time(value).map(|(date, _)| date))
+        .collect();
+    Some(Event {
+        summary: field("SUMMARY").map_or_else(|| "Untitled event".to_string(), unescape),
+        start,
+        time,
+        end,
+        repeat: field("RRULE").and_then(recurrence),
+        skipped,
+    })
+}
+
+/// "20250304" (all day), "20250304T093000" (local), or "20250304T093000Z".
+fn date_time(value: &str) -> Option<(Date, Option<u32>)> {
+    let value = value.trim();
+    let date = Date::new(
+        value.get(0..4)?.parse().ok()?,
+        value.get(4..6)?.parse().ok()?,
+        value.get(6..8)?.parse().ok()?,
+    )?;
+    let Some(time) = value.get(8..).filter(|rest| !rest.is_empty()) else {
+        return Some((date, None));
+    };
+    let time = time.strip_prefix('T')?;
+    let hours: u32 = time.get(0..2)?.parse().ok()?;
+    let minutes: u32 = time.get(2..4)?.parse().ok()?;
+    if time.ends_with('Z') {
+        let secs = date.days() * 86_400 + i64::from(hours * 3600 + minutes * 60);
+        let (date, secs) = clock::local_from_utc(secs);
+        return Some((date, Some(secs / 60)));
+    }
+    Some((date, Some(hours * 60 + minutes)))
+}
+
+/// "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;UNTIL=20251231T000000Z".
+fn recurrence(rule: &str) -> Option<Recurrence> {
+    let mut repeat = Recurrence {
+        frequency: Frequency::Daily,
+        interval: 1,
+        until: None,
+        count: None,
+        weekdays: Vec::new(),
+        months: Vec::new(),
+    };
+    let mut frequency = None;
+    for part in rule.split(';') {
+        let (key, value) = part.split_once('=')?;
+        match key.to_ascii_uppercase().as_str() {
+            "FREQ" => {
+                frequency = Some(match value.to_ascii_uppercase().as_str() {
+                    "DAILY" => Frequency::Daily,
+                    "WEEKLY" => Frequency::Weekly,
+                    "MONTHLY" => Frequency::Monthly,
+                    "YEARLY" => Frequency::Yearly,
+                    // Hourly and finer repeats aren't worth reading out.
+                    _ => return None,
+                })
+            }
+            "INTERVAL" => repeat.interval = value.parse().ok().filter(|n| *n > 0)?,
+            "UNTIL" => repeat.until = date_time(value).map(|(date, _)| date),
+            "COUNT" => repeat.count = value.parse().ok(),
+            "BYDAY" => {
+                repeat.weekdays = value
+                    .split(',')
+                    .filter_map(|day| {
+                        // Monthly and yearly rules prefix an ordinal: "1MO", "-1FR".
+                        let day = day.trim();
+                        let split = day.len().checked_sub(2)?;
+                        let code = day.get(split..)?;
+                        let nth = match day.get(..split)? {
+                            "" => 0,
+                            nth => nth.parse().ok()?,
+                        };
+                        let weekday = ["SU", "MO", "TU", "WE", "TH", "FR", "SA"]
+                            .iter()
+                            .position(|name| name.eq_ignore_ascii_case(code))?;
+                        Some((nth, weekday as u32))
+                    })
+                    .collect()
+            }
+            "BYMONTH" => {
+                repeat.months = value
+                    .split(',')
+                    .filter_map(|month| month.trim().parse().ok())
+                    .filter(|month| (1..=12).contains(month))
+                    .collect()
+            }
+            _ => {}
+        }
+    }
+    repeat.frequency = frequency?;
+    Some(repeat)
+}
+
+/// ICS text escapes: `\,`, `\;`, `\n`, and `\\`.
+fn unescape(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push(' '),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Date {
+        Date::new(year, month, day).unwrap()
+    }
+
+    /// A single event starting on `start` and repeating by `rule`.
+    fn repeating(start: &str, rule: &str) -> Event {
+        let ics = format!(
+            "BEGIN:VCALENDAR\nBEGIN:VEVENT\nSUMMARY:Review\nDTSTART:{}\nRRULE:{}\nEND:VEVENT\n\
+             END:VCALENDAR\n",
+            start, rule
+        );
+        parse(&ics).remove(0)
+    }
+
+    #[test]
+    fn reads_events_from_ics() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Lunch with Sam\\, Alex\r\n  \
+            and Kim\r\n\
+            DTSTART;TZID=Europe/London:20250304T123000\r\n\
+            BEGIN:VALARM\r\n\
+            SUMMARY:Reminder\r\n\
+            END:VALARM\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Conference\r\n\
+            DTSTART;VALUE=DATE:20250310\r\n\
+            DTEND;VALUE=DATE:20250312\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:No start\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        let events = parse(ics);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].summary, "Lunch with Sam, Alex and Kim");
+        assert_eq!(events[0].start, date(2025, 3, 4));
+        assert_eq!(events[0].time, Some(12 * 60 + 30));
+        assert_eq!(events[1].time, None);
+        assert!(events[1].occurs_on(date(2025, 3, 11)));
+        assert!(!events[1].occurs_on(date(2025, 3, 12)));
+    }
+
+    #[test]
+    fn repeats_weekly_on_the_listed_days() {
+        let event = repeating("20250303", "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;UNTIL=20250320");
+        for day in [date(2025, 3, 3), date(2025, 3, 5), date(2025, 3, 17), date(2025, 3, 19)] {
+            assert!(event.occurs_on(day), "{:?}", day);
+        }
+        for day in [date(2025, 3, 10), date(2025, 3, 12), date(2025, 3, 31)] {
+            assert!(!event.occurs_on(day), "{:?}", day);
+        }
+    }
+
+    #[test]
+    fn repeats_daily_on_weekdays_up_to_a_count() {
+        let event = repeating("20250306", "FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR;COUNT=3");
+        assert!(event.occurs_on(date(2025, 3, 7)));
+        assert!(!event.occurs_on(date(2025, 3, 8)));
+        assert!(event.occurs_on(date(2025, 3, 10)));
+        assert!(!event.occurs_on(date(2025, 3, 11)));
+    }
+
+    #[test]
+    fn repeats_monthly_on_the_nth_weekday() {
+        let second_tuesday = repeating("20250311", "FREQ=MONTHLY;BYDAY=2TU");
+        assert!(second_tuesday.occurs_on(date(2025, 4, 8)));
+        assert!(!second_tuesday.occurs_on(date(2025, 4, 11)));
+        assert!(!second_tuesday.occurs_on(date(2025, 4, 1)));
+        let last_friday = repeating("20250328", "FREQ=MONTHLY;BYDAY=-1FR");
+        assert!(last_friday.occurs_on(date(2025, 4, 25)));
+        assert!(last_friday.occurs_on(date(2025, 5, 30)));
+        assert!(!last_friday.occurs_on(date(2025, 5, 23)));
+        let on_the_day = repeating("20250131", "FREQ=MONTHLY");
+        assert!(on_the_day.occurs_on(date(2025, 3, 31)));
+        assert!(!on_the_day.occurs_on(date(2025, 2, 28)));
+    }
+
+    #[test]
+    fn repeats_yearly_in_the_listed_month() {
+        let thanksgiving = repeating("20251127", "FREQ=YEARLY;BYMONTH=11;BYDAY=4TH");
+        assert!(thanksgiving.occurs_on(date(2026, 11, 26)));
+        assert!(!thanksgiving.occurs_on(date(2026, 11, 27)));
+        let birthday = repeating("20250304", "FREQ=YEARLY");
+        assert!(birthday.occurs_on(date(2027, 3, 4)));
+        assert!(!birthday.occurs_on(date(2027, 4, 4)));
+    }
+
+    #[test]
+    fn leaves_out_skipped_repeats() {
+        let ics = "BEGIN:VEVENT\nSUMMARY:Standup\nDTSTART:20250303T093000\n\
+            RRULE:FREQ=DAILY\nEXDATE:20250304T093000,20250305T093000\nEND:VEVENT\n";
+        let event = parse(ics).remove(0);
+        assert!(event.occurs_on(date(2025, 3, 3)));
+        assert!(!event.occurs_on(date(2025, 3, 4)));
+        assert!(!event.occurs_on(date(2025, 3, 5)));
+        assert!(event.occurs_on(date(2025, 3, 6)));
+    }
+
+    #[test]
+    fn ignores_rules_it_cannot_read() {
+        assert!(recurrence("FREQ=HOURLY").is_none());
+        assert!(recurrence("INTERVAL=2").is_none());
+        let repeat = recurrence("FREQ=MONTHLY;BYDAY=+1MO,-2SU,XX,FR").unwrap();
+        assert_eq!(repeat.weekdays, vec![(1, 1), (-2, 0), (0, 5)]);
+    }
+}
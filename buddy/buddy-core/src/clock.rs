@@ -0,0 +1,449 @@
+use crate::{intent::Intent, rules::EXACT_CONFIDENCE};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Words that make a question about the calendar file.
+const CALENDAR_WORDS: &[&str] = &["calendar", "schedule", "agenda", "appointments", "meetings"];
+
+/// `TIME_ZONE_ID_DAYLIGHT`, which the windows crate doesn't export.
+#[cfg(windows)]
+const TIME_ZONE_ID_DAYLIGHT: u32 = 2;
+
+/// How a holiday's date is found in a given year.
+enum Rule {
+    Fixed { month: u32, day: u32 },
+    /// The `nth` `weekday` (0 is Sunday) of `month`; -1 is the last one.
+    Weekday { month: u32, weekday: u32, nth: i32 },
+    /// Days from Easter Sunday.
+    Easter(i64),
+}
+
+/// Spoken names (lowercase, without apostrophes), the name to read back, and
+/// the date. Movable US holidays follow the US calendar.
+const HOLIDAYS: &[(&[&str], &str, Rule)] = &[
+    (&["christmas", "christmas day", "xmas"], "Christmas", Rule::Fixed { month: 12, day: 25 }),
+    (&["christmas eve"], "Christmas Eve", Rule::Fixed { month: 12, day: 24 }),
+    (
+        &["new year", "new years", "new years day"],
+        "New Year's Day",
+        Rule::Fixed { month: 1, day: 1 },
+    ),
+    (&["new years eve"], "New Year's Eve", Rule::Fixed { month: 12, day: 31 }),
+    (
+        &["valentines", "valentines day"],
+        "Valentine's Day",
+        Rule::Fixed { month: 2, day: 14 },
+    ),
+    (
+        &["st patricks day", "saint patricks day"],
+        "St. Patrick's Day",
+        Rule::Fixed { month: 3, day: 17 },
+    ),
+    (&["halloween"], "Halloween", Rule::Fixed { month: 10, day: 31 }),
+    (
+        &["independence day", "fourth of july", "the fourth of july"],
+        "Independence Day",
+        Rule::Fixed { month: 7, day: 4 },
+    ),
+    (&["easter", "easter sunday"], "Easter", Rule::Easter(0)),
+    (&["good friday"], "Good Friday", Rule::Easter(-2)),
+    (
+        &["thanksgiving"],
+        "Thanksgiving",
+        Rule::Weekday { month: 11, weekday: 4, nth: 4 },
+    ),
+    (
+        &["mothers day"],
+        "Mother's Day",
+        Rule::Weekday { month: 5, weekday: 0, nth: 2 },
+    ),
+    (
+        &["fathers day"],
+        "Father's Day",
+        Rule::Weekday { month: 6, weekday: 0, nth: 3 },
+    ),
+    (
+        &["memorial day"],
+        "Memorial Day",
+        Rule::Weekday { month: 5, weekday: 1, nth: -1 },
+    ),
+    (&["labor day"], "Labor Day", Rule::Weekday { month: 9, weekday: 1, nth: 1 }),
+];
+
+/// A day in the (proleptic) Gregorian calendar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn new(year: i32, month: u32, day: u32) -> Option<Self> {
+        ((1..=12).contains(&month) && day >= 1 && day <= days_in_month(year, month))
+            .then_some(Self { year, month, day })
+    }
+
+    /// Days since 1970-01-01 (Howard Hinnant's `days_from_civil`).
+    pub fn days(self) -> i64 {
+        let year = i64::from(self.year) - i64::from(self.month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = i64::from(self.month);
+        let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5
+            + i64::from(self.day)
+            - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    pub fn from_days(days: i64) -> Self {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days - era * 146_097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        } as u32;
+        let year = (year_of_era + era * 400 + i64::from(month <= 2)) as i32;
+        Self { year, month, day }
+    }
+
+    pub fn add_days(self, days: i64) -> Self {
+        Self::from_days(self.days() + days)
+    }
+
+    /// 0 is Sunday.
+    pub fn weekday(self) -> u32 {
+        // 1970-01-01 was a Thursday.
+        (self.days() + 4).rem_euclid(7) as u32
+    }
+
+    pub fn weekday_name(self) -> &'static str {
+        WEEKDAYS[self.weekday() as usize]
+    }
+
+    /// "Tuesday, March 4".
+    pub fn spoken(self) -> String {
+        format!(
+            "{}, {} {}",
+            self.weekday_name(),
+            MONTHS[self.month as usize - 1],
+            self.day
+        )
+    }
+}
+
+pub(crate) fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Today's local date and the seconds since local midnight.
+pub fn now() -> (Date, u32) {
+    local_from_utc(timestamp())
+}
+
+/// Seconds since the Unix epoch.
+pub fn timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// The local date and seconds since midnight for a Unix timestamp.
+pub fn local_from_utc(secs: i64) -> (Date, u32) {
+    let local = secs + utc_offset(secs);
+    (
+        Date::from_days(local.div_euclid(86_400)),
+        local.rem_euclid(86_400) as u32,
+    )
+}
+
+/// The Unix timestamp of `secs` after local midnight on `date`.
+pub fn utc_from_local(date: Date, secs: u32) -> i64 {
+    let local = date.days() * 86_400 + i64::from(secs);
+    // Look the offset up at the approximate instant, so a daylight saving
+    // change earlier that day is accounted for.
+    local - utc_offset(local - utc_offset(local))
+}
+
+/// Seconds east of UTC at `secs`, daylight saving included.
+#[cfg(unix)]
+fn utc_offset(secs: i64) -> i64 {
+    let time = secs as libc::time_t;
+    unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&time, &mut tm).is_null() {
+            0
+        } else {
+            tm.tm_gmtoff as i64
+        }
+    }
+}
+
+/// Uses today's offset for every timestamp, which is only off for events on
+/// the far side of a daylight saving change.
+#[cfg(windows)]
+fn utc_offset(_secs: i64) -> i64 {
+    use windows::Win32::System::Time::{GetTimeZoneInformation, TIME_ZONE_INFORMATION};
+    let mut info = TIME_ZONE_INFORMATION::default();
+    let zone = unsafe { GetTimeZoneInformation(&mut info) };
+    let bias = if zone == TIME_ZONE_ID_DAYLIGHT {
+        info.Bias + info.DaylightBias
+    } else {
+        info.Bias + info.StandardBias
+    };
+    -i64::from(bias) * 60
+}
+
+#[cfg(not(any(unix, windows)))]
+fn utc_offset(_secs: i64) -> i64 {
+    0
+}
+
+/// "3:05 PM", from minutes after midnight.
+pub fn spoken_time(minutes: u32) -> String {
+    let (hour, minute) = (minutes / 60 % 24, minutes % 60);
+    let suffix = if hour < 12 { "AM" } else { "PM" };
+    let hour = match hour % 12 {
+        0 => 12,
+        hour => hour,
+    };
+    if minute == 0 {
+        format!("{} {}", hour, suffix)
+    } else {
+        format!("{}:{:02} {}", hour, minute, suffix)
+    }
+}
+
+/// Recognizes "what time is it", "what's the date", "what day is
+/// Christmas", and "what's on my calendar tomorrow" so they are answered
+/// without the intent model.
+pub fn recognize(transcript: &str) -> Option<Intent> {
+    let words = words(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    if words.iter().any(|word| CALENDAR_WORDS.contains(word))
+        && matches!(
+            words.first(),
+            Some(&("what" | "whats" | "do" | "read" | "any" | "anything" | "tell" | "show" | "is"))
+        )
+    {
+        let day = words.iter().find(|word| is_day(word)).unwrap_or(&"today");
+        return Some(Intent::Calendar {
+            target: day.to_string(),
+            confidence: EXACT_CONFIDENCE,
+        });
+    }
+    // "What time is it now", "what's the date today".
+    let (question, today) = match words.as_slice() {
+        [question @ .., "right", "now"] | [question @ .., "now"] => (question, false),
+        [question @ .., "today"] => (question, true),
+        question => (question, false),
+    };
+    let target = match question {
+        ["what", "time", "is", "it"]
+        | ["whats" | "what", "the", "time"]
+        | ["whats" | "what", "is", "the", "time"]
+        | ["tell", "me", "the", "time"]
+        | ["time"] => "time".to_string(),
+        ["whats" | "what", "the", "date"]
+        | ["whats" | "what", "is", "the", "date"]
+        | ["whats" | "what", "todays", "date"]
+        | ["whats" | "what", "is", "todays", "date"]
+        | ["what", "date", "is", "it"]
+        | ["todays", "date"] => "date".to_string(),
+        ["what", "day", "is", "it"] => "day".to_string(),
+        ["what", "day", "is"] | ["whats"] | ["what", "is"] if today => "day".to_string(),
+        ["what", "day" | "date", "is", holiday @ ..] | ["when", "is", holiday @ ..] => {
+            let holiday = holiday
+                .strip_suffix(&["this", "year"])
+                .unwrap_or(holiday)
+                .join(" ");
+            find_holiday(&holiday)?;
+            holiday
+        }
+        _ => return None,
+    };
+    Some(Intent::Time {
+        target,
+        confidence: EXACT_CONFIDENCE,
+    })
+}
+
+/// Whether `Intent::Time` can answer `target`: "time", "date", "day", or a
+/// holiday.
+pub fn is_query(target: &str) -> bool {
+    matches!(target, "time" | "date" | "day") || find_holiday(&holiday_key(target)).is_some()
+}
+
+/// The spoken answer to an `Intent::Time` target.
+pub fn answer(target: &str) -> Option<String> {
+    let (today, secs) = now();
+    Some(match target {
+        "time" => format!("It's {}", spoken_time(secs / 60)),
+        "date" => format!("It's {}, {}", today.spoken(), today.year),
+        "day" => format!("It's {}", today.weekday_name()),
+        holiday => {
+            let (name, date) = next_holiday(&holiday_key(holiday), today)?;
+            match date.days() - today.days() {
+                0 => format!("{} is today", name),
+                1 => format!("{} is tomorrow, {}", name, date.spoken()),
+                days => format!("{} is on {}, in {} days", name, date.spoken(), days),
+            }
+        }
+    })
+}
+
+/// "today", "tomorrow", "yesterday", a weekday (the next one, or today), or
+/// "2025-03-04", relative to `today`.
+pub fn resolve_day(target: &str, today: Date) -> Option<Date> {
+    let target = target.trim().to_lowercase();
+    if let Some(days) = day_offset(&target, today) {
+        return Some(today.add_days(days));
+    }
+    let mut parts = target.splitn(3, '-').map(str::parse::<u32>);
+    let (year, month, day) = (parts.next()?.ok()?, parts.next()?.ok()?, parts.next()?.ok()?);
+    Date::new(i32::try_from(year).ok()?, month, day)
+}
+
+/// "today" for today, or "on Friday, March 7".
+pub fn spoken_day(day: Date, today: Date) -> String {
+    match day.days() - today.days() {
+        0 => "today".to_string(),
+        1 => "tomorrow".to_string(),
+        -1 => "yesterday".to_string(),
+        _ => format!("on {}", day.spoken()),
+    }
+}
+
+/// "today", "tomorrow", "yesterday", or a weekday name.
+pub fn is_day(word: &str) -> bool {
+    matches!(word, "today" | "tonight" | "tomorrow" | "yesterday")
+        || WEEKDAYS.iter().any(|name| name.eq_ignore_ascii_case(word))
+}
+
+fn day_offset(word: &str, today: Date) -> Option<i64> {
+    match word {
+        "today" | "tonight" => Some(0),
+        "tomorrow" => Some(1),
+        "yesterday" => Some(-1),
+        _ => {
+            let weekday = WEEKDAYS
+                .iter()
+                .position(|name| name.eq_ignore_ascii_case(word))? as i64;
+            Some((weekday - i64::from(today.weekday())).rem_euclid(7))
+        }
+    }
+}
+
+/// Lowercase words without punctuation; "what's" becomes "whats".
+pub fn words(transcript: &str) -> Vec<String> {
+    transcript
+        .to_lowercase()
+        .chars()
+        .filter(|c| !matches!(c, '\'' | '’'))
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+fn holiday_key(name: &str) -> String {
+    words(name).join(" ")
+}
+
+fn find_holiday(key: &str) -> Option<&'static (&'static [&'static str], &'static str, Rule)> {
+    HOLIDAYS.iter().find(|(names, _, _)| names.contains(&key))
+}
+
+/// The holiday's name and its next date, counting today.
+fn next_holiday(key: &str, today: Date) -> Option<(&'static str, Date)> {
+    let (_, name, rule) = find_holiday(key)?;
+    let date = [today.year, today.year + 1]
+        .into_iter()
+        .filter_map(|year| holiday_date(rule, year))
+        .find(|date| *date >= today)?;
+    Some((name, date))
+}
+
+fn holiday_date(rule: &Rule, year: i32) -> Option<Date> {
+    match *rule {
+        Rule::Fixed { month, day } => Date::new(year, month, day),
+        Rule::Weekday {
+            month,
+            weekday,
+            nth,
+        } => {
+            if nth > 0 {
+                let first = Date::new(year, month, 1)?;
+                let offset = (i64::from(weekday) - i64::from(first.weekday())).rem_euclid(7);
+                Some(first.add_days(offset + 7 * i64::from(nth - 1)))
+            } else {
+                let last = Date::new(year, month, days_in_month(year, month))?;
+                let offset = (i64::from(last.weekday()) - i64::from(weekday)).rem_euclid(7);
+                Some(last.add_days(-offset - 7 * i64::from(-nth - 1)))
+            }
+        }
+        Rule::Easter(offset) => Some(easter(year).add_days(offset)),
+    }
+}
+
+/// Easter Sunday (the anonymous Gregorian algorithm).
+fn easter(year: i32) -> Date {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    Date {
+        year,
+        month: month as u32,
+        day: day as u32,
+    }
+}
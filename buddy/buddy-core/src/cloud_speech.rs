@@ -0,0 +1,240 @@
+//! Speech to text by a web service (OpenAI, Deepgram or Azure), behind
+//! `transcription.engine = "cloud"`.
+
+use crate::{
+    config::{CloudSttConfig, CloudSttProvider},
+    executor,
+    transcription::TranscriptionError,
+};
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde_json::Value;
+use std::time::Duration;
+
+const SAMPLE_RATE: u32 = 16_000;
+/// Wait before the first retry, doubled for each one after.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+const BOUNDARY: &str = "buddy-recording-boundary";
+
+pub struct Recognizer {
+    provider: CloudSttProvider,
+    endpoint: String,
+    api_key: String,
+    model: Option<String>,
+    timeout: Duration,
+    retries: u32,
+}
+
+impl Recognizer {
+    /// Fails unless `allow_cloud` is set and the provider has a key and an
+    /// address.
+    pub fn new(cfg: &CloudSttConfig, allow_cloud: bool) -> Result<Self, TranscriptionError> {
+        if !allow_cloud {
+            return Err(cloud_error(
+                "transcription.allow_cloud is off; set it to true to send recordings to the \
+                 [transcription.cloud] service",
+            ));
+        }
+        let endpoint = match (&cfg.endpoint, cfg.provider) {
+            (Some(endpoint), _) => endpoint.clone(),
+            (None, CloudSttProvider::OpenAi) => {
+                "https://api.openai.com/v1/audio/transcriptions".to_string()
+            }
+            (None, CloudSttProvider::Deepgram) => "https://api.deepgram.com/v1/listen".to_string(),
+            (None, CloudSttProvider::Azure) => {
+                return Err(cloud_error("Azure needs transcription.cloud.endpoint"))
+            }
+        };
+        let api_key = cfg
+            .api_key
+            .clone()
+            .filter(|key| !key.trim().is_empty())
+            .ok_or_else(|| cloud_error("transcription.cloud.api_key is not set"))?;
+        Ok(Self {
+            provider: cfg.provider,
+            endpoint,
+            api_key,
+            model: cfg.model.clone(),
+            timeout: Duration::from_secs(cfg.timeout_secs),
+            retries: cfg.retries,
+        })
+    }
+
+    /// Sends `audio` as a WAV file. `language` is a code such as "de", or
+    /// "auto" to have the service detect it; Azure takes its language from
+    /// the endpoint instead.
+    pub fn recognize(
+        &self,
+        audio: &[i16],
+        language: Option<&str>,
+    ) -> Result<String, TranscriptionError> {
+        let wav = wav(audio);
+        executor::block_on(self.send(wav, language))
+    }
+
+    /// Posts the recording, retrying timeouts, dropped connections and server
+    /// errors up to `retries` times.
+    async fn send(
+        &self,
+        wav: Vec<u8>,
+        language: Option<&str>,
+    ) -> Result<String, TranscriptionError> {
+        let client = Client::builder()
+            .timeout(self.timeout)
+            .build()
+            .map_err(|err| cloud_error(&err.to_string()))?;
+        let mut delay = RETRY_DELAY;
+        let mut attempt = 0;
+        loop {
+            let err = match self.request(&client, wav.clone(), language).send().await {
+                Ok(response) if response.status().is_success() => {
+                    let body: Value = response
+                        .json()
+                        .await
+                        .map_err(|err| cloud_error(&err.to_string()))?;
+                    return self.transcript(&body);
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    let detail = response.text().await.unwrap_or_default();
+                    let message = format!("{}: {}", status, detail.trim());
+                    if !retryable(status) {
+                        return Err(cloud_error(&message));
+                    }
+                    message
+                }
+                Err(err) if err.is_timeout() || err.is_connect() || err.is_request() => {
+                    err.to_string()
+                }
+                Err(err) => return Err(cloud_error(&err.to_string())),
+            };
+            if attempt >= self.retries {
+                return Err(cloud_error(&err));
+            }
+            attempt += 1;
+            eprintln!(
+                "Cloud transcription failed ({}); retrying ({}/{})",
+                err, attempt, self.retries
+            );
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+
+    fn request(&self, client: &Client, wav: Vec<u8>, language: Option<&str>) -> RequestBuilder {
+        let detect = language == Some("auto");
+        let language = language.filter(|_| !detect);
+        match self.provider {
+            CloudSttProvider::OpenAi => {
+                let model = self.model.as_deref().unwrap_or("whisper-1");
+                let mut fields = vec![("model", model)];
+                fields.extend(language.map(|language| ("language", language)));
+                client
+                    .post(&self.endpoint)
+                    .bearer_auth(&self.api_key)
+                    .header(
+                        "Content-Type",
+                        format!("multipart/form-data; boundary={}", BOUNDARY),
+                    )
+                    .body(multipart(&fields, "recording.wav", &wav))
+            }
+            CloudSttProvider::Deepgram => {
+                let mut query = vec![("smart_format", "true")];
+                query.extend(self.model.as_deref().map(|model| ("model", model)));
+                query.extend(language.map(|language| ("language", language)));
+                if detect {
+                    query.push(("detect_language", "true"));
+                }
+                client
+                    .post(&self.endpoint)
+                    .query(&query)
+                    .header("Authorization", format!("Token {}", self.api_key))
+                    .header("Content-Type", "audio/wav")
+                    .body(wav)
+            }
+            CloudSttProvider::Azure => client
+                .post(&self.endpoint)
+                .header("Ocp-Apim-Subscription-Key", &self.api_key)
+                .header(
+                    "Content-Type",
+                    format!("audio/wav; codecs=audio/pcm; samplerate={}", SAMPLE_RATE),
+                )
+                .body(wav),
+        }
+    }
+
+    /// The text out of a provider's reply; silence comes back empty.
+    fn transcript(&self, body: &Value) -> Result<String, TranscriptionError> {
+        let text = match self.provider {
+            CloudSttProvider::OpenAi => body.get("text"),
+            CloudSttProvider::Deepgram => {
+                body.pointer("/results/channels/0/alternatives/0/transcript")
+            }
+            CloudSttProvider::Azure => match body.get("RecognitionStatus").and_then(Value::as_str) {
+                Some("Success") => body.get("DisplayText"),
+                Some("NoMatch" | "InitialSilenceTimeout" | "BabbleTimeout") => {
+                    return Ok(String::new())
+                }
+                Some(status) => return Err(cloud_error(&format!("Azure: {}", status))),
+                None => None,
+            },
+        };
+        text.and_then(Value::as_str)
+            .map(|text| text.trim().to_string())
+            .ok_or_else(|| cloud_error(&format!("unexpected reply: {}", body)))
+    }
+}
+
+fn retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+fn cloud_error(message: &str) -> TranscriptionError {
+    TranscriptionError::Cloud(message.to_string())
+}
+
+/// 16 kHz mono 16-bit PCM in a WAV container.
+fn wav(audio: &[i16]) -> Vec<u8> {
+    let data_len = (audio.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + audio.len() * 2);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in audio {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+/// A multipart/form-data body of text `fields` and one WAV file.
+fn multipart(fields: &[(&str, &str)], file_name: &str, file: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(file.len() + 512);
+    for (name, value) in fields {
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                BOUNDARY, name, value
+            )
+            .as_bytes(),
+        );
+    }
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\
+             Content-Type: audio/wav\r\n\r\n",
+            BOUNDARY, file_name
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(file);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", BOUNDARY).as_bytes());
+    body
+}
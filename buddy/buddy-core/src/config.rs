@@ -0,0 +1,2058 @@
+use crate::prompt_template::{self, TemplateError};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub audio: AudioConfig,
+    #[serde(default)]
+    pub hotkey: HotkeyConfig,
+    #[serde(default)]
+    pub feedback: FeedbackConfig,
+    #[serde(default)]
+    pub deepseek: DeepSeekConfig,
+    /// Other models to put questions to by name ("ask chatgpt ...").
+    #[serde(default)]
+    pub assistants: HashMap<String, AssistantConfig>,
+    #[serde(default)]
+    pub transcription: TranscriptionConfig,
+    #[serde(default)]
+    pub files: HashMap<String, FileEntry>,
+    #[serde(default)]
+    pub applications: HashMap<String, String>,
+    /// Bookmarks: "open github" goes to the URL under `github`.
+    #[serde(default)]
+    pub urls: HashMap<String, String>,
+    /// Commands that take words from the request, e.g. sending a message.
+    #[serde(default)]
+    pub actions: HashMap<String, ActionTemplate>,
+    /// `[sendkeys]`: key presses run like `[actions]`, e.g. `save = "ctrl+s"`.
+    #[serde(default)]
+    pub sendkeys: HashMap<String, String>,
+    /// `[contexts."code.exe"]`: commands for while that program has the focus.
+    #[serde(default)]
+    pub contexts: HashMap<String, ContextConfig>,
+    #[serde(default)]
+    pub open_with: HashMap<String, String>,
+    #[serde(default)]
+    pub system: SystemConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    #[serde(default)]
+    pub confirmation: ConfirmationConfig,
+    #[serde(default)]
+    pub http: HttpConfig,
+    #[serde(default)]
+    pub policy: PolicyConfig,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    #[serde(default)]
+    pub meeting: MeetingConfig,
+    #[serde(default)]
+    pub calendar: CalendarConfig,
+    #[serde(default)]
+    pub weather: WeatherConfig,
+    #[serde(default)]
+    pub reminders: RemindersConfig,
+    #[serde(default)]
+    pub usage: UsageConfig,
+    #[serde(default)]
+    pub corrections: CorrectionsConfig,
+    #[serde(default)]
+    pub export: ExportConfig,
+    #[serde(default)]
+    pub macros: MacrosConfig,
+    #[serde(default)]
+    pub typing: TypingConfig,
+    #[serde(default)]
+    pub screenshot: ScreenshotConfig,
+    #[serde(default)]
+    pub media: MediaConfig,
+    #[serde(default)]
+    pub homeassistant: HomeAssistantConfig,
+    #[serde(default)]
+    pub wsl: WslConfig,
+    #[serde(default)]
+    pub dev: DevConfig,
+    #[serde(default)]
+    pub shell: ShellConfig,
+    #[serde(default)]
+    pub rag: RagConfig,
+    #[serde(default)]
+    pub phrases: PhrasesConfig,
+    /// `[profiles.work]` and so on: sections merged over the rest of the
+    /// file by `load`.
+    #[serde(default)]
+    pub profiles: HashMap<String, toml::Table>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AudioConfig {
+    #[allow(dead_code)]
+    pub device_name: Option<String>,
+    pub capture_duration_secs: u64,
+    pub silence_stop_secs: u64,
+    pub min_speech_secs: u64,
+    pub silence_threshold: i16,
+    pub noise_floor_secs: u64,
+    pub silence_floor_multiplier: f32,
+    pub silence_floor_offset: i16,
+    #[allow(dead_code)]
+    pub sample_rate: u32,
+    #[serde(default = "AudioConfig::default_level_warnings")]
+    pub level_warnings: bool,
+    #[serde(default = "AudioConfig::default_agc")]
+    pub agc: bool,
+    #[serde(default = "AudioConfig::default_agc_target_rms")]
+    pub agc_target_rms: f32,
+    #[serde(default = "AudioConfig::default_agc_max_gain")]
+    pub agc_max_gain: f32,
+    /// What meeting transcription records; commands always use the microphone.
+    #[serde(default)]
+    pub source: AudioSource,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioSource {
+    #[default]
+    Microphone,
+    /// Whatever the PC is playing (WASAPI loopback of the default output).
+    Loopback,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct HotkeyConfig {
+    /// Command mode: listen for a command and carry it out.
+    #[serde(default = "HotkeyConfig::default_key")]
+    pub key: String,
+    /// Dictation mode: type what was said into the focused window.
+    #[serde(default)]
+    pub dictation: Option<String>,
+    /// Stops a recording in progress and any speech.
+    #[serde(default)]
+    pub cancel: Option<String>,
+    /// Stops speech and an answer still being streamed, even mid-command.
+    #[serde(default)]
+    pub stop: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeedbackConfig {
+    #[serde(default = "FeedbackMode::default")]
+    pub mode: FeedbackMode,
+    pub success_sound: Option<PathBuf>,
+    pub error_sound: Option<PathBuf>,
+    #[serde(default)]
+    pub tts_engine: TtsEngine,
+    #[serde(default = "FeedbackConfig::default_voice")]
+    pub tts_voice: String,
+    /// Multiplier on the voice's normal speaking rate.
+    #[serde(default = "FeedbackConfig::default_tts_rate")]
+    pub tts_rate: f32,
+    /// 0.0 to 1.0, for both sounds and speech.
+    #[serde(default = "FeedbackConfig::default_volume")]
+    pub volume: f32,
+    #[serde(default = "FeedbackConfig::default_greeting")]
+    pub greeting: Option<String>,
+    pub greeting_sound: Option<PathBuf>,
+    /// Said on the way out (Ctrl-C, "buddy quit"); unset for silence.
+    pub goodbye: Option<String>,
+    /// Played when Buddy starts recording.
+    pub listen_sound: Option<PathBuf>,
+    /// Played when recording ends.
+    pub listen_stop_sound: Option<PathBuf>,
+    /// Played while waiting on the intent model.
+    pub thinking_sound: Option<PathBuf>,
+    /// Longest answer read out before Buddy asks whether to continue; 0 for
+    /// no limit.
+    #[serde(default = "FeedbackConfig::default_max_spoken_chars")]
+    pub max_spoken_chars: usize,
+    /// Volume multiplier for feedback while the user talks over it; heard
+    /// by the `listen_for_stop` listener.
+    #[serde(default = "FeedbackConfig::default_duck_volume")]
+    pub duck_volume: f32,
+    /// Listen in short windows while Buddy talks, and stop on "stop".
+    #[serde(default)]
+    pub listen_for_stop: bool,
+    /// Voice model (`.onnx`) for `tts_engine = "piper"`.
+    pub piper_model: Option<PathBuf>,
+    #[serde(default = "FeedbackConfig::default_piper_path")]
+    pub piper_path: String,
+    /// Voices for reading translations, keyed by language code or name
+    /// ("fr", "german"): a system voice name, or a Piper model path.
+    #[serde(default)]
+    pub voices: HashMap<String, String>,
+    /// Also show answers, errors, and reminders as Windows toasts.
+    #[serde(default)]
+    pub toast: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedbackMode {
+    Sound,
+    Tts,
+    Both,
+}
+
+impl FeedbackMode {
+    fn default() -> Self {
+        Self::Tts
+    }
+}
+
+/// Where spoken feedback comes from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TtsEngine {
+    /// SAPI on Windows, AVFoundation on macOS, speech-dispatcher or espeak-ng
+    /// on Linux.
+    #[default]
+    System,
+    /// Piper, a local neural voice.
+    Piper,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeepSeekConfig {
+    #[serde(default = "DeepSeekConfig::default_endpoint")]
+    pub endpoint: String,
+    #[serde(default = "DeepSeekConfig::default_model")]
+    pub model: String,
+    /// Longest wait for the model to send anything, which includes loading
+    /// it when it isn't in memory.
+    #[serde(default = "DeepSeekConfig::default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Longest wait to connect to `endpoint`.
+    #[serde(default = "DeepSeekConfig::default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    /// Further attempts after a failed connection, a timeout or a server
+    /// error.
+    #[serde(default = "DeepSeekConfig::default_retries")]
+    pub retries: u32,
+    #[serde(default = "DeepSeekConfig::default_max_transcript_chars")]
+    pub max_transcript_chars: usize,
+    #[serde(default)]
+    pub truncation: TruncationStrategy,
+    #[serde(default)]
+    pub fallback: IntentFallback,
+    #[serde(default)]
+    pub rules_first: bool,
+    #[serde(default)]
+    pub stream_answers: bool,
+    #[serde(default = "DeepSeekConfig::default_ready_timeout_secs")]
+    pub ready_timeout_secs: u64,
+    #[serde(default)]
+    pub start_degraded: bool,
+    #[serde(default)]
+    pub output_format: OutputFormat,
+    /// Intents that would act on the system below this confidence are
+    /// confirmed with "Did you mean ...?" first; 0 disables the check.
+    #[serde(default = "DeepSeekConfig::default_min_confidence")]
+    pub min_confidence: f32,
+    /// How many times a reply that isn't valid intent JSON is sent back with
+    /// `repair_prompt` before giving up.
+    #[serde(default = "DeepSeekConfig::default_repair_retries")]
+    pub repair_retries: u32,
+    #[serde(default = "DeepSeekConfig::default_repair_prompt")]
+    pub repair_prompt: String,
+    /// Sent for "how do you say X in French"; `{text}` and `{language}` are
+    /// filled in.
+    #[serde(default = "DeepSeekConfig::default_translate_prompt")]
+    pub translate_prompt: String,
+    /// A small, fast model asked first, such as "qwen2.5:0.5b"; `model` is
+    /// only asked for answers and what the router isn't sure of.
+    #[serde(default)]
+    pub router_model: Option<String>,
+    /// Router intents below this confidence are asked of `model` again.
+    #[serde(default = "DeepSeekConfig::default_router_confidence")]
+    pub router_confidence: f32,
+    /// How long Ollama keeps the models loaded between commands: seconds
+    /// ("-1" for good) or a duration such as "30m"; empty leaves it to
+    /// Ollama.
+    #[serde(default = "DeepSeekConfig::default_keep_alive")]
+    pub keep_alive: String,
+    /// Pulls a model Ollama doesn't have yet at startup.
+    #[serde(default)]
+    pub auto_pull: bool,
+    /// Sent as a system message ahead of every intent prompt.
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// A file holding the intent prompt, in place of the built-in one; see
+    /// `prompt_template` for its placeholders.
+    #[serde(default)]
+    pub prompt_template: Option<PathBuf>,
+    /// Generation options sent with every chat request; unset ones are left
+    /// to the model.
+    #[serde(default)]
+    pub options: ModelOptions,
+}
+
+/// An `[assistants.<name>]` entry. Commands are still classified by
+/// `[deepseek]`; only the questions asked of this assistant go to it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AssistantConfig {
+    #[serde(default)]
+    pub api: AssistantApi,
+    /// The API's usual address when unset: local Ollama, or OpenAI's.
+    pub endpoint: Option<String>,
+    pub model: String,
+    /// Sent as a bearer token.
+    pub api_key: Option<String>,
+    #[serde(default = "AssistantConfig::default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Sent as a system message ahead of each question; empty sends none.
+    #[serde(default = "AssistantConfig::default_system_prompt")]
+    pub system_prompt: String,
+}
+
+/// How an assistant is spoken to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AssistantApi {
+    /// Ollama's `/api/chat`.
+    #[default]
+    Ollama,
+    /// OpenAI-style chat completions, which most hosted models offer.
+    OpenAi,
+}
+
+/// Ollama's `options`. Those not named here (`num_predict`, `top_k`...) are
+/// passed along as written.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ModelOptions {
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    /// Context window, in tokens.
+    pub num_ctx: Option<u32>,
+    /// Makes replies repeatable; most useful with a temperature of 0.
+    pub seed: Option<i64>,
+    #[serde(flatten)]
+    pub other: toml::Table,
+}
+
+/// How strictly the model is held to the intent JSON.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Plain chat; for backends without structured output.
+    Off,
+    /// Any JSON object (Ollama `format: "json"`).
+    Json,
+    /// The intent schema itself (Ollama structured outputs).
+    #[default]
+    Schema,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IntentFallback {
+    #[default]
+    Rules,
+    Fail,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TruncationStrategy {
+    Head,
+    Tail,
+    #[default]
+    HeadTail,
+    Summarize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TranscriptionConfig {
+    #[serde(default)]
+    pub engine: SttEngine,
+    #[serde(default = "TranscriptionConfig::default_model_path")]
+    pub model_path: PathBuf,
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Threads per whisper pass; the physical cores (at most 16) when unset
+    /// or 0.
+    #[serde(default)]
+    pub threads: Option<usize>,
+    #[serde(default = "TranscriptionConfig::default_min_free_vram_mb")]
+    pub min_free_vram_mb: u64,
+    #[serde(default)]
+    pub translate: bool,
+    #[serde(default = "TranscriptionConfig::default_use_gpu")]
+    pub use_gpu: bool,
+    #[serde(default)]
+    pub gpu_device: i32,
+    /// Runs whisper once on a second of silence after loading, so the first
+    /// command isn't slowed down by one-time setup.
+    #[serde(default = "TranscriptionConfig::default_warmup")]
+    pub warmup: bool,
+    /// How far a command's target ("open résumé a") may be from a configured
+    /// file, app or URL name and still be corrected to it, as the share of
+    /// the name's letters that differ. 0 turns correction off.
+    #[serde(default = "TranscriptionConfig::default_command_bias")]
+    pub command_bias: f32,
+    /// Below this average word probability (0 to 1), whisper's transcript is
+    /// thrown away and the user asked to say it again. 0 turns it off.
+    #[serde(default = "TranscriptionConfig::default_min_confidence")]
+    pub min_confidence: f32,
+    /// Lets `engine = "cloud"` send recordings off this machine.
+    #[serde(default)]
+    pub allow_cloud: bool,
+    #[serde(default)]
+    pub cloud: CloudSttConfig,
+    /// `[transcription.models]`: whisper models by name, for `bench-stt` to
+    /// compare.
+    #[serde(default)]
+    pub models: HashMap<String, PathBuf>,
+}
+
+/// What turns speech into text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SttEngine {
+    /// whisper.cpp with `model_path`, on the GPU when built with CUDA.
+    #[default]
+    Whisper,
+    /// The speech recognizer built into Windows: no model file or GPU needed,
+    /// but less accurate, and only in the language Windows speech is set to.
+    Windows,
+    /// A speech-to-text service under `[transcription.cloud]`; needs
+    /// `allow_cloud`.
+    Cloud,
+}
+
+/// `[transcription.cloud]`: where `engine = "cloud"` sends each recording.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CloudSttConfig {
+    #[serde(default)]
+    pub provider: CloudSttProvider,
+    /// Overrides the provider's address. Azure has none, since it depends on
+    /// the region.
+    pub endpoint: Option<String>,
+    pub api_key: Option<String>,
+    /// The provider's model ("whisper-1", "nova-2"); its default when unset.
+    pub model: Option<String>,
+    #[serde(default = "CloudSttConfig::default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Further attempts after a timeout, a dropped connection or a server
+    /// error.
+    #[serde(default = "CloudSttConfig::default_retries")]
+    pub retries: u32,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CloudSttProvider {
+    /// OpenAI's Whisper API.
+    #[default]
+    OpenAi,
+    Deepgram,
+    /// Azure AI Speech, with an `endpoint` for the resource's region.
+    Azure,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SystemConfig {
+    #[serde(default)]
+    pub volume_mute: bool,
+    #[serde(default)]
+    pub volume_up: bool,
+    #[serde(default)]
+    pub volume_down: bool,
+    #[serde(default)]
+    pub volume_set: bool,
+    /// "What's the volume", which only reads it.
+    #[serde(default = "SystemConfig::default_volume_query")]
+    pub volume_query: bool,
+    /// Percentage points "volume up" and "turn it down a bit" move the volume
+    /// by.
+    #[serde(default = "SystemConfig::default_volume_step")]
+    pub volume_step: u8,
+    /// "Mute my mic", "unmute my mic" and "is my mic muted". Buddy won't
+    /// record while the microphone stays muted by it.
+    #[serde(default)]
+    pub mic_mute: bool,
+    /// "Turn off the screen", which sleeps the monitors until the mouse moves.
+    #[serde(default)]
+    pub screen_off: bool,
+    /// "Extend displays", "duplicate displays", "PC screen only" and "second
+    /// screen only", as Win+P offers them.
+    #[serde(default)]
+    pub display_extend: bool,
+    #[serde(default)]
+    pub display_duplicate: bool,
+    #[serde(default)]
+    pub display_internal: bool,
+    #[serde(default)]
+    pub display_external: bool,
+    /// "Rotate the screen" a quarter turn, and "rotate the screen back".
+    #[serde(default)]
+    pub rotate_screen: bool,
+    /// "Turn off bluetooth", "turn on bluetooth" and "is bluetooth on".
+    #[serde(default)]
+    pub bluetooth: bool,
+    /// "Turn off wifi", "turn on wifi" and "is wifi on".
+    #[serde(default)]
+    pub wifi: bool,
+    /// "Airplane mode on", which turns every radio off, and "airplane mode
+    /// off".
+    #[serde(default)]
+    pub airplane_mode: bool,
+    /// "Switch to high performance", "balanced mode" and "power saver".
+    #[serde(default)]
+    pub power_plan: bool,
+    /// "Battery status", which reads the charge and the time left.
+    #[serde(default)]
+    pub battery_status: bool,
+    #[serde(default)]
+    pub sleep: bool,
+    #[serde(default)]
+    pub shutdown: bool,
+    #[serde(default)]
+    pub restart: bool,
+    #[serde(default)]
+    pub lock: bool,
+    /// "Close chrome" and "is steam running".
+    #[serde(default)]
+    pub close_apps: bool,
+    /// "Kill chrome" and "kill the frozen app", which end programs without
+    /// letting them save.
+    #[serde(default)]
+    pub kill_apps: bool,
+    /// "Empty the recycle bin", without Windows asking first.
+    #[serde(default)]
+    pub empty_recycle_bin: bool,
+    /// "Create a new folder called X on the desktop".
+    #[serde(default)]
+    pub new_folder: bool,
+    /// "Delete the last screenshot" from `screenshot.dir`.
+    #[serde(default)]
+    pub delete_last_screenshot: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoggingConfig {
+    #[serde(default)]
+    pub debug: bool,
+    #[serde(default)]
+    pub whisper_log: bool,
+    #[serde(default)]
+    pub session_log: Option<PathBuf>,
+    #[serde(default)]
+    pub audit_log: Option<PathBuf>,
+    /// Key for the audit log's hashes, made on first use; defaults to the
+    /// log's path with a `.key` extension.
+    #[serde(default)]
+    pub audit_key: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConfirmationConfig {
+    #[serde(default = "ConfirmationConfig::default_actions")]
+    pub actions: Vec<String>,
+    #[serde(default = "ConfirmationConfig::default_toast")]
+    pub toast: bool,
+    #[serde(default = "ConfirmationConfig::default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// A `[files]` value: either a bare path, or a table that can add a search
+/// depth for folders (`budgets = { path = "D:/Budgets", depth = 2 }`) or an
+/// open command (`todo = { path = "todo.md", open_with = "code --goto {path}:{line}" }`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum FileEntry {
+    Path(PathBuf),
+    Mapping(FileMapping),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileMapping {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub depth: Option<usize>,
+    #[serde(default)]
+    pub open_with: Option<String>,
+}
+
+impl FileEntry {
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Path(path) => path,
+            Self::Mapping(mapping) => &mapping.path,
+        }
+    }
+
+    /// How deep a spoken query searches below this entry; only folders search.
+    pub fn search_depth(&self) -> Option<usize> {
+        match self {
+            Self::Mapping(FileMapping {
+                depth: Some(depth), ..
+            }) => Some(*depth),
+            _ if self.path().is_dir() => Some(Self::default_depth()),
+            _ => None,
+        }
+    }
+
+    pub fn open_with(&self) -> Option<&str> {
+        match self {
+            Self::Path(_) => None,
+            Self::Mapping(mapping) => mapping.open_with.as_deref(),
+        }
+    }
+
+    const fn default_depth() -> usize {
+        3
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HttpConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "HttpConfig::default_bind")]
+    pub bind: String,
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Serve session statistics for Prometheus at `GET /metrics`.
+    #[serde(default)]
+    pub metrics: bool,
+}
+
+/// What Buddy may do at all. Entries name an action kind ("open_app",
+/// "system") or a system action ("shutdown", "volume_set").
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PolicyConfig {
+    /// Never executed, even when confirmed.
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Confirmed first, like `[confirmation] actions`.
+    #[serde(default)]
+    pub confirm: Vec<String>,
+    /// Report what each action would run instead of running it;
+    /// `--dry-run` (or `--safe-mode`) sets this.
+    #[serde(default, alias = "safe_mode")]
+    pub dry_run: bool,
+}
+
+/// Shell commands run around each command Buddy executes. Each gets the
+/// intent as JSON on stdin and in `BUDDY_INTENT`, with `BUDDY_ACTION` and
+/// `BUDDY_TARGET`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HooksConfig {
+    /// Run before the command, which goes ahead only if this exits with 0.
+    #[serde(default)]
+    pub pre_execute: Option<String>,
+    /// Run after the command, with `BUDDY_SUCCEEDED` and `BUDDY_MESSAGE`
+    /// (what Buddy reported, or the error); Buddy doesn't wait for it.
+    #[serde(default)]
+    pub post_execute: Option<String>,
+    /// Longest a hook may run before it is stopped; a `pre_execute` stopped
+    /// this way cancels the command.
+    #[serde(default = "HooksConfig::default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// "Start transcribing" and `--transcribe`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MeetingConfig {
+    /// Where transcripts are written when no file is named.
+    #[serde(default = "MeetingConfig::default_dir")]
+    pub dir: PathBuf,
+    #[serde(default)]
+    pub format: TranscriptFormat,
+    /// Mark speaker changes; needs a tinydiarize model (`*-tdrz.bin`).
+    #[serde(default)]
+    pub speaker_turns: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptFormat {
+    #[default]
+    Markdown,
+    Srt,
+}
+
+impl TranscriptFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Srt => "srt",
+        }
+    }
+}
+
+/// "What's on my calendar today".
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CalendarConfig {
+    /// An `.ics` file, such as a calendar app's export or a synced copy of
+    /// its secret iCal address.
+    pub ics_path: Option<PathBuf>,
+}
+
+/// "What's the weather tomorrow".
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct WeatherConfig {
+    #[serde(default)]
+    pub provider: WeatherProvider,
+    /// A place name looked up with the provider's geocoding, e.g. "Berlin".
+    pub location: Option<String>,
+    /// Skip the place lookup; both must be set.
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Needed by providers other than Open-Meteo.
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub units: WeatherUnits,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum WeatherProvider {
+    #[serde(rename = "open-meteo")]
+    #[default]
+    OpenMeteo,
+    #[serde(rename = "openweathermap")]
+    OpenWeatherMap,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WeatherUnits {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+/// "Remind me to ..." and "take a note".
+#[derive(Debug, Clone, Deserialize)]
+pub struct RemindersConfig {
+    /// JSON file holding pending reminders and notes.
+    #[serde(default = "RemindersConfig::default_path")]
+    pub path: PathBuf,
+}
+
+/// Counts of the files, apps and URLs opened, for ranking them.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UsageConfig {
+    #[serde(default = "UsageConfig::default_enabled")]
+    pub enabled: bool,
+    /// JSON file holding the counts.
+    #[serde(default = "UsageConfig::default_path")]
+    pub path: PathBuf,
+    /// Days after which a use counts half as much; 0 never fades them.
+    #[serde(default = "UsageConfig::default_half_life_days")]
+    pub half_life_days: f64,
+    /// How many of the most used keys the prompts single out.
+    #[serde(default = "UsageConfig::default_top")]
+    pub top: usize,
+}
+
+/// "No, I meant ..." corrections, shown to the intent model as examples.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CorrectionsConfig {
+    #[serde(default = "CorrectionsConfig::default_enabled")]
+    pub enabled: bool,
+    /// JSON file holding the corrections.
+    #[serde(default = "CorrectionsConfig::default_path")]
+    pub path: PathBuf,
+    /// How many of the corrections most like a command go into its prompt.
+    #[serde(default = "CorrectionsConfig::default_examples")]
+    pub examples: usize,
+    /// The oldest corrections are dropped beyond this many.
+    #[serde(default = "CorrectionsConfig::default_limit")]
+    pub limit: usize,
+}
+
+/// "Save this conversation".
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExportConfig {
+    /// Where saved conversations are written.
+    #[serde(default = "ExportConfig::default_dir")]
+    pub dir: PathBuf,
+    /// Used unless the command names a format ("save this conversation as JSON").
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Markdown,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// "Start macro work morning" ... "end macro", then "run work morning".
+#[derive(Debug, Clone, Deserialize)]
+pub struct MacrosConfig {
+    /// JSON file holding the recorded macros.
+    #[serde(default = "MacrosConfig::default_path")]
+    pub path: PathBuf,
+    /// Pause recorded between steps; each step's `delay_ms` can be edited.
+    #[serde(default = "MacrosConfig::default_step_delay_ms")]
+    pub step_delay_ms: u64,
+}
+
+/// Dictation and "type that".
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TypingConfig {
+    /// Pause between typed characters, for applications that drop fast
+    /// input; 0 types everything at once.
+    #[serde(default)]
+    pub char_delay_ms: u64,
+}
+
+/// "Take a screenshot" and "what's on my screen".
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScreenshotConfig {
+    #[serde(default = "ScreenshotConfig::default_dir")]
+    pub dir: PathBuf,
+    /// A multimodal model that describes the screen, such as "llava"; unset
+    /// turns "what's on my screen" off.
+    pub vision_model: Option<String>,
+    /// Chat endpoint for `vision_model`; defaults to `deepseek.endpoint`.
+    pub vision_endpoint: Option<String>,
+    #[serde(default = "ScreenshotConfig::default_describe_prompt")]
+    pub describe_prompt: String,
+    /// Vision models are slow on large images, so they get their own limit.
+    #[serde(default = "ScreenshotConfig::default_vision_timeout_secs")]
+    pub vision_timeout_secs: u64,
+}
+
+/// Answers drawn from the user's own documents: the folders are split into
+/// passages, each embedded with an Ollama model, and the passages closest to
+/// a command are shown to the intent model with it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RagConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Searched with their subfolders for text, Markdown and PDF files.
+    #[serde(default)]
+    pub folders: Vec<PathBuf>,
+    /// JSON file holding the passages and their embeddings.
+    #[serde(default = "RagConfig::default_index_path")]
+    pub index_path: PathBuf,
+    #[serde(default = "RagConfig::default_embed_model")]
+    pub embed_model: String,
+    /// Ollama's `/api/embed`; defaults to the one next to `deepseek.endpoint`.
+    pub embed_endpoint: Option<String>,
+    /// Longest passage, in characters.
+    #[serde(default = "RagConfig::default_chunk_chars")]
+    pub chunk_chars: usize,
+    /// Most passages shown with a command.
+    #[serde(default = "RagConfig::default_top_k")]
+    pub top_k: usize,
+    /// Passages less similar to the command than this (0 to 1) are left out,
+    /// so that "open notepad" doesn't come with excerpts of a contract.
+    #[serde(default = "RagConfig::default_min_score")]
+    pub min_score: f32,
+}
+
+/// `[actions.send_teams_message]`: a command the intent model can run with
+/// free text taken from the request.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ActionTemplate {
+    /// Program and arguments, split like `open_with`; `{text}` becomes the
+    /// words the request supplies.
+    pub command: String,
+    /// What the action does, so the model knows when to pick it.
+    #[serde(default)]
+    pub description: String,
+}
+
+/// Commands scoped to one program, e.g. `[contexts."code.exe".commands.build]`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ContextConfig {
+    /// Like `[actions]`, and used over an action of the same name while the
+    /// program has the focus.
+    #[serde(default)]
+    pub commands: HashMap<String, ActionTemplate>,
+    /// Like `[sendkeys]`, and used over the global ones the same way.
+    #[serde(default)]
+    pub sendkeys: HashMap<String, String>,
+}
+
+/// A custom action as `Config::action` finds it.
+#[derive(Debug, Clone, Copy)]
+pub enum CustomAction<'a> {
+    /// Runs a program.
+    Command(&'a ActionTemplate),
+    /// Presses keys in the focused window: "ctrl+s", "ctrl+k ctrl+c".
+    Keys(&'a str),
+}
+
+/// Playback control and "what song is this".
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MediaConfig {
+    /// Spotify Web API access, needed to play requests and like songs.
+    pub spotify: Option<SpotifyConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpotifyConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    /// From Spotify's authorization code flow, with the
+    /// user-read-currently-playing, user-modify-playback-state and
+    /// user-library-modify scopes.
+    pub refresh_token: String,
+}
+
+/// Smart-home devices switched and asked about by voice ("turn off the
+/// living room lights"), through Home Assistant's REST API or MQTT.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HomeAssistantConfig {
+    /// Home Assistant's address, e.g. "http://homeassistant.local:8123".
+    #[serde(default)]
+    pub url: Option<String>,
+    /// A long-lived access token from the Home Assistant user profile.
+    #[serde(default)]
+    pub token: Option<String>,
+    /// The broker for devices with MQTT topics.
+    #[serde(default)]
+    pub mqtt: Option<MqttConfig>,
+    /// `[homeassistant.entities]`: the devices by the name they are called,
+    /// with underscores for spaces (`living_room_lights`).
+    #[serde(default)]
+    pub entities: HashMap<String, SmartEntity>,
+    /// How long to wait for Home Assistant or the broker.
+    #[serde(default = "HomeAssistantConfig::default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MqttConfig {
+    pub host: String,
+    #[serde(default = "MqttConfig::default_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default = "MqttConfig::default_client_id")]
+    pub client_id: String,
+}
+
+/// An `[homeassistant.entities]` value: a Home Assistant entity id
+/// (`"light.living_room"`), or a table with MQTT topics
+/// (`{ command_topic = "home/fan/set", state_topic = "home/fan" }`).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum SmartEntity {
+    Entity(String),
+    Mqtt(MqttEntity),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MqttEntity {
+    pub command_topic: String,
+    /// Where the device reports its state; without it, the state can't be
+    /// asked for.
+    #[serde(default)]
+    pub state_topic: Option<String>,
+    #[serde(default = "MqttEntity::default_payload_on")]
+    pub payload_on: String,
+    #[serde(default = "MqttEntity::default_payload_off")]
+    pub payload_off: String,
+}
+
+/// Commands run inside the Windows Subsystem for Linux ("run the deploy
+/// script in wsl"), with `wsl.exe -e`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WslConfig {
+    /// The distribution to run in; WSL's default one when unset.
+    #[serde(default)]
+    pub distro: Option<String>,
+    /// How long a command may run before it is stopped.
+    #[serde(default = "WslConfig::default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// How many lines of a command's output are read out, from the end,
+    /// where the result usually is.
+    #[serde(default = "WslConfig::default_spoken_lines")]
+    pub spoken_lines: usize,
+    /// `[wsl.commands.deploy]`: the commands by the name they are called.
+    #[serde(default)]
+    pub commands: HashMap<String, WslCommand>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WslCommand {
+    /// Program and arguments, split like `[actions]` and run without a
+    /// shell; `{text}` becomes the words the request supplies.
+    pub command: String,
+    /// What the command does, so the model knows when to pick it.
+    #[serde(default)]
+    pub description: String,
+    /// The Linux folder it runs in.
+    #[serde(default = "WslCommand::default_cwd")]
+    pub cwd: String,
+    /// Overrides `wsl.distro` for this command.
+    #[serde(default)]
+    pub distro: Option<String>,
+    #[serde(default)]
+    pub output: WslOutput,
+}
+
+/// What becomes of a WSL command's output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WslOutput {
+    /// Printed in the console and the JSON output, with "Ran deploy" said.
+    #[default]
+    Show,
+    /// The last `spoken_lines` lines read out as the answer.
+    Speak,
+    /// Dropped, for commands that open something.
+    Ignore,
+}
+
+/// Git checkouts asked about and updated by voice: "what branch am I on",
+/// "pull the repo", "open the last failing build".
+#[derive(Debug, Clone, Deserialize)]
+pub struct DevConfig {
+    /// `[dev.repos]`: each checkout's folder, by the name it is called.
+    #[serde(default)]
+    pub repos: HashMap<String, PathBuf>,
+    /// The repo meant when none is named; with a single repo, that one.
+    #[serde(default)]
+    pub default_repo: Option<String>,
+    /// How long git (or gh, for builds) may take before it is stopped.
+    #[serde(default = "DevConfig::default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// "Run command: ..." turns a request into a shell command with the intent
+/// model, confirms it, runs it and reads out a summary of what it printed.
+/// Off unless enabled.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShellConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// The folder commands run in; Buddy's own when unset.
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    /// How long a command may run before it is stopped.
+    #[serde(default = "ShellConfig::default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Output kept for the summary; longer output keeps its end.
+    #[serde(default = "ShellConfig::default_max_output_chars")]
+    pub max_output_chars: usize,
+    /// Have the intent model sum up the output; otherwise its last lines are
+    /// read out.
+    #[serde(default = "ShellConfig::default_summarize")]
+    pub summarize: bool,
+    /// When set, only commands starting with one of these programs run, and
+    /// never with operators.
+    #[serde(default)]
+    pub allowed_programs: Vec<String>,
+    /// Let commands chain, pipe and redirect with `&&`, `|`, `>` and the
+    /// like.
+    #[serde(default)]
+    pub allow_operators: bool,
+    /// Programs, with flags if they are only dangerous with them ("rm -r"),
+    /// that never run. Flags match however they're ordered or combined.
+    #[serde(default = "ShellConfig::default_blocked")]
+    pub blocked: Vec<String>,
+    /// Asks for the command; `{request}`, `{os}` and `{shell}` are filled in.
+    #[serde(default = "ShellConfig::default_command_prompt")]
+    pub command_prompt: String,
+    /// Asks for the summary; `{command}` and `{output}` are filled in.
+    #[serde(default = "ShellConfig::default_summary_prompt")]
+    pub summary_prompt: String,
+}
+
+/// What Buddy says, overriding the built-in English phrase by name.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PhrasesConfig {
+    /// The table used until the language is switched by voice.
+    #[serde(default = "PhrasesConfig::default_locale")]
+    pub locale: String,
+    /// `[phrases.de]` and so on: phrase name to text.
+    #[serde(flatten)]
+    pub locales: HashMap<String, HashMap<String, String>>,
+}
+
+impl PhrasesConfig {
+    fn default_locale() -> String {
+        "en".into()
+    }
+}
+
+impl Default for PhrasesConfig {
+    fn default() -> Self {
+        Self {
+            locale: Self::default_locale(),
+            locales: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads `path`, with `[profiles.<profile>]` laid over it if given:
+    /// tables such as `[files]` or `[system]` are merged key by key, so a
+    /// profile only lists what it changes; any other value is replaced.
+    /// A `deepseek.prompt_template` is checked here, so a broken one is
+    /// caught before the first command.
+    pub fn load(path: impl AsRef<Path>, profile: Option<&str>) -> Result<Self, ConfigError> {
+        let data = fs::read_to_string(path).map_err(ConfigError::Io)?;
+        let config = Self::parse(&data, profile)?;
+        if let Some(template) = &config.deepseek.prompt_template {
+            prompt_template::load(template).map_err(ConfigError::PromptTemplate)?;
+        }
+        Ok(config)
+    }
+
+    fn parse(data: &str, profile: Option<&str>) -> Result<Self, ConfigError> {
+        let Some(profile) = profile else {
+            return toml::from_str(data).map_err(ConfigError::Toml);
+        };
+        let mut table: toml::Table = data.parse().map_err(ConfigError::Toml)?;
+        let overrides = table
+            .get("profiles")
+            .and_then(|profiles| profiles.get(profile))
+            .and_then(toml::Value::as_table)
+            .cloned()
+            .ok_or_else(|| ConfigError::UnknownProfile(profile.to_string()))?;
+        merge_tables(&mut table, overrides);
+        toml::Value::Table(table).try_into().map_err(ConfigError::Toml)
+    }
+
+    /// The `[profiles]` name a spoken one refers to ("work" for "Work", and
+    /// "home office" for `home_office`).
+    pub fn profile_key(&self, spoken: &str) -> Option<String> {
+        self.profiles
+            .keys()
+            .find(|key| key.replace(['_', '-'], " ").eq_ignore_ascii_case(spoken))
+            .cloned()
+    }
+
+    pub fn deepseek_timeout(&self) -> Duration {
+        Duration::from_secs(self.deepseek.timeout_secs)
+    }
+
+    pub fn file_keys(&self) -> Vec<String> {
+        self.files.keys().cloned().collect()
+    }
+
+    /// The open command for `path`: the entry's own `open_with`, else the one
+    /// registered for its extension in `[open_with]`.
+    pub fn open_template<'a>(&'a self, entry: &'a FileEntry, path: &Path) -> Option<&'a str> {
+        entry.open_with().or_else(|| {
+            let extension = path.extension()?.to_string_lossy().to_lowercase();
+            self.open_with.get(&extension).map(String::as_str)
+        })
+    }
+
+    /// File keys that map to folders, which accept a search query.
+    pub fn folder_keys(&self) -> Vec<String> {
+        self.files
+            .iter()
+            .filter(|(_, entry)| entry.search_depth().is_some())
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    pub fn app_keys(&self) -> Vec<String> {
+        self.applications.keys().cloned().collect()
+    }
+
+    pub fn url_keys(&self) -> Vec<String> {
+        self.urls.keys().cloned().collect()
+    }
+
+    /// "name (description)" for each custom action `action` can find, for
+    /// the prompt.
+    pub fn action_summaries(&self, context: Option<&ContextConfig>) -> Vec<String> {
+        let mut names: Vec<&String> = context
+            .into_iter()
+            .flat_map(|context| context.commands.keys().chain(context.sendkeys.keys()))
+            .chain(self.actions.keys())
+            .chain(self.sendkeys.keys())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+            .into_iter()
+            .filter_map(|name| match self.action(name, context)? {
+                CustomAction::Command(action) if action.description.is_empty() => {
+                    Some(name.clone())
+                }
+                CustomAction::Command(action) => {
+                    Some(format!("{} ({})", name, action.description))
+                }
+                CustomAction::Keys(keys) => Some(format!("{} (press {})", name, keys)),
+            })
+            .collect()
+    }
+
+    /// What the custom action `name` does: its command or keys in `context`,
+    /// or else its `[actions]` or `[sendkeys]` entry.
+    pub fn action<'a>(
+        &'a self,
+        name: &str,
+        context: Option<&'a ContextConfig>,
+    ) -> Option<CustomAction<'a>> {
+        context
+            .and_then(|context| custom_action(&context.commands, &context.sendkeys, name))
+            .or_else(|| custom_action(&self.actions, &self.sendkeys, name))
+    }
+
+    pub fn system_actions(&self) -> Vec<&'static str> {
+        self.system.enabled_actions()
+    }
+}
+
+fn custom_action<'a>(
+    commands: &'a HashMap<String, ActionTemplate>,
+    sendkeys: &'a HashMap<String, String>,
+    name: &str,
+) -> Option<CustomAction<'a>> {
+    match commands.get(name) {
+        Some(action) => Some(CustomAction::Command(action)),
+        None => sendkeys.get(name).map(|keys| CustomAction::Keys(keys)),
+    }
+}
+
+impl AudioConfig {
+    const fn default_level_warnings() -> bool {
+        true
+    }
+
+    const fn default_agc() -> bool {
+        true
+    }
+
+    const fn default_agc_target_rms() -> f32 {
+        0.1
+    }
+
+    const fn default_agc_max_gain() -> f32 {
+        8.0
+    }
+}
+
+impl Default for HotkeyConfig {
+    fn default() -> Self {
+        Self {
+            key: Self::default_key(),
+            dictation: None,
+            cancel: None,
+            stop: None,
+        }
+    }
+}
+
+impl HotkeyConfig {
+    fn default_key() -> String {
+        "ctrl+alt+b".to_string()
+    }
+}
+
+impl Default for FeedbackConfig {
+    fn default() -> Self {
+        Self {
+            mode: FeedbackMode::default(),
+            success_sound: None,
+            error_sound: None,
+            tts_engine: TtsEngine::default(),
+            tts_voice: Self::default_voice(),
+            tts_rate: Self::default_tts_rate(),
+            volume: Self::default_volume(),
+            greeting: Self::default_greeting(),
+            greeting_sound: None,
+            goodbye: None,
+            listen_sound: None,
+            listen_stop_sound: None,
+            thinking_sound: None,
+            max_spoken_chars: Self::default_max_spoken_chars(),
+            duck_volume: Self::default_duck_volume(),
+            listen_for_stop: false,
+            piper_model: None,
+            piper_path: Self::default_piper_path(),
+            voices: HashMap::new(),
+            toast: false,
+        }
+    }
+}
+
+impl FeedbackConfig {
+    fn default_voice() -> String {
+        "default".to_string()
+    }
+
+    const fn default_tts_rate() -> f32 {
+        1.0
+    }
+
+    const fn default_volume() -> f32 {
+        1.0
+    }
+
+    const fn default_max_spoken_chars() -> usize {
+        400
+    }
+
+    const fn default_duck_volume() -> f32 {
+        0.3
+    }
+
+    fn default_greeting() -> Option<String> {
+        Some("Buddy ready".to_string())
+    }
+
+    fn default_piper_path() -> String {
+        "piper".to_string()
+    }
+}
+
+impl Default for DeepSeekConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: Self::default_endpoint(),
+            model: Self::default_model(),
+            timeout_secs: Self::default_timeout_secs(),
+            connect_timeout_secs: Self::default_connect_timeout_secs(),
+            retries: Self::default_retries(),
+            max_transcript_chars: Self::default_max_transcript_chars(),
+            truncation: TruncationStrategy::default(),
+            fallback: IntentFallback::default(),
+            rules_first: false,
+            stream_answers: false,
+            ready_timeout_secs: Self::default_ready_timeout_secs(),
+            start_degraded: false,
+            output_format: OutputFormat::default(),
+            min_confidence: Self::default_min_confidence(),
+            repair_retries: Self::default_repair_retries(),
+            repair_prompt: Self::default_repair_prompt(),
+            translate_prompt: Self::default_translate_prompt(),
+            router_model: None,
+            router_confidence: Self::default_router_confidence(),
+            keep_alive: Self::default_keep_alive(),
+            auto_pull: false,
+            system_prompt: None,
+            prompt_template: None,
+            options: ModelOptions::default(),
+        }
+    }
+}
+
+impl DeepSeekConfig {
+    fn default_endpoint() -> String {
+        "http://localhost:11434/api/chat".to_string()
+    }
+
+    fn default_model() -> String {
+        "deepseek-r1:latest".to_string()
+    }
+
+    const fn default_timeout_secs() -> u64 {
+        60
+    }
+
+    const fn default_connect_timeout_secs() -> u64 {
+        5
+    }
+
+    const fn default_retries() -> u32 {
+        2
+    }
+
+    const fn default_max_transcript_chars() -> usize {
+        1000
+    }
+
+    const fn default_ready_timeout_secs() -> u64 {
+        240
+    }
+
+    const fn default_min_confidence() -> f32 {
+        0.6
+    }
+
+    const fn default_router_confidence() -> f32 {
+        0.8
+    }
+
+    fn default_keep_alive() -> String {
+        "30m".to_string()
+    }
+
+    const fn default_repair_retries() -> u32 {
+        1
+    }
+
+    fn default_repair_prompt() -> String {
+        "Your last reply was not valid JSON. Reply with the JSON object only (keys action, target, query, response, confidence), no explanation.".to_string()
+    }
+
+    fn default_translate_prompt() -> String {
+        "Translate \"{text}\" into {language}. Reply with the translation only, in {language}'s own script, with no quotes, notes, or pronunciation guide.".to_string()
+    }
+}
+
+impl Default for TranscriptionConfig {
+    fn default() -> Self {
+        Self {
+            engine: SttEngine::default(),
+            model_path: Self::default_model_path(),
+            language: None,
+            threads: None,
+            min_free_vram_mb: Self::default_min_free_vram_mb(),
+            translate: false,
+            use_gpu: Self::default_use_gpu(),
+            gpu_device: 0,
+            warmup: Self::default_warmup(),
+            command_bias: Self::default_command_bias(),
+            min_confidence: Self::default_min_confidence(),
+            allow_cloud: false,
+            cloud: CloudSttConfig::default(),
+            models: HashMap::new(),
+        }
+    }
+}
+
+impl Default for CloudSttConfig {
+    fn default() -> Self {
+        Self {
+            provider: CloudSttProvider::default(),
+            endpoint: None,
+            api_key: None,
+            model: None,
+            timeout_secs: Self::default_timeout_secs(),
+            retries: Self::default_retries(),
+        }
+    }
+}
+
+impl CloudSttConfig {
+    const fn default_timeout_secs() -> u64 {
+        15
+    }
+
+    const fn default_retries() -> u32 {
+        2
+    }
+}
+
+impl AssistantConfig {
+    /// `endpoint`, or where `api` is usually found.
+    pub fn endpoint(&self) -> &str {
+        match (&self.endpoint, self.api) {
+            (Some(endpoint), _) => endpoint,
+            (None, AssistantApi::Ollama) => "http://localhost:11434/api/chat",
+            (None, AssistantApi::OpenAi) => "https://api.openai.com/v1/chat/completions",
+        }
+    }
+
+    const fn default_timeout_secs() -> u64 {
+        60
+    }
+
+    fn default_system_prompt() -> String {
+        "Your answer will be read aloud. Answer in a few plain sentences, without markdown, \
+         lists or code."
+            .to_string()
+    }
+}
+
+impl TranscriptionConfig {
+    fn default_model_path() -> PathBuf {
+        PathBuf::from("models/ggml-medium.en.bin")
+    }
+
+    const fn default_min_free_vram_mb() -> u64 {
+        1024
+    }
+
+    const fn default_use_gpu() -> bool {
+        true
+    }
+
+    const fn default_warmup() -> bool {
+        true
+    }
+
+    const fn default_command_bias() -> f32 {
+        0.25
+    }
+
+    const fn default_min_confidence() -> f32 {
+        0.4
+    }
+}
+
+impl Default for SystemConfig {
+    fn default() -> Self {
+        Self {
+            volume_mute: true,
+            volume_up: true,
+            volume_down: true,
+            volume_set: true,
+            volume_query: Self::default_volume_query(),
+            volume_step: Self::default_volume_step(),
+            mic_mute: true,
+            screen_off: true,
+            display_extend: true,
+            display_duplicate: true,
+            display_internal: true,
+            display_external: true,
+            rotate_screen: true,
+            bluetooth: true,
+            wifi: true,
+            airplane_mode: true,
+            power_plan: true,
+            battery_status: true,
+            sleep: true,
+            shutdown: true,
+            restart: true,
+            lock: true,
+            close_apps: false,
+            kill_apps: false,
+            empty_recycle_bin: false,
+            new_folder: false,
+            delete_last_screenshot: false,
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            debug: false,
+            whisper_log: false,
+            session_log: None,
+            audit_log: None,
+            audit_key: None,
+        }
+    }
+}
+
+impl Default for ConfirmationConfig {
+    fn default() -> Self {
+        Self {
+            actions: Self::default_actions(),
+            toast: Self::default_toast(),
+            timeout_secs: Self::default_timeout_secs(),
+        }
+    }
+}
+
+impl ConfirmationConfig {
+    fn default_actions() -> Vec<String> {
+        vec![
+            "shutdown".to_string(),
+            "restart".to_string(),
+            "kill_app".to_string(),
+            "empty_recycle_bin".to_string(),
+            "delete_last_screenshot".to_string(),
+        ]
+    }
+
+    const fn default_toast() -> bool {
+        true
+    }
+
+    const fn default_timeout_secs() -> u64 {
+        10
+    }
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: Self::default_bind(),
+            token: None,
+            metrics: false,
+        }
+    }
+}
+
+impl HttpConfig {
+    fn default_bind() -> String {
+        "127.0.0.1:7341".to_string()
+    }
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        Self {
+            pre_execute: None,
+            post_execute: None,
+            timeout_secs: Self::default_timeout_secs(),
+        }
+    }
+}
+
+impl HooksConfig {
+    const fn default_timeout_secs() -> u64 {
+        10
+    }
+}
+
+impl Default for MeetingConfig {
+    fn default() -> Self {
+        Self {
+            dir: Self::default_dir(),
+            format: TranscriptFormat::default(),
+            speaker_turns: false,
+        }
+    }
+}
+
+impl Default for RemindersConfig {
+    fn default() -> Self {
+        Self {
+            path: Self::default_path(),
+        }
+    }
+}
+
+impl RemindersConfig {
+    fn default_path() -> PathBuf {
+        PathBuf::from("reminders.json")
+    }
+}
+
+impl Default for UsageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: Self::default_enabled(),
+            path: Self::default_path(),
+            half_life_days: Self::default_half_life_days(),
+            top: Self::default_top(),
+        }
+    }
+}
+
+impl UsageConfig {
+    const fn default_enabled() -> bool {
+        true
+    }
+
+    fn default_path() -> PathBuf {
+        PathBuf::from("usage.json")
+    }
+
+    const fn default_half_life_days() -> f64 {
+        30.0
+    }
+
+    const fn default_top() -> usize {
+        5
+    }
+}
+
+impl Default for CorrectionsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: Self::default_enabled(),
+            path: Self::default_path(),
+            examples: Self::default_examples(),
+            limit: Self::default_limit(),
+        }
+    }
+}
+
+impl CorrectionsConfig {
+    const fn default_enabled() -> bool {
+        true
+    }
+
+    fn default_path() -> PathBuf {
+        PathBuf::from("corrections.json")
+    }
+
+    const fn default_examples() -> usize {
+        3
+    }
+
+    const fn default_limit() -> usize {
+        200
+    }
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            dir: Self::default_dir(),
+            format: ExportFormat::default(),
+        }
+    }
+}
+
+impl ExportConfig {
+    fn default_dir() -> PathBuf {
+        PathBuf::from("conversations")
+    }
+}
+
+impl Default for MacrosConfig {
+    fn default() -> Self {
+        Self {
+            path: Self::default_path(),
+            step_delay_ms: Self::default_step_delay_ms(),
+        }
+    }
+}
+
+impl MacrosConfig {
+    fn default_path() -> PathBuf {
+        PathBuf::from("macros.json")
+    }
+
+    const fn default_step_delay_ms() -> u64 {
+        500
+    }
+}
+
+impl Default for ScreenshotConfig {
+    fn default() -> Self {
+        Self {
+            dir: Self::default_dir(),
+            vision_model: None,
+            vision_endpoint: None,
+            describe_prompt: Self::default_describe_prompt(),
+            vision_timeout_secs: Self::default_vision_timeout_secs(),
+        }
+    }
+}
+
+impl ScreenshotConfig {
+    fn default_dir() -> PathBuf {
+        PathBuf::from("screenshots")
+    }
+
+    fn default_describe_prompt() -> String {
+        "This is a screenshot of the user's screen. In two or three short sentences for reading aloud, say which application is in front and what it shows. No markdown.".to_string()
+    }
+
+    const fn default_vision_timeout_secs() -> u64 {
+        120
+    }
+}
+
+impl Default for HomeAssistantConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            token: None,
+            mqtt: None,
+            entities: HashMap::new(),
+            timeout_secs: Self::default_timeout_secs(),
+        }
+    }
+}
+
+impl HomeAssistantConfig {
+    const fn default_timeout_secs() -> u64 {
+        5
+    }
+}
+
+impl MqttConfig {
+    const fn default_port() -> u16 {
+        1883
+    }
+
+    fn default_client_id() -> String {
+        "buddy".to_string()
+    }
+}
+
+impl MqttEntity {
+    fn default_payload_on() -> String {
+        "ON".to_string()
+    }
+
+    fn default_payload_off() -> String {
+        "OFF".to_string()
+    }
+}
+
+impl Default for WslConfig {
+    fn default() -> Self {
+        Self {
+            distro: None,
+            timeout_secs: Self::default_timeout_secs(),
+            spoken_lines: Self::default_spoken_lines(),
+            commands: HashMap::new(),
+        }
+    }
+}
+
+impl WslConfig {
+    const fn default_timeout_secs() -> u64 {
+        60
+    }
+
+    const fn default_spoken_lines() -> usize {
+        3
+    }
+}
+
+impl WslCommand {
+    fn default_cwd() -> String {
+        "~".to_string()
+    }
+}
+
+impl Default for DevConfig {
+    fn default() -> Self {
+        Self {
+            repos: HashMap::new(),
+            default_repo: None,
+            timeout_secs: Self::default_timeout_secs(),
+        }
+    }
+}
+
+impl DevConfig {
+    const fn default_timeout_secs() -> u64 {
+        30
+    }
+}
+
+impl Default for ShellConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cwd: None,
+            timeout_secs: Self::default_timeout_secs(),
+            max_output_chars: Self::default_max_output_chars(),
+            summarize: Self::default_summarize(),
+            allowed_programs: Vec::new(),
+            allow_operators: false,
+            blocked: Self::default_blocked(),
+            command_prompt: Self::default_command_prompt(),
+            summary_prompt: Self::default_summary_prompt(),
+        }
+    }
+}
+
+impl ShellConfig {
+    const fn default_timeout_secs() -> u64 {
+        30
+    }
+
+    const fn default_max_output_chars() -> usize {
+        4000
+    }
+
+    const fn default_summarize() -> bool {
+        true
+    }
+
+    fn default_blocked() -> Vec<String> {
+        [
+            "rm -r",
+            "rm --recursive",
+            "mkfs",
+            "dd if=",
+            "diskpart",
+            "format",
+            "shutdown",
+            "del /s",
+            "erase /s",
+            "rd /s",
+            "rmdir /s",
+            "reg delete",
+            "remove-item",
+        ]
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+    }
+
+    fn default_command_prompt() -> String {
+        "Write one {shell} command for {os} that does this: {request}. Prefer commands that only read and list things. Reply with the command only, on one line, with no explanation or code fence.".to_string()
+    }
+
+    fn default_summary_prompt() -> String {
+        "The command `{command}` printed the output below. Sum it up in one or two short sentences to be read aloud, without markdown.\n\n{output}".to_string()
+    }
+}
+
+impl Default for RagConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            folders: Vec::new(),
+            index_path: Self::default_index_path(),
+            embed_model: Self::default_embed_model(),
+            embed_endpoint: None,
+            chunk_chars: Self::default_chunk_chars(),
+            top_k: Self::default_top_k(),
+            min_score: Self::default_min_score(),
+        }
+    }
+}
+
+impl RagConfig {
+    fn default_index_path() -> PathBuf {
+        PathBuf::from("documents.json")
+    }
+
+    fn default_embed_model() -> String {
+        "nomic-embed-text".to_string()
+    }
+
+    const fn default_chunk_chars() -> usize {
+        1000
+    }
+
+    const fn default_top_k() -> usize {
+        3
+    }
+
+    const fn default_min_score() -> f32 {
+        0.5
+    }
+}
+
+impl MeetingConfig {
+    fn default_dir() -> PathBuf {
+        PathBuf::from("transcripts")
+    }
+}
+
+impl SystemConfig {
+    const fn default_volume_query() -> bool {
+        true
+    }
+
+    const fn default_volume_step() -> u8 {
+        10
+    }
+
+    pub fn enabled_actions(&self) -> Vec<&'static str> {
+        let mut actions = Vec::new();
+        if self.volume_mute {
+            actions.push("volume_mute");
+        }
+        if self.volume_up {
+            actions.push("volume_up");
+        }
+        if self.volume_down {
+            actions.push("volume_down");
+        }
+        if self.volume_set {
+            actions.push("volume_set");
+        }
+        if self.volume_query {
+            actions.push("volume_query");
+        }
+        if self.mic_mute {
+            actions.extend(["mic_mute", "mic_unmute", "mic_state"]);
+        }
+        if self.screen_off {
+            actions.push("screen_off");
+        }
+        if self.display_extend {
+            actions.push("display_extend");
+        }
+        if self.display_duplicate {
+            actions.push("display_duplicate");
+        }
+        if self.display_internal {
+            actions.push("display_internal");
+        }
+        if self.display_external {
+            actions.push("display_external");
+        }
+        if self.rotate_screen {
+            actions.push("rotate_screen");
+        }
+        if self.bluetooth {
+            actions.extend(["bluetooth_on", "bluetooth_off", "bluetooth_state"]);
+        }
+        if self.wifi {
+            actions.extend(["wifi_on", "wifi_off", "wifi_state"]);
+        }
+        if self.airplane_mode {
+            actions.extend(["airplane_mode_on", "airplane_mode_off"]);
+        }
+        if self.power_plan {
+            actions.extend([
+                "power_plan_balanced",
+                "power_plan_high_performance",
+                "power_plan_power_saver",
+            ]);
+        }
+        if self.battery_status {
+            actions.push("battery_status");
+        }
+        if self.sleep {
+            actions.push("sleep");
+        }
+        if self.shutdown {
+            actions.push("shutdown");
+        }
+        if self.restart {
+            actions.push("restart");
+        }
+        if self.lock {
+            actions.push("lock");
+        }
+        actions
+    }
+}
+
+fn merge_tables(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(value)) => {
+                merge_tables(base, value)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Toml(toml::de::Error),
+    UnknownProfile(String),
+    PromptTemplate(TemplateError),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read config: {}", err),
+            Self::Toml(err) => write!(f, "failed to parse config: {}", err),
+            Self::UnknownProfile(name) => write!(f, "no [profiles.{}] in config", name),
+            Self::PromptTemplate(err) => write!(f, "bad deepseek.prompt_template: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Toml(err) => Some(err),
+            Self::UnknownProfile(_) => None,
+            Self::PromptTemplate(err) => Some(err),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Commands Buddy got wrong and what was meant instead ("no, I meant open
+//! notepad"), kept as JSON at `corrections.path`. The few most like a new
+//! command are shown to the intent model as examples, so the same mistake
+//! isn't made twice.
+
+use crate::{clock, config::CorrectionsConfig, intent::Intent};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Corrections sharing less than this part of their words with a command
+/// aren't shown for it.
+const MIN_SIMILARITY: f64 = 0.3;
+
+/// Keeps two commands from interleaving a read and a write of the store.
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Store {
+    /// Oldest first.
+    #[serde(default)]
+    corrections: Vec<Correction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Correction {
+    /// What was said when Buddy got it wrong.
+    pub transcript: String,
+    /// What was meant, in the shape the model answers with.
+    pub intent: serde_json::Value,
+    /// Unix seconds.
+    pub at: i64,
+}
+
+#[derive(Debug)]
+pub enum CorrectionsError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+}
+
+impl std::fmt::Display for CorrectionsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "failed to access {}: {}", path.display(), err),
+            Self::Parse(path, err) => write!(f, "failed to read {}: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for CorrectionsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, err) => Some(err),
+            Self::Parse(_, err) => Some(err),
+        }
+    }
+}
+
+/// Saves `intent` as what `transcript` meant, replacing an earlier
+/// correction of the same words. Unknown intents teach nothing and are
+/// skipped.
+pub fn record(
+    config: &CorrectionsConfig,
+    transcript: &str,
+    intent: &Intent,
+) -> Result<(), CorrectionsError> {
+    if !config.enabled || matches!(intent, Intent::Unknown { .. }) {
+        return Ok(());
+    }
+    let mut meant = intent.to_json();
+    // A translation is made afresh each time; only answers are kept.
+    if !matches!(intent, Intent::Answer { .. }) {
+        meant["response"] = serde_json::Value::Null;
+    }
+    // The user said so, and the model's own examples leave out unused keys.
+    meant["confidence"] = serde_json::json!(1.0);
+    if let Some(fields) = meant.as_object_mut() {
+        fields.retain(|key, value| {
+            !value.is_null() || matches!(key.as_str(), "target" | "response")
+        });
+    }
+    let _guard = STORE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let path = &config.path;
+    let mut store = read(path)?;
+    let words = clock::words(transcript);
+    store
+        .corrections
+        .retain(|correction| clock::words(&correction.transcript) != words);
+    store.corrections.push(Correction {
+        transcript: transcript.trim().to_string(),
+        intent: meant,
+        at: clock::timestamp(),
+    });
+    let excess = store.corrections.len().saturating_sub(config.limit.max(1));
+    store.corrections.drain(..excess);
+    let text = serde_json::to_string_pretty(&store)
+        .map_err(|err| CorrectionsError::Parse(path.to_path_buf(), err))?;
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|err| CorrectionsError::Io(parent.to_path_buf(), err))?;
+    }
+    fs::write(path, text).map_err(|err| CorrectionsError::Io(path.to_path_buf(), err))
+}
+
+/// Up to `corrections.examples` corrections worded most like `transcript`,
+/// closest first; empty when there are none or the store can't be read.
+pub fn relevant(config: &CorrectionsConfig, transcript: &str) -> Vec<Correction> {
+    if !config.enabled || config.examples == 0 {
+        return Vec::new();
+    }
+    let store = {
+        let _guard = STORE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        read(&config.path)
+    };
+    let store = match store {
+        Ok(store) => store,
+        Err(err) => {
+            eprintln!("Ignoring corrections: {}", err);
+            return Vec::new();
+        }
+    };
+    let words = clock::words(transcript);
+    let mut scored: Vec<(f64, Correction)> = store
+        .corrections
+        .into_iter()
+        .map(|correction| (similarity(&words, &clock::words(&correction.transcript)), correction))
+        .filter(|(score, _)| *score >= MIN_SIMILARITY)
+        .collect();
+    // Of equally close ones, the newest wins.
+    scored.sort_by(|(a, a_correction), (b, b_correction)| {
+        b.partial_cmp(a)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| b_correction.at.cmp(&a_correction.at))
+    });
+    scored
+        .into_iter()
+        .take(config.examples)
+        .map(|(_, correction)| correction)
+        .collect()
+}
+
+/// The share of distinct words the two have in common.
+fn similarity(a: &[String], b: &[String]) -> f64 {
+    let a: HashSet<&String> = a.iter().collect();
+    let b: HashSet<&String> = b.iter().collect();
+    let all = a.union(&b).count();
+    if all == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / all as f64
+}
+
+fn read(path: &Path) -> Result<Store, CorrectionsError> {
+    match fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text)
+            .map_err(|err| CorrectionsError::Parse(path.to_path_buf(), err)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Store::default()),
+        Err(err) => Err(CorrectionsError::Io(path.to_path_buf(), err)),
+    }
+}
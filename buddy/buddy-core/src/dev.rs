@@ -0,0 +1,308 @@
+//! `[dev.repos]`: git by voice. "What branch am I on", "is the repo clean",
+//! "pull the repo", "what was the last commit" and "open the last failing
+//! build" run git (or the GitHub CLI, for builds) in the checkout and sum up
+//! what it printed in a sentence.
+
+use crate::{clock, config::Config, intent::Intent, process, rules::EXACT_CONFIDENCE};
+use std::{fmt, io, path::Path, process::Stdio, time::Duration};
+
+/// Targets of the dev action.
+pub const COMMANDS: &[&str] = &["branch", "status", "pull", "last_commit", "failing_build"];
+
+/// Separates the fields `git log` is asked for.
+const FIELD: char = '\u{1f}';
+
+#[derive(Debug)]
+pub enum DevError {
+    /// The program that couldn't be started: git or gh.
+    Start(&'static str, io::Error),
+    TimedOut(Duration),
+    UnknownRepo(String),
+    /// No repo was named and there is no default; the names there are.
+    NoRepo(Vec<String>),
+    /// git or gh exited with an error; the last thing it printed.
+    Failed(String),
+    NoFailingBuild,
+}
+
+impl fmt::Display for DevError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Start(program, err) => write!(f, "failed to run {}: {}", program, err),
+            Self::TimedOut(timeout) => {
+                write!(f, "took longer than {}s and was stopped", timeout.as_secs())
+            }
+            Self::UnknownRepo(name) => write!(f, "no repo named '{}' in [dev.repos]", name),
+            Self::NoRepo(names) => {
+                write!(f, "no repo named and no dev.default_repo among {}", names.join(", "))
+            }
+            Self::Failed(reason) => write!(f, "{}", reason),
+            Self::NoFailingBuild => write!(f, "no failed build runs"),
+        }
+    }
+}
+
+impl std::error::Error for DevError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Start(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Recognizes the dev commands, for the repo named in the request if any:
+/// "what branch is the website on", "git status", "pull the repo", "what
+/// was the last commit in buddy", "open the last failing build".
+pub fn recognize(transcript: &str, config: &Config) -> Option<Intent> {
+    if config.dev.repos.is_empty() {
+        return None;
+    }
+    let words = clock::words(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let has = |phrase: &[&str]| words.windows(phrase.len()).any(|window| window == phrase);
+    let repo = repo_named(&words, config);
+    // "Pull up my resume" and "what branch of science" aren't about git.
+    let about_repo = repo.is_some() || has(&["repo"]) || has(&["repository"]);
+    let command = match words.as_slice() {
+        ["what" | "whats" | "which", ..]
+            if has(&["branch"]) && (about_repo || words.ends_with(&["on"]) || has(&["current"])) =>
+        {
+            "branch"
+        }
+        ["git", "status", ..] => "status",
+        ["is" | "are", ..] if has(&["clean"]) && about_repo => "status",
+        ["git", "pull", ..] => "pull",
+        ["pull", ..] if !has(&["up"]) && (about_repo || has(&["latest"]) || has(&["changes"])) => {
+            "pull"
+        }
+        _ if has(&["last", "commit"]) || has(&["latest", "commit"]) => "last_commit",
+        ["open" | "show", ..]
+            if (has(&["failing"]) || has(&["failed"])) && (has(&["build"]) || has(&["run"])) =>
+        {
+            "failing_build"
+        }
+        _ => return None,
+    };
+    Some(Intent::Dev {
+        target: command.to_string(),
+        repo,
+        confidence: EXACT_CONFIDENCE,
+    })
+}
+
+/// The `[dev.repos]` key said somewhere in `words`, the longest if several
+/// are.
+fn repo_named(words: &[&str], config: &Config) -> Option<String> {
+    let spoken = format!(" {} ", words.join(" "));
+    config
+        .dev
+        .repos
+        .keys()
+        .filter(|key| spoken.contains(&format!(" {} ", key.replace('_', " ").to_lowercase())))
+        .max_by_key(|key| key.len())
+        .cloned()
+}
+
+/// What a dev command does, e.g. "pull buddy".
+pub fn describe(command: &str, repo: Option<&str>) -> String {
+    let name = repo.map_or("the repo".to_string(), |repo| repo.replace('_', " "));
+    match command {
+        "branch" => format!("check the branch of {}", name),
+        "status" => format!("check the status of {}", name),
+        "pull" => format!("pull {}", name),
+        "last_commit" => format!("read the last commit in {}", name),
+        "failing_build" => format!("open the last failing build of {}", name),
+        _ => format!("{} {}", command.replace('_', " "), name),
+    }
+}
+
+/// The repo `name` names, or the default one: its key and folder.
+pub fn repo<'a>(name: Option<&str>, config: &'a Config) -> Result<(&'a str, &'a Path), DevError> {
+    let repos = &config.dev.repos;
+    let name = match name.or(config.dev.default_repo.as_deref()) {
+        Some(name) => name,
+        None if repos.len() == 1 => repos.keys().next().map(String::as_str).unwrap_or_default(),
+        None => {
+            let mut names: Vec<String> = repos.keys().map(|key| key.replace('_', " ")).collect();
+            names.sort();
+            return Err(DevError::NoRepo(names));
+        }
+    };
+    repos
+        .get_key_value(name)
+        .map(|(key, path)| (key.as_str(), path.as_path()))
+        .ok_or_else(|| DevError::UnknownRepo(name.to_string()))
+}
+
+/// Runs `command`, other than "failing_build", in the checkout at `path`
+/// and returns what to say.
+pub fn run(command: &str, name: &str, path: &Path, config: &Config) -> Result<String, DevError> {
+    let timeout = Duration::from_secs(config.dev.timeout_secs);
+    let name = name.replace('_', " ");
+    match command {
+        "branch" => {
+            let branch = git(path, &["rev-parse", "--abbrev-ref", "HEAD"], timeout)?;
+            if branch != "HEAD" {
+                return Ok(format!("{} is on {}", name, branch));
+            }
+            let commit = git(path, &["rev-parse", "--short", "HEAD"], timeout)?;
+            Ok(format!("{} isn't on a branch; it's at commit {}", name, commit))
+        }
+        "status" => {
+            let status = git(path, &["status", "--porcelain=v1", "--branch"], timeout)?;
+            Ok(summarize_status(&name, &status))
+        }
+        "pull" => {
+            // Fast-forward only: a pull by voice shouldn't leave a merge to sort out.
+            let pulled = git(path, &["pull", "--ff-only"], timeout)?;
+            Ok(summarize_pull(&name, &pulled))
+        }
+        "last_commit" => {
+            let format = format!("--format=%s{}%an{}%ar", FIELD, FIELD);
+            let log = git(path, &["log", "-1", &format], timeout)?;
+            let mut fields = log.split(FIELD);
+            let subject = fields.next().unwrap_or_default();
+            let author = fields.next().unwrap_or_default();
+            let when = fields.next().unwrap_or_default();
+            Ok(format!(
+                "The last commit in {} is \"{}\" by {}, {}",
+                name, subject, author, when
+            ))
+        }
+        _ => Err(DevError::Failed(format!("'{}' isn't a dev command", command))),
+    }
+}
+
+/// The address of the most recent failed GitHub Actions run of the repo at
+/// `path`, from the GitHub CLI.
+pub fn failing_build(path: &Path, config: &Config) -> Result<String, DevError> {
+    let timeout = Duration::from_secs(config.dev.timeout_secs);
+    let args = ["run", "list", "--status", "failure", "--limit", "1", "--json", "url"];
+    let runs = output("gh", path, &args, timeout)?;
+    let runs: serde_json::Value =
+        serde_json::from_str(&runs).map_err(|err| DevError::Failed(err.to_string()))?;
+    runs[0]["url"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or(DevError::NoFailingBuild)
+}
+
+fn git(path: &Path, args: &[&str], timeout: Duration) -> Result<String, DevError> {
+    output("git", path, args, timeout)
+}
+
+/// Runs `program` in `path` and returns what it printed, trimmed.
+fn output(
+    program: &'static str,
+    path: &Path,
+    args: &[&str],
+    timeout: Duration,
+) -> Result<String, DevError> {
+    let child = process::hidden(program)
+        .args(args)
+        .current_dir(path)
+        // A pull that needs a password fails rather than waiting for one.
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| DevError::Start(program, err))?;
+    let output = process::wait_output(child, timeout)
+        .map_err(|err| DevError::Start(program, err))?
+        .ok_or(DevError::TimedOut(timeout))?;
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() {
+        return Ok(stdout);
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    let said = [stderr, stdout]
+        .into_iter()
+        .find_map(|text| text.lines().last().map(str::to_string));
+    Err(DevError::Failed(said.unwrap_or_else(|| output.status.to_string())))
+}
+
+/// `git status --porcelain --branch` in a sentence: "buddy is on main, 2
+/// commits behind origin/main, with 3 changed files".
+fn summarize_status(name: &str, status: &str) -> String {
+    let mut lines = status.lines();
+    let header = lines.next().unwrap_or_default().trim_start_matches("## ");
+    let (changed, untracked) = lines.fold((0, 0), |(changed, untracked), line| {
+        if line.starts_with("??") {
+            (changed, untracked + 1)
+        } else {
+            (changed + 1, untracked)
+        }
+    });
+    let header = header.strip_prefix("No commits yet on ").unwrap_or(header);
+    let (branch, tracking) = match header.split_once("...") {
+        Some((branch, rest)) => (branch, Some(rest)),
+        None => (header.split(' ').next().unwrap_or(header), None),
+    };
+    let mut summary = match branch {
+        "HEAD" => format!("{} isn't on a branch", name),
+        branch => format!("{} is on {}", name, branch),
+    };
+    if let Some(tracking) = tracking {
+        // "origin/main [ahead 1, behind 2]", or "[gone]" when the upstream
+        // branch was deleted.
+        let (upstream, marks) = match tracking.split_once(" [") {
+            Some((upstream, marks)) => (upstream, marks.trim_end_matches(']')),
+            None => (tracking, ""),
+        };
+        let count = |which: &str| {
+            marks
+                .split(", ")
+                .find_map(|mark| mark.strip_prefix(which)?.trim().parse::<usize>().ok())
+        };
+        let tracked = match (count("ahead"), count("behind")) {
+            _ if marks == "gone" => format!("and {} is gone", upstream),
+            (Some(ahead), Some(behind)) => format!(
+                "{} ahead of {} and {} behind",
+                plural(ahead, "commit"),
+                upstream,
+                behind
+            ),
+            (Some(ahead), None) => format!("{} ahead of {}", plural(ahead, "commit"), upstream),
+            (None, Some(behind)) => format!("{} behind {}", plural(behind, "commit"), upstream),
+            (None, None) => format!("up to date with {}", upstream),
+        };
+        summary.push_str(&format!(", {}", tracked));
+    }
+    let changes = match (changed, untracked) {
+        (0, 0) => "nothing to commit".to_string(),
+        (changed, 0) => plural(changed, "changed file"),
+        (0, untracked) => plural(untracked, "untracked file"),
+        (changed, untracked) => format!(
+            "{} and {}",
+            plural(changed, "changed file"),
+            plural(untracked, "untracked file")
+        ),
+    };
+    format!("{}, with {}", summary, changes)
+}
+
+/// What `git pull` did, from its last lines: "Pulled buddy: 3 files
+/// changed, 10 insertions, 2 deletions".
+fn summarize_pull(name: &str, pulled: &str) -> String {
+    if pulled.lines().any(|line| line.starts_with("Already up")) {
+        return format!("{} is already up to date", name);
+    }
+    match pulled.lines().rev().find(|line| line.contains(" changed")) {
+        Some(stat) => format!(
+            "Pulled {}: {}",
+            name,
+            stat.trim().replace("(+)", "").replace("(-)", "")
+        ),
+        None => format!("Pulled {}", name),
+    }
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
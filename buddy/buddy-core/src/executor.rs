@@ -0,0 +1,1309 @@
+use crate::{
+    answer,
+    calendar::{self, CalendarError},
+    clock,
+    dev::{self, DevError},
+    config::{Config, CustomAction, FileEntry, WslOutput},
+    files::{self, FilesError},
+    hooks::{self, HookError},
+    hotkey,
+    intent::{FileLocation, Intent, IntentAction, IntentClient},
+    macros::Recorder,
+    media::{self, MediaError},
+    process::{self, Process, ProcessError},
+    reminders::{self, Note, Reminder, ReminderError},
+    screen::{self, ScreenError},
+    search::{self, SearchOutcome},
+    shell::{self, ShellError},
+    smart_home::{self, SmartHomeError},
+    ui_automation::{self, UiError},
+    urls, usage, weather,
+    windows_api::{
+        self, BatteryStatus, PowerPlan, RadioKind, SystemAction, Topology, WindowsActionError,
+    },
+    wsl::{self, WslError},
+};
+use std::{collections::HashMap, future::Future, path::Path, sync::OnceLock, time::Duration};
+
+/// Most choices read back when a search is ambiguous.
+const MAX_CHOICES: usize = 3;
+
+/// How long "close" waits to report whether a program exited.
+const CLOSE_WAIT: Duration = Duration::from_secs(3);
+
+/// How long "kill" lets a program close itself before ending it.
+const KILL_GRACE: Duration = Duration::from_secs(2);
+
+/// Executes one kind of intent. Handlers are registered with the executor by
+/// action, so new actions plug in without touching the dispatch code.
+pub trait IntentHandler: Send + Sync {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError>;
+}
+
+pub struct CommandExecutor<'a> {
+    config: &'a Config,
+    handlers: HashMap<IntentAction, Box<dyn IntentHandler>>,
+    recorder: Option<&'a Recorder>,
+}
+
+impl<'a> CommandExecutor<'a> {
+    pub fn new(config: &'a Config) -> Self {
+        let mut executor = Self {
+            config,
+            handlers: HashMap::new(),
+            recorder: None,
+        };
+        executor.register(IntentAction::OpenFile, OpenFileHandler);
+        executor.register(IntentAction::OpenApp, OpenAppHandler);
+        executor.register(IntentAction::OpenUrl, OpenUrlHandler);
+        executor.register(IntentAction::System, SystemHandler);
+        executor.register(IntentAction::Answer, AnswerHandler);
+        executor.register(IntentAction::Translate, TranslateHandler);
+        executor.register(IntentAction::Ask, AskHandler);
+        executor.register(IntentAction::Time, TimeHandler);
+        executor.register(IntentAction::Calendar, CalendarHandler);
+        executor.register(IntentAction::Weather, WeatherHandler);
+        executor.register(IntentAction::Remind, RemindHandler);
+        executor.register(IntentAction::Note, NoteHandler);
+        executor.register(IntentAction::List, ListHandler);
+        executor.register(IntentAction::Screenshot, ScreenshotHandler);
+        executor.register(IntentAction::DescribeScreen, DescribeScreenHandler);
+        executor.register(IntentAction::ReadText, ReadTextHandler);
+        executor.register(IntentAction::CloseApp, CloseAppHandler);
+        executor.register(IntentAction::KillApp, KillAppHandler);
+        executor.register(IntentAction::AppRunning, AppRunningHandler);
+        executor.register(IntentAction::Media, MediaHandler);
+        executor.register(IntentAction::SmartHome, SmartHomeHandler);
+        executor.register(IntentAction::Wsl, WslHandler);
+        executor.register(IntentAction::Dev, DevHandler);
+        executor.register(IntentAction::Shell, ShellHandler);
+        executor.register(IntentAction::Files, FilesHandler);
+        executor.register(IntentAction::Custom, CustomHandler);
+        executor
+    }
+
+    /// Adds or replaces the handler for `action`, returning the previous one.
+    pub fn register(
+        &mut self,
+        action: IntentAction,
+        handler: impl IntentHandler + 'static,
+    ) -> Option<Box<dyn IntentHandler>> {
+        self.handlers.insert(action, Box::new(handler))
+    }
+
+    pub fn config(&self) -> &'a Config {
+        self.config
+    }
+
+    /// Adds each intent that runs without error to `recorder`, for a macro.
+    pub fn with_recorder(mut self, recorder: &'a Recorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    pub fn execute(&self, intent: &Intent) -> Result<ExecutionResult, ExecutionError> {
+        let handler = self
+            .handlers
+            .get(&intent.action())
+            .ok_or(ExecutionError::UnknownIntent)?;
+        if let Some(name) = denied(self.config, intent) {
+            return Err(ExecutionError::Denied(name.to_string()));
+        }
+        // Nothing runs in a dry run, so there is nothing to hook.
+        let hooked = !self.config.policy.dry_run;
+        if hooked {
+            hooks::before(&self.config.hooks, intent).map_err(ExecutionError::Hook)?;
+        }
+        let result = handler.handle(intent, self.config);
+        if hooked {
+            hooks::after(&self.config.hooks, intent, &result);
+        }
+        let result = result?;
+        if let ExecutionResult::Action(_) = result {
+            if let Err(err) = usage::record(&self.config.usage, intent) {
+                eprintln!("Failed to record usage: {}", err);
+            }
+        }
+        // Of several matches, the one picked is recorded when it runs.
+        let choosing = matches!(result, ExecutionResult::Choose(_));
+        if let Some(recorder) = self.recorder.filter(|_| !choosing) {
+            recorder.record(intent);
+        }
+        Ok(result)
+    }
+}
+
+/// The names `[policy]` and `[confirmation]` lists match an intent by: its
+/// action kind, its target for system, file, smart-home, WSL, dev and
+/// custom actions (see `system_action_name`), and a smart-home command's
+/// name (see `smart_home::policy_name`).
+pub fn policy_names(intent: &Intent) -> [&str; 3] {
+    let action = intent.action().name();
+    let target = match intent {
+        Intent::System { target, .. } => system_action_name(target),
+        Intent::Files { target, .. }
+        | Intent::SmartHome { target, .. }
+        | Intent::Wsl { target, .. }
+        | Intent::Dev { target, .. }
+        | Intent::Custom { target, .. } => target.as_str(),
+        _ => action,
+    };
+    let command = match intent {
+        Intent::SmartHome { command, .. } => smart_home::policy_name(command),
+        _ => action,
+    };
+    [action, target, command]
+}
+
+/// The `[system]` action a target belongs to: `volume_set_40` is
+/// `volume_set`, `volume_up_20` is `volume_up`, `rotate_screen_90` is
+/// `rotate_screen`.
+pub fn system_action_name(target: &str) -> &str {
+    ["volume_set", "volume_up", "volume_down", "rotate_screen"]
+        .into_iter()
+        .find(|name| {
+            target
+                .strip_prefix(name)
+                .and_then(|rest| rest.strip_prefix('_'))
+                .is_some_and(|amount| amount.parse::<u16>().is_ok())
+        })
+        .unwrap_or(target)
+}
+
+/// The `policy.deny` entry that blocks `intent`, if any.
+pub fn denied<'a>(config: &'a Config, intent: &Intent) -> Option<&'a str> {
+    let names = policy_names(intent);
+    config
+        .policy
+        .deny
+        .iter()
+        .find(|name| names.contains(&name.as_str()))
+        .map(String::as_str)
+}
+
+/// Waits for `future` from synchronous code, such as a handler. Handlers
+/// run on threads the async runtime owns, where a future can't be waited
+/// on, so it is driven from a thread of its own by a runtime shared by all
+/// such requests.
+pub(crate) fn block_on<F>(future: F) -> F::Output
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    let runtime = RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .expect("failed to build the handler runtime")
+    });
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| runtime.block_on(future))
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
+}
+
+struct OpenFileHandler;
+
+impl IntentHandler for OpenFileHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::OpenFile {
+            target, query, at, ..
+        } = intent
+        else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        let entry = config
+            .files
+            .get(target)
+            .ok_or_else(|| ExecutionError::MissingMapping(target.to_string()))?;
+        let path = entry.path();
+        let resolved = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            std::env::current_dir()
+                .map_err(ExecutionError::Io)?
+                .join(path)
+        };
+        let (Some(query), Some(depth)) = (query, entry.search_depth()) else {
+            if config.policy.dry_run {
+                return dry_run_open(config, entry, &resolved, *at);
+            }
+            open_file(config, entry, &resolved, *at)?;
+            return Ok(ExecutionResult::Action(format!("Opened {}", target)));
+        };
+        match search::find_file(&resolved, query, depth) {
+            SearchOutcome::Found(file) if config.policy.dry_run => {
+                dry_run_open(config, entry, &file, *at)
+            }
+            SearchOutcome::Found(file) => {
+                open_file(config, entry, &file, *at)?;
+                Ok(ExecutionResult::Action(format!("Opened {}", file.display())))
+            }
+            SearchOutcome::Ambiguous(matches) => Ok(ExecutionResult::Choose(
+                matches
+                    .into_iter()
+                    .take(MAX_CHOICES)
+                    .map(|candidate| Choice {
+                        label: spoken_name(&candidate.path),
+                        // The exact file name is an unambiguous query.
+                        intent: Intent::OpenFile {
+                            target: target.clone(),
+                            query: candidate
+                                .path
+                                .file_name()
+                                .map(|name| name.to_string_lossy().to_string()),
+                            at: *at,
+                            confidence: 1.0,
+                        },
+                    })
+                    .collect(),
+            )),
+            SearchOutcome::None => Err(ExecutionError::NoMatch(query.clone())),
+        }
+    }
+}
+
+struct OpenAppHandler;
+
+impl IntentHandler for OpenAppHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::OpenApp { target, .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        let command = config
+            .applications
+            .get(target)
+            .ok_or_else(|| ExecutionError::MissingMapping(target.to_string()))?;
+        if config.policy.dry_run {
+            return Ok(ExecutionResult::DryRun(format!("Would launch: {}", command)));
+        }
+        windows_api::launch(command).map_err(ExecutionError::Windows)?;
+        Ok(ExecutionResult::Action(format!("Launched {}", target)))
+    }
+}
+
+struct OpenUrlHandler;
+
+impl IntentHandler for OpenUrlHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::OpenUrl { target, .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        let url = urls::resolve(target, config)
+            .ok_or_else(|| ExecutionError::MissingMapping(target.to_string()))?;
+        if config.policy.dry_run {
+            return Ok(ExecutionResult::DryRun(format!("Would open {}", url)));
+        }
+        windows_api::open_url(&url).map_err(ExecutionError::Windows)?;
+        Ok(ExecutionResult::Action(format!("Opened {}", url)))
+    }
+}
+
+struct SystemHandler;
+
+impl IntentHandler for SystemHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::System { target, .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        let action = parse_system_action(target, config.system.volume_step)?;
+        // Reading the volume, the microphone, a radio or the battery changes
+        // nothing, so dry runs do it too.
+        match action {
+            SystemAction::VolumeQuery => {
+                let (level, muted) =
+                    windows_api::volume_level().map_err(ExecutionError::Windows)?;
+                let muted = if muted { ", muted" } else { "" };
+                return Ok(ExecutionResult::Answer(format!(
+                    "The volume is at {}%{}",
+                    level, muted
+                )));
+            }
+            SystemAction::MicQuery => {
+                let muted = windows_api::mic_muted().map_err(ExecutionError::Windows)?;
+                let state = if muted { "muted" } else { "on" };
+                return Ok(ExecutionResult::Answer(format!("Your microphone is {}", state)));
+            }
+            SystemAction::RadioQuery(kind) => {
+                let answer = match windows_api::radio_on(kind).map_err(ExecutionError::Windows)? {
+                    Some(true) => format!("{} is on", kind),
+                    Some(false) => format!("{} is off", kind),
+                    None => format!("This PC has no {}", kind),
+                };
+                return Ok(ExecutionResult::Answer(answer));
+            }
+            SystemAction::BatteryQuery => {
+                let answer = match windows_api::battery_status().map_err(ExecutionError::Windows)? {
+                    Some(status) => describe_battery(status),
+                    None => "This PC has no battery".to_string(),
+                };
+                return Ok(ExecutionResult::Answer(answer));
+            }
+            _ => {}
+        }
+        if config.policy.dry_run {
+            return Ok(ExecutionResult::DryRun(format!(
+                "Would {}",
+                describe_system_action(action)
+            )));
+        }
+        windows_api::execute_system(action).map_err(ExecutionError::Windows)?;
+        Ok(ExecutionResult::Action(format!("Executed {}", target)))
+    }
+}
+
+struct AnswerHandler;
+
+impl IntentHandler for AnswerHandler {
+    fn handle(&self, intent: &Intent, _config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::Answer { response, .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        Ok(ExecutionResult::Answer(response.clone()))
+    }
+}
+
+struct AskHandler;
+
+impl IntentHandler for AskHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::Ask {
+            assistant,
+            question,
+            ..
+        } = intent
+        else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        if config.policy.dry_run {
+            return Ok(ExecutionResult::DryRun(format!("Would ask {}", assistant)));
+        }
+        let client = IntentClient::new(config);
+        let text = match block_on(client.ask(assistant, question, config)) {
+            Ok(response) if !response.is_empty() => response,
+            Ok(_) => format!("{} had nothing to say.", assistant),
+            Err(err) => {
+                eprintln!("Asking {} failed: {}", assistant, err);
+                format!("I couldn't get an answer from {}.", assistant)
+            }
+        };
+        Ok(ExecutionResult::Answer(text))
+    }
+}
+
+struct TranslateHandler;
+
+impl IntentHandler for TranslateHandler {
+    fn handle(&self, intent: &Intent, _config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::Translate {
+            language,
+            translation: Some(translation),
+            ..
+        } = intent
+        else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        Ok(ExecutionResult::Translation {
+            text: translation.clone(),
+            language: language.clone(),
+        })
+    }
+}
+
+struct TimeHandler;
+
+impl IntentHandler for TimeHandler {
+    fn handle(&self, intent: &Intent, _config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::Time { target, .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        clock::answer(target)
+            .map(ExecutionResult::Answer)
+            .ok_or_else(|| ExecutionError::NoMatch(target.clone()))
+    }
+}
+
+struct CalendarHandler;
+
+impl IntentHandler for CalendarHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::Calendar { target, .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        let Some(path) = &config.calendar.ics_path else {
+            return Ok(ExecutionResult::Answer(
+                "No calendar is set up. Add calendar.ics_path to the config.".to_string(),
+            ));
+        };
+        let today = clock::now().0;
+        let day = clock::resolve_day(target, today)
+            .ok_or_else(|| ExecutionError::NoMatch(target.clone()))?;
+        let events = calendar::load(path).map_err(ExecutionError::Calendar)?;
+        let agenda = calendar::events_on(&events, day);
+        Ok(ExecutionResult::Answer(calendar::spoken_agenda(&agenda, day, today)))
+    }
+}
+
+struct WeatherHandler;
+
+impl IntentHandler for WeatherHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::Weather { target, .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        if config.policy.dry_run {
+            return Ok(ExecutionResult::DryRun(format!(
+                "Would check the weather for {}",
+                target
+            )));
+        }
+        let text = block_on(weather::forecast(&config.weather, target)).unwrap_or_else(|err| {
+            eprintln!("Weather lookup failed: {}", err);
+            "I couldn't get the weather forecast.".to_string()
+        });
+        Ok(ExecutionResult::Answer(text))
+    }
+}
+
+struct RemindHandler;
+
+impl IntentHandler for RemindHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::Remind { text, when, .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        let now = clock::timestamp();
+        let Some(due) = reminders::due(when, now) else {
+            return Ok(ExecutionResult::Answer(
+                "I didn't catch when. Try \"remind me to call Bob at 3pm\".".to_string(),
+            ));
+        };
+        let when = reminders::spoken_due(due, now);
+        if config.policy.dry_run {
+            return Ok(ExecutionResult::DryRun(format!("Would remind you {}: {}", when, text)));
+        }
+        let reminder = Reminder {
+            text: text.clone(),
+            due,
+        };
+        reminders::update(&config.reminders.path, |store| store.reminders.push(reminder))
+            .map_err(ExecutionError::Reminders)?;
+        Ok(ExecutionResult::Answer(format!("OK, I'll remind you {}: {}", when, text)))
+    }
+}
+
+struct NoteHandler;
+
+impl IntentHandler for NoteHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::Note { text, .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        if config.policy.dry_run {
+            return Ok(ExecutionResult::DryRun(format!("Would save the note: {}", text)));
+        }
+        let note = Note {
+            text: text.clone(),
+            created: clock::timestamp(),
+        };
+        reminders::update(&config.reminders.path, |store| store.notes.push(note))
+            .map_err(ExecutionError::Reminders)?;
+        Ok(ExecutionResult::Answer("Noted".to_string()))
+    }
+}
+
+struct ListHandler;
+
+impl IntentHandler for ListHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::List { target, .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        let store = reminders::load(&config.reminders.path).map_err(ExecutionError::Reminders)?;
+        let text = match target.as_str() {
+            "reminders" => reminders::spoken_reminders(&store.reminders, clock::timestamp()),
+            _ => reminders::spoken_notes(&store.notes),
+        };
+        Ok(ExecutionResult::Answer(text))
+    }
+}
+
+struct ScreenshotHandler;
+
+impl IntentHandler for ScreenshotHandler {
+    fn handle(&self, _intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let dir = &config.screenshot.dir;
+        if config.policy.dry_run {
+            return Ok(ExecutionResult::DryRun(format!(
+                "Would save a screenshot in {}",
+                dir.display()
+            )));
+        }
+        let path = screen::save(dir).map_err(ExecutionError::Screen)?;
+        Ok(ExecutionResult::Answer(format!(
+            "Screenshot saved as {}",
+            spoken_name(&path)
+        )))
+    }
+}
+
+struct DescribeScreenHandler;
+
+impl IntentHandler for DescribeScreenHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::DescribeScreen { .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        if config.screenshot.vision_model.is_none() {
+            return Ok(ExecutionResult::Answer(
+                "No vision model is set up. Add screenshot.vision_model to the config.".to_string(),
+            ));
+        }
+        if config.policy.dry_run {
+            return Ok(ExecutionResult::DryRun(
+                "Would send a screenshot to the vision model".to_string(),
+            ));
+        }
+        let description = screen::capture().map_err(|err| err.to_string()).and_then(|png| {
+            block_on(IntentClient::new(config).describe_image(&png, config))
+                .map_err(|err| err.to_string())
+        });
+        let text = description.unwrap_or_else(|err| {
+            eprintln!("Describing the screen failed: {}", err);
+            "I couldn't see the screen.".to_string()
+        });
+        Ok(ExecutionResult::Answer(text))
+    }
+}
+
+/// Answers with the text, so a long selection is read a part at a time
+/// like any long answer, with "want me to continue?" in between.
+struct ReadTextHandler;
+
+impl IntentHandler for ReadTextHandler {
+    fn handle(
+        &self,
+        _intent: &Intent,
+        _config: &Config,
+    ) -> Result<ExecutionResult, ExecutionError> {
+        let text = ui_automation::focused_text().map_err(ExecutionError::Ui)?;
+        if text.is_empty() {
+            return Ok(ExecutionResult::Answer("There's nothing to read here".to_string()));
+        }
+        Ok(ExecutionResult::Answer(text))
+    }
+}
+
+struct CloseAppHandler;
+
+impl IntentHandler for CloseAppHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::CloseApp { target, .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        let processes = process::find(target, config).map_err(ExecutionError::Process)?;
+        let name = target.replace('_', " ");
+        if processes.is_empty() {
+            return Ok(ExecutionResult::Answer(format!("{} isn't running", name)));
+        }
+        if config.policy.dry_run {
+            return Ok(ExecutionResult::DryRun(format!("Would close {}", name)));
+        }
+        if process::close(&processes, CLOSE_WAIT).is_empty() {
+            Ok(ExecutionResult::Action(format!("Closed {}", name)))
+        } else {
+            Ok(ExecutionResult::Answer(format!(
+                "{} is still open. It may be asking to save.",
+                name
+            )))
+        }
+    }
+}
+
+struct KillAppHandler;
+
+impl IntentHandler for KillAppHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::KillApp { target, .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        let (processes, name) = if target == "frozen" {
+            let processes = process::frozen().map_err(ExecutionError::Process)?;
+            let name = program_names(&processes);
+            (processes, name)
+        } else {
+            let processes = process::find_exact(target, config).map_err(ExecutionError::Process)?;
+            (processes, target.replace('_', " "))
+        };
+        if processes.is_empty() {
+            let text = if target == "frozen" {
+                "Nothing has stopped responding".to_string()
+            } else {
+                format!("{} isn't running", name)
+            };
+            return Ok(ExecutionResult::Answer(text));
+        }
+        if config.policy.dry_run {
+            return Ok(ExecutionResult::DryRun(format!("Would end {}", name)));
+        }
+        // A program that still responds gets the chance to exit cleanly.
+        let running = process::close(&processes, KILL_GRACE);
+        process::kill(&running).map_err(ExecutionError::Process)?;
+        Ok(ExecutionResult::Action(format!("Ended {}", name)))
+    }
+}
+
+struct AppRunningHandler;
+
+impl IntentHandler for AppRunningHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::AppRunning { target, .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        let processes = process::find(target, config).map_err(ExecutionError::Process)?;
+        let name = target.replace('_', " ");
+        let text = if processes.is_empty() {
+            format!("No, {} isn't running", name)
+        } else {
+            format!("Yes, {} is running", name)
+        };
+        Ok(ExecutionResult::Answer(text))
+    }
+}
+
+struct MediaHandler;
+
+impl IntentHandler for MediaHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::Media { target, query, .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        let result = match target.as_str() {
+            "now_playing" => media::run(target, None, config).map(ExecutionResult::Answer),
+            _ if config.policy.dry_run => {
+                let what = media::describe(target, query.as_deref());
+                return Ok(ExecutionResult::DryRun(format!("Would {}", what)));
+            }
+            "play" if query.is_some() => {
+                media::run(target, query.as_deref(), config).map(ExecutionResult::Answer)
+            }
+            _ => media::run(target, query.as_deref(), config).map(ExecutionResult::Action),
+        };
+        match result {
+            Err(MediaError::NeedsSpotify) => Ok(ExecutionResult::Answer(
+                "That needs Spotify. Add [media.spotify] to the config.".to_string(),
+            )),
+            Err(MediaError::NoPlayer) => {
+                Ok(ExecutionResult::Answer("Nothing is playing".to_string()))
+            }
+            Err(MediaError::NoDevice) => Ok(ExecutionResult::Answer(
+                "Open Spotify on one of your devices first".to_string(),
+            )),
+            Err(MediaError::NotFound(query)) => Ok(ExecutionResult::Answer(format!(
+                "I couldn't find {} on Spotify",
+                query
+            ))),
+            result => result.map_err(ExecutionError::Media),
+        }
+    }
+}
+
+struct SmartHomeHandler;
+
+impl IntentHandler for SmartHomeHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::SmartHome {
+            target, command, ..
+        } = intent
+        else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        let result = match command.as_str() {
+            "state" => smart_home::run(target, command, config).map(ExecutionResult::Answer),
+            _ if config.policy.dry_run => {
+                let what = smart_home::describe(target, command);
+                return Ok(ExecutionResult::DryRun(format!("Would {}", what)));
+            }
+            _ => smart_home::run(target, command, config).map(ExecutionResult::Action),
+        };
+        match result {
+            Err(SmartHomeError::NoState(name)) => Ok(ExecutionResult::Answer(format!(
+                "I don't know the state of {}",
+                name
+            ))),
+            Err(SmartHomeError::TimedOut) => Ok(ExecutionResult::Answer(
+                "The smart home didn't answer".to_string(),
+            )),
+            result => result.map_err(ExecutionError::SmartHome),
+        }
+    }
+}
+
+struct WslHandler;
+
+impl IntentHandler for WslHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::Wsl { target, text, .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        let command = config
+            .wsl
+            .commands
+            .get(target)
+            .ok_or_else(|| ExecutionError::MissingMapping(target.to_string()))?;
+        let name = target.replace('_', " ");
+        if command.command.contains("{text}") && text.trim().is_empty() {
+            return Ok(ExecutionResult::Answer(format!("I didn't catch what to {}", name)));
+        }
+        // Filled in after splitting, as for custom actions.
+        let args: Vec<String> = split_args(&command.command)
+            .into_iter()
+            .map(|arg| arg.replace("{text}", text.trim()))
+            .collect();
+        if args.is_empty() {
+            return Err(ExecutionError::InvalidTemplate(command.command.clone()));
+        }
+        let args = wsl::arguments(config, command, args);
+        if config.policy.dry_run {
+            let line: Vec<String> = args.into_iter().map(quote).collect();
+            return Ok(ExecutionResult::DryRun(format!("Would run: wsl.exe {}", line.join(" "))));
+        }
+        let timeout = Duration::from_secs(config.wsl.timeout_secs);
+        let output = wsl::run(&args, timeout).map_err(ExecutionError::Wsl)?;
+        Ok(match command.output {
+            WslOutput::Show if !output.is_empty() => {
+                ExecutionResult::Action(format!("Ran {}:\n{}", target, output))
+            }
+            WslOutput::Speak if !output.is_empty() => {
+                ExecutionResult::Answer(answer::last_lines(&output, config.wsl.spoken_lines))
+            }
+            WslOutput::Speak => ExecutionResult::Answer(format!("{} printed nothing", name)),
+            WslOutput::Show | WslOutput::Ignore => {
+                ExecutionResult::Action(format!("Ran {}", target))
+            }
+        })
+    }
+}
+
+struct DevHandler;
+
+impl IntentHandler for DevHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::Dev { target, repo, .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        let (name, path) = match dev::repo(repo.as_deref(), config) {
+            Ok(found) => found,
+            Err(DevError::NoRepo(names)) => {
+                let names = match names.split_last() {
+                    Some((last, rest)) if !rest.is_empty() => {
+                        format!("{} or {}", rest.join(", "), last)
+                    }
+                    _ => names.concat(),
+                };
+                return Ok(ExecutionResult::Answer(format!("Which repo? {}?", names)));
+            }
+            Err(err) => return Err(ExecutionError::Dev(err)),
+        };
+        // Branch, status and the last commit only read the repo.
+        if config.policy.dry_run && matches!(target.as_str(), "pull" | "failing_build") {
+            let what = dev::describe(target, Some(name));
+            return Ok(ExecutionResult::DryRun(format!("Would {}", what)));
+        }
+        if target != "failing_build" {
+            return dev::run(target, name, path, config)
+                .map(ExecutionResult::Answer)
+                .map_err(ExecutionError::Dev);
+        }
+        let url = match dev::failing_build(path, config) {
+            Ok(url) => url,
+            Err(DevError::NoFailingBuild) => {
+                return Ok(ExecutionResult::Answer(format!(
+                    "{} has no failed builds",
+                    name.replace('_', " ")
+                )));
+            }
+            Err(err) => return Err(ExecutionError::Dev(err)),
+        };
+        windows_api::open_url(&url).map_err(ExecutionError::Windows)?;
+        Ok(ExecutionResult::Action(format!("Opened the last failing build of {}", name)))
+    }
+}
+
+struct ShellHandler;
+
+impl IntentHandler for ShellHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::Shell {
+            request, command, ..
+        } = intent
+        else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        if !config.shell.enabled {
+            return Err(ExecutionError::Denied("shell".to_string()));
+        }
+        // A dry run doesn't have the command written.
+        if let (None, true) = (command, config.policy.dry_run) {
+            return Ok(ExecutionResult::DryRun(format!(
+                "Would write and run a command to {}",
+                request
+            )));
+        }
+        let Some(command) = command else {
+            return Ok(ExecutionResult::Answer(
+                "I couldn't work out a command for that".to_string(),
+            ));
+        };
+        if let Some(reason) = shell::refused(command, &config.shell) {
+            return Ok(ExecutionResult::Answer(reason));
+        }
+        if config.policy.dry_run {
+            return Ok(ExecutionResult::DryRun(format!("Would run: {}", command)));
+        }
+        let ran = shell::run(command, config).map_err(ExecutionError::Shell)?;
+        Ok(ExecutionResult::Ran {
+            summary: shell::summarize(command, &ran, config),
+            status: ran.status,
+        })
+    }
+}
+
+struct FilesHandler;
+
+impl IntentHandler for FilesHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::Files { target, name, .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        if config.policy.dry_run {
+            let what = files::describe(target, name.as_deref());
+            return Ok(ExecutionResult::DryRun(format!("Would {}", what)));
+        }
+        match files::run(target, name.as_deref(), config) {
+            Ok(text) => Ok(ExecutionResult::Answer(text)),
+            Err(FilesError::BadName(name)) => Ok(ExecutionResult::Answer(format!(
+                "{} can't be a folder name",
+                name
+            ))),
+            Err(err) => Err(ExecutionError::Files(err)),
+        }
+    }
+}
+
+struct CustomHandler;
+
+impl IntentHandler for CustomHandler {
+    fn handle(&self, intent: &Intent, config: &Config) -> Result<ExecutionResult, ExecutionError> {
+        let Intent::Custom { target, text, .. } = intent else {
+            return Err(ExecutionError::UnknownIntent);
+        };
+        let action = config
+            .action(target, process::focused_context(config))
+            .ok_or_else(|| ExecutionError::MissingMapping(target.to_string()))?;
+        let action = match action {
+            CustomAction::Command(action) => action,
+            CustomAction::Keys(keys) => return press_keys(target, keys, config),
+        };
+        let name = target.replace('_', " ");
+        if action.command.contains("{text}") && text.trim().is_empty() {
+            return Ok(ExecutionResult::Answer(format!("I didn't catch what to {}", name)));
+        }
+        // Substituted after splitting, so the text stays one argument however
+        // many words or quotes it has.
+        let mut args = split_args(&action.command)
+            .into_iter()
+            .map(|arg| arg.replace("{text}", text.trim()));
+        let program = args
+            .next()
+            .ok_or_else(|| ExecutionError::InvalidTemplate(action.command.clone()))?;
+        let args: Vec<String> = args.collect();
+        if config.policy.dry_run {
+            let line: Vec<String> = std::iter::once(program).chain(args).map(quote).collect();
+            return Ok(ExecutionResult::DryRun(format!("Would run: {}", line.join(" "))));
+        }
+        windows_api::run_command(&program, &args).map_err(ExecutionError::Windows)?;
+        Ok(ExecutionResult::Action(format!("Ran {}", target)))
+    }
+}
+
+/// Runs a `[sendkeys]` custom action.
+fn press_keys(
+    target: &str,
+    keys: &str,
+    config: &Config,
+) -> Result<ExecutionResult, ExecutionError> {
+    let combos = hotkey::parse_sequence(keys)
+        .map_err(|reason| ExecutionError::InvalidKeys(keys.to_string(), reason))?;
+    if config.policy.dry_run {
+        return Ok(ExecutionResult::DryRun(format!("Would press {}", keys)));
+    }
+    windows_api::send_keys(&combos).map_err(ExecutionError::Windows)?;
+    Ok(ExecutionResult::Action(format!("Pressed {} for {}", keys, target)))
+}
+
+/// "notepad and excel" for the programs behind `processes`.
+fn program_names(processes: &[Process]) -> String {
+    let mut names: Vec<String> = processes
+        .iter()
+        .map(|process| spoken_name(Path::new(&process.name)))
+        .collect();
+    names.sort();
+    names.dedup();
+    match names.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        _ => names.concat(),
+    }
+}
+
+/// Opens `path` with the entry's open template when one applies, otherwise
+/// with its default application.
+fn open_file(
+    config: &Config,
+    entry: &FileEntry,
+    path: &Path,
+    at: Option<FileLocation>,
+) -> Result<(), ExecutionError> {
+    match open_command(config, entry, path, at)? {
+        Some((program, args)) => {
+            windows_api::launch_with_args(&program, &args).map_err(ExecutionError::Windows)
+        }
+        None => windows_api::open_path(path).map_err(ExecutionError::Windows),
+    }
+}
+
+/// What `open_file` would run, without running it.
+fn dry_run_open(
+    config: &Config,
+    entry: &FileEntry,
+    path: &Path,
+    at: Option<FileLocation>,
+) -> Result<ExecutionResult, ExecutionError> {
+    let message = match open_command(config, entry, path, at)? {
+        Some((program, args)) => {
+            let line: Vec<String> = std::iter::once(program).chain(args).map(quote).collect();
+            format!("Would open {} with: {}", path.display(), line.join(" "))
+        }
+        None => format!("Would open {} with its default application", path.display()),
+    };
+    Ok(ExecutionResult::DryRun(message))
+}
+
+/// The program and arguments from the entry's open template, or `None` when
+/// the file opens with its default application.
+fn open_command(
+    config: &Config,
+    entry: &FileEntry,
+    path: &Path,
+    at: Option<FileLocation>,
+) -> Result<Option<(String, Vec<String>)>, ExecutionError> {
+    let Some(template) = config.open_template(entry, path) else {
+        return Ok(None);
+    };
+    let mut args = render_template(template, path, at).into_iter();
+    let program = args
+        .next()
+        .ok_or_else(|| ExecutionError::InvalidTemplate(template.to_string()))?;
+    Ok(Some((program, args.collect())))
+}
+
+/// Quotes an argument containing spaces so a printed command line reads back
+/// the way the template would split it.
+fn quote(arg: String) -> String {
+    if arg.contains(char::is_whitespace) {
+        format!("\"{}\"", arg)
+    } else {
+        arg
+    }
+}
+
+/// Splits an open template into program and arguments (double quotes group
+/// words) and fills in `{path}`, `{line}` and `{page}`. A missing line or
+/// page becomes 1 so templates like `{path}:{line}` always stay valid.
+fn render_template(template: &str, path: &Path, at: Option<FileLocation>) -> Vec<String> {
+    let line = match at {
+        Some(FileLocation::Line(line)) => line,
+        _ => 1,
+    };
+    let page = match at {
+        Some(FileLocation::Page(page)) => page,
+        _ => 1,
+    };
+    let path = path.display().to_string();
+    split_args(template)
+        .into_iter()
+        .map(|arg| {
+            arg.replace("{path}", &path)
+                .replace("{line}", &line.to_string())
+                .replace("{page}", &page.to_string())
+        })
+        .collect()
+}
+
+fn split_args(template: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut started = false;
+    for c in template.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                started = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if started {
+                    args.push(std::mem::take(&mut current));
+                    started = false;
+                }
+            }
+            c => {
+                current.push(c);
+                started = true;
+            }
+        }
+    }
+    if started {
+        args.push(current);
+    }
+    args
+}
+
+/// "budget_july-2023.xlsx" reads better aloud as "budget july 2023".
+fn spoken_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().replace(['_', '-', '.'], " "))
+        .unwrap_or_default()
+}
+
+fn describe_system_action(action: SystemAction) -> String {
+    match action {
+        SystemAction::VolumeMute => "mute the volume".to_string(),
+        SystemAction::VolumeAdjust(change) if change < 0 => {
+            format!("turn the volume down by {} points", -change)
+        }
+        SystemAction::VolumeAdjust(change) => format!("turn the volume up by {} points", change),
+        SystemAction::VolumeSet(level) => format!("set the volume to {}%", level),
+        SystemAction::VolumeQuery => "read the volume".to_string(),
+        SystemAction::MicMute => "mute the microphone".to_string(),
+        SystemAction::MicUnmute => "unmute the microphone".to_string(),
+        SystemAction::MicQuery => "check the microphone".to_string(),
+        SystemAction::ScreenOff => "turn off the screen".to_string(),
+        SystemAction::DisplayTopology(Topology::Extend) => "extend the displays".to_string(),
+        SystemAction::DisplayTopology(Topology::Duplicate) => {
+            "duplicate the displays".to_string()
+        }
+        SystemAction::DisplayTopology(Topology::Internal) => {
+            "show only the PC screen".to_string()
+        }
+        SystemAction::DisplayTopology(Topology::External) => {
+            "show only the second screen".to_string()
+        }
+        SystemAction::RotateScreen(Some(degrees)) => {
+            format!("rotate the screen to {} degrees", degrees)
+        }
+        SystemAction::RotateScreen(None) => "rotate the screen a quarter turn".to_string(),
+        SystemAction::Radio(kind, true) => format!("turn on {}", kind),
+        SystemAction::Radio(kind, false) => format!("turn off {}", kind),
+        SystemAction::RadioQuery(kind) => format!("check {}", kind),
+        SystemAction::AirplaneMode(true) => "turn every radio off".to_string(),
+        SystemAction::AirplaneMode(false) => "turn every radio back on".to_string(),
+        SystemAction::PowerPlan(plan) => format!("switch to the {} power plan", plan),
+        SystemAction::BatteryQuery => "read the battery".to_string(),
+        SystemAction::Sleep => "put the computer to sleep".to_string(),
+        SystemAction::Shutdown => "shut down the computer".to_string(),
+        SystemAction::Restart => "restart the computer".to_string(),
+        SystemAction::Lock => "lock the workstation".to_string(),
+    }
+}
+
+/// "The battery is at 64%, about 2 hours 5 minutes left".
+fn describe_battery(status: BatteryStatus) -> String {
+    let mut text = match status.percent {
+        Some(percent) => format!("The battery is at {}%", percent),
+        None => "The battery level is unknown".to_string(),
+    };
+    if status.plugged_in {
+        text.push_str(", plugged in");
+    } else if let Some(remaining) = status.remaining {
+        let minutes = remaining.as_secs() / 60;
+        let (hours, minutes) = (minutes / 60, minutes % 60);
+        let plural = |n: u64| if n == 1 { "" } else { "s" };
+        let left = match (hours, minutes) {
+            (0, minutes) => format!("{} minute{}", minutes, plural(minutes)),
+            (hours, 0) => format!("{} hour{}", hours, plural(hours)),
+            (hours, minutes) => format!(
+                "{} hour{} {} minute{}",
+                hours,
+                plural(hours),
+                minutes,
+                plural(minutes)
+            ),
+        };
+        text.push_str(&format!(", about {} left", left));
+    }
+    text
+}
+
+/// `step` is how far a bare `volume_up` or `volume_down` moves the volume.
+fn parse_system_action(target: &str, step: u8) -> Result<SystemAction, ExecutionError> {
+    let amount = |name: &str| {
+        let amount = target.strip_prefix(name)?;
+        if amount.is_empty() {
+            return Some(step.min(100) as i8);
+        }
+        amount.strip_prefix('_')?.parse::<u8>().ok().map(|amount| amount.min(100) as i8)
+    };
+    if let Some(amount) = amount("volume_up") {
+        return Ok(SystemAction::VolumeAdjust(amount));
+    }
+    if let Some(amount) = amount("volume_down") {
+        return Ok(SystemAction::VolumeAdjust(-amount));
+    }
+    match target {
+        "volume_mute" => Ok(SystemAction::VolumeMute),
+        "volume_query" => Ok(SystemAction::VolumeQuery),
+        "mic_mute" => Ok(SystemAction::MicMute),
+        "mic_unmute" => Ok(SystemAction::MicUnmute),
+        "mic_state" => Ok(SystemAction::MicQuery),
+        "screen_off" => Ok(SystemAction::ScreenOff),
+        "display_extend" => Ok(SystemAction::DisplayTopology(Topology::Extend)),
+        "display_duplicate" => Ok(SystemAction::DisplayTopology(Topology::Duplicate)),
+        "display_internal" => Ok(SystemAction::DisplayTopology(Topology::Internal)),
+        "display_external" => Ok(SystemAction::DisplayTopology(Topology::External)),
+        "rotate_screen" => Ok(SystemAction::RotateScreen(None)),
+        "bluetooth_on" => Ok(SystemAction::Radio(RadioKind::Bluetooth, true)),
+        "bluetooth_off" => Ok(SystemAction::Radio(RadioKind::Bluetooth, false)),
+        "bluetooth_state" => Ok(SystemAction::RadioQuery(RadioKind::Bluetooth)),
+        "wifi_on" => Ok(SystemAction::Radio(RadioKind::WiFi, true)),
+        "wifi_off" => Ok(SystemAction::Radio(RadioKind::WiFi, false)),
+        "wifi_state" => Ok(SystemAction::RadioQuery(RadioKind::WiFi)),
+        "airplane_mode_on" => Ok(SystemAction::AirplaneMode(true)),
+        "airplane_mode_off" => Ok(SystemAction::AirplaneMode(false)),
+        "power_plan_balanced" => Ok(SystemAction::PowerPlan(PowerPlan::Balanced)),
+        "power_plan_high_performance" => Ok(SystemAction::PowerPlan(PowerPlan::HighPerformance)),
+        "power_plan_power_saver" => Ok(SystemAction::PowerPlan(PowerPlan::PowerSaver)),
+        "battery_status" => Ok(SystemAction::BatteryQuery),
+        "sleep" => Ok(SystemAction::Sleep),
+        "shutdown" => Ok(SystemAction::Shutdown),
+        "restart" => Ok(SystemAction::Restart),
+        "lock" => Ok(SystemAction::Lock),
+        action if action.starts_with("volume_set") => {
+            let digits: String = action.chars().filter(|c| c.is_ascii_digit()).collect();
+            let level = digits.parse::<u8>().unwrap_or(50);
+            Ok(SystemAction::VolumeSet(level))
+        }
+        action if action.starts_with("rotate_screen_") => {
+            match action["rotate_screen_".len()..].parse::<u16>() {
+                Ok(degrees @ (0 | 90 | 180 | 270)) => Ok(SystemAction::RotateScreen(Some(degrees))),
+                _ => Err(ExecutionError::UnsupportedSystemAction(action.to_string())),
+            }
+        }
+        other => Err(ExecutionError::UnsupportedSystemAction(other.to_string())),
+    }
+}
+
+#[derive(Debug)]
+pub enum ExecutionError {
+    MissingMapping(String),
+    Windows(WindowsActionError),
+    UnknownIntent,
+    UnsupportedSystemAction(String),
+    NoMatch(String),
+    InvalidTemplate(String),
+    /// A `[sendkeys]` entry that doesn't parse, and why.
+    InvalidKeys(String, String),
+    /// Blocked by this `policy.deny` entry.
+    Denied(String),
+    /// The `pre_execute` hook didn't let it run.
+    Hook(HookError),
+    Calendar(CalendarError),
+    Reminders(ReminderError),
+    Screen(ScreenError),
+    Process(ProcessError),
+    Media(MediaError),
+    SmartHome(SmartHomeError),
+    Wsl(WslError),
+    Dev(DevError),
+    Shell(ShellError),
+    Files(FilesError),
+    Ui(UiError),
+    Io(std::io::Error),
+}
+
+#[derive(Debug)]
+pub enum ExecutionResult {
+    Action(String),
+    Answer(String),
+    /// What a shell command did, summed up to be read out like an answer,
+    /// and its exit code (`None` when it was stopped without one).
+    Ran { summary: String, status: Option<i32> },
+    /// Read out with a voice for `language` when there is one.
+    Translation { text: String, language: String },
+    /// What an action would have done under `policy.dry_run`.
+    DryRun(String),
+    /// Several targets fit equally well; the user has to pick one.
+    Choose(Vec<Choice>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Choice {
+    pub label: String,
+    pub intent: Intent,
+}
+
+impl std::fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingMapping(key) => write!(f, "no mapping for key '{}'", key),
+            Self::Windows(err) => write!(f, "windows action failed: {}", err),
+            Self::UnknownIntent => write!(f, "intent classified as unknown"),
+            Self::UnsupportedSystemAction(action) => {
+                write!(f, "unsupported system action '{}'", action)
+            }
+            Self::NoMatch(query) => write!(f, "no file matching '{}'", query),
+            Self::InvalidTemplate(template) => write!(f, "invalid command template '{}'", template),
+            Self::InvalidKeys(keys, reason) => write!(f, "invalid keys '{}': {}", keys, reason),
+            Self::Denied(name) => write!(f, "'{}' is denied by policy", name),
+            Self::Hook(err) => write!(f, "{}", err),
+            Self::Calendar(err) => write!(f, "calendar error: {}", err),
+            Self::Reminders(err) => write!(f, "reminders error: {}", err),
+            Self::Screen(err) => write!(f, "screenshot error: {}", err),
+            Self::Process(err) => write!(f, "process error: {}", err),
+            Self::Media(err) => write!(f, "media error: {}", err),
+            Self::SmartHome(err) => write!(f, "smart home error: {}", err),
+            Self::Wsl(err) => write!(f, "WSL error: {}", err),
+            Self::Dev(err) => write!(f, "git error: {}", err),
+            Self::Shell(err) => write!(f, "shell error: {}", err),
+            Self::Files(err) => write!(f, "file action failed: {}", err),
+            Self::Ui(err) => write!(f, "couldn't read the window: {}", err),
+            Self::Io(err) => write!(f, "io error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ExecutionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Windows(err) => Some(err),
+            Self::Hook(err) => Some(err),
+            Self::Calendar(err) => Some(err),
+            Self::Reminders(err) => Some(err),
+            Self::Screen(err) => Some(err),
+            Self::Process(err) => Some(err),
+            Self::Media(err) => Some(err),
+            Self::SmartHome(err) => Some(err),
+            Self::Wsl(err) => Some(err),
+            Self::Dev(err) => Some(err),
+            Self::Shell(err) => Some(err),
+            Self::Files(err) => Some(err),
+            Self::Ui(err) => Some(err),
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
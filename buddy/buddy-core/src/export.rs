@@ -0,0 +1,103 @@
+//! "Save this conversation": writes the session's commands, with what Buddy
+//! did or answered, to a Markdown or JSON file in `export.dir`.
+
+use crate::{
+    clock,
+    config::{ExportConfig, ExportFormat},
+    intent::Intent,
+    pipeline::describe_intent,
+    session::HistoryEntry,
+};
+use std::{
+    fs, io,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Writes `entries` (oldest first) to a new file named after the current
+/// time and returns its path. Saving again within the same minute replaces
+/// the file, which by then holds the same conversation and more.
+pub fn save(
+    config: &ExportConfig,
+    entries: &[&HistoryEntry],
+    format: ExportFormat,
+) -> io::Result<PathBuf> {
+    fs::create_dir_all(&config.dir)?;
+    let (date, secs) = clock::now();
+    let name = format!(
+        "conversation-{:04}-{:02}-{:02}-{:02}{:02}.{}",
+        date.year,
+        date.month,
+        date.day,
+        secs / 3600,
+        secs / 60 % 60,
+        format.extension()
+    );
+    let text = match format {
+        ExportFormat::Markdown => markdown(entries),
+        ExportFormat::Json => json(entries),
+    };
+    let path = config.dir.join(name);
+    fs::write(&path, text)?;
+    Ok(path)
+}
+
+fn markdown(entries: &[&HistoryEntry]) -> String {
+    let mut text = String::from("# Conversation\n");
+    let mut day = None;
+    for entry in entries {
+        let (date, secs) = clock::local_from_utc(unix_secs(entry.at));
+        if day != Some(date) {
+            text.push_str(&format!(
+                "\n## {:04}-{:02}-{:02}\n",
+                date.year, date.month, date.day
+            ));
+            day = Some(date);
+        }
+        text.push_str(&format!(
+            "\n**{:02}:{:02}** You: {}\n\n",
+            secs / 3600,
+            secs / 60 % 60,
+            entry.transcript.trim()
+        ));
+        let reply = match (&entry.answer, &entry.intent) {
+            (Some(answer), _) => answer.clone(),
+            (None, Intent::Unknown { .. }) => "Didn't know how to do that".to_string(),
+            (None, intent) => {
+                let outcome = match entry.succeeded {
+                    Some(true) => "",
+                    Some(false) => " (failed)",
+                    None => " (not run)",
+                };
+                format!("_{}_{}", describe_intent(intent), outcome)
+            }
+        };
+        text.push_str(&format!("Buddy: {}\n", reply));
+    }
+    text
+}
+
+/// The same fields as the `history` control command, plus the reply.
+fn json(entries: &[&HistoryEntry]) -> String {
+    let entries: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            serde_json::json!({
+                "timestamp": unix_secs(entry.at),
+                "transcript": entry.transcript,
+                "confidence": entry.confidence,
+                "intent": entry.intent.to_json(),
+                "answer": entry.answer,
+                "succeeded": entry.succeeded,
+            })
+        })
+        .collect();
+    // Plain values always serialize.
+    serde_json::to_string_pretty(&entries).unwrap_or_default()
+}
+
+fn unix_secs(at: SystemTime) -> i64 {
+    at.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,610 @@
+use crate::{
+    answer,
+    config::{FeedbackConfig, FeedbackMode, PhrasesConfig},
+    notifications,
+    phrases::Phrases,
+    transcription,
+    voice::{Locale, Utterance, Voice},
+};
+use rodio::{buffer::SamplesBuffer, Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Cursor},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+use tokio::sync::oneshot;
+
+/// How often the playback thread checks whether a sound or utterance is done.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Plays sounds and speech on a background thread so callers never wait for
+/// audio to finish. Requests are queued in order; `stop` drops everything
+/// still queued and cuts off what is playing. Dropping the player leaves the
+/// thread to finish what is queued on its own, so a last "Ok" isn't cut off
+/// when Buddy reloads its config.
+pub struct FeedbackPlayer {
+    mode: FeedbackMode,
+    greeting: Option<String>,
+    goodbye: Option<String>,
+    /// Longest answer read out before asking to continue; 0 for no limit.
+    max_spoken_chars: usize,
+    streamed: StreamedAnswer,
+    /// The part of the last answer that was held back.
+    unspoken: Option<String>,
+    /// `feedback.voices`.
+    voices: HashMap<String, String>,
+    /// `feedback.toast`, off where toasts aren't supported.
+    toast: bool,
+    phrases: Phrases,
+    /// Bumped by `stop`; jobs queued under an older value are skipped.
+    generation: Arc<AtomicU64>,
+    /// Set by the worker while a sound or speech is playing.
+    playing: Arc<AtomicBool>,
+    /// Raised while the user talks over Buddy; see `ducking_flag`.
+    ducking: Arc<AtomicBool>,
+    jobs: Option<Sender<(u64, Job)>>,
+}
+
+/// How much of an answer streamed sentence by sentence has been read out.
+#[derive(Default)]
+struct StreamedAnswer {
+    chars: usize,
+    sentences: usize,
+    cut: bool,
+}
+
+/// The short cues configured under `[feedback]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Earcon {
+    Success,
+    Error,
+    Greeting,
+    Listen,
+    ListenStop,
+    Thinking,
+}
+
+enum Job {
+    Sound(Earcon),
+    Speak {
+        text: String,
+        interrupt: bool,
+        locale: Option<Locale>,
+    },
+    Stop,
+    /// Answered once everything queued before it has finished playing.
+    Idle(oneshot::Sender<()>),
+}
+
+impl FeedbackPlayer {
+    pub fn new(cfg: &FeedbackConfig, phrases: &PhrasesConfig) -> Self {
+        let generation = Arc::new(AtomicU64::new(0));
+        let playing = Arc::new(AtomicBool::new(false));
+        let ducking = Arc::new(AtomicBool::new(false));
+        let (jobs, queue) = mpsc::channel();
+        let worker_cfg = cfg.clone();
+        let worker_generation = Arc::clone(&generation);
+        let worker_playing = Arc::clone(&playing);
+        let worker_ducking = Arc::clone(&ducking);
+        let started = thread::Builder::new()
+            .name("feedback".into())
+            .spawn(move || {
+                Worker::new(&worker_cfg, worker_generation, worker_playing, worker_ducking)
+                    .run(queue)
+            })
+            .map_err(|err| eprintln!("failed to start feedback thread: {}", err))
+            .is_ok();
+        if cfg.toast && !cfg!(windows) {
+            eprintln!("feedback.toast needs Windows; answers won't be shown as toasts");
+        }
+        Self {
+            mode: cfg.mode.clone(),
+            greeting: cfg
+                .greeting
+                .clone()
+                .filter(|text| !text.trim().is_empty()),
+            goodbye: cfg.goodbye.clone().filter(|text| !text.trim().is_empty()),
+            max_spoken_chars: cfg.max_spoken_chars,
+            streamed: StreamedAnswer::default(),
+            unspoken: None,
+            voices: cfg.voices.clone(),
+            toast: cfg.toast && cfg!(windows),
+            phrases: Phrases::new(phrases),
+            generation,
+            playing,
+            ducking,
+            jobs: started.then_some(jobs),
+        }
+    }
+
+    /// The `[phrases]` text called `key`; see `Phrases::get`.
+    pub fn phrase(&self, key: &str, args: &[(&str, &str)]) -> String {
+        self.phrases.get(key, args)
+    }
+
+    /// Switches phrases to the language Whisper was pinned to, or back to
+    /// `phrases.locale` for `None`.
+    pub fn set_language(&mut self, code: Option<&str>) {
+        self.phrases.set_language(code);
+    }
+
+    /// Confirms a finished action with the first of `keys` that has a
+    /// configured phrase, or a plain "Ok".
+    pub fn success(&mut self, keys: &[&str], args: &[(&str, &str)]) {
+        let message = self
+            .phrases
+            .find(keys, args)
+            .unwrap_or_else(|| self.phrases.get("ok", &[]));
+        match self.mode {
+            FeedbackMode::Sound => self.play(Earcon::Success),
+            FeedbackMode::Tts => self.speak(&message, false),
+            FeedbackMode::Both => {
+                self.play(Earcon::Success);
+                self.speak(&message, false);
+            }
+        }
+    }
+
+    /// Announces that the pipeline is up. The greeting sound plays in every
+    /// mode so headless setups get a cue even without TTS.
+    pub fn greet(&mut self) {
+        self.play(Earcon::Greeting);
+        if let Some(text) = self.greeting.clone() {
+            self.say(&text);
+        }
+    }
+
+    /// Says `feedback.goodbye`, if set, and waits for it and anything still
+    /// queued to finish before Buddy exits.
+    pub async fn goodbye(&mut self) {
+        if let Some(text) = self.goodbye.clone() {
+            self.say(&text);
+        }
+        self.wait_idle().await;
+    }
+
+    /// Says a reminder that has come due, with a toast that stays until
+    /// dismissed.
+    pub fn remind(&mut self, text: &str) {
+        self.toast("Reminder", text, true);
+        self.say(&self.phrases.get("reminder", &[("text", text)]));
+    }
+
+    pub fn say(&mut self, message: &str) {
+        match self.mode {
+            FeedbackMode::Sound => {}
+            FeedbackMode::Tts | FeedbackMode::Both => self.speak(message, true),
+        }
+    }
+
+    /// Says `message` in a voice for `language` (a name such as "French" or a
+    /// code), from `feedback.voices` or else the engine's own voice for it.
+    pub fn say_in(&mut self, message: &str, language: &str) {
+        self.toast(language, message, false);
+        if matches!(self.mode, FeedbackMode::Sound) {
+            return;
+        }
+        let code = transcription::language_code(language)
+            .map(str::to_string)
+            .unwrap_or_else(|| language.to_lowercase());
+        let voice = self
+            .voices
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(&code) || key.eq_ignore_ascii_case(language))
+            .map(|(_, voice)| voice.clone());
+        self.send(Job::Speak {
+            text: message.to_string(),
+            interrupt: true,
+            locale: Some(Locale {
+                language: code,
+                voice,
+            }),
+        });
+    }
+
+    /// Queues one sentence of a streamed answer behind any sentence still being
+    /// spoken, so playback keeps pace with generation. Sentences past
+    /// `max_spoken_chars` are held back for `finish_streamed_answer`.
+    pub fn say_sentence(&mut self, sentence: &str) {
+        if matches!(self.mode, FeedbackMode::Sound) || self.streamed.cut {
+            return;
+        }
+        let sentence = answer::for_speech(sentence);
+        if sentence.is_empty() {
+            return;
+        }
+        let len = sentence.chars().count();
+        if self.max_spoken_chars > 0
+            && self.streamed.chars > 0
+            && self.streamed.chars + len > self.max_spoken_chars
+        {
+            self.streamed.cut = true;
+            return;
+        }
+        self.streamed.chars += len;
+        self.streamed.sentences += 1;
+        self.speak(&sentence, false);
+    }
+
+    /// Reads out a model answer, cleaned up for speech. Anything past
+    /// `max_spoken_chars` is kept for `continue_answer`.
+    pub fn answer(&mut self, text: &str) {
+        self.unspoken = None;
+        self.toast("Buddy", &answer::for_speech(text), false);
+        if matches!(self.mode, FeedbackMode::Sound) {
+            return;
+        }
+        let (now, rest) = answer::split_spoken(&answer::for_speech(text), self.max_spoken_chars);
+        self.speak(&now, true);
+        self.hold_back(rest);
+    }
+
+    /// Called with the full text of an answer that was streamed through
+    /// `say_sentence`; offers to read the sentences that were held back.
+    pub fn finish_streamed_answer(&mut self, text: &str) {
+        self.toast("Buddy", &answer::for_speech(text), false);
+        let streamed = std::mem::take(&mut self.streamed);
+        self.unspoken = None;
+        if !streamed.cut {
+            return;
+        }
+        let rest = answer::sentences(&answer::for_speech(text))
+            .into_iter()
+            .skip(streamed.sentences)
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.hold_back((!rest.is_empty()).then_some(rest));
+    }
+
+    /// Takes what was held back from the last answer. Whatever the next
+    /// request is, the offer to continue lapses.
+    pub fn take_unspoken(&mut self) -> Option<String> {
+        self.unspoken.take()
+    }
+
+    fn hold_back(&mut self, rest: Option<String>) {
+        if rest.is_some() {
+            let prompt = self.phrases.get("continue", &[]);
+            self.speak(&prompt, false);
+        }
+        self.unspoken = rest;
+    }
+
+    pub fn say_phrase(&mut self, key: &str) {
+        let message = self.phrases.get(key, &[]);
+        self.say(&message);
+    }
+
+    pub fn error_phrase(&mut self, key: &str) {
+        let message = self.phrases.get(key, &[]);
+        self.error(&message);
+    }
+
+    pub fn error(&mut self, message: &str) {
+        self.toast("Buddy", message, false);
+        match self.mode {
+            FeedbackMode::Sound => self.play(Earcon::Error),
+            FeedbackMode::Tts => self.speak(message, true),
+            FeedbackMode::Both => {
+                self.play(Earcon::Error);
+                self.speak(message, true);
+            }
+        }
+    }
+
+    /// Cuts off whatever is playing and plays the start-listening cue, so
+    /// nothing but the cue is heard while the user speaks. Resolves once the
+    /// cue has finished so the microphone doesn't pick it up.
+    pub async fn listening(&mut self) {
+        self.stop();
+        self.streamed = StreamedAnswer::default();
+        self.play(Earcon::Listen);
+        self.wait_idle().await;
+    }
+
+    pub fn listening_stopped(&mut self) {
+        self.play(Earcon::ListenStop);
+    }
+
+    /// Cue for the wait on the intent model.
+    pub fn thinking(&mut self) {
+        self.play(Earcon::Thinking);
+    }
+
+    /// Cuts off anything being played or spoken and drops whatever is queued.
+    pub fn stop(&mut self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.send(Job::Stop);
+    }
+
+    /// Flag that is up while Buddy is playing a sound or speaking, for
+    /// listening for "stop" only then.
+    pub fn playing_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.playing)
+    }
+
+    /// Flag that lowers feedback to `feedback.duck_volume` while it is up,
+    /// for a listener that hears the user talking over Buddy.
+    pub fn ducking_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.ducking)
+    }
+
+    /// Resolves once everything queued so far has been played, so a follow-up
+    /// recording doesn't pick up Buddy's own voice.
+    pub async fn wait_idle(&mut self) {
+        let (done, idle) = oneshot::channel();
+        self.send(Job::Idle(done));
+        let _ = idle.await;
+    }
+
+    /// Shows `message` as a toast as well when `feedback.toast` is set.
+    fn toast(&self, title: &str, message: &str, sticky: bool) {
+        if !self.toast {
+            return;
+        }
+        if let Err(err) = notifications::show_toast(title, message, sticky) {
+            eprintln!("Toast failed: {}", err);
+        }
+    }
+
+    fn play(&mut self, earcon: Earcon) {
+        self.send(Job::Sound(earcon));
+    }
+
+    fn speak(&mut self, text: &str, interrupt: bool) {
+        self.send(Job::Speak {
+            text: text.to_string(),
+            interrupt,
+            locale: None,
+        });
+    }
+
+    fn send(&self, job: Job) {
+        if let Some(jobs) = &self.jobs {
+            let _ = jobs.send((self.generation.load(Ordering::SeqCst), job));
+        }
+    }
+}
+
+/// Owns the audio output and the TTS engine, both of which have to stay on
+/// the thread that created them.
+struct Worker {
+    generation: Arc<AtomicU64>,
+    playing: Arc<AtomicBool>,
+    ducking: Arc<AtomicBool>,
+    volume: f32,
+    duck_volume: f32,
+    /// Whether the volume last set was the ducked one.
+    ducked: bool,
+    /// Decoded once up front; unset or unreadable sounds are left out.
+    sounds: HashMap<Earcon, Clip>,
+    output: Option<(OutputStream, OutputStreamHandle)>,
+    sink: Option<Sink>,
+    /// `None` in sound-only mode or when the engine failed to start.
+    voice: Option<Voice>,
+}
+
+impl Worker {
+    fn new(
+        cfg: &FeedbackConfig,
+        generation: Arc<AtomicU64>,
+        playing: Arc<AtomicBool>,
+        ducking: Arc<AtomicBool>,
+    ) -> Self {
+        let sounds = [
+            (Earcon::Success, &cfg.success_sound),
+            (Earcon::Error, &cfg.error_sound),
+            (Earcon::Greeting, &cfg.greeting_sound),
+            (Earcon::Listen, &cfg.listen_sound),
+            (Earcon::ListenStop, &cfg.listen_stop_sound),
+            (Earcon::Thinking, &cfg.thinking_sound),
+        ]
+        .into_iter()
+        .filter_map(|(earcon, path)| {
+            let path = path.as_deref()?;
+            match Clip::load(path) {
+                Ok(clip) => clip.map(|clip| (earcon, clip)),
+                Err(err) => {
+                    eprintln!("failed to load sound {}: {}", path.display(), err);
+                    None
+                }
+            }
+        })
+        .collect();
+        Self {
+            generation,
+            playing,
+            ducking,
+            volume: cfg.volume.clamp(0.0, 1.0),
+            duck_volume: cfg.duck_volume.clamp(0.0, 1.0),
+            ducked: false,
+            sounds,
+            output: None,
+            sink: None,
+            voice: match cfg.mode {
+                FeedbackMode::Sound => None,
+                FeedbackMode::Tts | FeedbackMode::Both => Voice::new(cfg)
+                    .map_err(|err| eprintln!("TTS unavailable: {}", err))
+                    .ok(),
+            },
+        }
+    }
+
+    fn run(mut self, queue: Receiver<(u64, Job)>) {
+        loop {
+            let (generation, job) = match queue.recv_timeout(POLL_INTERVAL) {
+                Ok(next) => next,
+                // Keeps `playing` current between jobs.
+                Err(RecvTimeoutError::Timeout) => {
+                    self.busy();
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            if generation != self.generation.load(Ordering::SeqCst) {
+                // Queued before a stop; `Idle` is still answered so no caller hangs.
+                if let Job::Idle(done) = job {
+                    let _ = done.send(());
+                }
+                continue;
+            }
+            match job {
+                Job::Sound(earcon) => {
+                    let Some(clip) = self.sounds.get(&earcon) else {
+                        continue;
+                    };
+                    if let Err(err) = self.append(clip.source()) {
+                        eprintln!("failed to play {:?} sound: {}", earcon, err);
+                    }
+                    // Sounds come before the speech that follows them.
+                    self.wait(generation);
+                }
+                Job::Speak {
+                    text,
+                    interrupt,
+                    locale,
+                } => self.speak(&text, interrupt, locale.as_ref(), generation),
+                Job::Stop => self.silence(),
+                Job::Idle(done) => {
+                    self.wait(generation);
+                    let _ = done.send(());
+                }
+            }
+        }
+        // The player was dropped: finish what is already playing.
+        let generation = self.generation.load(Ordering::SeqCst);
+        self.wait(generation);
+    }
+
+    fn current_volume(&self) -> f32 {
+        if self.ducked {
+            self.volume * self.duck_volume
+        } else {
+            self.volume
+        }
+    }
+
+    /// Lowers the sink and the voice while the ducking flag is up, and puts
+    /// them back once it drops.
+    fn follow_ducking(&mut self) {
+        let ducked = self.ducking.load(Ordering::Relaxed);
+        if ducked == self.ducked {
+            return;
+        }
+        self.ducked = ducked;
+        let volume = self.current_volume();
+        if let Some(sink) = &self.sink {
+            sink.set_volume(volume);
+        }
+        if let Some(voice) = self.voice.as_mut() {
+            voice.set_volume(volume);
+        }
+    }
+
+    fn append<S>(&mut self, source: S) -> Result<(), String>
+    where
+        S: Source + Send + 'static,
+        S::Item: rodio::Sample + Send,
+        f32: rodio::cpal::FromSample<S::Item>,
+    {
+        if self.output.is_none() {
+            self.output = Some(OutputStream::try_default().map_err(|e| e.to_string())?);
+        }
+        if self.sink.is_none() {
+            let (_, handle) = self.output.as_ref().expect("output opened above");
+            let sink = Sink::try_new(handle).map_err(|e| e.to_string())?;
+            sink.set_volume(self.current_volume());
+            self.sink = Some(sink);
+        }
+        self.sink.as_ref().expect("sink created above").append(source);
+        Ok(())
+    }
+
+    fn speak(&mut self, text: &str, interrupt: bool, locale: Option<&Locale>, generation: u64) {
+        if interrupt {
+            // Dropping the sink cuts off a sound that is still playing.
+            self.sink = None;
+        } else if self.voice.as_ref().is_some_and(|voice| !voice.queues()) {
+            self.wait(generation);
+        }
+        let Some(voice) = self.voice.as_mut() else {
+            eprintln!("TTS init failed; no voice output");
+            return;
+        };
+        match voice.speak(text, interrupt, locale) {
+            Ok(Utterance::Started) => {}
+            Ok(Utterance::Clip(wav)) => {
+                let played = Decoder::new(Cursor::new(wav))
+                    .map_err(|e| e.to_string())
+                    .and_then(|source| self.append(source));
+                if let Err(err) = played {
+                    eprintln!("TTS playback failed: {}", err);
+                }
+            }
+            Err(err) => eprintln!("TTS speak failed: {}", err),
+        }
+    }
+
+    fn silence(&mut self) {
+        self.sink = None;
+        if let Some(voice) = self.voice.as_mut() {
+            voice.stop();
+        }
+    }
+
+    /// Waits for playback to finish, giving up (and going quiet) as soon as
+    /// `stop` moves the generation on.
+    fn wait(&mut self, generation: u64) {
+        while self.busy() {
+            if generation != self.generation.load(Ordering::SeqCst) {
+                self.silence();
+                return;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn busy(&mut self) -> bool {
+        self.follow_ducking();
+        let playing = self.sink.as_ref().is_some_and(|sink| !sink.empty());
+        let speaking = self.voice.as_mut().is_some_and(Voice::is_speaking);
+        self.playing.store(playing || speaking, Ordering::Relaxed);
+        playing || speaking
+    }
+}
+
+/// A feedback sound decoded into memory.
+struct Clip {
+    channels: u16,
+    sample_rate: u32,
+    samples: Vec<i16>,
+}
+
+impl Clip {
+    /// `Ok(None)` when the file doesn't exist, so the shipped defaults can
+    /// point at sounds that haven't been added yet.
+    fn load(path: &Path) -> Result<Option<Self>, String> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        let decoder = Decoder::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+        Ok(Some(Self {
+            channels: decoder.channels(),
+            sample_rate: decoder.sample_rate(),
+            samples: decoder.collect(),
+        }))
+    }
+
+    fn source(&self) -> SamplesBuffer<i16> {
+        SamplesBuffer::new(self.channels, self.sample_rate, self.samples.clone())
+    }
+}
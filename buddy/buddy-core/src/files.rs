@@ -0,0 +1,162 @@
+//! "Empty the recycle bin", "create a new folder called X on the desktop" and
+//! "delete the last screenshot", each behind its own `[system]` switch.
+
+use crate::{
+    clock,
+    config::Config,
+    intent::Intent,
+    rules::EXACT_CONFIDENCE,
+    windows_api::{self, WindowsActionError},
+};
+use std::{fs, io, path::PathBuf};
+
+/// Characters Windows doesn't allow in a file name.
+const RESERVED: &[char] = &['\\', '/', ':', '*', '?', '"', '<', '>', '|'];
+
+#[derive(Debug)]
+pub enum FilesError {
+    Io(io::Error),
+    Windows(WindowsActionError),
+    /// `new_folder` without a usable name; holds what was heard.
+    BadName(String),
+}
+
+impl std::fmt::Display for FilesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "io error: {}", err),
+            Self::Windows(err) => write!(f, "{}", err),
+            Self::BadName(name) => write!(f, "'{}' can't be a folder name", name),
+        }
+    }
+}
+
+impl std::error::Error for FilesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Windows(err) => Some(err),
+            Self::BadName(_) => None,
+        }
+    }
+}
+
+/// Whether `target` is switched on under `[system]`.
+pub fn enabled(target: &str, config: &Config) -> bool {
+    match target {
+        "empty_recycle_bin" => config.system.empty_recycle_bin,
+        "new_folder" => config.system.new_folder,
+        "delete_last_screenshot" => config.system.delete_last_screenshot,
+        _ => false,
+    }
+}
+
+pub fn recognize(transcript: &str, config: &Config) -> Option<Intent> {
+    let words = clock::words(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let words = words.strip_suffix(&["please"]).unwrap_or(&words);
+    let (target, name) = match words {
+        ["empty", "the" | "my", "recycle", "bin"]
+        | ["empty", "recycle", "bin"]
+        | ["empty", "the" | "my", "trash"]
+        | ["empty", "trash"] => ("empty_recycle_bin", None),
+        ["delete" | "remove", "the" | "my", "last" | "latest", "screenshot"] => {
+            ("delete_last_screenshot", None)
+        }
+        ["create" | "make" | "add", ..] => ("new_folder", Some(folder_name(transcript)?)),
+        _ => return None,
+    };
+    enabled(target, config).then(|| Intent::Files {
+        target: target.to_string(),
+        name,
+        confidence: EXACT_CONFIDENCE,
+    })
+}
+
+/// "Create a new folder called Tax Returns on the desktop." names "Tax
+/// Returns"; the casing is kept.
+fn folder_name(transcript: &str) -> Option<String> {
+    let tokens: Vec<&str> = transcript.split_whitespace().collect();
+    let lower: Vec<String> = tokens.iter().map(|token| clock::words(token).concat()).collect();
+    let folder = lower.iter().position(|word| word == "folder")?;
+    let start = folder + 1;
+    if !matches!(lower.get(start).map(String::as_str), Some("called" | "named")) {
+        return None;
+    }
+    let mut end = tokens.len();
+    if end >= start + 3
+        && lower[end - 3] == "on"
+        && matches!(lower[end - 2].as_str(), "the" | "my")
+        && lower[end - 1] == "desktop"
+    {
+        end -= 3;
+    }
+    let name = tokens.get(start + 1..end)?.join(" ");
+    let name = name.trim_end_matches(['.', ',', '!', '?']).trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// What a file command does, e.g. "empty the recycle bin".
+pub fn describe(target: &str, name: Option<&str>) -> String {
+    match (target, name) {
+        ("empty_recycle_bin", _) => "empty the recycle bin".to_string(),
+        ("new_folder", Some(name)) => format!("create a folder called {} on the desktop", name),
+        ("delete_last_screenshot", _) => "delete the last screenshot".to_string(),
+        (other, _) => other.replace('_', " "),
+    }
+}
+
+/// Runs a file command and returns what to say about it.
+pub fn run(target: &str, name: Option<&str>, config: &Config) -> Result<String, FilesError> {
+    match target {
+        "empty_recycle_bin" => {
+            let items = windows_api::empty_recycle_bin().map_err(FilesError::Windows)?;
+            Ok(match items {
+                0 => "The recycle bin is already empty".to_string(),
+                1 => "Emptied the recycle bin: 1 item".to_string(),
+                items => format!("Emptied the recycle bin: {} items", items),
+            })
+        }
+        "new_folder" => {
+            let name = name.unwrap_or_default();
+            if name.is_empty() || name.contains(RESERVED) || name.trim_matches('.').is_empty() {
+                return Err(FilesError::BadName(name.to_string()));
+            }
+            let path = windows_api::desktop_dir()
+                .map_err(FilesError::Windows)?
+                .join(name);
+            if path.exists() {
+                return Ok(format!("There's already a {} on the desktop", name));
+            }
+            fs::create_dir(&path).map_err(FilesError::Io)?;
+            Ok(format!("Created the folder {} on the desktop", name))
+        }
+        "delete_last_screenshot" => match last_screenshot(config)? {
+            Some(path) => {
+                fs::remove_file(&path).map_err(FilesError::Io)?;
+                Ok("Deleted the last screenshot".to_string())
+            }
+            None => Ok("There are no screenshots to delete".to_string()),
+        },
+        other => Ok(format!("I can't {}", other.replace('_', " "))),
+    }
+}
+
+/// The newest screenshot Buddy saved in `screenshot.dir`; their names sort
+/// by the time they were taken.
+fn last_screenshot(config: &Config) -> Result<Option<PathBuf>, FilesError> {
+    let entries = match fs::read_dir(&config.screenshot.dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(FilesError::Io(err)),
+    };
+    Ok(entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("screenshot-") && name.ends_with(".png"))
+        })
+        .max())
+}
@@ -0,0 +1,42 @@
+/// Free local video memory (MB) on the adapter with the most dedicated VRAM,
+/// or `None` when it cannot be queried.
+#[cfg(target_os = "windows")]
+pub fn available_vram_mb() -> Option<u64> {
+    use windows::core::Interface;
+    use windows::Win32::Graphics::Dxgi::{
+        CreateDXGIFactory1, IDXGIAdapter3, IDXGIFactory1, DXGI_MEMORY_SEGMENT_GROUP_LOCAL,
+        DXGI_QUERY_VIDEO_MEMORY_INFO,
+    };
+
+    unsafe {
+        let factory: IDXGIFactory1 = CreateDXGIFactory1().ok()?;
+        let mut best: Option<(usize, IDXGIAdapter3)> = None;
+        let mut index = 0;
+        while let Ok(adapter) = factory.EnumAdapters1(index) {
+            index += 1;
+            let Ok(desc) = adapter.GetDesc1() else {
+                continue;
+            };
+            let Ok(adapter3) = adapter.cast::<IDXGIAdapter3>() else {
+                continue;
+            };
+            if best
+                .as_ref()
+                .is_none_or(|(memory, _)| desc.DedicatedVideoMemory > *memory)
+            {
+                best = Some((desc.DedicatedVideoMemory, adapter3));
+            }
+        }
+        let (_, adapter) = best?;
+        let mut info = DXGI_QUERY_VIDEO_MEMORY_INFO::default();
+        adapter
+            .QueryVideoMemoryInfo(0, DXGI_MEMORY_SEGMENT_GROUP_LOCAL, &mut info)
+            .ok()?;
+        Some(info.Budget.saturating_sub(info.CurrentUsage) / (1024 * 1024))
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn available_vram_mb() -> Option<u64> {
+    None
+}
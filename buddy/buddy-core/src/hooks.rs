@@ -0,0 +1,143 @@
+//! `[hooks]`: the user's shell commands run before and after each command
+//! Buddy executes, for logging, home automation or notifications. A hook
+//! gets the intent as JSON on stdin and in the environment; a failing
+//! `pre_execute` cancels the command.
+
+use crate::{
+    config::HooksConfig,
+    executor::{ExecutionError, ExecutionResult},
+    intent::Intent,
+    process::{self, Output},
+};
+use std::{
+    fmt,
+    io::{self, Write},
+    process::{Child, Stdio},
+    thread,
+    time::Duration,
+};
+
+/// Runs `pre_execute` for `intent`, and waits for it. `Err` means the
+/// command must not run.
+pub fn before(config: &HooksConfig, intent: &Intent) -> Result<(), HookError> {
+    let Some(command) = &config.pre_execute else {
+        return Ok(());
+    };
+    let child = start(command, intent, &[])?;
+    let output = wait(child, Duration::from_secs(config.timeout_secs))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    // What the hook printed is the reason it gives.
+    let said = [&output.stderr, &output.stdout]
+        .into_iter()
+        .map(|text| String::from_utf8_lossy(text).trim().to_string())
+        .find(|text| !text.is_empty());
+    Err(HookError::Refused(said.unwrap_or_else(|| output.status.to_string())))
+}
+
+/// Starts `post_execute` with the outcome of `intent`, without waiting for
+/// it; problems are only printed.
+pub fn after(
+    config: &HooksConfig,
+    intent: &Intent,
+    result: &Result<ExecutionResult, ExecutionError>,
+) {
+    let Some(command) = &config.post_execute else {
+        return;
+    };
+    let (succeeded, message) = match result {
+        Ok(result) => ("true", describe(result)),
+        Err(err) => ("false", err.to_string()),
+    };
+    let env = [("BUDDY_SUCCEEDED", succeeded), ("BUDDY_MESSAGE", message.as_str())];
+    let child = match start(command, intent, &env) {
+        Ok(child) => child,
+        Err(err) => {
+            eprintln!("post_execute hook: {}", err);
+            return;
+        }
+    };
+    let timeout = Duration::from_secs(config.timeout_secs);
+    thread::spawn(move || match wait(child, timeout) {
+        Ok(output) if !output.status.success() => {
+            eprintln!("post_execute hook failed: {}", output.status);
+        }
+        Ok(_) => {}
+        Err(err) => eprintln!("post_execute hook: {}", err),
+    });
+}
+
+/// Starts `command` in the shell with `intent` on stdin.
+fn start(command: &str, intent: &Intent, env: &[(&str, &str)]) -> Result<Child, HookError> {
+    let json = intent.to_json();
+    let target = json["target"].as_str().unwrap_or_default();
+    let mut child = process::shell(command)
+        .env("BUDDY_INTENT", json.to_string())
+        .env("BUDDY_ACTION", intent.action().name())
+        .env("BUDDY_TARGET", target)
+        .envs(env.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(HookError::Start)?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that doesn't read stdin may have closed it already.
+        let _ = writeln!(stdin, "{}", json);
+    }
+    Ok(child)
+}
+
+/// Waits for `child` to exit, stopping it after `timeout`.
+fn wait(child: Child, timeout: Duration) -> Result<Output, HookError> {
+    process::wait_output(child, timeout)
+        .map_err(HookError::Start)?
+        .ok_or(HookError::TimedOut(timeout))
+}
+
+/// What Buddy reported for `result`.
+fn describe(result: &ExecutionResult) -> String {
+    match result {
+        ExecutionResult::Action(message)
+        | ExecutionResult::Answer(message)
+        | ExecutionResult::DryRun(message)
+        | ExecutionResult::Ran {
+            summary: message, ..
+        } => message.clone(),
+        ExecutionResult::Translation { text, .. } => text.clone(),
+        ExecutionResult::Choose(choices) => {
+            let labels: Vec<&str> = choices.iter().map(|choice| choice.label.as_str()).collect();
+            format!("Several matches: {}", labels.join("; "))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum HookError {
+    Start(io::Error),
+    TimedOut(Duration),
+    /// `pre_execute` exited with an error; what it printed, or its status.
+    Refused(String),
+}
+
+impl fmt::Display for HookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Start(err) => write!(f, "failed to run hook: {}", err),
+            Self::TimedOut(timeout) => {
+                write!(f, "hook took longer than {}s and was stopped", timeout.as_secs())
+            }
+            Self::Refused(reason) => write!(f, "cancelled by pre_execute hook: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for HookError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Start(err) => Some(err),
+            Self::TimedOut(_) | Self::Refused(_) => None,
+        }
+    }
+}
@@ -0,0 +1,856 @@
+use crate::config::HotkeyConfig;
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+#[cfg(target_os = "windows")]
+use std::{
+    ptr,
+    sync::{atomic::AtomicU32, mpsc as std_mpsc},
+};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+#[cfg(target_os = "windows")]
+use windows::Win32::{
+    Foundation::{HWND, LPARAM, WPARAM},
+    System::Threading::GetCurrentThreadId,
+    UI::{
+        Input::KeyboardAndMouse::{self, *},
+        WindowsAndMessaging::{GetMessageW, PostThreadMessageW, MSG, WM_HOTKEY, WM_QUIT},
+    },
+};
+
+#[cfg(target_os = "windows")]
+static HOTKEY_ID: AtomicU32 = AtomicU32::new(1);
+
+pub use platform::{HotkeyError, HotkeyListener};
+
+/// What a hotkey press asks for; each is bound to its own key in `[hotkey]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    Command,
+    Dictation,
+    Cancel,
+    Stop,
+}
+
+/// Flags a hotkey press raises on the spot, so work in progress can stop
+/// without waiting for the main loop to see the press.
+#[derive(Debug, Clone, Default)]
+pub struct Interrupts {
+    /// The cancel hotkey: stops a recording in progress.
+    pub cancel: Arc<AtomicBool>,
+    /// The stop hotkey: stops speech and an answer still streaming in.
+    pub stop: Arc<AtomicBool>,
+}
+
+impl Interrupts {
+    fn raise(&self, action: HotkeyAction) {
+        match action {
+            HotkeyAction::Cancel => self.cancel.store(true, Ordering::Relaxed),
+            HotkeyAction::Stop => self.stop.store(true, Ordering::Relaxed),
+            HotkeyAction::Command | HotkeyAction::Dictation => {}
+        }
+    }
+}
+
+/// A key and the modifiers held with it, as the config writes them:
+/// "ctrl+shift+s", "win+d", "f9". Hotkeys and `[sendkeys]` both use these.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyCombo {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    /// The Windows key; Super on Linux keyboards, Command on a Mac.
+    pub win: bool,
+    /// The name of the key pressed with them, lowercase: "s", "f9", "enter".
+    pub key: String,
+}
+
+impl KeyCombo {
+    /// Reads "ctrl+alt+b". The key name is left for each platform to check;
+    /// the error names what is wrong.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut combo = Self {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            win: false,
+            key: String::new(),
+        };
+        for token in text.split('+') {
+            let token = token.trim().to_lowercase();
+            match token.as_str() {
+                "ctrl" | "control" => combo.ctrl = true,
+                "alt" | "option" => combo.alt = true,
+                "shift" => combo.shift = true,
+                "win" | "windows" | "super" | "meta" | "cmd" | "command" => combo.win = true,
+                _ => combo.key = token,
+            }
+        }
+        if combo.key.is_empty() {
+            return Err("missing key".into());
+        }
+        Ok(combo)
+    }
+}
+
+/// Combos separated by spaces, pressed one after another: "ctrl+k ctrl+c".
+/// Every key has to have a Windows virtual-key code.
+pub fn parse_sequence(text: &str) -> Result<Vec<KeyCombo>, String> {
+    let combos = text
+        .split_whitespace()
+        .map(|part| {
+            let combo = KeyCombo::parse(part)?;
+            match virtual_key(&combo.key) {
+                Some(_) => Ok(combo),
+                None => Err(format!("unknown key '{}'", combo.key)),
+            }
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    if combos.is_empty() {
+        return Err("no keys".into());
+    }
+    Ok(combos)
+}
+
+/// The Windows virtual-key code of a key name: letters, digits, F1-F24,
+/// and the named keys below.
+pub fn virtual_key(key: &str) -> Option<u16> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_alphanumeric() {
+            return Some(c.to_ascii_uppercase() as u16);
+        }
+    }
+    Some(match key {
+        "space" => 0x20,
+        "enter" | "return" => 0x0D,
+        "tab" => 0x09,
+        "escape" | "esc" => 0x1B,
+        "backspace" => 0x08,
+        "delete" | "del" => 0x2E,
+        "insert" | "ins" => 0x2D,
+        "home" => 0x24,
+        "end" => 0x23,
+        "pageup" => 0x21,
+        "pagedown" => 0x22,
+        "left" => 0x25,
+        "up" => 0x26,
+        "right" => 0x27,
+        "down" => 0x28,
+        _ => match key.strip_prefix('f')?.parse::<u16>().ok()? {
+            number @ 1..=24 => 0x6F + number,
+            _ => return None,
+        },
+    })
+}
+
+/// The configured bindings, command mode first.
+fn bindings(cfg: &HotkeyConfig) -> Vec<(HotkeyAction, &str)> {
+    let mut bindings = vec![(HotkeyAction::Command, cfg.key.as_str())];
+    if let Some(key) = &cfg.dictation {
+        bindings.push((HotkeyAction::Dictation, key));
+    }
+    if let Some(key) = &cfg.cancel {
+        bindings.push((HotkeyAction::Cancel, key));
+    }
+    if let Some(key) = &cfg.stop {
+        bindings.push((HotkeyAction::Stop, key));
+    }
+    bindings
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::*;
+    use windows::core::Error as WinError;
+    pub struct HotkeyListener {
+        rx: UnboundedReceiver<HotkeyAction>,
+        thread: Option<thread::JoinHandle<()>>,
+        thread_id: u32,
+    }
+
+    impl HotkeyListener {
+        /// Registers every configured hotkey. `interrupts` are raised the
+        /// moment the cancel or stop hotkey fires, so a recording in progress
+        /// can stop without waiting for the main loop.
+        pub fn new(cfg: &HotkeyConfig, interrupts: Interrupts) -> Result<Self, HotkeyError> {
+            let mut hotkeys = Vec::new();
+            for (action, key) in bindings(cfg) {
+                let (modifiers, vk) = parse_hotkey(key)?;
+                let hotkey_id = super::HOTKEY_ID.fetch_add(1, Ordering::Relaxed);
+                hotkeys.push(Binding {
+                    id: hotkey_id,
+                    modifiers,
+                    vk,
+                    action,
+                });
+            }
+            let (event_tx, event_rx) = mpsc::unbounded_channel();
+            let (ready_tx, ready_rx) = std_mpsc::channel();
+
+            let thread =
+                thread::spawn(move || hotkey_worker(hotkeys, event_tx, interrupts, ready_tx));
+
+            let ready = match ready_rx.recv().map_err(|_| HotkeyError::ThreadInit)? {
+                Ok(data) => data,
+                Err(err) => return Err(err),
+            };
+
+            Ok(Self {
+                rx: event_rx,
+                thread: Some(thread),
+                thread_id: ready.thread_id,
+            })
+        }
+
+        /// Waits for the next hotkey press and says which one it was.
+        pub async fn wait(&mut self) -> Result<HotkeyAction, HotkeyError> {
+            self.rx.recv().await.ok_or(HotkeyError::Channel)
+        }
+    }
+
+    impl Drop for HotkeyListener {
+        fn drop(&mut self) {
+            unsafe {
+                let _ = PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
+            }
+            if let Some(handle) = self.thread.take() {
+                let _ = handle.join();
+            }
+        }
+    }
+
+    struct HotkeyReady {
+        thread_id: u32,
+    }
+
+    struct Binding {
+        id: u32,
+        modifiers: HOT_KEY_MODIFIERS,
+        vk: VIRTUAL_KEY,
+        action: HotkeyAction,
+    }
+
+    fn hotkey_worker(
+        hotkeys: Vec<Binding>,
+        tx: UnboundedSender<HotkeyAction>,
+        interrupts: Interrupts,
+        ready: std_mpsc::Sender<Result<HotkeyReady, HotkeyError>>,
+    ) {
+        unsafe {
+            let thread_id = GetCurrentThreadId();
+            for (index, hotkey) in hotkeys.iter().enumerate() {
+                let flags = hotkey.modifiers | MOD_NOREPEAT;
+                if let Err(err) = KeyboardAndMouse::RegisterHotKey(
+                    HWND(ptr::null_mut()),
+                    hotkey.id as i32,
+                    flags,
+                    hotkey.vk.0 as u32,
+                ) {
+                    for registered in &hotkeys[..index] {
+                        let _ = KeyboardAndMouse::UnregisterHotKey(
+                            HWND(ptr::null_mut()),
+                            registered.id as i32,
+                        );
+                    }
+                    let _ = ready.send(Err(HotkeyError::Register(err)));
+                    return;
+                }
+            }
+            let _ = ready.send(Ok(HotkeyReady { thread_id }));
+
+            let mut msg = MSG::default();
+            loop {
+                let status = GetMessageW(&mut msg, HWND(ptr::null_mut()), 0, 0);
+                if status.0 <= 0 {
+                    break;
+                }
+                if msg.message == WM_HOTKEY {
+                    if let Some(hotkey) = hotkeys
+                        .iter()
+                        .find(|hotkey| msg.wParam == WPARAM(hotkey.id as usize))
+                    {
+                        interrupts.raise(hotkey.action);
+                        let _ = tx.send(hotkey.action);
+                    }
+                }
+                if msg.message == WM_QUIT {
+                    break;
+                }
+            }
+
+            for hotkey in &hotkeys {
+                let _ =
+                    KeyboardAndMouse::UnregisterHotKey(HWND(ptr::null_mut()), hotkey.id as i32);
+            }
+        }
+    }
+
+    fn parse_hotkey(hotkey: &str) -> Result<(HOT_KEY_MODIFIERS, VIRTUAL_KEY), HotkeyError> {
+        let combo = KeyCombo::parse(hotkey).map_err(HotkeyError::Parse)?;
+        let key = virtual_key(&combo.key).ok_or_else(|| HotkeyError::Parse(combo.key.clone()))?;
+        let mut modifiers = HOT_KEY_MODIFIERS(0);
+        for (held, modifier) in [
+            (combo.ctrl, MOD_CONTROL),
+            (combo.alt, MOD_ALT),
+            (combo.shift, MOD_SHIFT),
+            (combo.win, MOD_WIN),
+        ] {
+            if held {
+                modifiers |= modifier;
+            }
+        }
+        Ok((modifiers, VIRTUAL_KEY(key)))
+    }
+
+    #[derive(Debug)]
+    pub enum HotkeyError {
+        Parse(String),
+        Register(WinError),
+        Channel,
+        ThreadInit,
+    }
+
+    impl fmt::Display for HotkeyError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::Parse(key) => write!(f, "invalid hotkey '{}'", key),
+                Self::Register(err) => write!(f, "failed to register hotkey: {}", err),
+                Self::Channel => write!(f, "hotkey event channel closed"),
+                Self::ThreadInit => write!(f, "failed to initialize hotkey listener"),
+            }
+        }
+    }
+
+    impl std::error::Error for HotkeyError {}
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use super::*;
+
+    pub struct HotkeyListener {
+        /// `None` when keys could not be grabbed; Enter in the console still works.
+        rx: Option<UnboundedReceiver<HotkeyAction>>,
+        _grab: Option<backend::Grab>,
+    }
+
+    impl HotkeyListener {
+        /// Grabs every configured hotkey. `interrupts` are raised the moment
+        /// the cancel or stop hotkey fires. A bad key string is an error; a
+        /// desktop that will not hand out key events is reported and Buddy
+        /// carries on with the console's Enter key as the trigger.
+        pub fn new(cfg: &HotkeyConfig, interrupts: Interrupts) -> Result<Self, HotkeyError> {
+            let mut combos = Vec::new();
+            for (action, key) in bindings(cfg) {
+                combos.push((action, parse_combo(key)?));
+            }
+            let (tx, rx) = mpsc::unbounded_channel();
+            match backend::grab(combos, tx, interrupts) {
+                Ok(grab) => Ok(Self {
+                    rx: Some(rx),
+                    _grab: Some(grab),
+                }),
+                Err(reason) => {
+                    eprintln!("Global hotkeys unavailable: {}", reason);
+                    println!("Press Enter to simulate hotkey '{}'", cfg.key);
+                    Ok(Self {
+                        rx: None,
+                        _grab: None,
+                    })
+                }
+            }
+        }
+
+        /// Waits for the next hotkey press and says which one it was.
+        pub async fn wait(&mut self) -> Result<HotkeyAction, HotkeyError> {
+            match self.rx.as_mut() {
+                Some(rx) => rx.recv().await.ok_or(HotkeyError::Channel),
+                // Never fires; other triggers still run alongside it in `select!`.
+                None => std::future::pending().await,
+            }
+        }
+    }
+
+    /// A parsed "ctrl+alt+b" whose key the backend can map.
+    fn parse_combo(hotkey: &str) -> Result<KeyCombo, HotkeyError> {
+        let combo = KeyCombo::parse(hotkey).map_err(HotkeyError::Parse)?;
+        if backend::key_code(&combo.key).is_none() {
+            return Err(HotkeyError::Parse(combo.key));
+        }
+        Ok(combo)
+    }
+
+    /// Reads keyboards through evdev, which works the same under X11 and
+    /// Wayland but needs read access to `/dev/input/event*`.
+    #[cfg(target_os = "linux")]
+    mod backend {
+        use super::*;
+        use std::{
+            fs::{self, File},
+            io::{self, Read},
+        };
+
+        const EV_KEY: u16 = 1;
+        const KEY_A: u32 = 30;
+        /// `struct input_event`: a timeval followed by type, code and value.
+        const EVENT_SIZE: usize = std::mem::size_of::<usize>() * 2 + 8;
+
+        const CTRL: [u16; 2] = [29, 97];
+        const SHIFT: [u16; 2] = [42, 54];
+        const ALT: [u16; 2] = [56, 100];
+        const META: [u16; 2] = [125, 126];
+
+        /// Stops the reader threads once they see their next event.
+        pub struct Grab {
+            stop: Arc<AtomicBool>,
+        }
+
+        impl Drop for Grab {
+            fn drop(&mut self) {
+                self.stop.store(true, Ordering::Relaxed);
+            }
+        }
+
+        pub fn grab(
+            combos: Vec<(HotkeyAction, KeyCombo)>,
+            tx: UnboundedSender<HotkeyAction>,
+            interrupts: Interrupts,
+        ) -> Result<Grab, String> {
+            let bindings: Arc<Vec<(HotkeyAction, KeyCombo, u16)>> = Arc::new(
+                combos
+                    .into_iter()
+                    .filter_map(|(action, combo)| {
+                        let code = key_code(&combo.key)?;
+                        Some((action, combo, code))
+                    })
+                    .collect(),
+            );
+            let (keyboards, denied) = open_keyboards()?;
+            if keyboards.is_empty() {
+                let mut reason = if denied {
+                    "no permission to read keyboards in /dev/input (add your user to the \
+                     'input' group and log in again)"
+                        .to_string()
+                } else {
+                    "no keyboards found in /dev/input".to_string()
+                };
+                if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+                    reason.push_str("; Wayland does not let applications grab keys directly");
+                }
+                return Err(reason);
+            }
+            let stop = Arc::new(AtomicBool::new(false));
+            for keyboard in keyboards {
+                let bindings = Arc::clone(&bindings);
+                let tx = tx.clone();
+                let interrupts = interrupts.clone();
+                let stop = Arc::clone(&stop);
+                thread::spawn(move || read_keyboard(keyboard, &bindings, &tx, &interrupts, &stop));
+            }
+            Ok(Grab { stop })
+        }
+
+        /// Opens every event device that reports letter keys. The flag tells
+        /// whether some device could not be opened for lack of permission.
+        fn open_keyboards() -> Result<(Vec<File>, bool), String> {
+            let entries = fs::read_dir("/dev/input")
+                .map_err(|err| format!("cannot list /dev/input: {}", err))?;
+            let mut keyboards = Vec::new();
+            let mut denied = false;
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if !name.starts_with("event") || !has_letter_keys(&name) {
+                    continue;
+                }
+                match File::open(entry.path()) {
+                    Ok(file) => keyboards.push(file),
+                    Err(err) if err.kind() == io::ErrorKind::PermissionDenied => denied = true,
+                    Err(_) => {}
+                }
+            }
+            Ok((keyboards, denied))
+        }
+
+        /// The key capability bitmap in sysfs is a list of hex words, most
+        /// significant first; KEY_A lives in the last one.
+        fn has_letter_keys(event: &str) -> bool {
+            let path = format!("/sys/class/input/{}/device/capabilities/key", event);
+            fs::read_to_string(path)
+                .ok()
+                .and_then(|bits| {
+                    let word = bits.split_whitespace().last()?;
+                    u64::from_str_radix(word, 16).ok()
+                })
+                .is_some_and(|word| word & (1 << KEY_A) != 0)
+        }
+
+        fn read_keyboard(
+            mut keyboard: File,
+            bindings: &[(HotkeyAction, KeyCombo, u16)],
+            tx: &UnboundedSender<HotkeyAction>,
+            interrupts: &Interrupts,
+            stop: &AtomicBool,
+        ) {
+            let mut held = Vec::new();
+            let mut event = [0u8; EVENT_SIZE];
+            while keyboard.read_exact(&mut event).is_ok() {
+                if stop.load(Ordering::Relaxed) {
+                    return;
+                }
+                let offset = EVENT_SIZE - 8;
+                let kind = u16::from_ne_bytes([event[offset], event[offset + 1]]);
+                let code = u16::from_ne_bytes([event[offset + 2], event[offset + 3]]);
+                let value = i32::from_ne_bytes([
+                    event[offset + 4],
+                    event[offset + 5],
+                    event[offset + 6],
+                    event[offset + 7],
+                ]);
+                if kind != EV_KEY {
+                    continue;
+                }
+                // 0 = release, 1 = press, 2 = autorepeat (ignored, like MOD_NOREPEAT).
+                match value {
+                    0 => held.retain(|&key| key != code),
+                    1 => {
+                        let down = |keys: [u16; 2]| keys.iter().any(|key| held.contains(key));
+                        let fired = bindings.iter().find(|(_, combo, key)| {
+                            *key == code
+                                && combo.ctrl == down(CTRL)
+                                && combo.alt == down(ALT)
+                                && combo.shift == down(SHIFT)
+                                && combo.win == down(META)
+                        });
+                        if let Some((action, _, _)) = fired {
+                            interrupts.raise(*action);
+                            if tx.send(*action).is_err() {
+                                return;
+                            }
+                        }
+                        held.push(code);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        /// Linux input event codes (linux/input-event-codes.h).
+        pub(super) fn key_code(key: &str) -> Option<u16> {
+            const LETTERS: &str = "qwertyuiop";
+            const HOME_ROW: &str = "asdfghjkl";
+            const BOTTOM_ROW: &str = "zxcvbnm";
+            let mut chars = key.chars();
+            if let (Some(c), None) = (chars.next(), chars.next()) {
+                let row = [(LETTERS, 16), (HOME_ROW, 30), (BOTTOM_ROW, 44)]
+                    .into_iter()
+                    .find_map(|(row, first)| row.find(c).map(|index| first + index as u16));
+                if row.is_some() {
+                    return row;
+                }
+                if let Some(digit) = c.to_digit(10) {
+                    return Some(if digit == 0 { 11 } else { digit as u16 + 1 });
+                }
+            }
+            match key {
+                "space" => Some(57),
+                "enter" => Some(28),
+                _ => {
+                    let number: u16 = key.strip_prefix('f')?.parse().ok()?;
+                    match number {
+                        1..=10 => Some(58 + number),
+                        11 | 12 => Some(76 + number),
+                        13..=24 => Some(170 + number),
+                        _ => None,
+                    }
+                }
+            }
+        }
+    }
+
+    /// A listen-only Quartz event tap. macOS asks the user to allow Buddy
+    /// under Privacy & Security > Input Monitoring the first time.
+    #[cfg(target_os = "macos")]
+    mod backend {
+        use super::*;
+        use std::{ffi::c_void, sync::mpsc as std_mpsc};
+
+        type CFMachPortRef = *mut c_void;
+        type CFRunLoopRef = *mut c_void;
+        type CFRunLoopSourceRef = *mut c_void;
+        type CGEventRef = *mut c_void;
+        type CGEventTapProxy = *mut c_void;
+        type CGEventTapCallBack = extern "C" fn(
+            proxy: CGEventTapProxy,
+            kind: u32,
+            event: CGEventRef,
+            user_info: *mut c_void,
+        ) -> CGEventRef;
+
+        const SESSION_EVENT_TAP: u32 = 1;
+        const HEAD_INSERT_EVENT_TAP: u32 = 0;
+        const TAP_OPTION_LISTEN_ONLY: u32 = 1;
+        const EVENT_KEY_DOWN: u32 = 10;
+        const EVENT_TAP_DISABLED_BY_TIMEOUT: u32 = 0xFFFF_FFFE;
+        const FIELD_AUTOREPEAT: u32 = 8;
+        const FIELD_KEYCODE: u32 = 9;
+        const FLAG_SHIFT: u64 = 0x0002_0000;
+        const FLAG_CONTROL: u64 = 0x0004_0000;
+        const FLAG_ALTERNATE: u64 = 0x0008_0000;
+        const FLAG_COMMAND: u64 = 0x0010_0000;
+
+        #[link(name = "ApplicationServices", kind = "framework")]
+        extern "C" {
+            fn CGEventTapCreate(
+                tap: u32,
+                place: u32,
+                options: u32,
+                events_of_interest: u64,
+                callback: CGEventTapCallBack,
+                user_info: *mut c_void,
+            ) -> CFMachPortRef;
+            fn CGEventTapEnable(tap: CFMachPortRef, enable: bool);
+            fn CGEventGetIntegerValueField(event: CGEventRef, field: u32) -> i64;
+            fn CGEventGetFlags(event: CGEventRef) -> u64;
+        }
+
+        #[link(name = "CoreFoundation", kind = "framework")]
+        extern "C" {
+            static kCFRunLoopCommonModes: *const c_void;
+            fn CFMachPortCreateRunLoopSource(
+                allocator: *const c_void,
+                port: CFMachPortRef,
+                order: isize,
+            ) -> CFRunLoopSourceRef;
+            fn CFRunLoopGetCurrent() -> CFRunLoopRef;
+            fn CFRunLoopAddSource(rl: CFRunLoopRef, source: CFRunLoopSourceRef, mode: *const c_void);
+            fn CFRunLoopRun();
+            fn CFRunLoopStop(rl: CFRunLoopRef);
+            fn CFRelease(cf: *const c_void);
+        }
+
+        /// Run loop of the tap thread, stopped when the grab is dropped.
+        struct RunLoop(CFRunLoopRef);
+
+        // CFRunLoopStop may be called from any thread.
+        unsafe impl Send for RunLoop {}
+
+        pub struct Grab {
+            run_loop: RunLoop,
+            thread: Option<thread::JoinHandle<()>>,
+        }
+
+        impl Drop for Grab {
+            fn drop(&mut self) {
+                unsafe { CFRunLoopStop(self.run_loop.0) };
+                if let Some(handle) = self.thread.take() {
+                    let _ = handle.join();
+                }
+            }
+        }
+
+        struct TapState {
+            bindings: Vec<(HotkeyAction, KeyCombo, u16)>,
+            tx: UnboundedSender<HotkeyAction>,
+            interrupts: Interrupts,
+            port: CFMachPortRef,
+        }
+
+        pub fn grab(
+            combos: Vec<(HotkeyAction, KeyCombo)>,
+            tx: UnboundedSender<HotkeyAction>,
+            interrupts: Interrupts,
+        ) -> Result<Grab, String> {
+            let bindings = combos
+                .into_iter()
+                .filter_map(|(action, combo)| {
+                    let code = key_code(&combo.key)?;
+                    Some((action, combo, code))
+                })
+                .collect();
+            let (ready_tx, ready_rx) = std_mpsc::channel();
+            let thread = thread::spawn(move || {
+                let mut state = Box::new(TapState {
+                    bindings,
+                    tx,
+                    interrupts,
+                    port: std::ptr::null_mut(),
+                });
+                unsafe {
+                    let port = CGEventTapCreate(
+                        SESSION_EVENT_TAP,
+                        HEAD_INSERT_EVENT_TAP,
+                        TAP_OPTION_LISTEN_ONLY,
+                        1 << EVENT_KEY_DOWN,
+                        on_event,
+                        &mut *state as *mut TapState as *mut c_void,
+                    );
+                    if port.is_null() {
+                        let _ = ready_tx.send(Err(
+                            "macOS refused the keyboard event tap; allow Buddy under \
+                             Privacy & Security > Input Monitoring"
+                                .to_string(),
+                        ));
+                        return;
+                    }
+                    state.port = port;
+                    let source = CFMachPortCreateRunLoopSource(std::ptr::null(), port, 0);
+                    let run_loop = CFRunLoopGetCurrent();
+                    CFRunLoopAddSource(run_loop, source, kCFRunLoopCommonModes);
+                    CGEventTapEnable(port, true);
+                    let _ = ready_tx.send(Ok(RunLoop(run_loop)));
+                    CFRunLoopRun();
+                    CFRelease(source);
+                    CFRelease(port);
+                }
+            });
+            match ready_rx.recv() {
+                Ok(Ok(run_loop)) => Ok(Grab {
+                    run_loop,
+                    thread: Some(thread),
+                }),
+                Ok(Err(reason)) => Err(reason),
+                Err(_) => Err("keyboard event tap thread exited".to_string()),
+            }
+        }
+
+        extern "C" fn on_event(
+            _proxy: CGEventTapProxy,
+            kind: u32,
+            event: CGEventRef,
+            user_info: *mut c_void,
+        ) -> CGEventRef {
+            let state = unsafe { &*(user_info as *const TapState) };
+            if kind == EVENT_TAP_DISABLED_BY_TIMEOUT {
+                unsafe { CGEventTapEnable(state.port, true) };
+                return event;
+            }
+            if kind != EVENT_KEY_DOWN
+                || unsafe { CGEventGetIntegerValueField(event, FIELD_AUTOREPEAT) } != 0
+            {
+                return event;
+            }
+            let code = unsafe { CGEventGetIntegerValueField(event, FIELD_KEYCODE) };
+            let flags = unsafe { CGEventGetFlags(event) };
+            let fired = state.bindings.iter().find(|(_, combo, key)| {
+                i64::from(*key) == code
+                    && combo.ctrl == (flags & FLAG_CONTROL != 0)
+                    && combo.alt == (flags & FLAG_ALTERNATE != 0)
+                    && combo.shift == (flags & FLAG_SHIFT != 0)
+                    && combo.win == (flags & FLAG_COMMAND != 0)
+            });
+            if let Some((action, _, _)) = fired {
+                state.interrupts.raise(*action);
+                let _ = state.tx.send(*action);
+            }
+            event
+        }
+
+        /// macOS virtual key codes (Carbon `kVK_*`, ANSI layout).
+        pub(super) fn key_code(key: &str) -> Option<u16> {
+            Some(match key {
+                "a" => 0x00,
+                "s" => 0x01,
+                "d" => 0x02,
+                "f" => 0x03,
+                "h" => 0x04,
+                "g" => 0x05,
+                "z" => 0x06,
+                "x" => 0x07,
+                "c" => 0x08,
+                "v" => 0x09,
+                "b" => 0x0B,
+                "q" => 0x0C,
+                "w" => 0x0D,
+                "e" => 0x0E,
+                "r" => 0x0F,
+                "y" => 0x10,
+                "t" => 0x11,
+                "1" => 0x12,
+                "2" => 0x13,
+                "3" => 0x14,
+                "4" => 0x15,
+                "6" => 0x16,
+                "5" => 0x17,
+                "9" => 0x19,
+                "7" => 0x1A,
+                "8" => 0x1C,
+                "0" => 0x1D,
+                "o" => 0x1F,
+                "u" => 0x20,
+                "i" => 0x22,
+                "p" => 0x23,
+                "l" => 0x25,
+                "j" => 0x26,
+                "k" => 0x28,
+                "n" => 0x2D,
+                "m" => 0x2E,
+                "enter" => 0x24,
+                "space" => 0x31,
+                "f1" => 0x7A,
+                "f2" => 0x78,
+                "f3" => 0x63,
+                "f4" => 0x76,
+                "f5" => 0x60,
+                "f6" => 0x61,
+                "f7" => 0x62,
+                "f8" => 0x64,
+                "f9" => 0x65,
+                "f10" => 0x6D,
+                "f11" => 0x67,
+                "f12" => 0x6F,
+                "f13" => 0x69,
+                "f14" => 0x6B,
+                "f15" => 0x71,
+                "f16" => 0x6A,
+                "f17" => 0x40,
+                "f18" => 0x4F,
+                "f19" => 0x50,
+                "f20" => 0x5A,
+                _ => return None,
+            })
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    mod backend {
+        use super::*;
+
+        pub struct Grab;
+
+        pub fn grab(
+            _combos: Vec<(HotkeyAction, KeyCombo)>,
+            _tx: UnboundedSender<HotkeyAction>,
+            _interrupts: Interrupts,
+        ) -> Result<Grab, String> {
+            Err("global hotkeys are not supported on this platform".to_string())
+        }
+
+        pub(super) fn key_code(key: &str) -> Option<u16> {
+            (!key.is_empty()).then_some(0)
+        }
+    }
+
+    #[derive(Debug)]
+    pub enum HotkeyError {
+        Parse(String),
+        Channel,
+    }
+
+    impl fmt::Display for HotkeyError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::Parse(key) => write!(f, "invalid hotkey '{}'", key),
+                Self::Channel => write!(f, "hotkey event channel closed"),
+            }
+        }
+    }
+
+    impl std::error::Error for HotkeyError {}
+}
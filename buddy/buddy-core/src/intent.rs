@@ -0,0 +1,2002 @@
+use crate::{
+    answer,
+    clock,
+    config::{
+        AssistantApi, Config, IntentFallback, ModelOptions, OutputFormat, TruncationStrategy,
+    },
+    corrections, dev, executor, files, media, process, prompt_template, rag, reminders, rules,
+    smart_home, transcription, urls,
+    usage::Ranking,
+};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::time::sleep;
+
+/// Downloading a model can take a long while on a slow connection.
+const PULL_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+/// Pause before the first retry of a failed request; doubled for each one
+/// after it.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// What Whisper knew about an utterance besides the text to classify.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Spoken<'a> {
+    /// The untranslated wording, when the text is a translation.
+    pub original: Option<&'a str>,
+    /// The language code spoken, when whisper detected it.
+    pub language: Option<&'a str>,
+    /// How sure whisper was of the words, from 0 to 1.
+    pub confidence: Option<f32>,
+}
+
+#[derive(Clone)]
+pub struct IntentClient {
+    client: Client,
+    endpoint: String,
+    model: String,
+    /// `deepseek.keep_alive` as Ollama takes it; `None` leaves it out.
+    keep_alive: Option<serde_json::Value>,
+    /// `deepseek.options`; `None` when none are set.
+    options: Option<serde_json::Value>,
+    /// `deepseek.retries`.
+    retries: u32,
+    /// Set while the model is still loading in the background; requests are
+    /// answered by the rule matcher until it clears.
+    warming_up: Arc<AtomicBool>,
+}
+
+impl IntentClient {
+    pub fn new(config: &Config) -> Self {
+        // A model loading cold can keep a request waiting for a while, but
+        // a server that is down should be noticed right away.
+        let client = Client::builder()
+            .connect_timeout(Duration::from_secs(config.deepseek.connect_timeout_secs))
+            .read_timeout(config.deepseek_timeout())
+            .build()
+            .expect("failed to build HTTP client");
+        Self {
+            client,
+            endpoint: config.deepseek.endpoint.clone(),
+            model: config.deepseek.model.clone(),
+            keep_alive: keep_alive(&config.deepseek.keep_alive),
+            options: model_options(&config.deepseek.options),
+            retries: config.deepseek.retries,
+            warming_up: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Loads the intent model (and `deepseek.router_model`) into Ollama ahead
+    /// of the first command. A model that was never pulled is pulled when
+    /// `deepseek.auto_pull` is set; otherwise Buddy says how to get it.
+    pub async fn preload_models(&self, config: &Config) {
+        let models = std::iter::once(&self.model).chain(&config.deepseek.router_model);
+        for model in models {
+            let loaded = match self.preload(model).await {
+                Err(IntentError::ModelNotFound(_)) if config.deepseek.auto_pull => {
+                    println!("Pulling {} into Ollama; this can take a while", model);
+                    match self.pull(model).await {
+                        Ok(()) => self.preload(model).await,
+                        Err(err) => Err(err),
+                    }
+                }
+                result => result,
+            };
+            match loaded {
+                Ok(()) => {}
+                Err(IntentError::ModelNotFound(_)) => eprintln!(
+                    "{} isn't pulled into Ollama; run `ollama pull {0}` or set \
+                     deepseek.auto_pull = true",
+                    model
+                ),
+                Err(err) => eprintln!("Could not preload {}: {}", model, err),
+            }
+        }
+    }
+
+    /// An empty generate request, which makes Ollama load `model` and keep
+    /// it for `deepseek.keep_alive`. Does nothing for other backends.
+    async fn preload(&self, model: &str) -> Result<(), IntentError> {
+        let Some(endpoint) = self.api_endpoint("generate") else {
+            return Ok(());
+        };
+        let payload = serde_json::json!({ "model": model, "keep_alive": self.keep_alive });
+        self.send(|| self.client.post(&endpoint).json(&payload), model)
+            .await
+            .map(|_| ())
+    }
+
+    /// Has Ollama download `model`; waits until it is done. The download is
+    /// streamed so that its progress keeps the read timeout from firing.
+    async fn pull(&self, model: &str) -> Result<(), IntentError> {
+        let Some(endpoint) = self.api_endpoint("pull") else {
+            return Ok(());
+        };
+        let mut response = self
+            .client
+            .post(endpoint)
+            .timeout(PULL_TIMEOUT)
+            .json(&serde_json::json!({ "model": model, "stream": true }))
+            .send()
+            .await
+            .map_err(IntentError::Request)?
+            .error_for_status()
+            .map_err(IntentError::Http)?;
+        let mut progress = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(IntentError::Response)? {
+            progress.extend_from_slice(&chunk);
+        }
+        // Ollama reports a name it can't find as an error line, not a status.
+        let failed = progress.split(|byte| *byte == b'\n').any(|line| {
+            serde_json::from_slice::<serde_json::Value>(line)
+                .is_ok_and(|line| line.get("error").is_some())
+        });
+        if failed {
+            return Err(IntentError::ModelNotFound(model.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Sends the request `build` makes, retrying failed connections,
+    /// timeouts and server errors up to `deepseek.retries` times with a
+    /// doubling pause in between.
+    async fn send(
+        &self,
+        build: impl Fn() -> reqwest::RequestBuilder,
+        model: &str,
+    ) -> Result<reqwest::Response, IntentError> {
+        let mut delay = RETRY_DELAY;
+        let mut attempt = 0;
+        loop {
+            let err = match build().send().await {
+                Ok(response) => check_status(response, model),
+                Err(err) => Err(IntentError::Request(err)),
+            };
+            let err = match err {
+                Err(err) if err.is_transient() && attempt < self.retries => err,
+                result => return result,
+            };
+            attempt += 1;
+            eprintln!(
+                "Intent request failed ({}); retrying ({}/{})",
+                err, attempt, self.retries
+            );
+            sleep(delay).await;
+            delay *= 2;
+        }
+    }
+
+    pub fn set_warming_up(&self, warming_up: bool) {
+        self.warming_up.store(warming_up, Ordering::Relaxed);
+    }
+
+    /// When `deepseek.stream_answers` is set the reply is streamed and each
+    /// complete sentence of an answer is passed to `on_sentence` as it arrives.
+    /// `spoken` names the language the user spoke, so answers can be given
+    /// in it.
+    pub async fn infer_intent(
+        &self,
+        transcription: &str,
+        spoken: Spoken<'_>,
+        config: &Config,
+        on_sentence: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<Intent, IntentError> {
+        if transcription.trim().is_empty() {
+            return Ok(Intent::Unknown { confidence: 0.0 });
+        }
+        if self.warming_up.load(Ordering::Relaxed) {
+            println!("Intent model still warming up; using rule-based intents");
+            return Ok(rules::infer_intent(transcription, config)
+                .unwrap_or(Intent::Unknown { confidence: 0.0 }));
+        }
+
+        let prepared = self.prepare_transcript(transcription, config).await;
+        let mut prompt = build_prompt(&prepared, config);
+        if config.rag.enabled {
+            match rag::context(transcription, config, self).await {
+                Ok(Some(passages)) => prompt.push_str(&format!(
+                    "\nPassages from the user's own documents, each after its file name. If the request asks about them, use action=answer, answer from these passages and name the file; if they don't hold the answer, say so:\n{}",
+                    passages
+                )),
+                Ok(None) => {}
+                Err(err) => eprintln!("Document search failed: {}", err),
+            }
+        }
+        let language = spoken
+            .language
+            .filter(|code| *code != "en")
+            .and_then(transcription::language_name);
+        match (spoken.original, language) {
+            (Some(original), language) => prompt.push_str(&format!(
+                "\nThe user actually spoke {}; their original words were: \"{}\". Write any response in that language.",
+                language.as_deref().unwrap_or("another language"),
+                original
+            )),
+            (None, Some(language)) => prompt.push_str(&format!(
+                "\nThe user is speaking {0}. Write any response in {0}.",
+                language
+            )),
+            (None, None) => {}
+        }
+        let system = config
+            .deepseek
+            .system_prompt
+            .as_deref()
+            .filter(|system| !system.trim().is_empty());
+        let mut messages: Vec<ChatMessage> = system
+            .map(|system| ChatMessage {
+                role: "system",
+                content: system.to_string(),
+                images: Vec::new(),
+            })
+            .into_iter()
+            .collect();
+        messages.push(ChatMessage {
+            role: "user",
+            content: prompt,
+            images: Vec::new(),
+        });
+        let format = output_format(config.deepseek.output_format);
+        if let Some(router) = &config.deepseek.router_model {
+            if let Some(intent) = self.route(router, &messages, format.as_ref(), config).await {
+                return Ok(intent);
+            }
+        }
+        let reply = if config.deepseek.stream_answers {
+            self.chat_stream(&messages, format.as_ref(), on_sentence).await
+        } else {
+            self.chat_messages(&messages, format.as_ref()).await
+        };
+        let content = match reply {
+            Ok(content) => content,
+            Err(err) if err.is_unavailable() && config.deepseek.fallback == IntentFallback::Rules => {
+                eprintln!("Intent service unavailable ({}); using rule-based fallback", err);
+                return match rules::infer_intent(transcription, config) {
+                    Some(intent) => Ok(intent),
+                    // "Try again in a moment" helps more than "I don't know".
+                    None if err.is_loading() => Err(err),
+                    None => Ok(Intent::Unknown { confidence: 0.0 }),
+                };
+            }
+            Err(err) => return Err(err),
+        };
+        let mut content = content;
+        let mut repairs = 0;
+        let intent = loop {
+            match parse_intent(&content) {
+                Ok(intent) => break intent,
+                Err(IntentError::InvalidFormat { .. })
+                    if repairs < config.deepseek.repair_retries =>
+                {
+                    repairs += 1;
+                    eprintln!(
+                        "Intent reply was not valid JSON; asking again ({}/{})",
+                        repairs, config.deepseek.repair_retries
+                    );
+                    messages.push(ChatMessage {
+                        role: "assistant",
+                        content,
+                        images: Vec::new(),
+                    });
+                    messages.push(ChatMessage {
+                        role: "user",
+                        content: config.deepseek.repair_prompt.clone(),
+                        images: Vec::new(),
+                    });
+                    content = self.chat_messages(&messages, format.as_ref()).await?;
+                }
+                Err(err) => return Err(err),
+            }
+        };
+        validate_intent_target(&intent, config)?;
+        let mut intent = intent;
+        if let Intent::Translate {
+            text,
+            language,
+            translation,
+            ..
+        } = &mut intent
+        {
+            match self.translate(text, language, config).await {
+                Ok(reply) if !reply.is_empty() => *translation = Some(reply),
+                Ok(_) => {}
+                Err(err) if translation.is_some() => {
+                    eprintln!("Translation request failed; using the intent reply: {}", err)
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(intent)
+    }
+
+    /// Asks `deepseek.router_model` first. Its intent stands unless it is an
+    /// answer (the main model knows more), a translation, unknown, below
+    /// `deepseek.router_confidence`, or names something not configured; then
+    /// `None` sends the command on to the main model.
+    async fn route(
+        &self,
+        router: &str,
+        messages: &[ChatMessage<'_>],
+        format: Option<&serde_json::Value>,
+        config: &Config,
+    ) -> Option<Intent> {
+        let reply = match self.chat_with(router, messages, format).await {
+            Ok(reply) => reply,
+            Err(err) => {
+                eprintln!("Router model failed ({}); asking {}", err, self.model);
+                return None;
+            }
+        };
+        let intent = parse_intent(&reply).ok()?;
+        let escalate = matches!(
+            intent,
+            Intent::Answer { .. } | Intent::Translate { .. } | Intent::Unknown { .. }
+        ) || intent.confidence() < config.deepseek.router_confidence
+            || validate_intent_target(&intent, config).is_err();
+        (!escalate).then_some(intent)
+    }
+
+    /// `text` in `language`, from `deepseek.translate_prompt`.
+    async fn translate(
+        &self,
+        text: &str,
+        language: &str,
+        config: &Config,
+    ) -> Result<String, IntentError> {
+        let prompt = config
+            .deepseek
+            .translate_prompt
+            .replace("{text}", text)
+            .replace("{language}", language);
+        let reply = self.chat(prompt).await?;
+        Ok(answer::strip_think(&reply)
+            .trim()
+            .trim_matches(['"', '\u{201c}', '\u{201d}'])
+            .trim()
+            .to_string())
+    }
+
+    /// Puts `question` to the `[assistants]` entry `name`, for "ask chatgpt
+    /// ...". Only `[deepseek]`'s retries apply; its options and keep-alive
+    /// are for the intent model.
+    pub async fn ask(
+        &self,
+        name: &str,
+        question: &str,
+        config: &Config,
+    ) -> Result<String, IntentError> {
+        let assistant = config
+            .assistants
+            .get(name)
+            .ok_or_else(|| IntentError::UnknownTarget(name.to_string()))?;
+        let system = (!assistant.system_prompt.trim().is_empty()).then(|| ChatMessage {
+            role: "system",
+            content: assistant.system_prompt.clone(),
+            images: Vec::new(),
+        });
+        let messages: Vec<ChatMessage> = system
+            .into_iter()
+            .chain(Some(ChatMessage {
+                role: "user",
+                content: question.to_string(),
+                images: Vec::new(),
+            }))
+            .collect();
+        let model = assistant.model.as_str();
+        let timeout = Duration::from_secs(assistant.timeout_secs);
+        let request = |payload: &serde_json::Value| {
+            let request = self
+                .client
+                .post(assistant.endpoint())
+                .timeout(timeout)
+                .json(payload);
+            match &assistant.api_key {
+                Some(key) => request.bearer_auth(key),
+                None => request,
+            }
+        };
+        let reply = match assistant.api {
+            AssistantApi::Ollama => {
+                let payload = serde_json::json!({
+                    "model": model,
+                    "messages": messages,
+                    "stream": false,
+                });
+                self.send(|| request(&payload), model)
+                    .await?
+                    .json::<ChatResponse>()
+                    .await
+                    .map_err(IntentError::Response)?
+                    .message
+                    .map(|msg| msg.content)
+            }
+            AssistantApi::OpenAi => {
+                let payload = serde_json::json!({ "model": model, "messages": messages });
+                self.send(|| request(&payload), model)
+                    .await?
+                    .json::<CompletionResponse>()
+                    .await
+                    .map_err(IntentError::Response)?
+                    .choices
+                    .into_iter()
+                    .next()
+                    .map(|choice| choice.message.content)
+            }
+        };
+        Ok(answer::strip_think(&reply.unwrap_or_default()).trim().to_string())
+    }
+
+    /// One embedding per text in `input`, in order, from Ollama's
+    /// `/api/embed` at `endpoint`.
+    pub async fn embed(
+        &self,
+        endpoint: &str,
+        model: &str,
+        input: &[String],
+    ) -> Result<Vec<Vec<f32>>, IntentError> {
+        let payload = serde_json::json!({ "model": model, "input": input });
+        Ok(self
+            .send(|| self.client.post(endpoint).json(&payload), model)
+            .await?
+            .json::<EmbedResponse>()
+            .await
+            .map_err(IntentError::Response)?
+            .embeddings)
+    }
+
+    async fn chat(&self, prompt: String) -> Result<String, IntentError> {
+        let messages = [ChatMessage {
+            role: "user",
+            content: prompt,
+            images: Vec::new(),
+        }];
+        self.chat_messages(&messages, None).await
+    }
+
+    /// `prompt` put to the intent model, for a reply in plain text.
+    pub async fn complete(&self, prompt: String) -> Result<String, IntentError> {
+        let reply = self.chat(prompt).await?;
+        Ok(answer::strip_think(&reply).trim().to_string())
+    }
+
+    /// Describes a PNG with `screenshot.vision_model`, sent to
+    /// `screenshot.vision_endpoint` or else the intent endpoint.
+    pub async fn describe_image(&self, png: &[u8], config: &Config) -> Result<String, IntentError> {
+        let screenshot = &config.screenshot;
+        let model = screenshot.vision_model.as_deref().unwrap_or(&self.model);
+        let endpoint = screenshot.vision_endpoint.as_deref().unwrap_or(&self.endpoint);
+        let messages = [ChatMessage {
+            role: "user",
+            content: screenshot.describe_prompt.clone(),
+            images: vec![base64(png)],
+        }];
+        // Vision models are big and rarely needed, so Ollama may unload this
+        // one as usual.
+        let payload = ChatRequest {
+            model,
+            messages: &messages,
+            stream: false,
+            format: None,
+            keep_alive: None,
+            options: None,
+        };
+        let timeout = Duration::from_secs(screenshot.vision_timeout_secs);
+        let request = || self.client.post(endpoint).timeout(timeout).json(&payload);
+        let response = self
+            .send(request, model)
+            .await?
+            .json::<ChatResponse>()
+            .await
+            .map_err(IntentError::Response)?;
+        let content = response.message.map(|msg| msg.content).unwrap_or_default();
+        Ok(answer::strip_think(&content).trim().to_string())
+    }
+
+    async fn chat_messages(
+        &self,
+        messages: &[ChatMessage<'_>],
+        format: Option<&serde_json::Value>,
+    ) -> Result<String, IntentError> {
+        self.chat_with(&self.model, messages, format).await
+    }
+
+    async fn chat_with(
+        &self,
+        model: &str,
+        messages: &[ChatMessage<'_>],
+        format: Option<&serde_json::Value>,
+    ) -> Result<String, IntentError> {
+        let payload = ChatRequest {
+            model,
+            messages,
+            stream: false,
+            format,
+            keep_alive: self.keep_alive.as_ref(),
+            options: self.options.as_ref(),
+        };
+
+        let response = self
+            .send(|| self.client.post(&self.endpoint).json(&payload), model)
+            .await?
+            .json::<ChatResponse>()
+            .await
+            .map_err(IntentError::Response)?;
+
+        Ok(response
+            .message
+            .map(|msg| msg.content.trim().to_string())
+            .unwrap_or_default())
+    }
+
+    async fn chat_stream(
+        &self,
+        messages: &[ChatMessage<'_>],
+        format: Option<&serde_json::Value>,
+        on_sentence: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<String, IntentError> {
+        let payload = ChatRequest {
+            model: &self.model,
+            messages,
+            stream: true,
+            format,
+            keep_alive: self.keep_alive.as_ref(),
+            options: self.options.as_ref(),
+        };
+        let mut response = self
+            .send(|| self.client.post(&self.endpoint).json(&payload), &self.model)
+            .await?;
+
+        let mut extractor = AnswerExtractor::default();
+        let mut pending: Vec<u8> = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(IntentError::Response)? {
+            pending.extend_from_slice(&chunk);
+            while let Some(pos) = pending.iter().position(|byte| *byte == b'\n') {
+                let line: Vec<u8> = pending.drain(..=pos).collect();
+                extractor.feed(&parse_stream_line(&line)?, on_sentence);
+            }
+        }
+        extractor.feed(&parse_stream_line(&pending)?, on_sentence);
+        Ok(extractor.finish(on_sentence))
+    }
+
+    /// Limits how much of a long dictation reaches the classifier prompt. The
+    /// caller keeps the full transcript.
+    async fn prepare_transcript(&self, transcription: &str, config: &Config) -> String {
+        let limit = config.deepseek.max_transcript_chars;
+        if limit == 0 || transcription.chars().count() <= limit {
+            return transcription.to_string();
+        }
+        match config.deepseek.truncation {
+            TruncationStrategy::Head => truncate_head(transcription, limit),
+            TruncationStrategy::Tail => truncate_tail(transcription, limit),
+            TruncationStrategy::HeadTail => truncate_head_tail(transcription, limit),
+            TruncationStrategy::Summarize => {
+                let prompt = format!(
+                    "Summarize this voice command in at most {limit} characters. Keep any file, app, or action names exactly as spoken. Reply with the summary only.\n\n{transcription}",
+                    limit = limit,
+                    transcription = transcription
+                );
+                match self.chat(prompt).await {
+                    Ok(summary) if !summary.is_empty() => truncate_head(&summary, limit),
+                    Ok(_) => truncate_head(transcription, limit),
+                    Err(err) => {
+                        eprintln!("Transcript summary failed, truncating instead: {}", err);
+                        truncate_head(transcription, limit)
+                    }
+                }
+            }
+        }
+    }
+
+    pub async fn wait_for_ready(&self) -> Result<(), IntentError> {
+        self.client
+            .get(self.tags_endpoint())
+            .send()
+            .await
+            .map_err(IntentError::Request)?
+            .error_for_status()
+            .map_err(IntentError::Http)?;
+
+        Ok(())
+    }
+
+    /// Whether the intent model has been pulled into Ollama; "deepseek-r1"
+    /// matches "deepseek-r1:latest".
+    pub async fn has_model(&self) -> Result<bool, IntentError> {
+        let tags: TagsResponse = self
+            .client
+            .get(self.tags_endpoint())
+            .send()
+            .await
+            .map_err(IntentError::Request)?
+            .error_for_status()
+            .map_err(IntentError::Http)?
+            .json()
+            .await
+            .map_err(IntentError::Response)?;
+        let latest = format!("{}:latest", self.model);
+        Ok(tags
+            .models
+            .iter()
+            .any(|model| model.name == self.model || model.name == latest))
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn tags_endpoint(&self) -> String {
+        if self.endpoint.ends_with("/api/chat") {
+            self.endpoint.replace("/api/chat", "/api/tags")
+        } else {
+            self.endpoint.clone()
+        }
+    }
+
+    /// Another Ollama API next to `/api/chat`; `None` for other backends.
+    pub fn api_endpoint(&self, name: &str) -> Option<String> {
+        let base = self.endpoint.strip_suffix("/api/chat")?;
+        Some(format!("{}/api/{}", base, name))
+    }
+}
+
+/// Ollama answers 404 for a model it doesn't have.
+fn check_status(
+    response: reqwest::Response,
+    model: &str,
+) -> Result<reqwest::Response, IntentError> {
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(IntentError::ModelNotFound(model.to_string()));
+    }
+    response.error_for_status().map_err(IntentError::Http)
+}
+
+/// A number of seconds goes to Ollama as a number ("-1" keeps the model
+/// loaded for good), anything else as a duration such as "30m"; empty
+/// leaves it to the server.
+fn keep_alive(value: &str) -> Option<serde_json::Value> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    Some(match value.parse::<i64>() {
+        Ok(seconds) => serde_json::json!(seconds),
+        Err(_) => serde_json::json!(value),
+    })
+}
+
+/// `deepseek.options` as Ollama's `options` object; `None` when none are set.
+fn model_options(options: &ModelOptions) -> Option<serde_json::Value> {
+    let mut fields = serde_json::to_value(&options.other)
+        .ok()
+        .and_then(|other| other.as_object().cloned())
+        .unwrap_or_default();
+    let named = [
+        ("temperature", options.temperature.map(serde_json::Value::from)),
+        ("top_p", options.top_p.map(serde_json::Value::from)),
+        ("num_ctx", options.num_ctx.map(serde_json::Value::from)),
+        ("seed", options.seed.map(serde_json::Value::from)),
+    ];
+    for (name, value) in named {
+        if let Some(value) = value {
+            fields.insert(name.to_string(), value);
+        }
+    }
+    (!fields.is_empty()).then_some(serde_json::Value::Object(fields))
+}
+
+fn parse_stream_line(line: &[u8]) -> Result<String, IntentError> {
+    let text = String::from_utf8_lossy(line);
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(String::new());
+    }
+    let chunk: ChatResponse =
+        serde_json::from_str(text).map_err(|err| IntentError::InvalidFormat {
+            raw: text.to_string(),
+            err,
+        })?;
+    Ok(chunk.message.map(|msg| msg.content).unwrap_or_default())
+}
+
+/// Pulls the `response` string out of a partially streamed intent JSON object
+/// and splits it into sentences. Nothing is emitted unless the action has
+/// already been seen as `answer`.
+#[derive(Default)]
+struct AnswerExtractor {
+    content: String,
+    cursor: usize,
+    state: ExtractState,
+    sentence: String,
+}
+
+#[derive(Default, PartialEq)]
+enum ExtractState {
+    #[default]
+    Seeking,
+    InString,
+    Done,
+}
+
+impl AnswerExtractor {
+    fn feed(&mut self, delta: &str, on_sentence: &mut dyn FnMut(&str)) {
+        self.content.push_str(delta);
+        loop {
+            match self.state {
+                ExtractState::Seeking => {
+                    if !self.seek_response() {
+                        return;
+                    }
+                }
+                ExtractState::InString => {
+                    self.read_string(on_sentence);
+                    return;
+                }
+                ExtractState::Done => return,
+            }
+        }
+    }
+
+    /// Returns true once the state changed and scanning should continue.
+    fn seek_response(&mut self) -> bool {
+        let start = match self.content.find("</think>") {
+            Some(end) => self.cursor.max(end + "</think>".len()),
+            None if self.content.trim_start().starts_with("<think>") => return false,
+            None => self.cursor,
+        };
+        let Some(offset) = self.content[start..].find("\"response\"") else {
+            return false;
+        };
+        let key_end = start + offset + "\"response\"".len();
+        let rest = self.content[key_end..].trim_start();
+        let Some(rest) = rest.strip_prefix(':') else {
+            return false;
+        };
+        let value = rest.trim_start();
+        let Some(first) = value.chars().next() else {
+            return false;
+        };
+        let before_key = &self.content[..start + offset];
+        let is_answer = before_key
+            .split("\"action\"")
+            .nth(1)
+            .map(|after| {
+                after
+                    .trim_start()
+                    .trim_start_matches(':')
+                    .trim_start()
+                    .to_lowercase()
+                    .starts_with("\"answer\"")
+            })
+            .unwrap_or(false);
+        if first != '"' || !is_answer {
+            self.state = ExtractState::Done;
+            return true;
+        }
+        self.cursor = self.content.len() - value.len() + 1;
+        self.state = ExtractState::InString;
+        true
+    }
+
+    fn read_string(&mut self, on_sentence: &mut dyn FnMut(&str)) {
+        let base = self.cursor;
+        let tail = self.content[base..].to_string();
+        let mut chars = tail.char_indices().peekable();
+        while let Some((idx, c)) = chars.next() {
+            let consumed = match c {
+                '"' => {
+                    self.cursor = base + idx + 1;
+                    self.state = ExtractState::Done;
+                    self.flush(on_sentence);
+                    return;
+                }
+                '\\' => {
+                    let Some(&(_, escaped)) = chars.peek() else {
+                        // Wait for the rest of the escape sequence.
+                        self.cursor = base + idx;
+                        return;
+                    };
+                    chars.next();
+                    match escaped {
+                        'n' | 't' | 'r' => self.sentence.push(' '),
+                        'u' => {
+                            let Some(hex) = tail.get(idx + 2..idx + 6) else {
+                                self.cursor = base + idx;
+                                return;
+                            };
+                            if let Some(ch) =
+                                u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+                            {
+                                self.sentence.push(ch);
+                            }
+                            for _ in 0..4 {
+                                chars.next();
+                            }
+                        }
+                        other => self.sentence.push(other),
+                    }
+                    None
+                }
+                other => Some(other),
+            };
+            if let Some(c) = consumed {
+                if c.is_whitespace() && self.sentence.ends_with(['.', '!', '?']) {
+                    self.flush(on_sentence);
+                } else {
+                    self.sentence.push(c);
+                }
+            }
+        }
+        self.cursor = self.content.len();
+    }
+
+    fn flush(&mut self, on_sentence: &mut dyn FnMut(&str)) {
+        let sentence = self.sentence.trim();
+        if !sentence.is_empty() {
+            on_sentence(sentence);
+        }
+        self.sentence.clear();
+    }
+
+    fn finish(mut self, on_sentence: &mut dyn FnMut(&str)) -> String {
+        if self.state == ExtractState::InString {
+            self.flush(on_sentence);
+        }
+        self.content.trim().to_string()
+    }
+}
+
+fn truncate_head(text: &str, limit: usize) -> String {
+    text.chars().take(limit).collect()
+}
+
+fn truncate_tail(text: &str, limit: usize) -> String {
+    let skip = text.chars().count().saturating_sub(limit);
+    text.chars().skip(skip).collect()
+}
+
+/// Joins the two ends of a truncated transcript.
+const HEAD_TAIL_SEPARATOR: &str = " ... ";
+
+fn truncate_head_tail(text: &str, limit: usize) -> String {
+    // The separator counts against the limit too; with no room left for
+    // both ends, keep the head.
+    let budget = limit.saturating_sub(HEAD_TAIL_SEPARATOR.len());
+    if budget < 2 {
+        return truncate_head(text, limit);
+    }
+    let head = budget / 2;
+    let tail = budget - head;
+    format!("{}{}{}", truncate_head(text, head), HEAD_TAIL_SEPARATOR, truncate_tail(text, tail))
+}
+
+/// The intent prompt, unless `deepseek.prompt_template` replaces it.
+const PROMPT: &str = "You interpret voice commands for a desktop assistant.\nUser said: \"{transcript}\"\nAvailable files: {files}\nSearchable folders (subset of files): {folders}\nAvailable apps: {apps}\nAvailable URLs: {urls}\nCustom actions: {actions}\nAssistants: {assistants}\nSmart home devices: {devices}\nLinux (WSL) commands: {wsl}\nGit repositories: {repos}\nAvailable system actions: {systems}\n{most_used}Rules:\n- action must be one of: open_file, open_app, open_url, system, answer, translate, ask, time, calendar, weather, remind, note, list, screenshot, describe_screen, read_text, close_app, kill_app, app_running, media, smart_home, wsl, dev, files, custom, unknown\n- use open_file/open_app/system only when the request matches an available key\n- to open a website, use action=open_url with target=the URL key if one matches, else the address (\"example.com\")\n- for a request a custom action handles, use action=custom with target=the action name and query=the free text it needs, such as the message to send\n- for questions, facts, calculations, or definitions, use action=answer and provide a direct response\n- for action=answer, set target to null\n- to open a file inside a searchable folder, use action=open_file with target=the folder key and query=the words naming the file\n- if the user names a line or page in a file, add \"line\" or \"page\" with that number\n- for the current time, date, or weekday, or the date of a holiday, use action=time with target=\"time\", \"date\", \"day\", or the holiday name\n- for the user's calendar, schedule, or meetings, use action=calendar with target=\"today\", \"tomorrow\", a weekday, or a YYYY-MM-DD date\n- for the weather or forecast where the user is, use action=weather with target=\"today\", \"tomorrow\", or a weekday\n- to set a reminder, use action=remind with target=when (\"at 3pm\", \"in 10 minutes\", \"tomorrow at 9am\") and query=what to be reminded of\n- to take a note, use action=note with query=the note\n- to hear saved notes or reminders, use action=list with target=\"notes\" or \"reminders\"\n- to save a screenshot, use action=screenshot; to hear what is on the screen, use action=describe_screen; to hear the selected text or the focused window read aloud, use action=read_text\n- to close a running program, use action=close_app; to force it closed, action=kill_app; to ask whether it is running, action=app_running; target=the app key if one matches, else the program's name; kill_app only takes an app key, or \"frozen\" for whatever has stopped responding\n- for music playback, use action=media with target=\"play\" and query=what to play, or target=\"pause\", \"resume\", \"next\", \"previous\", \"now_playing\" (what song is this), or \"like\" (save the song)\n- for lights, switches and other smart home devices, use action=smart_home with target=the device key and query=\"turn_on\", \"turn_off\", \"toggle\", \"lock\" or \"unlock\" for a lock, \"open\" or \"close\" for a cover such as a garage door, \"set_50\" for a brightness, temperature or position, or \"state\" to ask whether it is on or what it reads\n- to run one of the Linux (WSL) commands, use action=wsl with target=the command name and query=the free text it needs, if any\n- for the user's git repositories, use action=dev with target=\"branch\" (the current branch), \"status\" (changes and commits to push or pull), \"pull\", \"last_commit\" or \"failing_build\" (open the last failed CI run) and query=the repository name, or null if none is named\n- for the volume, use action=system with target=\"volume_up\" or \"volume_down\" for a step (\"a bit louder\"), \"volume_up_20\" or \"volume_down_20\" to change it by an amount, \"volume_set_50\" for a level (half is 50), or \"volume_query\" to hear the current level\n- to mute or unmute the microphone, use action=system with target=\"mic_mute\" or \"mic_unmute\"; to ask whether it is muted, \"mic_state\"\n- for the monitors, use action=system with target=\"screen_off\", \"display_extend\", \"display_duplicate\", \"display_internal\" (PC screen only), \"display_external\" (second screen only), \"rotate_screen\" for a quarter turn, or \"rotate_screen_0\" (or _90, _180, _270) for an orientation\n- for the radios, use action=system with target=\"bluetooth_on\", \"bluetooth_off\", \"wifi_on\", \"wifi_off\", \"airplane_mode_on\" or \"airplane_mode_off\"; to ask whether one is on, \"bluetooth_state\" or \"wifi_state\"\n- for the power plan, use action=system with target=\"power_plan_high_performance\", \"power_plan_balanced\" or \"power_plan_power_saver\"; for the battery level or time left, \"battery_status\"\n- to empty the recycle bin, use action=files with target=\"empty_recycle_bin\"; to delete the last screenshot, target=\"delete_last_screenshot\"; to make a folder on the desktop, target=\"new_folder\" and query=the folder's name\n- to say something in another language, use action=translate with target=the language in English and query=the words to translate\n- to put a question to one of the assistants by name (\"ask chatgpt ...\"), use action=ask with target=the assistant name and query=the question\n- if unsure, use action=unknown and target=null\n{examples}{learned}Return JSON only (no markdown, no code fences) with keys action, target, query, response, confidence (query is null unless opening from a searchable folder, translating, asking an assistant, saving a reminder or note, playing music, naming a new folder, running a custom action or WSL command, naming a git repository, or controlling a smart home device).";
+
+/// The worked examples the built-in prompt ends with; `{examples}` in a
+/// template.
+const EXAMPLES: &str = "Examples:\nInput: \"open my resume\" => {\"action\":\"open_file\",\"target\":\"resume\",\"response\":null,\"confidence\":0.9}\nInput: \"open the budget from July\" (with folder budgets) => {\"action\":\"open_file\",\"target\":\"budgets\",\"query\":\"july\",\"response\":null,\"confidence\":0.8}\nInput: \"open my todo at line 40\" => {\"action\":\"open_file\",\"target\":\"todo\",\"line\":40,\"response\":null,\"confidence\":0.8}\nInput: \"start chrome\" => {\"action\":\"open_app\",\"target\":\"chrome\",\"response\":null,\"confidence\":0.8}\nInput: \"take me to wikipedia\" => {\"action\":\"open_url\",\"target\":\"wikipedia.org\",\"response\":null,\"confidence\":0.8}\nInput: \"turn it down a bit\" => {\"action\":\"system\",\"target\":\"volume_down\",\"response\":null,\"confidence\":0.8}\nInput: \"set the volume to half\" => {\"action\":\"system\",\"target\":\"volume_set_50\",\"response\":null,\"confidence\":0.9}\nInput: \"what's the volume\" => {\"action\":\"system\",\"target\":\"volume_query\",\"response\":null,\"confidence\":0.9}\nInput: \"give me full power\" => {\"action\":\"system\",\"target\":\"power_plan_high_performance\",\"response\":null,\"confidence\":0.8}\nInput: \"how long will my battery last\" => {\"action\":\"system\",\"target\":\"battery_status\",\"response\":null,\"confidence\":0.9}\nInput: \"what is 2+3\" => {\"action\":\"answer\",\"target\":null,\"response\":\"5\",\"confidence\":0.9}\nInput: \"how do you say good morning in French\" => {\"action\":\"translate\",\"target\":\"French\",\"query\":\"good morning\",\"response\":null,\"confidence\":0.9}\nInput: \"am I busy on Friday\" => {\"action\":\"calendar\",\"target\":\"friday\",\"response\":null,\"confidence\":0.8}\nInput: \"do I need an umbrella tomorrow\" => {\"action\":\"weather\",\"target\":\"tomorrow\",\"response\":null,\"confidence\":0.8}\nInput: \"don't let me forget to call Bob at 3\" => {\"action\":\"remind\",\"target\":\"at 3\",\"query\":\"call Bob\",\"response\":null,\"confidence\":0.8}\nInput: \"tell the team I'm running late\" (with custom action send_teams_message) => {\"action\":\"custom\",\"target\":\"send_teams_message\",\"query\":\"I'm running late\",\"response\":null,\"confidence\":0.8}\nInput: \"how tall is Barack Obama\" => {\"action\":\"answer\",\"target\":null,\"response\":\"1.87 meters (6 ft 1.5 in)\",\"confidence\":0.8}\n";
+
+/// The intent prompt for `transcription`: the built-in one, or the
+/// `deepseek.prompt_template` file re-read for each command so edits apply
+/// at once.
+fn build_prompt(transcription: &str, config: &Config) -> String {
+    let ranking = Ranking::load(&config.usage);
+    let files = ranking.sorted("open_file", config.files.keys()).join(", ");
+    let folders = ranking.sorted("open_file", &config.folder_keys()).join(", ");
+    let apps = ranking.sorted("open_app", config.applications.keys()).join(", ");
+    let urls = ranking.sorted("open_url", config.urls.keys()).join(", ");
+    let top = config.usage.top;
+    let used: Vec<String> = [
+        ranking.top("open_file", config.files.keys(), top),
+        ranking.top("open_app", config.applications.keys(), top),
+        ranking.top("open_url", config.urls.keys(), top),
+    ]
+    .concat();
+    // Lists are most used first; the line names the favorites outright.
+    let most_used = if used.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "Most used (prefer these when the request is unclear): {}\n",
+            used.join(", ")
+        )
+    };
+    let actions = config
+        .action_summaries(process::focused_context(config))
+        .join(", ");
+    let mut assistants: Vec<&str> = config.assistants.keys().map(String::as_str).collect();
+    assistants.sort_unstable();
+    let assistants = assistants.join(", ");
+    let systems = config.system_actions().join(", ");
+    let mut devices: Vec<&str> = config
+        .homeassistant
+        .entities
+        .keys()
+        .map(String::as_str)
+        .collect();
+    devices.sort_unstable();
+    let devices = devices.join(", ");
+    let mut wsl: Vec<String> = config
+        .wsl
+        .commands
+        .iter()
+        .map(|(name, command)| match command.description.as_str() {
+            "" => name.clone(),
+            description => format!("{} ({})", name, description),
+        })
+        .collect();
+    wsl.sort_unstable();
+    let wsl = wsl.join(", ");
+    let mut repos: Vec<&str> = config.dev.repos.keys().map(String::as_str).collect();
+    repos.sort_unstable();
+    let repos = repos.join(", ");
+    // The user's own corrections of commands like this one, after the
+    // general examples so they take precedence.
+    let learned: String = corrections::relevant(&config.corrections, transcription)
+        .iter()
+        .map(|correction| {
+            format!("Input: {:?} => {}\n", correction.transcript, correction.intent)
+        })
+        .collect();
+    let learned = if learned.is_empty() {
+        learned
+    } else {
+        format!("Corrections from this user (follow these):\n{}", learned)
+    };
+    let template = config
+        .deepseek
+        .prompt_template
+        .as_ref()
+        .and_then(|path| match prompt_template::load(path) {
+            Ok(template) => Some(template),
+            Err(err) => {
+                eprintln!("Using the built-in intent prompt: {}", err);
+                None
+            }
+        });
+    prompt_template::fill(
+        template.as_deref().unwrap_or(PROMPT),
+        &[
+            ("transcript", transcription),
+            ("files", &files),
+            ("folders", &folders),
+            ("apps", &apps),
+            ("urls", &urls),
+            ("actions", &actions),
+            ("assistants", &assistants),
+            ("devices", &devices),
+            ("wsl", &wsl),
+            ("repos", &repos),
+            ("systems", &systems),
+            ("most_used", &most_used),
+            ("examples", EXAMPLES),
+            ("learned", &learned),
+        ],
+    )
+}
+
+/// Reasoning models such as deepseek-r1 think out loud in `<think>` blocks
+/// and may wrap the JSON in prose or code fences, so this takes the first
+/// object in the rest of the reply that has an `action`.
+fn parse_intent(raw: &str) -> Result<Intent, IntentError> {
+    let visible = answer::strip_think(raw);
+    let mut first_err = None;
+    for candidate in json_objects(&visible) {
+        match serde_json::from_str::<RawIntent>(candidate) {
+            Ok(parsed) if parsed.action.is_some() => return Ok(parsed.into()),
+            Ok(_) => {}
+            Err(err) => {
+                first_err.get_or_insert(err);
+            }
+        }
+    }
+    let err = match serde_json::from_str::<RawIntent>(visible.trim()) {
+        Err(err) => first_err.unwrap_or(err),
+        // Valid JSON, but not an intent object.
+        Ok(_) => serde::de::Error::missing_field("action"),
+    };
+    Err(IntentError::InvalidFormat {
+        raw: raw.to_string(),
+        err,
+    })
+}
+
+/// Every balanced `{...}` in `text`, in order of their opening brace.
+/// Braces inside JSON strings don't count.
+fn json_objects(text: &str) -> impl Iterator<Item = &str> {
+    text.match_indices('{').filter_map(move |(start, _)| {
+        let mut depth = 0usize;
+        let mut in_string = false;
+        let mut escaped = false;
+        for (offset, c) in text[start..].char_indices() {
+            if in_string {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match c {
+                '"' => in_string = true,
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(&text[start..start + offset + 1]);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    })
+}
+
+/// What to send as `format` for an intent request.
+fn output_format(format: OutputFormat) -> Option<serde_json::Value> {
+    match format {
+        OutputFormat::Off => None,
+        OutputFormat::Json => Some(serde_json::Value::from("json")),
+        OutputFormat::Schema => Some(serde_json::json!({
+            "type": "object",
+            "properties": {
+                "action": {
+                    "type": "string",
+                    "enum": [
+                        "open_file", "open_app", "open_url", "system", "answer", "translate",
+                        "ask", "time", "calendar", "weather", "remind", "note", "list",
+                        "screenshot", "describe_screen", "read_text", "close_app", "kill_app",
+                        "app_running", "media", "smart_home", "wsl", "dev", "files",
+                        "custom", "unknown"
+                    ]
+                },
+                "target": { "type": ["string", "null"] },
+                "query": { "type": ["string", "null"] },
+                "line": { "type": ["integer", "null"] },
+                "page": { "type": ["integer", "null"] },
+                "response": { "type": ["string", "null"] },
+                "confidence": { "type": "number" }
+            },
+            "required": ["action", "target", "response", "confidence"]
+        })),
+    }
+}
+
+fn validate_intent_target(
+    intent: &Intent,
+    config: &Config,
+) -> Result<(), IntentError> {
+    match intent {
+        Intent::OpenFile { target, .. } => {
+            if !config.files.contains_key(target) {
+                return Err(IntentError::UnknownTarget(target.to_string()));
+            }
+        }
+        Intent::OpenApp { target, .. } => {
+            if !config.applications.contains_key(target) {
+                return Err(IntentError::UnknownTarget(target.to_string()));
+            }
+        }
+        Intent::OpenUrl { target, .. } => {
+            if urls::resolve(target, config).is_none() {
+                return Err(IntentError::UnknownTarget(target.to_string()));
+            }
+        }
+        Intent::System { target, .. } => {
+            let name = executor::system_action_name(target);
+            if !config.system_actions().contains(&name) {
+                return Err(IntentError::UnknownTarget(target.to_string()));
+            }
+        }
+        Intent::Time { target, .. } => {
+            if !clock::is_query(target) {
+                return Err(IntentError::UnknownTarget(target.to_string()));
+            }
+        }
+        Intent::Calendar { target, .. } | Intent::Weather { target, .. } => {
+            if clock::resolve_day(target, clock::now().0).is_none() {
+                return Err(IntentError::UnknownTarget(target.to_string()));
+            }
+        }
+        Intent::Remind { when, .. } => {
+            if !reminders::is_when(when) {
+                return Err(IntentError::UnknownTarget(when.to_string()));
+            }
+        }
+        Intent::List { target, .. } => {
+            if !matches!(target.as_str(), "notes" | "reminders") {
+                return Err(IntentError::UnknownTarget(target.to_string()));
+            }
+        }
+        // The target is checked against running processes when the intent
+        // runs; here only the config switch is.
+        Intent::CloseApp { target, .. } | Intent::AppRunning { target, .. } => {
+            if !config.system.close_apps {
+                return Err(IntentError::UnknownTarget(target.to_string()));
+            }
+        }
+        // Only programs the user named in `[applications]` are force-ended.
+        Intent::KillApp { target, .. } => {
+            if !config.system.kill_apps
+                || (target != "frozen" && !config.applications.contains_key(target))
+            {
+                return Err(IntentError::UnknownTarget(target.to_string()));
+            }
+        }
+        Intent::Custom { target, .. } => {
+            if config
+                .action(target, process::focused_context(config))
+                .is_none()
+            {
+                return Err(IntentError::UnknownTarget(target.to_string()));
+            }
+        }
+        Intent::Media { target, .. } => {
+            if !media::TARGETS.contains(&target.as_str()) {
+                return Err(IntentError::UnknownTarget(target.to_string()));
+            }
+        }
+        Intent::SmartHome {
+            target, command, ..
+        } => {
+            if !config.homeassistant.entities.contains_key(target)
+                || !smart_home::is_command(command)
+            {
+                return Err(IntentError::UnknownTarget(target.to_string()));
+            }
+        }
+        Intent::Wsl { target, .. } => {
+            if !config.wsl.commands.contains_key(target) {
+                return Err(IntentError::UnknownTarget(target.to_string()));
+            }
+        }
+        Intent::Dev { target, repo, .. } => {
+            if config.dev.repos.is_empty() || !dev::COMMANDS.contains(&target.as_str()) {
+                return Err(IntentError::UnknownTarget(target.to_string()));
+            }
+            if let Some(repo) = repo.as_ref().filter(|repo| !config.dev.repos.contains_key(*repo)) {
+                return Err(IntentError::UnknownTarget(repo.to_string()));
+            }
+        }
+        // Only "run command: ..." starts one, never the model's own reading
+        // of a request.
+        Intent::Shell { .. } => {
+            return Err(IntentError::UnknownTarget("shell".to_string()));
+        }
+        Intent::Files { target, name, .. } => {
+            if !files::enabled(target, config) || (target == "new_folder" && name.is_none()) {
+                return Err(IntentError::UnknownTarget(target.to_string()));
+            }
+        }
+        Intent::Ask { assistant, .. } => {
+            if !config.assistants.contains_key(assistant) {
+                return Err(IntentError::UnknownTarget(assistant.to_string()));
+            }
+        }
+        Intent::Answer { .. }
+        | Intent::Translate { .. }
+        | Intent::Note { .. }
+        | Intent::Screenshot { .. }
+        | Intent::DescribeScreen { .. }
+        | Intent::ReadText { .. }
+        | Intent::Unknown { .. } => {}
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage<'a>],
+    stream: bool,
+    /// Ollama's structured output: `"json"` or a JSON schema.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'a serde_json::Value>,
+    /// How long Ollama keeps the model loaded after this request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<&'a serde_json::Value>,
+    /// Temperature, context size and the like.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<&'a serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ChatMessage<'a> {
+    role: &'a str,
+    content: String,
+    /// Base64 images for multimodal models.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    images: Vec<String>,
+}
+
+/// Standard base64 with padding, as Ollama expects for images.
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for index in 0..4 {
+            if index <= chunk.len() {
+                out.push(ALPHABET[(bits >> (18 - 6 * index) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    message: Option<ChatResponseMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponseMessage {
+    content: String,
+}
+
+/// An OpenAI-style chat completion.
+#[derive(Debug, Deserialize)]
+struct CompletionResponse {
+    #[serde(default)]
+    choices: Vec<CompletionChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompletionChoice {
+    message: ChatResponseMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbedResponse {
+    #[serde(default)]
+    embeddings: Vec<Vec<f32>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    models: Vec<TagsModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TagsModel {
+    name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntentAction {
+    OpenFile,
+    OpenApp,
+    OpenUrl,
+    System,
+    Answer,
+    Translate,
+    Ask,
+    Time,
+    Calendar,
+    Weather,
+    Remind,
+    Note,
+    List,
+    Screenshot,
+    DescribeScreen,
+    ReadText,
+    CloseApp,
+    KillApp,
+    AppRunning,
+    Media,
+    SmartHome,
+    Wsl,
+    Dev,
+    Shell,
+    Files,
+    Custom,
+    Unknown,
+}
+
+impl IntentAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::OpenFile => "open_file",
+            Self::OpenApp => "open_app",
+            Self::OpenUrl => "open_url",
+            Self::System => "system",
+            Self::Answer => "answer",
+            Self::Translate => "translate",
+            Self::Ask => "ask",
+            Self::Time => "time",
+            Self::Calendar => "calendar",
+            Self::Weather => "weather",
+            Self::Remind => "remind",
+            Self::Note => "note",
+            Self::List => "list",
+            Self::Screenshot => "screenshot",
+            Self::DescribeScreen => "describe_screen",
+            Self::ReadText => "read_text",
+            Self::CloseApp => "close_app",
+            Self::KillApp => "kill_app",
+            Self::AppRunning => "app_running",
+            Self::Media => "media",
+            Self::SmartHome => "smart_home",
+            Self::Wsl => "wsl",
+            Self::Dev => "dev",
+            Self::Shell => "shell",
+            Self::Files => "files",
+            Self::Custom => "custom",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileLocation {
+    Line(u32),
+    Page(u32),
+}
+
+#[derive(Debug, Clone)]
+pub enum Intent {
+    /// `query` names a file inside a folder mapping ("the budget from July");
+    /// `at` is a spot inside the file ("at line 40").
+    OpenFile {
+        target: String,
+        query: Option<String>,
+        at: Option<FileLocation>,
+        confidence: f32,
+    },
+    OpenApp { target: String, confidence: f32 },
+    /// `target` is a `[urls]` key or a web address ("example.com").
+    OpenUrl { target: String, confidence: f32 },
+    System { target: String, confidence: f32 },
+    Answer { response: String, confidence: f32 },
+    /// "How do you say `text` in `language`"; `translation` is filled in by
+    /// a second request with `deepseek.translate_prompt`.
+    Translate {
+        text: String,
+        language: String,
+        translation: Option<String>,
+        confidence: f32,
+    },
+    /// "Ask chatgpt `question`": `assistant` is an `[assistants]` key.
+    Ask {
+        assistant: String,
+        question: String,
+        confidence: f32,
+    },
+    /// `target` is "time", "date", "day", or a holiday ("christmas").
+    Time { target: String, confidence: f32 },
+    /// `target` is "today", "tomorrow", a weekday, or "2025-03-04".
+    Calendar { target: String, confidence: f32 },
+    /// `target` is "today", "tomorrow", or a weekday.
+    Weather { target: String, confidence: f32 },
+    /// "Remind me to `text` `when`"; `when` is a phrase such as "at 3pm",
+    /// "in 10 minutes", or "tomorrow at 9", read when the intent runs.
+    Remind {
+        text: String,
+        when: String,
+        confidence: f32,
+    },
+    Note { text: String, confidence: f32 },
+    /// `target` is "notes" or "reminders".
+    List { target: String, confidence: f32 },
+    Screenshot { confidence: f32 },
+    /// What `screenshot.vision_model` sees on the screen.
+    DescribeScreen { confidence: f32 },
+    /// The selected text, or the focused control's, read aloud.
+    ReadText { confidence: f32 },
+    /// `target` is an application key or words from a window title.
+    CloseApp { target: String, confidence: f32 },
+    /// Like `CloseApp`, or "frozen" for whatever has stopped responding.
+    KillApp { target: String, confidence: f32 },
+    AppRunning { target: String, confidence: f32 },
+    /// `target` is one of `media::TARGETS`; `query` is what to play.
+    Media {
+        target: String,
+        query: Option<String>,
+        confidence: f32,
+    },
+    /// `target` is an `[homeassistant.entities]` key; `command` is one of
+    /// `smart_home::COMMANDS` or "set_50".
+    SmartHome {
+        target: String,
+        command: String,
+        confidence: f32,
+    },
+    /// Runs the `[wsl.commands]` entry `target` with `text` in its template.
+    Wsl {
+        target: String,
+        text: String,
+        confidence: f32,
+    },
+    /// `target` is one of `dev::COMMANDS`; `repo` is a `[dev.repos]` key, or
+    /// none for the default repo.
+    Dev {
+        target: String,
+        repo: Option<String>,
+        confidence: f32,
+    },
+    /// "Run command: `request`"; `command` is the shell command the model
+    /// wrote for it, once it has.
+    Shell {
+        request: String,
+        command: Option<String>,
+        confidence: f32,
+    },
+    /// `target` is "empty_recycle_bin", "new_folder" or
+    /// "delete_last_screenshot"; `name` is the new folder's.
+    Files {
+        target: String,
+        name: Option<String>,
+        confidence: f32,
+    },
+    /// Runs the `[actions]` entry `target` with `text` in its template.
+    Custom {
+        target: String,
+        text: String,
+        confidence: f32,
+    },
+    Unknown { confidence: f32 },
+}
+
+impl Intent {
+    /// The mapping, program or action an intent names, if it names one.
+    pub fn target(&self) -> Option<&str> {
+        match self {
+            Self::OpenFile { target, .. }
+            | Self::OpenApp { target, .. }
+            | Self::OpenUrl { target, .. }
+            | Self::System { target, .. }
+            | Self::List { target, .. }
+            | Self::CloseApp { target, .. }
+            | Self::KillApp { target, .. }
+            | Self::AppRunning { target, .. }
+            | Self::Media { target, .. }
+            | Self::SmartHome { target, .. }
+            | Self::Wsl { target, .. }
+            | Self::Dev { target, .. }
+            | Self::Files { target, .. }
+            | Self::Custom { target, .. } => Some(target),
+            Self::Ask { assistant, .. } => Some(assistant),
+            Self::Answer { .. }
+            | Self::Translate { .. }
+            | Self::Time { .. }
+            | Self::Calendar { .. }
+            | Self::Weather { .. }
+            | Self::Remind { .. }
+            | Self::Note { .. }
+            | Self::Screenshot { .. }
+            | Self::DescribeScreen { .. }
+            | Self::ReadText { .. }
+            | Self::Shell { .. }
+            | Self::Unknown { .. } => None,
+        }
+    }
+
+    pub fn confidence(&self) -> f32 {
+        match self {
+            Self::OpenFile { confidence, .. }
+            | Self::OpenApp { confidence, .. }
+            | Self::OpenUrl { confidence, .. }
+            | Self::System { confidence, .. }
+            | Self::Answer { confidence, .. }
+            | Self::Translate { confidence, .. }
+            | Self::Ask { confidence, .. }
+            | Self::Time { confidence, .. }
+            | Self::Calendar { confidence, .. }
+            | Self::Weather { confidence, .. }
+            | Self::Remind { confidence, .. }
+            | Self::Note { confidence, .. }
+            | Self::List { confidence, .. }
+            | Self::Screenshot { confidence, .. }
+            | Self::DescribeScreen { confidence, .. }
+            | Self::ReadText { confidence }
+            | Self::CloseApp { confidence, .. }
+            | Self::KillApp { confidence, .. }
+            | Self::AppRunning { confidence, .. }
+            | Self::Media { confidence, .. }
+            | Self::SmartHome { confidence, .. }
+            | Self::Wsl { confidence, .. }
+            | Self::Dev { confidence, .. }
+            | Self::Shell { confidence, .. }
+            | Self::Files { confidence, .. }
+            | Self::Custom { confidence, .. }
+            | Self::Unknown { confidence, .. } => *confidence,
+        }
+    }
+
+    /// The intent in the same shape the model is asked to produce.
+    pub fn to_json(&self) -> serde_json::Value {
+        let (target, response) = match self {
+            Self::OpenFile { target, .. }
+            | Self::OpenApp { target, .. }
+            | Self::OpenUrl { target, .. }
+            | Self::System { target, .. }
+            | Self::Time { target, .. }
+            | Self::Calendar { target, .. }
+            | Self::Weather { target, .. }
+            | Self::List { target, .. }
+            | Self::CloseApp { target, .. }
+            | Self::KillApp { target, .. }
+            | Self::AppRunning { target, .. }
+            | Self::Media { target, .. }
+            | Self::SmartHome { target, .. }
+            | Self::Wsl { target, .. }
+            | Self::Dev { target, .. }
+            | Self::Files { target, .. }
+            | Self::Custom { target, .. } => (Some(target.as_str()), None),
+            Self::Remind { when, .. } => (Some(when.as_str()), None),
+            Self::Shell { command, .. } => (command.as_deref(), None),
+            Self::Note { .. }
+            | Self::Screenshot { .. }
+            | Self::DescribeScreen { .. }
+            | Self::ReadText { .. } => (None, None),
+            Self::Answer { response, .. } => (None, Some(response.as_str())),
+            Self::Translate {
+                language,
+                translation,
+                ..
+            } => (Some(language.as_str()), translation.as_deref()),
+            Self::Ask { assistant, .. } => (Some(assistant.as_str()), None),
+            Self::Unknown { .. } => (None, None),
+        };
+        let (query, at) = match self {
+            Self::OpenFile { query, at, .. } => (query.as_deref(), *at),
+            Self::Media { query, .. } => (query.as_deref(), None),
+            Self::SmartHome { command, .. } => (Some(command.as_str()), None),
+            Self::Dev { repo, .. } => (repo.as_deref(), None),
+            Self::Files { name, .. } => (name.as_deref(), None),
+            Self::Translate { text, .. }
+            | Self::Remind { text, .. }
+            | Self::Note { text, .. }
+            | Self::Wsl { text, .. }
+            | Self::Custom { text, .. } => (Some(text.as_str()), None),
+            Self::Ask { question, .. } => (Some(question.as_str()), None),
+            Self::Shell { request, .. } => (Some(request.as_str()), None),
+            _ => (None, None),
+        };
+        let line = match at {
+            Some(FileLocation::Line(line)) => Some(line),
+            _ => None,
+        };
+        let page = match at {
+            Some(FileLocation::Page(page)) => Some(page),
+            _ => None,
+        };
+        serde_json::json!({
+            "action": self.action().name(),
+            "target": target,
+            "query": query,
+            "line": line,
+            "page": page,
+            "response": response,
+            "confidence": self.confidence(),
+        })
+    }
+
+    /// Reads back what `to_json` wrote.
+    pub fn from_json(value: serde_json::Value) -> Result<Self, serde_json::Error> {
+        serde_json::from_value::<RawIntent>(value).map(Self::from)
+    }
+
+    pub fn action(&self) -> IntentAction {
+        match self {
+            Self::OpenFile { .. } => IntentAction::OpenFile,
+            Self::OpenApp { .. } => IntentAction::OpenApp,
+            Self::OpenUrl { .. } => IntentAction::OpenUrl,
+            Self::System { .. } => IntentAction::System,
+            Self::Answer { .. } => IntentAction::Answer,
+            Self::Translate { .. } => IntentAction::Translate,
+            Self::Ask { .. } => IntentAction::Ask,
+            Self::Time { .. } => IntentAction::Time,
+            Self::Calendar { .. } => IntentAction::Calendar,
+            Self::Weather { .. } => IntentAction::Weather,
+            Self::Remind { .. } => IntentAction::Remind,
+            Self::Note { .. } => IntentAction::Note,
+            Self::List { .. } => IntentAction::List,
+            Self::Screenshot { .. } => IntentAction::Screenshot,
+            Self::DescribeScreen { .. } => IntentAction::DescribeScreen,
+            Self::ReadText { .. } => IntentAction::ReadText,
+            Self::CloseApp { .. } => IntentAction::CloseApp,
+            Self::KillApp { .. } => IntentAction::KillApp,
+            Self::AppRunning { .. } => IntentAction::AppRunning,
+            Self::Media { .. } => IntentAction::Media,
+            Self::SmartHome { .. } => IntentAction::SmartHome,
+            Self::Wsl { .. } => IntentAction::Wsl,
+            Self::Dev { .. } => IntentAction::Dev,
+            Self::Shell { .. } => IntentAction::Shell,
+            Self::Files { .. } => IntentAction::Files,
+            Self::Custom { .. } => IntentAction::Custom,
+            Self::Unknown { .. } => IntentAction::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RawIntent {
+    action: Option<String>,
+    target: Option<String>,
+    #[serde(default)]
+    query: Option<String>,
+    #[serde(default)]
+    line: Option<serde_json::Value>,
+    #[serde(default)]
+    page: Option<serde_json::Value>,
+    response: Option<String>,
+    confidence: Option<serde_json::Value>,
+}
+
+impl From<RawIntent> for Intent {
+    fn from(raw: RawIntent) -> Self {
+        let action = match raw
+            .action
+            .as_deref()
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "open_file" => IntentAction::OpenFile,
+            "open_app" => IntentAction::OpenApp,
+            "open_url" => IntentAction::OpenUrl,
+            "system" => IntentAction::System,
+            "answer" => IntentAction::Answer,
+            "translate" => IntentAction::Translate,
+            "ask" => IntentAction::Ask,
+            "time" => IntentAction::Time,
+            "calendar" => IntentAction::Calendar,
+            "weather" => IntentAction::Weather,
+            "remind" => IntentAction::Remind,
+            "note" => IntentAction::Note,
+            "list" => IntentAction::List,
+            "screenshot" => IntentAction::Screenshot,
+            "describe_screen" => IntentAction::DescribeScreen,
+            "read_text" => IntentAction::ReadText,
+            "close_app" => IntentAction::CloseApp,
+            "kill_app" => IntentAction::KillApp,
+            "app_running" => IntentAction::AppRunning,
+            "media" => IntentAction::Media,
+            "smart_home" => IntentAction::SmartHome,
+            "wsl" => IntentAction::Wsl,
+            "dev" => IntentAction::Dev,
+            "shell" => IntentAction::Shell,
+            "files" => IntentAction::Files,
+            "custom" => IntentAction::Custom,
+            _ => IntentAction::Unknown,
+        };
+        let confidence = match raw.confidence {
+            Some(serde_json::Value::Number(num)) => num.as_f64().unwrap_or(0.0) as f32,
+            Some(serde_json::Value::String(s)) => match s.to_lowercase().as_str() {
+                "high" => 0.9,
+                "medium" => 0.6,
+                "low" => 0.3,
+                _ => 0.0,
+            },
+            Some(serde_json::Value::Bool(val)) => if val { 1.0 } else { 0.0 },
+            _ => 0.0,
+        };
+        match action {
+            IntentAction::OpenFile => raw
+                .target
+                .map(|target| Self::OpenFile {
+                    target,
+                    query: raw.query.filter(|query| !query.trim().is_empty()),
+                    at: positive_number(raw.line.as_ref())
+                        .map(FileLocation::Line)
+                        .or_else(|| positive_number(raw.page.as_ref()).map(FileLocation::Page)),
+                    confidence,
+                })
+                .unwrap_or(Self::Unknown { confidence }),
+            IntentAction::OpenApp => raw
+                .target
+                .map(|target| Self::OpenApp { target, confidence })
+                .unwrap_or(Self::Unknown { confidence }),
+            IntentAction::OpenUrl => raw
+                .target
+                .map(|target| Self::OpenUrl { target, confidence })
+                .unwrap_or(Self::Unknown { confidence }),
+            IntentAction::System => raw
+                .target
+                .map(|target| Self::System { target, confidence })
+                .unwrap_or(Self::Unknown { confidence }),
+            IntentAction::Answer => raw
+                .response
+                .map(|response| Self::Answer { response, confidence })
+                .unwrap_or(Self::Unknown { confidence }),
+            IntentAction::Time => raw
+                .target
+                .map(|target| Self::Time { target, confidence })
+                .unwrap_or(Self::Unknown { confidence }),
+            IntentAction::Calendar => Self::Calendar {
+                target: raw.target.unwrap_or_else(|| "today".to_string()),
+                confidence,
+            },
+            IntentAction::Weather => Self::Weather {
+                target: raw.target.unwrap_or_else(|| "today".to_string()),
+                confidence,
+            },
+            IntentAction::Remind => match raw.query.filter(|text| !text.trim().is_empty()) {
+                Some(text) => Self::Remind {
+                    text,
+                    when: raw.target.unwrap_or_default(),
+                    confidence,
+                },
+                None => Self::Unknown { confidence },
+            },
+            IntentAction::Note => match raw.query.or(raw.response) {
+                Some(text) if !text.trim().is_empty() => Self::Note { text, confidence },
+                _ => Self::Unknown { confidence },
+            },
+            IntentAction::List => raw
+                .target
+                .map(|target| Self::List { target, confidence })
+                .unwrap_or(Self::Unknown { confidence }),
+            IntentAction::Screenshot => Self::Screenshot { confidence },
+            IntentAction::DescribeScreen => Self::DescribeScreen { confidence },
+            IntentAction::ReadText => Self::ReadText { confidence },
+            IntentAction::CloseApp | IntentAction::KillApp | IntentAction::AppRunning => {
+                match raw.target.filter(|target| !target.trim().is_empty()) {
+                    Some(target) if action == IntentAction::CloseApp => {
+                        Self::CloseApp { target, confidence }
+                    }
+                    Some(target) if action == IntentAction::KillApp => {
+                        Self::KillApp { target, confidence }
+                    }
+                    Some(target) => Self::AppRunning { target, confidence },
+                    None => Self::Unknown { confidence },
+                }
+            }
+            IntentAction::Media => raw
+                .target
+                .map(|target| Self::Media {
+                    target,
+                    query: raw.query.filter(|query| !query.trim().is_empty()),
+                    confidence,
+                })
+                .unwrap_or(Self::Unknown { confidence }),
+            IntentAction::SmartHome => match (raw.target, raw.query) {
+                (Some(target), Some(command)) if !command.trim().is_empty() => Self::SmartHome {
+                    target,
+                    command: command.trim().to_lowercase(),
+                    confidence,
+                },
+                _ => Self::Unknown { confidence },
+            },
+            IntentAction::Files => raw
+                .target
+                .map(|target| Self::Files {
+                    target,
+                    name: raw.query.filter(|name| !name.trim().is_empty()),
+                    confidence,
+                })
+                .unwrap_or(Self::Unknown { confidence }),
+            IntentAction::Wsl => raw
+                .target
+                .map(|target| Self::Wsl {
+                    target,
+                    text: raw.query.unwrap_or_default(),
+                    confidence,
+                })
+                .unwrap_or(Self::Unknown { confidence }),
+            IntentAction::Dev => raw
+                .target
+                .map(|target| Self::Dev {
+                    target: target.trim().to_lowercase(),
+                    repo: raw.query.filter(|repo| !repo.trim().is_empty()),
+                    confidence,
+                })
+                .unwrap_or(Self::Unknown { confidence }),
+            IntentAction::Shell => match raw.query.filter(|request| !request.trim().is_empty()) {
+                Some(request) => Self::Shell {
+                    request,
+                    command: raw.target.filter(|command| !command.trim().is_empty()),
+                    confidence,
+                },
+                None => Self::Unknown { confidence },
+            },
+            IntentAction::Custom => raw
+                .target
+                .map(|target| Self::Custom {
+                    target,
+                    text: raw.query.or(raw.response).unwrap_or_default(),
+                    confidence,
+                })
+                .unwrap_or(Self::Unknown { confidence }),
+            IntentAction::Translate => match (raw.query, raw.target) {
+                (Some(text), Some(language))
+                    if !text.trim().is_empty() && !language.trim().is_empty() =>
+                {
+                    Self::Translate {
+                        text,
+                        language,
+                        // Some models translate right away; kept in case the
+                        // dedicated request fails.
+                        translation: raw.response.filter(|response| !response.trim().is_empty()),
+                        confidence,
+                    }
+                }
+                _ => Self::Unknown { confidence },
+            },
+            IntentAction::Ask => match (raw.target, raw.query) {
+                (Some(assistant), Some(question)) if !question.trim().is_empty() => Self::Ask {
+                    assistant,
+                    question,
+                    confidence,
+                },
+                _ => Self::Unknown { confidence },
+            },
+            IntentAction::Unknown => Self::Unknown { confidence },
+        }
+    }
+}
+
+/// Models return numbers both as JSON numbers and as strings ("40").
+fn positive_number(value: Option<&serde_json::Value>) -> Option<u32> {
+    let number = match value? {
+        serde_json::Value::Number(num) => num.as_u64()?,
+        serde_json::Value::String(text) => text.trim().parse().ok()?,
+        _ => return None,
+    };
+    u32::try_from(number).ok().filter(|number| *number > 0)
+}
+
+#[derive(Debug)]
+pub enum IntentError {
+    Request(reqwest::Error),
+    Http(reqwest::Error),
+    Response(reqwest::Error),
+    InvalidFormat { raw: String, err: serde_json::Error },
+    UnknownTarget(String),
+    /// The service doesn't have this model.
+    ModelNotFound(String),
+}
+
+impl IntentError {
+    /// True when the endpoint could not be reached or timed out, as opposed to
+    /// answering with something unusable.
+    pub fn is_unavailable(&self) -> bool {
+        match self {
+            Self::Request(err) | Self::Response(err) => {
+                err.is_connect() || err.is_timeout() || err.is_request()
+            }
+            Self::Http(err) => err.status().is_some_and(|status| status.is_server_error()),
+            Self::ModelNotFound(_) => true,
+            Self::InvalidFormat { .. } | Self::UnknownTarget(_) => false,
+        }
+    }
+
+    /// Worth trying again: the connection failed or timed out, or the
+    /// server had a problem or is busy.
+    fn is_transient(&self) -> bool {
+        match self {
+            Self::Request(err) => err.is_connect() || err.is_timeout() || err.is_request(),
+            Self::Http(err) => err.status().is_some_and(|status| {
+                status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }),
+            _ => false,
+        }
+    }
+
+    /// The service is up but slow to answer, usually because it is still
+    /// loading the model; the same command should work in a moment.
+    pub fn is_loading(&self) -> bool {
+        match self {
+            Self::Request(err) | Self::Response(err) => err.is_timeout(),
+            Self::Http(err) => err.status() == Some(reqwest::StatusCode::SERVICE_UNAVAILABLE),
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for IntentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Request(err) => write!(f, "request failed: {}", err),
+            Self::Http(err) => write!(f, "HTTP error: {}", err),
+            Self::Response(err) => write!(f, "failed parsing response: {}", err),
+            Self::InvalidFormat { raw, err } => {
+                write!(f, "invalid intent payload '{}': {}", raw, err)
+            }
+            Self::UnknownTarget(target) => {
+                write!(f, "unknown target '{}'", target)
+            }
+            Self::ModelNotFound(model) => {
+                write!(f, "model '{}' not found (ollama pull {0})", model)
+            }
+        }
+    }
+}
+
+impl std::error::Error for IntentError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Request(err) | Self::Http(err) | Self::Response(err) => Some(err),
+            Self::InvalidFormat { err, .. } => Some(err),
+            Self::UnknownTarget(_) | Self::ModelNotFound(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Replies captured from deepseek-r1 through Ollama.
+
+    const R1_THINK_THEN_JSON: &str = "<think>\nOkay, the user said \"open my resume\". Looking at the available files, there's a key called resume. So the action is open_file with target resume.\n</think>\n\n{\"action\":\"open_file\",\"target\":\"resume\",\"response\":null,\"confidence\":0.95}";
+
+    const R1_LOST_OPENING_TAG: &str = "Alright, \"mute\" maps to the system action mute. I should return JSON only.\n</think>\n\n```json\n{\n  \"action\": \"system\",\n  \"target\": \"mute\",\n  \"response\": null,\n  \"confidence\": 0.9\n}\n```";
+
+    const R1_BRACES_IN_REASONING: &str = "<think>\nThe prompt gives me {files} and {apps}. Chrome is in apps, so {\"action\": \"open_app\"} fits. Let me write the full object.\n</think>\nHere is the intent:\n{\"action\":\"open_app\",\"target\":\"chrome\",\"response\":null,\"confidence\":0.85}\nLet me know if you need anything else.";
+
+    const R1_ANSWER_WITH_BRACES: &str = "<think>\nA math question, so action=answer.\n</think>\n{\"action\":\"answer\",\"target\":null,\"response\":\"In set notation that is {1, 2, 3}, and a \\\"set\\\" has 3 elements.\",\"confidence\":0.8}";
+
+    const R1_UNCLOSED_THINK: &str = "<think>\nThe user wants to open their budget. {\"action\": \"open_file\"";
+
+    #[test]
+    fn strips_think_block() {
+        let intent = parse_intent(R1_THINK_THEN_JSON).unwrap();
+        assert!(matches!(intent, Intent::OpenFile { ref target, .. } if target == "resume"));
+    }
+
+    #[test]
+    fn strips_reasoning_without_opening_tag_and_fences() {
+        let intent = parse_intent(R1_LOST_OPENING_TAG).unwrap();
+        assert!(matches!(intent, Intent::System { ref target, .. } if target == "mute"));
+    }
+
+    #[test]
+    fn takes_first_json_object_from_prose() {
+        let intent = parse_intent(R1_BRACES_IN_REASONING).unwrap();
+        assert!(matches!(intent, Intent::OpenApp { ref target, .. } if target == "chrome"));
+    }
+
+    #[test]
+    fn ignores_braces_inside_strings() {
+        match parse_intent(R1_ANSWER_WITH_BRACES).unwrap() {
+            Intent::Answer { response, .. } => {
+                assert_eq!(response, "In set notation that is {1, 2, 3}, and a \"set\" has 3 elements.")
+            }
+            other => panic!("expected an answer, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unfinished_reasoning_is_invalid() {
+        assert!(matches!(
+            parse_intent(R1_UNCLOSED_THINK),
+            Err(IntentError::InvalidFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn object_without_action_is_invalid() {
+        assert!(matches!(
+            parse_intent("{\"target\":\"resume\"}"),
+            Err(IntentError::InvalidFormat { .. })
+        ));
+    }
+
+    #[test]
+    fn head_tail_truncation_stays_within_the_limit() {
+        let long = "open the quarterly budget report from the shared drive please ".repeat(20);
+        for limit in [0, 1, 5, 6, 7, 40, 201] {
+            let truncated = truncate_head_tail(&long, limit);
+            assert!(truncated.chars().count() <= limit, "{} chars over {}", truncated.len(), limit);
+        }
+        let truncated = truncate_head_tail(&long, 40);
+        assert!(truncated.starts_with("open the quarter"));
+        assert!(truncated.ends_with("drive please "));
+    }
+}
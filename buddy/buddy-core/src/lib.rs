@@ -0,0 +1,64 @@
+//! Buddy's voice-command pipeline as a library, for embedding it in other
+//! apps (GUIs, tests, bots). `Pipeline` runs speech through to an action in
+//! a few calls. Its stages are the traits in `stages`, so each can be swapped
+//! or mocked; the pieces behind the built-in ones (`AudioCapturer`,
+//! `Transcriber`, `IntentClient`, `CommandExecutor`) can also be used on
+//! their own. The `buddy` binary adds the hotkey loop, spoken feedback, and
+//! control channels on top.
+
+mod answer;
+mod assistants;
+pub mod audio;
+pub mod audit;
+pub mod barge_in;
+mod calculator;
+mod calendar;
+mod clock;
+mod cloud_speech;
+pub mod config;
+pub mod corrections;
+mod dev;
+pub mod executor;
+pub mod export;
+pub mod feedback;
+mod files;
+mod gpu;
+pub mod hooks;
+pub mod hotkey;
+pub mod intent;
+pub mod longform;
+pub mod macros;
+mod media;
+pub mod metrics;
+pub mod notifications;
+mod numbers;
+mod phrases;
+pub mod pipeline;
+pub mod process;
+pub mod prompt_template;
+pub mod rag;
+pub mod reminders;
+pub mod rules;
+mod screen;
+mod smart_home;
+mod search;
+pub mod session;
+mod shell;
+pub mod stages;
+pub mod transcription;
+mod ui_automation;
+mod urls;
+pub mod usage;
+mod vocabulary;
+pub mod voice;
+mod weather;
+pub mod windows_api;
+mod windows_speech;
+mod wsl;
+
+pub use audio::AudioCapturer;
+pub use config::Config;
+pub use executor::{CommandExecutor, ExecutionResult};
+pub use intent::{Intent, IntentClient};
+pub use pipeline::{Outcome, Pipeline, PipelineError};
+pub use transcription::Transcriber;
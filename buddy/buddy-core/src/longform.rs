@@ -0,0 +1,230 @@
+use crate::{
+    audio::{AudioCapturer, AudioError},
+    config::{AudioSource, Config, TranscriptFormat},
+    transcription::{Segment, Transcriber},
+};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// A transcription running in the background ("start transcribing"): one
+/// thread records chunks from `audio.source`, another transcribes them and
+/// appends the timed segments to the transcript as they finish.
+pub struct Meeting {
+    path: PathBuf,
+    stop: Arc<AtomicBool>,
+    recorder: JoinHandle<Result<(), AudioError>>,
+    writer: JoinHandle<()>,
+}
+
+impl Meeting {
+    /// Starts transcribing into `output`, or a new file in `meeting.dir`. The
+    /// format follows the file extension (`.srt` or `.md`), then
+    /// `meeting.format`. `echo` prints each line as it is written.
+    pub fn start(
+        config: &Config,
+        transcriber: Arc<Transcriber>,
+        output: Option<PathBuf>,
+        echo: bool,
+        debug: bool,
+    ) -> Result<Self, String> {
+        let settings = &config.meeting;
+        let path = match output {
+            Some(path) => path,
+            None => {
+                fs::create_dir_all(&settings.dir)
+                    .map_err(|err| format!("{}: {}", settings.dir.display(), err))?;
+                let started = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|elapsed| elapsed.as_secs())
+                    .unwrap_or_default();
+                settings
+                    .dir
+                    .join(format!("meeting-{}.{}", started, settings.format.extension()))
+            }
+        };
+        let format = match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("srt") => TranscriptFormat::Srt,
+            Some(ext) if ext.eq_ignore_ascii_case("md") => TranscriptFormat::Markdown,
+            _ => settings.format,
+        };
+        let source = config.audio.source;
+        let capturer = AudioCapturer::for_source(&config.audio, source, debug)
+            .map_err(|err| format!("cannot record the {}: {}", source_name(source), err))?;
+        let mut transcript = TranscriptWriter::open(&path, format, echo)
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+        let recorder = {
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                let recorded = capturer.record_chunks(&stop, &tx);
+                if let Err(err) = &recorded {
+                    eprintln!("Transcription recording stopped: {}", err);
+                }
+                recorded
+            })
+        };
+        // Chunks are transcribed while the next one records; the channel
+        // holds any backlog if Whisper falls behind.
+        let speaker_turns = settings.speaker_turns;
+        let writer = {
+            let path = path.clone();
+            thread::spawn(move || {
+                let mut recorded = 0;
+                for chunk in rx {
+                    let offset = Duration::from_secs_f64(recorded as f64 / 16_000.0);
+                    recorded += chunk.len();
+                    let segments = match transcriber.transcribe_segments(&chunk, speaker_turns) {
+                        Ok(segments) => segments,
+                        Err(err) => {
+                            eprintln!("Transcription failed at {}: {}", clock(offset), err);
+                            continue;
+                        }
+                    };
+                    if let Err(err) = transcript.append(offset, &segments) {
+                        eprintln!("Cannot write {}: {}", path.display(), err);
+                    }
+                }
+            })
+        };
+        Ok(Self {
+            path,
+            stop,
+            recorder,
+            writer,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Stops recording and waits until the last chunk is written, which can
+    /// take a few seconds if Whisper is behind.
+    pub fn stop(self) -> PathBuf {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.recorder.join();
+        let _ = self.writer.join();
+        self.path
+    }
+}
+
+/// `--transcribe [file]`: a meeting transcription in the foreground, stopped
+/// with Enter.
+pub fn run(config: &Config, output: Option<PathBuf>, debug: bool, suppress_native_logs: bool) {
+    let transcriber =
+        match Transcriber::new(&config.transcription, None, debug, suppress_native_logs) {
+            Ok(transcriber) => Arc::new(transcriber),
+            Err(err) => {
+                eprintln!("Whisper is unavailable: {}", err);
+                return;
+            }
+        };
+    let meeting = match Meeting::start(config, transcriber, output, true, debug) {
+        Ok(meeting) => meeting,
+        Err(err) => {
+            eprintln!("Cannot start transcribing: {}", err);
+            return;
+        }
+    };
+    println!(
+        "Transcribing the {} to {}. Press Enter to stop.",
+        source_name(config.audio.source),
+        meeting.path().display()
+    );
+    let _ = io::stdin().lock().lines().next();
+    println!("Finishing the last chunk...");
+    println!("Transcript saved to {}", meeting.stop().display());
+}
+
+/// Appends timed segments as Markdown paragraphs or SRT cues.
+struct TranscriptWriter {
+    file: File,
+    format: TranscriptFormat,
+    echo: bool,
+    /// SRT cues numbered so far.
+    cues: usize,
+    /// The last segment ended with a speaker change.
+    turn: bool,
+}
+
+impl TranscriptWriter {
+    /// Appends to `path`; a new Markdown file gets a heading first, and SRT
+    /// numbering carries on from any cues already there.
+    fn open(path: &Path, format: TranscriptFormat, echo: bool) -> io::Result<Self> {
+        let existing = fs::read_to_string(path).unwrap_or_default();
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if format == TranscriptFormat::Markdown && existing.is_empty() {
+            writeln!(file, "# Transcript\n")?;
+        }
+        Ok(Self {
+            file,
+            format,
+            echo,
+            cues: existing.lines().filter(|line| line.contains(" --> ")).count(),
+            turn: false,
+        })
+    }
+
+    /// Writes `segments`, timed from `offset` into the recording.
+    fn append(&mut self, offset: Duration, segments: &[Segment]) -> io::Result<()> {
+        for segment in segments {
+            let start = offset + segment.start;
+            if self.echo {
+                println!("[{}] {}", clock(start), segment.text);
+            }
+            match self.format {
+                TranscriptFormat::Markdown => {
+                    if self.turn {
+                        writeln!(self.file, "---\n")?;
+                    }
+                    writeln!(self.file, "**{}** {}\n", clock(start), segment.text)?;
+                }
+                TranscriptFormat::Srt => {
+                    self.cues += 1;
+                    // A leading dash is the subtitle convention for a new speaker.
+                    let dash = if self.turn { "- " } else { "" };
+                    writeln!(
+                        self.file,
+                        "{}\n{} --> {}\n{}{}\n",
+                        self.cues,
+                        srt_time(start),
+                        srt_time(offset + segment.end),
+                        dash,
+                        segment.text
+                    )?;
+                }
+            }
+            self.turn = segment.speaker_turn;
+        }
+        self.file.flush()
+    }
+}
+
+fn source_name(source: AudioSource) -> &'static str {
+    match source {
+        AudioSource::Microphone => "microphone",
+        AudioSource::Loopback => "system audio",
+    }
+}
+
+/// "01:02:03" from the start of the recording.
+fn clock(at: Duration) -> String {
+    let secs = at.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// SRT's "01:02:03,456".
+fn srt_time(at: Duration) -> String {
+    format!("{},{:03}", clock(at), at.subsec_millis())
+}
@@ -0,0 +1,142 @@
+//! Voice macros: "start macro work morning" records the commands run until
+//! "end macro", and "run work morning" replays them. Macros are kept as JSON
+//! at `macros.path`, one list of steps per name.
+
+use crate::{clock, config::MacrosConfig, intent::Intent};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Keeps two commands from interleaving a read and a write of the store.
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Store {
+    /// By name, as said ("work morning").
+    #[serde(default)]
+    macros: BTreeMap<String, Vec<Step>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Step {
+    /// In the shape the model answers with; see `Intent::from_json`.
+    pub intent: serde_json::Value,
+    /// Pause before this step, in milliseconds.
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+#[derive(Debug)]
+pub enum MacrosError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+}
+
+impl std::fmt::Display for MacrosError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "failed to access {}: {}", path.display(), err),
+            Self::Parse(path, err) => write!(f, "failed to read {}: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for MacrosError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, err) => Some(err),
+            Self::Parse(_, err) => Some(err),
+        }
+    }
+}
+
+/// Collects the intents the executor runs while a macro is being recorded.
+#[derive(Debug)]
+pub struct Recorder {
+    name: String,
+    step_delay_ms: u64,
+    steps: Mutex<Vec<Step>>,
+}
+
+impl Recorder {
+    pub fn new(config: &MacrosConfig, name: &str) -> Self {
+        Self {
+            name: macro_name(name),
+            step_delay_ms: config.step_delay_ms,
+            steps: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Adds `intent` as the next step. Answers would only repeat the words
+    /// read out, so they and unknown intents are skipped.
+    pub fn record(&self, intent: &Intent) {
+        if matches!(intent, Intent::Answer { .. } | Intent::Unknown { .. }) {
+            return;
+        }
+        let mut meant = intent.to_json();
+        // Forecasts, translations and descriptions are looked up again.
+        meant["response"] = serde_json::Value::Null;
+        meant["confidence"] = serde_json::json!(1.0);
+        if let Some(fields) = meant.as_object_mut() {
+            fields.retain(|key, value| !value.is_null() || key == "target");
+        }
+        let mut steps = self.steps.lock().unwrap_or_else(|err| err.into_inner());
+        let delay_ms = if steps.is_empty() { 0 } else { self.step_delay_ms };
+        steps.push(Step {
+            intent: meant,
+            delay_ms,
+        });
+    }
+
+    /// Saves the steps recorded under the macro's name, replacing any macro
+    /// already called that, and returns how many there were. Nothing is saved
+    /// when no steps were recorded.
+    pub fn save(self, config: &MacrosConfig) -> Result<usize, MacrosError> {
+        let steps = self.steps.into_inner().unwrap_or_else(|err| err.into_inner());
+        if steps.is_empty() {
+            return Ok(0);
+        }
+        let count = steps.len();
+        let _guard = STORE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let path = &config.path;
+        let mut store = read(path)?;
+        store.macros.insert(self.name, steps);
+        let text = serde_json::to_string_pretty(&store)
+            .map_err(|err| MacrosError::Parse(path.to_path_buf(), err))?;
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|err| MacrosError::Io(parent.to_path_buf(), err))?;
+        }
+        fs::write(path, text).map_err(|err| MacrosError::Io(path.to_path_buf(), err))?;
+        Ok(count)
+    }
+}
+
+/// The steps of the macro called `name`, if there is one.
+pub fn load(config: &MacrosConfig, name: &str) -> Result<Option<Vec<Step>>, MacrosError> {
+    let _guard = STORE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut store = read(&config.path)?;
+    Ok(store.macros.remove(&macro_name(name)))
+}
+
+/// "Work  Morning." and "work_morning" both name the "work morning" macro.
+fn macro_name(name: &str) -> String {
+    clock::words(&name.replace('_', " ")).join(" ")
+}
+
+fn read(path: &Path) -> Result<Store, MacrosError> {
+    match fs::read_to_string(path) {
+        Ok(text) => {
+            serde_json::from_str(&text).map_err(|err| MacrosError::Parse(path.to_path_buf(), err))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Store::default()),
+        Err(err) => Err(MacrosError::Io(path.to_path_buf(), err)),
+    }
+}
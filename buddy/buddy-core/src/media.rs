@@ -0,0 +1,462 @@
+use crate::{
+    clock,
+    config::{Config, SpotifyConfig},
+    executor,
+    intent::Intent,
+    rules::EXACT_CONFIDENCE,
+};
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+
+#[cfg(not(target_os = "windows"))]
+use std::process::Command;
+
+const SPOTIFY_API: &str = "https://api.spotify.com/v1";
+const SPOTIFY_TOKEN_URL: &str = "https://accounts.spotify.com/api/token";
+
+/// Targets of the media action.
+pub const TARGETS: &[&str] = &[
+    "play",
+    "pause",
+    "resume",
+    "next",
+    "previous",
+    "now_playing",
+    "like",
+];
+
+#[derive(Debug)]
+pub enum MediaError {
+    Io(std::io::Error),
+    #[cfg(target_os = "windows")]
+    Windows(windows::core::Error),
+    /// Nothing is playing, or no player is open.
+    NoPlayer,
+    /// Playing a request and liking songs go through Spotify.
+    NeedsSpotify,
+    /// Spotify has no open device to play on.
+    NoDevice,
+    NotFound(String),
+    Request(reqwest::Error),
+}
+
+impl std::fmt::Display for MediaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "io error: {}", err),
+            #[cfg(target_os = "windows")]
+            Self::Windows(err) => write!(f, "media session error: {}", err),
+            Self::NoPlayer => write!(f, "no media player is open"),
+            Self::NeedsSpotify => write!(f, "[media.spotify] is not configured"),
+            Self::NoDevice => write!(f, "Spotify is not open on any device"),
+            Self::NotFound(query) => write!(f, "nothing on Spotify matches '{}'", query),
+            Self::Request(err) => write!(f, "Spotify request failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for MediaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            #[cfg(target_os = "windows")]
+            Self::Windows(err) => Some(err),
+            Self::Request(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for MediaError {
+    fn from(err: reqwest::Error) -> Self {
+        Self::Request(err)
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl From<windows::core::Error> for MediaError {
+    fn from(err: windows::core::Error) -> Self {
+        Self::Windows(err)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Track {
+    pub title: String,
+    pub artist: String,
+}
+
+impl Track {
+    pub fn spoken(&self) -> String {
+        if self.artist.is_empty() {
+            self.title.clone()
+        } else {
+            format!("{} by {}", self.title, self.artist)
+        }
+    }
+}
+
+/// Recognizes playback commands: "play some jazz", "pause the music",
+/// "next song", "what song is this", "like this song".
+pub fn recognize(transcript: &str) -> Option<Intent> {
+    let words = clock::words(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let words = words.strip_suffix(&["please"]).unwrap_or(&words);
+    let target = match words {
+        ["pause" | "stop", "the" | "my", "music" | "song" | "playback"]
+        | ["pause", "music" | "playback"]
+        | ["pause"] => "pause",
+        ["resume" | "unpause"] | ["resume" | "unpause" | "play", "the" | "my", "music"] | ["play"] => {
+            "resume"
+        }
+        ["next" | "skip", "song" | "track"] | ["skip", "this", "song" | "track"] | ["skip"] => "next",
+        ["previous" | "last", "song" | "track"] | ["go", "back", "a", "song" | "track"] => {
+            "previous"
+        }
+        ["what" | "whats", "song", "is", "this" | "playing"]
+        | ["what" | "whats", "playing"]
+        | ["what", "is", "playing"]
+        | ["who", "sings", "this"]
+        | ["who", "sings", "this", "song"] => "now_playing",
+        ["like" | "save", "this", "song" | "track"] | ["i", "like", "this", "song" | "track"] => {
+            "like"
+        }
+        ["play", ..] => {
+            return Some(Intent::Media {
+                target: "play".to_string(),
+                query: Some(play_query(transcript)?),
+                confidence: EXACT_CONFIDENCE,
+            })
+        }
+        _ => return None,
+    };
+    Some(Intent::Media {
+        target: target.to_string(),
+        query: None,
+        confidence: EXACT_CONFIDENCE,
+    })
+}
+
+/// "Play some jazz on Spotify." asks for "some jazz"; the casing is kept for
+/// the search.
+fn play_query(transcript: &str) -> Option<String> {
+    let text = transcript.trim().trim_end_matches(['.', '!', '?']);
+    let query = text.get(4..)?.trim_start_matches(|c: char| !c.is_alphanumeric());
+    let lower = query.to_lowercase();
+    let query = match lower.strip_suffix(" on spotify") {
+        Some(rest) => &query[..rest.len()],
+        None => query,
+    };
+    (!query.is_empty()).then(|| query.to_string())
+}
+
+/// What a media command does, e.g. "play some jazz" or "skip this track".
+pub fn describe(target: &str, query: Option<&str>) -> String {
+    match (target, query) {
+        ("play", Some(query)) => format!("play {}", query),
+        ("pause", _) => "pause the music".to_string(),
+        ("resume" | "play", _) => "resume the music".to_string(),
+        ("next", _) => "skip this track".to_string(),
+        ("previous", _) => "go back a track".to_string(),
+        ("now_playing", _) => "say what's playing".to_string(),
+        ("like", _) => "save this song".to_string(),
+        (other, _) => other.to_string(),
+    }
+}
+
+/// Runs a media command and returns what to say about it. Transport
+/// controls and "now playing" use the system's media session, falling back
+/// to Spotify when no local player answers (music playing on a phone, say).
+pub fn run(target: &str, query: Option<&str>, config: &Config) -> Result<String, MediaError> {
+    let spotify = config.media.spotify.as_ref();
+    match (target, query) {
+        ("play", Some(query)) => {
+            let spotify = spotify.ok_or(MediaError::NeedsSpotify)?;
+            executor::block_on(play(spotify, query)).map(|name| format!("Playing {}", name))
+        }
+        ("like", _) => {
+            let spotify = spotify.ok_or(MediaError::NeedsSpotify)?;
+            executor::block_on(like(spotify)).map(|track| format!("Saved {}", track.spoken()))
+        }
+        ("now_playing", _) => {
+            let track = match (now_playing(), spotify) {
+                (Err(_), Some(spotify)) => executor::block_on(spotify_now_playing(spotify)),
+                (result, _) => result,
+            }?;
+            Ok(track.spoken())
+        }
+        (command, _) => {
+            let command = if command == "play" { "resume" } else { command };
+            match (transport(command), spotify) {
+                (Err(_), Some(spotify)) => executor::block_on(spotify_transport(spotify, command)),
+                (result, _) => result,
+            }?;
+            Ok(match command {
+                "pause" => "Paused",
+                "resume" => "Playing",
+                "next" => "Skipped",
+                _ => "Went back a track",
+            }
+            .to_string())
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn session(
+) -> Result<windows::Media::Control::GlobalSystemMediaTransportControlsSession, MediaError> {
+    use windows::Media::Control::GlobalSystemMediaTransportControlsSessionManager;
+
+    let manager = GlobalSystemMediaTransportControlsSessionManager::RequestAsync()?.get()?;
+    manager.GetCurrentSession().map_err(|_| MediaError::NoPlayer)
+}
+
+/// The track in the current media session.
+#[cfg(target_os = "windows")]
+fn now_playing() -> Result<Track, MediaError> {
+    let properties = session()?.TryGetMediaPropertiesAsync()?.get()?;
+    let title = properties.Title()?.to_string();
+    if title.is_empty() {
+        return Err(MediaError::NoPlayer);
+    }
+    Ok(Track {
+        title,
+        artist: properties.Artist()?.to_string(),
+    })
+}
+
+/// The track MPRIS players report, through `playerctl`.
+#[cfg(not(target_os = "windows"))]
+fn now_playing() -> Result<Track, MediaError> {
+    let metadata = playerctl(&["metadata", "--format", "{{title}}\t{{artist}}"])?;
+    let (title, artist) = metadata.trim().split_once('\t').unwrap_or((metadata.trim(), ""));
+    if title.is_empty() {
+        return Err(MediaError::NoPlayer);
+    }
+    Ok(Track {
+        title: title.to_string(),
+        artist: artist.to_string(),
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn transport(command: &str) -> Result<(), MediaError> {
+    let session = session()?;
+    let request = match command {
+        "pause" => session.TryPauseAsync()?,
+        "resume" => session.TryPlayAsync()?,
+        "next" => session.TrySkipNextAsync()?,
+        _ => session.TrySkipPreviousAsync()?,
+    };
+    if request.get()? {
+        Ok(())
+    } else {
+        Err(MediaError::NoPlayer)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn transport(command: &str) -> Result<(), MediaError> {
+    let command = if command == "resume" { "play" } else { command };
+    playerctl(&[command]).map(|_| ())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn playerctl(args: &[&str]) -> Result<String, MediaError> {
+    let output = Command::new("playerctl")
+        .args(args)
+        .output()
+        .map_err(MediaError::Io)?;
+    if !output.status.success() {
+        return Err(MediaError::NoPlayer);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrentlyPlaying {
+    item: Option<SpotifyItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpotifyItem {
+    id: Option<String>,
+    uri: String,
+    name: String,
+    #[serde(default)]
+    artists: Vec<SpotifyArtist>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpotifyArtist {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    tracks: Option<SearchPage>,
+    artists: Option<SearchPage>,
+    playlists: Option<SearchPage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchPage {
+    /// Spotify sends `null` for playlists it can no longer show.
+    items: Vec<Option<SpotifyItem>>,
+}
+
+impl SearchPage {
+    fn first(self) -> Option<SpotifyItem> {
+        self.items.into_iter().flatten().next()
+    }
+}
+
+impl SpotifyItem {
+    fn track(&self) -> Track {
+        let artists: Vec<&str> = self.artists.iter().map(|artist| artist.name.as_str()).collect();
+        Track {
+            title: self.name.clone(),
+            artist: artists.join(" and "),
+        }
+    }
+}
+
+/// A fresh access token from the configured refresh token.
+async fn access_token(client: &Client, spotify: &SpotifyConfig) -> Result<String, MediaError> {
+    let response = client
+        .post(SPOTIFY_TOKEN_URL)
+        .basic_auth(&spotify.client_id, Some(&spotify.client_secret))
+        .form(&[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", spotify.refresh_token.as_str()),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<TokenResponse>()
+        .await?;
+    Ok(response.access_token)
+}
+
+/// Searches Spotify for `query` and starts playing the best match. "Some
+/// jazz" or "jazz music" plays a playlist, a name that matches an artist
+/// plays that artist, and anything else the top track. Returns what is
+/// playing, for reading out.
+async fn play(spotify: &SpotifyConfig, query: &str) -> Result<String, MediaError> {
+    let client = Client::new();
+    let token = access_token(&client, spotify).await?;
+    let lower = query.to_lowercase();
+    let wants_playlist = lower.starts_with("some ")
+        || lower.ends_with(" music")
+        || lower.ends_with(" playlist");
+    let search = lower
+        .trim_start_matches("some ")
+        .trim_end_matches(" playlist")
+        .to_string();
+    let results = client
+        .get(format!("{}/search", SPOTIFY_API))
+        .bearer_auth(&token)
+        .query(&[("q", search.as_str()), ("type", "track,artist,playlist"), ("limit", "1")])
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<SearchResponse>()
+        .await?;
+    let track = results.tracks.and_then(SearchPage::first);
+    let artist = results
+        .artists
+        .and_then(SearchPage::first)
+        .filter(|artist| artist.name.eq_ignore_ascii_case(&search));
+    let playlist = results.playlists.and_then(SearchPage::first);
+    let (body, name) = match (wants_playlist, playlist, artist, track) {
+        (true, Some(playlist), _, _) => (
+            serde_json::json!({ "context_uri": playlist.uri }),
+            playlist.name,
+        ),
+        (_, _, Some(artist), _) => (serde_json::json!({ "context_uri": artist.uri }), artist.name),
+        (_, _, _, Some(track)) => (serde_json::json!({ "uris": [track.uri] }), track.track().spoken()),
+        (_, Some(playlist), _, _) => (
+            serde_json::json!({ "context_uri": playlist.uri }),
+            playlist.name,
+        ),
+        _ => return Err(MediaError::NotFound(query.to_string())),
+    };
+    let response = client
+        .put(format!("{}/me/player/play", SPOTIFY_API))
+        .bearer_auth(&token)
+        .json(&body)
+        .send()
+        .await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Err(MediaError::NoDevice);
+    }
+    response.error_for_status()?;
+    Ok(name)
+}
+
+async fn spotify_now_playing(spotify: &SpotifyConfig) -> Result<Track, MediaError> {
+    let client = Client::new();
+    let token = access_token(&client, spotify).await?;
+    current_item(&client, &token).await.map(|item| item.track())
+}
+
+async fn current_item(client: &Client, token: &str) -> Result<SpotifyItem, MediaError> {
+    let response = client
+        .get(format!("{}/me/player/currently-playing", SPOTIFY_API))
+        .bearer_auth(token)
+        .send()
+        .await?
+        .error_for_status()?;
+    // 204 No Content when nothing is playing.
+    if response.status() == StatusCode::NO_CONTENT {
+        return Err(MediaError::NoPlayer);
+    }
+    response
+        .json::<CurrentlyPlaying>()
+        .await?
+        .item
+        .ok_or(MediaError::NoPlayer)
+}
+
+/// Saves the playing track to the user's Liked Songs.
+async fn like(spotify: &SpotifyConfig) -> Result<Track, MediaError> {
+    let client = Client::new();
+    let token = access_token(&client, spotify).await?;
+    let item = current_item(&client, &token).await?;
+    // Local files and podcast episodes have no track id to save.
+    let id = item.id.clone().ok_or(MediaError::NoPlayer)?;
+    client
+        .put(format!("{}/me/tracks", SPOTIFY_API))
+        .bearer_auth(&token)
+        .query(&[("ids", id.as_str())])
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(item.track())
+}
+
+async fn spotify_transport(spotify: &SpotifyConfig, command: &str) -> Result<(), MediaError> {
+    let client = Client::new();
+    let token = access_token(&client, spotify).await?;
+    let request = match command {
+        "pause" => client.put(format!("{}/me/player/pause", SPOTIFY_API)),
+        "resume" => client.put(format!("{}/me/player/play", SPOTIFY_API)),
+        "next" => client.post(format!("{}/me/player/next", SPOTIFY_API)),
+        _ => client.post(format!("{}/me/player/previous", SPOTIFY_API)),
+    };
+    let response = request
+        .bearer_auth(&token)
+        .header(reqwest::header::CONTENT_LENGTH, 0)
+        .send()
+        .await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Err(MediaError::NoDevice);
+    }
+    response.error_for_status()?;
+    Ok(())
+}
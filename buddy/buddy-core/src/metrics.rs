@@ -0,0 +1,340 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write as _,
+    time::{Duration, Instant},
+};
+
+/// Latency samples kept per stage; older ones roll off.
+const WINDOW: usize = 500;
+
+/// Per-stage durations for one command. Stages that did not run stay zero.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Timings {
+    pub capture: Duration,
+    pub transcribe: Duration,
+    pub intent: Duration,
+    pub execute: Duration,
+    pub total: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    Capture,
+    Transcribe,
+    Intent,
+    Execute,
+    Total,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [
+        Stage::Capture,
+        Stage::Transcribe,
+        Stage::Intent,
+        Stage::Execute,
+        Stage::Total,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Capture => "capture",
+            Self::Transcribe => "transcribe",
+            Self::Intent => "intent",
+            Self::Execute => "execute",
+            Self::Total => "total",
+        }
+    }
+}
+
+/// Running statistics for the current session.
+#[derive(Debug)]
+pub struct Metrics {
+    started: Instant,
+    commands: u64,
+    succeeded: u64,
+    stages: HashMap<Stage, VecDeque<Duration>>,
+    /// Every sample ever recorded per stage: total time and count.
+    stage_totals: HashMap<Stage, (Duration, u64)>,
+    command_counts: HashMap<String, u64>,
+    /// What classified each command: "model", "calculator", "rules", ...
+    sources: HashMap<String, u64>,
+    errors: HashMap<String, u64>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            commands: 0,
+            succeeded: 0,
+            stages: HashMap::new(),
+            stage_totals: HashMap::new(),
+            command_counts: HashMap::new(),
+            sources: HashMap::new(),
+            errors: HashMap::new(),
+        }
+    }
+
+    pub fn commands(&self) -> u64 {
+        self.commands
+    }
+
+    /// Counts one handled command under `label` (e.g. "open_app chrome").
+    pub fn record_command(&mut self, label: &str, succeeded: bool) {
+        self.commands += 1;
+        if succeeded {
+            self.succeeded += 1;
+        }
+        *self.command_counts.entry(label.to_string()).or_default() += 1;
+    }
+
+    /// Counts one intent classified by `source`.
+    pub fn record_source(&mut self, source: &str) {
+        *self.sources.entry(source.to_string()).or_default() += 1;
+    }
+
+    pub fn record_error(&mut self, error: impl Into<String>) {
+        *self.errors.entry(error.into()).or_default() += 1;
+    }
+
+    pub fn record_timings(&mut self, timings: &Timings) {
+        for stage in Stage::ALL {
+            let duration = match stage {
+                Stage::Capture => timings.capture,
+                Stage::Transcribe => timings.transcribe,
+                Stage::Intent => timings.intent,
+                Stage::Execute => timings.execute,
+                Stage::Total => timings.total,
+            };
+            if duration.is_zero() {
+                continue;
+            }
+            let (sum, count) = self.stage_totals.entry(stage).or_default();
+            *sum += duration;
+            *count += 1;
+            let samples = self.stages.entry(stage).or_default();
+            if samples.len() == WINDOW {
+                samples.pop_front();
+            }
+            samples.push_back(duration);
+        }
+    }
+
+    /// The `p`th percentile (0-100) of the recent samples for `stage`.
+    pub fn percentile(&self, stage: Stage, p: f64) -> Option<Duration> {
+        let samples = self.stages.get(&stage).filter(|samples| !samples.is_empty())?;
+        let mut sorted: Vec<Duration> = samples.iter().copied().collect();
+        sorted.sort();
+        let rank = ((p / 100.0) * (sorted.len() - 1) as f64).round() as usize;
+        sorted.get(rank.min(sorted.len() - 1)).copied()
+    }
+
+    fn success_rate(&self) -> f64 {
+        if self.commands == 0 {
+            0.0
+        } else {
+            self.succeeded as f64 / self.commands as f64 * 100.0
+        }
+    }
+
+    /// A sentence or two for "show stats".
+    pub fn spoken_summary(&self) -> String {
+        if self.commands == 0 {
+            return "No commands yet this session.".to_string();
+        }
+        let mut out = format!(
+            "{} commands this session, {:.0} percent succeeded.",
+            self.commands,
+            self.success_rate()
+        );
+        if let Some(p50) = self.percentile(Stage::Total, 50.0) {
+            let _ = write!(out, " Typical response time {:.1} seconds.", p50.as_secs_f64());
+        }
+        out
+    }
+
+    /// Multi-line report printed at shutdown.
+    pub fn summary(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "Session summary ({:.0} min)",
+            self.started.elapsed().as_secs_f64() / 60.0
+        );
+        let _ = writeln!(
+            out,
+            "  Commands: {} ({} succeeded, {:.0}%)",
+            self.commands,
+            self.succeeded,
+            self.success_rate()
+        );
+        for stage in Stage::ALL {
+            if let (Some(p50), Some(p95)) =
+                (self.percentile(stage, 50.0), self.percentile(stage, 95.0))
+            {
+                let _ = writeln!(
+                    out,
+                    "  {:<10} p50 {:>6.2}s  p95 {:>6.2}s",
+                    stage.name(),
+                    p50.as_secs_f64(),
+                    p95.as_secs_f64()
+                );
+            }
+        }
+        let sources = top_counts(&self.sources, usize::MAX);
+        if !sources.is_empty() {
+            let sources: Vec<String> = sources
+                .iter()
+                .map(|(source, count)| format!("{} {}", source, count))
+                .collect();
+            let _ = writeln!(out, "  Classified by: {}", sources.join(", "));
+        }
+        let top = top_counts(&self.command_counts, 5);
+        if !top.is_empty() {
+            let _ = writeln!(out, "  Most used:");
+            for (label, count) in top {
+                let _ = writeln!(out, "    {:>3}x {}", count, label);
+            }
+        }
+        let errors = top_counts(&self.errors, 5);
+        if !errors.is_empty() {
+            let _ = writeln!(out, "  Errors:");
+            for (error, count) in errors {
+                let _ = writeln!(out, "    {:>3}x {}", count, error);
+            }
+        }
+        out
+    }
+
+    /// The full breakdown for `--stats`: more percentiles, and every command
+    /// and error rather than the top five.
+    pub fn details(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Latency (recent {} per stage)", WINDOW);
+        for stage in Stage::ALL {
+            let Some((sum, count)) = self.stage_totals.get(&stage) else {
+                continue;
+            };
+            let quantiles: Vec<String> = [50.0, 90.0, 95.0, 99.0, 100.0]
+                .iter()
+                .filter_map(|p| {
+                    let value = self.percentile(stage, *p)?;
+                    let name = if *p == 100.0 { "max".to_string() } else { format!("p{}", p) };
+                    Some(format!("{} {:.2}s", name, value.as_secs_f64()))
+                })
+                .collect();
+            let _ = writeln!(
+                out,
+                "  {:<10} {}  mean {:.2}s over {}",
+                stage.name(),
+                quantiles.join("  "),
+                sum.as_secs_f64() / *count as f64,
+                count
+            );
+        }
+        for (title, counts) in [("Commands", &self.command_counts), ("Errors", &self.errors)] {
+            let entries = top_counts(counts, usize::MAX);
+            if !entries.is_empty() {
+                let _ = writeln!(out, "{}", title);
+                for (label, count) in entries {
+                    let _ = writeln!(out, "  {:>4}x {}", count, label);
+                }
+            }
+        }
+        out
+    }
+
+    /// The statistics in the Prometheus text format, for `GET /metrics`.
+    pub fn prometheus(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# HELP buddy_uptime_seconds Time since Buddy started.");
+        let _ = writeln!(out, "# TYPE buddy_uptime_seconds gauge");
+        let _ = writeln!(out, "buddy_uptime_seconds {:.3}", self.started.elapsed().as_secs_f64());
+        let _ = writeln!(out, "# HELP buddy_commands_total Commands handled, by outcome.");
+        let _ = writeln!(out, "# TYPE buddy_commands_total counter");
+        let _ = writeln!(out, "buddy_commands_total{{outcome=\"success\"}} {}", self.succeeded);
+        let _ = writeln!(
+            out,
+            "buddy_commands_total{{outcome=\"failure\"}} {}",
+            self.commands - self.succeeded
+        );
+        let _ = writeln!(out, "# HELP buddy_command_uses_total Commands handled, by command.");
+        let _ = writeln!(out, "# TYPE buddy_command_uses_total counter");
+        for (label, count) in top_counts(&self.command_counts, usize::MAX) {
+            let _ = writeln!(
+                out,
+                "buddy_command_uses_total{{command=\"{}\"}} {}",
+                escape_label(label),
+                count
+            );
+        }
+        let _ = writeln!(out, "# HELP buddy_intents_total Intents, by what classified them.");
+        let _ = writeln!(out, "# TYPE buddy_intents_total counter");
+        for (source, count) in top_counts(&self.sources, usize::MAX) {
+            let _ = writeln!(
+                out,
+                "buddy_intents_total{{source=\"{}\"}} {}",
+                escape_label(source),
+                count
+            );
+        }
+        let _ = writeln!(out, "# HELP buddy_errors_total Failed intents and actions.");
+        let _ = writeln!(out, "# TYPE buddy_errors_total counter");
+        let _ = writeln!(out, "buddy_errors_total {}", self.errors.values().sum::<u64>());
+        let _ = writeln!(out, "# HELP buddy_stage_seconds Latency of each pipeline stage.");
+        let _ = writeln!(out, "# TYPE buddy_stage_seconds summary");
+        for stage in Stage::ALL {
+            let Some((sum, count)) = self.stage_totals.get(&stage) else {
+                continue;
+            };
+            for quantile in [0.5, 0.9, 0.99] {
+                if let Some(value) = self.percentile(stage, quantile * 100.0) {
+                    let _ = writeln!(
+                        out,
+                        "buddy_stage_seconds{{stage=\"{}\",quantile=\"{}\"}} {:.3}",
+                        stage.name(),
+                        quantile,
+                        value.as_secs_f64()
+                    );
+                }
+            }
+            let _ = writeln!(
+                out,
+                "buddy_stage_seconds_sum{{stage=\"{}\"}} {:.3}",
+                stage.name(),
+                sum.as_secs_f64()
+            );
+            let _ = writeln!(
+                out,
+                "buddy_stage_seconds_count{{stage=\"{}\"}} {}",
+                stage.name(),
+                count
+            );
+        }
+        out
+    }
+}
+
+/// Escapes a Prometheus label value.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn top_counts(counts: &HashMap<String, u64>, limit: usize) -> Vec<(&str, u64)> {
+    let mut entries: Vec<(&str, u64)> = counts
+        .iter()
+        .map(|(label, count)| (label.as_str(), *count))
+        .collect();
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    entries.truncate(limit);
+    entries
+}
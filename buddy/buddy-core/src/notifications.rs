@@ -0,0 +1,188 @@
+use std::fmt;
+use tokio::sync::oneshot;
+
+/// AppUserModelID used for toasts. Unpackaged apps have no identity of their
+/// own, so borrow PowerShell's, which is registered on every Windows install.
+#[cfg(target_os = "windows")]
+const APP_ID: &str =
+    "{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\\WindowsPowerShell\\v1.0\\powershell.exe";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastChoice {
+    Confirm,
+    #[cfg_attr(not(windows), allow(dead_code))]
+    Cancel,
+}
+
+/// A toast with Confirm/Cancel buttons that stays on screen until answered or
+/// dismissed.
+pub struct ActionToast {
+    choice: Option<oneshot::Receiver<ToastChoice>>,
+    #[cfg(target_os = "windows")]
+    shown: Option<(
+        windows::UI::Notifications::ToastNotifier,
+        windows::UI::Notifications::ToastNotification,
+    )>,
+}
+
+impl ActionToast {
+    /// Resolves with the button the user clicked; pends forever if the toast is
+    /// closed without an answer so a competing source can win.
+    pub async fn choice(&mut self) -> ToastChoice {
+        match self.choice.take() {
+            Some(rx) => match rx.await {
+                Ok(choice) => choice,
+                Err(_) => std::future::pending().await,
+            },
+            None => std::future::pending().await,
+        }
+    }
+
+    pub fn dismiss(&self) {
+        #[cfg(target_os = "windows")]
+        if let Some((notifier, toast)) = &self.shown {
+            let _ = notifier.Hide(toast);
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub fn confirm_toast(title: &str, message: &str) -> Result<ActionToast, NotificationError> {
+    use std::sync::Mutex;
+    use windows::{
+        core::{IInspectable, Interface, HSTRING},
+        Foundation::TypedEventHandler,
+        UI::Notifications::{ToastActivatedEventArgs, ToastNotification, ToastNotificationManager},
+    };
+
+    let xml = format!(
+        concat!(
+            "<toast scenario=\"reminder\"><visual><binding template=\"ToastGeneric\">",
+            "<text>{}</text><text>{}</text></binding></visual><actions>",
+            "<action content=\"Confirm\" arguments=\"confirm\" activationType=\"foreground\"/>",
+            "<action content=\"Cancel\" arguments=\"cancel\" activationType=\"foreground\"/>",
+            "</actions></toast>"
+        ),
+        escape_xml(title),
+        escape_xml(message)
+    );
+    let toast = toast_from_xml(&xml)?;
+
+    let (tx, rx) = oneshot::channel();
+    let sender = Mutex::new(Some(tx));
+    toast
+        .Activated(&TypedEventHandler::<ToastNotification, IInspectable>::new(
+            move |_, args| {
+                let arguments = args
+                    .as_ref()
+                    .and_then(|args| args.cast::<ToastActivatedEventArgs>().ok())
+                    .and_then(|args| args.Arguments().ok())
+                    .map(|value| value.to_string())
+                    .unwrap_or_default();
+                let choice = if arguments == "confirm" {
+                    ToastChoice::Confirm
+                } else {
+                    ToastChoice::Cancel
+                };
+                if let Some(tx) = sender.lock().ok().and_then(|mut slot| slot.take()) {
+                    let _ = tx.send(choice);
+                }
+                Ok(())
+            },
+        ))
+        .map_err(NotificationError::Windows)?;
+
+    let notifier = ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(APP_ID))
+        .map_err(NotificationError::Windows)?;
+    notifier.Show(&toast).map_err(NotificationError::Windows)?;
+    Ok(ActionToast {
+        choice: Some(rx),
+        shown: Some((notifier, toast)),
+    })
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn confirm_toast(_title: &str, _message: &str) -> Result<ActionToast, NotificationError> {
+    Err(NotificationError::Unsupported)
+}
+
+/// A toast without buttons. Plain ones fade after a few seconds; `sticky`
+/// ones (reminders) stay until dismissed.
+#[cfg(target_os = "windows")]
+pub fn show_toast(title: &str, message: &str, sticky: bool) -> Result<(), NotificationError> {
+    use windows::{core::HSTRING, UI::Notifications::ToastNotificationManager};
+
+    // A reminder toast only stays on screen when it has a button.
+    let (scenario, actions) = if sticky {
+        (
+            " scenario=\"reminder\"",
+            concat!(
+                "<actions><action content=\"Dismiss\" arguments=\"dismiss\" ",
+                "activationType=\"system\"/></actions>"
+            ),
+        )
+    } else {
+        ("", "")
+    };
+    let xml = format!(
+        concat!(
+            "<toast{}><visual><binding template=\"ToastGeneric\">",
+            "<text>{}</text><text>{}</text></binding></visual>{}</toast>"
+        ),
+        scenario,
+        escape_xml(title),
+        escape_xml(message),
+        actions
+    );
+    let toast = toast_from_xml(&xml)?;
+    ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(APP_ID))
+        .and_then(|notifier| notifier.Show(&toast))
+        .map_err(NotificationError::Windows)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn show_toast(_title: &str, _message: &str, _sticky: bool) -> Result<(), NotificationError> {
+    Err(NotificationError::Unsupported)
+}
+
+#[cfg(target_os = "windows")]
+fn toast_from_xml(
+    xml: &str,
+) -> Result<windows::UI::Notifications::ToastNotification, NotificationError> {
+    use windows::{core::HSTRING, Data::Xml::Dom::XmlDocument, UI::Notifications::ToastNotification};
+
+    let document = XmlDocument::new().map_err(NotificationError::Windows)?;
+    document
+        .LoadXml(&HSTRING::from(xml))
+        .map_err(NotificationError::Windows)?;
+    ToastNotification::CreateToastNotification(&document).map_err(NotificationError::Windows)
+}
+
+#[cfg(target_os = "windows")]
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[derive(Debug)]
+pub enum NotificationError {
+    #[cfg(target_os = "windows")]
+    Windows(windows::core::Error),
+    #[cfg_attr(windows, allow(dead_code))]
+    Unsupported,
+}
+
+impl fmt::Display for NotificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(target_os = "windows")]
+            Self::Windows(err) => write!(f, "toast failed: {}", err),
+            Self::Unsupported => write!(f, "toast notifications require Windows"),
+        }
+    }
+}
+
+impl std::error::Error for NotificationError {}
@@ -0,0 +1,254 @@
+//! Spelled-out numbers to digits, as Whisper tends to write them out: "set
+//! volume to fifty percent" becomes "set volume to 50%", "the twenty first"
+//! becomes "the 21st".
+
+const UNITS: &[&str] = &[
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+    "eleven", "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen",
+    "nineteen",
+];
+const TENS: &[&str] = &[
+    "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+const ORDINAL_UNITS: &[&str] = &[
+    "zeroth", "first", "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth", "ninth",
+    "tenth", "eleventh", "twelfth", "thirteenth", "fourteenth", "fifteenth", "sixteenth",
+    "seventeenth", "eighteenth", "nineteenth",
+];
+const ORDINAL_TENS: &[&str] = &[
+    "twentieth", "thirtieth", "fortieth", "fiftieth", "sixtieth", "seventieth", "eightieth",
+    "ninetieth",
+];
+/// Words after which "second" is the unit of time ("wait a second").
+const BEFORE_SECOND: &[&str] = &["a", "one", "per", "each", "every"];
+
+#[derive(Clone, Copy)]
+enum Word {
+    /// Zero to ninety.
+    Small(u64),
+    /// Hundred, thousand, million.
+    Scale(u64),
+    /// "first" to "ninetieth"; ends the number.
+    Ordinal(u64),
+}
+
+/// A number being read word by word.
+#[derive(Clone, Default)]
+struct Number {
+    /// The thousands and millions read so far.
+    total: u64,
+    /// What comes below the last thousand or million.
+    current: u64,
+    words: usize,
+    ordinal: bool,
+}
+
+impl Number {
+    /// Takes `word` if it can continue the number: "twenty" takes "five" but
+    /// not "six hundred" ("twenty six hundred" is read as 2600, "one two" as
+    /// two numbers).
+    fn push(&mut self, word: Word) -> bool {
+        if self.ordinal || (self.words > 0 && self.total + self.current == 0) {
+            return false;
+        }
+        let fits = |n: u64| {
+            let rest = self.current % 100;
+            (rest == 0 && (n > 0 || self.words == 0))
+                || (rest >= 20 && rest.is_multiple_of(10) && n < 10)
+        };
+        match word {
+            Word::Small(n) if fits(n) => self.current += n,
+            Word::Ordinal(n) if fits(n) => {
+                self.current += n;
+                self.ordinal = true;
+            }
+            Word::Scale(100) if (1..100).contains(&self.current) => self.current *= 100,
+            Word::Scale(scale)
+                if scale > 100
+                    && (1..1000).contains(&self.current)
+                    && (self.total == 0 || self.total >= scale * 1000) =>
+            {
+                self.total += self.current * scale;
+                self.current = 0;
+            }
+            _ => return false,
+        }
+        self.words += 1;
+        true
+    }
+
+    fn value(&self) -> u64 {
+        self.total + self.current
+    }
+}
+
+/// `text` with spelled-out numbers and ordinals as digits, and "percent"
+/// after a number as "%". A lone "one" ("which one") and "second" as a unit
+/// of time are left alone.
+pub fn normalize(text: &str) -> String {
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    let mut words = Vec::with_capacity(tokens.len());
+    let mut index = 0;
+    while index < tokens.len() {
+        match number_at(&tokens, index) {
+            Some((number, next)) => {
+                words.push(number);
+                index = next;
+            }
+            None => {
+                words.push(tokens[index].to_string());
+                index += 1;
+            }
+        }
+    }
+    words.join(" ")
+}
+
+/// The number starting at `tokens[start]` as digits, and the index of the
+/// token after it.
+fn number_at(tokens: &[&str], start: usize) -> Option<(String, usize)> {
+    let mut number = Number::default();
+    let mut next = start;
+    let mut last = None;
+    let mut digits = None;
+    let mut punctuation = "";
+    while let Some(token) = tokens.get(next) {
+        let (core, trailing) = split_punctuation(token);
+        let core = core.to_lowercase();
+        if next == start {
+            if let Ok(value) = core.parse::<u64>() {
+                digits = Some(value);
+                punctuation = trailing;
+                next += 1;
+                break;
+            }
+            // "a hundred", "a thousand".
+            if core == "a" && tokens.get(next + 1).is_some_and(|token| scale_follows(token)) {
+                number.current = 1;
+                next += 1;
+                continue;
+            }
+        }
+        // "one hundred and five".
+        if core == "and"
+            && matches!(last, Some(Word::Scale(_)))
+            && trailing.is_empty()
+            && tokens.get(next + 1).is_some_and(|token| small_follows(token))
+        {
+            next += 1;
+            continue;
+        }
+        let Some(parts) = core.split('-').map(word).collect::<Option<Vec<Word>>>() else {
+            break;
+        };
+        // A hyphenated word ("twenty-five") joins the number whole or not at all.
+        let mut longer = number.clone();
+        if !parts.iter().all(|part| longer.push(*part)) {
+            break;
+        }
+        number = longer;
+        last = parts.last().copied();
+        punctuation = trailing;
+        next += 1;
+        if !trailing.is_empty() {
+            break;
+        }
+    }
+    let value = match digits {
+        Some(value) => value,
+        None if number.words == 0 => return None,
+        None => number.value(),
+    };
+    let single = digits.is_none() && number.words == 1 && next == start + 1;
+    let percent = punctuation.is_empty().then(|| percent_at(tokens, next)).flatten();
+    if percent.is_none() && single {
+        let word = split_punctuation(tokens[start]).0.to_lowercase();
+        let before = start
+            .checked_sub(1)
+            .map(|index| split_punctuation(tokens[index]).0.to_lowercase());
+        let time = before.is_some_and(|before| {
+            BEFORE_SECOND.contains(&before.as_str()) || before.parse::<u64>().is_ok()
+        });
+        if word == "one" || (word == "second" && time) {
+            return None;
+        }
+    }
+    if digits.is_some() && percent.is_none() {
+        return None;
+    }
+    let mut text = value.to_string();
+    if number.ordinal {
+        text.push_str(ordinal_suffix(value));
+    }
+    match percent {
+        Some((after, trailing)) => {
+            text.push('%');
+            text.push_str(trailing);
+            Some((text, after))
+        }
+        None => {
+            text.push_str(punctuation);
+            Some((text, next))
+        }
+    }
+}
+
+/// "percent" or "per cent" at `tokens[index]`: the index after it and its
+/// trailing punctuation.
+fn percent_at<'a>(tokens: &[&'a str], index: usize) -> Option<(usize, &'a str)> {
+    let (core, trailing) = split_punctuation(tokens.get(index)?);
+    match core.to_lowercase().as_str() {
+        "percent" => Some((index + 1, trailing)),
+        "per" => {
+            let (core, trailing) = split_punctuation(tokens.get(index + 1)?);
+            core.eq_ignore_ascii_case("cent").then_some((index + 2, trailing))
+        }
+        _ => None,
+    }
+}
+
+fn scale_follows(token: &str) -> bool {
+    matches!(word(&split_punctuation(token).0.to_lowercase()), Some(Word::Scale(_)))
+}
+
+fn small_follows(token: &str) -> bool {
+    let core = split_punctuation(token).0.to_lowercase();
+    core.split('-')
+        .all(|part| matches!(word(part), Some(Word::Small(_) | Word::Ordinal(_))))
+}
+
+fn word(text: &str) -> Option<Word> {
+    let position = |names: &[&str]| names.iter().position(|name| *name == text);
+    if let Some(n) = position(UNITS) {
+        Some(Word::Small(n as u64))
+    } else if let Some(n) = position(TENS) {
+        Some(Word::Small(n as u64 * 10 + 20))
+    } else if let Some(n) = position(ORDINAL_UNITS) {
+        Some(Word::Ordinal(n as u64))
+    } else if let Some(n) = position(ORDINAL_TENS) {
+        Some(Word::Ordinal(n as u64 * 10 + 20))
+    } else {
+        match text {
+            "hundred" => Some(Word::Scale(100)),
+            "thousand" => Some(Word::Scale(1000)),
+            "million" => Some(Word::Scale(1_000_000)),
+            _ => None,
+        }
+    }
+}
+
+/// A word and the punctuation after it: ("fifty", ".").
+fn split_punctuation(token: &str) -> (&str, &str) {
+    let core = token.trim_end_matches(['.', ',', '?', '!', ';', ':']);
+    (core, &token[core.len()..])
+}
+
+fn ordinal_suffix(value: u64) -> &'static str {
+    match (value % 10, value % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
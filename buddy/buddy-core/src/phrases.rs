@@ -0,0 +1,120 @@
+use crate::config::PhrasesConfig;
+use std::collections::HashMap;
+
+/// What Buddy says when `[phrases]` doesn't say otherwise.
+const ENGLISH: &[(&str, &str)] = &[
+    ("ok", "Ok"),
+    ("reminder", "Reminder: {text}"),
+    ("continue", "Want me to continue?"),
+    ("model_ready", "Language model ready"),
+    ("help", "Say: open <file>, launch <app>, set volume, mute, lock, sleep, or ask a question."),
+    ("didnt_hear", "I didn't hear anything"),
+    ("didnt_catch", "Sorry, I didn't catch that. Please say it again."),
+    ("mic_disconnected", "The microphone disconnected. Please try again."),
+    ("mic_unavailable", "The microphone isn't available"),
+    ("mic_muted", "Your microphone is muted. Unmute it in Windows or from the Buddy console."),
+    ("nothing_to_type", "There's no answer to type yet"),
+    ("typing_failed", "Typing failed"),
+    ("intent_failed", "Intent failed"),
+    ("model_loading", "The model is still loading. Try again in a moment."),
+    ("unknown_intent", "I don't know how to do that"),
+    ("denied", "That action is disabled"),
+    ("hook_refused", "Your pre-execute hook stopped that"),
+    ("access_denied", "Windows didn't allow that. Check Settings, Privacy."),
+    ("failed", "Command failed"),
+    ("did_you_mean", "Did you mean {action}?"),
+    ("are_you_sure", "Are you sure you want to {action}?"),
+    ("say_yes_or_no", "{question} Say yes or no."),
+    ("say_again", "Okay, please say it again"),
+    ("cancelled", "Cancelled"),
+    ("never_mind", "Never mind"),
+    ("nothing_to_correct", "There's nothing to correct yet"),
+    ("what_did_you_mean", "Sorry about that. What did you mean?"),
+    ("which_one", "I found several matches: {options}. Which one?"),
+    ("which_one_again", "Sorry, say first, second, or third."),
+    ("language_auto", "I'll detect the language from now on"),
+    ("language_switched", "Switched to {language}"),
+    ("profile_switched", "Switched to the {profile} profile"),
+    ("profile_default", "Back to the default settings"),
+    ("already_transcribing", "I'm already transcribing"),
+    ("transcribing", "Transcribing. Say stop transcribing when you're done."),
+    ("cannot_transcribe", "I can't transcribe right now"),
+    ("finishing_transcript", "Finishing the transcript"),
+    ("transcript_saved", "Transcript saved"),
+    ("not_transcribing", "I'm not transcribing"),
+    ("conversation_saved", "Saved the conversation as {file}"),
+    ("nothing_to_save", "There's no conversation to save yet"),
+    ("macro_recording", "Recording {name}. Say end macro when you're done."),
+    ("already_recording_macro", "I'm already recording a macro"),
+    ("not_recording_macro", "I'm not recording a macro"),
+    ("macro_empty", "Nothing was recorded"),
+    ("macro_saved", "Saved the {name} macro"),
+    ("indexing_documents", "Updating the document index"),
+    ("documents_indexed", "Indexed {count} new or changed documents"),
+    ("no_document_folders", "No document folders are set up"),
+];
+
+/// Looks up what Buddy says by name: the `[phrases.<locale>]` table for the
+/// language Whisper is pinned to, then the one for `phrases.locale`, then
+/// `[phrases.en]`, then the built-in English.
+#[derive(Debug, Clone)]
+pub struct Phrases {
+    locale: String,
+    /// The language Whisper is pinned to ("switch to German"), if any.
+    language: Option<String>,
+    tables: HashMap<String, HashMap<String, String>>,
+}
+
+impl Phrases {
+    pub fn new(cfg: &PhrasesConfig) -> Self {
+        Self {
+            locale: cfg.locale.to_lowercase(),
+            language: None,
+            tables: cfg
+                .locales
+                .iter()
+                .map(|(locale, table)| (locale.to_lowercase(), table.clone()))
+                .collect(),
+        }
+    }
+
+    /// Follows the transcription language: a code such as "de", or `None` to
+    /// go back to `phrases.locale`.
+    pub fn set_language(&mut self, code: Option<&str>) {
+        self.language = code.map(str::to_lowercase);
+    }
+
+    /// The phrase called `key`, with each `{name}` in it replaced by its
+    /// value from `args`.
+    pub fn get(&self, key: &str, args: &[(&str, &str)]) -> String {
+        let phrase = self
+            .configured(key)
+            .or_else(|| ENGLISH.iter().find(|(name, _)| *name == key).map(|(_, text)| *text))
+            .unwrap_or(key);
+        fill(phrase, args)
+    }
+
+    /// The first of `keys` with a configured phrase, for what Buddy says
+    /// after an action: `[phrases.de] volume_up = "Lautstärke erhöht"`.
+    pub fn find(&self, keys: &[&str], args: &[(&str, &str)]) -> Option<String> {
+        keys.iter()
+            .find_map(|key| self.configured(key))
+            .map(|phrase| fill(phrase, args))
+    }
+
+    fn configured(&self, key: &str) -> Option<&str> {
+        let locales = [self.language.as_deref(), Some(self.locale.as_str()), Some("en")];
+        locales
+            .into_iter()
+            .flatten()
+            .filter_map(|locale| self.tables.get(locale))
+            .find_map(|table| table.get(key))
+            .map(String::as_str)
+    }
+}
+
+fn fill(phrase: &str, args: &[(&str, &str)]) -> String {
+    args.iter().fold(phrase.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), value)
+    })
+}
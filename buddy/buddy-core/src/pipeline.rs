@@ -0,0 +1,682 @@
+use crate::{
+    assistants,
+    audio::AudioError,
+    calculator, clock, dev,
+    config::Config,
+    executor::{self, ExecutionError, ExecutionResult},
+    hotkey::HotkeyError,
+    intent::{FileLocation, Intent, IntentClient, IntentError, Spoken},
+    files, media, numbers, process, reminders, rules, screen, shell, smart_home,
+    stages::{
+        Actions, Capture, Executor, Feedback, IntentEngine, IntentService, Microphone, Silent,
+        Stt, Transcription, Trigger,
+    },
+    transcription::{Transcriber, Transcript, TranscriptionError},
+    ui_automation, urls,
+    usage::Ranking,
+    weather, wsl,
+};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Buddy's speech-to-action pipeline without the app around it: records,
+/// transcribes, classifies and runs commands the way the hotkey does, minus
+/// the follow-up questions. Each stage is a trait from `stages`, so any of
+/// them can be swapped for another backend or a mock.
+///
+/// ```no_run
+/// # async fn demo() -> Result<(), buddy_core::PipelineError> {
+/// let config = buddy_core::Config::load("config.toml", None).expect("config");
+/// let mut pipeline = buddy_core::Pipeline::new(config, false, true)?.with_microphone()?;
+/// let outcome = pipeline.listen().await?;
+/// println!("{} -> {:?}", outcome.transcript, outcome.result);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Pipeline {
+    config: Config,
+    debug: bool,
+    /// Needed by `next` only.
+    trigger: Option<Box<dyn Trigger>>,
+    /// Needed by `listen` and `next`; `handle_audio` and `handle_text` work
+    /// without it.
+    capture: Option<Box<dyn Capture>>,
+    stt: Box<dyn Stt>,
+    intents: Box<dyn IntentEngine>,
+    executor: Box<dyn Executor>,
+    feedback: Box<dyn Feedback>,
+}
+
+/// One command taken through the pipeline.
+#[derive(Debug)]
+pub struct Outcome {
+    /// What was heard (English when `transcription.translate` kicked in), or
+    /// the text given to `handle_text`.
+    pub transcript: String,
+    pub intent: Intent,
+    /// What classified the intent: "model", or the local recognizer that
+    /// matched ("calculator", "rules"...).
+    pub classified_by: &'static str,
+    /// `None` when the intent was held back because Buddy would ask first
+    /// (see `needs_confirmation` and `needs_clarification`). Ask the user,
+    /// then call `Pipeline::execute`.
+    pub result: Option<Result<ExecutionResult, ExecutionError>>,
+}
+
+impl Pipeline {
+    /// The stages the Buddy app uses: the `transcription.engine` recognizer
+    /// primed with and corrected to the configured file, app and URL names,
+    /// the local recognizers and intent service, and the command executor.
+    /// Add a microphone with `with_microphone`. `suppress_native_logs` hides
+    /// whisper.cpp's own output while the model loads.
+    pub fn new(
+        config: Config,
+        debug: bool,
+        suppress_native_logs: bool,
+    ) -> Result<Self, PipelineError> {
+        let transcriber = Transcriber::new(
+            &config.transcription,
+            build_transcription_prompt(&config),
+            debug,
+            suppress_native_logs,
+        )?;
+        transcriber.set_command_vocabulary(command_vocabulary(&config));
+        let intents = IntentService::new(&config, debug);
+        let executor = Actions::new(&config);
+        let stt = Transcription(Arc::new(transcriber));
+        let mut pipeline = Self::from_stages(config, stt, intents, executor);
+        pipeline.debug = debug;
+        Ok(pipeline)
+    }
+
+    /// A pipeline of the given stages, with no trigger, capture or feedback.
+    /// `config` still decides which intents are held back for confirmation.
+    pub fn from_stages(
+        config: Config,
+        stt: impl Stt + 'static,
+        intents: impl IntentEngine + 'static,
+        executor: impl Executor + 'static,
+    ) -> Self {
+        Self {
+            config,
+            debug: false,
+            trigger: None,
+            capture: None,
+            stt: Box::new(stt),
+            intents: Box::new(intents),
+            executor: Box::new(executor),
+            feedback: Box::new(Silent),
+        }
+    }
+
+    /// Records from the microphone in `[audio]`.
+    pub fn with_microphone(self) -> Result<Self, PipelineError> {
+        let microphone = Microphone::new(&self.config, self.debug)?;
+        Ok(self.with_capture(microphone))
+    }
+
+    pub fn with_capture(mut self, capture: impl Capture + 'static) -> Self {
+        self.capture = Some(Box::new(capture));
+        self
+    }
+
+    pub fn with_trigger(mut self, trigger: impl Trigger + 'static) -> Self {
+        self.trigger = Some(Box::new(trigger));
+        self
+    }
+
+    /// Reports each outcome and failure, e.g. to a `FeedbackPlayer`.
+    pub fn with_feedback(mut self, feedback: impl Feedback + 'static) -> Self {
+        self.feedback = Box::new(feedback);
+        self
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Waits up to `timeout` for the intent engine. Until the intent service
+    /// answers, only locally recognized commands (the calculator, clock,
+    /// rules...) classify.
+    pub async fn wait_until_ready(&self, timeout: Duration) -> Result<(), IntentError> {
+        self.intents.wait_until_ready(timeout).await
+    }
+
+    pub async fn transcribe(&self, audio: Vec<i16>) -> Result<Transcript, PipelineError> {
+        self.stt.transcribe(audio).await
+    }
+
+    pub async fn classify(
+        &self,
+        text: &str,
+        spoken: Spoken<'_>,
+    ) -> Result<(Intent, &'static str), IntentError> {
+        self.intents.classify(text, spoken).await
+    }
+
+    /// Runs `intent` without asking first.
+    pub fn execute(&self, intent: &Intent) -> Result<ExecutionResult, ExecutionError> {
+        self.executor.execute(intent)
+    }
+
+    /// Waits for the trigger, then handles one command like `listen`.
+    pub async fn next(&mut self) -> Result<Outcome, PipelineError> {
+        let trigger = self.trigger.as_mut().ok_or(PipelineError::NoTrigger)?;
+        trigger.wait().await?;
+        self.listen().await
+    }
+
+    /// Records, transcribes, classifies and runs one command.
+    pub async fn listen(&mut self) -> Result<Outcome, PipelineError> {
+        let Some(capture) = &self.capture else {
+            return self.finish(Err(PipelineError::NoMicrophone));
+        };
+        self.feedback.recording_started().await;
+        let audio = capture.record().await;
+        self.feedback.recording_stopped();
+        match audio {
+            Ok(audio) => self.handle_audio(audio).await,
+            Err(err) => self.finish(Err(err)),
+        }
+    }
+
+    /// Transcribes, classifies and runs a recorded command.
+    pub async fn handle_audio(&mut self, audio: Vec<i16>) -> Result<Outcome, PipelineError> {
+        // Only the stages are borrowed across awaits, so the future stays
+        // `Send` without needing a `Sync` trigger or feedback.
+        let (stt, intents) = (&self.stt, &self.intents);
+        let min_confidence = self.config.transcription.min_confidence;
+        let classified = async {
+            let transcript = stt.transcribe(audio).await?;
+            if transcript.text.trim().is_empty() {
+                return Err(PipelineError::NoSpeech);
+            }
+            if transcript.unclear(min_confidence) {
+                let confidence = transcript.confidence.unwrap_or_default();
+                return Err(PipelineError::Unclear(confidence));
+            }
+            let (intent, classified_by) =
+                intents.classify(&transcript.text, transcript.spoken()).await?;
+            Ok((transcript.text, intent, classified_by))
+        }
+        .await;
+        let outcome = classified.map(|(text, intent, by)| self.run(text, intent, by));
+        self.finish(outcome)
+    }
+
+    /// Classifies and runs a typed command, as if it had been spoken.
+    pub async fn handle_text(&mut self, text: &str) -> Result<Outcome, PipelineError> {
+        let outcome = match self.intents.classify(text, Spoken::default()).await {
+            Ok((intent, classified_by)) => Ok(self.run(text.to_string(), intent, classified_by)),
+            Err(err) => Err(err.into()),
+        };
+        self.finish(outcome)
+    }
+
+    fn run(&self, transcript: String, intent: Intent, classified_by: &'static str) -> Outcome {
+        let held_back = executor::denied(&self.config, &intent).is_none()
+            && (needs_clarification(&self.config, &intent)
+                || needs_confirmation(&self.config, &intent));
+        let result = (!held_back).then(|| self.execute(&intent));
+        Outcome {
+            transcript,
+            intent,
+            classified_by,
+            result,
+        }
+    }
+
+    fn finish(
+        &mut self,
+        outcome: Result<Outcome, PipelineError>,
+    ) -> Result<Outcome, PipelineError> {
+        match &outcome {
+            Ok(outcome) => self.feedback.report(outcome),
+            Err(err) => self.feedback.report_error(err),
+        }
+        outcome
+    }
+}
+
+/// Polls the intent service with exponential backoff until it answers or
+/// `timeout` passes. With `progress`, a single console line shows the wait.
+pub async fn wait_for_intent_ready(
+    intent_client: &IntentClient,
+    timeout: Duration,
+    progress: bool,
+) -> Result<(), IntentError> {
+    let start = Instant::now();
+    let mut delay = Duration::from_millis(250);
+    let max_delay = Duration::from_secs(8);
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let err = match intent_client.wait_for_ready().await {
+            Ok(()) => {
+                if progress && attempt > 1 {
+                    println!();
+                }
+                return Ok(());
+            }
+            Err(err) => err,
+        };
+        let elapsed = start.elapsed();
+        if elapsed >= timeout {
+            if progress {
+                println!();
+            }
+            return Err(err);
+        }
+        if progress {
+            use std::io::Write;
+            print!(
+                "\rWaiting for intent service... {}s / {}s (attempt {})   ",
+                elapsed.as_secs(),
+                timeout.as_secs(),
+                attempt
+            );
+            let _ = std::io::stdout().flush();
+        }
+        tokio::time::sleep(delay.min(timeout - elapsed)).await;
+        delay = (delay * 2).min(max_delay);
+    }
+}
+
+/// Answers arithmetic, unit conversions, and date and time questions locally,
+/// then tries the deterministic rule matcher (when enabled) so literal
+/// commands skip the LLM round trip; anything ambiguous goes to the intent
+/// service. Returns the intent and what classified it: "model", or the local
+/// recognizer that matched ("calculator", "rules"...).
+pub async fn classify(
+    intent_client: &IntentClient,
+    transcript: &str,
+    spoken: Spoken<'_>,
+    config: &Config,
+    debug: bool,
+    on_sentence: &mut (dyn FnMut(&str) + Send),
+) -> Result<(Intent, &'static str), IntentError> {
+    let (intent, source) =
+        classify_intent(intent_client, transcript, spoken, config, on_sentence).await?;
+    if debug {
+        println!("Intent path: {}", source);
+    }
+    Ok((with_command(intent, intent_client, config).await, source))
+}
+
+/// `classify` without writing the command for "run command", for measuring
+/// classification alone.
+pub async fn classify_intent(
+    intent_client: &IntentClient,
+    transcript: &str,
+    spoken: Spoken<'_>,
+    config: &Config,
+    on_sentence: &mut (dyn FnMut(&str) + Send),
+) -> Result<(Intent, &'static str), IntentError> {
+    let (source, intent) = match recognize_locally(transcript, config) {
+        Some(found) => found,
+        None => {
+            let intent = intent_client
+                .infer_intent(&numbers::normalize(transcript), spoken, config, on_sentence)
+                .await?;
+            ("model", intent)
+        }
+    };
+    Ok((intent, source))
+}
+
+/// Questions for a named assistant, "run command: ...", the calculator, date and time,
+/// reminders and notes, weather, screenshots, "read this", running apps, `[sendkeys]` names,
+/// URLs, media controls, smart-home devices, WSL commands, git repos, and (with
+/// `deepseek.rules_first`) the rule matcher, tried in that order before the intent model.
+fn recognize_locally(transcript: &str, config: &Config) -> Option<(&'static str, Intent)> {
+    let (path, intent) = if let Some(intent) = assistants::recognize(transcript, config) {
+        ("assistants", intent)
+    } else if let Some(intent) = shell::recognize(transcript, config) {
+        ("shell", intent)
+    } else if let Some(response) = calculator::answer(transcript) {
+        let intent = Intent::Answer {
+            response,
+            confidence: rules::EXACT_CONFIDENCE,
+        };
+        ("calculator", intent)
+    } else if let Some(intent) = clock::recognize(transcript) {
+        ("clock", intent)
+    } else if let Some(intent) = reminders::recognize(transcript) {
+        ("reminders", intent)
+    } else if let Some(intent) = weather::recognize(transcript) {
+        ("weather", intent)
+    } else if let Some(intent) = screen::recognize(transcript) {
+        ("screen", intent)
+    } else if let Some(intent) = ui_automation::recognize(transcript) {
+        ("read_text", intent)
+    } else if let Some(intent) = process::recognize(transcript, config) {
+        ("process", intent)
+    } else if let Some(intent) = rules::recognize_keys(transcript, config) {
+        ("sendkeys", intent)
+    } else if let Some(intent) = urls::recognize(transcript, config) {
+        ("urls", intent)
+    } else if let Some(intent) = media::recognize(transcript) {
+        ("media", intent)
+    } else if let Some(intent) = smart_home::recognize(transcript, config) {
+        ("smart_home", intent)
+    } else if let Some(intent) = wsl::recognize(transcript, config) {
+        ("wsl", intent)
+    } else if let Some(intent) = dev::recognize(transcript, config) {
+        ("dev", intent)
+    } else if let Some(intent) = files::recognize(transcript, config) {
+        ("files", intent)
+    } else if config.deepseek.rules_first {
+        let intent = rules::infer_intent(transcript, config)
+            .filter(|intent| intent.confidence() >= rules::EXACT_CONFIDENCE)?;
+        ("rules", intent)
+    } else {
+        return None;
+    };
+    Some((path, intent))
+}
+
+/// Writes the command "run command" runs, so it can be read out for a
+/// "yes" first. Forecasts, screen descriptions and assistants' replies are
+/// fetched by their handlers, once the policy and the user have let the
+/// intent run; a denied or dry-run command isn't written either.
+async fn with_command(mut intent: Intent, intent_client: &IntentClient, config: &Config) -> Intent {
+    if config.policy.dry_run || executor::denied(config, &intent).is_some() {
+        return intent;
+    }
+    if let Intent::Shell {
+        request, command, ..
+    } = &mut intent
+    {
+        match shell::command(intent_client, request, config).await {
+            Ok(line) => *command = line,
+            Err(err) => eprintln!("Writing the command failed: {}", err),
+        }
+    }
+    intent
+}
+
+pub fn build_transcription_prompt(config: &Config) -> Option<String> {
+    let mut phrases = Vec::new();
+    // Whisper gives the end of its prompt the most weight, so the most used
+    // keys are saved for last.
+    let ranking = Ranking::load(&config.usage);
+    let top = config.usage.top;
+    let mut favorites = Vec::new();
+    for (kind, verb, keys) in [
+        ("open_file", "Open", config.file_keys()),
+        ("open_app", "Launch", config.app_keys()),
+        ("open_url", "Open", config.url_keys()),
+    ] {
+        let used = ranking.top(kind, &keys, top);
+        let mut keys: Vec<_> = keys.into_iter().filter(|key| !used.contains(key)).collect();
+        keys.sort();
+        phrases.extend(keys.iter().map(|key| format!("{} {}.", verb, key)));
+        favorites.extend(used.iter().map(|key| format!("{} {}.", verb, key)));
+    }
+    let system = &config.system;
+    if system.volume_mute {
+        phrases.push("Mute volume.".to_string());
+    }
+    if system.volume_up {
+        phrases.push("Volume up.".to_string());
+    }
+    if system.volume_down {
+        phrases.push("Volume down.".to_string());
+    }
+    if system.volume_set {
+        phrases.push("Set volume to 50.".to_string());
+    }
+    if system.volume_query {
+        phrases.push("What's the volume?".to_string());
+    }
+    if system.mic_mute {
+        phrases.push("Mute my mic.".to_string());
+    }
+    if system.screen_off {
+        phrases.push("Turn off the screen.".to_string());
+    }
+    if system.display_extend {
+        phrases.push("Extend displays.".to_string());
+    }
+    if system.rotate_screen {
+        phrases.push("Rotate the screen.".to_string());
+    }
+    if system.bluetooth {
+        phrases.push("Turn off Bluetooth.".to_string());
+    }
+    if system.wifi {
+        phrases.push("Turn on Wi-Fi.".to_string());
+    }
+    if system.airplane_mode {
+        phrases.push("Airplane mode on.".to_string());
+    }
+    if system.power_plan {
+        phrases.push("Switch to high performance.".to_string());
+    }
+    if system.battery_status {
+        phrases.push("Battery status.".to_string());
+    }
+    if system.sleep {
+        phrases.push("Go to sleep.".to_string());
+    }
+    if system.restart {
+        phrases.push("Restart computer.".to_string());
+    }
+    if system.shutdown {
+        phrases.push("Shut down computer.".to_string());
+    }
+    if system.lock {
+        phrases.push("Lock computer.".to_string());
+    }
+    phrases.extend(favorites);
+    if phrases.is_empty() {
+        None
+    } else {
+        Some(phrases.join(" "))
+    }
+}
+
+/// The names misheard commands are corrected to: the `[files]`,
+/// `[applications]` and `[urls]` keys, most used first so they win ties.
+pub fn command_vocabulary(config: &Config) -> Vec<String> {
+    let ranking = Ranking::load(&config.usage);
+    let mut keys = Vec::new();
+    for (kind, names) in [
+        ("open_file", config.file_keys()),
+        ("open_app", config.app_keys()),
+        ("open_url", config.url_keys()),
+    ] {
+        keys.extend(names.into_iter().map(|key| (ranking.score(kind, &key), key)));
+    }
+    keys.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    keys.into_iter().map(|(_, key)| key).collect()
+}
+
+/// Whether `intent` is one the config says to confirm first. A command
+/// written for "run command" always is, unless it is refused anyway, and
+/// so is anything done to a lock or a cover.
+pub fn needs_confirmation(config: &Config, intent: &Intent) -> bool {
+    match intent {
+        Intent::Shell {
+            command: Some(command),
+            ..
+        } => {
+            // A blocked command is refused without being offered.
+            return shell::refused(command, &config.shell).is_none();
+        }
+        Intent::SmartHome {
+            target, command, ..
+        } if smart_home::needs_confirmation(target, command, config) => return true,
+        _ => {}
+    }
+    let names = executor::policy_names(intent);
+    config
+        .confirmation
+        .actions
+        .iter()
+        .chain(&config.policy.confirm)
+        .any(|name| names.contains(&name.as_str()))
+}
+
+/// Whether `intent` would act on the system with less confidence than
+/// `deepseek.min_confidence`; answers and unknown intents never do.
+pub fn needs_clarification(config: &Config, intent: &Intent) -> bool {
+    matches!(
+        intent,
+        Intent::OpenFile { .. }
+            | Intent::OpenApp { .. }
+            | Intent::System { .. }
+            | Intent::CloseApp { .. }
+            | Intent::KillApp { .. }
+            | Intent::Custom { .. }
+            | Intent::SmartHome { .. }
+    ) && intent.confidence() < config.deepseek.min_confidence
+}
+
+pub fn describe_intent(intent: &Intent) -> String {
+    let description = match intent {
+        Intent::OpenFile {
+            target,
+            query: Some(query),
+            ..
+        } => format!("open {} from {}", query, target),
+        Intent::OpenFile { target, .. } => format!("open {}", target),
+        Intent::OpenApp { target, .. } => format!("launch {}", target),
+        Intent::OpenUrl { target, .. } => format!("open {}", target),
+        Intent::System { target, .. } => target.replace('_', " "),
+        Intent::Answer { .. } => "answer".to_string(),
+        Intent::Translate { text, language, .. } => format!("say \"{}\" in {}", text, language),
+        Intent::Ask { assistant, .. } => format!("ask {}", assistant.replace('_', " ")),
+        Intent::Time { target, .. } if matches!(target.as_str(), "time" | "date" | "day") => {
+            format!("tell you the {}", target)
+        }
+        Intent::Time { target, .. } => format!("look up {}", target),
+        Intent::Calendar { target, .. } => format!("read your calendar for {}", target),
+        Intent::Weather { target, .. } => format!("check the weather for {}", target),
+        Intent::Remind { text, when, .. } => format!("remind you {}: {}", when, text),
+        Intent::Note { text, .. } => format!("note \"{}\"", text),
+        Intent::List { target, .. } => format!("read your {}", target),
+        Intent::Screenshot { .. } => "take a screenshot".to_string(),
+        Intent::DescribeScreen { .. } => "describe your screen".to_string(),
+        Intent::ReadText { .. } => "read the selected text".to_string(),
+        Intent::CloseApp { target, .. } => format!("close {}", target),
+        Intent::KillApp { target, .. } if target == "frozen" => {
+            "end whatever has stopped responding".to_string()
+        }
+        Intent::KillApp { target, .. } => format!("force {} to quit", target),
+        Intent::AppRunning { target, .. } => format!("check whether {} is running", target),
+        Intent::Media { target, query, .. } => media::describe(target, query.as_deref()),
+        Intent::SmartHome {
+            target, command, ..
+        } => smart_home::describe(target, command),
+        Intent::Wsl { target, text, .. } if text.is_empty() => {
+            format!("run {} in WSL", target.replace('_', " "))
+        }
+        Intent::Wsl { target, text, .. } => {
+            format!("run {} \"{}\" in WSL", target.replace('_', " "), text)
+        }
+        Intent::Dev { target, repo, .. } => dev::describe(target, repo.as_deref()),
+        Intent::Shell {
+            command: Some(command),
+            ..
+        } => format!("run {}", command),
+        Intent::Shell { request, .. } => format!("run a command to {}", request),
+        Intent::Files { target, name, .. } => files::describe(target, name.as_deref()),
+        Intent::Custom { target, text, .. } if text.is_empty() => target.replace('_', " "),
+        Intent::Custom { target, text, .. } => {
+            format!("{} \"{}\"", target.replace('_', " "), text)
+        }
+        Intent::Unknown { .. } => "do that".to_string(),
+    };
+    match intent {
+        Intent::OpenFile {
+            at: Some(FileLocation::Line(line)),
+            ..
+        } => format!("{} at line {}", description, line),
+        Intent::OpenFile {
+            at: Some(FileLocation::Page(page)),
+            ..
+        } => format!("{} at page {}", description, page),
+        _ => description,
+    }
+}
+
+#[derive(Debug)]
+pub enum PipelineError {
+    /// `listen` without a capture stage (`with_microphone`).
+    NoMicrophone,
+    /// `next` without a trigger stage (`with_trigger`).
+    NoTrigger,
+    /// The recording transcribed to nothing.
+    NoSpeech,
+    /// Whisper was less sure of the words than `transcription.min_confidence`.
+    Unclear(f32),
+    Audio(AudioError),
+    Transcription(TranscriptionError),
+    Intent(IntentError),
+    Hotkey(HotkeyError),
+    Join(tokio::task::JoinError),
+}
+
+impl std::fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoMicrophone => write!(f, "no capture stage (see Pipeline::with_microphone)"),
+            Self::NoTrigger => write!(f, "no trigger stage (see Pipeline::with_trigger)"),
+            Self::NoSpeech => write!(f, "no speech detected"),
+            Self::Unclear(confidence) => {
+                write!(f, "transcription too unsure to act on ({:.2})", confidence)
+            }
+            Self::Audio(err) => write!(f, "audio error: {}", err),
+            Self::Transcription(err) => write!(f, "transcription error: {}", err),
+            Self::Intent(err) => write!(f, "intent error: {}", err),
+            Self::Hotkey(err) => write!(f, "hotkey error: {}", err),
+            Self::Join(err) => write!(f, "task failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::NoMicrophone | Self::NoTrigger | Self::NoSpeech | Self::Unclear(_) => None,
+            Self::Audio(err) => Some(err),
+            Self::Transcription(err) => Some(err),
+            Self::Intent(err) => Some(err),
+            Self::Hotkey(err) => Some(err),
+            Self::Join(err) => Some(err),
+        }
+    }
+}
+
+impl From<AudioError> for PipelineError {
+    fn from(err: AudioError) -> Self {
+        Self::Audio(err)
+    }
+}
+
+impl From<TranscriptionError> for PipelineError {
+    fn from(err: TranscriptionError) -> Self {
+        Self::Transcription(err)
+    }
+}
+
+impl From<IntentError> for PipelineError {
+    fn from(err: IntentError) -> Self {
+        Self::Intent(err)
+    }
+}
+
+impl From<HotkeyError> for PipelineError {
+    fn from(err: HotkeyError) -> Self {
+        Self::Hotkey(err)
+    }
+}
+
+impl From<tokio::task::JoinError> for PipelineError {
+    fn from(err: tokio::task::JoinError) -> Self {
+        Self::Join(err)
+    }
+}
@@ -0,0 +1,541 @@
+use crate::{
+    clock,
+    config::{Config, ContextConfig},
+    intent::Intent,
+    rules::EXACT_CONFIDENCE,
+};
+use std::{
+    io::Read,
+    process::{Child, Command, ExitStatus},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Words dropped from around an app name: "close the chrome please".
+const FILLER_WORDS: &[&str] = &["the", "my", "a", "app", "please", "now"];
+
+#[derive(Debug)]
+pub enum ProcessError {
+    #[cfg_attr(windows, allow(dead_code))]
+    Io(std::io::Error),
+    #[cfg(target_os = "windows")]
+    Windows(windows::core::Error),
+    #[cfg_attr(windows, allow(dead_code))]
+    Unsupported(&'static str),
+}
+
+impl std::fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "io error: {}", err),
+            #[cfg(target_os = "windows")]
+            Self::Windows(err) => write!(f, "win32 error: {}", err),
+            Self::Unsupported(msg) => write!(f, "unsupported: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ProcessError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            #[cfg(target_os = "windows")]
+            Self::Windows(err) => Some(err),
+            Self::Unsupported(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Process {
+    pub pid: u32,
+    /// The executable's file name, e.g. "chrome.exe".
+    pub name: String,
+}
+
+/// Recognizes "close chrome", "kill steam", "kill the frozen app" and "is
+/// steam running" for configured applications. Other names are left to the
+/// model, whose close and running targets can also match window titles.
+pub fn recognize(transcript: &str, config: &Config) -> Option<Intent> {
+    let words = clock::words(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let (kind, rest) = match words.as_slice() {
+        ["close" | "quit" | "exit", rest @ ..] => ("close", rest),
+        ["kill" | "end", rest @ ..] | ["force", "quit" | "close", rest @ ..] => ("kill", rest),
+        ["is" | "are", rest @ .., "running" | "open"] => ("running", rest),
+        _ => return None,
+    };
+    let rest: Vec<&str> = rest
+        .iter()
+        .copied()
+        .filter(|word| !FILLER_WORDS.contains(word))
+        .collect();
+    let target = match rest.as_slice() {
+        ["frozen" | "hung" | "stuck"] | ["frozen" | "hung" | "stuck", "window" | "program"]
+            if kind == "kill" =>
+        {
+            "frozen".to_string()
+        }
+        _ => app_key(config, &rest.join(" "))?,
+    };
+    let enabled = match kind {
+        "kill" => config.system.kill_apps,
+        _ => config.system.close_apps,
+    };
+    if !enabled {
+        return None;
+    }
+    let confidence = EXACT_CONFIDENCE;
+    Some(match kind {
+        "close" => Intent::CloseApp { target, confidence },
+        "kill" => Intent::KillApp { target, confidence },
+        _ => Intent::AppRunning { target, confidence },
+    })
+}
+
+fn app_key(config: &Config, phrase: &str) -> Option<String> {
+    config
+        .applications
+        .keys()
+        .find(|key| key.to_lowercase().replace(['_', '-'], " ") == phrase)
+        .cloned()
+}
+
+/// Running processes that belong to `target`: the program its
+/// `applications` entry launches, an executable named like it ("steam"
+/// finds steam.exe), or else windows whose title contains it.
+pub fn find(target: &str, config: &Config) -> Result<Vec<Process>, ProcessError> {
+    let processes = processes()?;
+    let matched = named(target, config, &processes);
+    if !matched.is_empty() {
+        return Ok(matched);
+    }
+    let phrase = target.to_lowercase().replace('_', " ");
+    let pids: Vec<u32> = top_level_windows()
+        .into_iter()
+        .filter(|window| window.title.to_lowercase().contains(&phrase))
+        .map(|window| window.pid)
+        .collect();
+    Ok(processes
+        .into_iter()
+        .filter(|process| pids.contains(&process.pid))
+        .collect())
+}
+
+/// Like `find`, without falling back to window titles, for ending programs:
+/// a document named "chrome notes" mustn't put its editor in the way.
+pub fn find_exact(target: &str, config: &Config) -> Result<Vec<Process>, ProcessError> {
+    Ok(named(target, config, &processes()?))
+}
+
+/// The processes whose executable is `target`'s `applications` program, or
+/// is named like `target` itself.
+fn named(target: &str, config: &Config, processes: &[Process]) -> Vec<Process> {
+    let mut names = vec![target.to_lowercase().replace([' ', '_'], "")];
+    if let Some(command) = config.applications.get(target) {
+        names.push(program_name(command));
+    }
+    processes
+        .iter()
+        .filter(|process| names.contains(&stem(&process.name)))
+        .cloned()
+        .collect()
+}
+
+/// The executable behind the focused window, e.g. "Code.exe"; `None` when
+/// it can't be told.
+pub fn foreground() -> Option<String> {
+    let pid = foreground_pid()?;
+    processes()
+        .ok()?
+        .into_iter()
+        .find(|process| process.pid == pid)
+        .map(|process| process.name)
+}
+
+/// The `[contexts]` entry for the program with the focus, if any. Keys match
+/// the executable without case or extension: "code" or "Code.exe".
+pub fn focused_context(config: &Config) -> Option<&ContextConfig> {
+    // Spares a process snapshot for every command when there are none.
+    if config.contexts.is_empty() {
+        return None;
+    }
+    let program = stem(&foreground()?);
+    config
+        .contexts
+        .iter()
+        .find(|(key, _)| stem(key) == program)
+        .map(|(_, context)| context)
+}
+
+/// Processes with a window that has stopped responding.
+pub fn frozen() -> Result<Vec<Process>, ProcessError> {
+    let pids = hung_pids()?;
+    Ok(processes()?
+        .into_iter()
+        .filter(|process| pids.contains(&process.pid))
+        .collect())
+}
+
+/// Asks `processes` to close, the way clicking their close buttons would,
+/// and waits up to `wait` for them to exit. Returns the ones still running,
+/// for example because they asked to save first.
+pub fn close(processes: &[Process], wait: Duration) -> Vec<Process> {
+    let pids: Vec<u32> = processes.iter().map(|process| process.pid).collect();
+    request_close(&pids);
+    let deadline = Instant::now() + wait;
+    loop {
+        let running: Vec<Process> = processes
+            .iter()
+            .filter(|process| is_alive(process.pid))
+            .cloned()
+            .collect();
+        if running.is_empty() || Instant::now() >= deadline {
+            return running;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// Ends `processes` at once; unsaved work in them is lost.
+pub fn kill(processes: &[Process]) -> Result<(), ProcessError> {
+    processes.iter().try_for_each(|process| terminate(process.pid))
+}
+
+/// A command for `program` that doesn't flash a console window on Windows.
+#[cfg(windows)]
+pub(crate) fn hidden(program: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let mut command = Command::new(program);
+    command.creation_flags(CREATE_NO_WINDOW);
+    command
+}
+
+#[cfg(not(windows))]
+pub(crate) fn hidden(program: &str) -> Command {
+    Command::new(program)
+}
+
+/// `command` run by the shell: `cmd /C` on Windows, `sh -c` elsewhere.
+#[cfg(windows)]
+pub(crate) fn shell(command: &str) -> Command {
+    use std::os::windows::process::CommandExt;
+
+    let mut shell = hidden("cmd");
+    shell.arg("/C").raw_arg(command);
+    shell
+}
+
+#[cfg(not(windows))]
+pub(crate) fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+/// What a program Buddy ran printed, and how it exited.
+pub(crate) struct Output {
+    pub status: ExitStatus,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+/// Waits for `child`, started with its stdout and stderr piped, to exit and
+/// collects what it printed. `None` means it ran past `timeout` and was
+/// stopped.
+pub(crate) fn wait_output(mut child: Child, timeout: Duration) -> std::io::Result<Option<Output>> {
+    // Read while it runs, so a chatty program can't fill the pipe and stall.
+    let stdout = child.stdout.take().map(read_all);
+    let stderr = child.stderr.take().map(read_all);
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+    let collect = |reader: Option<JoinHandle<Vec<u8>>>| {
+        reader
+            .and_then(|reader| reader.join().ok())
+            .unwrap_or_default()
+    };
+    Ok(Some(Output {
+        status,
+        stdout: collect(stdout),
+        stderr: collect(stderr),
+    }))
+}
+
+fn read_all(mut pipe: impl Read + Send + 'static) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = pipe.read_to_end(&mut bytes);
+        bytes
+    })
+}
+
+/// Program output as text, trimmed. Most programs print UTF-8, but some,
+/// like `wsl.exe` ("There is no distribution with the supplied name"),
+/// print UTF-16, and colored output carries escape codes that would
+/// otherwise be read out.
+pub(crate) fn text(bytes: &[u8]) -> String {
+    let utf16 = bytes.len() >= 2 && bytes.iter().skip(1).step_by(2).all(|&byte| byte == 0);
+    let text = if utf16 {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(bytes).into_owned()
+    };
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            plain.push(c);
+            continue;
+        }
+        // An escape sequence: "\x1b[1;32m" and the like, ended by a letter.
+        if chars.next() == Some('[') {
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        }
+    }
+    plain.trim().to_string()
+}
+
+/// The program a launch command starts, lowercase and without extension:
+/// "chrome" for `chrome`, `"C:\Program Files\...\Code.exe"` or `code --new-window`.
+fn program_name(command: &str) -> String {
+    let command = command.trim().trim_matches('"');
+    let lower = command.to_lowercase();
+    let program = match lower.find(".exe") {
+        Some(end) => &command[..end + 4],
+        None => command.split_whitespace().next().unwrap_or_default(),
+    };
+    stem(program)
+}
+
+/// The lowercase file name without extension, whichever way the path's
+/// slashes lean: "code" for `C:\Program Files\Code.exe`.
+fn stem(name: &str) -> String {
+    let file = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let stem = file.rsplit_once('.').map_or(file, |(stem, _)| stem);
+    stem.to_lowercase()
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+struct Window {
+    #[cfg(target_os = "windows")]
+    hwnd: windows::Win32::Foundation::HWND,
+    pid: u32,
+    title: String,
+}
+
+#[cfg(target_os = "windows")]
+fn processes() -> Result<Vec<Process>, ProcessError> {
+    use windows::Win32::{
+        Foundation::CloseHandle,
+        System::Diagnostics::ToolHelp::{
+            CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+            TH32CS_SNAPPROCESS,
+        },
+    };
+
+    unsafe {
+        let snapshot =
+            CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0).map_err(ProcessError::Windows)?;
+        let mut entry = PROCESSENTRY32W {
+            dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+            ..Default::default()
+        };
+        let mut found = Vec::new();
+        let mut more = Process32FirstW(snapshot, &mut entry).is_ok();
+        while more {
+            let name = &entry.szExeFile;
+            let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+            found.push(Process {
+                pid: entry.th32ProcessID,
+                name: String::from_utf16_lossy(&name[..len]),
+            });
+            more = Process32NextW(snapshot, &mut entry).is_ok();
+        }
+        let _ = CloseHandle(snapshot);
+        Ok(found)
+    }
+}
+
+/// `ps` lists every process on Linux and macOS.
+#[cfg(not(target_os = "windows"))]
+fn processes() -> Result<Vec<Process>, ProcessError> {
+    let output = Command::new("ps")
+        .args(["-axo", "pid=,comm="])
+        .output()
+        .map_err(ProcessError::Io)?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (pid, command) = line.trim().split_once(char::is_whitespace)?;
+            // macOS prints the whole path.
+            let name = command.trim().rsplit('/').next()?;
+            Some(Process {
+                pid: pid.parse().ok()?,
+                name: name.to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Visible top-level windows.
+#[cfg(target_os = "windows")]
+fn top_level_windows() -> Vec<Window> {
+    use windows::Win32::{
+        Foundation::{BOOL, HWND, LPARAM, TRUE},
+        UI::WindowsAndMessaging::{
+            EnumWindows, GetWindowTextW, GetWindowThreadProcessId, IsWindowVisible,
+        },
+    };
+
+    unsafe extern "system" fn collect(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let windows = &mut *(lparam.0 as *mut Vec<Window>);
+        if IsWindowVisible(hwnd).as_bool() {
+            let mut title = [0u16; 512];
+            let len = GetWindowTextW(hwnd, &mut title).max(0) as usize;
+            let mut pid = 0;
+            GetWindowThreadProcessId(hwnd, Some(&mut pid));
+            windows.push(Window {
+                hwnd,
+                pid,
+                title: String::from_utf16_lossy(&title[..len]),
+            });
+        }
+        TRUE
+    }
+
+    let mut found: Vec<Window> = Vec::new();
+    unsafe {
+        let _ = EnumWindows(Some(collect), LPARAM(&mut found as *mut Vec<Window> as isize));
+    }
+    found
+}
+
+#[cfg(not(target_os = "windows"))]
+fn top_level_windows() -> Vec<Window> {
+    Vec::new()
+}
+
+#[cfg(target_os = "windows")]
+fn hung_pids() -> Result<Vec<u32>, ProcessError> {
+    use windows::Win32::UI::WindowsAndMessaging::IsHungAppWindow;
+
+    Ok(top_level_windows()
+        .into_iter()
+        .filter(|window| unsafe { IsHungAppWindow(window.hwnd).as_bool() })
+        .map(|window| window.pid)
+        .collect())
+}
+
+#[cfg(target_os = "windows")]
+fn foreground_pid() -> Option<u32> {
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    let mut pid = 0;
+    unsafe {
+        GetWindowThreadProcessId(GetForegroundWindow(), Some(&mut pid));
+    }
+    (pid != 0).then_some(pid)
+}
+
+/// Only Windows is asked which window has the focus.
+#[cfg(not(target_os = "windows"))]
+fn foreground_pid() -> Option<u32> {
+    None
+}
+
+#[cfg(not(target_os = "windows"))]
+fn hung_pids() -> Result<Vec<u32>, ProcessError> {
+    Err(ProcessError::Unsupported("finding frozen apps requires Windows"))
+}
+
+/// Posts WM_CLOSE to every window of `pids`.
+#[cfg(target_os = "windows")]
+fn request_close(pids: &[u32]) {
+    use windows::Win32::{
+        Foundation::{LPARAM, WPARAM},
+        UI::WindowsAndMessaging::{PostMessageW, WM_CLOSE},
+    };
+
+    for window in top_level_windows().into_iter().filter(|window| pids.contains(&window.pid)) {
+        unsafe {
+            let _ = PostMessageW(window.hwnd, WM_CLOSE, WPARAM(0), LPARAM(0));
+        }
+    }
+}
+
+/// Sends SIGTERM, which well-behaved programs treat as a request to quit.
+#[cfg(not(target_os = "windows"))]
+fn request_close(pids: &[u32]) {
+    for &pid in pids {
+        unsafe {
+            libc::kill(pid as libc::pid_t, libc::SIGTERM);
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn is_alive(pid: u32) -> bool {
+    use windows::Win32::{
+        Foundation::{CloseHandle, BOOL, WAIT_TIMEOUT},
+        System::Threading::{OpenProcess, WaitForSingleObject, PROCESS_SYNCHRONIZE},
+    };
+
+    unsafe {
+        let Ok(handle) = OpenProcess(PROCESS_SYNCHRONIZE, BOOL::from(false), pid) else {
+            return false;
+        };
+        let alive = WaitForSingleObject(handle, 0) == WAIT_TIMEOUT;
+        let _ = CloseHandle(handle);
+        alive
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn is_alive(pid: u32) -> bool {
+    unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+}
+
+#[cfg(target_os = "windows")]
+fn terminate(pid: u32) -> Result<(), ProcessError> {
+    use windows::Win32::{
+        Foundation::{CloseHandle, BOOL},
+        System::Threading::{OpenProcess, TerminateProcess, PROCESS_TERMINATE},
+    };
+
+    unsafe {
+        let handle =
+            OpenProcess(PROCESS_TERMINATE, BOOL::from(false), pid).map_err(ProcessError::Windows)?;
+        let result = TerminateProcess(handle, 1).map_err(ProcessError::Windows);
+        let _ = CloseHandle(handle);
+        result
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn terminate(pid: u32) -> Result<(), ProcessError> {
+    if unsafe { libc::kill(pid as libc::pid_t, libc::SIGKILL) } == 0 {
+        Ok(())
+    } else {
+        Err(ProcessError::Io(std::io::Error::last_os_error()))
+    }
+}
@@ -0,0 +1,120 @@
+//! `deepseek.prompt_template`: the intent prompt from a file of the user's,
+//! so it can be translated or reworded without rebuilding Buddy. Each
+//! `{name}` in it is filled in per command.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Everything a template can name.
+pub const PLACEHOLDERS: &[&str] = &[
+    "transcript",
+    "files",
+    "folders",
+    "apps",
+    "urls",
+    "actions",
+    "assistants",
+    "devices",
+    "wsl",
+    "repos",
+    "systems",
+    "most_used",
+    "examples",
+    "learned",
+];
+
+/// Without these the model never hears the command.
+const REQUIRED: &[&str] = &["transcript"];
+
+#[derive(Debug)]
+pub enum TemplateError {
+    Io(PathBuf, io::Error),
+    Missing(PathBuf, &'static str),
+    Unknown(PathBuf, String),
+}
+
+impl std::fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "failed to read {}: {}", path.display(), err),
+            Self::Missing(path, name) => {
+                write!(f, "{} has no {{{}}} placeholder", path.display(), name)
+            }
+            Self::Unknown(path, name) => write!(
+                f,
+                "{} names an unknown placeholder {{{}}} (known: {})",
+                path.display(),
+                name,
+                PLACEHOLDERS.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, err) => Some(err),
+            Self::Missing(..) | Self::Unknown(..) => None,
+        }
+    }
+}
+
+/// Reads the template at `path`, checking that it names the required
+/// placeholders and no unknown ones.
+pub fn load(path: &Path) -> Result<String, TemplateError> {
+    let template = fs::read_to_string(path).map_err(|err| TemplateError::Io(path.into(), err))?;
+    let named = placeholders(&template);
+    if let Some(unknown) = named.iter().find(|name| !PLACEHOLDERS.contains(name)) {
+        return Err(TemplateError::Unknown(path.into(), unknown.to_string()));
+    }
+    if let Some(missing) = REQUIRED.iter().find(|name| !named.contains(name)) {
+        return Err(TemplateError::Missing(path.into(), missing));
+    }
+    Ok(template)
+}
+
+/// `template` with each `{name}` replaced by its value. Values are put in
+/// as they are, so a transcript that says "{files}" stays that way.
+pub fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = placeholder_at(after)
+            .and_then(|name| values.iter().find(|(key, _)| *key == name))
+            .map(|(name, value)| (name.len(), value));
+        match value {
+            Some((len, value)) => {
+                filled.push_str(value);
+                rest = &after[len + 1..];
+            }
+            None => {
+                filled.push('{');
+                rest = after;
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// The names of the `{name}` placeholders in `template`. Other braces, such
+/// as those of JSON examples, are left alone.
+fn placeholders(template: &str) -> Vec<&str> {
+    template
+        .match_indices('{')
+        .filter_map(|(start, _)| placeholder_at(&template[start + 1..]))
+        .collect()
+}
+
+/// The placeholder name `text` starts with, when it is a `name}`.
+fn placeholder_at(text: &str) -> Option<&str> {
+    let end = text.find('}')?;
+    let name = &text[..end];
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_lowercase() || c == '_');
+    valid.then_some(name)
+}
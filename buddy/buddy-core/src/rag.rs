@@ -0,0 +1,441 @@
+//! `[rag]`: answers from the user's own documents. Updating the index splits
+//! the text, Markdown and PDF files under `rag.folders` into passages and has
+//! Ollama embed each one; the passages most like a command then go into its
+//! intent prompt, so "what does my contract say about the notice period" is
+//! answered from the contract. The index is kept as JSON at `rag.index_path`.
+
+use crate::{
+    config::{Config, RagConfig},
+    intent::{IntentClient, IntentError},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Read as they are; PDFs go through `pdftotext`.
+const TEXT_EXTENSIONS: &[&str] = &["txt", "md", "markdown"];
+/// Stops a folder such as a whole drive from being indexed by mistake.
+const MAX_FILES: usize = 5000;
+/// Passages sent in one embed request.
+const BATCH: usize = 32;
+/// Shortest `rag.chunk_chars` honoured; shorter passages say too little.
+const MIN_CHUNK_CHARS: usize = 200;
+
+/// Set while an update runs, so two can't write the index at once.
+static UPDATING: AtomicBool = AtomicBool::new(false);
+/// The index as last read, with the file's modification time, so that each
+/// command doesn't parse it again.
+static LOADED: Mutex<Option<(PathBuf, SystemTime, Arc<Index>)>> = Mutex::new(None);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Index {
+    /// Embeddings from different models can't be compared, so changing
+    /// `rag.embed_model` embeds everything again.
+    #[serde(default)]
+    model: String,
+    #[serde(default)]
+    documents: BTreeMap<PathBuf, Document>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Document {
+    /// Seconds since the epoch; a file modified since is indexed again.
+    modified: u64,
+    passages: Vec<Passage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Passage {
+    text: String,
+    embedding: Vec<f32>,
+}
+
+/// What an update did, file by file.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Update {
+    pub indexed: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    /// Files that could not be read.
+    pub skipped: usize,
+}
+
+impl std::fmt::Display for Update {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} indexed, {} unchanged, {} removed, {} skipped",
+            self.indexed, self.unchanged, self.removed, self.skipped
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum RagError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+    Intent(IntentError),
+    /// `deepseek.endpoint` isn't Ollama's and `rag.embed_endpoint` is unset.
+    NoEndpoint,
+    /// Another update is still running.
+    Busy,
+}
+
+impl std::fmt::Display for RagError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "failed to access {}: {}", path.display(), err),
+            Self::Parse(path, err) => write!(f, "failed to read {}: {}", path.display(), err),
+            Self::Intent(err) => write!(f, "embedding failed: {}", err),
+            Self::NoEndpoint => write!(f, "rag.embed_endpoint is not set"),
+            Self::Busy => write!(f, "the document index is already being updated"),
+        }
+    }
+}
+
+impl std::error::Error for RagError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, err) => Some(err),
+            Self::Parse(_, err) => Some(err),
+            Self::Intent(err) => Some(err),
+            Self::NoEndpoint | Self::Busy => None,
+        }
+    }
+}
+
+impl From<IntentError> for RagError {
+    fn from(err: IntentError) -> Self {
+        Self::Intent(err)
+    }
+}
+
+/// Clears `UPDATING` however the update ends.
+struct Updating;
+
+impl Drop for Updating {
+    fn drop(&mut self) {
+        UPDATING.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Brings the index up to date with `rag.folders`: new and modified files
+/// are embedded, files that are gone are dropped and the rest are kept as
+/// they are. What was embedded before a failure is saved all the same.
+pub async fn update(config: &Config, client: &IntentClient) -> Result<Update, RagError> {
+    if UPDATING.swap(true, Ordering::Relaxed) {
+        return Err(RagError::Busy);
+    }
+    let _updating = Updating;
+    let rag = &config.rag;
+    let endpoint = endpoint(rag, client)?;
+    let path = &rag.index_path;
+    let mut index = read(path)?;
+    if index.model != rag.embed_model {
+        index.documents.clear();
+        index.model = rag.embed_model.clone();
+    }
+    let files = documents(&rag.folders);
+    let found: HashSet<&Path> = files.iter().map(|(file, _)| file.as_path()).collect();
+    let before = index.documents.len();
+    index.documents.retain(|file, _| found.contains(file.as_path()));
+    let mut update = Update {
+        removed: before - index.documents.len(),
+        ..Update::default()
+    };
+    let result = embed_documents(&mut index, &files, &endpoint, rag, client, &mut update).await;
+    write(path, &index)?;
+    result.map(|()| update)
+}
+
+async fn embed_documents(
+    index: &mut Index,
+    files: &[(PathBuf, u64)],
+    endpoint: &str,
+    rag: &RagConfig,
+    client: &IntentClient,
+    update: &mut Update,
+) -> Result<(), RagError> {
+    let mut missing_pdftotext = 0;
+    for (file, modified) in files {
+        if index.documents.get(file).is_some_and(|doc| doc.modified == *modified) {
+            update.unchanged += 1;
+            continue;
+        }
+        let text = match extract(file) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound && is_pdf(file) => {
+                missing_pdftotext += 1;
+                update.skipped += 1;
+                continue;
+            }
+            Err(err) => {
+                eprintln!("Skipping {}: {}", file.display(), err);
+                update.skipped += 1;
+                continue;
+            }
+        };
+        println!("Indexing {}", file.display());
+        // The file name goes into each embedding, so "my contract" finds
+        // passages of contract.pdf that never say "contract".
+        let name = display_name(file);
+        let texts = split(&text, rag.chunk_chars);
+        let mut passages = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(BATCH) {
+            let input: Vec<String> = batch.iter().map(|text| format!("{}: {}", name, text)).collect();
+            let embeddings = client.embed(endpoint, &rag.embed_model, &input).await?;
+            passages.extend(
+                batch
+                    .iter()
+                    .zip(embeddings)
+                    .map(|(text, embedding)| Passage {
+                        text: text.clone(),
+                        embedding,
+                    }),
+            );
+        }
+        index.documents.insert(
+            file.clone(),
+            Document {
+                modified: *modified,
+                passages,
+            },
+        );
+        update.indexed += 1;
+    }
+    if missing_pdftotext > 0 {
+        eprintln!(
+            "Skipped {} PDFs: install pdftotext (poppler-utils) to index them",
+            missing_pdftotext
+        );
+    }
+    Ok(())
+}
+
+/// The indexed passages most like `question`, one per line under their file
+/// name, or `None` when none is similar enough.
+pub async fn context(
+    question: &str,
+    config: &Config,
+    client: &IntentClient,
+) -> Result<Option<String>, RagError> {
+    let rag = &config.rag;
+    let index = load(&rag.index_path)?;
+    if index.documents.is_empty() {
+        return Ok(None);
+    }
+    if index.model != rag.embed_model {
+        eprintln!(
+            "The document index was built with {}; update it to use {}",
+            index.model, rag.embed_model
+        );
+        return Ok(None);
+    }
+    let endpoint = endpoint(rag, client)?;
+    let query = client
+        .embed(&endpoint, &rag.embed_model, &[question.to_string()])
+        .await?
+        .into_iter()
+        .next()
+        .unwrap_or_default();
+    let mut scored: Vec<(f32, &Path, &str)> = index
+        .documents
+        .iter()
+        .flat_map(|(file, doc)| doc.passages.iter().map(move |passage| (file, passage)))
+        .map(|(file, passage)| {
+            let score = similarity(&query, &passage.embedding);
+            (score, file.as_path(), passage.text.as_str())
+        })
+        .filter(|(score, ..)| *score >= rag.min_score)
+        .collect();
+    if scored.is_empty() {
+        return Ok(None);
+    }
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.truncate(rag.top_k);
+    let lines: Vec<String> = scored
+        .iter()
+        .map(|(_, file, text)| format!("[{}] {}", display_name(file), text))
+        .collect();
+    Ok(Some(lines.join("\n")))
+}
+
+fn endpoint(rag: &RagConfig, client: &IntentClient) -> Result<String, RagError> {
+    rag.embed_endpoint
+        .clone()
+        .or_else(|| client.api_endpoint("embed"))
+        .ok_or(RagError::NoEndpoint)
+}
+
+/// Text and PDF files under `folders` and their subfolders, with their
+/// modification times. Hidden folders are left out.
+fn documents(folders: &[PathBuf]) -> Vec<(PathBuf, u64)> {
+    let mut found = Vec::new();
+    let mut pending: Vec<PathBuf> = folders.to_vec();
+    while let Some(dir) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                eprintln!("Cannot read {}: {}", dir.display(), err);
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                if !entry.file_name().to_string_lossy().starts_with('.') {
+                    pending.push(path);
+                }
+                continue;
+            }
+            if !is_pdf(&path) && !has_extension(&path, TEXT_EXTENSIONS) {
+                continue;
+            }
+            if found.len() == MAX_FILES {
+                eprintln!("Indexing only the first {} documents", MAX_FILES);
+                return found;
+            }
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |age| age.as_secs());
+            found.push((path, modified));
+        }
+    }
+    found
+}
+
+fn extract(path: &Path) -> io::Result<String> {
+    if !is_pdf(path) {
+        return fs::read(path).map(|bytes| String::from_utf8_lossy(&bytes).into_owned());
+    }
+    let output = Command::new("pdftotext")
+        .args(["-q", "-enc", "UTF-8"])
+        .arg(path)
+        .arg("-")
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!("pdftotext failed ({})", output.status)));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `text` in passages of at most `max_chars`, broken between paragraphs
+/// where possible and otherwise between words.
+fn split(text: &str, max_chars: usize) -> Vec<String> {
+    let max_chars = max_chars.max(MIN_CHUNK_CHARS);
+    let text = text.replace("\r\n", "\n");
+    let mut passages = Vec::new();
+    let mut current = String::new();
+    let mut len = 0;
+    for paragraph in text.split("\n\n") {
+        let words: Vec<&str> = paragraph.split_whitespace().collect();
+        if words.is_empty() {
+            continue;
+        }
+        // A paragraph that fits in a passage of its own isn't broken up.
+        let paragraph_len: usize = words.iter().map(|word| word.chars().count() + 1).sum();
+        if len > 0 && len + paragraph_len > max_chars {
+            passages.push(std::mem::take(&mut current));
+            len = 0;
+        }
+        let mut separator = '\n';
+        for word in words {
+            let word_len = word.chars().count();
+            if len > 0 && len + 1 + word_len > max_chars {
+                passages.push(std::mem::take(&mut current));
+                len = 0;
+            }
+            if len > 0 {
+                current.push(separator);
+                len += 1;
+            }
+            current.push_str(word);
+            len += word_len;
+            separator = ' ';
+        }
+    }
+    if !current.is_empty() {
+        passages.push(current);
+    }
+    passages
+}
+
+/// Cosine similarity; 0 for embeddings that can't be compared.
+fn similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+fn is_pdf(path: &Path) -> bool {
+    has_extension(path, &["pdf"])
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.iter().any(|known| ext.eq_ignore_ascii_case(known)))
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// The index at `path`, read again only when the file has changed.
+fn load(path: &Path) -> Result<Arc<Index>, RagError> {
+    let modified = match fs::metadata(path).and_then(|metadata| metadata.modified()) {
+        Ok(modified) => modified,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Arc::default()),
+        Err(err) => return Err(RagError::Io(path.to_path_buf(), err)),
+    };
+    let mut loaded = LOADED.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some((cached, time, index)) = loaded.as_ref() {
+        if cached == path && *time == modified {
+            return Ok(Arc::clone(index));
+        }
+    }
+    let index = Arc::new(read(path)?);
+    *loaded = Some((path.to_path_buf(), modified, Arc::clone(&index)));
+    Ok(index)
+}
+
+fn read(path: &Path) -> Result<Index, RagError> {
+    match fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text).map_err(|err| RagError::Parse(path.to_path_buf(), err)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Index::default()),
+        Err(err) => Err(RagError::Io(path.to_path_buf(), err)),
+    }
+}
+
+fn write(path: &Path, index: &Index) -> Result<(), RagError> {
+    let text =
+        serde_json::to_string(index).map_err(|err| RagError::Parse(path.to_path_buf(), err))?;
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|err| RagError::Io(parent.to_path_buf(), err))?;
+    }
+    fs::write(path, text).map_err(|err| RagError::Io(path.to_path_buf(), err))
+}
@@ -0,0 +1,427 @@
+use crate::{
+    clock,
+    intent::Intent,
+    rules::EXACT_CONFIDENCE,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// How often the scheduler looks for reminders that are due.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+/// "Remind me tomorrow to ..." without a time.
+const DEFAULT_MINUTES: u32 = 9 * 60;
+
+/// Keeps the executor and the scheduler from interleaving a read and a
+/// write of the store.
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Everything saved by "remind me" and "take a note", kept as JSON at
+/// `reminders.path`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Store {
+    #[serde(default)]
+    pub reminders: Vec<Reminder>,
+    #[serde(default)]
+    pub notes: Vec<Note>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reminder {
+    pub text: String,
+    /// Unix seconds.
+    pub due: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Note {
+    pub text: String,
+    /// Unix seconds.
+    pub created: i64,
+}
+
+#[derive(Debug)]
+pub enum ReminderError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+}
+
+impl std::fmt::Display for ReminderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "failed to access {}: {}", path.display(), err),
+            Self::Parse(path, err) => write!(f, "failed to read {}: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for ReminderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, err) => Some(err),
+            Self::Parse(_, err) => Some(err),
+        }
+    }
+}
+
+/// Reads the store; a missing file is an empty one.
+pub fn load(path: &Path) -> Result<Store, ReminderError> {
+    let _guard = STORE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    read(path)
+}
+
+/// Applies `change` to the store at `path` and saves it.
+pub fn update<T>(path: &Path, change: impl FnOnce(&mut Store) -> T) -> Result<T, ReminderError> {
+    let _guard = STORE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let mut store = read(path)?;
+    let result = change(&mut store);
+    let text = serde_json::to_string_pretty(&store)
+        .map_err(|err| ReminderError::Parse(path.to_path_buf(), err))?;
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|err| ReminderError::Io(parent.to_path_buf(), err))?;
+    }
+    fs::write(path, text).map_err(|err| ReminderError::Io(path.to_path_buf(), err))?;
+    Ok(result)
+}
+
+fn read(path: &Path) -> Result<Store, ReminderError> {
+    match fs::read_to_string(path) {
+        Ok(text) => {
+            serde_json::from_str(&text).map_err(|err| ReminderError::Parse(path.to_path_buf(), err))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Store::default()),
+        Err(err) => Err(ReminderError::Io(path.to_path_buf(), err)),
+    }
+}
+
+/// Checks the store every few seconds and sends each reminder that has
+/// come due to `tx`, removing it from the store. Reminders that fell due
+/// while Buddy wasn't running are sent on the first check. The path is the
+/// one configured at startup.
+pub fn spawn_scheduler(path: PathBuf, tx: UnboundedSender<Reminder>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        // Only report a broken store once rather than on every check.
+        let mut last_error = None;
+        loop {
+            interval.tick().await;
+            let now = clock::timestamp();
+            let due = load(&path).and_then(|store| {
+                if store.reminders.iter().any(|reminder| reminder.due <= now) {
+                    update(&path, |store| take_due(store, now))
+                } else {
+                    Ok(Vec::new())
+                }
+            });
+            let due = match due {
+                Ok(due) => {
+                    last_error = None;
+                    due
+                }
+                Err(err) => {
+                    let message = err.to_string();
+                    if last_error.as_ref() != Some(&message) {
+                        eprintln!("Reminder check failed: {}", message);
+                        last_error = Some(message);
+                    }
+                    continue;
+                }
+            };
+            for reminder in due {
+                if tx.send(reminder).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+}
+
+fn take_due(store: &mut Store, now: i64) -> Vec<Reminder> {
+    let (due, later) = store
+        .reminders
+        .drain(..)
+        .partition(|reminder| reminder.due <= now);
+    store.reminders = later;
+    due
+}
+
+/// Recognizes "remind me to call Bob at 3pm", "take a note: ...", and
+/// "read my notes" / "what are my reminders".
+pub fn recognize(transcript: &str) -> Option<Intent> {
+    let tokens: Vec<&str> = transcript.split_whitespace().collect();
+    let words: Vec<String> = tokens.iter().map(|token| word(token)).collect();
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    if let ["remind", "me", ..] = words.as_slice() {
+        let (text, when) = split_reminder(&tokens[2..], &words[2..]);
+        return Some(Intent::Remind {
+            text,
+            when,
+            confidence: EXACT_CONFIDENCE,
+        });
+    }
+    let note = match words.as_slice() {
+        ["take" | "make", "a", "note", ..] => 3,
+        ["note" | "remember", "that", ..] => 1,
+        ["note" | "jot" | "write", "down", ..] => 2,
+        _ => 0,
+    };
+    if note > 0 {
+        let start = if words.get(note) == Some(&"that") { note + 1 } else { note };
+        let text = phrase(&tokens[start..]);
+        return (!text.is_empty()).then_some(Intent::Note {
+            text,
+            confidence: EXACT_CONFIDENCE,
+        });
+    }
+    let target = match words.as_slice() {
+        [first, .., last @ ("notes" | "reminders")]
+            if words.len() <= 6
+                && matches!(
+                    *first,
+                    "read" | "what" | "whats" | "list" | "show" | "tell" | "do" | "any"
+                ) =>
+        {
+            *last
+        }
+        _ => return None,
+    };
+    Some(Intent::List {
+        target: target.to_string(),
+        confidence: EXACT_CONFIDENCE,
+    })
+}
+
+/// Splits "to call Bob at 3pm" or "at 3pm to call Bob" into what to be
+/// reminded of and when; `when` is empty if no time was understood.
+fn split_reminder(tokens: &[&str], words: &[&str]) -> (String, String) {
+    let now = clock::timestamp();
+    // "at 3pm to call Bob", "tomorrow to pay rent".
+    if let Some(to) = words.iter().position(|word| *word == "to") {
+        let when = phrase(&tokens[..to]);
+        if to > 0 && due(&when, now).is_some() {
+            return (phrase(&tokens[to + 1..]), when);
+        }
+    }
+    let start = usize::from(matches!(words.first(), Some(&("to" | "that"))));
+    // The earliest split whose tail reads as a time: "call Bob | at 3pm".
+    for split in start + 1..tokens.len() {
+        let when = phrase(&tokens[split..]);
+        if due(&when, now).is_some() {
+            return (phrase(&tokens[start..split]), when);
+        }
+    }
+    (phrase(&tokens[start..]), String::new())
+}
+
+/// When a reminder phrase falls due: "in 10 minutes", "in half an hour",
+/// "at 3pm", "at 9:30 tomorrow", "on Friday at 5", "tonight at 8", or just
+/// "tomorrow" (9 AM). A time without AM or PM is the next one to come.
+pub fn due(when: &str, now: i64) -> Option<i64> {
+    let words = clock::words(when);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    if let ["in", amount @ ..] = words.as_slice() {
+        return duration(amount).map(|secs| now + secs);
+    }
+    let (today, secs) = clock::local_from_utc(now);
+    let mut day = None;
+    let mut tonight = false;
+    let mut rest = Vec::new();
+    for word in words {
+        if clock::is_day(word) {
+            day = Some(clock::resolve_day(word, today)?);
+            tonight |= word == "tonight";
+        } else if !matches!(word, "on" | "at" | "this" | "o" | "clock" | "oclock") {
+            rest.push(word);
+        }
+    }
+    let minutes = match time_of_day(&rest)? {
+        Some((minutes, true)) => minutes,
+        Some((minutes, false)) => {
+            let hour = minutes / 60;
+            let later = minutes + 12 * 60;
+            match day {
+                _ if hour == 0 || hour >= 12 => minutes,
+                _ if tonight => later,
+                // Nobody means 3 AM on Friday.
+                Some(day) if day != today => {
+                    if hour < 7 {
+                        later
+                    } else {
+                        minutes
+                    }
+                }
+                // Today: the earliest of the two still to come.
+                _ if minutes * 60 > secs => minutes,
+                _ if later * 60 > secs => later,
+                _ => minutes,
+            }
+        }
+        None => {
+            day?;
+            DEFAULT_MINUTES
+        }
+    };
+    let day = match day {
+        Some(day) => day,
+        // An hour already gone today means tomorrow.
+        None if minutes * 60 <= secs => today.add_days(1),
+        None => today,
+    };
+    let due = clock::utc_from_local(day, minutes * 60);
+    (due > now).then_some(due)
+}
+
+/// Minutes after midnight and whether AM or PM was given; `Some(None)`
+/// when there are no words and `None` when they aren't a time.
+fn time_of_day(words: &[&str]) -> Option<Option<(u32, bool)>> {
+    let mut words = words.to_vec();
+    // Whisper writes "3pm" and "3:30pm" as one word at times.
+    if let Some(last) = words.last().copied() {
+        for suffix in ["am", "pm"] {
+            let number = last
+                .strip_suffix(suffix)
+                .filter(|number| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()));
+            if let Some(number) = number {
+                words.pop();
+                words.extend([number, suffix]);
+            }
+        }
+    }
+    let (time, pm) = match words.as_slice() {
+        [] => return Some(None),
+        ["noon" | "midday"] => return Some(Some((12 * 60, true))),
+        ["midnight"] => return Some(Some((0, true))),
+        [time @ .., "am"] | [time @ .., "a", "m"] | [time @ .., "in", "the", "morning"] => {
+            (time, Some(false))
+        }
+        [time @ .., "pm"] | [time @ .., "p", "m"] | [time @ .., "in", "the", "afternoon"]
+        | [time @ .., "in", "the", "evening"] => (time, Some(true)),
+        time => (time, None),
+    };
+    let (hour, minute) = match time {
+        [hour] => (*hour, None),
+        [hour, minute] => (*hour, Some(*minute)),
+        _ => return None,
+    };
+    let hour: u32 = hour.parse().ok().filter(|hour| *hour < 24)?;
+    let minute: u32 = match minute {
+        Some(minute) => minute.parse().ok().filter(|minute| *minute < 60)?,
+        None => 0,
+    };
+    let hour = match pm {
+        Some(_) if hour == 0 || hour > 12 => return None,
+        Some(true) => hour % 12 + 12,
+        Some(false) => hour % 12,
+        None => hour,
+    };
+    Some(Some((hour * 60 + minute, pm.is_some())))
+}
+
+/// "10 minutes", "an hour", "half an hour", "2 days".
+fn duration(words: &[&str]) -> Option<i64> {
+    let (count, unit) = match words {
+        ["half", "an", "hour"] => return Some(30 * 60),
+        [count, unit] => (number(count)?, *unit),
+        _ => return None,
+    };
+    let unit = match unit.trim_end_matches('s') {
+        "second" | "sec" => 1,
+        "minute" | "min" => 60,
+        "hour" | "hr" => 3600,
+        "day" => 86_400,
+        _ => return None,
+    };
+    Some(count * unit)
+}
+
+fn number(word: &str) -> Option<i64> {
+    let small = [
+        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "eleven",
+        "twelve",
+    ];
+    match word {
+        "a" | "an" => Some(1),
+        "fifteen" => Some(15),
+        "twenty" => Some(20),
+        "thirty" => Some(30),
+        "forty" => Some(40),
+        "fifty" => Some(50),
+        _ => word.parse().ok().filter(|count| *count > 0).or_else(|| {
+            small
+                .iter()
+                .position(|name| *name == word)
+                .map(|index| index as i64 + 1)
+        }),
+    }
+}
+
+/// "at 3 PM", "tomorrow at 9 AM", or "on Friday, March 7 at 5 PM".
+pub fn spoken_due(due: i64, now: i64) -> String {
+    let (day, secs) = clock::local_from_utc(due);
+    let today = clock::local_from_utc(now).0;
+    let time = clock::spoken_time(secs / 60);
+    match day.days() - today.days() {
+        0 => format!("at {}", time),
+        _ => format!("{} at {}", clock::spoken_day(day, today), time),
+    }
+}
+
+/// "You have 2 reminders: call Bob at 3 PM, and pay rent on Friday, ...".
+pub fn spoken_reminders(reminders: &[Reminder], now: i64) -> String {
+    let mut reminders: Vec<&Reminder> = reminders.iter().collect();
+    reminders.sort_by_key(|reminder| reminder.due);
+    let items: Vec<String> = reminders
+        .iter()
+        .map(|reminder| format!("{} {}", reminder.text, spoken_due(reminder.due, now)))
+        .collect();
+    match items.as_slice() {
+        [] => "You have no reminders".to_string(),
+        [only] => format!("You have one reminder: {}", only),
+        [first @ .., last] => {
+            format!("You have {} reminders: {}, and {}", items.len(), first.join(", "), last)
+        }
+    }
+}
+
+/// "You have 2 notes. Buy milk. Call the bank about the card."
+pub fn spoken_notes(notes: &[Note]) -> String {
+    let mut text = match notes.len() {
+        0 => return "You have no notes".to_string(),
+        1 => "You have one note.".to_string(),
+        count => format!("You have {} notes.", count),
+    };
+    for note in notes {
+        text.push(' ');
+        text.push_str(note.text.trim_end_matches('.'));
+        text.push('.');
+    }
+    text
+}
+
+/// Whether `due` understands `when`, for checking an intent the model
+/// returned.
+pub fn is_when(when: &str) -> bool {
+    due(when, clock::timestamp()).is_some()
+}
+
+/// Lowercase letters and digits of one token: "note:" is "note".
+fn word(token: &str) -> String {
+    token
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Joins tokens back into text, without punctuation at either end.
+fn phrase(tokens: &[&str]) -> String {
+    tokens
+        .join(" ")
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_string()
+}
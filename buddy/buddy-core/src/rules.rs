@@ -0,0 +1,662 @@
+use crate::{
+    clock,
+    config::{Config, ExportFormat},
+    executor,
+    intent::{FileLocation, Intent},
+    numbers, process,
+    usage::Ranking,
+};
+
+pub const EXACT_CONFIDENCE: f32 = 1.0;
+const PARTIAL_CONFIDENCE: f32 = 0.6;
+
+const FILLER_WORDS: &[&str] = &["please", "the", "my", "a", "computer", "pc", "now"];
+/// Words that make a volume change smaller without changing what it is:
+/// "turn it down a bit" is a plain step down.
+const SOFTENERS: &[&str] = &["little bit", "bit", "little", "touch", "slightly", "tad"];
+
+/// Offline pattern matcher over the configured file, app, and system keys.
+/// Exact phrases ("open resume", "mute") score 1.0; keys found inside a longer
+/// sentence score lower so callers can decide how far to trust them.
+pub fn infer_intent(transcript: &str, config: &Config) -> Option<Intent> {
+    let words = normalize(transcript);
+    if words.is_empty() {
+        return None;
+    }
+    if let Some(intent) = match_system(&words, config) {
+        return Some(intent);
+    }
+    let (verb, rest) = words.split_first()?;
+    let prefer_app = match verb.as_str() {
+        "open" | "show" => false,
+        "launch" | "start" | "run" => true,
+        _ => return None,
+    };
+    let mut rest: Vec<&str> = rest
+        .iter()
+        .map(String::as_str)
+        .filter(|word| !FILLER_WORDS.contains(word))
+        .collect();
+    let at = take_location(&mut rest);
+    if rest.is_empty() {
+        return None;
+    }
+    let phrase = rest.join(" ");
+    // Of several partial matches, the most used wins.
+    let ranking = Ranking::load(&config.usage);
+    let files = ranking.sorted("open_file", config.files.keys());
+    let apps = ranking.sorted("open_app", config.applications.keys());
+    let file = find_key(files.iter(), &phrase).map(|(target, exact)| Intent::OpenFile {
+        target,
+        query: None,
+        at,
+        confidence: score(exact),
+    });
+    let app = find_key(apps.iter(), &phrase).map(|(target, exact)| {
+        Intent::OpenApp {
+            target,
+            confidence: score(exact),
+        }
+    });
+    if prefer_app {
+        app.or(file)
+    } else {
+        file.or(app)
+    }
+}
+
+/// "Save" or "press save" for a `[sendkeys]` name, the focused program's
+/// first. Runs as a custom action, so a command of the same name wins.
+pub fn recognize_keys(transcript: &str, config: &Config) -> Option<Intent> {
+    if config.sendkeys.is_empty()
+        && config.contexts.values().all(|context| context.sendkeys.is_empty())
+    {
+        return None;
+    }
+    let words = normalize(transcript);
+    let words = match words.as_slice() {
+        [press, rest @ ..] if press == "press" => rest,
+        words => words,
+    };
+    let phrase = words.join(" ");
+    let context = process::focused_context(config);
+    let target = context
+        .into_iter()
+        .flat_map(|context| context.sendkeys.keys())
+        .chain(config.sendkeys.keys())
+        .find(|name| name.to_lowercase().replace('_', " ") == phrase)?;
+    Some(Intent::Custom {
+        target: target.clone(),
+        text: String::new(),
+        confidence: EXACT_CONFIDENCE,
+    })
+}
+
+/// Pulls "at line 40" / "on page 3" out of the words so the rest can still
+/// match a file key.
+fn take_location(words: &mut Vec<&str>) -> Option<FileLocation> {
+    let index = words.windows(2).position(|pair| {
+        matches!(pair[0], "line" | "page") && pair[1].parse::<u32>().is_ok_and(|n| n > 0)
+    })?;
+    let number = words[index + 1].parse().ok()?;
+    let location = if words[index] == "line" {
+        FileLocation::Line(number)
+    } else {
+        FileLocation::Page(number)
+    };
+    let start = if index > 0 && matches!(words[index - 1], "at" | "on" | "to") {
+        index - 1
+    } else {
+        index
+    };
+    words.drain(start..index + 2);
+    Some(location)
+}
+
+fn match_system(words: &[String], config: &Config) -> Option<Intent> {
+    let phrase = words
+        .iter()
+        .map(String::as_str)
+        .filter(|word| !FILLER_WORDS.contains(word))
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("wi-fi", "wifi")
+        .replace("wi fi", "wifi");
+    let target = match without_softeners(&phrase) {
+        "mute" | "mute volume" | "mute audio" | "mute sound" | "unmute" | "unmute volume"
+        | "shush" | "shoosh" => "volume_mute",
+        "volume up" | "louder" | "turn volume up" | "turn up volume" | "turn it up"
+        | "raise volume" => "volume_up",
+        "volume down" | "quieter" | "turn volume down" | "turn down volume" | "turn it down"
+        | "lower volume" => "volume_down",
+        "sleep" | "go to sleep" | "suspend" => "sleep",
+        "shutdown" | "shut down" | "power off" => "shutdown",
+        "restart" | "reboot" => "restart",
+        "lock" | "lock screen" | "lock workstation" => "lock",
+        "mute mic" | "mute microphone" | "turn off mic" | "turn off microphone" => "mic_mute",
+        "unmute mic" | "unmute microphone" | "turn on mic" | "turn on microphone" => "mic_unmute",
+        "is mic muted" | "is microphone muted" | "is mic on" | "is microphone on" => "mic_state",
+        "turn off screen" | "screen off" | "turn off display" | "turn off monitor"
+        | "turn off monitors" => "screen_off",
+        "extend displays" | "extend display" | "extend screens" => "display_extend",
+        "duplicate displays" | "duplicate display" | "duplicate screen" | "mirror displays"
+        | "mirror screen" => "display_duplicate",
+        // "PC screen only"; "pc" is a filler word.
+        "screen only" | "only screen" | "laptop screen only" => "display_internal",
+        "second screen only" | "only second screen" | "external screen only" => "display_external",
+        "rotate screen" | "rotate display" => "rotate_screen",
+        "rotate screen back" | "reset screen rotation" | "rotate display back" => "rotate_screen_0",
+        "turn on bluetooth" | "turn bluetooth on" | "bluetooth on" | "enable bluetooth" => {
+            "bluetooth_on"
+        }
+        "turn off bluetooth" | "turn bluetooth off" | "bluetooth off" | "disable bluetooth" => {
+            "bluetooth_off"
+        }
+        "is bluetooth on" | "is bluetooth off" | "is bluetooth enabled" => "bluetooth_state",
+        "turn on wifi" | "turn wifi on" | "wifi on" | "enable wifi" => "wifi_on",
+        "turn off wifi" | "turn wifi off" | "wifi off" | "disable wifi" => "wifi_off",
+        "is wifi on" | "is wifi off" | "is wifi enabled" => "wifi_state",
+        "airplane mode" | "airplane mode on" | "turn on airplane mode" | "turn airplane mode on"
+        | "enable airplane mode" => "airplane_mode_on",
+        "airplane mode off" | "turn off airplane mode" | "turn airplane mode off"
+        | "disable airplane mode" => "airplane_mode_off",
+        "switch to high performance" | "high performance mode" | "high performance"
+        | "switch to high performance mode" | "high performance power plan" => {
+            "power_plan_high_performance"
+        }
+        "switch to balanced" | "balanced mode" | "switch to balanced mode"
+        | "balanced power plan" => "power_plan_balanced",
+        "switch to power saver" | "power saver" | "power saver mode" | "power saving mode" => {
+            "power_plan_power_saver"
+        }
+        "battery status" | "battery level" | "battery" | "how much battery" | "how s battery"
+        | "how is battery" | "how much battery is left" | "how much battery left" => {
+            "battery_status"
+        }
+        "what s volume" | "what is volume" | "what volume" | "what s volume level"
+        | "what is volume level" | "volume level" | "current volume" | "how loud is it" => {
+            "volume_query"
+        }
+        _ => {
+            // "set volume to fifty percent".
+            let words = normalize(&numbers::normalize(&words.join(" ")));
+            return match_volume_set(&words, config).or_else(|| match_volume_adjust(&words, config));
+        }
+    };
+    if !config
+        .system_actions()
+        .contains(&executor::system_action_name(target))
+    {
+        return None;
+    }
+    Some(Intent::System {
+        target: target.to_string(),
+        confidence: EXACT_CONFIDENCE,
+    })
+}
+
+/// `phrase` without a softener before or after it ("bit louder", "turn it
+/// down little bit").
+fn without_softeners(phrase: &str) -> &str {
+    let phrase = SOFTENERS
+        .iter()
+        .find_map(|softener| {
+            phrase
+                .strip_prefix(softener)
+                .and_then(|rest| rest.strip_prefix(' '))
+        })
+        .unwrap_or(phrase);
+    SOFTENERS
+        .iter()
+        .find_map(|softener| {
+            phrase
+                .strip_suffix(softener)
+                .and_then(|rest| rest.strip_suffix(' '))
+        })
+        .unwrap_or(phrase)
+}
+
+/// "Set volume to 50", "volume to half".
+fn match_volume_set(words: &[String], config: &Config) -> Option<Intent> {
+    if !config.system.volume_set || !words.iter().any(|word| word == "volume") {
+        return None;
+    }
+    if !words.iter().any(|word| word == "set" || word == "to") {
+        return None;
+    }
+    let level = words.iter().find_map(|word| match word.as_str() {
+        "quarter" => Some(25),
+        "half" => Some(50),
+        "full" | "max" | "maximum" => Some(100),
+        word => percent(word),
+    })?;
+    Some(Intent::System {
+        target: format!("volume_set_{}", level.min(100)),
+        confidence: EXACT_CONFIDENCE,
+    })
+}
+
+/// "Turn it up by 20", "volume down 5 percent".
+fn match_volume_adjust(words: &[String], config: &Config) -> Option<Intent> {
+    let has = |names: &[&str]| words.iter().any(|word| names.contains(&word.as_str()));
+    let up = has(&["up", "louder", "raise"]);
+    if up == has(&["down", "quieter", "lower"]) || !has(&["volume", "it", "louder", "quieter"]) {
+        return None;
+    }
+    // "Turn it up to 80" names a level, not a change.
+    if has(&["to"]) {
+        return None;
+    }
+    let amount = words.iter().find_map(|word| percent(word))?;
+    let name = if up { "volume_up" } else { "volume_down" };
+    if !config.system_actions().contains(&name) {
+        return None;
+    }
+    Some(Intent::System {
+        target: format!("{}_{}", name, amount.min(100)),
+        confidence: EXACT_CONFIDENCE,
+    })
+}
+
+fn percent(word: &str) -> Option<u8> {
+    word.trim_end_matches('%')
+        .trim_end_matches("percent")
+        .parse()
+        .ok()
+}
+
+fn find_key<'a>(keys: impl Iterator<Item = &'a String>, phrase: &str) -> Option<(String, bool)> {
+    let mut partial = None;
+    for key in keys {
+        let lowered = key.to_lowercase().replace(['_', '-'], " ");
+        if lowered == phrase {
+            return Some((key.clone(), true));
+        }
+        if partial.is_none() && contains_words(phrase, &lowered) {
+            partial = Some((key.clone(), false));
+        }
+    }
+    partial
+}
+
+fn contains_words(phrase: &str, key: &str) -> bool {
+    let phrase_words: Vec<&str> = phrase.split_whitespace().collect();
+    let key_words: Vec<&str> = key.split_whitespace().collect();
+    !key_words.is_empty()
+        && phrase_words
+            .windows(key_words.len())
+            .any(|window| window == key_words.as_slice())
+}
+
+fn score(exact: bool) -> f32 {
+    if exact {
+        EXACT_CONFIDENCE
+    } else {
+        PARTIAL_CONFIDENCE
+    }
+}
+
+fn normalize(transcript: &str) -> Vec<String> {
+    transcript
+        .to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '%' || c == '_' || c == '-' {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Reads a spoken yes/no reply. Returns `None` when the reply is neither, or
+/// both (for example when the question itself was picked up by the mic).
+pub fn yes_no(transcript: &str) -> Option<bool> {
+    // Without the apostrophe, so "ok, don't" has a "dont" in it.
+    let words = clock::words(transcript);
+    let yes = words.iter().any(|word| {
+        matches!(
+            word.as_str(),
+            "yes" | "yeah" | "yep" | "sure" | "confirm" | "confirmed" | "ok" | "okay" | "correct"
+        )
+    });
+    let no = words.iter().any(|word| {
+        matches!(
+            word.as_str(),
+            "no" | "nope" | "cancel" | "stop" | "dont" | "negative"
+        )
+    });
+    match (yes, no) {
+        (true, false) => Some(true),
+        (false, true) => Some(false),
+        _ => None,
+    }
+}
+
+/// Reads a reply to "want me to continue?".
+pub fn wants_more(transcript: &str) -> bool {
+    let phrase = format!(" {} ", normalize(transcript).join(" "));
+    let more = [" continue ", " more ", " go on ", " keep going ", " carry on "]
+        .iter()
+        .any(|cue| phrase.contains(cue));
+    match yes_no(transcript) {
+        Some(answer) => answer,
+        None => more,
+    }
+}
+
+/// "Stop" or "that's enough" in reply to "want me to continue?". Other
+/// replies, even ones starting with "no", are taken as a new request.
+pub fn wants_stop(transcript: &str) -> bool {
+    let words = normalize(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    wants_silence(transcript)
+        || matches!(
+            words.as_slice(),
+            ["no" | "nope"] | ["no", "thanks" | "thank" | "more"]
+        )
+}
+
+/// "Stop", "be quiet", "that's enough": cut Buddy off. Kept to a few words
+/// so an answer heard back through the speakers rarely matches.
+pub fn wants_silence(transcript: &str) -> bool {
+    let words = normalize(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let words = words.strip_suffix(&["please"]).unwrap_or(&words);
+    matches!(
+        words,
+        ["stop" | "enough" | "quiet" | "silence" | "hush"]
+            | ["stop", "talking" | "reading" | "there" | "it"]
+            | ["be", "quiet"]
+            | ["shut", "up"]
+            | ["thats" | "that", "enough"]
+            | ["that", "s" | "is", "enough"]
+    )
+}
+
+/// "Type that", "insert the answer", "paste it here".
+pub fn wants_insert(transcript: &str) -> bool {
+    let words = normalize(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let words = match words.as_slice() {
+        [rest @ .., "in" | "here" | "there"] | [rest @ .., "for", "me"] => rest,
+        words => words,
+    };
+    matches!(
+        words,
+        ["type" | "insert" | "paste", "that" | "it" | "this"]
+            | ["type" | "insert" | "paste", "the" | "that" | "your", "answer"]
+    )
+}
+
+/// "Show stats", "show me your statistics", "how are you doing".
+pub fn wants_stats(transcript: &str) -> bool {
+    let words = normalize(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    matches!(
+        words.as_slice(),
+        ["show" | "read" | "give", "stats" | "statistics"]
+            | ["show" | "read" | "give", "me", "the" | "your", "stats" | "statistics"]
+            | ["show" | "read", "the" | "your", "stats" | "statistics"]
+            | ["how", "are", "you", "doing"]
+    )
+}
+
+/// "Update the document index", "index my documents".
+pub fn wants_index_update(transcript: &str) -> bool {
+    let words = normalize(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    matches!(
+        words.as_slice(),
+        ["update" | "rebuild" | "refresh", "the" | "my", "document" | "documents", "index"]
+            | ["index" | "reindex", "the" | "my", "documents" | "files"]
+            | ["reindex", "documents"]
+    )
+}
+
+/// "Buddy quit", "exit buddy", "goodbye buddy".
+pub fn wants_quit(transcript: &str) -> bool {
+    let words = normalize(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    matches!(
+        words.as_slice(),
+        ["quit"]
+            | ["buddy", "quit" | "exit"]
+            | ["quit" | "exit" | "close", "buddy"]
+            | ["goodbye" | "bye", "buddy"]
+            | ["good", "bye", "buddy"]
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeetingCommand {
+    Start,
+    Stop,
+}
+
+/// "Start transcribing", "transcribe this meeting", "stop transcription".
+pub fn meeting_command(transcript: &str) -> Option<MeetingCommand> {
+    let words = normalize(transcript);
+    if words.len() > 4 || !words.iter().any(|word| word.starts_with("transcri")) {
+        return None;
+    }
+    match words.first()?.as_str() {
+        "start" | "begin" | "transcribe" => Some(MeetingCommand::Start),
+        "stop" | "end" | "finish" => Some(MeetingCommand::Stop),
+        _ => None,
+    }
+}
+
+/// "Save this conversation", "export the chat as JSON". Returns the format
+/// asked for, or `default` when none is named.
+pub fn export_command(transcript: &str, default: ExportFormat) -> Option<ExportFormat> {
+    let words = normalize(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let rest = match words.as_slice() {
+        ["save" | "export", "this" | "the" | "our" | "my", "conversation" | "chat", rest @ ..]
+        | ["save" | "export", "conversation" | "chat", rest @ ..] => rest,
+        _ => return None,
+    };
+    let rest = match rest {
+        [rest @ .., "file"] => rest,
+        rest => rest,
+    };
+    match rest {
+        [] => Some(default),
+        ["as" | "to" | "in", "a" | "an", format] | ["as" | "to" | "in", format] => match *format {
+            "json" => Some(ExportFormat::Json),
+            "markdown" | "md" => Some(ExportFormat::Markdown),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MacroCommand {
+    /// Start recording a macro with this name.
+    Start(String),
+    End,
+    /// Replay the macro with this name, if there is one.
+    Run(String),
+}
+
+/// "Start macro work morning", "record a macro called work morning", "end
+/// macro", "run work morning".
+pub fn macro_command(transcript: &str) -> Option<MacroCommand> {
+    let words = normalize(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let (start, name) = match words.as_slice() {
+        ["end" | "stop" | "finish" | "save", "the" | "this", "macro"]
+        | ["end" | "stop" | "finish" | "save", "macro"] => return Some(MacroCommand::End),
+        ["start" | "record" | "begin", "a", "macro", name @ ..]
+        | ["start" | "record" | "begin", "macro", name @ ..] => (true, name),
+        ["run" | "play" | "replay", "the" | "my", "macro", name @ ..]
+        | ["run" | "play" | "replay", "macro", name @ ..]
+        | ["run" | "play" | "replay", name @ ..] => (false, name),
+        _ => return None,
+    };
+    let name = match name {
+        ["called" | "named", name @ ..] => name,
+        name => name,
+    };
+    if name.is_empty() {
+        return None;
+    }
+    let name = name.join(" ");
+    Some(if start {
+        MacroCommand::Start(name)
+    } else {
+        MacroCommand::Run(name)
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Correction {
+    /// "That was wrong"; what was meant still has to be asked.
+    Wrong,
+    /// The command that was meant, as said.
+    Meant(String),
+}
+
+/// "That was wrong", "no, I meant open notepad", "I meant to say launch
+/// steam".
+pub fn correction_command(transcript: &str) -> Option<Correction> {
+    let words = normalize(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let words = words.strip_prefix(&["no"]).unwrap_or(&words);
+    match words {
+        ["that", "was" | "is" | "s", "wrong"]
+        | ["wrong"]
+        | ["you", "got", "it" | "that", "wrong"]
+        | ["that", "s" | "is", "not", "what", "i", "meant" | "said"] => {
+            return Some(Correction::Wrong)
+        }
+        ["i", "meant" | "said", ..] => {}
+        _ => return None,
+    }
+    // The rest keeps its casing and punctuation for the intent model.
+    let tokens: Vec<&str> = transcript.split_whitespace().collect();
+    let lower: Vec<String> = tokens.iter().map(|token| normalize(token).concat()).collect();
+    let verb = lower.iter().position(|word| word == "meant" || word == "said")?;
+    let mut start = verb + 1;
+    if lower.get(start..start + 2) == Some(&["to".to_string(), "say".to_string()]) {
+        start += 2;
+    }
+    let meant = tokens.get(start..)?.join(" ");
+    let meant = meant.trim_start_matches([',', ':', '-', ' ']);
+    (!meant.is_empty()).then(|| Correction::Meant(meant.to_string()))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LanguageChoice {
+    /// Detect the language of each utterance.
+    Auto,
+    /// The language as said ("german"); the caller checks it is one.
+    Pin(String),
+}
+
+/// "Switch to German", "change language to French", "speak Spanish",
+/// "detect language automatically".
+pub fn language_command(transcript: &str) -> Option<LanguageChoice> {
+    let words = normalize(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let rest = match words.as_slice() {
+        ["detect", "language", "automatically"] | ["auto", "detect", "language"] => {
+            return Some(LanguageChoice::Auto)
+        }
+        ["switch" | "change", "language", "to", rest @ ..]
+        | ["switch" | "change", "to", rest @ ..]
+        | ["speak", rest @ ..] => rest,
+        _ => return None,
+    };
+    match rest {
+        ["auto" | "automatic"] | ["auto" | "automatic", "detect" | "detection"] => {
+            Some(LanguageChoice::Auto)
+        }
+        [language] | [language, "language"] => Some(LanguageChoice::Pin(language.to_string())),
+        _ => None,
+    }
+}
+
+/// "Switch to the work profile", "use home profile", "change profile to
+/// work". Returns the spoken name, which may not be a profile at all.
+pub fn profile_command(transcript: &str) -> Option<String> {
+    let words = normalize(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let name = match words.as_slice() {
+        ["switch" | "change", "to", name @ .., "profile"]
+        | ["use" | "load", name @ .., "profile"]
+        | ["switch" | "change", "profile", "to", name @ ..]
+        | ["switch" | "change", "to", "profile", name @ ..] => name,
+        _ => return None,
+    };
+    let name = match name {
+        ["the" | "my", name @ ..] => name,
+        name => name,
+    };
+    (!name.is_empty()).then(|| name.join(" "))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pick {
+    Index(usize),
+    Cancel,
+}
+
+/// Reads a reply to "which one?" for `labels` offered in order: words from
+/// a single label ("the July one"), or else a reply that is only an ordinal
+/// ("the first one", "number two", "the last one").
+pub fn pick_choice(transcript: &str, labels: &[String]) -> Option<Pick> {
+    let words = normalize(transcript);
+    if words
+        .iter()
+        .any(|word| matches!(word.as_str(), "cancel" | "none" | "neither" | "nevermind" | "stop"))
+        || transcript.to_lowercase().contains("never mind")
+    {
+        return Some(Pick::Cancel);
+    }
+    if let Some(index) = label_named(&words, labels) {
+        return Some(Pick::Index(index));
+    }
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let words = words.strip_suffix(&["please"]).unwrap_or(&words);
+    let index = match words {
+        // Whisper often hears "number two" as "number to".
+        ["number", "to" | "too"] => Some(1),
+        [word] | [word, "one"] | ["the" | "number", word] | ["the", word, "one"] => match *word {
+            "first" | "one" | "1" | "1st" => Some(0),
+            "second" | "two" | "2" | "2nd" => Some(1),
+            "third" | "three" | "3" | "3rd" => Some(2),
+            "fourth" | "four" | "4" | "4th" => Some(3),
+            "fifth" | "five" | "5" | "5th" => Some(4),
+            "last" => labels.len().checked_sub(1),
+            _ => None,
+        },
+        _ => None,
+    };
+    index.filter(|index| *index < labels.len()).map(Pick::Index)
+}
+
+/// The one label that has every word of the reply besides fillers and "one".
+fn label_named(words: &[String], labels: &[String]) -> Option<usize> {
+    let spoken: Vec<&String> = words
+        .iter()
+        .filter(|word| !FILLER_WORDS.contains(&word.as_str()) && word.as_str() != "one")
+        .collect();
+    if spoken.is_empty() {
+        return None;
+    }
+    let mut hits = labels.iter().enumerate().filter(|(_, label)| {
+        let label_words = normalize(label);
+        spoken.iter().all(|word| label_words.contains(word))
+    });
+    match (hits.next(), hits.next()) {
+        (Some((index, _)), None) => Some(index),
+        _ => None,
+    }
+}
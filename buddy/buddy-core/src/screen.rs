@@ -0,0 +1,260 @@
+use crate::{clock, intent::Intent, rules::EXACT_CONFIDENCE};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(not(target_os = "windows"))]
+use std::process::Command;
+
+#[derive(Debug)]
+pub enum ScreenError {
+    Io(io::Error),
+    #[cfg(target_os = "windows")]
+    Windows(windows::core::Error),
+    /// No screenshot tool worked; holds what was tried.
+    #[cfg_attr(windows, allow(dead_code))]
+    NoTool(String),
+}
+
+impl std::fmt::Display for ScreenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "io error: {}", err),
+            #[cfg(target_os = "windows")]
+            Self::Windows(err) => write!(f, "screen capture failed: {}", err),
+            Self::NoTool(tried) => write!(f, "no screenshot tool worked (tried {})", tried),
+        }
+    }
+}
+
+impl std::error::Error for ScreenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            #[cfg(target_os = "windows")]
+            Self::Windows(err) => Some(err),
+            Self::NoTool(_) => None,
+        }
+    }
+}
+
+/// Recognizes "take a screenshot" and "what's on my screen".
+pub fn recognize(transcript: &str) -> Option<Intent> {
+    let words = clock::words(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let words = words.strip_suffix(&["please"]).unwrap_or(&words);
+    match words {
+        ["take" | "grab" | "save", "a" | "the", "screenshot"]
+        | ["take" | "grab" | "save", "a", "screen", "shot"]
+        | ["screenshot"]
+        | ["capture" | "screenshot", "the" | "my", "screen"] => Some(Intent::Screenshot {
+            confidence: EXACT_CONFIDENCE,
+        }),
+        ["whats" | "what", "on", "my" | "the", "screen"]
+        | ["what", "is", "on", "my" | "the", "screen"]
+        | ["describe", "my" | "the", "screen"]
+        | ["what", "am", "i", "looking", "at"] => Some(Intent::DescribeScreen {
+            confidence: EXACT_CONFIDENCE,
+        }),
+        _ => None,
+    }
+}
+
+/// Saves a screenshot of every monitor into `dir`, named after the local
+/// time ("screenshot-2025-03-04-153005.png"), and returns its path.
+pub fn save(dir: &Path) -> Result<PathBuf, ScreenError> {
+    fs::create_dir_all(dir).map_err(ScreenError::Io)?;
+    let (date, secs) = clock::now();
+    let name = format!(
+        "screenshot-{:04}-{:02}-{:02}-{:02}{:02}{:02}.png",
+        date.year,
+        date.month,
+        date.day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    );
+    let path = dir.join(name);
+    write_png(&path)?;
+    Ok(path)
+}
+
+/// A PNG of every monitor, for the vision model; nothing is kept on disk.
+pub fn capture() -> Result<Vec<u8>, ScreenError> {
+    let path = std::env::temp_dir().join(format!("buddy-screen-{}.png", std::process::id()));
+    write_png(&path)?;
+    let png = fs::read(&path).map_err(ScreenError::Io);
+    let _ = fs::remove_file(&path);
+    png
+}
+
+/// Copies the whole virtual screen with GDI.
+#[cfg(target_os = "windows")]
+fn write_png(path: &Path) -> Result<(), ScreenError> {
+    use windows::Win32::{
+        Foundation::HWND,
+        Graphics::Gdi::{
+            BitBlt, CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC,
+            GetDIBits, ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, CAPTUREBLT,
+            DIB_RGB_COLORS, SRCCOPY,
+        },
+        UI::WindowsAndMessaging::{
+            GetSystemMetrics, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN,
+            SM_YVIRTUALSCREEN,
+        },
+    };
+
+    let (left, top, width, height) = unsafe {
+        (
+            GetSystemMetrics(SM_XVIRTUALSCREEN),
+            GetSystemMetrics(SM_YVIRTUALSCREEN),
+            GetSystemMetrics(SM_CXVIRTUALSCREEN),
+            GetSystemMetrics(SM_CYVIRTUALSCREEN),
+        )
+    };
+    let mut bgra = vec![0u8; width as usize * height as usize * 4];
+    let copied = unsafe {
+        let screen = GetDC(HWND::default());
+        let memory = CreateCompatibleDC(screen);
+        let bitmap = CreateCompatibleBitmap(screen, width, height);
+        let previous = SelectObject(memory, bitmap);
+        let copied = BitBlt(memory, 0, 0, width, height, screen, left, top, SRCCOPY | CAPTUREBLT);
+        SelectObject(memory, previous);
+        let mut info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                // Negative for rows top to bottom, as PNG stores them.
+                biHeight: -height,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let rows = GetDIBits(
+            memory,
+            bitmap,
+            0,
+            height as u32,
+            Some(bgra.as_mut_ptr().cast()),
+            &mut info,
+            DIB_RGB_COLORS,
+        );
+        let _ = DeleteObject(bitmap);
+        let _ = DeleteDC(memory);
+        ReleaseDC(HWND::default(), screen);
+        copied.and_then(|()| {
+            if rows == height {
+                Ok(())
+            } else {
+                Err(windows::core::Error::from_win32())
+            }
+        })
+    };
+    copied.map_err(ScreenError::Windows)?;
+    let rgb: Vec<u8> = bgra
+        .chunks_exact(4)
+        .flat_map(|pixel| [pixel[2], pixel[1], pixel[0]])
+        .collect();
+    fs::write(path, png(width as u32, height as u32, &rgb)).map_err(ScreenError::Io)
+}
+
+/// Hands the capture to whichever screenshot tool is installed.
+#[cfg(not(target_os = "windows"))]
+fn write_png(path: &Path) -> Result<(), ScreenError> {
+    let file = path.as_os_str();
+    let tools: Vec<(&str, Vec<&std::ffi::OsStr>)> = if cfg!(target_os = "macos") {
+        vec![("screencapture", vec!["-x".as_ref(), file])]
+    } else {
+        vec![
+            ("grim", vec![file]),
+            ("gnome-screenshot", vec!["-f".as_ref(), file]),
+            ("spectacle", vec!["-b".as_ref(), "-n".as_ref(), "-o".as_ref(), file]),
+            ("scrot", vec!["-o".as_ref(), file]),
+            ("import", vec!["-window".as_ref(), "root".as_ref(), file]),
+        ]
+    };
+    for (program, args) in &tools {
+        let succeeded = Command::new(program)
+            .args(args)
+            .status()
+            .is_ok_and(|status| status.success());
+        if succeeded && path.exists() {
+            return Ok(());
+        }
+    }
+    let tried: Vec<&str> = tools.iter().map(|(program, _)| *program).collect();
+    Err(ScreenError::NoTool(tried.join(", ")))
+}
+
+/// Encodes 8-bit RGB rows as a PNG. The image data uses stored (uncompressed)
+/// deflate blocks, so files are large but no compression library is needed.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn png(width: u32, height: u32, rgb: &[u8]) -> Vec<u8> {
+    let row = width as usize * 3;
+    // Each row starts with filter type 0 (none).
+    let mut raw = Vec::with_capacity((row + 1) * height as usize);
+    for line in rgb.chunks_exact(row) {
+        raw.push(0);
+        raw.extend_from_slice(line);
+    }
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(0xffff).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        zlib.push(u8::from(blocks.peek().is_none()));
+        let len = block.len() as u16;
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8 bits per channel, truecolor, deflate, adaptive filtering, no interlace.
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    for (kind, data) in [(b"IHDR", &header), (b"IDAT", &zlib), (b"IEND", &Vec::new())] {
+        png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        let start = png.len();
+        png.extend_from_slice(kind);
+        png.extend_from_slice(data);
+        let crc = crc32(&png[start..]);
+        png.extend_from_slice(&crc.to_be_bytes());
+    }
+    png
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += u32::from(*byte);
+            b += a;
+        }
+        a %= 65_521;
+        b %= 65_521;
+    }
+    (b << 16) | a
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
@@ -0,0 +1,170 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Upper bound on directory entries visited per search, so a mapping that
+/// points at a huge tree cannot stall the command.
+const MAX_ENTRIES: usize = 5_000;
+
+/// Candidates scoring within this margin of the best are treated as a tie.
+const TIE_MARGIN: f32 = 0.05;
+
+const STOP_WORDS: &[&str] = &[
+    "the", "a", "an", "my", "from", "of", "for", "in", "on", "file", "document", "one",
+];
+
+const MONTHS: &[(&str, &str, &str)] = &[
+    ("january", "jan", "01"),
+    ("february", "feb", "02"),
+    ("march", "mar", "03"),
+    ("april", "apr", "04"),
+    ("may", "may", "05"),
+    ("june", "jun", "06"),
+    ("july", "jul", "07"),
+    ("august", "aug", "08"),
+    ("september", "sep", "09"),
+    ("october", "oct", "10"),
+    ("november", "nov", "11"),
+    ("december", "dec", "12"),
+];
+
+#[derive(Debug, Clone)]
+pub struct Match {
+    pub path: PathBuf,
+    pub score: f32,
+}
+
+pub enum SearchOutcome {
+    None,
+    Found(PathBuf),
+    /// Several files scored about the same; best first.
+    Ambiguous(Vec<Match>),
+}
+
+/// Fuzzy-matches `query` against file names below `root`, at most `depth`
+/// directories deep. An exact file name (with or without extension) wins
+/// outright, which is also how a previously offered choice is reopened.
+pub fn find_file(root: &Path, query: &str, depth: usize) -> SearchOutcome {
+    let words = query_words(query);
+    if words.is_empty() {
+        return SearchOutcome::None;
+    }
+    let wanted = query.trim().to_lowercase();
+    let mut matches = Vec::new();
+    let mut visited = 0;
+    walk(root, depth, &mut visited, &mut |path| {
+        let Some(name) = path.file_name().map(|name| name.to_string_lossy().to_lowercase()) else {
+            return;
+        };
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let score = if name == wanted || stem == wanted {
+            f32::MAX
+        } else {
+            score_name(&stem, &words)
+        };
+        if score > 0.0 {
+            matches.push(Match {
+                path: path.to_path_buf(),
+                score,
+            });
+        }
+    });
+    matches.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.path.as_os_str().len().cmp(&b.path.as_os_str().len()))
+    });
+    let Some(best) = matches.first() else {
+        return SearchOutcome::None;
+    };
+    let best_score = best.score;
+    let tied = matches
+        .iter()
+        .take_while(|candidate| best_score - candidate.score <= TIE_MARGIN)
+        .count();
+    if tied <= 1 {
+        return SearchOutcome::Found(matches.swap_remove(0).path);
+    }
+    SearchOutcome::Ambiguous(matches)
+}
+
+fn walk(dir: &Path, depth: usize, visited: &mut usize, on_file: &mut dyn FnMut(&Path)) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        *visited += 1;
+        if *visited > MAX_ENTRIES {
+            return;
+        }
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(kind) if kind.is_dir() => {
+                if depth > 0 {
+                    walk(&path, depth - 1, visited, on_file);
+                }
+            }
+            Ok(_) => on_file(&path),
+            Err(_) => {}
+        }
+    }
+}
+
+fn query_words(query: &str) -> Vec<String> {
+    tokens(&query.to_lowercase())
+        .into_iter()
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+fn tokens(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Fraction of query words found in the name: whole tokens count fully,
+/// prefixes ("budg" / "budget2024") partly, and month names also match their
+/// abbreviation or number.
+fn score_name(stem: &str, words: &[String]) -> f32 {
+    let name_tokens = tokens(stem);
+    let mut total = 0.0;
+    for word in words {
+        let forms = word_forms(word);
+        let best = name_tokens
+            .iter()
+            .map(|token| {
+                if forms.iter().any(|form| token == form) {
+                    1.0
+                } else if word.len() >= 3
+                    && (token.starts_with(word.as_str())
+                        || (token.len() >= 3 && word.starts_with(token.as_str())))
+                {
+                    0.7
+                } else if forms.iter().any(|form| form.len() >= 3 && stem.contains(form)) {
+                    0.5
+                } else {
+                    0.0
+                }
+            })
+            .fold(0.0, f32::max);
+        total += best;
+    }
+    total / words.len() as f32
+}
+
+fn word_forms(word: &str) -> Vec<String> {
+    let mut forms = vec![word.to_string()];
+    if let Some((name, short, number)) = MONTHS
+        .iter()
+        .find(|(name, short, _)| word == *name || word == *short)
+    {
+        forms.extend([name.to_string(), short.to_string(), number.to_string()]);
+    }
+    forms
+}
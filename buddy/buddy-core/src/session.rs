@@ -0,0 +1,170 @@
+use crate::{config::Config, intent::Intent};
+use std::{collections::VecDeque, time::SystemTime};
+
+/// How many handled commands are kept for `/history`.
+const HISTORY_LIMIT: usize = 50;
+
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    pub transcript: String,
+    /// How sure transcription was of `transcript`; `None` for typed commands
+    /// and engines that don't say.
+    pub confidence: Option<f32>,
+    pub intent: Intent,
+    pub at: SystemTime,
+    /// What was read out in reply, for answers and translations.
+    pub answer: Option<String>,
+    /// Whether running it went well; `None` when it didn't run, say because
+    /// it was cancelled.
+    pub succeeded: Option<bool>,
+}
+
+#[derive(Debug, Default)]
+pub struct Session {
+    last_intent: Option<Intent>,
+    last_answer: Option<String>,
+    history: VecDeque<HistoryEntry>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, transcript: &str, confidence: Option<f32>, intent: &Intent) {
+        if self.history.len() == HISTORY_LIMIT {
+            self.history.pop_front();
+        }
+        self.history.push_back(HistoryEntry {
+            transcript: transcript.to_string(),
+            confidence,
+            intent: intent.clone(),
+            at: SystemTime::now(),
+            answer: None,
+            succeeded: None,
+        });
+        if !matches!(intent, Intent::Unknown { .. }) {
+            self.last_intent = Some(intent.clone());
+            // An answer is only the last thing said until the next command;
+            // if that one answers too, `remember_answer` follows.
+            self.last_answer = None;
+        }
+    }
+
+    /// Keeps the text of the answer just read out, for "open it" and "type
+    /// that".
+    pub fn remember_answer(&mut self, text: String) {
+        if let Some(entry) = self.history.back_mut() {
+            entry.answer = Some(text.clone());
+        }
+        self.last_answer = Some(text);
+    }
+
+    /// Notes how running the last recorded command went.
+    pub fn record_outcome(&mut self, succeeded: bool) {
+        if let Some(entry) = self.history.back_mut() {
+            entry.succeeded = Some(succeeded);
+        }
+    }
+
+    pub fn last_answer(&self) -> Option<&str> {
+        self.last_answer.as_deref()
+    }
+
+    /// Oldest first.
+    pub fn history(&self) -> impl Iterator<Item = &HistoryEntry> {
+        self.history.iter()
+    }
+
+    /// Resolves phrases like "open it" against the last answer or intent so the
+    /// follow-up runs without another round trip to the LLM.
+    pub fn resolve_follow_up(&self, transcript: &str, config: &Config) -> Option<Intent> {
+        let verb = follow_up_verb(transcript)?;
+        let prefer_app = verb != "open";
+        if let Some(answer) = &self.last_answer {
+            if let Some(intent) = target_in_text(answer, config, prefer_app) {
+                return Some(intent);
+            }
+        }
+        match self.last_intent.as_ref()? {
+            Intent::OpenFile {
+                target, query, at, ..
+            } => Some(Intent::OpenFile {
+                target: target.clone(),
+                query: query.clone(),
+                at: *at,
+                confidence: 1.0,
+            }),
+            Intent::OpenApp { target, .. } => Some(Intent::OpenApp {
+                target: target.clone(),
+                confidence: 1.0,
+            }),
+            _ => None,
+        }
+    }
+}
+
+fn follow_up_verb(transcript: &str) -> Option<&'static str> {
+    let cleaned: String = transcript
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase();
+    let words: Vec<&str> = cleaned.split_whitespace().collect();
+    let (verb, rest) = words.split_first()?;
+    let verb = match *verb {
+        "open" => "open",
+        "launch" => "launch",
+        "start" => "start",
+        "run" => "run",
+        _ => return None,
+    };
+    match rest {
+        ["it"] | ["that"] | ["this"] | ["it", "up"] | ["that", "one"] => Some(verb),
+        _ => None,
+    }
+}
+
+/// The first file or application, by key, that `text` mentions.
+fn target_in_text(text: &str, config: &Config, prefer_app: bool) -> Option<Intent> {
+    let lowered = text.to_lowercase();
+    let mut files: Vec<_> = config.files.iter().collect();
+    files.sort_by_key(|(key, _)| *key);
+    let mut apps: Vec<_> = config.applications.keys().collect();
+    apps.sort();
+    let file = files
+        .into_iter()
+        .find(|(key, path)| {
+            mentions(&lowered, &key.to_lowercase())
+                || mentions(&lowered, &path.path().to_string_lossy().to_lowercase())
+        })
+        .map(|(key, _)| Intent::OpenFile {
+            target: key.clone(),
+            query: None,
+            at: None,
+            confidence: 1.0,
+        });
+    let app = apps
+        .into_iter()
+        .find(|key| mentions(&lowered, &key.to_lowercase()))
+        .map(|key| Intent::OpenApp {
+            target: key.clone(),
+            confidence: 1.0,
+        });
+    if prefer_app {
+        app.or(file)
+    } else {
+        file.or(app)
+    }
+}
+
+fn mentions(haystack: &str, needle: &str) -> bool {
+    if needle.is_empty() {
+        return false;
+    }
+    haystack.match_indices(needle).any(|(idx, _)| {
+        let before = haystack[..idx].chars().next_back();
+        let after = haystack[idx + needle.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
@@ -0,0 +1,354 @@
+//! `[shell]`: "run command: list what's in downloads". The intent model
+//! writes the command, which is always confirmed before it runs; what it
+//! prints is summed up aloud. Off unless `shell.enabled` is set.
+
+use crate::{
+    answer,
+    config::{Config, ShellConfig},
+    executor,
+    intent::{Intent, IntentClient, IntentError},
+    process,
+    rules::EXACT_CONFIDENCE,
+};
+use std::{fmt, io, process::Stdio, time::Duration};
+
+/// What a request starts with.
+const PREFIXES: &[&str] = &["run command", "run the command", "run a command"];
+
+/// Chain, pipe or redirect commands, or run one inside another; refused
+/// unless `shell.allow_operators` is set.
+const OPERATORS: &[&str] = &["&", "|", ";", ">", "<", "`", "$(", "\n"];
+
+/// Lines read out when the output isn't summarized.
+const SPOKEN_LINES: usize = 3;
+
+#[derive(Debug)]
+pub enum ShellError {
+    Start(io::Error),
+    TimedOut(Duration),
+}
+
+impl fmt::Display for ShellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Start(err) => write!(f, "failed to start the shell: {}", err),
+            Self::TimedOut(timeout) => {
+                write!(f, "the command took longer than {}s and was stopped", timeout.as_secs())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShellError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Start(err) => Some(err),
+            Self::TimedOut(_) => None,
+        }
+    }
+}
+
+/// What a command printed, and whether it succeeded.
+pub struct Ran {
+    pub output: String,
+    pub succeeded: bool,
+    /// The exit code, if it exited with one.
+    pub status: Option<i32>,
+}
+
+/// Recognizes "run command: ..." and "run the command ...", keeping the
+/// rest as the request; the command itself is written by `command`.
+pub fn recognize(transcript: &str, config: &Config) -> Option<Intent> {
+    if !config.shell.enabled {
+        return None;
+    }
+    let spoken = transcript.trim();
+    let rest = PREFIXES.iter().find_map(|prefix| {
+        let start = spoken.get(..prefix.len())?;
+        start.eq_ignore_ascii_case(prefix).then(|| &spoken[prefix.len()..])
+    })?;
+    // Whisper writes "Run command, list the files." as often as with a colon.
+    if !rest.starts_with([':', ',', '.', ' ']) {
+        return None;
+    }
+    let request = rest
+        .trim_start_matches([':', ',', '.', ' '])
+        .trim_end_matches('.')
+        .trim();
+    if request.is_empty() {
+        return None;
+    }
+    Some(Intent::Shell {
+        request: request.to_string(),
+        command: None,
+        confidence: EXACT_CONFIDENCE,
+    })
+}
+
+/// Asks the intent model for the command that does `request`.
+pub async fn command(
+    intent_client: &IntentClient,
+    request: &str,
+    config: &Config,
+) -> Result<Option<String>, IntentError> {
+    let (os, shell) = if cfg!(windows) {
+        ("Windows", "cmd.exe")
+    } else {
+        (std::env::consts::OS, "sh")
+    };
+    let prompt = config
+        .shell
+        .command_prompt
+        .replace("{request}", request)
+        .replace("{os}", os)
+        .replace("{shell}", shell);
+    let reply = intent_client.complete(prompt).await?;
+    Ok(command_line(&reply))
+}
+
+/// The command in a model's reply, without the code fence or backticks some
+/// models put around it anyway.
+fn command_line(reply: &str) -> Option<String> {
+    reply
+        .lines()
+        .map(|line| line.trim().trim_matches('`').trim())
+        .filter(|line| !line.is_empty() && !line.starts_with("```"))
+        // "```sh" leaves "sh" once the backticks are trimmed.
+        .find(|line| !matches!(*line, "sh" | "bash" | "cmd" | "bat" | "batch" | "powershell"))
+        .map(|line| line.trim_start_matches("$ ").to_string())
+}
+
+/// Why `command` may not run, if it may not: an operator, a `shell.blocked`
+/// entry, or a program `shell.allowed_programs` doesn't list.
+pub fn refused(command: &str, shell: &ShellConfig) -> Option<String> {
+    // Whatever comes after an operator escapes `allowed_programs`.
+    if !shell.allow_operators || !shell.allowed_programs.is_empty() {
+        if let Some(operator) = OPERATORS.iter().find(|operator| command.contains(*operator)) {
+            let operator = match *operator {
+                "\n" => "a line break".to_string(),
+                operator => format!("\"{}\"", operator),
+            };
+            return Some(format!("I won't run that: it uses {}", operator));
+        }
+    }
+    let words = words(command);
+    if let Some(blocked) = shell.blocked.iter().find(|blocked| matches(&words, blocked)) {
+        return Some(format!("I won't run that: \"{}\" is blocked", blocked));
+    }
+    if shell.allowed_programs.is_empty() {
+        return None;
+    }
+    let name = words.first().map(|word| program(word)).unwrap_or_default();
+    if shell
+        .allowed_programs
+        .iter()
+        .any(|allowed| program(&allowed.to_lowercase()) == name)
+    {
+        return None;
+    }
+    Some(format!("I won't run that: {} isn't an allowed program", name))
+}
+
+/// `command` in lowercase words, without the quotes around them.
+fn words(command: &str) -> Vec<String> {
+    command
+        .split_whitespace()
+        .map(|word| word.trim_matches(['"', '\'']).to_lowercase())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// The program a word names, without its folder or ".exe".
+fn program(word: &str) -> &str {
+    let name = word.rsplit(['/', '\\']).next().unwrap_or(word);
+    name.strip_suffix(".exe").unwrap_or(name)
+}
+
+/// Whether `words` run the program `blocked` names with each of its flags,
+/// however they're spaced, ordered or combined: "rm -r" matches `rm -fr`,
+/// `rm -f -r` and `/bin/rm -Rf`, "del /s" matches `del /q /s`, and "mkfs"
+/// matches `mkfs.ext4`.
+fn matches(words: &[String], blocked: &str) -> bool {
+    let blocked = self::words(blocked);
+    let Some((name, flags)) = blocked.split_first() else {
+        return false;
+    };
+    words.iter().enumerate().any(|(at, word)| {
+        let program = program(word);
+        let named = program
+            .strip_prefix(name.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'));
+        named && flags.iter().all(|flag| has_flag(&words[at + 1..], flag))
+    })
+}
+
+fn has_flag(words: &[String], flag: &str) -> bool {
+    if let Some(letters) = flag.strip_prefix('-').filter(|letters| !letters.starts_with('-')) {
+        // Each letter in some group of single-letter flags: "-rf" is given
+        // as "-rf", "-fr" or "-r -f".
+        return letters.chars().all(|letter| {
+            words.iter().any(|word| {
+                word.strip_prefix('-')
+                    .is_some_and(|given| !given.starts_with('-') && given.contains(letter))
+            })
+        });
+    }
+    words.iter().any(|word| match flag.strip_prefix('/') {
+        // cmd takes "/s /q" as "/s/q" too.
+        Some(switch) => word.starts_with('/') && word.split('/').any(|given| given == switch),
+        // "if=" is any input file.
+        None if flag.ends_with('=') => word.starts_with(flag),
+        None => word == flag,
+    })
+}
+
+/// Runs `command` in the shell and collects what it printed, the end of it
+/// when there is more than `shell.max_output_chars`.
+pub fn run(command: &str, config: &Config) -> Result<Ran, ShellError> {
+    let mut shell = process::shell(command);
+    if let Some(cwd) = &config.shell.cwd {
+        shell.current_dir(cwd);
+    }
+    let child = shell
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(ShellError::Start)?;
+    let timeout = Duration::from_secs(config.shell.timeout_secs);
+    let output = process::wait_output(child, timeout)
+        .map_err(ShellError::Start)?
+        .ok_or(ShellError::TimedOut(timeout))?;
+    let printed = [process::text(&output.stdout), process::text(&output.stderr)]
+        .into_iter()
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let chars = printed.chars().count();
+    let max = config.shell.max_output_chars;
+    let kept = if chars > max {
+        let end: String = printed.chars().skip(chars - max).collect();
+        format!("(earlier output cut)\n{}", end)
+    } else {
+        printed
+    };
+    Ok(Ran {
+        output: kept,
+        succeeded: output.status.success(),
+        status: output.status.code(),
+    })
+}
+
+/// What to say about `ran`: the model's summary, or its last lines when
+/// `shell.summarize` is off or the model can't be reached.
+pub fn summarize(command: &str, ran: &Ran, config: &Config) -> String {
+    let failed = if ran.succeeded { "" } else { "The command failed. " };
+    if ran.output.is_empty() {
+        return format!("{}It printed nothing", failed);
+    }
+    if config.shell.summarize {
+        let prompt = config
+            .shell
+            .summary_prompt
+            .replace("{command}", command)
+            .replace("{output}", &ran.output);
+        match executor::block_on(IntentClient::new(config).complete(prompt)) {
+            Ok(summary) if !summary.is_empty() => return format!("{}{}", failed, summary),
+            Ok(_) => {}
+            Err(err) => eprintln!("Summarizing the output failed: {}", err),
+        }
+    }
+    format!("{}{}", failed, answer::last_lines(&ran.output, SPOKEN_LINES))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowing(programs: &[&str]) -> ShellConfig {
+        ShellConfig {
+            allowed_programs: programs.iter().map(|program| program.to_string()).collect(),
+            ..ShellConfig::default()
+        }
+    }
+
+    #[test]
+    fn blocks_flags_however_they_are_written() {
+        let shell = ShellConfig::default();
+        for command in [
+            "rm -rf /",
+            "rm  -rf ~",
+            "rm -r -f build",
+            "rm -fR build",
+            "/bin/rm -r build",
+            "sudo rm --recursive build",
+            "\"rm\" -rf build",
+            "del /q /s C:\\temp",
+            "DEL /S/Q C:\\temp",
+            "rd /s /q C:\\temp",
+            "dd if=/dev/zero of=/dev/sda",
+            "mkfs.ext4 /dev/sdb1",
+            "format d:",
+            "shutdown.exe /s /t 0",
+        ] {
+            assert!(refused(command, &shell).is_some(), "{}", command);
+        }
+    }
+
+    #[test]
+    fn lets_harmless_commands_through() {
+        let shell = ShellConfig::default();
+        for command in [
+            "rm notes.txt",
+            "del C:\\temp\\old.log",
+            "dir /s C:\\Users",
+            "git log --format=%s",
+            "df -h",
+        ] {
+            assert_eq!(refused(command, &shell), None, "{}", command);
+        }
+    }
+
+    #[test]
+    fn refuses_operators_unless_allowed() {
+        let shell = ShellConfig::default();
+        for command in [
+            "ls && rm -f x",
+            "dir | find \"x\"",
+            "echo hi > out.txt",
+            "ls; reboot",
+            "echo `whoami`",
+            "echo $(whoami)",
+            "ls\nreboot",
+        ] {
+            assert!(refused(command, &shell).is_some(), "{}", command);
+        }
+        let shell = ShellConfig {
+            allow_operators: true,
+            ..ShellConfig::default()
+        };
+        assert_eq!(refused("df -h | sort", &shell), None);
+        assert!(refused("cd build && rm -rf *", &shell).is_some());
+    }
+
+    #[test]
+    fn runs_only_allowed_programs() {
+        let shell = allowing(&["git", "ipconfig.exe"]);
+        assert_eq!(refused("git status", &shell), None);
+        assert_eq!(refused("IPCONFIG /all", &shell), None);
+        assert!(refused("curl example.com", &shell).is_some());
+        let shell = ShellConfig {
+            allow_operators: true,
+            ..allowing(&["git"])
+        };
+        assert!(refused("git status && curl example.com", &shell).is_some());
+    }
+
+    #[test]
+    fn takes_the_command_out_of_the_reply() {
+        assert_eq!(command_line("```sh\nls -la\n```").as_deref(), Some("ls -la"));
+        assert_eq!(command_line("`dir`").as_deref(), Some("dir"));
+        assert_eq!(command_line("$ df -h").as_deref(), Some("df -h"));
+        assert_eq!(command_line("  \n"), None);
+    }
+}
@@ -0,0 +1,425 @@
+//! `[homeassistant]`: lights, switches, thermostats and the like by voice,
+//! through Home Assistant's REST API or straight over MQTT.
+
+use crate::{
+    clock,
+    config::{Config, HomeAssistantConfig, MqttConfig, MqttEntity, SmartEntity},
+    executor,
+    intent::Intent,
+    numbers,
+    rules::EXACT_CONFIDENCE,
+};
+use reqwest::Client;
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{future::Future, time::Duration};
+
+/// Commands of the smart_home action, besides "set_50" for a level.
+pub const COMMANDS: &[&str] = &[
+    "turn_on", "turn_off", "toggle", "lock", "unlock", "open", "close", "state",
+];
+
+/// Words left out when matching a device's name.
+const FILLER_WORDS: &[&str] = &["the", "my"];
+
+#[derive(Debug)]
+pub enum SmartHomeError {
+    UnknownDevice(String),
+    /// What is missing from `[homeassistant]`.
+    NotConfigured(&'static str),
+    /// The command, and the device it doesn't apply to.
+    Unsupported(String, String),
+    /// The device has no `state_topic`, or nothing arrived on it in time.
+    NoState(String),
+    Request(reqwest::Error),
+    Mqtt(String),
+    TimedOut,
+}
+
+impl std::fmt::Display for SmartHomeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownDevice(name) => write!(f, "no device named '{}'", name),
+            Self::NotConfigured(what) => write!(f, "[homeassistant] needs {}", what),
+            Self::Unsupported(command, device) => {
+                write!(f, "'{}' doesn't apply to {}", command, device)
+            }
+            Self::NoState(device) => write!(f, "no state reported for {}", device),
+            Self::Request(err) => write!(f, "Home Assistant request failed: {}", err),
+            Self::Mqtt(err) => write!(f, "MQTT error: {}", err),
+            Self::TimedOut => write!(f, "the smart home didn't answer in time"),
+        }
+    }
+}
+
+impl std::error::Error for SmartHomeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Request(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for SmartHomeError {
+    fn from(err: reqwest::Error) -> Self {
+        Self::Request(err)
+    }
+}
+
+impl From<rumqttc::ClientError> for SmartHomeError {
+    fn from(err: rumqttc::ClientError) -> Self {
+        Self::Mqtt(err.to_string())
+    }
+}
+
+impl From<rumqttc::ConnectionError> for SmartHomeError {
+    fn from(err: rumqttc::ConnectionError) -> Self {
+        Self::Mqtt(err.to_string())
+    }
+}
+
+/// Whether `command` is one of `COMMANDS` or a level such as "set_50".
+pub fn is_command(command: &str) -> bool {
+    COMMANDS.contains(&command) || level(command).is_some()
+}
+
+fn level(command: &str) -> Option<f64> {
+    command.strip_prefix("set_")?.parse().ok()
+}
+
+/// The name `[policy]` and `[confirmation]` lists give `command`, such as
+/// "smart_home:unlock", or "smart_home:set" for a level.
+pub fn policy_name(command: &str) -> &'static str {
+    match command {
+        "turn_on" => "smart_home:turn_on",
+        "turn_off" => "smart_home:turn_off",
+        "toggle" => "smart_home:toggle",
+        "lock" => "smart_home:lock",
+        "unlock" => "smart_home:unlock",
+        "open" => "smart_home:open",
+        "close" => "smart_home:close",
+        "state" => "smart_home:state",
+        _ => "smart_home:set",
+    }
+}
+
+/// Whether `command` works a lock or a cover such as a garage door, which
+/// is always confirmed first; reading its state isn't.
+pub fn needs_confirmation(target: &str, command: &str, config: &Config) -> bool {
+    let domain = config.homeassistant.entities.get(target).map(domain);
+    command != "state"
+        && (["lock", "unlock", "open", "close"].contains(&command)
+            || matches!(domain, Some("lock" | "cover")))
+}
+
+/// The Home Assistant domain of an entity id ("light", "lock", ...); MQTT
+/// devices have none.
+fn domain(entity: &SmartEntity) -> &str {
+    match entity {
+        SmartEntity::Entity(id) => id.split('.').next().unwrap_or_default(),
+        SmartEntity::Mqtt(_) => "",
+    }
+}
+
+/// Recognizes commands for the configured devices: "turn off the living
+/// room lights", "switch the fan on", "toggle the porch light", "set the
+/// bedroom lamp to 30 percent", "unlock the front door", "close the garage
+/// door", "is the garage door open", "what's the office temperature".
+pub fn recognize(transcript: &str, config: &Config) -> Option<Intent> {
+    if config.homeassistant.entities.is_empty() {
+        return None;
+    }
+    let words = clock::words(&numbers::normalize(transcript));
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let words = words.strip_suffix(&["please"]).unwrap_or(&words);
+    let (command, name) = match words {
+        ["turn" | "switch", "on", name @ ..] | ["turn" | "switch", name @ .., "on"] => {
+            ("turn_on".to_string(), name)
+        }
+        ["turn" | "switch", "off", name @ ..] | ["turn" | "switch", name @ .., "off"] => {
+            ("turn_off".to_string(), name)
+        }
+        ["toggle", name @ ..] => ("toggle".to_string(), name),
+        ["lock", name @ ..] => ("lock".to_string(), name),
+        ["unlock", name @ ..] => ("unlock".to_string(), name),
+        ["open", name @ ..] => ("open".to_string(), name),
+        ["close" | "shut", name @ ..] => ("close".to_string(), name),
+        ["set" | "dim" | "turn", name @ .., "to", value]
+        | ["set" | "dim" | "turn", name @ .., "to", value, "percent" | "degrees"] => {
+            let value: f64 = value.parse().ok()?;
+            (format!("set_{}", value), name)
+        }
+        ["is" | "are", name @ .., "on" | "off" | "open" | "closed" | "locked" | "unlocked"]
+        | ["what" | "whats", "is", name @ ..]
+        | ["what" | "whats", name @ ..] => ("state".to_string(), name),
+        _ => return None,
+    };
+    let target = device_key(config, name)?;
+    // "open the desk fan" isn't for the smart home.
+    let kind = match command.as_str() {
+        "lock" | "unlock" => Some("lock"),
+        "open" | "close" => Some("cover"),
+        _ => None,
+    };
+    if kind.is_some_and(|kind| domain(&config.homeassistant.entities[&target]) != kind) {
+        return None;
+    }
+    Some(Intent::SmartHome {
+        target,
+        command,
+        confidence: EXACT_CONFIDENCE,
+    })
+}
+
+/// The `[homeassistant.entities]` key spoken as `words`; a plural or
+/// singular last word still matches ("light" for `porch_lights`).
+fn device_key(config: &Config, words: &[&str]) -> Option<String> {
+    let spoken: Vec<&str> = words
+        .iter()
+        .copied()
+        .filter(|word| !FILLER_WORDS.contains(word))
+        .collect();
+    let spoken = spoken.join(" ");
+    if spoken.is_empty() {
+        return None;
+    }
+    config
+        .homeassistant
+        .entities
+        .keys()
+        .find(|key| {
+            let name = key.replace('_', " ").to_lowercase();
+            name == spoken || name.trim_end_matches('s') == spoken.trim_end_matches('s')
+        })
+        .cloned()
+}
+
+/// What a smart_home command does, e.g. "turn off living room lights".
+pub fn describe(target: &str, command: &str) -> String {
+    let name = target.replace('_', " ");
+    match command {
+        "turn_on" => format!("turn on {}", name),
+        "turn_off" => format!("turn off {}", name),
+        "toggle" => format!("toggle {}", name),
+        "state" => format!("check {}", name),
+        _ => match level(command) {
+            Some(value) => format!("set {} to {}", name, value),
+            None => format!("{} {}", command.replace('_', " "), name),
+        },
+    }
+}
+
+/// Runs `command` on the device `target` and returns what to say: what was
+/// done, or for "state" what the device reports.
+pub fn run(target: &str, command: &str, config: &Config) -> Result<String, SmartHomeError> {
+    let home = &config.homeassistant;
+    let entity = home
+        .entities
+        .get(target)
+        .ok_or_else(|| SmartHomeError::UnknownDevice(target.to_string()))?;
+    let name = target.replace('_', " ");
+    let timeout = Duration::from_secs(home.timeout_secs);
+    if command == "state" {
+        return match entity {
+            SmartEntity::Entity(id) => block_on(timeout, rest_state(home, id, &name)),
+            SmartEntity::Mqtt(entity) => {
+                let topic = entity
+                    .state_topic
+                    .as_deref()
+                    .ok_or_else(|| SmartHomeError::NoState(name.clone()))?;
+                let payload = block_on(timeout, mqtt_state(mqtt(home)?, topic, &name))?;
+                let state = match payload.trim() {
+                    value if value == entity.payload_on => "on",
+                    value if value == entity.payload_off => "off",
+                    value => value,
+                };
+                Ok(format!("{} is {}", name, state))
+            }
+        };
+    }
+    match entity {
+        SmartEntity::Entity(id) => block_on(timeout, call_service(home, id, command))?,
+        SmartEntity::Mqtt(entity) => {
+            let payload = mqtt_payload(entity, command)
+                .ok_or_else(|| SmartHomeError::Unsupported(command.to_string(), name.clone()))?;
+            block_on(timeout, mqtt_publish(mqtt(home)?, &entity.command_topic, payload))?
+        }
+    }
+    Ok(match command {
+        "turn_on" => format!("Turned on {}", name),
+        "turn_off" => format!("Turned off {}", name),
+        "toggle" => format!("Toggled {}", name),
+        "lock" => format!("Locked {}", name),
+        "unlock" => format!("Unlocked {}", name),
+        "open" => format!("Opened {}", name),
+        "close" => format!("Closed {}", name),
+        _ => format!("Set {} to {}", name, level(command).unwrap_or_default()),
+    })
+}
+
+/// `future`'s result, or `TimedOut` if it takes longer than `timeout`.
+fn block_on<T: Send>(
+    timeout: Duration,
+    future: impl Future<Output = Result<T, SmartHomeError>> + Send,
+) -> Result<T, SmartHomeError> {
+    executor::block_on(async {
+        tokio::time::timeout(timeout, future)
+            .await
+            .unwrap_or(Err(SmartHomeError::TimedOut))
+    })
+}
+
+/// Home Assistant's address and token, for entity ids.
+fn api(home: &HomeAssistantConfig) -> Result<(&str, &str), SmartHomeError> {
+    match (&home.url, &home.token) {
+        (Some(url), Some(token)) => Ok((url.trim_end_matches('/'), token)),
+        _ => Err(SmartHomeError::NotConfigured("url and token")),
+    }
+}
+
+fn mqtt(home: &HomeAssistantConfig) -> Result<&MqttConfig, SmartHomeError> {
+    home.mqtt
+        .as_ref()
+        .ok_or(SmartHomeError::NotConfigured("[homeassistant.mqtt]"))
+}
+
+/// The Home Assistant service `command` calls on an entity of `domain`
+/// ("light", "cover", ...), and the data it needs besides the entity id.
+/// Locks and covers only take their own commands, so "turn off the front
+/// door" can't unlock it.
+fn service(domain: &str, command: &str) -> Option<(&'static str, Value)> {
+    let none = json!({});
+    Some(match (domain, command) {
+        ("lock", "lock") => ("lock", none),
+        ("lock", "unlock") => ("unlock", none),
+        ("cover", "open") => ("open_cover", none),
+        ("cover", "close") => ("close_cover", none),
+        ("lock" | "cover", "turn_on" | "turn_off" | "toggle") => return None,
+        (_, "lock" | "unlock" | "open" | "close") => return None,
+        (_, "turn_on") => ("turn_on", none),
+        (_, "turn_off") => ("turn_off", none),
+        (_, "toggle") => ("toggle", none),
+        (domain, command) => {
+            let value = level(command)?;
+            match domain {
+                "light" => ("turn_on", json!({ "brightness_pct": value })),
+                "climate" => ("set_temperature", json!({ "temperature": value })),
+                "cover" => ("set_cover_position", json!({ "position": value })),
+                "fan" => ("set_percentage", json!({ "percentage": value })),
+                "media_player" => ("volume_set", json!({ "volume_level": value / 100.0 })),
+                "input_number" | "number" => ("set_value", json!({ "value": value })),
+                _ => return None,
+            }
+        }
+    })
+}
+
+async fn call_service(
+    home: &HomeAssistantConfig,
+    entity_id: &str,
+    command: &str,
+) -> Result<(), SmartHomeError> {
+    let (url, token) = api(home)?;
+    let domain = entity_id.split('.').next().unwrap_or_default();
+    let (service, mut data) = service(domain, command)
+        .ok_or_else(|| SmartHomeError::Unsupported(command.to_string(), entity_id.to_string()))?;
+    data["entity_id"] = Value::from(entity_id);
+    Client::new()
+        .post(format!("{}/api/services/{}/{}", url, domain, service))
+        .bearer_auth(token)
+        .json(&data)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct EntityState {
+    state: String,
+    #[serde(default)]
+    attributes: Attributes,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Attributes {
+    friendly_name: Option<String>,
+    unit_of_measurement: Option<String>,
+}
+
+/// "Living Room Lights is on", "Office Temperature is 21.5 °C".
+async fn rest_state(
+    home: &HomeAssistantConfig,
+    entity_id: &str,
+    name: &str,
+) -> Result<String, SmartHomeError> {
+    let (url, token) = api(home)?;
+    let state = Client::new()
+        .get(format!("{}/api/states/{}", url, entity_id))
+        .bearer_auth(token)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<EntityState>()
+        .await?;
+    let name = state.attributes.friendly_name.as_deref().unwrap_or(name);
+    let value = state.state.replace('_', " ");
+    Ok(match &state.attributes.unit_of_measurement {
+        Some(unit) => format!("{} is {} {}", name, value, unit),
+        None => format!("{} is {}", name, value),
+    })
+}
+
+/// What to publish to an MQTT device's command topic.
+fn mqtt_payload(entity: &MqttEntity, command: &str) -> Option<String> {
+    match command {
+        "turn_on" => Some(entity.payload_on.clone()),
+        "turn_off" => Some(entity.payload_off.clone()),
+        "toggle" => Some("TOGGLE".to_string()),
+        _ => level(command).map(|value| value.to_string()),
+    }
+}
+
+fn mqtt_client(mqtt: &MqttConfig) -> (AsyncClient, rumqttc::EventLoop) {
+    let mut options = MqttOptions::new(&mqtt.client_id, &mqtt.host, mqtt.port);
+    options.set_keep_alive(Duration::from_secs(5));
+    if let Some(username) = &mqtt.username {
+        options.set_credentials(username, mqtt.password.clone().unwrap_or_default());
+    }
+    AsyncClient::new(options, 10)
+}
+
+/// Publishes `payload` and waits for the broker to acknowledge it.
+async fn mqtt_publish(mqtt: &MqttConfig, topic: &str, payload: String) -> Result<(), SmartHomeError> {
+    let (client, mut events) = mqtt_client(mqtt);
+    client.publish(topic, QoS::AtLeastOnce, false, payload).await?;
+    loop {
+        if let Event::Incoming(Packet::PubAck(_)) = events.poll().await? {
+            break;
+        }
+    }
+    let _ = client.disconnect().await;
+    Ok(())
+}
+
+/// The next message on `topic`; devices usually publish their state
+/// retained, so it arrives as soon as Buddy subscribes.
+async fn mqtt_state(mqtt: &MqttConfig, topic: &str, name: &str) -> Result<String, SmartHomeError> {
+    let (client, mut events) = mqtt_client(mqtt);
+    client.subscribe(topic, QoS::AtMostOnce).await?;
+    let payload = loop {
+        match events.poll().await? {
+            Event::Incoming(Packet::Publish(message)) if message.topic == topic => {
+                break String::from_utf8_lossy(&message.payload).to_string();
+            }
+            _ => {}
+        }
+    };
+    let _ = client.disconnect().await;
+    if payload.trim().is_empty() {
+        return Err(SmartHomeError::NoState(name.to_string()));
+    }
+    Ok(payload)
+}
@@ -0,0 +1,259 @@
+use crate::{
+    audio::{AudioCapturer, AudioError},
+    audit::{self, Source},
+    config::Config,
+    executor::{self, CommandExecutor, ExecutionError, ExecutionResult},
+    feedback::FeedbackPlayer,
+    hotkey::{HotkeyAction, HotkeyListener},
+    intent::{Intent, IntentClient, IntentError, Spoken},
+    pipeline::{self, Outcome, PipelineError},
+    transcription::{Transcriber, Transcript},
+    windows_api::WindowsActionError,
+};
+use std::{future::Future, pin::Pin, sync::Arc, time::Duration};
+
+/// What a stage's async methods return. Boxed so stages can be swapped at run
+/// time (`Box<dyn Stt>`) and mocked in tests.
+pub type StageFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Says when to listen: a hotkey, a wake word, a button in a GUI.
+pub trait Trigger: Send {
+    /// Resolves on the next request to listen.
+    fn wait(&mut self) -> StageFuture<'_, Result<(), PipelineError>>;
+}
+
+/// Records one utterance as 16 kHz mono samples.
+pub trait Capture: Send + Sync {
+    fn record(&self) -> StageFuture<'_, Result<Vec<i16>, PipelineError>>;
+}
+
+/// Turns a recording into text.
+pub trait Stt: Send + Sync {
+    fn transcribe(&self, audio: Vec<i16>) -> StageFuture<'_, Result<Transcript, PipelineError>>;
+}
+
+/// Turns text into an intent.
+pub trait IntentEngine: Send + Sync {
+    /// Classifies `text`, returning the intent and what classified it:
+    /// "model", "calculator", "rules"...
+    fn classify<'a>(
+        &'a self,
+        text: &'a str,
+        spoken: Spoken<'a>,
+    ) -> StageFuture<'a, Result<(Intent, &'static str), IntentError>>;
+
+    /// Resolves once the engine can classify, or with why it can't after
+    /// `timeout`. Engines with nothing to wait for are always ready.
+    fn wait_until_ready(&self, _timeout: Duration) -> StageFuture<'_, Result<(), IntentError>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Carries out intents.
+pub trait Executor: Send + Sync {
+    fn execute(&self, intent: &Intent) -> Result<ExecutionResult, ExecutionError>;
+}
+
+/// Tells the user how it went. Every method does nothing unless overridden.
+pub trait Feedback: Send {
+    /// Resolves once recording may start, e.g. after a start cue has played.
+    fn recording_started(&mut self) -> StageFuture<'_, ()> {
+        Box::pin(async {})
+    }
+    fn recording_stopped(&mut self) {}
+    fn report(&mut self, _outcome: &Outcome) {}
+    fn report_error(&mut self, _err: &PipelineError) {}
+}
+
+/// Feedback for embedders that show results themselves.
+pub struct Silent;
+
+impl Feedback for Silent {}
+
+/// The `[audio]` microphone, recording for up to `audio.capture_duration_secs`.
+pub struct Microphone {
+    capturer: Arc<AudioCapturer>,
+    max_duration: Option<Duration>,
+}
+
+impl Microphone {
+    pub fn new(config: &Config, debug: bool) -> Result<Self, AudioError> {
+        let max_duration = match config.audio.capture_duration_secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        };
+        Ok(Self {
+            capturer: Arc::new(AudioCapturer::new(&config.audio, debug)?),
+            max_duration,
+        })
+    }
+}
+
+impl Capture for Microphone {
+    fn record(&self) -> StageFuture<'_, Result<Vec<i16>, PipelineError>> {
+        let capturer = Arc::clone(&self.capturer);
+        let max_duration = self.max_duration;
+        Box::pin(async move {
+            Ok(tokio::task::spawn_blocking(move || capturer.capture(max_duration)).await??)
+        })
+    }
+}
+
+/// The `transcription.engine` recognizer, on a blocking thread.
+pub struct Transcription(pub Arc<Transcriber>);
+
+impl Stt for Transcription {
+    fn transcribe(&self, audio: Vec<i16>) -> StageFuture<'_, Result<Transcript, PipelineError>> {
+        let transcriber = Arc::clone(&self.0);
+        Box::pin(async move {
+            Ok(tokio::task::spawn_blocking(move || transcriber.transcribe_command(&audio)).await??)
+        })
+    }
+}
+
+/// The local recognizers, then the intent service; see `pipeline::classify`.
+/// "Run command" intents come back with the command written.
+pub struct IntentService {
+    client: IntentClient,
+    config: Config,
+    debug: bool,
+}
+
+impl IntentService {
+    pub fn new(config: &Config, debug: bool) -> Self {
+        Self {
+            client: IntentClient::new(config),
+            config: config.clone(),
+            debug,
+        }
+    }
+}
+
+impl IntentEngine for IntentService {
+    fn classify<'a>(
+        &'a self,
+        text: &'a str,
+        spoken: Spoken<'a>,
+    ) -> StageFuture<'a, Result<(Intent, &'static str), IntentError>> {
+        Box::pin(async move {
+            pipeline::classify(&self.client, text, spoken, &self.config, self.debug, &mut |_| {})
+                .await
+        })
+    }
+
+    fn wait_until_ready(&self, timeout: Duration) -> StageFuture<'_, Result<(), IntentError>> {
+        Box::pin(pipeline::wait_for_intent_ready(&self.client, timeout, false))
+    }
+}
+
+/// `CommandExecutor` under `[policy]`, writing system actions to
+/// `logging.audit_log`.
+pub struct Actions {
+    config: Config,
+    source: Source,
+}
+
+impl Actions {
+    /// Audits what it runs as coming from `Source::Library`.
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: config.clone(),
+            source: Source::Library,
+        }
+    }
+
+    /// Audits what it runs as coming from `source`, e.g. `Source::Schedule`
+    /// for commands an app runs at set times.
+    pub fn with_source(mut self, source: Source) -> Self {
+        self.source = source;
+        self
+    }
+}
+
+impl Executor for Actions {
+    fn execute(&self, intent: &Intent) -> Result<ExecutionResult, ExecutionError> {
+        let result = CommandExecutor::new(&self.config).execute(intent);
+        audit::record_executed(&self.config, self.source, intent, &result);
+        result
+    }
+}
+
+/// Listens on the command hotkey; the dictation and cancel hotkeys are
+/// ignored.
+impl Trigger for HotkeyListener {
+    fn wait(&mut self) -> StageFuture<'_, Result<(), PipelineError>> {
+        Box::pin(async move {
+            loop {
+                if HotkeyListener::wait(self).await? == HotkeyAction::Command {
+                    return Ok(());
+                }
+            }
+        })
+    }
+}
+
+/// Speaks results the way the Buddy app does, minus its follow-up questions.
+impl Feedback for FeedbackPlayer {
+    fn recording_started(&mut self) -> StageFuture<'_, ()> {
+        Box::pin(self.listening())
+    }
+
+    fn recording_stopped(&mut self) {
+        self.listening_stopped();
+    }
+
+    fn report(&mut self, outcome: &Outcome) {
+        let intent = &outcome.intent;
+        match &outcome.result {
+            // Held back for the caller to confirm.
+            None => {}
+            Some(Ok(ExecutionResult::Action(_))) => {
+                let target = intent.target().map(|target| target.replace('_', " "));
+                let [action, name, _] = executor::policy_names(intent);
+                self.success(
+                    &[name, action],
+                    &[("target", target.as_deref().unwrap_or_default())],
+                );
+            }
+            Some(Ok(ExecutionResult::DryRun(message))) => self.say(message),
+            Some(Ok(
+                ExecutionResult::Answer(response)
+                | ExecutionResult::Ran {
+                    summary: response, ..
+                },
+            )) => self.answer(response),
+            Some(Ok(ExecutionResult::Translation { text, language })) => {
+                self.say_in(text, language)
+            }
+            Some(Ok(ExecutionResult::Choose(choices))) => {
+                let labels: Vec<&str> =
+                    choices.iter().map(|choice| choice.label.as_str()).collect();
+                let question = self.phrase("which_one", &[("options", &labels.join("; "))]);
+                self.say(&question);
+            }
+            Some(Err(ExecutionError::UnknownIntent)) => self.error_phrase("unknown_intent"),
+            Some(Err(ExecutionError::Denied(_))) => self.error_phrase("denied"),
+            Some(Err(ExecutionError::Hook(_))) => self.error_phrase("hook_refused"),
+            Some(Err(ExecutionError::Windows(WindowsActionError::AccessDenied(_)))) => {
+                self.error_phrase("access_denied")
+            }
+            Some(Err(_)) => self.error_phrase("failed"),
+        }
+    }
+
+    fn report_error(&mut self, err: &PipelineError) {
+        match err {
+            PipelineError::NoSpeech => self.error_phrase("didnt_hear"),
+            PipelineError::Unclear(_) => self.error_phrase("didnt_catch"),
+            PipelineError::Audio(AudioError::Cancelled) => {}
+            PipelineError::Audio(AudioError::DeviceLost) => self.error_phrase("mic_disconnected"),
+            PipelineError::Audio(AudioError::MicMuted) => self.error_phrase("mic_muted"),
+            PipelineError::NoMicrophone | PipelineError::Audio(_) => {
+                self.error_phrase("mic_unavailable")
+            }
+            PipelineError::Intent(err) if err.is_loading() => self.error_phrase("model_loading"),
+            PipelineError::Intent(_) => self.error_phrase("intent_failed"),
+            _ => self.error_phrase("failed"),
+        }
+    }
+}
@@ -0,0 +1,804 @@
+use crate::{
+    clock, cloud_speech,
+    config::{SttEngine, TranscriptionConfig},
+    gpu,
+    intent::Spoken,
+    numbers,
+    vocabulary::Vocabulary,
+    windows_speech,
+};
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Mutex, OnceLock, RwLock,
+    },
+    time::{Duration, Instant},
+};
+use whisper_rs::{
+    FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState,
+};
+
+/// One second of 16 kHz audio, for the warm-up pass.
+const WARMUP_SAMPLES: usize = 16_000;
+
+/// Speech to text with the `transcription.engine` recognizer.
+pub struct Transcriber {
+    engine: Engine,
+    debug: bool,
+    /// A whisper language code, "auto" to detect it per utterance, or `None`
+    /// for whisper's default (English). "Switch to German" changes it.
+    language: RwLock<Option<String>>,
+    translate: bool,
+    initial_prompt: RwLock<Option<String>>,
+    vocabulary: RwLock<Vocabulary>,
+    /// Commands transcribed, and how many of them `vocabulary` corrected.
+    commands: AtomicU32,
+    corrected: AtomicU32,
+}
+
+enum Engine {
+    Whisper(Whisper),
+    Windows(windows_speech::Recognizer),
+    Cloud(cloud_speech::Recognizer),
+}
+
+struct Whisper {
+    model: Model,
+    cpu_model: OnceLock<Model>,
+    model_path: String,
+    use_gpu: bool,
+    /// Set after a GPU inference error; later utterances go straight to CPU.
+    gpu_failed: AtomicBool,
+    min_free_vram_mb: u64,
+    threads: i32,
+    debug: bool,
+    suppress_native_logs: bool,
+}
+
+/// A loaded model and the state its passes reuse.
+struct Model {
+    ctx: WhisperContext,
+    /// Kept between passes so each doesn't set up whisper's buffers anew.
+    state: Mutex<Option<WhisperState>>,
+}
+
+impl Transcriber {
+    pub fn new(
+        cfg: &TranscriptionConfig,
+        initial_prompt: Option<String>,
+        debug: bool,
+        suppress_native_logs: bool,
+    ) -> Result<Self, TranscriptionError> {
+        let engine = match cfg.engine {
+            SttEngine::Whisper => Engine::Whisper(Whisper::load(cfg, debug, suppress_native_logs)?),
+            SttEngine::Windows => {
+                if cfg.translate || cfg.language.as_deref().is_some_and(|code| code != "en") {
+                    println!(
+                        "transcription.language and translate apply to whisper only; Windows \
+                         speech recognition uses the language Windows speech is set to"
+                    );
+                }
+                Engine::Windows(windows_speech::Recognizer::new()?)
+            }
+            SttEngine::Cloud => {
+                if cfg.translate {
+                    println!("transcription.translate applies to whisper only");
+                }
+                Engine::Cloud(cloud_speech::Recognizer::new(&cfg.cloud, cfg.allow_cloud)?)
+            }
+        };
+        let transcriber = Self {
+            engine,
+            debug,
+            language: RwLock::new(cfg.language.clone()),
+            translate: cfg.translate,
+            initial_prompt: RwLock::new(initial_prompt),
+            vocabulary: RwLock::new(Vocabulary::new(Vec::new(), cfg.command_bias)),
+            commands: AtomicU32::new(0),
+            corrected: AtomicU32::new(0),
+        };
+        if cfg.warmup {
+            transcriber.warm_up();
+        }
+        Ok(transcriber)
+    }
+
+    /// Runs whisper once on silence, so that the first command doesn't pay
+    /// for setting up the GPU and the model's buffers.
+    fn warm_up(&self) {
+        if !matches!(self.engine, Engine::Whisper(_)) {
+            return;
+        }
+        let start = Instant::now();
+        match self.run(&[0; WARMUP_SAMPLES], false) {
+            Ok(_) if self.debug => {
+                println!("Whisper warmed up in {:.2}s", start.elapsed().as_secs_f32());
+            }
+            Ok(_) => {}
+            Err(err) => eprintln!("Whisper warm-up failed: {}", err),
+        }
+    }
+
+    pub fn transcribe(&self, audio: &[i16]) -> Result<String, TranscriptionError> {
+        self.run(audio, false).map(|pass| pass.text)
+    }
+
+    /// Transcribes a command for intent classification. With `translate` on,
+    /// non-English speech is translated to English by a second whisper pass and
+    /// the original wording is kept alongside it. A misheard file, app or URL
+    /// name is corrected to the command vocabulary. The confidence is the
+    /// first pass's, over what was actually said.
+    pub fn transcribe_command(&self, audio: &[i16]) -> Result<Transcript, TranscriptionError> {
+        let Pass {
+            text: original,
+            language,
+            confidence,
+        } = self.run(audio, false)?;
+        if !self.translate || original.is_empty() || matches!(language, None | Some("en")) {
+            return Ok(Transcript {
+                text: self.correct_command(original),
+                original: None,
+                language,
+                confidence,
+            });
+        }
+        let english = self.run(audio, true)?.text;
+        if self.debug {
+            println!(
+                "Translated from {}: {} -> {}",
+                language.unwrap_or("?"),
+                original,
+                english
+            );
+        }
+        Ok(Transcript {
+            text: self.correct_command(english),
+            original: Some(original),
+            language,
+            confidence,
+        })
+    }
+
+    /// Times the same clip on whisper's GPU and CPU contexts (the GPU entry
+    /// is an error when CUDA is not in use), or on the other engines.
+    pub fn benchmark(
+        &self,
+        audio: &[i16],
+    ) -> Vec<(&'static str, BenchPass)> {
+        let whisper = match &self.engine {
+            Engine::Whisper(whisper) => whisper,
+            Engine::Windows(_) => return vec![("Windows", time_pass(|| self.run(audio, false)))],
+            Engine::Cloud(_) => return vec![("Cloud", time_pass(|| self.run(audio, false)))],
+        };
+        let gpu = if whisper.use_gpu {
+            time_pass(|| self.run_on(whisper, &whisper.model, audio, false))
+        } else {
+            Err(TranscriptionError::Model(
+                "GPU not in use (needs the cuda feature and transcription.use_gpu)".to_string(),
+            ))
+        };
+        let cpu = whisper
+            .cpu_model()
+            .and_then(|model| time_pass(|| self.run_on(whisper, model, audio, false)));
+        vec![("GPU", gpu), ("CPU", cpu)]
+    }
+
+    /// Transcribes a long recording (a meeting, not a command) into timed
+    /// segments. `speaker_turns` needs a tinydiarize model.
+    pub fn transcribe_segments(
+        &self,
+        audio: &[i16],
+        speaker_turns: bool,
+    ) -> Result<Vec<Segment>, TranscriptionError> {
+        if audio.is_empty() {
+            return Ok(Vec::new());
+        }
+        match &self.engine {
+            Engine::Whisper(whisper) => {
+                whisper.with_model(|model| self.segments_on(whisper, model, audio, speaker_turns))
+            }
+            Engine::Windows(recognizer) => recognizer.recognize(audio),
+            // Services time their words in different ways, so the chunk is
+            // one segment.
+            Engine::Cloud(recognizer) => {
+                let text = recognizer.recognize(audio, self.language().as_deref())?;
+                if text.is_empty() {
+                    return Ok(Vec::new());
+                }
+                Ok(vec![Segment {
+                    start: Duration::ZERO,
+                    end: Duration::from_secs_f64(audio.len() as f64 / 16_000.0),
+                    text,
+                    speaker_turn: false,
+                }])
+            }
+        }
+    }
+
+    /// One pass over `audio`.
+    fn run(&self, audio: &[i16], translate: bool) -> Result<Pass, TranscriptionError> {
+        if audio.is_empty() {
+            return Ok(Pass::default());
+        }
+        match &self.engine {
+            Engine::Whisper(whisper) => {
+                whisper.with_model(|model| self.run_on(whisper, model, audio, translate))
+            }
+            Engine::Windows(recognizer) => {
+                let phrases: Vec<String> = recognizer
+                    .recognize(audio)?
+                    .into_iter()
+                    .map(|segment| segment.text)
+                    .collect();
+                Ok(Pass {
+                    text: phrases.join(" "),
+                    ..Pass::default()
+                })
+            }
+            Engine::Cloud(recognizer) => Ok(Pass {
+                text: recognizer.recognize(audio, self.language().as_deref())?,
+                ..Pass::default()
+            }),
+        }
+    }
+
+    fn segments_on(
+        &self,
+        whisper: &Whisper,
+        model: &Model,
+        audio: &[i16],
+        speaker_turns: bool,
+    ) -> Result<Vec<Segment>, TranscriptionError> {
+        let _silencer = if whisper.suppress_native_logs {
+            StderrSilencer::new()
+        } else {
+            None
+        };
+        // Greedy keeps up with real time on long audio; the command prompt and
+        // token cap don't apply to free-form speech.
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_n_threads(whisper.threads);
+        let language = self.language();
+        params.set_language(Some(language.as_deref().unwrap_or("auto")));
+        params.set_translate(false);
+        params.set_no_context(true);
+        params.set_suppress_blank(true);
+        params.set_suppress_non_speech_tokens(true);
+        params.set_tdrz_enable(speaker_turns);
+        model.with_state(|state| {
+            let start = Instant::now();
+            state
+                .full(params, &to_f32(audio))
+                .map_err(|err| TranscriptionError::Inference(err.to_string()))?;
+            whisper.report_speed(model, audio, start.elapsed());
+
+            let num_segments = state
+                .full_n_segments()
+                .map_err(|err| TranscriptionError::State(err.to_string()))?;
+            let mut segments = Vec::new();
+            for idx in 0..num_segments {
+                let Ok(text) = state.full_get_segment_text(idx) else {
+                    continue;
+                };
+                let text = text.trim();
+                if text.is_empty() {
+                    continue;
+                }
+                // whisper times segments in centiseconds.
+                let time = |t: Result<i64, _>| {
+                    Duration::from_millis(t.unwrap_or(0).max(0) as u64 * 10)
+                };
+                segments.push(Segment {
+                    start: time(state.full_get_segment_t0(idx)),
+                    end: time(state.full_get_segment_t1(idx)),
+                    text: text.to_string(),
+                    speaker_turn: speaker_turns && state.full_get_segment_speaker_turn_next(idx),
+                });
+            }
+            Ok(segments)
+        })
+    }
+
+    fn run_on(
+        &self,
+        whisper: &Whisper,
+        model: &Model,
+        audio: &[i16],
+        translate: bool,
+    ) -> Result<Pass, TranscriptionError> {
+        let _silencer = if whisper.suppress_native_logs {
+            StderrSilencer::new()
+        } else {
+            None
+        };
+        let mut params = FullParams::new(SamplingStrategy::BeamSearch {
+            beam_size: 5,
+            patience: 0.0,
+        });
+        params.set_n_threads(whisper.threads);
+        // whisper assumes English unless told to detect the language.
+        let language = self
+            .language()
+            .or_else(|| self.translate.then(|| "auto".to_string()));
+        if let Some(lang) = &language {
+            params.set_language(Some(lang));
+        }
+        params.set_translate(translate);
+        params.set_temperature(0.0);
+        params.set_temperature_inc(0.0);
+        params.set_no_context(true);
+        params.set_single_segment(true);
+        params.set_max_tokens(32);
+        params.set_suppress_blank(true);
+        params.set_suppress_non_speech_tokens(true);
+        let initial_prompt = self
+            .initial_prompt
+            .read()
+            .map(|prompt| prompt.clone())
+            .unwrap_or_default();
+        if let Some(prompt) = &initial_prompt {
+            params.set_initial_prompt(prompt);
+        }
+
+        model.with_state(|state| {
+            let start = Instant::now();
+            state
+                .full(params, &to_f32(audio))
+                .map_err(|err| TranscriptionError::Inference(err.to_string()))?;
+            whisper.report_speed(model, audio, start.elapsed());
+
+            let num_segments = state
+                .full_n_segments()
+                .map_err(|err| TranscriptionError::State(err.to_string()))?;
+            let mut transcript = String::new();
+            let eot = model.ctx.token_eot();
+            let (mut probability, mut tokens) = (0.0, 0);
+            for idx in 0..num_segments {
+                if let Ok(segment) = state.full_get_segment_text(idx) {
+                    let text = segment.trim();
+                    if !text.is_empty() {
+                        if !transcript.is_empty() {
+                            transcript.push(' ');
+                        }
+                        transcript.push_str(text);
+                    }
+                }
+                // Tokens from end-of-text up are markers and timestamps, not
+                // words.
+                for token in 0..state.full_n_tokens(idx).unwrap_or(0) {
+                    if state.full_get_token_id(idx, token).is_ok_and(|id| id < eot) {
+                        probability += state.full_get_token_prob(idx, token).unwrap_or(0.0);
+                        tokens += 1;
+                    }
+                }
+            }
+            let language = if self.translate || language.as_deref() == Some("auto") {
+                state
+                    .full_lang_id_from_state()
+                    .ok()
+                    .and_then(whisper_rs::get_lang_str)
+            } else {
+                None
+            };
+            Ok(Pass {
+                text: transcript,
+                language,
+                confidence: (tokens > 0).then(|| probability / tokens as f32),
+            })
+        })
+    }
+}
+
+/// What one pass over a recording heard.
+#[derive(Debug, Default)]
+struct Pass {
+    text: String,
+    /// The language spoken, when whisper was auto-detecting it.
+    language: Option<&'static str>,
+    confidence: Option<f32>,
+}
+
+/// One stretch of speech in a long recording, timed from the start of the
+/// audio it came from.
+#[derive(Debug, Clone)]
+pub struct Segment {
+    pub start: Duration,
+    pub end: Duration,
+    pub text: String,
+    /// tinydiarize heard someone else start talking after this segment.
+    pub speaker_turn: bool,
+}
+
+pub struct Transcript {
+    /// Text to classify; English when translation kicked in.
+    pub text: String,
+    /// What was actually said, when `text` is a translation.
+    pub original: Option<String>,
+    /// The language spoken, when whisper was asked to detect it ("auto") or
+    /// to translate.
+    pub language: Option<&'static str>,
+    /// Whisper's average probability for the words it heard, from 0 to 1;
+    /// `None` from engines that don't give one.
+    pub confidence: Option<f32>,
+}
+
+impl Transcript {
+    pub fn spoken(&self) -> Spoken<'_> {
+        Spoken {
+            original: self.original.as_deref(),
+            language: self.language,
+            confidence: self.confidence,
+        }
+    }
+
+    /// Whether whisper was too unsure of the words to act on them (see
+    /// `transcription.min_confidence`).
+    pub fn unclear(&self, min_confidence: f32) -> bool {
+        self.confidence.is_some_and(|confidence| confidence < min_confidence)
+    }
+}
+
+impl Transcriber {
+    pub fn language(&self) -> Option<String> {
+        self.language
+            .read()
+            .map(|language| language.clone())
+            .unwrap_or_default()
+    }
+
+    /// Pins the spoken language (a whisper code), or "auto" to detect it.
+    pub fn set_language(&self, language: Option<String>) {
+        if let Ok(mut current) = self.language.write() {
+            *current = language;
+        }
+    }
+
+    /// The names commands are corrected to (see `transcription.command_bias`):
+    /// the configured file, app and URL keys.
+    pub fn set_command_vocabulary(&self, names: Vec<String>) {
+        if let Ok(mut vocabulary) = self.vocabulary.write() {
+            *vocabulary = Vocabulary::new(names, vocabulary.bias());
+        }
+    }
+
+    /// `text` with its target corrected to the command vocabulary, if it is a
+    /// command that needs it. Debug mode prints each correction and how many
+    /// commands have needed one.
+    fn correct_command(&self, text: String) -> String {
+        if text.is_empty() {
+            return text;
+        }
+        let commands = self.commands.fetch_add(1, Ordering::Relaxed) + 1;
+        let Some(corrected) = self
+            .vocabulary
+            .read()
+            .ok()
+            .and_then(|vocabulary| vocabulary.correct(&text))
+        else {
+            return text;
+        };
+        let count = self.corrected.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.debug {
+            println!(
+                "Corrected \"{}\" to \"{}\" ({} of {} commands, {:.0}%)",
+                text,
+                corrected,
+                count,
+                commands,
+                count as f32 * 100.0 / commands as f32
+            );
+        }
+        corrected
+    }
+
+    pub fn set_initial_prompt(&self, prompt: Option<String>) {
+        if let Ok(mut current) = self.initial_prompt.write() {
+            *current = prompt;
+        }
+    }
+}
+
+impl Whisper {
+    fn load(
+        cfg: &TranscriptionConfig,
+        debug: bool,
+        suppress_native_logs: bool,
+    ) -> Result<Self, TranscriptionError> {
+        let model_path = resolve_path(&cfg.model_path);
+        let mut use_gpu = cfg!(feature = "cuda") && cfg.use_gpu;
+        if cfg.use_gpu && !cfg!(feature = "cuda") && debug {
+            println!("transcription.use_gpu is set but Buddy was built without the cuda feature");
+        }
+        if debug {
+            println!("Whisper context use_gpu: {} (device {})", use_gpu, cfg.gpu_device);
+        }
+        let model = if use_gpu {
+            match Model::load(&model_path, Some(cfg.gpu_device)) {
+                Ok(model) => model,
+                Err(err) => {
+                    eprintln!("GPU transcription unavailable ({}); falling back to CPU", err);
+                    use_gpu = false;
+                    Model::load(&model_path, None)?
+                }
+            }
+        } else {
+            Model::load(&model_path, None)?
+        };
+        // Extra threads on hyperthreaded cores only slow whisper down.
+        let threads = cfg
+            .threads
+            .filter(|threads| *threads > 0)
+            .unwrap_or_else(num_cpus::get_physical)
+            .clamp(1, 16) as i32;
+        if debug {
+            println!("Whisper threads: {}", threads);
+        }
+        Ok(Self {
+            model,
+            cpu_model: OnceLock::new(),
+            model_path,
+            use_gpu,
+            gpu_failed: AtomicBool::new(false),
+            min_free_vram_mb: cfg.min_free_vram_mb,
+            threads,
+            debug,
+            suppress_native_logs,
+        })
+    }
+
+    /// Runs `pass` on the model `select_model` picks. A failed GPU pass is
+    /// retried on CPU, which then serves the rest of the session.
+    fn with_model<T>(
+        &self,
+        pass: impl Fn(&Model) -> Result<T, TranscriptionError>,
+    ) -> Result<T, TranscriptionError> {
+        let model = self.select_model()?;
+        match pass(model) {
+            Err(err) if self.on_gpu(model) => {
+                eprintln!("GPU transcription failed ({}); switching to CPU", err);
+                self.gpu_failed.store(true, Ordering::Relaxed);
+                pass(self.cpu_model()?)
+            }
+            result => result,
+        }
+    }
+
+    fn on_gpu(&self, model: &Model) -> bool {
+        self.use_gpu && std::ptr::eq(model, &self.model)
+    }
+
+    /// In debug, how long a pass took against the length of its audio: under
+    /// 1x keeps up with speech.
+    fn report_speed(&self, model: &Model, audio: &[i16], elapsed: Duration) {
+        if !self.debug {
+            return;
+        }
+        let seconds = audio.len() as f32 / 16_000.0;
+        println!(
+            "Whisper: {:.1}s of audio in {:.2}s on {} ({:.2}x realtime, {} threads)",
+            seconds,
+            elapsed.as_secs_f32(),
+            if self.on_gpu(model) { "GPU" } else { "CPU" },
+            elapsed.as_secs_f32() / seconds.max(f32::EPSILON),
+            self.threads
+        );
+    }
+
+    /// Falls back to the CPU model for this utterance when another process
+    /// (usually a game) has left too little VRAM for CUDA inference.
+    fn select_model(&self) -> Result<&Model, TranscriptionError> {
+        if !self.use_gpu {
+            return Ok(&self.model);
+        }
+        if self.gpu_failed.load(Ordering::Relaxed) {
+            return self.cpu_model();
+        }
+        if self.min_free_vram_mb == 0 {
+            return Ok(&self.model);
+        }
+        match gpu::available_vram_mb() {
+            Some(free) if free < self.min_free_vram_mb => {
+                println!(
+                    "Only {} MB VRAM free (need {} MB); transcribing on CPU",
+                    free, self.min_free_vram_mb
+                );
+                self.cpu_model()
+            }
+            Some(free) => {
+                if self.debug {
+                    println!("VRAM free: {} MB; transcribing on GPU", free);
+                }
+                Ok(&self.model)
+            }
+            None => Ok(&self.model),
+        }
+    }
+
+    /// The CPU model: the main one when the GPU is not used, otherwise a
+    /// second copy loaded on first use.
+    fn cpu_model(&self) -> Result<&Model, TranscriptionError> {
+        if !self.use_gpu {
+            return Ok(&self.model);
+        }
+        if let Some(model) = self.cpu_model.get() {
+            return Ok(model);
+        }
+        let model = Model::load(&self.model_path, None)?;
+        Ok(self.cpu_model.get_or_init(|| model))
+    }
+}
+
+impl Model {
+    /// Loads the model on `gpu_device`, or on the CPU when `None`.
+    fn load(model_path: &str, gpu_device: Option<i32>) -> Result<Self, TranscriptionError> {
+        let mut ctx_params = WhisperContextParameters::new();
+        ctx_params.use_gpu(gpu_device.is_some());
+        if let Some(device) = gpu_device {
+            ctx_params.gpu_device(device);
+        }
+        let ctx = WhisperContext::new_with_params(model_path, ctx_params)
+            .map_err(|err| TranscriptionError::Model(err.to_string()))?;
+        Ok(Self {
+            ctx,
+            state: Mutex::new(None),
+        })
+    }
+
+    /// Runs `pass` on the kept state, or on a fresh one while another pass
+    /// (a meeting chunk during a command) is using it.
+    fn with_state<T>(
+        &self,
+        pass: impl FnOnce(&mut WhisperState) -> Result<T, TranscriptionError>,
+    ) -> Result<T, TranscriptionError> {
+        let Ok(mut kept) = self.state.try_lock() else {
+            return pass(&mut self.new_state()?);
+        };
+        let state = match kept.as_mut() {
+            Some(state) => state,
+            None => kept.insert(self.new_state()?),
+        };
+        pass(state)
+    }
+
+    fn new_state(&self) -> Result<WhisperState, TranscriptionError> {
+        self.ctx
+            .create_state()
+            .map_err(|err| TranscriptionError::State(err.to_string()))
+    }
+}
+
+/// The whisper code for a language named in English ("german" -> "de");
+/// codes themselves pass through.
+pub fn language_code(name: &str) -> Option<&'static str> {
+    whisper_rs::get_lang_id(&name.to_lowercase()).and_then(whisper_rs::get_lang_str)
+}
+
+/// "German" for "de".
+pub fn language_name(code: &str) -> Option<String> {
+    let name = whisper_rs::get_lang_id(code).and_then(whisper_rs::get_lang_str_full)?;
+    let mut chars = name.chars();
+    Some(chars.next()?.to_uppercase().chain(chars).collect())
+}
+
+/// Words `heard` got wrong against `reference` (substituted, missed or
+/// added), and the words in `reference`: their ratio is the word error
+/// rate. Case, punctuation and spelled-out numbers don't count as errors.
+pub fn word_errors(reference: &str, heard: &str) -> (usize, usize) {
+    let reference = comparable_words(reference);
+    let heard = comparable_words(heard);
+    let mut previous: Vec<usize> = (0..=heard.len()).collect();
+    for (row, expected) in reference.iter().enumerate() {
+        let mut current = vec![row + 1; heard.len() + 1];
+        for (column, word) in heard.iter().enumerate() {
+            let substitution = previous[column] + usize::from(expected != word);
+            current[column + 1] = substitution
+                .min(previous[column + 1] + 1)
+                .min(current[column] + 1);
+        }
+        previous = current;
+    }
+    (previous[heard.len()], reference.len())
+}
+
+fn comparable_words(text: &str) -> Vec<String> {
+    numbers::normalize(&clock::words(text).join(" "))
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+fn to_f32(audio: &[i16]) -> Vec<f32> {
+    audio
+        .iter()
+        .map(|sample| *sample as f32 / i16::MAX as f32)
+        .collect()
+}
+
+/// Wall time and text of one benchmarked pass.
+pub type BenchPass = Result<(Duration, String), TranscriptionError>;
+
+fn time_pass(pass: impl FnOnce() -> Result<Pass, TranscriptionError>) -> BenchPass {
+    let started = Instant::now();
+    pass().map(|pass| (started.elapsed(), pass.text))
+}
+
+struct StderrSilencer {
+    saved_fd: i32,
+}
+
+impl StderrSilencer {
+    fn new() -> Option<Self> {
+        unsafe {
+            let saved_fd = _dup(2);
+            if saved_fd == -1 {
+                return None;
+            }
+            let nul = std::fs::OpenOptions::new().write(true).open("NUL").ok()?;
+            let handle = std::os::windows::io::IntoRawHandle::into_raw_handle(nul);
+            let nul_fd = _open_osfhandle(handle as isize, 0);
+            if nul_fd == -1 {
+                let _ = _close(saved_fd);
+                return None;
+            }
+            if _dup2(nul_fd, 2) == -1 {
+                let _ = _close(nul_fd);
+                let _ = _close(saved_fd);
+                return None;
+            }
+            let _ = _close(nul_fd);
+            Some(Self { saved_fd })
+        }
+    }
+}
+
+impl Drop for StderrSilencer {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = _dup2(self.saved_fd, 2);
+            let _ = _close(self.saved_fd);
+        }
+    }
+}
+
+extern "C" {
+    fn _dup(fd: i32) -> i32;
+    fn _dup2(fd: i32, fd2: i32) -> i32;
+    fn _close(fd: i32) -> i32;
+    fn _open_osfhandle(osfhandle: isize, flags: i32) -> i32;
+}
+
+fn resolve_path(path: &Path) -> String {
+    if path.is_absolute() {
+        path.to_string_lossy().to_string()
+    } else {
+        std::env::current_dir()
+            .unwrap_or_else(|_| Path::new(".").to_path_buf())
+            .join(path)
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+#[derive(Debug)]
+pub enum TranscriptionError {
+    Model(String),
+    State(String),
+    Inference(String),
+    Recognizer(String),
+    Cloud(String),
+}
+
+impl std::fmt::Display for TranscriptionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Model(err) => write!(f, "failed to load Whisper model: {}", err),
+            Self::State(err) => write!(f, "failed to initialize Whisper state: {}", err),
+            Self::Inference(err) => write!(f, "transcription error: {}", err),
+            Self::Recognizer(err) => write!(f, "Windows speech recognition failed: {}", err),
+            Self::Cloud(err) => write!(f, "cloud transcription failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for TranscriptionError {}
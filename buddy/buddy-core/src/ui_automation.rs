@@ -0,0 +1,147 @@
+//! "Read this": finds the selected text in the focused window, or failing
+//! that the focused control's own text, so it can be read aloud.
+
+use crate::{clock, intent::Intent, rules::EXACT_CONFIDENCE};
+
+/// Longer text is cut here; a whole book is more than anyone wants to hear
+/// "continue" after every few sentences of.
+const MAX_CHARS: usize = 20_000;
+
+#[derive(Debug)]
+pub enum UiError {
+    #[cfg(target_os = "windows")]
+    Windows(windows::core::Error),
+    #[cfg_attr(windows, allow(dead_code))]
+    Unsupported(&'static str),
+}
+
+impl std::fmt::Display for UiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(target_os = "windows")]
+            Self::Windows(err) => write!(f, "ui automation error: {}", err),
+            Self::Unsupported(msg) => write!(f, "unsupported: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for UiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(target_os = "windows")]
+            Self::Windows(err) => Some(err),
+            Self::Unsupported(_) => None,
+        }
+    }
+}
+
+/// Recognizes "read this", "read this out loud" and "read the selection".
+pub fn recognize(transcript: &str) -> Option<Intent> {
+    let words = clock::words(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let words = words.strip_suffix(&["please"]).unwrap_or(&words);
+    let words = words
+        .strip_suffix(&["to", "me"])
+        .or_else(|| words.strip_suffix(&["out", "loud"]))
+        .or_else(|| words.strip_suffix(&["aloud"]))
+        .or_else(|| words.strip_suffix(&["out"]))
+        .unwrap_or(words);
+    match words {
+        ["read", "this" | "that" | "it"]
+        | ["read", "the" | "my", "selection" | "selected" | "highlighted"]
+        | ["read", "the" | "my", "selected" | "highlighted", "text"]
+        | ["read", "what", "i", "selected" | "highlighted"]
+        | ["read", "the", "window" | "page"] => Some(Intent::ReadText {
+            confidence: EXACT_CONFIDENCE,
+        }),
+        _ => None,
+    }
+}
+
+/// The text selected in the focused window, or the focused control's own
+/// text when nothing is selected; empty when there is neither. Cut to
+/// `MAX_CHARS`.
+pub fn focused_text() -> Result<String, UiError> {
+    let text = read_focused()?;
+    let text = text.trim();
+    Ok(match text.char_indices().nth(MAX_CHARS) {
+        Some((end, _)) => text[..end].to_string(),
+        None => text.to_string(),
+    })
+}
+
+/// Asks UI Automation for the selection first, failing that the focused
+/// control's document, value or name. The selection isn't copied with
+/// ctrl+c, which would leave the user's clipboard changed.
+#[cfg(target_os = "windows")]
+fn read_focused() -> Result<String, UiError> {
+    use windows::Win32::{
+        System::Com::{
+            CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER,
+            COINIT_MULTITHREADED,
+        },
+        UI::Accessibility::{
+            CUIAutomation, IUIAutomation, IUIAutomationTextPattern, IUIAutomationValuePattern,
+            UIA_TextPatternId, UIA_ValuePatternId,
+        },
+    };
+
+    let initialized = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED).is_ok() };
+    let result = (|| unsafe {
+        let automation: IUIAutomation =
+            CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER)
+                .map_err(UiError::Windows)?;
+        let element = automation.GetFocusedElement().map_err(UiError::Windows)?;
+        let text_pattern =
+            element.GetCurrentPatternAs::<IUIAutomationTextPattern>(UIA_TextPatternId).ok();
+        if let Some(selected) = text_pattern.as_ref().and_then(|pattern| selection(pattern)) {
+            return Ok(selected);
+        }
+        let document = text_pattern
+            .and_then(|pattern| pattern.DocumentRange().ok())
+            .and_then(|range| range.GetText(-1).ok())
+            .map(|text| text.to_string())
+            .filter(|text| !text.trim().is_empty());
+        if let Some(document) = document {
+            return Ok(document);
+        }
+        let value = element
+            .GetCurrentPatternAs::<IUIAutomationValuePattern>(UIA_ValuePatternId)
+            .and_then(|pattern| pattern.CurrentValue())
+            .map(|text| text.to_string())
+            .ok()
+            .filter(|text| !text.trim().is_empty());
+        if let Some(value) = value {
+            return Ok(value);
+        }
+        Ok(element
+            .CurrentName()
+            .map(|text| text.to_string())
+            .unwrap_or_default())
+    })();
+    if initialized {
+        unsafe { CoUninitialize() };
+    }
+    result
+}
+
+#[cfg(not(target_os = "windows"))]
+fn read_focused() -> Result<String, UiError> {
+    Err(UiError::Unsupported("reading the focused window requires Windows"))
+}
+
+/// Every selected range, joined; `None` when nothing is selected.
+#[cfg(target_os = "windows")]
+unsafe fn selection(
+    pattern: &windows::Win32::UI::Accessibility::IUIAutomationTextPattern,
+) -> Option<String> {
+    let ranges = pattern.GetSelection().ok()?;
+    let mut parts = Vec::new();
+    for index in 0..ranges.Length().ok()? {
+        if let Ok(text) = ranges.GetElement(index).and_then(|range| range.GetText(-1)) {
+            parts.push(text.to_string());
+        }
+    }
+    let text = parts.join("\n");
+    (!text.trim().is_empty()).then_some(text)
+}
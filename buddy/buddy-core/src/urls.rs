@@ -0,0 +1,94 @@
+use crate::{clock, config::Config, intent::Intent, rules::EXACT_CONFIDENCE};
+
+/// Words dropped before a bookmark name: "open my dashboard".
+const FILLER_WORDS: &[&str] = &["the", "my", "a", "website", "site", "page", "please"];
+
+/// Recognizes "open github" for `[urls]` names and "go to example dot com"
+/// for spoken addresses.
+pub fn recognize(transcript: &str, config: &Config) -> Option<Intent> {
+    let words = clock::words(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let (address, rest) = match words.as_slice() {
+        ["go" | "browse" | "navigate", "to", rest @ ..] | ["visit", rest @ ..] => (true, rest),
+        ["open" | "show", rest @ ..] => (false, rest),
+        _ => return None,
+    };
+    let trimmed: Vec<&str> = rest
+        .iter()
+        .copied()
+        .filter(|word| !FILLER_WORDS.contains(word))
+        .collect();
+    // A key may itself start with a filler word ("my_dashboard").
+    let key = url_key(config, &rest.join(" ")).or_else(|| url_key(config, &trimmed.join(" ")));
+    let target = match key {
+        Some(key) => key,
+        // The transcript, not `words`, keeps the dots Whisper writes itself.
+        None if address => spoken_address(transcript)?,
+        None => return None,
+    };
+    Some(Intent::OpenUrl {
+        target,
+        confidence: EXACT_CONFIDENCE,
+    })
+}
+
+fn url_key(config: &Config, phrase: &str) -> Option<String> {
+    config
+        .urls
+        .keys()
+        .find(|key| key.to_lowercase().replace(['_', '-'], " ") == phrase)
+        .cloned()
+}
+
+/// The address after "go to", if it reads as one.
+fn spoken_address(transcript: &str) -> Option<String> {
+    let lower = transcript.to_lowercase();
+    let rest = ["go to", "browse to", "navigate to", "visit"]
+        .iter()
+        .find_map(|verb| lower.trim().strip_prefix(verb))?;
+    normalize(rest)
+}
+
+/// The URL for `target`: its `[urls]` entry, or `target` itself read as an
+/// address.
+pub fn resolve(target: &str, config: &Config) -> Option<String> {
+    match config.urls.get(target) {
+        Some(url) => Some(url.clone()),
+        None => normalize(target),
+    }
+}
+
+/// Turns a spoken address into a URL: "example dot com slash docs" becomes
+/// "https://example.com/docs". Returns `None` unless the result has a host
+/// with a dot and a top-level domain of letters.
+pub fn normalize(spoken: &str) -> Option<String> {
+    let spoken = spoken.trim().trim_end_matches(['.', '!', '?']).to_lowercase();
+    let mut address = String::new();
+    for word in spoken.split_whitespace() {
+        match word {
+            "dot" | "period" | "point" => address.push('.'),
+            "slash" => address.push('/'),
+            "colon" => address.push(':'),
+            "dash" | "hyphen" => address.push('-'),
+            "underscore" => address.push('_'),
+            _ => address.push_str(word),
+        }
+    }
+    let (scheme, rest) = match address.split_once("://") {
+        Some((scheme, rest)) if matches!(scheme, "http" | "https") => (scheme, rest),
+        Some(_) => return None,
+        None => ("https", address.as_str()),
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_name = host.split(':').next().unwrap_or_default();
+    let valid_host = host_name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-');
+    let top_level = host_name.rsplit_once('.').map(|(_, tld)| tld);
+    let valid_tld =
+        top_level.is_some_and(|tld| tld.len() >= 2 && tld.chars().all(|c| c.is_ascii_alphabetic()));
+    if !valid_host || !valid_tld || host_name.starts_with('.') || host_name.contains("..") {
+        return None;
+    }
+    Some(format!("{}://{}", scheme, rest))
+}
@@ -0,0 +1,194 @@
+//! How often each file, app and URL key is opened, kept as JSON at
+//! `usage.path`. The most used keys go first in the prompts and win ties in
+//! the offline matchers. Counts fade by half every `usage.half_life_days`,
+//! so old habits give way to new ones.
+
+use crate::{clock, config::UsageConfig, intent::Intent};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Scores that have faded below this are dropped from the store.
+const FORGOTTEN: f64 = 0.01;
+
+/// Keeps two commands from interleaving a read and a write of the store.
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Store {
+    /// By "open_app:chrome" and so on.
+    #[serde(default)]
+    targets: HashMap<String, Entry>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Entry {
+    score: f64,
+    /// Unix seconds when `score` was last brought up to date.
+    updated: i64,
+}
+
+#[derive(Debug)]
+pub enum UsageError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, serde_json::Error),
+}
+
+impl std::fmt::Display for UsageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "failed to access {}: {}", path.display(), err),
+            Self::Parse(path, err) => write!(f, "failed to read {}: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for UsageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, err) => Some(err),
+            Self::Parse(_, err) => Some(err),
+        }
+    }
+}
+
+/// The faded scores at one moment; empty when `usage.enabled` is off or the
+/// store can't be read.
+#[derive(Debug, Default)]
+pub struct Ranking {
+    scores: HashMap<String, f64>,
+}
+
+impl Ranking {
+    pub fn load(config: &UsageConfig) -> Self {
+        if !config.enabled {
+            return Self::default();
+        }
+        let store = {
+            let _guard = STORE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+            read(&config.path)
+        };
+        match store {
+            Ok(store) => {
+                let now = clock::timestamp();
+                let scores = store
+                    .targets
+                    .into_iter()
+                    .map(|(key, entry)| (key, faded(entry, now, config.half_life_days)))
+                    .collect();
+                Self { scores }
+            }
+            Err(err) => {
+                eprintln!("Ignoring usage counts: {}", err);
+                Self::default()
+            }
+        }
+    }
+
+    /// `kind` is "open_file", "open_app" or "open_url".
+    pub fn score(&self, kind: &str, key: &str) -> f64 {
+        self.scores
+            .get(&store_key(kind, key))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// `keys` most used first, the rest by name.
+    pub fn sorted<'a>(
+        &self,
+        kind: &str,
+        keys: impl IntoIterator<Item = &'a String>,
+    ) -> Vec<String> {
+        let mut keys: Vec<(f64, &String)> = keys
+            .into_iter()
+            .map(|key| (self.score(kind, key), key))
+            .collect();
+        keys.sort_by(|(a, a_key), (b, b_key)| {
+            b.partial_cmp(a)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a_key.cmp(b_key))
+        });
+        keys.into_iter().map(|(_, key)| key.clone()).collect()
+    }
+
+    /// Up to `count` of `keys` that have been used, most used first.
+    pub fn top<'a>(
+        &self,
+        kind: &str,
+        keys: impl IntoIterator<Item = &'a String>,
+        count: usize,
+    ) -> Vec<String> {
+        let keys = self.sorted(kind, keys);
+        keys.into_iter()
+            .filter(|key| self.score(kind, key) > 0.0)
+            .take(count)
+            .collect()
+    }
+}
+
+/// Counts a file, app or URL that was opened; other intents are ignored.
+pub fn record(config: &UsageConfig, intent: &Intent) -> Result<(), UsageError> {
+    if !config.enabled {
+        return Ok(());
+    }
+    let key = match intent {
+        Intent::OpenFile { target, .. }
+        | Intent::OpenApp { target, .. }
+        | Intent::OpenUrl { target, .. } => store_key(intent.action().name(), target),
+        _ => return Ok(()),
+    };
+    let _guard = STORE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let path = &config.path;
+    let mut store = read(path)?;
+    let now = clock::timestamp();
+    for entry in store.targets.values_mut() {
+        *entry = Entry {
+            score: faded(*entry, now, config.half_life_days),
+            updated: now,
+        };
+    }
+    store.targets.retain(|_, entry| entry.score >= FORGOTTEN);
+    store
+        .targets
+        .entry(key)
+        .or_insert(Entry {
+            score: 0.0,
+            updated: now,
+        })
+        .score += 1.0;
+    let text = serde_json::to_string_pretty(&store)
+        .map_err(|err| UsageError::Parse(path.to_path_buf(), err))?;
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|err| UsageError::Io(parent.to_path_buf(), err))?;
+    }
+    fs::write(path, text).map_err(|err| UsageError::Io(path.to_path_buf(), err))
+}
+
+fn store_key(kind: &str, key: &str) -> String {
+    format!("{}:{}", kind, key)
+}
+
+/// `entry`'s score at `now`, halved for every `half_life_days` since it was
+/// updated; 0 days turns fading off.
+fn faded(entry: Entry, now: i64, half_life_days: f64) -> f64 {
+    if half_life_days <= 0.0 {
+        return entry.score;
+    }
+    let days = (now - entry.updated).max(0) as f64 / 86_400.0;
+    entry.score * 0.5f64.powf(days / half_life_days)
+}
+
+fn read(path: &Path) -> Result<Store, UsageError> {
+    match fs::read_to_string(path) {
+        Ok(text) => {
+            serde_json::from_str(&text).map_err(|err| UsageError::Parse(path.to_path_buf(), err))
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Store::default()),
+        Err(err) => Err(UsageError::Io(path.to_path_buf(), err)),
+    }
+}
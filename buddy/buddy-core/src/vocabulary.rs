@@ -0,0 +1,133 @@
+//! Corrects misheard command targets to the configured names, so "open résumé
+//! a" becomes "open resume" when `resume` is a file key.
+
+/// Words that start a command naming a file, app or URL.
+const VERBS: &[&str] = &["open", "show", "launch", "start", "run", "close", "quit", "kill"];
+/// Words kept between the verb and the name ("open my resume").
+const ARTICLES: &[&str] = &["the", "my", "a", "an"];
+/// Names with fewer letters than this are only matched exactly.
+const MIN_FUZZY_LEN: usize = 4;
+
+#[derive(Debug, Default)]
+pub struct Vocabulary {
+    /// Each name as spoken ("vs code"), and the letters compared ("vscode").
+    names: Vec<(String, Vec<char>)>,
+    /// The share of a name's letters that may differ; see
+    /// `transcription.command_bias`.
+    bias: f32,
+}
+
+impl Vocabulary {
+    /// Of two names equally close to what was heard, the one earlier in
+    /// `names` wins.
+    pub fn new(names: Vec<String>, bias: f32) -> Self {
+        let mut spoken: Vec<(String, Vec<char>)> = Vec::with_capacity(names.len());
+        for name in names {
+            let name = name.to_lowercase().replace(['_', '-'], " ");
+            let letters = letters(&name);
+            if !letters.is_empty() && !spoken.iter().any(|(seen, _)| *seen == name) {
+                spoken.push((name, letters));
+            }
+        }
+        Self {
+            names: spoken,
+            bias,
+        }
+    }
+
+    pub fn bias(&self) -> f32 {
+        self.bias
+    }
+
+    /// `text` with everything after its command verb replaced by the closest
+    /// name, or `None` when it isn't a command, already says the name, or no
+    /// name is close enough. Spaces, case, punctuation and accents are
+    /// ignored when comparing.
+    pub fn correct(&self, text: &str) -> Option<String> {
+        if self.bias <= 0.0 {
+            return None;
+        }
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let verb: String = letters(words.first()?).into_iter().collect();
+        if !VERBS.contains(&verb.as_str()) {
+            return None;
+        }
+        let start = 1 + words[1..]
+            .iter()
+            .take_while(|word| {
+                ARTICLES.contains(&letters(word).into_iter().collect::<String>().as_str())
+            })
+            .count();
+        let heard: Vec<char> = words[start..].iter().flat_map(|word| letters(word)).collect();
+        if heard.is_empty() {
+            return None;
+        }
+        let (name, _) = self
+            .names
+            .iter()
+            .filter_map(|(name, letters)| {
+                let distance = distance(&heard, letters);
+                let close = if letters.len() < MIN_FUZZY_LEN {
+                    distance == 0
+                } else {
+                    distance as f32 <= self.bias * letters.len() as f32
+                };
+                close.then(|| (name, distance as f32 / letters.len() as f32))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))?;
+        // Accents count here: "résumé" doesn't match a `resume` key.
+        let said: Vec<String> = words[start..]
+            .iter()
+            .map(|word| {
+                word.chars()
+                    .flat_map(char::to_lowercase)
+                    .filter(|c| c.is_alphanumeric())
+                    .collect()
+            })
+            .filter(|word: &String| !word.is_empty())
+            .collect();
+        if said.join(" ") == *name {
+            return None;
+        }
+        Some(format!("{} {}", words[..start].join(" "), name))
+    }
+}
+
+/// The lowercase letters and digits of `text`, with accents dropped.
+fn letters(text: &str) -> Vec<char> {
+    text.chars()
+        .flat_map(char::to_lowercase)
+        .map(unaccented)
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
+
+fn unaccented(c: char) -> char {
+    match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+        'ç' => 'c',
+        'è' | 'é' | 'ê' | 'ë' => 'e',
+        'ì' | 'í' | 'î' | 'ï' => 'i',
+        'ñ' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => 'o',
+        'ù' | 'ú' | 'û' | 'ü' => 'u',
+        'ý' | 'ÿ' => 'y',
+        _ => c,
+    }
+}
+
+/// Levenshtein distance: the insertions, deletions and substitutions that
+/// turn `a` into `b`.
+fn distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, x) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(x != y);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
@@ -0,0 +1,384 @@
+use crate::config::{FeedbackConfig, TtsEngine};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+#[cfg(not(any(windows, target_os = "macos")))]
+use std::process::Child;
+
+#[cfg(any(windows, target_os = "macos"))]
+use tts::Tts;
+
+/// espeak-ng's default speaking rate, in words per minute.
+#[cfg(not(any(windows, target_os = "macos")))]
+const ESPEAK_WPM: f32 = 175.0;
+
+/// Numbers Piper's scratch files so queued utterances never share one.
+static PIPER_CLIPS: AtomicU64 = AtomicU64::new(0);
+
+/// What `Voice::speak` produced.
+pub enum Utterance {
+    /// The engine is speaking on its own; poll `is_speaking` for the end.
+    Started,
+    /// A synthesized WAV for the caller to play.
+    Clip(Vec<u8>),
+}
+
+/// Who reads a translation: the `feedback.voices` entry for its language if
+/// there is one, otherwise whatever voice the engine has for `language`.
+#[derive(Debug, Clone)]
+pub struct Locale {
+    /// A whisper language code ("fr"), or the lowercased name when whisper
+    /// doesn't know it.
+    pub language: String,
+    pub voice: Option<String>,
+}
+
+/// A text-to-speech engine chosen by `feedback.tts_engine`.
+pub enum Voice {
+    /// SAPI on Windows, AVFoundation on macOS. `default` is the configured
+    /// voice, put back after a translation.
+    #[cfg(any(windows, target_os = "macos"))]
+    Native {
+        tts: Tts,
+        default: Option<tts::Voice>,
+    },
+    /// speech-dispatcher or espeak-ng, one process per utterance.
+    #[cfg(not(any(windows, target_os = "macos")))]
+    Process {
+        program: SpeechProgram,
+        voice: Option<String>,
+        rate: f32,
+        volume: f32,
+        child: Option<Child>,
+    },
+    /// A local Piper model; renders WAVs that play through the feedback sink.
+    Piper {
+        program: String,
+        model: PathBuf,
+        rate: f32,
+    },
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeechProgram {
+    /// `spd-say`, which hands text to the speech-dispatcher daemon.
+    SpeechDispatcher,
+    EspeakNg,
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+impl SpeechProgram {
+    fn binary(&self) -> &'static str {
+        match self {
+            Self::SpeechDispatcher => "spd-say",
+            Self::EspeakNg => "espeak-ng",
+        }
+    }
+}
+
+impl Voice {
+    /// Opens the configured engine. `volume` only applies to engines that
+    /// speak on their own; Piper clips go through the sink's volume instead.
+    pub fn new(cfg: &FeedbackConfig) -> Result<Self, String> {
+        let rate = if cfg.tts_rate > 0.0 { cfg.tts_rate } else { 1.0 };
+        let volume = cfg.volume.clamp(0.0, 1.0);
+        match cfg.tts_engine {
+            TtsEngine::System => system_voice(&cfg.tts_voice, rate, volume),
+            TtsEngine::Piper => {
+                let model = cfg
+                    .piper_model
+                    .clone()
+                    .ok_or("tts_engine = \"piper\" needs feedback.piper_model")?;
+                if !model.exists() {
+                    return Err(format!("Piper model {} not found", model.display()));
+                }
+                Ok(Self::Piper {
+                    program: cfg.piper_path.clone(),
+                    model,
+                    rate,
+                })
+            }
+        }
+    }
+
+    /// Starts speaking `text`; `interrupt` cuts off whatever is being said.
+    /// `locale` picks another voice for this utterance only.
+    pub fn speak(
+        &mut self,
+        text: &str,
+        interrupt: bool,
+        locale: Option<&Locale>,
+    ) -> Result<Utterance, String> {
+        match self {
+            #[cfg(any(windows, target_os = "macos"))]
+            Self::Native { tts, default } => {
+                if interrupt {
+                    let _ = tts.stop();
+                }
+                let voice = locale
+                    .and_then(|locale| native_voice(tts, locale))
+                    .or_else(|| default.clone());
+                if let Some(voice) = voice {
+                    let _ = tts.set_voice(&voice);
+                }
+                tts.speak(text, interrupt).map_err(|err| err.to_string())?;
+                Ok(Utterance::Started)
+            }
+            #[cfg(not(any(windows, target_os = "macos")))]
+            Self::Process {
+                program,
+                voice,
+                rate,
+                volume,
+                child,
+            } => {
+                if interrupt {
+                    stop_process(*program, child);
+                }
+                let voice = locale
+                    .and_then(|locale| locale.voice.as_ref())
+                    .or(voice.as_ref());
+                // Without a named voice, espeak-ng takes the language as one.
+                let language = locale
+                    .filter(|locale| locale.voice.is_none())
+                    .map(|locale| locale.language.as_str());
+                let mut command = Command::new(program.binary());
+                match program {
+                    SpeechProgram::SpeechDispatcher => {
+                        // -w keeps spd-say alive until the daemon has finished.
+                        command
+                            .arg("-w")
+                            .arg("-r")
+                            .arg(percent(*rate - 1.0))
+                            .arg("-i")
+                            .arg(percent(*volume * 2.0 - 1.0));
+                        if let Some(language) = language {
+                            command.arg("-l").arg(language);
+                        } else if let Some(voice) = voice {
+                            command.arg("-y").arg(voice);
+                        }
+                        command.arg("--").arg(text);
+                    }
+                    SpeechProgram::EspeakNg => {
+                        command
+                            .arg("-s")
+                            .arg(((ESPEAK_WPM * *rate).round() as u32).to_string())
+                            .arg("-a")
+                            .arg(((*volume * 100.0).round() as u32).to_string());
+                        if let Some(voice) = language.or(voice.map(String::as_str)) {
+                            command.arg("-v").arg(voice);
+                        }
+                        command.arg("--").arg(text);
+                    }
+                }
+                let spawned = command
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()
+                    .map_err(|err| format!("{}: {}", program.binary(), err))?;
+                *child = Some(spawned);
+                Ok(Utterance::Started)
+            }
+            Self::Piper {
+                program,
+                model,
+                rate,
+            } => {
+                let _ = interrupt;
+                // Piper voices are one language each, so only a mapped model helps.
+                let model = locale
+                    .and_then(|locale| locale.voice.as_deref())
+                    .map(Path::new)
+                    .filter(|path| path.exists())
+                    .unwrap_or(model.as_path());
+                piper(program, model, *rate, text).map(Utterance::Clip)
+            }
+        }
+    }
+
+    /// Sets the volume (0.0 to 1.0) of engines that speak on their own: at
+    /// once where the engine allows it, otherwise from the next utterance.
+    pub fn set_volume(&mut self, level: f32) {
+        match self {
+            #[cfg(any(windows, target_os = "macos"))]
+            Self::Native { tts, .. } => {
+                let volume = tts.min_volume() + (tts.max_volume() - tts.min_volume()) * level;
+                let _ = tts.set_volume(volume);
+            }
+            #[cfg(not(any(windows, target_os = "macos")))]
+            Self::Process { volume, .. } => *volume = level,
+            Self::Piper { .. } => {}
+        }
+    }
+
+    /// Stops an utterance the engine is speaking on its own.
+    pub fn stop(&mut self) {
+        match self {
+            #[cfg(any(windows, target_os = "macos"))]
+            Self::Native { tts, .. } => {
+                let _ = tts.stop();
+            }
+            #[cfg(not(any(windows, target_os = "macos")))]
+            Self::Process { program, child, .. } => stop_process(*program, child),
+            Self::Piper { .. } => {}
+        }
+    }
+
+    pub fn is_speaking(&mut self) -> bool {
+        match self {
+            #[cfg(any(windows, target_os = "macos"))]
+            Self::Native { tts, .. } => tts.is_speaking().unwrap_or(false),
+            #[cfg(not(any(windows, target_os = "macos")))]
+            Self::Process { child, .. } => match child {
+                Some(process) => {
+                    let running = matches!(process.try_wait(), Ok(None));
+                    if !running {
+                        *child = None;
+                    }
+                    running
+                }
+                None => false,
+            },
+            Self::Piper { .. } => false,
+        }
+    }
+
+    /// Whether `speak(.., false)` queues behind the current utterance by
+    /// itself; process engines have to wait for the previous one to exit.
+    pub fn queues(&self) -> bool {
+        match self {
+            #[cfg(any(windows, target_os = "macos"))]
+            Self::Native { .. } => true,
+            #[cfg(not(any(windows, target_os = "macos")))]
+            Self::Process { .. } => false,
+            Self::Piper { .. } => true,
+        }
+    }
+}
+
+/// `rate` scales the engine's normal speaking rate; `volume` is 0.0 to 1.0.
+#[cfg(any(windows, target_os = "macos"))]
+fn system_voice(preferred_voice: &str, rate: f32, volume: f32) -> Result<Voice, String> {
+    let mut tts = Tts::default().map_err(|err| err.to_string())?;
+    if !preferred_voice.eq_ignore_ascii_case("default") {
+        if let Ok(voices) = tts.voices() {
+            if let Some(voice) = voices
+                .into_iter()
+                .find(|voice| voice.name().eq_ignore_ascii_case(preferred_voice))
+            {
+                let _ = tts.set_voice(&voice);
+            }
+        }
+    }
+    let rate = (tts.normal_rate() * rate).clamp(tts.min_rate(), tts.max_rate());
+    let _ = tts.set_rate(rate);
+    let volume = tts.min_volume() + (tts.max_volume() - tts.min_volume()) * volume;
+    let _ = tts.set_volume(volume);
+    let default = tts.voice().ok().flatten();
+    Ok(Voice::Native { tts, default })
+}
+
+/// The installed voice named by `locale`, or the first one for its language.
+#[cfg(any(windows, target_os = "macos"))]
+fn native_voice(tts: &Tts, locale: &Locale) -> Option<tts::Voice> {
+    let voices = tts.voices().ok()?;
+    if let Some(name) = &locale.voice {
+        if let Some(voice) = voices.iter().find(|voice| voice.name().eq_ignore_ascii_case(name)) {
+            return Some(voice.clone());
+        }
+    }
+    voices.into_iter().find(|voice| {
+        voice
+            .language()
+            .primary_language()
+            .eq_ignore_ascii_case(&locale.language)
+    })
+}
+
+/// Prefers speech-dispatcher, which follows the desktop's voice settings, and
+/// falls back to calling espeak-ng directly.
+#[cfg(not(any(windows, target_os = "macos")))]
+fn system_voice(preferred_voice: &str, rate: f32, volume: f32) -> Result<Voice, String> {
+    let program = [SpeechProgram::SpeechDispatcher, SpeechProgram::EspeakNg]
+        .into_iter()
+        .find(|program| {
+            Command::new(program.binary())
+                .arg("--version")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success())
+        })
+        .ok_or("neither spd-say (speech-dispatcher) nor espeak-ng is installed")?;
+    Ok(Voice::Process {
+        program,
+        voice: (!preferred_voice.eq_ignore_ascii_case("default"))
+            .then(|| preferred_voice.to_string()),
+        rate,
+        volume,
+        child: None,
+    })
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn stop_process(program: SpeechProgram, child: &mut Option<Child>) {
+    if let Some(mut process) = child.take() {
+        let _ = process.kill();
+        let _ = process.wait();
+        // Killing spd-say doesn't stop the daemon from finishing the sentence.
+        if program == SpeechProgram::SpeechDispatcher {
+            let _ = Command::new("spd-say")
+                .arg("-C")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+        }
+    }
+}
+
+/// spd-say takes rate and volume as -100 to 100.
+#[cfg(not(any(windows, target_os = "macos")))]
+fn percent(value: f32) -> String {
+    ((value * 100.0).round().clamp(-100.0, 100.0) as i32).to_string()
+}
+
+/// Renders `text` with Piper and returns the WAV bytes.
+fn piper(program: &str, model: &Path, rate: f32, text: &str) -> Result<Vec<u8>, String> {
+    let clip = std::env::temp_dir().join(format!(
+        "buddy-piper-{}-{}.wav",
+        std::process::id(),
+        PIPER_CLIPS.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut child = Command::new(program)
+        .arg("--model")
+        .arg(model)
+        .arg("--output_file")
+        .arg(&clip)
+        // Piper stretches time, so a faster rate is a shorter length.
+        .arg("--length_scale")
+        .arg(format!("{:.2}", 1.0 / rate))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| format!("{}: {}", program, err))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(text.as_bytes())
+            .map_err(|err| format!("{}: {}", program, err))?;
+    }
+    let status = child.wait().map_err(|err| format!("{}: {}", program, err))?;
+    if !status.success() {
+        let _ = fs::remove_file(&clip);
+        return Err(format!("{} exited with {}", program, status));
+    }
+    let wav = fs::read(&clip).map_err(|err| format!("{}: {}", clip.display(), err));
+    let _ = fs::remove_file(&clip);
+    wav
+}
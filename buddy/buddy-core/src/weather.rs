@@ -0,0 +1,434 @@
+use crate::{
+    clock::{self, Date},
+    config::{WeatherConfig, WeatherProvider, WeatherUnits},
+    intent::Intent,
+    rules::EXACT_CONFIDENCE,
+};
+use reqwest::Client;
+use serde::Deserialize;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where the forecast is for.
+struct Place {
+    /// Read out with the forecast; `None` when only coordinates are set.
+    name: Option<String>,
+    latitude: f64,
+    longitude: f64,
+}
+
+/// One day of a provider's forecast.
+struct DayForecast {
+    conditions: String,
+    high: f64,
+    low: f64,
+    /// Percent chance of rain or snow, when the provider gives one.
+    rain_chance: Option<f64>,
+    /// Temperature and conditions right now; only filled in for today.
+    now: Option<(f64, String)>,
+}
+
+#[derive(Debug)]
+pub enum WeatherError {
+    NoLocation,
+    MissingApiKey,
+    UnknownPlace(String),
+    /// The day is past the provider's forecast; holds how many days it has.
+    OutOfRange(i64),
+    NoData,
+    Request(reqwest::Error),
+}
+
+impl std::fmt::Display for WeatherError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoLocation => {
+                write!(f, "set weather.location (or latitude and longitude) in the config")
+            }
+            Self::MissingApiKey => write!(f, "weather.api_key is required for this provider"),
+            Self::UnknownPlace(name) => write!(f, "no place called {} was found", name),
+            Self::OutOfRange(days) => write!(f, "the forecast only covers the next {} days", days),
+            Self::NoData => write!(f, "the weather service returned no forecast for that day"),
+            Self::Request(err) => write!(f, "weather request failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for WeatherError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Request(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for WeatherError {
+    fn from(err: reqwest::Error) -> Self {
+        // OpenWeatherMap's URL carries the API key, and errors get printed.
+        Self::Request(err.without_url())
+    }
+}
+
+/// Recognizes "what's the weather tomorrow", "what's the forecast for
+/// Friday", and "will it rain today" so they skip the intent model.
+pub fn recognize(transcript: &str) -> Option<Intent> {
+    let words = clock::words(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let weather = words.iter().any(|word| matches!(*word, "weather" | "forecast"));
+    let rain = matches!(words.as_slice(), ["will" | "is", "it", ..])
+        && words
+            .iter()
+            .any(|word| matches!(*word, "rain" | "raining" | "snow" | "snowing"));
+    if !(weather || rain) {
+        return None;
+    }
+    // "Open the weather app" and "weather in Paris" are left to the model.
+    let question = matches!(
+        words.first(),
+        Some(&("what" | "whats" | "how" | "hows" | "will" | "is" | "tell" | "give" | "check"))
+            | Some(&("weather" | "forecast"))
+    );
+    let elsewhere = words
+        .windows(2)
+        .any(|pair| pair[0] == "in" && !clock::is_day(pair[1]) && pair[1] != "the");
+    if !question || elsewhere || words.contains(&"app") {
+        return None;
+    }
+    let day = words.iter().find(|word| clock::is_day(word)).unwrap_or(&"today");
+    Some(Intent::Weather {
+        target: day.to_string(),
+        confidence: EXACT_CONFIDENCE,
+    })
+}
+
+/// Fetches the forecast for `target` ("today", "tomorrow", or a weekday)
+/// and words it for speech.
+pub async fn forecast(config: &WeatherConfig, target: &str) -> Result<String, WeatherError> {
+    let today = clock::now().0;
+    let day = clock::resolve_day(target, today).ok_or(WeatherError::NoData)?;
+    let days = match config.provider {
+        WeatherProvider::OpenMeteo => 7,
+        WeatherProvider::OpenWeatherMap => 5,
+    };
+    if !(0..days).contains(&(day.days() - today.days())) {
+        return Err(WeatherError::OutOfRange(days));
+    }
+    let client = Client::builder().timeout(TIMEOUT).build()?;
+    let place = locate(&client, config).await?;
+    let forecast = match config.provider {
+        WeatherProvider::OpenMeteo => open_meteo(&client, &place, config.units, day).await?,
+        WeatherProvider::OpenWeatherMap => {
+            let key = config
+                .api_key
+                .as_deref()
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .ok_or(WeatherError::MissingApiKey)?;
+            open_weather_map(&client, &place, config.units, key, day, day == today).await?
+        }
+    };
+    Ok(spoken(&forecast, place.name.as_deref(), day, today))
+}
+
+/// "Tomorrow in Berlin: light rain, with a high of 14 and a low of 7
+/// degrees, and an 80% chance of rain."
+fn spoken(forecast: &DayForecast, place: Option<&str>, day: Date, today: Date) -> String {
+    let mut text = String::new();
+    if let Some((temperature, conditions)) = &forecast.now {
+        text.push_str(&format!(
+            "Right now it's {} degrees and {}. ",
+            temperature.round(),
+            conditions
+        ));
+    }
+    let when = clock::spoken_day(day, today);
+    let mut chars = when.chars();
+    let when: String = chars.next().into_iter().flat_map(char::to_uppercase).chain(chars).collect();
+    text.push_str(&when);
+    if let Some(place) = place {
+        text.push_str(&format!(" in {}", place));
+    }
+    text.push_str(&format!(
+        ": {}, with a high of {} and a low of {} degrees",
+        forecast.conditions,
+        forecast.high.round(),
+        forecast.low.round()
+    ));
+    match forecast.rain_chance.map(f64::round) {
+        Some(chance) if chance >= 20.0 => {
+            text.push_str(&format!(", and a {}% chance of rain", chance))
+        }
+        _ => {}
+    }
+    text.push('.');
+    text
+}
+
+/// The configured coordinates, or `weather.location` looked up with
+/// Open-Meteo's geocoding, which needs no key whatever the provider.
+async fn locate(client: &Client, config: &WeatherConfig) -> Result<Place, WeatherError> {
+    if let (Some(latitude), Some(longitude)) = (config.latitude, config.longitude) {
+        return Ok(Place {
+            name: config.location.clone(),
+            latitude,
+            longitude,
+        });
+    }
+    let name = config
+        .location
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .ok_or(WeatherError::NoLocation)?;
+    let reply: Geocoding = client
+        .get("https://geocoding-api.open-meteo.com/v1/search")
+        .query(&[("name", name), ("count", "1")])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let found = reply
+        .results
+        .into_iter()
+        .next()
+        .ok_or_else(|| WeatherError::UnknownPlace(name.to_string()))?;
+    Ok(Place {
+        name: Some(found.name),
+        latitude: found.latitude,
+        longitude: found.longitude,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct Geocoding {
+    #[serde(default)]
+    results: Vec<GeocodedPlace>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeocodedPlace {
+    name: String,
+    latitude: f64,
+    longitude: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenMeteoReply {
+    current: Option<OpenMeteoCurrent>,
+    daily: OpenMeteoDaily,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenMeteoCurrent {
+    temperature_2m: f64,
+    weather_code: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenMeteoDaily {
+    time: Vec<String>,
+    weather_code: Vec<Option<u32>>,
+    temperature_2m_max: Vec<Option<f64>>,
+    temperature_2m_min: Vec<Option<f64>>,
+    #[serde(default)]
+    precipitation_probability_max: Vec<Option<f64>>,
+}
+
+async fn open_meteo(
+    client: &Client,
+    place: &Place,
+    units: WeatherUnits,
+    day: Date,
+) -> Result<DayForecast, WeatherError> {
+    let temperature_unit = match units {
+        WeatherUnits::Metric => "celsius",
+        WeatherUnits::Imperial => "fahrenheit",
+    };
+    let reply: OpenMeteoReply = client
+        .get("https://api.open-meteo.com/v1/forecast")
+        .query(&[
+            ("latitude", place.latitude.to_string().as_str()),
+            ("longitude", place.longitude.to_string().as_str()),
+            (
+                "daily",
+                "weather_code,temperature_2m_max,temperature_2m_min,precipitation_probability_max",
+            ),
+            ("current", "temperature_2m,weather_code"),
+            ("temperature_unit", temperature_unit),
+            // Days are the place's own, matching how the user thinks of them.
+            ("timezone", "auto"),
+            ("forecast_days", "7"),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let date = format!("{:04}-{:02}-{:02}", day.year, day.month, day.day);
+    let daily = &reply.daily;
+    let index = daily
+        .time
+        .iter()
+        .position(|time| *time == date)
+        .ok_or(WeatherError::NoData)?;
+    let value = |values: &[Option<f64>]| values.get(index).copied().flatten();
+    let code = daily
+        .weather_code
+        .get(index)
+        .copied()
+        .flatten()
+        .ok_or(WeatherError::NoData)?;
+    Ok(DayForecast {
+        conditions: conditions(code).to_string(),
+        high: value(&daily.temperature_2m_max).ok_or(WeatherError::NoData)?,
+        low: value(&daily.temperature_2m_min).ok_or(WeatherError::NoData)?,
+        rain_chance: value(&daily.precipitation_probability_max),
+        now: reply
+            .current
+            .filter(|_| index == 0)
+            .map(|now| (now.temperature_2m, conditions(now.weather_code).to_string())),
+    })
+}
+
+/// WMO weather interpretation codes, as Open-Meteo reports them.
+fn conditions(code: u32) -> &'static str {
+    match code {
+        0 => "clear skies",
+        1 => "mainly clear",
+        2 => "partly cloudy",
+        3 => "overcast",
+        45 | 48 => "fog",
+        51 | 53 | 55 => "drizzle",
+        56 | 57 => "freezing drizzle",
+        61 => "light rain",
+        63 => "rain",
+        65 => "heavy rain",
+        66 | 67 => "freezing rain",
+        71 => "light snow",
+        73 => "snow",
+        75 => "heavy snow",
+        77 => "snow grains",
+        80 => "light showers",
+        81 => "showers",
+        82 => "heavy showers",
+        85 | 86 => "snow showers",
+        95 => "thunderstorms",
+        96 | 99 => "thunderstorms with hail",
+        _ => "mixed weather",
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OwmForecast {
+    list: Vec<OwmEntry>,
+    city: OwmCity,
+}
+
+#[derive(Debug, Deserialize)]
+struct OwmCity {
+    /// Seconds east of UTC.
+    timezone: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct OwmEntry {
+    dt: i64,
+    main: OwmMain,
+    #[serde(default)]
+    weather: Vec<OwmCondition>,
+    /// Probability of precipitation, 0 to 1.
+    #[serde(default)]
+    pop: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct OwmCurrent {
+    main: OwmMain,
+    #[serde(default)]
+    weather: Vec<OwmCondition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OwmMain {
+    temp: f64,
+    temp_min: f64,
+    temp_max: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct OwmCondition {
+    description: String,
+}
+
+/// OpenWeatherMap's free 5 day forecast, which comes in 3 hour steps that
+/// are folded into one day here.
+async fn open_weather_map(
+    client: &Client,
+    place: &Place,
+    units: WeatherUnits,
+    key: &str,
+    day: Date,
+    today: bool,
+) -> Result<DayForecast, WeatherError> {
+    let query = [
+        ("lat", place.latitude.to_string()),
+        ("lon", place.longitude.to_string()),
+        ("appid", key.to_string()),
+        (
+            "units",
+            match units {
+                WeatherUnits::Metric => "metric",
+                WeatherUnits::Imperial => "imperial",
+            }
+            .to_string(),
+        ),
+    ];
+    let reply: OwmForecast = client
+        .get("https://api.openweathermap.org/data/2.5/forecast")
+        .query(&query)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let offset = reply.city.timezone;
+    let entries: Vec<&OwmEntry> = reply
+        .list
+        .iter()
+        .filter(|entry| Date::from_days((entry.dt + offset).div_euclid(86_400)) == day)
+        .collect();
+    // The step nearest midday describes the day best.
+    let midday = entries
+        .iter()
+        .min_by_key(|entry| ((entry.dt + offset).rem_euclid(86_400) - 43_200).abs())
+        .ok_or(WeatherError::NoData)?;
+    let now = if today {
+        let current: OwmCurrent = client
+            .get("https://api.openweathermap.org/data/2.5/weather")
+            .query(&query)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Some((current.main.temp, description(&current.weather)))
+    } else {
+        None
+    };
+    Ok(DayForecast {
+        conditions: description(&midday.weather),
+        high: entries.iter().map(|entry| entry.main.temp_max).fold(f64::MIN, f64::max),
+        low: entries.iter().map(|entry| entry.main.temp_min).fold(f64::MAX, f64::min),
+        rain_chance: entries.iter().map(|entry| entry.pop * 100.0).reduce(f64::max),
+        now,
+    })
+}
+
+fn description(conditions: &[OwmCondition]) -> String {
+    conditions
+        .first()
+        .map_or_else(|| "mixed weather".to_string(), |condition| condition.description.clone())
+}
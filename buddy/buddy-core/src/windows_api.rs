@@ -0,0 +1,883 @@
+use crate::hotkey::KeyCombo;
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+#[cfg(target_os = "windows")]
+use std::process::Command;
+
+#[derive(Debug)]
+pub enum WindowsActionError {
+    Io(std::io::Error),
+    #[cfg(target_os = "windows")]
+    Windows(windows::core::Error),
+    #[cfg_attr(windows, allow(dead_code))]
+    Unsupported(&'static str),
+    /// Windows refused, such as radio access turned off under Settings >
+    /// Privacy.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    AccessDenied(&'static str),
+}
+
+impl std::fmt::Display for WindowsActionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "io error: {}", err),
+            #[cfg(target_os = "windows")]
+            Self::Windows(err) => write!(f, "win32 error: {}", err),
+            Self::Unsupported(msg) => write!(f, "unsupported: {}", msg),
+            Self::AccessDenied(msg) => write!(f, "access denied: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for WindowsActionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            #[cfg(target_os = "windows")]
+            Self::Windows(err) => Some(err),
+            Self::Unsupported(_) | Self::AccessDenied(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SystemAction {
+    VolumeMute,
+    /// Changes the volume by this many percentage points; raising it also
+    /// unmutes.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    VolumeAdjust(i8),
+    #[cfg_attr(not(windows), allow(dead_code))]
+    VolumeSet(u8),
+    /// Reads the volume; see `volume_level`.
+    VolumeQuery,
+    MicMute,
+    MicUnmute,
+    /// Reads whether the microphone is muted; see `mic_muted`.
+    MicQuery,
+    /// Puts the monitors to sleep until the mouse or keyboard is used.
+    ScreenOff,
+    #[cfg_attr(not(windows), allow(dead_code))]
+    DisplayTopology(Topology),
+    /// Turns the main display to this many degrees clockwise (0, 90, 180
+    /// or 270), or a quarter turn on from where it is.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    RotateScreen(Option<u16>),
+    #[cfg_attr(not(windows), allow(dead_code))]
+    Radio(RadioKind, bool),
+    /// Reads whether a radio is on; see `radio_on`.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    RadioQuery(RadioKind),
+    /// Turns every radio off (true) or back on (false).
+    #[cfg_attr(not(windows), allow(dead_code))]
+    AirplaneMode(bool),
+    #[cfg_attr(not(windows), allow(dead_code))]
+    PowerPlan(PowerPlan),
+    /// Reads the battery; see `battery_status`.
+    BatteryQuery,
+    Sleep,
+    Shutdown,
+    Restart,
+    Lock,
+}
+
+/// How the desktop is spread over the connected displays, as Win+P offers.
+#[derive(Debug, Clone, Copy)]
+pub enum Topology {
+    Extend,
+    Duplicate,
+    /// The PC's own screen only.
+    Internal,
+    /// The second screen only.
+    External,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RadioKind {
+    WiFi,
+    Bluetooth,
+}
+
+/// The power plans Windows ships with.
+#[derive(Debug, Clone, Copy)]
+pub enum PowerPlan {
+    Balanced,
+    HighPerformance,
+    PowerSaver,
+}
+
+impl std::fmt::Display for PowerPlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Balanced => write!(f, "balanced"),
+            Self::HighPerformance => write!(f, "high performance"),
+            Self::PowerSaver => write!(f, "power saver"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BatteryStatus {
+    /// `None` while Windows is still working it out.
+    pub percent: Option<u8>,
+    pub plugged_in: bool,
+    /// Time left on battery, known only when unplugged.
+    pub remaining: Option<Duration>,
+}
+
+impl std::fmt::Display for RadioKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WiFi => write!(f, "Wi-Fi"),
+            Self::Bluetooth => write!(f, "Bluetooth"),
+        }
+    }
+}
+
+/// Set while the microphone is muted by a "mute my mic" command.
+static MIC_MUTED_BY_BUDDY: AtomicBool = AtomicBool::new(false);
+
+/// Opens `path` with its default application. Goes through the shell
+/// directly, since `cmd` would run whatever follows a `&` in the name.
+#[cfg(target_os = "windows")]
+pub fn open_path(path: &Path) -> Result<(), WindowsActionError> {
+    shell_open(&path.to_string_lossy())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn open_path(_path: &Path) -> Result<(), WindowsActionError> {
+    Err(WindowsActionError::Unsupported(
+        "open path is only supported on Windows",
+    ))
+}
+
+#[cfg(target_os = "windows")]
+pub fn launch(app: &str) -> Result<(), WindowsActionError> {
+    let mut cmd = Command::new("cmd");
+    cmd.args(["/C", "start", "", app]);
+    run_detached(&mut cmd)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn launch(_app: &str) -> Result<(), WindowsActionError> {
+    Err(WindowsActionError::Unsupported("launch requires Windows"))
+}
+
+/// Opens `url` in the default browser. Goes through the shell directly,
+/// since `cmd` would split the URL at `&`.
+#[cfg(target_os = "windows")]
+pub fn open_url(url: &str) -> Result<(), WindowsActionError> {
+    shell_open(url)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn open_url(_url: &str) -> Result<(), WindowsActionError> {
+    Err(WindowsActionError::Unsupported("opening URLs requires Windows"))
+}
+
+/// Opens a file or URL the way Explorer would.
+#[cfg(target_os = "windows")]
+fn shell_open(target: &str) -> Result<(), WindowsActionError> {
+    use windows::{
+        core::{HSTRING, PCWSTR},
+        Win32::{Foundation::HWND, UI::Shell::ShellExecuteW, UI::WindowsAndMessaging::SW_SHOWNORMAL},
+    };
+
+    let instance = unsafe {
+        ShellExecuteW(
+            HWND::default(),
+            &HSTRING::from("open"),
+            &HSTRING::from(target),
+            PCWSTR::null(),
+            PCWSTR::null(),
+            SW_SHOWNORMAL,
+        )
+    };
+    // Values up to 32 are error codes.
+    if instance.0 as isize > 32 {
+        Ok(())
+    } else {
+        Err(last_os_error())
+    }
+}
+
+/// Starts `program` with separate arguments, e.g. an editor opened at a line.
+/// Started directly, not through `cmd`, so a file name can't add commands.
+#[cfg(target_os = "windows")]
+pub fn launch_with_args(program: &str, args: &[String]) -> Result<(), WindowsActionError> {
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    run_detached(&mut cmd)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn launch_with_args(_program: &str, _args: &[String]) -> Result<(), WindowsActionError> {
+    Err(WindowsActionError::Unsupported("launch requires Windows"))
+}
+
+/// Starts `program` directly rather than through a shell, so arguments
+/// reach it exactly as given, whatever characters they contain.
+pub fn run_command(program: &str, args: &[String]) -> Result<(), WindowsActionError> {
+    std::process::Command::new(program)
+        .args(args)
+        .spawn()
+        .map(|_| ())
+        .map_err(WindowsActionError::Io)
+}
+
+/// Types `text` into the focused window, as if it came from the keyboard.
+/// Line breaks and tabs are sent as Enter and Tab; everything else as
+/// Unicode, so accents and emoji arrive intact. A non-zero `delay` is waited
+/// between characters for applications that drop fast input.
+#[cfg(target_os = "windows")]
+pub fn type_text(text: &str, delay: std::time::Duration) -> Result<(), WindowsActionError> {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
+        KEYEVENTF_UNICODE, VIRTUAL_KEY, VK_RETURN, VK_TAB,
+    };
+
+    let key = |vk: VIRTUAL_KEY, scan: u16, flags: KEYBD_EVENT_FLAGS| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                wScan: scan,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    };
+    // One press and release per character; characters outside the Basic
+    // Multilingual Plane are two UTF-16 units and are sent together.
+    let characters: Vec<Vec<INPUT>> = text
+        .chars()
+        .filter(|c| *c != '\r')
+        .map(|c| match c {
+            '\n' | '\t' => {
+                let vk = if c == '\n' { VK_RETURN } else { VK_TAB };
+                vec![
+                    key(vk, 0, KEYBD_EVENT_FLAGS::default()),
+                    key(vk, 0, KEYEVENTF_KEYUP),
+                ]
+            }
+            _ => c
+                .encode_utf16(&mut [0; 2])
+                .iter()
+                .flat_map(|unit| {
+                    [KEYEVENTF_UNICODE, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP]
+                        .map(|flags| key(VIRTUAL_KEY(0), *unit, flags))
+                })
+                .collect(),
+        })
+        .collect();
+    let send = |inputs: &[INPUT]| {
+        let sent = unsafe { SendInput(inputs, std::mem::size_of::<INPUT>() as i32) };
+        if sent as usize == inputs.len() {
+            Ok(())
+        } else {
+            Err(last_os_error())
+        }
+    };
+    if delay.is_zero() {
+        let inputs = characters.concat();
+        return if inputs.is_empty() { Ok(()) } else { send(inputs.as_slice()) };
+    }
+    for inputs in &characters {
+        send(inputs.as_slice())?;
+        std::thread::sleep(delay);
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn type_text(_text: &str, _delay: std::time::Duration) -> Result<(), WindowsActionError> {
+    Err(WindowsActionError::Unsupported("typing requires Windows"))
+}
+
+/// Presses each combo in turn in the focused window: modifiers down, the
+/// key down and up, then the modifiers up again.
+#[cfg(target_os = "windows")]
+pub fn send_keys(combos: &[KeyCombo]) -> Result<(), WindowsActionError> {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
+        VIRTUAL_KEY, VK_CONTROL, VK_LWIN, VK_MENU, VK_SHIFT,
+    };
+
+    let key = |vk: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                wScan: 0,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    };
+    let mut inputs = Vec::new();
+    for combo in combos {
+        let code = crate::hotkey::virtual_key(&combo.key).ok_or(WindowsActionError::Unsupported(
+            "that key has no virtual-key code",
+        ))?;
+        let modifiers: Vec<VIRTUAL_KEY> = [
+            (combo.ctrl, VK_CONTROL),
+            (combo.alt, VK_MENU),
+            (combo.shift, VK_SHIFT),
+            (combo.win, VK_LWIN),
+        ]
+        .into_iter()
+        .filter_map(|(held, vk)| held.then_some(vk))
+        .collect();
+        inputs.extend(modifiers.iter().map(|vk| key(*vk, KEYBD_EVENT_FLAGS::default())));
+        inputs.push(key(VIRTUAL_KEY(code), KEYBD_EVENT_FLAGS::default()));
+        inputs.push(key(VIRTUAL_KEY(code), KEYEVENTF_KEYUP));
+        inputs.extend(modifiers.iter().rev().map(|vk| key(*vk, KEYEVENTF_KEYUP)));
+    }
+    let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+    if sent as usize == inputs.len() {
+        Ok(())
+    } else {
+        Err(last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn send_keys(_combos: &[KeyCombo]) -> Result<(), WindowsActionError> {
+    Err(WindowsActionError::Unsupported("pressing keys requires Windows"))
+}
+
+#[cfg(target_os = "windows")]
+pub fn execute_system(action: SystemAction) -> Result<(), WindowsActionError> {
+    match action {
+        SystemAction::Sleep => suspend_system(),
+        SystemAction::Shutdown => {
+            let mut cmd = Command::new("shutdown");
+            cmd.args(["/s", "/t", "0"]);
+            run_detached(&mut cmd)
+        }
+        SystemAction::Restart => {
+            let mut cmd = Command::new("shutdown");
+            cmd.args(["/r", "/t", "0"]);
+            run_detached(&mut cmd)
+        }
+        SystemAction::Lock => lock_workstation(),
+        SystemAction::VolumeMute => send_volume_key(0xAD),
+        SystemAction::VolumeAdjust(change) => adjust_master_volume(change),
+        SystemAction::VolumeSet(level) => set_master_volume(level),
+        SystemAction::VolumeQuery => volume_level().map(drop),
+        SystemAction::MicMute => {
+            set_mic_mute(true)?;
+            MIC_MUTED_BY_BUDDY.store(true, Ordering::Relaxed);
+            Ok(())
+        }
+        SystemAction::MicUnmute => {
+            set_mic_mute(false)?;
+            MIC_MUTED_BY_BUDDY.store(false, Ordering::Relaxed);
+            Ok(())
+        }
+        SystemAction::MicQuery => mic_muted().map(drop),
+        SystemAction::ScreenOff => display::screen_off(),
+        SystemAction::DisplayTopology(topology) => display::set_topology(topology),
+        SystemAction::RotateScreen(degrees) => display::rotate(degrees),
+        SystemAction::Radio(kind, on) => radios::set(Some(kind), on),
+        SystemAction::RadioQuery(kind) => radio_on(kind).map(drop),
+        SystemAction::AirplaneMode(on) => radios::set(None, !on),
+        SystemAction::PowerPlan(plan) => set_power_plan(plan),
+        SystemAction::BatteryQuery => battery_status().map(drop),
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn execute_system(_action: SystemAction) -> Result<(), WindowsActionError> {
+    Err(WindowsActionError::Unsupported(
+        "system controls available only on Windows",
+    ))
+}
+
+/// The default output device's volume (0-100), and whether it is muted.
+#[cfg(target_os = "windows")]
+pub fn volume_level() -> Result<(u8, bool), WindowsActionError> {
+    use windows::Win32::Media::Audio::eRender;
+
+    unsafe {
+        let _guard = ComGuard::new()?;
+        let endpoint = endpoint_volume(eRender)?;
+        let level = endpoint
+            .GetMasterVolumeLevelScalar()
+            .map_err(WindowsActionError::Windows)?;
+        let muted = endpoint.GetMute().map_err(WindowsActionError::Windows)?;
+        Ok(((level * 100.0).round() as u8, muted.as_bool()))
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn volume_level() -> Result<(u8, bool), WindowsActionError> {
+    Err(WindowsActionError::Unsupported(
+        "reading the volume requires Windows",
+    ))
+}
+
+/// Whether the default recording device is muted.
+#[cfg(target_os = "windows")]
+pub fn mic_muted() -> Result<bool, WindowsActionError> {
+    use windows::Win32::Media::Audio::eCapture;
+
+    unsafe {
+        let _guard = ComGuard::new()?;
+        let muted = endpoint_volume(eCapture)?
+            .GetMute()
+            .map_err(WindowsActionError::Windows)?;
+        Ok(muted.as_bool())
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn mic_muted() -> Result<bool, WindowsActionError> {
+    Err(WindowsActionError::Unsupported(
+        "reading the microphone requires Windows",
+    ))
+}
+
+/// The battery's charge, or `None` on a PC without one.
+#[cfg(target_os = "windows")]
+pub fn battery_status() -> Result<Option<BatteryStatus>, WindowsActionError> {
+    use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    /// `BatteryFlag` bit for "no system battery".
+    const NO_BATTERY: u8 = 128;
+    /// `BatteryLifePercent` and `ACLineStatus` when unknown.
+    const UNKNOWN: u8 = 255;
+
+    let mut status = SYSTEM_POWER_STATUS::default();
+    unsafe { GetSystemPowerStatus(&mut status) }.map_err(WindowsActionError::Windows)?;
+    if status.BatteryFlag != UNKNOWN && status.BatteryFlag & NO_BATTERY != 0 {
+        return Ok(None);
+    }
+    Ok(Some(BatteryStatus {
+        percent: (status.BatteryLifePercent <= 100).then_some(status.BatteryLifePercent),
+        plugged_in: status.ACLineStatus == 1,
+        remaining: (status.BatteryLifeTime != u32::MAX)
+            .then(|| Duration::from_secs(status.BatteryLifeTime.into())),
+    }))
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn battery_status() -> Result<Option<BatteryStatus>, WindowsActionError> {
+    Err(WindowsActionError::Unsupported(
+        "reading the battery requires Windows",
+    ))
+}
+
+/// Empties the Recycle Bin of every drive without asking, and returns how
+/// many items it held.
+#[cfg(target_os = "windows")]
+pub fn empty_recycle_bin() -> Result<u64, WindowsActionError> {
+    use windows::{
+        core::PCWSTR,
+        Win32::UI::Shell::{
+            SHEmptyRecycleBinW, SHQueryRecycleBinW, SHERB_NOCONFIRMATION, SHERB_NOPROGRESSUI,
+            SHERB_NOSOUND, SHQUERYRBINFO,
+        },
+    };
+
+    let mut info = SHQUERYRBINFO {
+        cbSize: std::mem::size_of::<SHQUERYRBINFO>() as u32,
+        ..Default::default()
+    };
+    unsafe {
+        SHQueryRecycleBinW(PCWSTR::null(), &mut info).map_err(WindowsActionError::Windows)?;
+        // Emptying an empty bin fails, so it isn't tried.
+        if info.i64NumItems == 0 {
+            return Ok(0);
+        }
+        SHEmptyRecycleBinW(
+            None,
+            PCWSTR::null(),
+            SHERB_NOCONFIRMATION | SHERB_NOPROGRESSUI | SHERB_NOSOUND,
+        )
+        .map_err(WindowsActionError::Windows)?;
+    }
+    Ok(info.i64NumItems as u64)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn empty_recycle_bin() -> Result<u64, WindowsActionError> {
+    Err(WindowsActionError::Unsupported(
+        "the recycle bin is only on Windows",
+    ))
+}
+
+/// The user's desktop folder, wherever OneDrive or a policy has moved it.
+#[cfg(target_os = "windows")]
+pub fn desktop_dir() -> Result<PathBuf, WindowsActionError> {
+    use windows::Win32::{
+        System::Com::CoTaskMemFree,
+        UI::Shell::{FOLDERID_Desktop, SHGetKnownFolderPath, KF_FLAG_DEFAULT},
+    };
+
+    unsafe {
+        let path = SHGetKnownFolderPath(&FOLDERID_Desktop, KF_FLAG_DEFAULT, None)
+            .map_err(WindowsActionError::Windows)?;
+        let text = path.to_string();
+        CoTaskMemFree(Some(path.0 as *const _));
+        text.map(PathBuf::from)
+            .map_err(|_| WindowsActionError::Unsupported("the desktop path isn't valid text"))
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn desktop_dir() -> Result<PathBuf, WindowsActionError> {
+    std::env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join("Desktop"))
+        .ok_or(WindowsActionError::Unsupported("HOME is not set"))
+}
+
+/// Whether Buddy muted the microphone and it is still muted. Unmuting it
+/// in Windows counts too, so recording isn't refused after that.
+pub fn mic_muted_by_buddy() -> bool {
+    if !MIC_MUTED_BY_BUDDY.load(Ordering::Relaxed) {
+        return false;
+    }
+    if let Ok(false) = mic_muted() {
+        MIC_MUTED_BY_BUDDY.store(false, Ordering::Relaxed);
+        return false;
+    }
+    true
+}
+
+#[cfg(target_os = "windows")]
+fn run_detached(cmd: &mut Command) -> Result<(), WindowsActionError> {
+    cmd.spawn().map(|_| ()).map_err(WindowsActionError::Io)
+}
+
+#[cfg(target_os = "windows")]
+fn send_volume_key(vk_code: u8) -> Result<(), WindowsActionError> {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        keybd_event, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
+    };
+
+    unsafe {
+        keybd_event(vk_code, 0, KEYBD_EVENT_FLAGS(0), 0);
+        keybd_event(vk_code, 0, KEYEVENTF_KEYUP, 0);
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn set_master_volume(level: u8) -> Result<(), WindowsActionError> {
+    use windows::Win32::Media::Audio::eRender;
+
+    unsafe {
+        let _guard = ComGuard::new()?;
+        let scalar = (level.min(100) as f32) / 100.0;
+        endpoint_volume(eRender)?
+            .SetMasterVolumeLevelScalar(scalar, std::ptr::null())
+            .map_err(WindowsActionError::Windows)?;
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn adjust_master_volume(change: i8) -> Result<(), WindowsActionError> {
+    use windows::Win32::{Foundation::BOOL, Media::Audio::eRender};
+
+    unsafe {
+        let _guard = ComGuard::new()?;
+        let endpoint = endpoint_volume(eRender)?;
+        let level = endpoint
+            .GetMasterVolumeLevelScalar()
+            .map_err(WindowsActionError::Windows)?;
+        let scalar = (level + change as f32 / 100.0).clamp(0.0, 1.0);
+        endpoint
+            .SetMasterVolumeLevelScalar(scalar, std::ptr::null())
+            .map_err(WindowsActionError::Windows)?;
+        if change > 0 {
+            endpoint
+                .SetMute(BOOL::from(false), std::ptr::null())
+                .map_err(WindowsActionError::Windows)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn set_mic_mute(muted: bool) -> Result<(), WindowsActionError> {
+    use windows::Win32::{Foundation::BOOL, Media::Audio::eCapture};
+
+    unsafe {
+        let _guard = ComGuard::new()?;
+        endpoint_volume(eCapture)?
+            .SetMute(BOOL::from(muted), std::ptr::null())
+            .map_err(WindowsActionError::Windows)
+    }
+}
+
+/// The volume control of the default output (`eRender`) or recording
+/// (`eCapture`) device. COM must be initialized.
+#[cfg(target_os = "windows")]
+unsafe fn endpoint_volume(
+    flow: windows::Win32::Media::Audio::EDataFlow,
+) -> Result<windows::Win32::Media::Audio::Endpoints::IAudioEndpointVolume, WindowsActionError> {
+    use windows::Win32::Media::Audio::Endpoints::IAudioEndpointVolume;
+    use windows::Win32::Media::Audio::{eConsole, IMMDeviceEnumerator, MMDeviceEnumerator};
+    use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_ALL};
+
+    let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+        .map_err(WindowsActionError::Windows)?;
+    let device = enumerator
+        .GetDefaultAudioEndpoint(flow, eConsole)
+        .map_err(WindowsActionError::Windows)?;
+    device
+        .Activate::<IAudioEndpointVolume>(CLSCTX_ALL, None)
+        .map_err(WindowsActionError::Windows)
+}
+
+#[cfg(target_os = "windows")]
+struct ComGuard;
+
+#[cfg(target_os = "windows")]
+impl ComGuard {
+    fn new() -> Result<Self, WindowsActionError> {
+        unsafe {
+            windows::Win32::System::Com::CoInitializeEx(
+                None,
+                windows::Win32::System::Com::COINIT_MULTITHREADED,
+            )
+            .ok()
+            .map_err(WindowsActionError::Windows)?;
+        }
+        Ok(Self)
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for ComGuard {
+    fn drop(&mut self) {
+        unsafe {
+            windows::Win32::System::Com::CoUninitialize();
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn suspend_system() -> Result<(), WindowsActionError> {
+    use windows::Win32::Foundation::BOOLEAN;
+    use windows::Win32::System::Power::SetSuspendState;
+
+    unsafe {
+        if SetSuspendState(BOOLEAN(0), BOOLEAN(0), BOOLEAN(0)).as_bool() {
+            Ok(())
+        } else {
+            Err(last_os_error())
+        }
+    }
+}
+
+/// Switches plans with `powercfg`, by the aliases Windows gives its own.
+#[cfg(target_os = "windows")]
+fn set_power_plan(plan: PowerPlan) -> Result<(), WindowsActionError> {
+    let scheme = match plan {
+        PowerPlan::Balanced => "SCHEME_BALANCED",
+        PowerPlan::HighPerformance => "SCHEME_MIN",
+        PowerPlan::PowerSaver => "SCHEME_MAX",
+    };
+    let status = Command::new("powercfg")
+        .args(["/setactive", scheme])
+        .status()
+        .map_err(WindowsActionError::Io)?;
+    if status.success() {
+        Ok(())
+    } else {
+        // Modern Standby PCs hide every plan but balanced.
+        Err(WindowsActionError::Unsupported(
+            "that power plan isn't available on this PC",
+        ))
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn lock_workstation() -> Result<(), WindowsActionError> {
+    use windows::Win32::System::Shutdown::LockWorkStation;
+
+    unsafe { LockWorkStation().map_err(WindowsActionError::Windows) }
+}
+
+/// Whether the PC's `kind` radio is on, or `None` when it has none.
+#[cfg(target_os = "windows")]
+pub fn radio_on(kind: RadioKind) -> Result<Option<bool>, WindowsActionError> {
+    use windows::Devices::Radios::RadioState;
+
+    let radios = radios::find(Some(kind))?;
+    if radios.is_empty() {
+        return Ok(None);
+    }
+    let mut on = false;
+    for radio in radios {
+        on |= radio.State().map_err(WindowsActionError::Windows)? == RadioState::On;
+    }
+    Ok(Some(on))
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn radio_on(_kind: RadioKind) -> Result<Option<bool>, WindowsActionError> {
+    Err(WindowsActionError::Unsupported(
+        "reading the radios requires Windows",
+    ))
+}
+
+#[cfg(target_os = "windows")]
+mod radios {
+    use super::{RadioKind, WindowsActionError};
+    use windows::Devices::Radios::{
+        Radio, RadioAccessStatus, RadioKind as WinRadioKind, RadioState,
+    };
+
+    /// The radios of `kind`, or all of them for `None`.
+    pub fn find(kind: Option<RadioKind>) -> Result<Vec<Radio>, WindowsActionError> {
+        let access = Radio::RequestAccessAsync()
+            .and_then(|request| request.get())
+            .map_err(WindowsActionError::Windows)?;
+        check(access)?;
+        let radios = Radio::GetRadiosAsync()
+            .and_then(|request| request.get())
+            .map_err(WindowsActionError::Windows)?;
+        let wanted = kind.map(|kind| match kind {
+            RadioKind::WiFi => WinRadioKind::WiFi,
+            RadioKind::Bluetooth => WinRadioKind::Bluetooth,
+        });
+        Ok(radios
+            .into_iter()
+            .filter(|radio| match wanted {
+                Some(wanted) => radio.Kind().is_ok_and(|kind| kind == wanted),
+                None => true,
+            })
+            .collect())
+    }
+
+    pub fn set(kind: Option<RadioKind>, on: bool) -> Result<(), WindowsActionError> {
+        let radios = find(kind)?;
+        if radios.is_empty() {
+            return Err(WindowsActionError::Unsupported("this PC has no such radio"));
+        }
+        let state = if on { RadioState::On } else { RadioState::Off };
+        for radio in radios {
+            let access = radio
+                .SetStateAsync(state)
+                .and_then(|request| request.get())
+                .map_err(WindowsActionError::Windows)?;
+            check(access)?;
+        }
+        Ok(())
+    }
+
+    fn check(access: RadioAccessStatus) -> Result<(), WindowsActionError> {
+        match access {
+            RadioAccessStatus::Allowed => Ok(()),
+            RadioAccessStatus::DeniedByUser => Err(WindowsActionError::AccessDenied(
+                "radio control is off under Settings > Privacy > Radios",
+            )),
+            _ => Err(WindowsActionError::AccessDenied(
+                "Windows doesn't allow changing the radios",
+            )),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod display {
+    use super::{last_os_error, Topology, WindowsActionError};
+    use windows::{
+        core::{HRESULT, PCWSTR},
+        Win32::{
+            Devices::Display::{
+                SetDisplayConfig, SDC_APPLY, SDC_TOPOLOGY_CLONE, SDC_TOPOLOGY_EXTEND,
+                SDC_TOPOLOGY_EXTERNAL, SDC_TOPOLOGY_INTERNAL,
+            },
+            Foundation::{LPARAM, WPARAM},
+            Graphics::Gdi::{
+                ChangeDisplaySettingsW, EnumDisplaySettingsW, CDS_UPDATEREGISTRY, DEVMODEW,
+                DEVMODE_DISPLAY_ORIENTATION, DISP_CHANGE_SUCCESSFUL, DM_DISPLAYORIENTATION,
+                DM_PELSHEIGHT, DM_PELSWIDTH, ENUM_CURRENT_SETTINGS,
+            },
+            UI::WindowsAndMessaging::{
+                SendMessageTimeoutW, HWND_BROADCAST, SC_MONITORPOWER, SMTO_ABORTIFHUNG,
+                WM_SYSCOMMAND,
+            },
+        },
+    };
+
+    /// SC_MONITORPOWER's "off"; 1 is low power, -1 on.
+    const MONITOR_OFF: isize = 2;
+
+    pub fn screen_off() -> Result<(), WindowsActionError> {
+        unsafe {
+            SendMessageTimeoutW(
+                HWND_BROADCAST,
+                WM_SYSCOMMAND,
+                WPARAM(SC_MONITORPOWER as usize),
+                LPARAM(MONITOR_OFF),
+                SMTO_ABORTIFHUNG,
+                1000,
+                None,
+            );
+        }
+        Ok(())
+    }
+
+    pub fn set_topology(topology: Topology) -> Result<(), WindowsActionError> {
+        let topology = match topology {
+            Topology::Extend => SDC_TOPOLOGY_EXTEND,
+            Topology::Duplicate => SDC_TOPOLOGY_CLONE,
+            Topology::Internal => SDC_TOPOLOGY_INTERNAL,
+            Topology::External => SDC_TOPOLOGY_EXTERNAL,
+        };
+        match unsafe { SetDisplayConfig(None, None, SDC_APPLY | topology) } {
+            0 => Ok(()),
+            status => Err(WindowsActionError::Windows(
+                HRESULT::from_win32(status as u32).into(),
+            )),
+        }
+    }
+
+    pub fn rotate(degrees: Option<u16>) -> Result<(), WindowsActionError> {
+        let mut mode = DEVMODEW {
+            dmSize: std::mem::size_of::<DEVMODEW>() as u16,
+            ..Default::default()
+        };
+        unsafe {
+            if !EnumDisplaySettingsW(PCWSTR::null(), ENUM_CURRENT_SETTINGS, &mut mode).as_bool() {
+                return Err(last_os_error());
+            }
+            // Quarter turns clockwise from the display's natural orientation.
+            let current = mode.Anonymous1.Anonymous2.dmDisplayOrientation.0;
+            let turns = match degrees {
+                Some(degrees) => u32::from(degrees / 90 % 4),
+                None => (current + 1) % 4,
+            };
+            // Portrait and landscape swap the width and height.
+            if turns % 2 != current % 2 {
+                std::mem::swap(&mut mode.dmPelsWidth, &mut mode.dmPelsHeight);
+            }
+            mode.Anonymous1.Anonymous2.dmDisplayOrientation = DEVMODE_DISPLAY_ORIENTATION(turns);
+            mode.dmFields = DM_DISPLAYORIENTATION | DM_PELSWIDTH | DM_PELSHEIGHT;
+            let result = ChangeDisplaySettingsW(Some(&mode as *const _), CDS_UPDATEREGISTRY);
+            if result != DISP_CHANGE_SUCCESSFUL {
+                return Err(WindowsActionError::Unsupported(
+                    "the display driver refused the rotation",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn last_os_error() -> WindowsActionError {
+    WindowsActionError::Windows(windows::core::Error::from_win32())
+}
@@ -0,0 +1,236 @@
+//! The speech recognizer built into Windows (SAPI's in-process dictation),
+//! behind `transcription.engine = "windows"`.
+
+use crate::transcription::{Segment, TranscriptionError};
+
+/// Recognizes recorded 16 kHz mono audio. Each call sets up its own
+/// recognizer, since SAPI objects can't be shared between the threads
+/// transcription runs on.
+#[cfg(target_os = "windows")]
+pub struct Recognizer;
+
+#[cfg(target_os = "windows")]
+impl Recognizer {
+    /// Fails when Windows has no speech recognizer for its language.
+    pub fn new() -> Result<Self, TranscriptionError> {
+        platform::check().map_err(recognizer_error)?;
+        Ok(Self)
+    }
+
+    /// Every phrase heard in `audio`, timed from its start.
+    pub fn recognize(&self, audio: &[i16]) -> Result<Vec<Segment>, TranscriptionError> {
+        if audio.is_empty() {
+            return Ok(Vec::new());
+        }
+        platform::recognize(audio).map_err(recognizer_error)
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn recognizer_error(err: windows::core::Error) -> TranscriptionError {
+    TranscriptionError::Recognizer(err.message())
+}
+
+/// Never built off Windows: `new` says why.
+#[cfg(not(target_os = "windows"))]
+pub enum Recognizer {}
+
+#[cfg(not(target_os = "windows"))]
+impl Recognizer {
+    pub fn new() -> Result<Self, TranscriptionError> {
+        Err(TranscriptionError::Recognizer("only available on Windows".to_string()))
+    }
+
+    pub fn recognize(&self, _audio: &[i16]) -> Result<Vec<Segment>, TranscriptionError> {
+        match *self {}
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use crate::transcription::Segment;
+    use std::{
+        ffi::c_void,
+        time::{Duration, Instant},
+    };
+    use windows::{
+        core::{Error, IUnknown, Interface, Result, GUID, PCWSTR, PWSTR},
+        Win32::{
+            Foundation::BOOL,
+            Media::{
+                Audio::{WAVEFORMATEX, WAVE_FORMAT_PCM},
+                Speech::{
+                    ISpRecoContext, ISpRecoGrammar, ISpRecoResult, ISpRecognizer, ISpStream,
+                    SpInprocRecognizer, SpStream, SPEI_END_SR_STREAM, SPEI_RECOGNITION,
+                    SPEI_RESERVED1, SPEI_RESERVED2, SPEVENT, SPEVENTENUM, SPET_LPARAM_IS_OBJECT,
+                    SPET_LPARAM_IS_POINTER, SPET_LPARAM_IS_STRING, SPET_LPARAM_IS_TOKEN,
+                    SPLO_STATIC, SPRECORESULTTIMES, SPRST_ACTIVE, SPRS_ACTIVE,
+                },
+            },
+            System::Com::{
+                CoCreateInstance, CoInitializeEx, CoTaskMemFree, CoUninitialize, CLSCTX_ALL,
+                COINIT_MULTITHREADED,
+            },
+            UI::Shell::SHCreateMemStream,
+        },
+    };
+
+    /// SPDFID_WaveFormatEx: the stream format is described by a WAVEFORMATEX.
+    const WAVE_FORMAT_EX: GUID = GUID::from_u128(0xc31adbae_527f_4ff5_a230_f62bb61ff70c);
+    /// SP_GETWHOLEPHRASE for `GetText`.
+    const WHOLE_PHRASE: u32 = u32::MAX;
+    const SAMPLE_RATE: u32 = 16_000;
+    /// How long recognition may run beyond the length of the audio.
+    const GRACE: Duration = Duration::from_secs(10);
+    const POLL_MS: u32 = 250;
+
+    /// Creates a recognizer and its dictation grammar, which fails when no
+    /// recognizer is installed for the Windows speech language.
+    pub fn check() -> Result<()> {
+        let _com = Com::new()?;
+        unsafe { Session::start(&[0; 2]) }.map(drop)
+    }
+
+    pub fn recognize(audio: &[i16]) -> Result<Vec<Segment>> {
+        let _com = Com::new()?;
+        let bytes: Vec<u8> = audio.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        let length = Duration::from_secs_f64(audio.len() as f64 / SAMPLE_RATE as f64);
+        let deadline = Instant::now() + length + GRACE;
+        let mut segments = Vec::new();
+        unsafe {
+            let session = Session::start(&bytes)?;
+            while Instant::now() < deadline {
+                session.context.WaitForNotifyEvent(POLL_MS)?;
+                let mut event = SPEVENT::default();
+                let mut fetched = 0;
+                loop {
+                    session.context.GetEvents(1, &mut event, &mut fetched)?;
+                    if fetched == 0 {
+                        break;
+                    }
+                    let id = SPEVENTENUM(event._bitfield & 0xffff);
+                    let object = take_lparam(&event);
+                    if id == SPEI_END_SR_STREAM {
+                        return Ok(segments);
+                    }
+                    let result = object.and_then(|object| object.cast::<ISpRecoResult>().ok());
+                    if let (SPEI_RECOGNITION, Some(result)) = (id, result) {
+                        segments.extend(segment(&result)?);
+                    }
+                }
+            }
+        }
+        Ok(segments)
+    }
+
+    /// Fields drop in order: the grammar, then the context, then what the
+    /// context reads from.
+    struct Session {
+        _grammar: ISpRecoGrammar,
+        context: ISpRecoContext,
+        _recognizer: ISpRecognizer,
+        _stream: ISpStream,
+    }
+
+    impl Session {
+        /// Dictation over `bytes` (16 kHz mono 16-bit PCM), already running.
+        unsafe fn start(bytes: &[u8]) -> Result<Self> {
+            let recognizer: ISpRecognizer =
+                CoCreateInstance(&SpInprocRecognizer, None, CLSCTX_ALL)?;
+            let memory = SHCreateMemStream(Some(bytes)).ok_or_else(Error::from_win32)?;
+            let stream: ISpStream = CoCreateInstance(&SpStream, None, CLSCTX_ALL)?;
+            let format = WAVEFORMATEX {
+                wFormatTag: WAVE_FORMAT_PCM as u16,
+                nChannels: 1,
+                nSamplesPerSec: SAMPLE_RATE,
+                nAvgBytesPerSec: SAMPLE_RATE * 2,
+                nBlockAlign: 2,
+                wBitsPerSample: 16,
+                cbSize: 0,
+            };
+            stream.SetBaseStream(&memory, &WAVE_FORMAT_EX, &format)?;
+            recognizer.SetInput(&stream, BOOL::from(true))?;
+            let context = recognizer.CreateRecoContext()?;
+            context.SetNotifyWin32Event()?;
+            let interest = [SPEI_RECOGNITION, SPEI_END_SR_STREAM]
+                .iter()
+                .fold(flag(SPEI_RESERVED1) | flag(SPEI_RESERVED2), |mask, event| {
+                    mask | flag(*event)
+                });
+            context.SetInterest(interest, interest)?;
+            let grammar = context.CreateGrammar(0)?;
+            grammar.LoadDictation(PCWSTR::null(), SPLO_STATIC)?;
+            grammar.SetDictationState(SPRS_ACTIVE)?;
+            recognizer.SetRecoState(SPRST_ACTIVE)?;
+            Ok(Self {
+                _grammar: grammar,
+                context,
+                _recognizer: recognizer,
+                _stream: stream,
+            })
+        }
+    }
+
+    fn flag(event: SPEVENTENUM) -> u64 {
+        1 << event.0
+    }
+
+    /// Takes ownership of what an event's lParam points to, freeing it unless
+    /// it is a COM object, which is returned.
+    unsafe fn take_lparam(event: &SPEVENT) -> Option<IUnknown> {
+        let pointer = event.lParam.0 as *mut c_void;
+        if pointer.is_null() {
+            return None;
+        }
+        match event._bitfield >> 16 {
+            kind if kind == SPET_LPARAM_IS_OBJECT.0 || kind == SPET_LPARAM_IS_TOKEN.0 => {
+                Some(IUnknown::from_raw(pointer))
+            }
+            kind if kind == SPET_LPARAM_IS_POINTER.0 || kind == SPET_LPARAM_IS_STRING.0 => {
+                CoTaskMemFree(Some(pointer));
+                None
+            }
+            _ => None,
+        }
+    }
+
+    unsafe fn segment(result: &ISpRecoResult) -> Result<Option<Segment>> {
+        let mut text = PWSTR::null();
+        result.GetText(WHOLE_PHRASE, WHOLE_PHRASE, BOOL::from(true), &mut text, None)?;
+        if text.is_null() {
+            return Ok(None);
+        }
+        let phrase = text.to_string().unwrap_or_default();
+        CoTaskMemFree(Some(text.0 as *const c_void));
+        let phrase = phrase.trim();
+        if phrase.is_empty() {
+            return Ok(None);
+        }
+        // Both in 100 ns units from the start of the stream.
+        let mut times = SPRECORESULTTIMES::default();
+        result.GetResultTimes(&mut times)?;
+        let start = Duration::from_nanos(times.ullStart * 100);
+        Ok(Some(Segment {
+            start,
+            end: start + Duration::from_nanos(times.ullLength * 100),
+            text: phrase.to_string(),
+            speaker_turn: false,
+        }))
+    }
+
+    /// COM for the calling thread, for as long as it is held.
+    struct Com;
+
+    impl Com {
+        fn new() -> Result<Self> {
+            unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) }.ok()?;
+            Ok(Self)
+        }
+    }
+
+    impl Drop for Com {
+        fn drop(&mut self) {
+            unsafe { CoUninitialize() };
+        }
+    }
+}
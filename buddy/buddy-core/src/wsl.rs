@@ -0,0 +1,111 @@
+//! `[wsl]`: commands run inside the Windows Subsystem for Linux, through
+//! `wsl.exe -e` and so without a Linux shell, with what they print kept to
+//! be shown or read out.
+
+use crate::{
+    clock,
+    config::{Config, WslCommand},
+    intent::Intent,
+    process,
+    rules::EXACT_CONFIDENCE,
+};
+use std::{fmt, io, process::Stdio, time::Duration};
+
+/// Words left out when matching a command's name: "run the deploy script".
+const FILLER_WORDS: &[&str] = &["the", "my", "script", "command"];
+
+#[derive(Debug)]
+pub enum WslError {
+    Start(io::Error),
+    TimedOut(Duration),
+    /// The command exited with an error; the last thing it printed, or its
+    /// status.
+    Failed(String),
+}
+
+impl fmt::Display for WslError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Start(err) => write!(f, "failed to run wsl.exe: {}", err),
+            Self::TimedOut(timeout) => {
+                write!(f, "the command took longer than {}s and was stopped", timeout.as_secs())
+            }
+            Self::Failed(reason) => write!(f, "the command failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for WslError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Start(err) => Some(err),
+            Self::TimedOut(_) | Self::Failed(_) => None,
+        }
+    }
+}
+
+/// Recognizes "run deploy in wsl" and "run the backup script in linux" for
+/// the `[wsl.commands]` entries.
+pub fn recognize(transcript: &str, config: &Config) -> Option<Intent> {
+    if config.wsl.commands.is_empty() {
+        return None;
+    }
+    let words = clock::words(transcript);
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    let words = words.strip_suffix(&["please"]).unwrap_or(&words);
+    let name = match words {
+        ["run", name @ .., "in" | "on", "wsl" | "linux"]
+        | ["run", name @ .., "in" | "on", "the", "wsl" | "linux"] => name,
+        _ => return None,
+    };
+    let spoken: Vec<&str> = name
+        .iter()
+        .copied()
+        .filter(|word| !FILLER_WORDS.contains(word))
+        .collect();
+    let spoken = spoken.join(" ");
+    let target = config
+        .wsl
+        .commands
+        .keys()
+        .find(|key| key.replace('_', " ").to_lowercase() == spoken)?;
+    Some(Intent::Wsl {
+        target: target.clone(),
+        text: String::new(),
+        confidence: EXACT_CONFIDENCE,
+    })
+}
+
+/// The arguments for `wsl.exe` that run `args`, a program and its
+/// arguments, the way `command` is set up.
+pub fn arguments(config: &Config, command: &WslCommand, args: Vec<String>) -> Vec<String> {
+    let mut line = Vec::new();
+    if let Some(distro) = command.distro.as_ref().or(config.wsl.distro.as_ref()) {
+        line.extend(["-d".to_string(), distro.clone()]);
+    }
+    line.extend(["--cd".to_string(), command.cwd.clone(), "-e".to_string()]);
+    line.extend(args);
+    line
+}
+
+/// Runs `wsl.exe` with `args` and returns what it printed, trimmed.
+pub fn run(args: &[String], timeout: Duration) -> Result<String, WslError> {
+    let child = process::hidden("wsl.exe")
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(WslError::Start)?;
+    let output = process::wait_output(child, timeout)
+        .map_err(WslError::Start)?
+        .ok_or(WslError::TimedOut(timeout))?;
+    let stdout = process::text(&output.stdout);
+    if output.status.success() {
+        return Ok(stdout);
+    }
+    let said = [process::text(&output.stderr), stdout]
+        .into_iter()
+        .find_map(|text| text.lines().last().map(str::to_string));
+    Err(WslError::Failed(said.unwrap_or_else(|| output.status.to_string())))
+}
@@ -0,0 +1,212 @@
+//! Stand-ins for the hardware and services behind each pipeline stage, so
+//! the whole loop runs without a microphone, a whisper model or Ollama.
+
+use buddy_core::{
+    audio,
+    executor::{ExecutionError, ExecutionResult},
+    intent::{Intent, IntentError, Spoken},
+    pipeline::{self, PipelineError},
+    stages::{Capture, Executor, Feedback, IntentEngine, StageFuture, Stt, Trigger},
+    transcription::Transcript,
+    Config, Outcome,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+/// A peak below this counts as silence, as whisper would hear nothing.
+const SILENT_PEAK: i16 = 330;
+
+pub fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+/// `tests/fixtures/config.toml`, which points the intent service at a port
+/// nothing listens on.
+pub fn config() -> Config {
+    Config::load(fixture("config.toml"), None).expect("fixture config")
+}
+
+/// "Records" a WAV fixture.
+pub struct WavFile(pub PathBuf);
+
+impl WavFile {
+    pub fn fixture(name: &str) -> Self {
+        Self(fixture(name))
+    }
+}
+
+impl Capture for WavFile {
+    fn record(&self) -> StageFuture<'_, Result<Vec<i16>, PipelineError>> {
+        Box::pin(async move { Ok(audio::load_clip(&self.0)?) })
+    }
+}
+
+/// Hears the next of its transcripts in each clip with sound in it, and
+/// nothing in silence.
+pub struct Scripted(Mutex<VecDeque<String>>);
+
+impl Scripted {
+    pub fn new(transcripts: &[&str]) -> Self {
+        Self(Mutex::new(transcripts.iter().map(|text| text.to_string()).collect()))
+    }
+}
+
+impl Stt for Scripted {
+    fn transcribe(&self, audio: Vec<i16>) -> StageFuture<'_, Result<Transcript, PipelineError>> {
+        let (peak, _) = audio::peak_rms(&audio);
+        let text = if peak < SILENT_PEAK {
+            String::new()
+        } else {
+            self.0.lock().unwrap().pop_front().unwrap_or_default()
+        };
+        Box::pin(async move {
+            Ok(Transcript {
+                text,
+                original: None,
+                language: None,
+                confidence: None,
+            })
+        })
+    }
+}
+
+/// Answers with the intent canned for each transcript, and `Unknown` for
+/// the rest. A transcript canned with `failing` gets an error instead.
+#[derive(Default)]
+pub struct Canned {
+    intents: HashMap<String, Intent>,
+    failures: HashMap<String, String>,
+}
+
+impl Canned {
+    pub fn with(mut self, text: &str, intent: Intent) -> Self {
+        self.intents.insert(text.to_string(), intent);
+        self
+    }
+
+    /// Fails `text` as if the model had named a target that isn't mapped.
+    pub fn failing(mut self, text: &str, target: &str) -> Self {
+        self.failures.insert(text.to_string(), target.to_string());
+        self
+    }
+}
+
+impl IntentEngine for Canned {
+    fn classify<'a>(
+        &'a self,
+        text: &'a str,
+        _spoken: Spoken<'a>,
+    ) -> StageFuture<'a, Result<(Intent, &'static str), IntentError>> {
+        Box::pin(async move {
+            if let Some(target) = self.failures.get(text) {
+                return Err(IntentError::UnknownTarget(target.clone()));
+            }
+            let intent = self
+                .intents
+                .get(text)
+                .cloned()
+                .unwrap_or(Intent::Unknown { confidence: 0.0 });
+            Ok((intent, "canned"))
+        })
+    }
+}
+
+/// Runs nothing, but keeps every intent it was given. Clones share the log,
+/// so a test can hold on to one after handing the other to a `Pipeline`.
+#[derive(Clone, Default)]
+pub struct Recorder(Arc<Mutex<Vec<Intent>>>);
+
+impl Recorder {
+    pub fn ran(&self) -> Vec<Intent> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+impl Executor for Recorder {
+    fn execute(&self, intent: &Intent) -> Result<ExecutionResult, ExecutionError> {
+        self.0.lock().unwrap().push(intent.clone());
+        Ok(match intent {
+            Intent::Answer { response, .. } => ExecutionResult::Answer(response.clone()),
+            Intent::Unknown { .. } => return Err(ExecutionError::UnknownIntent),
+            _ => ExecutionResult::Action(pipeline::describe_intent(intent)),
+        })
+    }
+}
+
+/// A trigger pressed `presses` times, after which it fails as if there were
+/// no trigger. Clones share the count.
+#[derive(Clone)]
+pub struct Presses {
+    left: Arc<AtomicUsize>,
+}
+
+impl Presses {
+    pub fn new(presses: usize) -> Self {
+        Self {
+            left: Arc::new(AtomicUsize::new(presses)),
+        }
+    }
+
+    pub fn left(&self) -> usize {
+        self.left.load(Ordering::SeqCst)
+    }
+}
+
+impl Trigger for Presses {
+    fn wait(&mut self) -> StageFuture<'_, Result<(), PipelineError>> {
+        let pressed = self
+            .left
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| left.checked_sub(1))
+            .is_ok();
+        Box::pin(async move {
+            match pressed {
+                true => Ok(()),
+                false => Err(PipelineError::NoTrigger),
+            }
+        })
+    }
+}
+
+/// Keeps what the user would have been told, one line per event.
+#[derive(Clone, Default)]
+pub struct Events(Arc<Mutex<Vec<String>>>);
+
+impl Events {
+    pub fn seen(&self) -> Vec<String> {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn push(&self, event: String) {
+        self.0.lock().unwrap().push(event);
+    }
+}
+
+impl Feedback for Events {
+    fn recording_started(&mut self) -> StageFuture<'_, ()> {
+        self.push("listening".to_string());
+        Box::pin(async {})
+    }
+
+    fn recording_stopped(&mut self) {
+        self.push("stopped".to_string());
+    }
+
+    fn report(&mut self, outcome: &Outcome) {
+        let status = match &outcome.result {
+            None => "held back",
+            Some(Ok(_)) => "done",
+            Some(Err(_)) => "failed",
+        };
+        self.push(format!("{}: {}", outcome.intent.action().name(), status));
+    }
+
+    fn report_error(&mut self, err: &PipelineError) {
+        self.push(format!("error: {}", err));
+    }
+}
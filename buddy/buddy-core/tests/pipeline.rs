@@ -0,0 +1,218 @@
+//! The pipeline end to end, with the mock stages in `mocks` standing in for
+//! the microphone, whisper, the intent service and the system.
+
+mod mocks;
+
+use buddy_core::{
+    config::IntentFallback,
+    executor::{ExecutionError, ExecutionResult},
+    intent::{Intent, IntentError},
+    stages::IntentService,
+    Pipeline, PipelineError,
+};
+use mocks::{Canned, Events, Presses, Recorder, Scripted, WavFile};
+
+fn open_app(target: &str) -> Intent {
+    Intent::OpenApp {
+        target: target.to_string(),
+        confidence: 0.95,
+    }
+}
+
+/// A pipeline of mocks hearing `transcripts` in order, with its executor's
+/// and feedback's logs.
+fn mocked(transcripts: &[&str], intents: Canned) -> (Pipeline, Recorder, Events) {
+    let recorder = Recorder::default();
+    let events = Events::default();
+    let pipeline = Pipeline::from_stages(
+        mocks::config(),
+        Scripted::new(transcripts),
+        intents,
+        recorder.clone(),
+    )
+    .with_feedback(events.clone());
+    (pipeline, recorder, events)
+}
+
+#[tokio::test]
+async fn spoken_command_runs() {
+    let intents = Canned::default().with("open notepad", open_app("notepad"));
+    let (pipeline, recorder, events) = mocked(&["open notepad"], intents);
+    let mut pipeline = pipeline.with_capture(WavFile::fixture("command.wav"));
+
+    let outcome = pipeline.listen().await.unwrap();
+
+    assert_eq!(outcome.transcript, "open notepad");
+    assert_eq!(outcome.classified_by, "canned");
+    assert!(matches!(outcome.result, Some(Ok(ExecutionResult::Action(_)))));
+    let ran = recorder.ran();
+    assert_eq!(ran.len(), 1);
+    assert_eq!(ran[0].target(), Some("notepad"));
+    assert_eq!(events.seen(), ["listening", "stopped", "open_app: done"]);
+}
+
+#[tokio::test]
+async fn trigger_starts_each_command() {
+    let intents = Canned::default()
+        .with("open notepad", open_app("notepad"))
+        .with("open calculator", open_app("calculator"));
+    let (pipeline, recorder, _) = mocked(&["open notepad", "open calculator"], intents);
+    let presses = Presses::new(2);
+    let mut pipeline = pipeline
+        .with_capture(WavFile::fixture("command.wav"))
+        .with_trigger(presses.clone());
+
+    pipeline.next().await.unwrap();
+    pipeline.next().await.unwrap();
+
+    assert_eq!(presses.left(), 0);
+    let ran = recorder.ran();
+    let targets: Vec<_> = ran.iter().filter_map(Intent::target).collect();
+    assert_eq!(targets, ["notepad", "calculator"]);
+    assert!(matches!(pipeline.next().await, Err(PipelineError::NoTrigger)));
+}
+
+#[tokio::test]
+async fn typed_command_runs() {
+    let intents = Canned::default().with("open notepad", open_app("notepad"));
+    let (mut pipeline, recorder, _) = mocked(&[], intents);
+
+    let outcome = pipeline.handle_text("open notepad").await.unwrap();
+
+    assert!(matches!(outcome.result, Some(Ok(_))));
+    assert_eq!(recorder.ran().len(), 1);
+}
+
+#[tokio::test]
+async fn risky_command_is_held_back_until_confirmed() {
+    let shutdown = Intent::System {
+        target: "shutdown".to_string(),
+        confidence: 0.95,
+    };
+    let intents = Canned::default().with("shut down", shutdown);
+    let (mut pipeline, recorder, events) = mocked(&[], intents);
+
+    let outcome = pipeline.handle_text("shut down").await.unwrap();
+
+    assert!(outcome.result.is_none());
+    assert!(recorder.ran().is_empty());
+    assert_eq!(events.seen(), ["system: held back"]);
+
+    pipeline.execute(&outcome.intent).unwrap();
+    assert_eq!(recorder.ran().len(), 1);
+}
+
+#[tokio::test]
+async fn unsure_command_is_held_back() {
+    let unsure = Intent::OpenApp {
+        target: "notepad".to_string(),
+        confidence: 0.2,
+    };
+    let intents = Canned::default().with("open note pad", unsure);
+    let (mut pipeline, recorder, _) = mocked(&[], intents);
+
+    let outcome = pipeline.handle_text("open note pad").await.unwrap();
+
+    assert!(outcome.result.is_none());
+    assert!(recorder.ran().is_empty());
+}
+
+#[tokio::test]
+async fn silence_is_not_a_command() {
+    let (pipeline, recorder, events) = mocked(&["open notepad"], Canned::default());
+    let mut pipeline = pipeline.with_capture(WavFile::fixture("silence.wav"));
+
+    let result = pipeline.listen().await;
+
+    assert!(matches!(result, Err(PipelineError::NoSpeech)));
+    assert!(recorder.ran().is_empty());
+    assert_eq!(events.seen(), ["listening", "stopped", "error: no speech detected"]);
+}
+
+#[tokio::test]
+async fn missing_clip_fails_the_recording() {
+    let (pipeline, recorder, _) = mocked(&["open notepad"], Canned::default());
+    let mut pipeline = pipeline.with_capture(WavFile::fixture("missing.wav"));
+
+    let result = pipeline.listen().await;
+
+    assert!(matches!(result, Err(PipelineError::Audio(_))));
+    assert!(recorder.ran().is_empty());
+}
+
+#[tokio::test]
+async fn listening_needs_a_capture_stage() {
+    let (mut pipeline, _, events) = mocked(&[], Canned::default());
+
+    assert!(matches!(pipeline.listen().await, Err(PipelineError::NoMicrophone)));
+    assert!(matches!(pipeline.next().await, Err(PipelineError::NoTrigger)));
+    assert_eq!(events.seen().len(), 1);
+}
+
+#[tokio::test]
+async fn intent_error_runs_nothing() {
+    let intents = Canned::default().failing("open the thing", "thing");
+    let (mut pipeline, recorder, events) = mocked(&[], intents);
+
+    let result = pipeline.handle_text("open the thing").await;
+
+    assert!(matches!(
+        result,
+        Err(PipelineError::Intent(IntentError::UnknownTarget(target))) if target == "thing"
+    ));
+    assert!(recorder.ran().is_empty());
+    assert_eq!(events.seen().len(), 1);
+}
+
+#[tokio::test]
+async fn unknown_command_reports_failure() {
+    let (mut pipeline, _, events) = mocked(&[], Canned::default());
+
+    let outcome = pipeline.handle_text("sing me a song").await.unwrap();
+
+    assert!(matches!(outcome.result, Some(Err(ExecutionError::UnknownIntent))));
+    assert_eq!(events.seen(), ["unknown: failed"]);
+}
+
+/// The built-in intent engine against an intent service that isn't running.
+fn offline(fallback: IntentFallback) -> (Pipeline, Recorder) {
+    let mut config = mocks::config();
+    config.deepseek.fallback = fallback;
+    let intents = IntentService::new(&config, false);
+    let recorder = Recorder::default();
+    let pipeline = Pipeline::from_stages(config, Scripted::new(&[]), intents, recorder.clone());
+    (pipeline, recorder)
+}
+
+#[tokio::test]
+async fn arithmetic_is_answered_locally() {
+    let (mut pipeline, _) = offline(IntentFallback::Fail);
+
+    let outcome = pipeline.handle_text("what is 12 times 4").await.unwrap();
+
+    assert_eq!(outcome.classified_by, "calculator");
+    assert!(matches!(
+        outcome.result,
+        Some(Ok(ExecutionResult::Answer(answer))) if answer.contains("48")
+    ));
+}
+
+#[tokio::test]
+async fn rules_stand_in_for_an_unreachable_service() {
+    let (mut pipeline, recorder) = offline(IntentFallback::Rules);
+
+    let outcome = pipeline.handle_text("open notepad").await.unwrap();
+
+    assert_eq!(outcome.intent.target(), Some("notepad"));
+    assert_eq!(recorder.ran().len(), 1);
+}
+
+#[tokio::test]
+async fn unreachable_service_fails_without_fallback() {
+    let (mut pipeline, recorder) = offline(IntentFallback::Fail);
+
+    let result = pipeline.handle_text("tell me a joke").await;
+
+    assert!(matches!(result, Err(PipelineError::Intent(err)) if err.is_unavailable()));
+    assert!(recorder.ran().is_empty());
+}
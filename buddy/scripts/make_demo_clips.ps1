@@ -0,0 +1,40 @@
+# Records the sample utterances used by `buddy --demo` with Windows text-to-speech.
+Param(
+    [string]$Voice = ""
+)
+
+Add-Type -AssemblyName System.Speech
+
+$ScriptDir = Split-Path -Path $MyInvocation.MyCommand.Path -Parent
+$RootDir = Split-Path -Path $ScriptDir -Parent
+$ClipDir = Join-Path $RootDir "resources/demo"
+
+if (-not (Test-Path $ClipDir)) {
+    New-Item -ItemType Directory -Path $ClipDir | Out-Null
+}
+
+$Samples = [ordered]@{
+    "open_details.wav"  = "Open details"
+    "launch_chrome.wav" = "Launch chrome"
+    "todo_line.wav"     = "Open my todo at line 40"
+    "set_volume.wav"    = "Set volume to 25"
+    "mute.wav"          = "Mute the audio"
+    "lock.wav"          = "Lock the computer"
+}
+
+$Synth = New-Object System.Speech.Synthesis.SpeechSynthesizer
+if ($Voice) {
+    $Synth.SelectVoice($Voice)
+}
+# Whisper's native format: 16 kHz, 16-bit, mono
+$Format = New-Object System.Speech.AudioFormat.SpeechAudioFormatInfo(16000, [System.Speech.AudioFormat.AudioBitsPerSample]::Sixteen, [System.Speech.AudioFormat.AudioChannel]::Mono)
+
+foreach ($Clip in $Samples.GetEnumerator()) {
+    $Target = Join-Path $ClipDir $Clip.Key
+    $Synth.SetOutputToWaveFile($Target, $Format)
+    $Synth.Speak($Clip.Value)
+    Write-Host "Recorded '$($Clip.Value)' to $Target"
+}
+
+$Synth.SetOutputToNull()
+$Synth.Dispose()
@@ -0,0 +1,1405 @@
+use crate::{
+    colorize, console, http,
+    ipc::{self, ControlCommand},
+    output, shutdown, BuddyError, Color,
+};
+use buddy_core::{
+    audio::{self, AudioCapturer},
+    audit::{self, Source},
+    barge_in::StopListener,
+    config::{Config, ExportFormat},
+    corrections,
+    executor::{self, Choice, CommandExecutor, ExecutionError, ExecutionResult},
+    export,
+    feedback::FeedbackPlayer,
+    hotkey::{HotkeyAction, HotkeyListener, Interrupts},
+    intent::{Intent, IntentClient, IntentError, Spoken},
+    longform::Meeting,
+    macros::{self, Recorder, Step},
+    metrics::{Metrics, Timings},
+    notifications::{self, ToastChoice},
+    pipeline::{
+        build_transcription_prompt, classify, command_vocabulary, describe_intent,
+        needs_clarification, needs_confirmation, wait_for_intent_ready,
+    },
+    rag, reminders,
+    rules::{self, Correction, LanguageChoice, MacroCommand, MeetingCommand, Pick},
+    session::Session,
+    transcription::{self, Transcriber},
+    windows_api::{self, WindowsActionError},
+};
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+use tokio::sync::{
+    mpsc::{self, UnboundedReceiver},
+    oneshot,
+};
+
+/// How `buddy` was started, beyond what the config says.
+pub struct Options {
+    /// The config file "reload config" re-reads.
+    pub config_path: PathBuf,
+    /// `--profile`.
+    pub profile: Option<String>,
+    /// `--dry-run`.
+    pub dry_run: bool,
+    /// `--stats`.
+    pub stats: bool,
+    pub debug: bool,
+    /// `--whisper-log`.
+    pub whisper_log: bool,
+    /// `--daemon`: also take commands over the control channel.
+    pub daemon: bool,
+}
+
+/// Listens for the hotkey and control requests until asked to quit, then
+/// prints the session summary.
+pub async fn run(
+    config: Config,
+    options: Options,
+    intent_client: IntentClient,
+    warmup: Option<oneshot::Receiver<Result<(), IntentError>>>,
+) -> Result<(), BuddyError> {
+    let Options {
+        config_path,
+        profile,
+        dry_run,
+        stats,
+        debug,
+        whisper_log,
+        daemon,
+    } = options;
+    // The speech model, the microphone, the voice and the intent service
+    // each take a while, so they all start at once; commands are taken when
+    // the last of them is ready. With `deepseek.start_degraded` the intent
+    // service is already warming up in the background and isn't waited for.
+    let feedback = FeedbackPlayer::new(&config.feedback, &config.phrases);
+    let audio_config = config.audio.clone();
+    let capturer = async {
+        let (capturer, elapsed) = tokio::task::spawn_blocking(move || {
+            let start = Instant::now();
+            (AudioCapturer::new(&audio_config, debug), start.elapsed())
+        })
+        .await?;
+        Ok::<_, BuddyError>((Arc::new(capturer?), elapsed))
+    };
+    let transcription_config = config.transcription.clone();
+    let initial_prompt = build_transcription_prompt(&config);
+    let transcriber = async {
+        let (transcriber, elapsed) = tokio::task::spawn_blocking(move || {
+            let start = Instant::now();
+            let transcriber =
+                Transcriber::new(&transcription_config, initial_prompt, debug, !whisper_log);
+            (transcriber, start.elapsed())
+        })
+        .await?;
+        Ok::<_, BuddyError>((Arc::new(transcriber?), elapsed))
+    };
+    // A missing intent service may be fine (see `deepseek.fallback`), so it
+    // doesn't cut the others short.
+    let intent_ready = async {
+        if warmup.is_some() {
+            return Ok(Ok(()));
+        }
+        let timeout = Duration::from_secs(config.deepseek.ready_timeout_secs);
+        let ready = wait_for_intent_ready(&intent_client, timeout, true).await;
+        if ready.is_ok() {
+            intent_client.preload_models(&config).await;
+        }
+        Ok(ready)
+    };
+    let ((capturer, audio_time), (transcriber, model_time), ready) =
+        tokio::try_join!(capturer, transcriber, intent_ready)?;
+    if debug {
+        println!(
+            "Microphone ready in {:.1}s, speech model in {:.1}s",
+            audio_time.as_secs_f32(),
+            model_time.as_secs_f32()
+        );
+    }
+    if let Err(err) = ready {
+        crate::intent_unavailable(&config, err)?;
+    }
+    transcriber.set_command_vocabulary(command_vocabulary(&config));
+    if debug {
+        println!("Whisper system info: {}", whisper_rs::print_system_info());
+    }
+    let interrupts = Interrupts {
+        cancel: capturer.cancel_flag(),
+        stop: Arc::new(AtomicBool::new(false)),
+    };
+    let hotkey = HotkeyListener::new(&config.hotkey, interrupts.clone())?;
+    let (control_tx, control_rx) = mpsc::unbounded_channel();
+    shutdown::install(capturer.cancel_flag(), control_tx.clone());
+    console::spawn(control_tx.clone());
+    if daemon {
+        ipc::spawn_server(control_tx.clone(), true)?;
+        println!("Control server listening on {}", ipc::endpoint());
+    } else if let Err(err) = ipc::spawn_server(control_tx.clone(), false) {
+        eprintln!("Cannot open the control channel ({}); a second buddy won't see this one", err);
+    }
+    if config.http.enabled {
+        let addr = http::spawn_server(&config.http, control_tx.clone()).await?;
+        println!("HTTP API listening on http://{}", addr);
+    }
+    let (reminder_tx, reminder_rx) = mpsc::unbounded_channel();
+    reminders::spawn_scheduler(config.reminders.path.clone(), reminder_tx);
+    let mut app = App {
+        feedback,
+        config,
+        config_path,
+        profile,
+        dry_run,
+        stats,
+        debug,
+        intent_client,
+        capturer,
+        transcriber,
+        session: Session::new(),
+        metrics: Metrics::new(),
+        meeting: None,
+        recording: None,
+        stop: interrupts.stop,
+        stop_listener: None,
+        quit: false,
+    };
+    app.follow_language();
+    app.listen_for_stop();
+
+    println!(
+        "Buddy ready. Press '{}' to issue a voice command.",
+        app.config.hotkey.key
+    );
+    app.feedback.greet();
+
+    let result = app.serve(hotkey, control_rx, reminder_rx, warmup).await;
+    if let Err(err) = &result {
+        app.metrics.record_error(err.to_string());
+    }
+    if let Some(meeting) = app.meeting.take() {
+        println!("Transcript saved to {}", meeting.stop().display());
+    }
+    app.report_session();
+    app.feedback.goodbye().await;
+    result
+}
+
+/// Resolves once `stop` is raised.
+async fn stop_requested(stop: &AtomicBool) {
+    while !stop.load(Ordering::Relaxed) {
+        tokio::time::sleep(STOP_POLL).await;
+    }
+}
+
+/// A reply being recorded. Dropped before it finishes, as when the toast
+/// answers first, it stops the recording so the microphone is free again.
+struct PendingCapture {
+    cancel: Arc<AtomicBool>,
+    capture: Option<tokio::task::JoinHandle<Result<Vec<i16>, audio::AudioError>>>,
+}
+
+impl PendingCapture {
+    async fn finish(&mut self) -> Option<Vec<i16>> {
+        let audio = self.capture.as_mut()?.await;
+        self.capture = None;
+        audio.ok()?.ok()
+    }
+}
+
+impl Drop for PendingCapture {
+    fn drop(&mut self) {
+        let Some(capture) = self.capture.take() else {
+            return;
+        };
+        let cancel = Arc::clone(&self.cancel);
+        // A capture that hasn't started yet clears the flag, so it is set
+        // until the capture is over.
+        tokio::spawn(async move {
+            while !capture.is_finished() {
+                cancel.store(true, Ordering::Relaxed);
+                tokio::time::sleep(STOP_POLL).await;
+            }
+        });
+    }
+}
+
+async fn next_warmup(
+    warmup: &mut Option<oneshot::Receiver<Result<(), IntentError>>>,
+) -> Option<Result<(), IntentError>> {
+    match warmup {
+        Some(rx) => {
+            let result = rx.await.ok();
+            *warmup = None;
+            result
+        }
+        None => std::future::pending().await,
+    }
+}
+
+
+/// How often the stop flag is checked while waiting on other things.
+const STOP_POLL: Duration = Duration::from_millis(50);
+
+/// Re-asks allowed before a "which one?" question is dropped.
+const MAX_CHOICE_ATTEMPTS: u32 = 2;
+
+/// Follow-up state after a command has been classified.
+enum Dialog {
+    Run { intent: Intent, streamed: bool },
+    /// Waiting for the user to pick one of `choices`; `attempt` counts re-asks.
+    Choose { choices: Vec<Choice>, attempt: u32 },
+}
+
+
+/// Groups commands for the session summary, e.g. "open_app chrome".
+fn command_label(intent: &Intent) -> String {
+    match intent.target() {
+        Some(target) => format!("{} {}", intent.action().name(), target),
+        None => intent.action().name().to_string(),
+    }
+}
+
+/// Everything the command loop needs between hotkey presses.
+struct App {
+    config: Config,
+    config_path: PathBuf,
+    /// The `[profiles]` entry laid over the config, from `--profile` or
+    /// "switch to the work profile"; kept across reloads.
+    profile: Option<String>,
+    /// `--dry-run`, which outlives config reloads.
+    dry_run: bool,
+    /// `--stats`: print the full statistics on exit, not just the summary.
+    stats: bool,
+    debug: bool,
+    intent_client: IntentClient,
+    capturer: Arc<AudioCapturer>,
+    transcriber: Arc<Transcriber>,
+    feedback: FeedbackPlayer,
+    session: Session,
+    metrics: Metrics,
+    /// Set between "start transcribing" and "stop transcribing".
+    meeting: Option<Meeting>,
+    /// Set between "start macro ..." and "end macro".
+    recording: Option<Recorder>,
+    /// Raised by the stop hotkey, or by "stop" heard while Buddy talks.
+    stop: Arc<AtomicBool>,
+    /// Running with `feedback.listen_for_stop`.
+    stop_listener: Option<StopListener>,
+    /// Set by "buddy quit"; `serve` returns once the command is done.
+    quit: bool,
+}
+
+impl App {
+    /// Services hotkey presses and control requests until asked to quit.
+    async fn serve(
+        &mut self,
+        mut hotkey: HotkeyListener,
+        mut control: UnboundedReceiver<ipc::ControlRequest>,
+        mut reminders: UnboundedReceiver<reminders::Reminder>,
+        mut warmup: Option<oneshot::Receiver<Result<(), IntentError>>>,
+    ) -> Result<(), BuddyError> {
+        loop {
+            if self.debug {
+                println!("Waiting for hotkey...");
+            }
+            // A reload or profile switch may change the hotkeys.
+            let hotkeys = self.config.hotkey.clone();
+            let stop = Arc::clone(&self.stop);
+            tokio::select! {
+                result = hotkey.wait() => {
+                    let action = result?;
+                    if self.debug {
+                        println!("Hotkey received: {:?}", action);
+                    }
+                    match action {
+                        HotkeyAction::Command => self.listen(Source::Hotkey).await?,
+                        HotkeyAction::Dictation => self.dictate().await?,
+                        // Nothing is recording; just cut off any speech.
+                        HotkeyAction::Cancel => self.feedback.stop(),
+                        HotkeyAction::Stop => self.stop_speaking(),
+                    }
+                }
+                () = stop_requested(&stop) => self.stop_speaking(),
+                Some(result) = next_warmup(&mut warmup) => {
+                    match result {
+                        Ok(()) => {
+                            println!("{}", colorize("Intent model ready", Color::Green));
+                            self.feedback.say_phrase("model_ready");
+                        }
+                        Err(err) => eprintln!(
+                            "{}",
+                            colorize(
+                                &format!(
+                                    "Intent service still unavailable ({}); staying on rule-based intents",
+                                    err
+                                ),
+                                Color::Yellow
+                            )
+                        ),
+                    }
+                }
+                Some(reminder) = reminders.recv() => {
+                    println!("{}", colorize(&format!("Reminder: {}", reminder.text), Color::Cyan));
+                    self.feedback.remind(&reminder.text);
+                }
+                Some(request) = control.recv() => {
+                    if matches!(request.command, ControlCommand::Quit) {
+                        request.respond("bye");
+                        return Ok(());
+                    }
+                    self.handle_control(request).await?;
+                }
+            }
+            if self.quit {
+                return Ok(());
+            }
+            if self.config.hotkey != hotkeys {
+                // Unregister the old combinations before registering the new ones.
+                drop(hotkey);
+                hotkey = HotkeyListener::new(&self.config.hotkey, self.interrupts())?;
+            }
+        }
+    }
+
+    /// Prints the session summary and appends it to `logging.session_log`.
+    fn report_session(&self) {
+        let summary = self.metrics.summary();
+        println!("{}", summary);
+        if self.stats {
+            println!("{}", self.metrics.details());
+        }
+        if let Some(path) = &self.config.logging.session_log {
+            let appended = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| {
+                    use std::io::Write;
+                    writeln!(file, "{}", summary)
+                });
+            if let Err(err) = appended {
+                eprintln!("Failed to write session log {}: {}", path.display(), err);
+            }
+        }
+    }
+
+    /// Records one utterance and runs it through the pipeline.
+    async fn listen(&mut self, source: Source) -> Result<(), BuddyError> {
+        let total_start = Instant::now();
+        let mut timings = Timings::default();
+        println!("Recording audio...");
+        let capture_start = Instant::now();
+        let Some(audio_buffer) = self.record().await? else {
+            return Ok(());
+        };
+        timings.capture = capture_start.elapsed();
+
+        println!("Transcribing...");
+        let transcribe_start = Instant::now();
+        let transcript = self.transcriber.transcribe_command(&audio_buffer)?;
+        timings.transcribe = transcribe_start.elapsed();
+        if transcript.text.trim().is_empty() {
+            eprintln!("No speech detected");
+            output::error("transcribe", "no speech detected");
+            self.feedback.error_phrase("didnt_hear");
+            return Ok(());
+        }
+        output::transcript(&transcript);
+        if transcript.unclear(self.config.transcription.min_confidence) {
+            println!(
+                "Heard (unsure, {:.2}): {}",
+                transcript.confidence.unwrap_or_default(),
+                transcript.text
+            );
+            output::error("transcribe", "unclear speech");
+            self.feedback.error_phrase("didnt_catch");
+            self.metrics.record_command("unclear", false);
+            return Ok(());
+        }
+        match &transcript.original {
+            Some(original) => println!("Heard: {} (translated: {})", original, transcript.text),
+            None => println!("Heard: {}", transcript.text),
+        }
+        self.handle_transcript(
+            &transcript.text,
+            transcript.spoken(),
+            source,
+            &mut timings,
+        )
+        .await;
+        timings.total = total_start.elapsed();
+        self.metrics.record_timings(&timings);
+        output::timings(&timings);
+        if self.debug {
+            let confidence = transcript
+                .confidence
+                .map(|confidence| format!(" confidence={:.2}", confidence))
+                .unwrap_or_default();
+            println!(
+                "{}",
+                colorize(
+                    &format!(
+                        "Timings: capture={:.2}s transcribe={:.2}s intent={:.2}s execute={:.2}s total={:.2}s{}",
+                        timings.capture.as_secs_f64(),
+                        timings.transcribe.as_secs_f64(),
+                        timings.intent.as_secs_f64(),
+                        timings.execute.as_secs_f64(),
+                        timings.total.as_secs_f64(),
+                        confidence
+                    ),
+                    Color::Cyan
+                )
+            );
+        }
+        if self.debug {
+            println!("Command complete");
+        }
+        Ok(())
+    }
+
+    /// Records one utterance. `None` means nothing was recorded: the cancel
+    /// hotkey stopped it or the microphone failed.
+    async fn record(&mut self) -> Result<Option<Vec<i16>>, BuddyError> {
+        // A new request cuts off whatever Buddy is still saying.
+        self.feedback.listening().await;
+        let capturer = Arc::clone(&self.capturer);
+        let max_duration = if self.config.audio.capture_duration_secs == 0 {
+            None
+        } else {
+            Some(Duration::from_secs(self.config.audio.capture_duration_secs))
+        };
+        let captured = tokio::task::spawn_blocking(move || capturer.capture(max_duration)).await?;
+        self.feedback.listening_stopped();
+        if let Some(change) = self.capturer.take_device_change() {
+            self.feedback.say(&change.message());
+        }
+        let audio = match captured {
+            Ok(audio) => audio,
+            Err(audio::AudioError::Cancelled) => {
+                println!("Cancelled");
+                self.feedback.stop();
+                return Ok(None);
+            }
+            // Not fatal: the device may come back before the next press.
+            Err(err) => {
+                eprintln!("Recording failed: {}", err);
+                output::error("capture", &err);
+                let key = match err {
+                    audio::AudioError::DeviceLost => "mic_disconnected",
+                    audio::AudioError::MicMuted => "mic_muted",
+                    _ => "mic_unavailable",
+                };
+                self.feedback.error_phrase(key);
+                return Ok(None);
+            }
+        };
+        if let Some(warning) = self.capturer.take_level_warning() {
+            eprintln!("{}", warning.message());
+            self.feedback.say(warning.message());
+        }
+        Ok(Some(audio))
+    }
+
+    /// Dictation mode: types what was said into the focused window instead of
+    /// treating it as a command.
+    async fn dictate(&mut self) -> Result<(), BuddyError> {
+        println!("Dictating...");
+        let Some(audio) = self.record().await? else {
+            return Ok(());
+        };
+        let text = self.transcriber.transcribe(&audio)?;
+        if text.trim().is_empty() {
+            eprintln!("No speech detected");
+            self.feedback.error_phrase("didnt_hear");
+            return Ok(());
+        }
+        println!("Dictated: {}", text);
+        self.type_text("dictation", text).await;
+        Ok(())
+    }
+
+    /// Types the last answer into the focused window.
+    async fn insert_answer(&mut self) {
+        let Some(text) = self.session.last_answer().map(str::to_string) else {
+            println!("No answer to type");
+            self.feedback.error_phrase("nothing_to_type");
+            self.metrics.record_command("insert", false);
+            return;
+        };
+        println!("Typing: {}", text);
+        self.type_text("insert", text).await;
+    }
+
+    /// Saves the commands handled so far, and what came of them, to a file
+    /// in `export.dir` and says its name.
+    fn export_conversation(&mut self, format: ExportFormat) {
+        let entries: Vec<_> = self.session.history().collect();
+        if entries.is_empty() {
+            println!("Nothing to save");
+            self.feedback.say_phrase("nothing_to_save");
+            self.metrics.record_command("export", false);
+            return;
+        }
+        match export::save(&self.config.export, &entries, format) {
+            Ok(path) => {
+                println!("Conversation saved to {}", path.display());
+                let file = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let reply = self.feedback.phrase("conversation_saved", &[("file", &file)]);
+                self.feedback.say(&reply);
+                self.metrics.record_command("export", true);
+            }
+            Err(err) => {
+                let dir = self.config.export.dir.display();
+                eprintln!("Cannot save the conversation to {}: {}", dir, err);
+                self.feedback.error_phrase("failed");
+                self.metrics.record_command("export", false);
+                self.metrics.record_error(format!("export: {}", err));
+            }
+        }
+    }
+
+    /// "Update the document index". Embedding new documents can take a
+    /// while, during which Buddy isn't listening.
+    async fn index_documents(&mut self) {
+        if self.config.rag.folders.is_empty() {
+            println!("rag.folders is empty; nothing to index");
+            self.feedback.say_phrase("no_document_folders");
+            self.metrics.record_command("index", false);
+            return;
+        }
+        self.feedback.say_phrase("indexing_documents");
+        match rag::update(&self.config, &self.intent_client).await {
+            Ok(update) => {
+                println!("Document index updated: {}", update);
+                let count = update.indexed.to_string();
+                let reply = self.feedback.phrase("documents_indexed", &[("count", &count)]);
+                self.feedback.say(&reply);
+                self.metrics.record_command("index", true);
+            }
+            Err(err) => {
+                eprintln!("Cannot update the document index: {}", err);
+                self.feedback.error_phrase("failed");
+                self.metrics.record_command("index", false);
+                self.metrics.record_error(format!("index: {}", err));
+            }
+        }
+    }
+
+    /// Types `text` off the main task, since a typing delay can make it take
+    /// a while; `label` names the command in metrics.
+    async fn type_text(&mut self, label: &str, text: String) {
+        let delay = Duration::from_millis(self.config.typing.char_delay_ms);
+        let typed = tokio::task::spawn_blocking(move || windows_api::type_text(&text, delay)).await;
+        let result = match typed {
+            Ok(result) => result.map_err(|err| err.to_string()),
+            Err(err) => Err(err.to_string()),
+        };
+        match result {
+            Ok(()) => self.metrics.record_command(label, true),
+            Err(err) => {
+                eprintln!("Typing failed: {}", err);
+                self.feedback.error_phrase("typing_failed");
+                self.metrics.record_command(label, false);
+                self.metrics.record_error(format!("{}: {}", label, err));
+            }
+        }
+    }
+
+    /// Classifies and executes an already transcribed command.
+    async fn handle_transcript(
+        &mut self,
+        transcript: &str,
+        spoken: Spoken<'_>,
+        source: Source,
+        timings: &mut Timings,
+    ) {
+        // "No, I meant open notepad" runs the meant command instead, and
+        // saves it as what the previous command meant.
+        let meant;
+        let mut corrected = None;
+        let (transcript, spoken) = match rules::correction_command(transcript) {
+            Some(correction) => {
+                let Some(previous) = self.session.history().last() else {
+                    println!("Nothing to correct");
+                    self.feedback.say_phrase("nothing_to_correct");
+                    self.metrics.record_command("correction", false);
+                    return;
+                };
+                let previous = previous.transcript.clone();
+                meant = match correction {
+                    Correction::Meant(text) => text,
+                    Correction::Wrong => match self.ask_meant().await {
+                        Some(text) => text,
+                        None => {
+                            self.feedback.say_phrase("never_mind");
+                            self.metrics.record_command("correction", false);
+                            return;
+                        }
+                    },
+                };
+                println!("Correcting \"{}\" to \"{}\"", previous, meant);
+                corrected = Some(previous);
+                (meant.as_str(), Spoken::default())
+            }
+            None => (transcript, spoken),
+        };
+        let normalized = transcript
+            .trim()
+            .trim_end_matches(|c: char| c == '.' || c == '!' || c == '?');
+        if normalized.eq_ignore_ascii_case("help") {
+            let help = self.feedback.phrase("help", &[]);
+            println!("Help: {}", help);
+            self.feedback.say(&help);
+            self.metrics.record_command("help", true);
+            return;
+        }
+        if let Some(name) = rules::profile_command(normalized) {
+            // Like languages, an unknown name is let through as a command.
+            if self.switch_profile(&name) {
+                self.metrics.record_command("profile", true);
+                return;
+            }
+        }
+        if let Some(choice) = rules::language_command(normalized) {
+            // "Switch to chrome" isn't a language; let it through as a command.
+            if self.switch_language(choice) {
+                self.metrics.record_command("language", true);
+                return;
+            }
+        }
+        if let Some(command) = rules::meeting_command(normalized) {
+            self.meeting_command(command).await;
+            self.metrics.record_command("meeting", true);
+            return;
+        }
+        if let Some(command) = rules::macro_command(normalized) {
+            // "Run notepad" isn't a macro unless one is called that.
+            if self.macro_command(command, source).await {
+                return;
+            }
+        }
+        if rules::wants_quit(normalized) {
+            println!("Quitting");
+            self.metrics.record_command("quit", true);
+            self.quit = true;
+            return;
+        }
+        if rules::wants_stats(normalized) {
+            println!("{}", self.metrics.summary());
+            let stats = self.metrics.spoken_summary();
+            self.feedback.say(&stats);
+            self.metrics.record_command("stats", true);
+            return;
+        }
+        if rules::wants_index_update(normalized) {
+            self.index_documents().await;
+            return;
+        }
+        if rules::wants_insert(normalized) {
+            self.insert_answer().await;
+            return;
+        }
+        if let Some(format) = rules::export_command(normalized, self.config.export.format) {
+            self.export_conversation(format);
+            return;
+        }
+        if rules::wants_silence(normalized) {
+            // The hotkey press already cut Buddy off; this drops the rest.
+            println!("Stopped");
+            self.feedback.take_unspoken();
+            self.stop_speaking();
+            self.metrics.record_command("stop", true);
+            return;
+        }
+        if let Some(rest) = self.feedback.take_unspoken() {
+            if rules::wants_more(normalized) {
+                println!("Answer (continued): {}", rest);
+                self.feedback.answer(&rest);
+                self.metrics.record_command("answer", true);
+                return;
+            }
+            if rules::wants_stop(normalized) {
+                return;
+            }
+        }
+
+        let intent_start = Instant::now();
+        let mut streamed = false;
+        let intent = if let Some(intent) = self.session.resolve_follow_up(normalized, &self.config) {
+            if self.debug {
+                println!("Resolved follow-up from previous command: {:?}", intent);
+            }
+            output::intent(&intent, "follow_up");
+            self.metrics.record_source("follow_up");
+            intent
+        } else {
+            self.feedback.thinking();
+            // A stop pressed while recording was meant for the speech before.
+            self.stop.store(false, Ordering::Relaxed);
+            let stop = Arc::clone(&self.stop);
+            let feedback = &mut self.feedback;
+            let mut on_sentence = |sentence: &str| {
+                streamed = true;
+                feedback.say_sentence(sentence);
+            };
+            let classified = tokio::select! {
+                result = classify(
+                    &self.intent_client,
+                    transcript,
+                    spoken,
+                    &self.config,
+                    self.debug,
+                    &mut on_sentence,
+                ) => Some(result),
+                () = stop_requested(&stop) => None,
+            };
+            let Some(classified) = classified else {
+                // Dropping the request ends the answer streaming in.
+                println!("Stopped");
+                self.stop_speaking();
+                self.metrics.record_command("stop", true);
+                return;
+            };
+            match classified {
+                Ok((intent, source)) => {
+                    output::intent(&intent, source);
+                    self.metrics.record_source(source);
+                    intent
+                }
+                Err(err) => {
+                    eprintln!("Intent error: {}", err);
+                    output::error("intent", &err);
+                    if err.is_loading() {
+                        self.feedback.error_phrase("model_loading");
+                    } else {
+                        self.feedback.error_phrase("intent_failed");
+                    }
+                    self.metrics.record_command("unclassified", false);
+                    self.metrics.record_error(format!("intent: {}", err));
+                    return;
+                }
+            }
+        };
+        timings.intent = intent_start.elapsed();
+        if let Some(previous) = &corrected {
+            if let Err(err) = corrections::record(&self.config.corrections, previous, &intent) {
+                eprintln!("Failed to save correction: {}", err);
+            }
+        }
+        self.session.record(transcript, spoken.confidence, &intent);
+        let label = command_label(&intent);
+        // A "yes" to "did you mean" also covers the risky-action confirmation.
+        // Denied actions aren't worth a question; the executor refuses them.
+        let denied = executor::denied(&self.config, &intent).is_some();
+        let description = describe_intent(&intent);
+        if !denied && needs_clarification(&self.config, &intent) {
+            let question = self.feedback.phrase("did_you_mean", &[("action", &description)]);
+            if !self.confirm(&question).await {
+                println!("Not executing low-confidence intent");
+                self.feedback.say_phrase("say_again");
+                self.metrics.record_command(&label, true);
+                return;
+            }
+        } else if !denied && needs_confirmation(&self.config, &intent) {
+            let question = self.feedback.phrase("are_you_sure", &[("action", &description)]);
+            if !self.confirm(&question).await {
+                println!("Cancelled");
+                self.feedback.say_phrase("cancelled");
+                self.metrics.record_command(&label, true);
+                return;
+            }
+        }
+        let execute_start = Instant::now();
+        let outcome = self.run_dialog(intent, streamed, source).await;
+        timings.execute = execute_start.elapsed();
+        self.session.record_outcome(outcome.is_ok());
+        self.metrics.record_command(&label, outcome.is_ok());
+        if let Err(err) = outcome {
+            self.metrics.record_error(format!("action: {}", err));
+        }
+    }
+
+    /// Pins (or unpins) the language Whisper listens for. Returns false when
+    /// `choice` names something that isn't a language.
+    fn switch_language(&mut self, choice: LanguageChoice) -> bool {
+        let (language, name) = match choice {
+            LanguageChoice::Auto => ("auto", None),
+            LanguageChoice::Pin(name) => {
+                let Some(code) = transcription::language_code(&name) else {
+                    return false;
+                };
+                (code, Some(transcription::language_name(code).unwrap_or(name)))
+            }
+        };
+        println!("Transcription language: {}", language);
+        self.transcriber.set_language(Some(language.to_string()));
+        self.follow_language();
+        let reply = match name {
+            Some(name) => self.feedback.phrase("language_switched", &[("language", &name)]),
+            None => self.feedback.phrase("language_auto", &[]),
+        };
+        self.feedback.say(&reply);
+        true
+    }
+
+    /// Reloads the config with the `[profiles]` entry called `spoken`, or
+    /// without one for "default". Returns false when there is no such profile.
+    fn switch_profile(&mut self, spoken: &str) -> bool {
+        let profile = match self.config.profile_key(spoken) {
+            Some(key) => Some(key),
+            None if matches!(spoken, "default" | "normal" | "no") => None,
+            None => return false,
+        };
+        match Config::load(&self.config_path, profile.as_deref()) {
+            Ok(config) => {
+                println!("Profile: {}", profile.as_deref().unwrap_or("none"));
+                self.profile = profile;
+                self.apply_config(config);
+                let reply = match &self.profile {
+                    Some(name) => {
+                        let name = name.replace('_', " ");
+                        self.feedback.phrase("profile_switched", &[("profile", &name)])
+                    }
+                    None => self.feedback.phrase("profile_default", &[]),
+                };
+                self.feedback.say(&reply);
+            }
+            Err(err) => {
+                eprintln!("Cannot switch profile: {}", err);
+                self.feedback.error_phrase("failed");
+            }
+        }
+        true
+    }
+
+    /// Swaps in a freshly loaded config and rebuilds what is derived from it:
+    /// the intent client, Whisper's vocabulary prompt, the command vocabulary
+    /// and the feedback player.
+    fn apply_config(&mut self, mut config: Config) {
+        config.policy.dry_run |= self.dry_run;
+        self.config = config;
+        self.intent_client = IntentClient::new(&self.config);
+        self.transcriber
+            .set_initial_prompt(build_transcription_prompt(&self.config));
+        self.transcriber
+            .set_command_vocabulary(command_vocabulary(&self.config));
+        self.feedback = FeedbackPlayer::new(&self.config.feedback, &self.config.phrases);
+        self.follow_language();
+        self.listen_for_stop();
+    }
+
+    /// Starts (or restarts, for a new feedback player) listening for "stop"
+    /// while Buddy talks, if `feedback.listen_for_stop` is on.
+    fn listen_for_stop(&mut self) {
+        // The old listener has to let go of the microphone first.
+        self.stop_listener = None;
+        if self.config.feedback.listen_for_stop {
+            self.stop_listener = Some(StopListener::start(
+                Arc::clone(&self.capturer),
+                Arc::clone(&self.transcriber),
+                self.feedback.playing_flag(),
+                self.feedback.ducking_flag(),
+                Arc::clone(&self.stop),
+            ));
+        }
+    }
+
+    /// The flags the hotkeys raise the moment they are pressed.
+    fn interrupts(&self) -> Interrupts {
+        Interrupts {
+            cancel: self.capturer.cancel_flag(),
+            stop: Arc::clone(&self.stop),
+        }
+    }
+
+    /// Cuts off whatever Buddy is saying and lowers the stop flag.
+    fn stop_speaking(&mut self) {
+        self.stop.store(false, Ordering::Relaxed);
+        self.feedback.stop();
+    }
+
+    /// Speaks `[phrases]` in the language Whisper is pinned to, if any.
+    fn follow_language(&mut self) {
+        let language = self.transcriber.language().filter(|language| language != "auto");
+        self.feedback.set_language(language.as_deref());
+    }
+
+    /// Starts or stops the background meeting transcription.
+    async fn meeting_command(&mut self, command: MeetingCommand) {
+        match (command, self.meeting.take()) {
+            (MeetingCommand::Start, Some(meeting)) => {
+                self.meeting = Some(meeting);
+                self.feedback.say_phrase("already_transcribing");
+            }
+            (MeetingCommand::Start, None) => {
+                let transcriber = Arc::clone(&self.transcriber);
+                match Meeting::start(&self.config, transcriber, None, self.debug, self.debug) {
+                    Ok(meeting) => {
+                        println!("Transcribing to {}", meeting.path().display());
+                        self.feedback.say_phrase("transcribing");
+                        self.meeting = Some(meeting);
+                    }
+                    Err(err) => {
+                        eprintln!("Cannot start transcribing: {}", err);
+                        self.feedback.error_phrase("cannot_transcribe");
+                    }
+                }
+            }
+            (MeetingCommand::Stop, Some(meeting)) => {
+                self.feedback.say_phrase("finishing_transcript");
+                match tokio::task::spawn_blocking(move || meeting.stop()).await {
+                    Ok(path) => {
+                        println!("Transcript saved to {}", path.display());
+                        self.feedback.say_phrase("transcript_saved");
+                    }
+                    Err(err) => eprintln!("Transcription did not finish: {}", err),
+                }
+            }
+            (MeetingCommand::Stop, None) => self.feedback.say_phrase("not_transcribing"),
+        }
+    }
+
+    /// Starts or ends recording a macro, or replays one. Returns false when
+    /// asked to run a macro that doesn't exist.
+    async fn macro_command(&mut self, command: MacroCommand, source: Source) -> bool {
+        match (command, self.recording.take()) {
+            (MacroCommand::Start(_), Some(recorder)) => {
+                self.recording = Some(recorder);
+                self.feedback.say_phrase("already_recording_macro");
+                self.metrics.record_command("macro", false);
+            }
+            (MacroCommand::Start(name), None) => {
+                let recorder = Recorder::new(&self.config.macros, &name);
+                println!("Recording macro \"{}\"", recorder.name());
+                let reply = self.feedback.phrase("macro_recording", &[("name", recorder.name())]);
+                self.feedback.say(&reply);
+                self.recording = Some(recorder);
+                self.metrics.record_command("macro", true);
+            }
+            (MacroCommand::End, None) => {
+                self.feedback.say_phrase("not_recording_macro");
+                self.metrics.record_command("macro", false);
+            }
+            (MacroCommand::End, Some(recorder)) => {
+                let name = recorder.name().to_string();
+                match recorder.save(&self.config.macros) {
+                    Ok(0) => {
+                        println!("Macro \"{}\" is empty; not saved", name);
+                        self.feedback.say_phrase("macro_empty");
+                        self.metrics.record_command("macro", false);
+                    }
+                    Ok(steps) => {
+                        println!("Saved macro \"{}\" ({} steps)", name, steps);
+                        let reply = self.feedback.phrase("macro_saved", &[("name", &name)]);
+                        self.feedback.say(&reply);
+                        self.metrics.record_command("macro", true);
+                    }
+                    Err(err) => {
+                        eprintln!("Cannot save macro \"{}\": {}", name, err);
+                        self.feedback.error_phrase("failed");
+                        self.metrics.record_command("macro", false);
+                        self.metrics.record_error(format!("macro: {}", err));
+                    }
+                }
+            }
+            (MacroCommand::Run(name), recorder) => {
+                self.recording = recorder;
+                let steps = match macros::load(&self.config.macros, &name) {
+                    Ok(Some(steps)) => steps,
+                    Ok(None) => return false,
+                    Err(err) => {
+                        eprintln!("Cannot read macros: {}", err);
+                        return false;
+                    }
+                };
+                let ok = self.run_macro(&name, steps, source).await;
+                self.metrics.record_command("macro", ok);
+            }
+        }
+        true
+    }
+
+    /// Runs the steps of a macro in order, each after its pause. Stops at the
+    /// first step that fails or isn't confirmed; returns whether all ran.
+    async fn run_macro(&mut self, name: &str, steps: Vec<Step>, source: Source) -> bool {
+        println!("Running macro \"{}\" ({} steps)", name, steps.len());
+        for (index, step) in steps.into_iter().enumerate() {
+            let intent = match Intent::from_json(step.intent) {
+                Ok(intent) => intent,
+                Err(err) => {
+                    eprintln!("Macro \"{}\" step {} is invalid: {}", name, index + 1, err);
+                    self.feedback.error_phrase("failed");
+                    return false;
+                }
+            };
+            tokio::time::sleep(Duration::from_millis(step.delay_ms)).await;
+            let denied = executor::denied(&self.config, &intent).is_some();
+            if !denied && needs_confirmation(&self.config, &intent) {
+                let description = describe_intent(&intent);
+                let question = self.feedback.phrase("are_you_sure", &[("action", &description)]);
+                if !self.confirm(&question).await {
+                    println!("Cancelled");
+                    self.feedback.say_phrase("cancelled");
+                    return false;
+                }
+            }
+            if let Err(err) = self.run_dialog(intent, false, source).await {
+                self.metrics.record_error(format!("macro {}: {}", name, err));
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Executes `intent`, then keeps the conversation going while the result
+    /// needs a follow-up answer (currently: picking one of several matches).
+    async fn run_dialog(
+        &mut self,
+        intent: Intent,
+        streamed: bool,
+        source: Source,
+    ) -> Result<(), ExecutionError> {
+        let mut state = Dialog::Run { intent, streamed };
+        loop {
+            state = match state {
+                Dialog::Run { intent, streamed } => {
+                    let mut executor = CommandExecutor::new(&self.config);
+                    if let Some(recorder) = &self.recording {
+                        executor = executor.with_recorder(recorder);
+                    }
+                    let feedback = &mut self.feedback;
+                    match handle_intent(&executor, intent, feedback, streamed, source)? {
+                        Handled::Choose(choices) => Dialog::Choose {
+                            choices,
+                            attempt: 0,
+                        },
+                        Handled::Answered(text) => {
+                            self.session.remember_answer(text);
+                            return Ok(());
+                        }
+                        Handled::Done => return Ok(()),
+                    }
+                }
+                Dialog::Choose { choices, attempt } => {
+                    if attempt >= MAX_CHOICE_ATTEMPTS {
+                        println!("No choice made");
+                        self.feedback.say_phrase("never_mind");
+                        return Ok(());
+                    }
+                    let labels: Vec<String> =
+                        choices.iter().map(|choice| choice.label.clone()).collect();
+                    let prompt = if attempt == 0 {
+                        let options = labels
+                            .iter()
+                            .enumerate()
+                            .map(|(index, label)| format!("{}, {}", index + 1, label))
+                            .collect::<Vec<_>>()
+                            .join("; ");
+                        self.feedback.phrase("which_one", &[("options", &options)])
+                    } else {
+                        self.feedback.phrase("which_one_again", &[])
+                    };
+                    println!("{}", prompt);
+                    self.feedback.say(&prompt);
+                    let timeout =
+                        Duration::from_secs(self.config.confirmation.timeout_secs.max(1));
+                    self.feedback.wait_idle().await;
+                    let reply = self.hear_reply(timeout).await;
+                    match reply.and_then(|text| rules::pick_choice(&text, &labels)) {
+                        Some(Pick::Index(index)) => Dialog::Run {
+                            intent: choices[index].intent.clone(),
+                            streamed: false,
+                        },
+                        Some(Pick::Cancel) => {
+                            println!("Cancelled");
+                            self.feedback.say_phrase("cancelled");
+                            return Ok(());
+                        }
+                        None => Dialog::Choose {
+                            choices,
+                            attempt: attempt + 1,
+                        },
+                    }
+                }
+            };
+        }
+    }
+
+    /// Asks what the last command should have done and returns the reply.
+    async fn ask_meant(&mut self) -> Option<String> {
+        let question = self.feedback.phrase("what_did_you_mean", &[]);
+        println!("{}", question);
+        self.feedback.say(&question);
+        let timeout = Duration::from_secs(self.config.confirmation.timeout_secs.max(1));
+        self.feedback.wait_idle().await;
+        self.hear_reply(timeout).await
+    }
+
+    /// Records a short spoken reply (yes/no, "the second one") and returns its
+    /// transcript, or `None` if nothing usable was heard.
+    fn hear_reply(&self, timeout: Duration) -> impl std::future::Future<Output = Option<String>> {
+        let capturer = Arc::clone(&self.capturer);
+        let transcriber = Arc::clone(&self.transcriber);
+        async move {
+            let mut capture = PendingCapture {
+                cancel: capturer.cancel_flag(),
+                capture: Some(tokio::task::spawn_blocking(move || capturer.capture(Some(timeout)))),
+            };
+            let reply = capture
+                .finish()
+                .await
+                .and_then(|audio| transcriber.transcribe(&audio).ok())
+                .filter(|text| !text.trim().is_empty());
+            if let Some(text) = reply.as_deref() {
+                println!("Heard: {}", text);
+            }
+            reply
+        }
+    }
+
+    /// Asks a yes/no question and races a spoken reply against the toast
+    /// buttons; whichever answers first wins. Silence or a timeout means no.
+    async fn confirm(&mut self, question: &str) -> bool {
+        println!("Confirm: {}", question);
+        let prompt = self.feedback.phrase("say_yes_or_no", &[("question", question)]);
+        self.feedback.say(&prompt);
+        let timeout = Duration::from_secs(self.config.confirmation.timeout_secs.max(1));
+        let mut toast = if self.config.confirmation.toast {
+            match notifications::confirm_toast("Buddy", question) {
+                Ok(toast) => Some(toast),
+                Err(err) => {
+                    if self.debug {
+                        eprintln!("Confirmation toast unavailable: {}", err);
+                    }
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        self.feedback.wait_idle().await;
+        let reply = self.hear_reply(timeout);
+        let voice = async move {
+            match reply.await.as_deref().and_then(rules::yes_no) {
+                Some(answer) => answer,
+                None => std::future::pending().await,
+            }
+        };
+        let clicked = async {
+            match toast.as_mut() {
+                Some(toast) => toast.choice().await == ToastChoice::Confirm,
+                None => std::future::pending().await,
+            }
+        };
+        let answer = tokio::select! {
+            answer = voice => answer,
+            answer = clicked => answer,
+            _ = tokio::time::sleep(timeout) => false,
+        };
+        if let Some(toast) = &toast {
+            toast.dismiss();
+        }
+        answer
+    }
+
+    async fn handle_control(&mut self, request: ipc::ControlRequest) -> Result<(), BuddyError> {
+        match request.command.clone() {
+            ControlCommand::Trigger => {
+                let source = request.source;
+                request.respond("listening");
+                self.listen(source).await?;
+            }
+            ControlCommand::Status => {
+                request.respond(format!(
+                    "running; pid={}; config={}; hotkey={}; commands={}",
+                    std::process::id(),
+                    self.config_path.display(),
+                    self.config.hotkey.key,
+                    self.metrics.commands()
+                ));
+            }
+            ControlCommand::ReloadConfig => {
+                match Config::load(&self.config_path, self.profile.as_deref()) {
+                    Ok(config) => {
+                        self.apply_config(config);
+                        println!("Reloaded config from '{}'", self.config_path.display());
+                        request.respond("reloaded");
+                    }
+                    Err(err) => request.respond(format!("error: {}", err)),
+                }
+            }
+            ControlCommand::TestIntent(phrase) => {
+                let reply = match classify(
+                    &self.intent_client,
+                    &phrase,
+                    Spoken::default(),
+                    &self.config,
+                    self.debug,
+                    &mut |_| {},
+                )
+                .await
+                {
+                    Ok((intent, _)) => format!(
+                        "action={:?} confidence={:.2} intent={:?}",
+                        intent.action(),
+                        intent.confidence(),
+                        intent
+                    ),
+                    Err(err) => format!("error: {}", err),
+                };
+                request.respond(reply);
+            }
+            ControlCommand::Intent(text) => {
+                let reply = match classify(
+                    &self.intent_client,
+                    &text,
+                    Spoken::default(),
+                    &self.config,
+                    self.debug,
+                    &mut |_| {},
+                )
+                .await
+                {
+                    Ok((intent, _)) => intent.to_json().to_string(),
+                    Err(err) => format!("error: {}", err),
+                };
+                request.respond(reply);
+            }
+            ControlCommand::Text(text) => {
+                println!("Text: {}", text);
+                let mut timings = Timings::default();
+                self.handle_transcript(&text, Spoken::default(), request.source, &mut timings)
+                    .await;
+                request.respond("done");
+            }
+            // The main loop stops before dispatching here.
+            ControlCommand::Quit => request.respond("bye"),
+            ControlCommand::Say(text) => {
+                request.respond("ok");
+                self.feedback.say(&text);
+            }
+            ControlCommand::Metrics => request.respond(self.metrics.prometheus()),
+            ControlCommand::History => {
+                let entries: Vec<_> = self
+                    .session
+                    .history()
+                    .map(|entry| {
+                        serde_json::json!({
+                            "timestamp": entry
+                                .at
+                                .duration_since(UNIX_EPOCH)
+                                .map(|elapsed| elapsed.as_secs())
+                                .unwrap_or_default(),
+                            "transcript": entry.transcript,
+                            "confidence": entry.confidence,
+                            "intent": entry.intent.to_json(),
+                        })
+                    })
+                    .collect();
+                request.respond(serde_json::Value::from(entries).to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+
+/// What came of running an intent.
+pub enum Handled {
+    Done,
+    /// The answer or translation that was read out.
+    Answered(String),
+    /// Several targets fit; the user picks one.
+    Choose(Vec<Choice>),
+}
+
+/// Runs `intent`, auditing it as coming from `source`, and tells the user how
+/// it went.
+pub fn handle_intent(
+    executor: &CommandExecutor<'_>,
+    intent: Intent,
+    feedback: &mut FeedbackPlayer,
+    already_spoken: bool,
+    source: Source,
+) -> Result<Handled, ExecutionError> {
+    let confidence = intent.confidence();
+    let result = |fields| output::result(&intent, fields);
+    let executed = executor.execute(&intent);
+    audit::record_executed(executor.config(), source, &intent, &executed);
+    match executed {
+        Ok(outcome) => match outcome {
+            ExecutionResult::Action(message) => {
+                println!("{} (confidence {:.2})", message, confidence);
+                result(serde_json::json!({ "message": message }));
+                let target = intent.target().map(|target| target.replace('_', " "));
+                let [action, name, _] = executor::policy_names(&intent);
+                feedback.success(
+                    &[name, action],
+                    &[("target", target.as_deref().unwrap_or_default())],
+                );
+            }
+            ExecutionResult::DryRun(message) => {
+                println!("{} (confidence {:.2})", message, confidence);
+                result(serde_json::json!({ "message": message, "dry_run": true }));
+                feedback.say(&message);
+            }
+            ExecutionResult::Answer(response)
+            | ExecutionResult::Ran {
+                summary: response, ..
+            } => {
+                println!("Answer: {} (confidence {:.2})", response, confidence);
+                result(serde_json::json!({ "answer": response }));
+                if already_spoken {
+                    feedback.finish_streamed_answer(&response);
+                } else {
+                    println!("Speaking response...");
+                    feedback.answer(&response);
+                }
+                return Ok(Handled::Answered(response));
+            }
+            ExecutionResult::Translation { text, language } => {
+                println!("{}: {} (confidence {:.2})", language, text, confidence);
+                result(serde_json::json!({ "translation": text, "language": language }));
+                feedback.say_in(&text, &language);
+                return Ok(Handled::Answered(text));
+            }
+            ExecutionResult::Choose(choices) => {
+                let labels: Vec<&str> =
+                    choices.iter().map(|choice| choice.label.as_str()).collect();
+                result(serde_json::json!({ "choices": labels }));
+                return Ok(Handled::Choose(choices));
+            }
+        },
+        Err(err) => {
+            eprintln!("Action failed: {}", err);
+            result(serde_json::json!({ "succeeded": false, "error": err.to_string() }));
+            if matches!(err, ExecutionError::UnknownIntent) {
+                feedback.error_phrase("unknown_intent");
+            } else if matches!(err, ExecutionError::Denied(_)) {
+                feedback.error_phrase("denied");
+            } else if matches!(err, ExecutionError::Hook(_)) {
+                feedback.error_phrase("hook_refused");
+            } else if matches!(
+                err,
+                ExecutionError::Windows(WindowsActionError::AccessDenied(_))
+            ) {
+                feedback.error_phrase("access_denied");
+            } else {
+                feedback.error_phrase("failed");
+            }
+            return Err(err);
+        }
+    }
+    Ok(Handled::Done)
+}
+
@@ -0,0 +1,281 @@
+//! `--install-autostart` and `--uninstall-autostart`: starting Buddy when the
+//! user logs in. That means the Run key on Windows, an XDG autostart entry on
+//! Linux and a launch agent on macOS. A Windows service would run outside the
+//! user's session, where it can't register hotkeys or speak, so Buddy runs
+//! as an ordinary program instead.
+//!
+//! The config's relative paths (the Whisper model, `macros.json`, ...) are
+//! resolved against the working directory. The entry starts Buddy in the
+//! directory it was installed from, with the config's absolute path, so it
+//! finds them however it is launched.
+
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+/// What the entry runs and where.
+#[derive(Debug)]
+pub struct Launch {
+    pub exe: PathBuf,
+    pub config: PathBuf,
+    pub working_dir: PathBuf,
+    pub profile: Option<String>,
+    pub daemon: bool,
+}
+
+impl Launch {
+    /// This executable with `config`, run from the current directory.
+    pub fn current(
+        config: &Path,
+        profile: Option<&str>,
+        daemon: bool,
+    ) -> Result<Self, AutostartError> {
+        let exe = std::env::current_exe().map_err(AutostartError::Locate)?;
+        let working_dir = std::env::current_dir().map_err(AutostartError::Locate)?;
+        let config =
+            std::path::absolute(config).map_err(|err| AutostartError::Io(config.into(), err))?;
+        Ok(Self {
+            exe,
+            config,
+            working_dir,
+            profile: profile.map(str::to_string),
+            daemon,
+        })
+    }
+
+    /// The arguments after the program.
+    fn args(&self) -> Vec<String> {
+        let mut args = vec![
+            self.config.display().to_string(),
+            "--working-dir".to_string(),
+            self.working_dir.display().to_string(),
+        ];
+        if let Some(profile) = &self.profile {
+            args.push("--profile".to_string());
+            args.push(profile.clone());
+        }
+        if self.daemon {
+            args.push("--daemon".to_string());
+        }
+        args
+    }
+}
+
+/// Registers `launch` to run at login, replacing an entry already there,
+/// and returns where it was written.
+pub fn install(launch: &Launch) -> Result<String, AutostartError> {
+    platform::install(launch)
+}
+
+/// Removes the entry; false when there was none.
+pub fn uninstall() -> Result<bool, AutostartError> {
+    platform::uninstall()
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+    use std::process::Command;
+
+    const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+    const VALUE: &str = "Buddy";
+
+    pub fn install(launch: &Launch) -> Result<String, AutostartError> {
+        let command = std::iter::once(launch.exe.display().to_string())
+            .chain(launch.args())
+            .map(|arg| quote(&arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+        reg(&["add", RUN_KEY, "/v", VALUE, "/t", "REG_SZ", "/d", &command, "/f"])?;
+        Ok(format!(r"{}\{}", RUN_KEY, VALUE))
+    }
+
+    pub fn uninstall() -> Result<bool, AutostartError> {
+        let present = Command::new("reg")
+            .args(["query", RUN_KEY, "/v", VALUE])
+            .output()
+            .is_ok_and(|output| output.status.success());
+        if present {
+            reg(&["delete", RUN_KEY, "/v", VALUE, "/f"])?;
+        }
+        Ok(present)
+    }
+
+    /// Quoted for the command line; a trailing backslash is doubled so that
+    /// it doesn't escape the closing quote.
+    fn quote(arg: &str) -> String {
+        if arg.ends_with('\\') {
+            format!("\"{}\\\"", arg)
+        } else {
+            format!("\"{}\"", arg)
+        }
+    }
+
+    fn reg(args: &[&str]) -> Result<(), AutostartError> {
+        let output = Command::new("reg")
+            .args(args)
+            .output()
+            .map_err(|err| AutostartError::Io("reg".into(), err))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(AutostartError::Reg(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ))
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::*;
+
+    const LABEL: &str = "com.buddy.assistant";
+
+    fn plist_path() -> PathBuf {
+        let home = std::env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+        home.join("Library/LaunchAgents").join(format!("{}.plist", LABEL))
+    }
+
+    pub fn install(launch: &Launch) -> Result<String, AutostartError> {
+        let arguments: String = std::iter::once(launch.exe.display().to_string())
+            .chain(launch.args())
+            .map(|arg| format!("        <string>{}</string>\n", escape(&arg)))
+            .collect();
+        let plist = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \
+             \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+             <plist version=\"1.0\">\n\
+             <dict>\n\
+             \x20   <key>Label</key>\n\
+             \x20   <string>{}</string>\n\
+             \x20   <key>ProgramArguments</key>\n\
+             \x20   <array>\n\
+             {}\
+             \x20   </array>\n\
+             \x20   <key>WorkingDirectory</key>\n\
+             \x20   <string>{}</string>\n\
+             \x20   <key>RunAtLoad</key>\n\
+             \x20   <true/>\n\
+             </dict>\n\
+             </plist>\n",
+            LABEL,
+            arguments,
+            escape(&launch.working_dir.display().to_string())
+        );
+        write_entry(&plist_path(), &plist)
+    }
+
+    pub fn uninstall() -> Result<bool, AutostartError> {
+        remove_entry(&plist_path())
+    }
+
+    fn escape(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod platform {
+    use super::*;
+
+    fn desktop_path() -> PathBuf {
+        let config = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .unwrap_or_default();
+        config.join("autostart").join("buddy.desktop")
+    }
+
+    pub fn install(launch: &Launch) -> Result<String, AutostartError> {
+        let exec = std::iter::once(launch.exe.display().to_string())
+            .chain(launch.args())
+            .map(|arg| quote(&arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let entry = format!(
+            "[Desktop Entry]\n\
+             Type=Application\n\
+             Name=Buddy\n\
+             Comment=Voice commands\n\
+             Exec={}\n\
+             Path={}\n\
+             Terminal=false\n\
+             X-GNOME-Autostart-enabled=true\n",
+            exec,
+            launch.working_dir.display()
+        );
+        write_entry(&desktop_path(), &entry)
+    }
+
+    pub fn uninstall() -> Result<bool, AutostartError> {
+        remove_entry(&desktop_path())
+    }
+
+    /// Quoted as the desktop entry spec asks for `Exec` arguments.
+    fn quote(arg: &str) -> String {
+        let mut quoted = String::from("\"");
+        for c in arg.chars() {
+            if matches!(c, '"' | '`' | '$' | '\\') {
+                quoted.push('\\');
+            }
+            quoted.push(c);
+        }
+        quoted.push('"');
+        // The spec reserves '%' for field codes.
+        quoted.replace('%', "%%")
+    }
+}
+
+#[cfg(unix)]
+fn write_entry(path: &Path, text: &str) -> Result<String, AutostartError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|err| AutostartError::Io(parent.into(), err))?;
+    }
+    std::fs::write(path, text).map_err(|err| AutostartError::Io(path.into(), err))?;
+    Ok(path.display().to_string())
+}
+
+#[cfg(unix)]
+fn remove_entry(path: &Path) -> Result<bool, AutostartError> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(AutostartError::Io(path.into(), err)),
+    }
+}
+
+#[derive(Debug)]
+pub enum AutostartError {
+    /// The executable or the current directory couldn't be found.
+    Locate(io::Error),
+    Io(PathBuf, io::Error),
+    /// `reg.exe` failed; what it said.
+    #[cfg(windows)]
+    Reg(String),
+}
+
+impl fmt::Display for AutostartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Locate(err) => write!(f, "cannot find buddy's executable: {}", err),
+            Self::Io(path, err) => write!(f, "failed to access {}: {}", path.display(), err),
+            #[cfg(windows)]
+            Self::Reg(message) => write!(f, "reg failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for AutostartError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Locate(err) | Self::Io(_, err) => Some(err),
+            #[cfg(windows)]
+            Self::Reg(_) => None,
+        }
+    }
+}
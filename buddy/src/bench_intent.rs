@@ -0,0 +1,299 @@
+//! `buddy bench-intent <dataset>`: runs labelled utterances through intent
+//! classification and reports, for each model tried, how often each action
+//! was picked right, how long classification took and what was mistaken for
+//! what. The dataset has one JSON object per line:
+//! `{"text": "open my resume", "action": "open_file", "target": "resume"}`,
+//! where `target` may be left out.
+
+use crate::{colorize, Color};
+use buddy_core::{
+    config::Config,
+    intent::{IntentClient, Spoken},
+    pipeline,
+};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// Stands in for the action when classification failed outright.
+const FAILED: &str = "error";
+/// Widest column in the confusion matrix; longer action names are cut.
+const MAX_COLUMN: usize = 10;
+
+pub struct Options {
+    pub dataset: PathBuf,
+    /// Passes over the dataset per model; latency varies between them.
+    pub runs: usize,
+    /// `deepseek.model` when empty.
+    pub models: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Sample {
+    text: String,
+    action: String,
+    #[serde(default)]
+    target: Option<String>,
+}
+
+/// One model's results over every run.
+struct Report {
+    model: String,
+    /// Expected and predicted action of each classification.
+    pairs: Vec<(String, String)>,
+    targets_checked: usize,
+    targets_right: usize,
+    latencies: Vec<Duration>,
+    /// What classified each intent: "model", "rules", ...
+    sources: BTreeMap<&'static str, usize>,
+}
+
+#[derive(Debug)]
+pub enum BenchError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, usize, serde_json::Error),
+    Empty(PathBuf),
+}
+
+impl fmt::Display for BenchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "failed to read {}: {}", path.display(), err),
+            Self::Parse(path, line, err) => {
+                write!(f, "{} line {} is not a labelled utterance: {}", path.display(), line, err)
+            }
+            Self::Empty(path) => write!(f, "{} has no utterances", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for BenchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, err) => Some(err),
+            Self::Parse(_, _, err) => Some(err),
+            Self::Empty(_) => None,
+        }
+    }
+}
+
+pub async fn run(config: &Config, options: &Options) -> Result<(), BenchError> {
+    let samples = load(&options.dataset)?;
+    let models = if options.models.is_empty() {
+        vec![config.deepseek.model.clone()]
+    } else {
+        options.models.clone()
+    };
+    let runs = options.runs.max(1);
+    let mut reports = Vec::new();
+    for model in models {
+        let mut config = config.clone();
+        config.deepseek.model = model.clone();
+        let client = IntentClient::new(&config);
+        if let Err(err) = client.wait_for_ready().await {
+            eprintln!("Skipping {}: {}", model, err);
+            continue;
+        }
+        println!("Benchmarking {}: {} utterances x {} runs", model, samples.len(), runs);
+        reports.push(bench(&client, &config, &samples, runs, model).await);
+    }
+    if reports.is_empty() {
+        return Ok(());
+    }
+    for report in &reports {
+        print_summary(report);
+    }
+    print_actions(&reports);
+    for report in &reports {
+        print_confusion(report);
+    }
+    Ok(())
+}
+
+fn load(path: &Path) -> Result<Vec<Sample>, BenchError> {
+    let text = fs::read_to_string(path).map_err(|err| BenchError::Io(path.into(), err))?;
+    let samples = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|err| BenchError::Parse(path.into(), index + 1, err))
+        })
+        .collect::<Result<Vec<Sample>, _>>()?;
+    if samples.is_empty() {
+        return Err(BenchError::Empty(path.into()));
+    }
+    Ok(samples)
+}
+
+async fn bench(
+    client: &IntentClient,
+    config: &Config,
+    samples: &[Sample],
+    runs: usize,
+    model: String,
+) -> Report {
+    let mut report = Report {
+        model,
+        pairs: Vec::new(),
+        targets_checked: 0,
+        targets_right: 0,
+        latencies: Vec::new(),
+        sources: BTreeMap::new(),
+    };
+    for _ in 0..runs {
+        for sample in samples {
+            let start = Instant::now();
+            let spoken = Spoken::default();
+            let classified =
+                pipeline::classify_intent(client, &sample.text, spoken, config, &mut |_| {}).await;
+            report.latencies.push(start.elapsed());
+            let (predicted, target) = match &classified {
+                Ok((intent, source)) => {
+                    *report.sources.entry(source).or_default() += 1;
+                    (intent.action().name(), intent.target())
+                }
+                Err(err) => {
+                    eprintln!("\"{}\" failed: {}", sample.text, err);
+                    (FAILED, None)
+                }
+            };
+            if let Some(expected) = &sample.target {
+                report.targets_checked += 1;
+                if target.is_some_and(|target| target.eq_ignore_ascii_case(expected)) {
+                    report.targets_right += 1;
+                }
+            }
+            report.pairs.push((sample.action.clone(), predicted.to_string()));
+        }
+    }
+    report
+}
+
+fn print_summary(report: &Report) {
+    let right = report.pairs.iter().filter(|(expected, predicted)| expected == predicted).count();
+    let total: Duration = report.latencies.iter().sum();
+    let average = total / report.latencies.len().max(1) as u32;
+    let slowest = report.latencies.iter().max().copied().unwrap_or_default();
+    let sources: Vec<String> = report
+        .sources
+        .iter()
+        .map(|(source, count)| format!("{} {}", source, count))
+        .collect();
+    println!();
+    println!("{}", colorize(&report.model, Color::Cyan));
+    println!(
+        "  action accuracy  {} ({}/{})",
+        percent(right, report.pairs.len()),
+        right,
+        report.pairs.len()
+    );
+    println!(
+        "  target accuracy  {} ({}/{})",
+        percent(report.targets_right, report.targets_checked),
+        report.targets_right,
+        report.targets_checked
+    );
+    println!(
+        "  latency          avg {:.2}s, max {:.2}s",
+        average.as_secs_f64(),
+        slowest.as_secs_f64()
+    );
+    println!("  classified by    {}", sources.join(", "));
+}
+
+/// Precision and recall of each action, with the models side by side.
+fn print_actions(reports: &[Report]) {
+    let actions = actions(reports);
+    let width = actions.iter().map(String::len).max().unwrap_or(0).max("action".len());
+    println!();
+    let mut header = format!("{:<width$}", "action", width = width);
+    for report in reports {
+        header.push_str(&format!("  {:>15}", shorten(&report.model, 15)));
+    }
+    println!("{}", header);
+    let mut columns = format!("{:<width$}", "", width = width);
+    for _ in reports {
+        columns.push_str(&format!("  {:>7} {:>7}", "prec", "recall"));
+    }
+    println!("{}", columns);
+    for action in &actions {
+        let mut row = format!("{:<width$}", action, width = width);
+        for report in reports {
+            let right = report
+                .pairs
+                .iter()
+                .filter(|(expected, predicted)| expected == action && predicted == action)
+                .count();
+            let predicted = report.pairs.iter().filter(|(_, guess)| guess == action).count();
+            let expected = report.pairs.iter().filter(|(label, _)| label == action).count();
+            row.push_str(&format!(
+                "  {:>7} {:>7}",
+                percent(right, predicted),
+                percent(right, expected)
+            ));
+        }
+        println!("{}", row);
+    }
+}
+
+/// Rows are the labelled actions, columns what was predicted.
+fn print_confusion(report: &Report) {
+    let actions = actions(std::slice::from_ref(report));
+    let label = actions.iter().map(String::len).max().unwrap_or(0);
+    let column = actions
+        .iter()
+        .map(|action| action.len().min(MAX_COLUMN))
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    println!();
+    println!("Confusion matrix for {} (rows: expected, columns: predicted)", report.model);
+    let mut header = format!("{:<label$}", "", label = label);
+    for action in &actions {
+        header.push_str(&format!(" {:>column$}", shorten(action, column), column = column));
+    }
+    println!("{}", header);
+    let mut counts: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    for (expected, predicted) in &report.pairs {
+        *counts.entry((expected, predicted)).or_default() += 1;
+    }
+    for expected in &actions {
+        let mut row = format!("{:<label$}", expected, label = label);
+        for predicted in &actions {
+            let count = counts
+                .get(&(expected.as_str(), predicted.as_str()))
+                .copied()
+                .unwrap_or(0);
+            let cell = if count == 0 { ".".to_string() } else { count.to_string() };
+            row.push_str(&format!(" {:>column$}", cell, column = column));
+        }
+        println!("{}", row);
+    }
+}
+
+/// Every action labelled or predicted in any of `reports`.
+fn actions(reports: &[Report]) -> Vec<String> {
+    let actions: BTreeSet<&String> = reports
+        .iter()
+        .flat_map(|report| report.pairs.iter())
+        .flat_map(|(expected, predicted)| [expected, predicted])
+        .collect();
+    actions.into_iter().cloned().collect()
+}
+
+fn percent(part: usize, whole: usize) -> String {
+    if whole == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.1}%", part as f64 * 100.0 / whole as f64)
+    }
+}
+
+fn shorten(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
@@ -0,0 +1,238 @@
+//! `buddy bench-stt <folder>`: transcribes a folder of recordings with each
+//! whisper model under `[transcription.models]` and reports the word error
+//! rate and time of each, to help choose between model sizes. Every
+//! `name.wav` needs its reference transcript next to it in `name.txt`.
+
+use buddy_core::{
+    audio,
+    config::{Config, SttEngine, TranscriptionConfig},
+    transcription::{self, Transcriber},
+};
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// Samples per second of the audio whisper is given.
+const SAMPLE_RATE: f64 = 16_000.0;
+
+/// A recording and what was said in it.
+struct Clip {
+    name: String,
+    audio: Vec<i16>,
+    reference: String,
+}
+
+/// One model's totals over the folder.
+struct Report {
+    model: String,
+    errors: usize,
+    words: usize,
+    elapsed: Duration,
+    clips: usize,
+    /// Seconds of audio in the clips transcribed.
+    audio_secs: f64,
+}
+
+#[derive(Debug)]
+pub enum BenchSttError {
+    Io(PathBuf, io::Error),
+    Empty(PathBuf),
+}
+
+impl fmt::Display for BenchSttError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "failed to read {}: {}", path.display(), err),
+            Self::Empty(path) => {
+                write!(f, "{} has no .wav files with a .txt transcript", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for BenchSttError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, err) => Some(err),
+            Self::Empty(_) => None,
+        }
+    }
+}
+
+pub fn run(
+    config: &Config,
+    folder: &Path,
+    debug: bool,
+    suppress_native_logs: bool,
+) -> Result<(), BenchSttError> {
+    let clips = load(folder)?;
+    let audio_secs: f64 = clips.iter().map(|clip| clip.audio.len() as f64 / SAMPLE_RATE).sum();
+    println!("{} clips, {:.1}s of audio", clips.len(), audio_secs);
+    let mut reports = Vec::new();
+    for (model, cfg) in models(config) {
+        println!();
+        println!("Loading {}...", model);
+        let transcriber = match Transcriber::new(&cfg, None, debug, suppress_native_logs) {
+            Ok(transcriber) => transcriber,
+            Err(err) => {
+                eprintln!("Skipping {}: {}", model, err);
+                continue;
+            }
+        };
+        reports.push(bench(&transcriber, &clips, model));
+    }
+    if reports.len() > 1 {
+        print_comparison(&reports);
+    }
+    Ok(())
+}
+
+/// The models to compare, by name, with the transcription settings for
+/// each: those under `[transcription.models]`, or the configured recognizer
+/// alone when there are none.
+fn models(config: &Config) -> Vec<(String, TranscriptionConfig)> {
+    if config.transcription.models.is_empty() {
+        let name = match config.transcription.engine {
+            SttEngine::Whisper => config.transcription.model_path.display().to_string(),
+            SttEngine::Windows => "Windows speech recognition".to_string(),
+            SttEngine::Cloud => "the [transcription.cloud] service".to_string(),
+        };
+        return vec![(name, config.transcription.clone())];
+    }
+    let mut models: Vec<_> = config
+        .transcription
+        .models
+        .iter()
+        .map(|(name, path)| {
+            let mut cfg = config.transcription.clone();
+            cfg.engine = SttEngine::Whisper;
+            cfg.model_path = path.clone();
+            (format!("{} ({})", name, path.display()), cfg)
+        })
+        .collect();
+    models.sort_by(|a, b| a.0.cmp(&b.0));
+    models
+}
+
+fn load(folder: &Path) -> Result<Vec<Clip>, BenchSttError> {
+    let entries = fs::read_dir(folder).map_err(|err| BenchSttError::Io(folder.into(), err))?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("wav"))
+        })
+        .collect();
+    paths.sort();
+    let mut clips = Vec::new();
+    for path in paths {
+        let transcript = path.with_extension("txt");
+        let reference = match fs::read_to_string(&transcript) {
+            Ok(reference) => reference,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                eprintln!("Skipping {}: no {}", path.display(), transcript.display());
+                continue;
+            }
+            Err(err) => return Err(BenchSttError::Io(transcript, err)),
+        };
+        let audio = match audio::load_clip(&path) {
+            Ok(audio) => audio,
+            Err(err) => {
+                eprintln!("Skipping {}: {}", path.display(), err);
+                continue;
+            }
+        };
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        clips.push(Clip {
+            name,
+            audio,
+            reference: reference.trim().to_string(),
+        });
+    }
+    if clips.is_empty() {
+        return Err(BenchSttError::Empty(folder.into()));
+    }
+    Ok(clips)
+}
+
+fn bench(transcriber: &Transcriber, clips: &[Clip], model: String) -> Report {
+    let mut report = Report {
+        model,
+        errors: 0,
+        words: 0,
+        elapsed: Duration::ZERO,
+        clips: 0,
+        audio_secs: 0.0,
+    };
+    let width = clips.iter().map(|clip| clip.name.len()).max().unwrap_or(0).max("total".len());
+    println!("  {:<width$}  {:>6}  {:>6}", "file", "WER", "time", width = width);
+    for clip in clips {
+        let start = Instant::now();
+        let heard = match transcriber.transcribe(&clip.audio) {
+            Ok(heard) => heard,
+            Err(err) => {
+                println!("  {:<width$}  failed: {}", clip.name, err, width = width);
+                continue;
+            }
+        };
+        let elapsed = start.elapsed();
+        let (errors, words) = transcription::word_errors(&clip.reference, &heard);
+        println!(
+            "  {:<width$}  {:>6}  {:>5.2}s  \"{}\"",
+            clip.name,
+            rate(errors, words),
+            elapsed.as_secs_f64(),
+            heard,
+            width = width
+        );
+        report.errors += errors;
+        report.words += words;
+        report.elapsed += elapsed;
+        report.clips += 1;
+        report.audio_secs += clip.audio.len() as f64 / SAMPLE_RATE;
+    }
+    println!(
+        "  {:<width$}  {:>6}  {:>5.2}s",
+        "total",
+        rate(report.errors, report.words),
+        report.elapsed.as_secs_f64(),
+        width = width
+    );
+    report
+}
+
+/// The models side by side. The real-time factor is the transcription time
+/// over the length of the audio; below 1 is faster than it was spoken.
+fn print_comparison(reports: &[Report]) {
+    let width = reports.iter().map(|report| report.model.len()).max().unwrap_or(0).max(5);
+    println!();
+    println!(
+        "{:<width$}  {:>6}  {:>8}  {:>5}",
+        "model", "WER", "per clip", "RTF",
+        width = width
+    );
+    for report in reports {
+        let elapsed = report.elapsed.as_secs_f64();
+        println!(
+            "{:<width$}  {:>6}  {:>7.2}s  {:>5.2}",
+            report.model,
+            rate(report.errors, report.words),
+            elapsed / report.clips.max(1) as f64,
+            elapsed / report.audio_secs.max(f64::EPSILON),
+            width = width
+        );
+    }
+}
+
+fn rate(errors: usize, words: usize) -> String {
+    if words == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.1}%", errors as f64 * 100.0 / words as f64)
+    }
+}
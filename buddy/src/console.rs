@@ -0,0 +1,60 @@
+use crate::ipc::{ControlCommand, ControlRequest};
+use buddy_core::audit::Source;
+use std::{io::BufRead, thread};
+use tokio::sync::mpsc::UnboundedSender;
+
+const HELP: &str =
+    "Console commands: :trigger, :text <phrase>, :reload, :stats, :metrics, :history, :quit";
+
+/// Reads developer commands (`:trigger`, `:text open chrome`, ...) from stdin on
+/// a background thread and forwards them to the main loop like IPC requests.
+/// Stops quietly when stdin closes, e.g. when started without a terminal.
+pub fn spawn(tx: UnboundedSender<ControlRequest>) {
+    thread::spawn(move || {
+        let stdin = std::io::stdin();
+        for line in stdin.lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            let command = match parse(&line) {
+                Ok(Some(command)) => command,
+                Ok(None) => continue,
+                Err(message) => {
+                    eprintln!("{}", message);
+                    continue;
+                }
+            };
+            let (request, reply) = ControlRequest::new(command, Source::Console);
+            if tx.send(request).is_err() {
+                break;
+            }
+            if let Ok(reply) = reply.blocking_recv() {
+                println!("{}", reply);
+            }
+        }
+    });
+}
+
+fn parse(line: &str) -> Result<Option<ControlCommand>, String> {
+    let line = line.trim();
+    if line.is_empty() {
+        // Off Windows the hotkey may not be grabbable, so Enter stands in for it.
+        return Ok(cfg!(not(windows)).then_some(ControlCommand::Trigger));
+    }
+    let Some(command) = line.strip_prefix(':') else {
+        return Err(format!("Unrecognized input '{}'. {}", line, HELP));
+    };
+    let (name, rest) = command.split_once(' ').unwrap_or((command, ""));
+    let name = match name {
+        "reload" => "reload-config",
+        "stats" => "status",
+        "help" | "?" => {
+            println!("{}", HELP);
+            return Ok(None);
+        }
+        other => other,
+    };
+    ControlCommand::parse(&format!("{} {}", name, rest))
+        .map(Some)
+        .ok_or_else(|| format!("Unknown command '{}'. {}", line, HELP))
+}
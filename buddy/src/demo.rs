@@ -0,0 +1,83 @@
+use buddy_core::{
+    audio, config::Config, feedback::FeedbackPlayer, pipeline, rules, transcription::Transcriber,
+};
+use std::path::Path;
+
+/// Where `scripts/make_demo_clips.ps1` writes its recordings, relative to the
+/// config file.
+const CLIP_DIR: &str = "resources/demo";
+
+/// Sample utterances: the clip that says it, and the text used when the clip
+/// (or the Whisper model) is missing.
+const SAMPLES: &[(&str, &str)] = &[
+    ("open_details.wav", "Open details"),
+    ("launch_chrome.wav", "Launch chrome"),
+    ("todo_line.wav", "Open my todo at line 40"),
+    ("set_volume.wav", "Set volume to 25"),
+    ("mute.wav", "Mute the audio"),
+    ("lock.wav", "Lock the computer"),
+];
+
+/// `--demo`: walks the sample utterances through transcription and the rules
+/// parser and says what Buddy would do, without a microphone, the intent
+/// model, or touching the system.
+pub fn run(config: &Config, config_path: &Path, debug: bool, suppress_native_logs: bool) {
+    let clips = config_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(CLIP_DIR);
+    let transcriber = if clips.is_dir() {
+        match Transcriber::new(
+            &config.transcription,
+            pipeline::build_transcription_prompt(config),
+            debug,
+            suppress_native_logs,
+        ) {
+            Ok(transcriber) => {
+                transcriber.set_command_vocabulary(pipeline::command_vocabulary(config));
+                Some(transcriber)
+            }
+            Err(err) => {
+                eprintln!("Demo clips found but Whisper is unavailable ({}); using text", err);
+                None
+            }
+        }
+    } else {
+        println!(
+            "No clips in {}; using text (scripts/make_demo_clips.ps1 records them)",
+            clips.display()
+        );
+        None
+    };
+    let mut feedback = FeedbackPlayer::new(&config.feedback, &config.phrases);
+    println!("Buddy demo: nothing below is actually executed.\n");
+    for (clip, text) in SAMPLES {
+        let heard = transcriber
+            .as_ref()
+            .and_then(|transcriber| transcribe_clip(transcriber, &clips.join(clip)))
+            .unwrap_or_else(|| text.to_string());
+        println!("You:   {}", heard);
+        let reply = match rules::infer_intent(&heard, config) {
+            Some(intent) => format!("I would {}", pipeline::describe_intent(&intent)),
+            None => "I didn't catch that; the intent model handles free-form requests".to_string(),
+        };
+        println!("Buddy: {}\n", reply);
+        feedback.say(&reply);
+    }
+    println!("Edit config.toml to map your own files and apps, then run Buddy without --demo.");
+}
+
+fn transcribe_clip(transcriber: &Transcriber, path: &Path) -> Option<String> {
+    if !path.exists() {
+        return None;
+    }
+    let audio = audio::load_clip(path)
+        .map_err(|err| eprintln!("{}: {}", path.display(), err))
+        .ok()?;
+    transcriber
+        .transcribe_command(&audio)
+        .map(|transcript| transcript.text)
+        .map_err(|err| eprintln!("{}: {}", path.display(), err))
+        .ok()
+        .filter(|text| !text.is_empty())
+}
@@ -0,0 +1,164 @@
+use crate::{colorize, Color};
+use buddy_core::{
+    audio::{self, AudioCapturer},
+    config::{Config, FeedbackMode, SttEngine, TtsEngine},
+    hotkey::{self, HotkeyListener, Interrupts},
+    intent::IntentClient,
+    transcription::Transcriber,
+    voice::Voice,
+};
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// Longest recording taken for the level check.
+const LEVEL_CHECK: Duration = Duration::from_secs(4);
+/// Clip transcribed when no `--doctor <clip>` is given: silence, which still
+/// shows how long a pass through the model takes.
+const SILENT_CLIP_SAMPLES: usize = 3 * 16_000;
+/// A peak below this (about 1% of full scale) means the microphone picked
+/// nothing up.
+const SILENT_PEAK: i16 = 330;
+
+/// `--doctor [clip]`: tries each part of Buddy on its own and prints a
+/// pass/fail table, so a broken setup shows which part to fix.
+pub async fn run(config: &Config, clip: Option<&Path>, debug: bool, suppress_native_logs: bool) {
+    let mut checks = Vec::new();
+    checks.push(("Microphone", microphone(config, debug)));
+    let stt = match config.transcription.engine {
+        SttEngine::Whisper => "Whisper",
+        SttEngine::Windows => "Windows speech",
+        SttEngine::Cloud => "Cloud speech",
+    };
+    checks.push((stt, transcription(config, clip, debug, suppress_native_logs)));
+    let client = IntentClient::new(config);
+    let service = client
+        .wait_for_ready()
+        .await
+        .map(|()| config.deepseek.endpoint.clone())
+        .map_err(|err| err.to_string());
+    let model = match &service {
+        Ok(_) => match client.has_model().await {
+            Ok(true) => Ok(client.model().to_string()),
+            Ok(false) => Err(format!(
+                "{} is not pulled (ollama pull {0}, or set deepseek.auto_pull)",
+                client.model()
+            )),
+            Err(err) => Err(err.to_string()),
+        },
+        Err(_) => Err("service unreachable".to_string()),
+    };
+    checks.push(("Intent service", service));
+    checks.push(("Intent model", model));
+    checks.push(("Text to speech", speech(config)));
+    checks.push(("Hotkey", hotkey(config)));
+    if let Some(keys) = sendkeys(config) {
+        checks.push(("Key presses", keys));
+    }
+
+    println!();
+    let failed = checks.iter().filter(|(_, result)| result.is_err()).count();
+    for (name, result) in &checks {
+        let (status, detail) = match result {
+            Ok(detail) => (colorize("PASS", Color::Green), detail),
+            Err(detail) => (colorize("FAIL", Color::Red), detail),
+        };
+        println!("{:<16} {}  {}", name, status, detail);
+    }
+    println!();
+    if failed == 0 {
+        println!("{}", colorize("All checks passed", Color::Green));
+    } else {
+        let summary = format!("{} of {} checks failed", failed, checks.len());
+        println!("{}", colorize(&summary, Color::Red));
+    }
+}
+
+fn microphone(config: &Config, debug: bool) -> Result<String, String> {
+    let capturer = AudioCapturer::new(&config.audio, debug).map_err(|err| err.to_string())?;
+    println!("Say something for the microphone check...");
+    let audio = capturer
+        .capture(Some(LEVEL_CHECK))
+        .map_err(|err| err.to_string())?;
+    if audio.is_empty() {
+        return Err("recorded nothing".to_string());
+    }
+    let (peak, rms) = audio::peak_rms(&audio);
+    let levels = format!(
+        "peak {:.1}%, rms {:.1}%",
+        peak as f64 / i16::MAX as f64 * 100.0,
+        rms / i16::MAX as f64 * 100.0
+    );
+    if peak < SILENT_PEAK {
+        return Err(format!("{}: no sound picked up", levels));
+    }
+    Ok(levels)
+}
+
+fn transcription(
+    config: &Config,
+    clip: Option<&Path>,
+    debug: bool,
+    suppress_native_logs: bool,
+) -> Result<String, String> {
+    let audio = match clip {
+        Some(path) => audio::load_clip(path).map_err(|err| err.to_string())?,
+        None => vec![0; SILENT_CLIP_SAMPLES],
+    };
+    let start = Instant::now();
+    let transcriber = Transcriber::new(&config.transcription, None, debug, suppress_native_logs)
+        .map_err(|err| err.to_string())?;
+    let loaded = start.elapsed();
+    let start = Instant::now();
+    let text = transcriber
+        .transcribe(&audio)
+        .map_err(|err| err.to_string())?;
+    let detail = format!(
+        "loaded in {:.1}s, {:.1}s clip in {:.2}s",
+        loaded.as_secs_f64(),
+        audio.len() as f64 / 16_000.0,
+        start.elapsed().as_secs_f64()
+    );
+    Ok(match clip {
+        Some(_) => format!("{}: \"{}\"", detail, text.trim()),
+        None => detail,
+    })
+}
+
+fn speech(config: &Config) -> Result<String, String> {
+    if matches!(config.feedback.mode, FeedbackMode::Sound) {
+        return Ok("not used (feedback.mode = \"sound\")".to_string());
+    }
+    let engine = match config.feedback.tts_engine {
+        TtsEngine::System => "system voice",
+        TtsEngine::Piper => "Piper voice",
+    };
+    Voice::new(&config.feedback).map(|_| format!("{} ready", engine))
+}
+
+/// Parses every `[sendkeys]` entry, including those under `[contexts]`;
+/// `None` when there are none.
+fn sendkeys(config: &Config) -> Option<Result<String, String>> {
+    let entries: Vec<(&String, &String)> = config
+        .sendkeys
+        .iter()
+        .chain(config.contexts.values().flat_map(|context| &context.sendkeys))
+        .collect();
+    if entries.is_empty() {
+        return None;
+    }
+    for (name, keys) in &entries {
+        if let Err(err) = hotkey::parse_sequence(keys) {
+            return Some(Err(format!("{} = \"{}\": {}", name, keys, err)));
+        }
+    }
+    Some(Ok(format!("{} key presses parse", entries.len())))
+}
+
+fn hotkey(config: &Config) -> Result<String, String> {
+    let listener = HotkeyListener::new(&config.hotkey, Interrupts::default())
+        .map_err(|err| err.to_string())?;
+    drop(listener);
+    Ok(format!("{} registered and released", config.hotkey.key))
+}
@@ -0,0 +1,168 @@
+use crate::ipc::{ControlCommand, ControlRequest};
+use buddy_core::{audit::Source, config::HttpConfig};
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use std::{fmt, net::SocketAddr, sync::Arc};
+use subtle::ConstantTimeEq;
+use tokio::{net::TcpListener, sync::mpsc::UnboundedSender};
+
+struct ServerState {
+    token: String,
+    tx: UnboundedSender<ControlRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TextBody {
+    text: String,
+}
+
+/// Starts the REST API on `config.bind`. Requests are forwarded to `tx` and
+/// serviced by the main loop exactly like IPC control commands.
+pub async fn spawn_server(
+    config: &HttpConfig,
+    tx: UnboundedSender<ControlRequest>,
+) -> Result<SocketAddr, HttpError> {
+    let token = config
+        .token
+        .as_deref()
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .ok_or(HttpError::MissingToken)?
+        .to_string();
+    let listener = TcpListener::bind(&config.bind)
+        .await
+        .map_err(|err| HttpError::Bind(config.bind.clone(), err))?;
+    let addr = listener
+        .local_addr()
+        .map_err(|err| HttpError::Bind(config.bind.clone(), err))?;
+    let state = Arc::new(ServerState { token, tx });
+    let mut app = Router::new()
+        .route("/listen", post(listen))
+        .route("/say", post(say))
+        .route("/intent", post(intent))
+        .route("/history", get(history));
+    if config.metrics {
+        app = app.route("/metrics", get(metrics));
+    }
+    let app = app.with_state(state);
+    tokio::spawn(async move {
+        if let Err(err) = axum::serve(listener, app).await {
+            eprintln!("HTTP server stopped: {}", err);
+        }
+    });
+    Ok(addr)
+}
+
+async fn listen(State(state): State<Arc<ServerState>>, headers: HeaderMap) -> Response {
+    dispatch(&state, &headers, ControlCommand::Trigger, Reply::Status).await
+}
+
+async fn say(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Json(body): Json<TextBody>,
+) -> Response {
+    dispatch(&state, &headers, ControlCommand::Say(body.text), Reply::Status).await
+}
+
+async fn intent(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Json(body): Json<TextBody>,
+) -> Response {
+    dispatch(&state, &headers, ControlCommand::Intent(body.text), Reply::Json).await
+}
+
+async fn history(State(state): State<Arc<ServerState>>, headers: HeaderMap) -> Response {
+    dispatch(&state, &headers, ControlCommand::History, Reply::Json).await
+}
+
+async fn metrics(State(state): State<Arc<ServerState>>, headers: HeaderMap) -> Response {
+    dispatch(&state, &headers, ControlCommand::Metrics, Reply::Text).await
+}
+
+/// How the main loop's reply is sent back.
+enum Reply {
+    /// Wrapped as `{"status": ...}`.
+    Status,
+    /// Already a JSON document.
+    Json,
+    /// Plain text, such as the Prometheus exposition format.
+    Text,
+}
+
+/// Checks the token, hands the command to the main loop and turns its reply
+/// into a response.
+async fn dispatch(
+    state: &ServerState,
+    headers: &HeaderMap,
+    command: ControlCommand,
+    kind: Reply,
+) -> Response {
+    if !authorized(headers, &state.token) {
+        return error(StatusCode::UNAUTHORIZED, "missing or invalid token");
+    }
+    let (request, reply) = ControlRequest::new(command, Source::Http);
+    if state.tx.send(request).is_err() {
+        return error(StatusCode::SERVICE_UNAVAILABLE, "buddy is shutting down");
+    }
+    let Ok(reply) = reply.await else {
+        return error(StatusCode::INTERNAL_SERVER_ERROR, "no reply");
+    };
+    if let Some(message) = reply.strip_prefix("error: ") {
+        return error(StatusCode::BAD_GATEWAY, message);
+    }
+    match kind {
+        Reply::Status => Json(serde_json::json!({ "status": reply })).into_response(),
+        Reply::Json => match serde_json::from_str::<serde_json::Value>(&reply) {
+            Ok(value) => Json(value).into_response(),
+            Err(_) => error(StatusCode::INTERNAL_SERVER_ERROR, &reply),
+        },
+        Reply::Text => {
+            ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], reply).into_response()
+        }
+    }
+}
+
+/// Compares the bearer token in constant time, so response timing gives
+/// away nothing about how much of a guess was right.
+fn authorized(headers: &HeaderMap, token: &str) -> bool {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.as_bytes().strip_prefix(b"Bearer "))
+        .is_some_and(|given| bool::from(given.ct_eq(token.as_bytes())))
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+#[derive(Debug)]
+pub enum HttpError {
+    MissingToken,
+    Bind(String, std::io::Error),
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingToken => write!(f, "[http] enabled but no token is configured"),
+            Self::Bind(addr, err) => write!(f, "failed to listen on {}: {}", addr, err),
+        }
+    }
+}
+
+impl std::error::Error for HttpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::MissingToken => None,
+            Self::Bind(_, err) => Some(err),
+        }
+    }
+}
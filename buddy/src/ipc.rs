@@ -0,0 +1,285 @@
+use buddy_core::audit::Source;
+use std::{fmt, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    sync::{mpsc::UnboundedSender, oneshot},
+};
+
+#[derive(Debug, Clone)]
+pub enum ControlCommand {
+    Trigger,
+    Status,
+    ReloadConfig,
+    TestIntent(String),
+    /// Classify text and reply with the intent as JSON, without executing it.
+    Intent(String),
+    Say(String),
+    History,
+    /// Session statistics in the Prometheus text format.
+    Metrics,
+    /// Run text through the pipeline as if it had been spoken.
+    Text(String),
+    Quit,
+}
+
+/// How long a running instance gets to answer "status" before it is taken
+/// to be busy with a command.
+const STATUS_TIMEOUT: Duration = Duration::from_secs(2);
+
+impl ControlCommand {
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        let (name, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match name {
+            "trigger" => Some(Self::Trigger),
+            "status" => Some(Self::Status),
+            "reload-config" => Some(Self::ReloadConfig),
+            "test-intent" if !rest.trim().is_empty() => {
+                Some(Self::TestIntent(rest.trim().to_string()))
+            }
+            "intent" if !rest.trim().is_empty() => Some(Self::Intent(rest.trim().to_string())),
+            "say" if !rest.trim().is_empty() => Some(Self::Say(rest.trim().to_string())),
+            "history" => Some(Self::History),
+            "metrics" => Some(Self::Metrics),
+            "text" if !rest.trim().is_empty() => Some(Self::Text(rest.trim().to_string())),
+            "quit" => Some(Self::Quit),
+            _ => None,
+        }
+    }
+
+    /// What an instance started without `--daemon` still answers: enough
+    /// for a second one to see it and take over.
+    fn without_daemon(&self) -> bool {
+        matches!(self, Self::Status | Self::Quit)
+    }
+}
+
+/// A command received over IPC; the main loop answers through `reply`.
+pub struct ControlRequest {
+    pub command: ControlCommand,
+    pub source: Source,
+    reply: oneshot::Sender<String>,
+}
+
+impl ControlRequest {
+    pub fn new(command: ControlCommand, source: Source) -> (Self, oneshot::Receiver<String>) {
+        let (reply, rx) = oneshot::channel();
+        (
+            Self {
+                command,
+                source,
+                reply,
+            },
+            rx,
+        )
+    }
+
+    pub fn respond(self, message: impl Into<String>) {
+        let _ = self.reply.send(message.into());
+    }
+}
+
+/// Starts the control server on the platform endpoint; requests are forwarded
+/// to `tx` for the main loop to service. Without `daemon` only "status" and
+/// "quit" are taken. Holding the endpoint is also what marks this as the
+/// running instance.
+pub fn spawn_server(tx: UnboundedSender<ControlRequest>, daemon: bool) -> Result<(), IpcError> {
+    platform::spawn_server(tx, daemon)
+}
+
+/// The reply to "status" from a Buddy that is already running, or `None`
+/// when none is. One busy with a command may not answer in time, and is
+/// then reported as busy.
+pub async fn running_instance() -> Option<String> {
+    if !platform::is_listening().await {
+        return None;
+    }
+    let status = tokio::time::timeout(STATUS_TIMEOUT, send_command("status")).await;
+    Some(match status {
+        Ok(Ok(reply)) => reply,
+        _ => "busy".to_string(),
+    })
+}
+
+/// Sends one command line to a running daemon and returns its reply.
+pub async fn send_command(line: &str) -> Result<String, IpcError> {
+    let mut stream = platform::connect().await?;
+    stream
+        .write_all(format!("{}\n", line.trim()).as_bytes())
+        .await
+        .map_err(IpcError::Io)?;
+    let mut reply = String::new();
+    BufReader::new(stream)
+        .read_line(&mut reply)
+        .await
+        .map_err(IpcError::Io)?;
+    Ok(reply.trim_end().replace("\\n", "\n"))
+}
+
+pub fn endpoint() -> String {
+    platform::endpoint()
+}
+
+async fn serve_connection<S>(stream: S, tx: UnboundedSender<ControlRequest>, daemon: bool)
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    if reader.read_line(&mut line).await.is_err() {
+        return;
+    }
+    let reply = match ControlCommand::parse(&line) {
+        Some(command) if !daemon && !command.without_daemon() => {
+            format!("error: start buddy with --daemon to use '{}'", line.trim())
+        }
+        Some(command) => {
+            let (request, reply_rx) = ControlRequest::new(command, Source::Ipc);
+            if tx.send(request).is_err() {
+                "error: buddy is shutting down".to_string()
+            } else {
+                reply_rx
+                    .await
+                    .unwrap_or_else(|_| "error: no reply".to_string())
+            }
+        }
+        None => format!("error: unknown command '{}'", line.trim()),
+    };
+    // Replies travel as a single line; multi-line text is escaped.
+    let reply = format!("{}\n", reply.replace('\n', "\\n"));
+    let mut stream = reader.into_inner();
+    let _ = stream.write_all(reply.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::*;
+    use tokio::net::windows::named_pipe::{ClientOptions, NamedPipeClient, ServerOptions};
+    use windows::Win32::Foundation::ERROR_PIPE_BUSY;
+
+    pub fn endpoint() -> String {
+        r"\\.\pipe\buddy".to_string()
+    }
+
+    pub fn spawn_server(tx: UnboundedSender<ControlRequest>, daemon: bool) -> Result<(), IpcError> {
+        let name = endpoint();
+        let mut server = ServerOptions::new()
+            .first_pipe_instance(true)
+            .create(&name)
+            .map_err(IpcError::Bind)?;
+        tokio::spawn(async move {
+            loop {
+                let connected = server.connect().await;
+                let previous = server;
+                server = match ServerOptions::new().create(&name) {
+                    Ok(next) => next,
+                    Err(err) => {
+                        eprintln!("IPC server stopped: {}", err);
+                        return;
+                    }
+                };
+                match connected {
+                    Ok(()) => {
+                        tokio::spawn(serve_connection(previous, tx.clone(), daemon));
+                    }
+                    Err(err) => eprintln!("IPC connect failed: {}", err),
+                }
+            }
+        });
+        Ok(())
+    }
+
+    pub async fn connect() -> Result<NamedPipeClient, IpcError> {
+        ClientOptions::new()
+            .open(endpoint())
+            .map_err(IpcError::Connect)
+    }
+
+    /// A pipe whose instances are all busy still belongs to a running Buddy.
+    pub async fn is_listening() -> bool {
+        match ClientOptions::new().open(endpoint()) {
+            Ok(_) => true,
+            Err(err) => err.raw_os_error() == Some(ERROR_PIPE_BUSY.0 as i32),
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use super::*;
+    use tokio::net::{UnixListener, UnixStream};
+
+    pub fn endpoint() -> String {
+        let dir = std::env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string());
+        let user = std::env::var("USER").unwrap_or_else(|_| "user".to_string());
+        format!("{}/buddy-{}.sock", dir, user)
+    }
+
+    pub fn spawn_server(tx: UnboundedSender<ControlRequest>, daemon: bool) -> Result<(), IpcError> {
+        let path = endpoint();
+        if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+            return Err(IpcError::AlreadyRunning("running".to_string()));
+        }
+        // A socket file left by a crashed instance would make bind fail.
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).map_err(IpcError::Bind)?;
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        tokio::spawn(serve_connection(stream, tx.clone(), daemon));
+                    }
+                    Err(err) => eprintln!("IPC accept failed: {}", err),
+                }
+            }
+        });
+        Ok(())
+    }
+
+    pub async fn connect() -> Result<UnixStream, IpcError> {
+        UnixStream::connect(endpoint())
+            .await
+            .map_err(IpcError::Connect)
+    }
+
+    pub async fn is_listening() -> bool {
+        connect().await.is_ok()
+    }
+}
+
+#[derive(Debug)]
+pub enum IpcError {
+    Bind(std::io::Error),
+    Connect(std::io::Error),
+    Io(std::io::Error),
+    /// Another instance holds the control channel; its status reply.
+    AlreadyRunning(String),
+    /// `--takeover` asked the running instance to quit, but it didn't.
+    StillRunning,
+}
+
+impl fmt::Display for IpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bind(err) => write!(f, "failed to start control server: {}", err),
+            Self::Connect(err) => write!(f, "is buddy running with --daemon? {}", err),
+            Self::Io(err) => write!(f, "control connection error: {}", err),
+            Self::AlreadyRunning(status) => write!(
+                f,
+                "buddy is already running ({}); start with --takeover to replace it",
+                status
+            ),
+            Self::StillRunning => write!(f, "the running buddy did not quit"),
+        }
+    }
+}
+
+impl std::error::Error for IpcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Bind(err) | Self::Connect(err) | Self::Io(err) => Some(err),
+            Self::AlreadyRunning(_) | Self::StillRunning => None,
+        }
+    }
+}
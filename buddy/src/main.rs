@@ -1,42 +1,112 @@
-mod audio;
-mod config;
-mod executor;
-mod feedback;
-mod hotkey;
-mod intent;
-mod transcription;
-mod windows_api;
-
-use audio::AudioCapturer;
-use config::Config;
-use executor::{CommandExecutor, ExecutionResult};
-use feedback::FeedbackPlayer;
-use hotkey::{HotkeyError, HotkeyListener};
-use intent::{Intent, IntentClient, IntentError};
-use std::{path::Path, path::PathBuf, sync::Arc, time::Duration, time::Instant};
+mod app;
+mod autostart;
+mod bench_intent;
+mod bench_stt;
+mod console;
+mod demo;
+mod doctor;
+mod http;
+mod ipc;
+mod output;
+mod shutdown;
+
+use app::Handled;
+use autostart::AutostartError;
+use bench_intent::BenchError;
+use bench_stt::BenchSttError;
+use buddy_core::{
+    audio::{self, AudioCapturer},
+    audit::{self, AuditError, Source},
+    config::{self, Config, IntentFallback, SttEngine},
+    executor::CommandExecutor,
+    feedback::FeedbackPlayer,
+    hotkey::HotkeyError,
+    intent::{Intent, IntentClient, IntentError, Spoken},
+    longform,
+    pipeline::{
+        classify, describe_intent, needs_clarification, needs_confirmation, wait_for_intent_ready,
+        PipelineError,
+    },
+    rag::{self, RagError},
+    transcription::{self, Transcriber},
+    Pipeline,
+};
+use http::HttpError;
+use ipc::IpcError;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use tokio::sync::oneshot;
 #[cfg(windows)]
 use windows::Win32::System::LibraryLoader::{GetModuleHandleW, LoadLibraryW};
 #[cfg(windows)]
 use windows::Win32::Foundation::HINSTANCE;
-use transcription::Transcriber;
+
+/// How long `--takeover` waits for the running instance to finish up.
+const TAKEOVER_TIMEOUT: Duration = Duration::from_secs(15);
 
 #[tokio::main]
 async fn main() {
     if let Err(err) = run().await {
+        output::error("fatal", &err);
         eprintln!("Buddy exited with error: {}", err);
+        std::process::exit(1);
     }
 }
 
 async fn run() -> Result<(), BuddyError> {
     let args: Vec<String> = std::env::args().skip(1).collect();
+    let invoked_as_ctl = std::env::args()
+        .next()
+        .map(|program| {
+            Path::new(&program)
+                .file_stem()
+                .is_some_and(|stem| stem.eq_ignore_ascii_case("buddyctl"))
+        })
+        .unwrap_or(false);
+    if invoked_as_ctl {
+        return run_ctl(&args).await;
+    }
+    if args.first().map(String::as_str) == Some("ctl") {
+        return run_ctl(&args[1..]).await;
+    }
     let mut test_phrases: Vec<String> = Vec::new();
     if args.iter().any(|arg| arg == "--list-audio") {
         audio::print_input_devices()?;
         return Ok(());
     }
+    if args.iter().any(|arg| arg == "--uninstall-autostart") {
+        if autostart::uninstall()? {
+            println!("Buddy will no longer start at login");
+        } else {
+            println!("Buddy was not set to start at login");
+        }
+        return Ok(());
+    }
     let mut config_path = None;
     let mut debug_override: Option<bool> = None;
     let mut whisper_log_override: Option<bool> = None;
+    let mut daemon = false;
+    let mut bench: Option<Option<PathBuf>> = None;
+    let mut doctor: Option<Option<PathBuf>> = None;
+    let mut transcribe: Option<Option<PathBuf>> = None;
+    let mut demo = false;
+    let mut verify_audit: Option<Option<PathBuf>> = None;
+    let mut from_wav: Option<PathBuf> = None;
+    let mut execute = false;
+    let mut dry_run = false;
+    let mut stats = false;
+    let mut index_documents = false;
+    let mut takeover = false;
+    let mut install_autostart = false;
+    let mut json_output = false;
+    let mut bench_intent: Option<PathBuf> = None;
+    let mut bench_stt: Option<PathBuf> = None;
+    let mut bench_runs = 1;
+    let mut bench_models: Vec<String> = Vec::new();
+    let mut device: Option<String> = None;
+    let mut profile: Option<String> = None;
     let mut index = 0;
     while index < args.len() {
         let arg = &args[index];
@@ -45,6 +115,123 @@ async fn run() -> Result<(), BuddyError> {
             "--no-debug" => debug_override = Some(false),
             "--whisper-log" => whisper_log_override = Some(true),
             "--no-whisper-log" => whisper_log_override = Some(false),
+            "--daemon" => daemon = true,
+            "--takeover" => takeover = true,
+            "--demo" => demo = true,
+            "--execute" => execute = true,
+            "--dry-run" | "--safe-mode" => dry_run = true,
+            "--stats" => stats = true,
+            "--index-documents" => index_documents = true,
+            "--install-autostart" => install_autostart = true,
+            "bench-intent" if index == 0 => {
+                let Some(dataset) = args.get(1).filter(|next| !next.starts_with("--")) else {
+                    eprintln!(
+                        "Usage: buddy bench-intent <dataset.jsonl> [config] [--runs N] \
+                         [--model NAME]..."
+                    );
+                    return Ok(());
+                };
+                bench_intent = Some(PathBuf::from(dataset));
+                index += 1;
+            }
+            "bench-stt" if index == 0 => {
+                let Some(folder) = args.get(1).filter(|next| !next.starts_with("--")) else {
+                    eprintln!("Usage: buddy bench-stt <folder> [config]");
+                    return Ok(());
+                };
+                bench_stt = Some(PathBuf::from(folder));
+                index += 1;
+            }
+            "--runs" => {
+                let Some(runs) = args.get(index + 1).and_then(|next| next.parse().ok()) else {
+                    eprintln!("--runs takes a number");
+                    return Ok(());
+                };
+                bench_runs = runs;
+                index += 1;
+            }
+            "--model" => {
+                let Some(model) = args.get(index + 1) else {
+                    eprintln!("Missing value for --model");
+                    return Ok(());
+                };
+                bench_models.push(model.clone());
+                index += 1;
+            }
+            "--output" => {
+                json_output = match args.get(index + 1).map(String::as_str) {
+                    Some("json") => true,
+                    Some("text") => false,
+                    _ => {
+                        eprintln!("--output takes json or text");
+                        return Ok(());
+                    }
+                };
+                index += 1;
+            }
+            "--working-dir" => {
+                let Some(dir) = args.get(index + 1) else {
+                    eprintln!("Missing value for --working-dir");
+                    return Ok(());
+                };
+                // Before the config is read, so its relative paths resolve from here.
+                if let Err(err) = std::env::set_current_dir(dir) {
+                    eprintln!("Cannot change to '{}': {}", dir, err);
+                }
+                index += 1;
+            }
+            "--device" => {
+                let Some(name) = args.get(index + 1) else {
+                    eprintln!("Missing value for --device");
+                    return Ok(());
+                };
+                device = Some(name.clone());
+                index += 1;
+            }
+            "--profile" => {
+                let Some(name) = args.get(index + 1) else {
+                    eprintln!("Missing value for --profile");
+                    return Ok(());
+                };
+                profile = Some(name.clone());
+                index += 1;
+            }
+            "--from-wav" => {
+                let Some(path) = args.get(index + 1) else {
+                    eprintln!("Missing value for --from-wav");
+                    return Ok(());
+                };
+                from_wav = Some(PathBuf::from(path));
+                index += 1;
+            }
+            "--verify-audit" => {
+                let log = args.get(index + 1).filter(|next| !next.starts_with("--"));
+                if log.is_some() {
+                    index += 1;
+                }
+                verify_audit = Some(log.map(PathBuf::from));
+            }
+            "--transcribe" => {
+                let file = args.get(index + 1).filter(|next| !next.starts_with("--"));
+                if file.is_some() {
+                    index += 1;
+                }
+                transcribe = Some(file.map(PathBuf::from));
+            }
+            "--bench-transcription" => {
+                let clip = args.get(index + 1).filter(|next| !next.starts_with("--"));
+                if clip.is_some() {
+                    index += 1;
+                }
+                bench = Some(clip.map(PathBuf::from));
+            }
+            "--doctor" => {
+                let clip = args.get(index + 1).filter(|next| !next.starts_with("--"));
+                if clip.is_some() {
+                    index += 1;
+                }
+                doctor = Some(clip.map(PathBuf::from));
+            }
             "--test-intent" => {
                 let next = args.get(index + 1);
                 if let Some(phrase) = next {
@@ -60,8 +247,15 @@ async fn run() -> Result<(), BuddyError> {
         }
         index += 1;
     }
+    if json_output {
+        if let Err(err) = output::use_json() {
+            eprintln!("Cannot switch to JSON output: {}", err);
+            return Ok(());
+        }
+    }
     let config_path = config_path.unwrap_or_else(|| "config.toml".into());
-    let config = match Config::load(&config_path) {
+    let mut loaded_path = PathBuf::from(&config_path);
+    let mut config = match Config::load(&config_path, profile.as_deref()) {
         Ok(cfg) => cfg,
         Err(err) => {
             eprintln!(
@@ -72,15 +266,26 @@ async fn run() -> Result<(), BuddyError> {
                 .parent()
                 .map(|dir| dir.join("config.default.toml"))
                 .unwrap_or_else(|| PathBuf::from("config.default.toml"));
-            match Config::load(&fallback_path) {
+            match Config::load(&fallback_path, profile.as_deref()) {
                 Ok(cfg) => {
                     println!("Loaded default config from '{}'", fallback_path.display());
+                    loaded_path = fallback_path;
                     cfg
                 }
                 Err(fallback_err) => return Err(BuddyError::Config(fallback_err)),
             }
         }
     };
+    config.policy.dry_run |= dry_run;
+    if let Some(name) = &profile {
+        println!("Using profile '{}'", name);
+    }
+    if device.is_some() {
+        config.audio.device_name = device;
+    }
+    if config.policy.dry_run {
+        println!("{}", colorize("Dry run: actions are reported, not run", Color::Yellow));
+    }
     let debug = debug_override.unwrap_or(config.logging.debug);
     let whisper_log = whisper_log_override.unwrap_or(config.logging.whisper_log);
     if !whisper_log {
@@ -90,7 +295,7 @@ async fn run() -> Result<(), BuddyError> {
     }
     if debug {
         println!("Loaded config from '{}'", config_path);
-        if let Some(path) = config.files.get("resume") {
+        if let Some(path) = config.files.get("resume").map(|entry| entry.path()) {
             println!("Config mapping: resume -> {}", path.display());
             if !path.exists() {
                 eprintln!("Warning: resume path does not exist");
@@ -121,120 +326,240 @@ async fn run() -> Result<(), BuddyError> {
         }
     }
 
+    if install_autostart {
+        let launch = autostart::Launch::current(&loaded_path, profile.as_deref(), daemon)?;
+        let entry = autostart::install(&launch)?;
+        println!("Buddy will start at login ({})", entry);
+        println!("Relative paths in the config resolve from {}", launch.working_dir.display());
+        let model = launch.working_dir.join(&config.transcription.model_path);
+        if config.transcription.engine == SttEngine::Whisper && !model.exists() {
+            eprintln!("Warning: the Whisper model {} does not exist", model.display());
+        }
+        return Ok(());
+    }
+    if demo {
+        demo::run(&config, &loaded_path, debug, !whisper_log);
+        return Ok(());
+    }
+    if let Some(log) = verify_audit {
+        let Some(log) = log.or_else(|| config.logging.audit_log.clone()) else {
+            eprintln!("No audit log given and logging.audit_log is not set");
+            return Ok(());
+        };
+        let key = audit::load_key(&audit::key_path(&config.logging, &log))?;
+        let entries = audit::verify(&log, &key)?;
+        println!("Audit log {} verified: {} entries", log.display(), entries);
+        return Ok(());
+    }
+    if let Some(output) = transcribe {
+        longform::run(&config, output, debug, !whisper_log);
+        return Ok(());
+    }
+    if let Some(clip) = bench {
+        return bench_transcription(&config, clip.as_deref(), debug, !whisper_log);
+    }
+    if let Some(clip) = doctor {
+        doctor::run(&config, clip.as_deref(), debug, !whisper_log).await;
+        return Ok(());
+    }
+    if let Some(dataset) = bench_intent {
+        let options = bench_intent::Options {
+            dataset,
+            runs: bench_runs,
+            models: bench_models,
+        };
+        bench_intent::run(&config, &options).await?;
+        return Ok(());
+    }
+    if let Some(folder) = bench_stt {
+        bench_stt::run(&config, &folder, debug, !whisper_log)?;
+        return Ok(());
+    }
+
     let intent_client = IntentClient::new(&config);
-    wait_for_intent_ready(&intent_client).await?;
+    if index_documents {
+        let update = rag::update(&config, &intent_client).await?;
+        println!("Document index updated: {}", update);
+        return Ok(());
+    }
+    let ready_timeout = Duration::from_secs(config.deepseek.ready_timeout_secs);
+    let mut warmup = None;
+    let interactive = test_phrases.is_empty() && from_wav.is_none();
+    if interactive {
+        claim_instance(takeover).await?;
+    }
+    if config.deepseek.start_degraded && interactive {
+        println!("Starting with rule-based intents while the intent model warms up");
+        intent_client.set_warming_up(true);
+        let client = intent_client.clone();
+        let preload_config = config.clone();
+        let (tx, rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let result = wait_for_intent_ready(&client, ready_timeout, false).await;
+            if result.is_ok() {
+                client.preload_models(&preload_config).await;
+            }
+            client.set_warming_up(false);
+            let _ = tx.send(result);
+        });
+        warmup = Some(rx);
+    } else if !interactive {
+        // The app waits for the intent service while it loads the rest.
+        let ready = wait_for_intent_ready(&intent_client, ready_timeout, true).await;
+        if let Err(err) = ready {
+            intent_unavailable(&config, err)?;
+        }
+    }
     if !test_phrases.is_empty() {
         for phrase in test_phrases {
             println!("Input: {}", phrase);
-            match intent_client.infer_intent(&phrase, &config).await {
-                Ok(intent) => {
+            let spoken = Spoken::default();
+            match classify(&intent_client, &phrase, spoken, &config, debug, &mut |_| {}).await {
+                Ok((intent, source)) => {
+                    output::intent(&intent, source);
                     println!(
                         "Output: action={:?} confidence={:.2}",
                         intent.action(),
                         intent.confidence()
                     );
                 }
-                Err(err) => eprintln!("Intent error: {}", err),
+                Err(err) => {
+                    eprintln!("Intent error: {}", err);
+                    output::error("intent", &err);
+                }
             }
         }
         return Ok(());
     }
-
-    let capturer = Arc::new(AudioCapturer::new(&config.audio, debug)?);
-    let initial_prompt = build_transcription_prompt(&config);
-    let transcriber = Arc::new(Transcriber::new(
-        &config.transcription,
-        initial_prompt,
-        debug,
-        !whisper_log,
-    )?);
-    if debug {
-        println!("Whisper system info: {}", whisper_rs::print_system_info());
+    if let Some(path) = from_wav {
+        return replay_wav(&config, &path, execute, debug, !whisper_log).await;
     }
-    let executor = CommandExecutor::new(&config);
-    let mut feedback = FeedbackPlayer::new(&config.feedback);
-    let mut hotkey = HotkeyListener::new(&config.hotkey)?;
+    let options = app::Options {
+        config_path: loaded_path,
+        profile,
+        dry_run,
+        stats,
+        debug,
+        whisper_log,
+        daemon,
+    };
+    app::run(config, options, intent_client, warmup).await
+}
 
+/// `--bench-transcription [clip]`: transcribes one clip (or a fresh
+/// recording) on GPU and CPU and prints how long each took.
+fn bench_transcription(
+    config: &Config,
+    clip: Option<&Path>,
+    debug: bool,
+    suppress_native_logs: bool,
+) -> Result<(), BuddyError> {
+    let audio = match clip {
+        Some(path) => audio::load_clip(path)?,
+        None => {
+            println!("No clip given; say a sample command now...");
+            AudioCapturer::new(&config.audio, debug)?.capture(None)?
+        }
+    };
+    let engine = match config.transcription.engine {
+        SttEngine::Whisper => config.transcription.model_path.display().to_string(),
+        SttEngine::Windows => "Windows speech recognition".to_string(),
+        SttEngine::Cloud => "the [transcription.cloud] service".to_string(),
+    };
     println!(
-        "Buddy ready. Press '{}' to issue a voice command.",
-        config.hotkey.key
+        "Benchmarking {:.1}s of audio with {}",
+        audio.len() as f64 / 16_000.0,
+        engine
     );
-
-    loop {
-        if debug {
-            println!("Waiting for hotkey...");
-        }
-        hotkey.wait().await?;
-        if debug {
-            println!("Hotkey received");
-        }
-        let total_start = Instant::now();
-        println!("Recording audio...");
-        let capturer_clone = Arc::clone(&capturer);
-        let max_duration = if config.audio.capture_duration_secs == 0 {
-            None
-        } else {
-            Some(Duration::from_secs(config.audio.capture_duration_secs))
-        };
-        let capture_start = Instant::now();
-        let audio_buffer =
-            tokio::task::spawn_blocking(move || capturer_clone.capture(max_duration)).await??;
-        let capture_elapsed = capture_start.elapsed();
-
-        println!("Transcribing...");
-        let transcribe_start = Instant::now();
-        let transcript = transcriber.transcribe(&audio_buffer)?;
-        let transcribe_elapsed = transcribe_start.elapsed();
-        if transcript.trim().is_empty() {
-            eprintln!("No speech detected");
-            feedback.error("I didn't hear anything");
-            continue;
-        }
-        println!("Heard: {}", transcript);
-        let normalized = transcript
-            .trim()
-            .trim_end_matches(|c: char| c == '.' || c == '!' || c == '?');
-        if normalized.eq_ignore_ascii_case("help") {
-            let help = "Say: open <file>, launch <app>, set volume, mute, lock, sleep, or ask a question.";
-            println!("Help: {}", help);
-            feedback.say(help);
-            continue;
-        }
-
-        let intent_start = Instant::now();
-        let intent = match intent_client.infer_intent(&transcript, &config).await {
-            Ok(intent) => intent,
-            Err(err) => {
-                eprintln!("Intent error: {}", err);
-                feedback.error("Intent failed");
-                continue;
+    let transcriber = Transcriber::new(&config.transcription, None, debug, suppress_native_logs)?;
+    for (backend, result) in transcriber.benchmark(&audio) {
+        match result {
+            Ok((elapsed, text)) => {
+                println!("  {}: {:>6.2}s  \"{}\"", backend, elapsed.as_secs_f64(), text)
             }
-        };
-        let intent_elapsed = intent_start.elapsed();
-        let execute_start = Instant::now();
-        handle_intent(&executor, intent, &mut feedback);
-        let execute_elapsed = execute_start.elapsed();
-        if debug {
-            let total_elapsed = total_start.elapsed();
-            println!(
-                "{}",
-                colorize(
-                    &format!(
-                        "Timings: capture={:.2}s transcribe={:.2}s intent={:.2}s execute={:.2}s total={:.2}s",
-                        capture_elapsed.as_secs_f64(),
-                        transcribe_elapsed.as_secs_f64(),
-                        intent_elapsed.as_secs_f64(),
-                        execute_elapsed.as_secs_f64(),
-                        total_elapsed.as_secs_f64()
-                    ),
-                    Color::Cyan
-                )
-            );
+            Err(err) => println!("  {}: skipped ({})", backend, err),
         }
-        if debug {
-            println!("Command complete");
+    }
+    Ok(())
+}
+
+/// `--from-wav <path> [--execute]`: runs a recording through transcription
+/// and intent classification instead of the microphone, printing the intent
+/// as JSON. With `--execute` the intent is also carried out; actions that
+/// need confirmation are refused since nobody is there to confirm them.
+async fn replay_wav(
+    config: &Config,
+    path: &Path,
+    execute: bool,
+    debug: bool,
+    suppress_native_logs: bool,
+) -> Result<(), BuddyError> {
+    let audio = audio::load_clip(path)?;
+    let pipeline = Pipeline::new(config.clone(), debug, suppress_native_logs)?;
+    let transcript = pipeline.transcribe(audio).await?;
+    match &transcript.original {
+        Some(original) => println!("Heard: {} (translated: {})", original, transcript.text),
+        None => println!("Heard: {}", transcript.text),
+    }
+    output::transcript(&transcript);
+    let (intent, source) = pipeline
+        .classify(&transcript.text, transcript.spoken())
+        .await?;
+    println!("Intent: {}", intent.to_json());
+    output::intent(&intent, source);
+    if !execute {
+        return Ok(());
+    }
+    if needs_confirmation(config, &intent) || needs_clarification(config, &intent) {
+        eprintln!("Not executing {}: it requires confirmation", describe_intent(&intent));
+        output::error("execute", "requires confirmation");
+        return Ok(());
+    }
+    let mut feedback = FeedbackPlayer::new(&config.feedback, &config.phrases);
+    let executor = CommandExecutor::new(config);
+    let result = app::handle_intent(&executor, intent, &mut feedback, false, Source::Replay);
+    if let Ok(Handled::Choose(choices)) = result {
+        let labels: Vec<&str> = choices.iter().map(|choice| choice.label.as_str()).collect();
+        println!("Several matches: {}", labels.join("; "));
+    }
+    Ok(())
+}
+
+async fn run_ctl(args: &[String]) -> Result<(), BuddyError> {
+    if args.is_empty() {
+        eprintln!(
+            "Usage: buddy ctl <trigger|status|reload-config|history|quit|test-intent <phrase>|intent <phrase>|say <text>|text <phrase>>"
+        );
+        return Ok(());
+    }
+    if args.len() == 1 && args[0] == "status" {
+        println!("{}", ipc::running_instance().await.as_deref().unwrap_or("not running"));
+        return Ok(());
+    }
+    let reply = ipc::send_command(&args.join(" ")).await?;
+    println!("{}", reply);
+    Ok(())
+}
+
+/// Keeps two instances from fighting over the hotkey: one already running is
+/// asked to quit with `--takeover`, and otherwise this one doesn't start.
+async fn claim_instance(takeover: bool) -> Result<(), BuddyError> {
+    let Some(status) = ipc::running_instance().await else {
+        return Ok(());
+    };
+    if !takeover {
+        return Err(IpcError::AlreadyRunning(status).into());
+    }
+    println!("Asking the running buddy to quit");
+    ipc::send_command("quit").await?;
+    let asked = Instant::now();
+    while ipc::running_instance().await.is_some() {
+        if asked.elapsed() > TAKEOVER_TIMEOUT {
+            return Err(IpcError::StillRunning.into());
         }
+        tokio::time::sleep(Duration::from_millis(200)).await;
     }
+    Ok(())
 }
 
 #[derive(Clone, Copy)]
@@ -245,6 +570,22 @@ enum Color {
     Cyan,
 }
 
+/// Goes on with rule-based intents when `deepseek.fallback` allows it, and
+/// fails otherwise.
+fn intent_unavailable(config: &Config, err: IntentError) -> Result<(), BuddyError> {
+    if config.deepseek.fallback != IntentFallback::Rules {
+        return Err(err.into());
+    }
+    eprintln!(
+        "{}",
+        colorize(
+            &format!("Intent service unavailable ({}); continuing with rule-based intents", err),
+            Color::Yellow
+        )
+    );
+    Ok(())
+}
+
 fn colorize(text: &str, color: Color) -> String {
     if std::env::var_os("NO_COLOR").is_some() {
         return text.to_string();
@@ -283,75 +624,6 @@ fn load_library(name: &str) -> Option<HINSTANCE> {
     None
 }
 
-async fn wait_for_intent_ready(intent_client: &IntentClient) -> Result<(), IntentError> {
-    let attempts = 240;
-    let delay = Duration::from_secs(1);
-    for attempt in 1..=attempts {
-        match intent_client.wait_for_ready().await {
-            Ok(()) => return Ok(()),
-            Err(err) => {
-                if attempt == attempts {
-                    return Err(err);
-                }
-                eprintln!(
-                    "Intent service not ready (attempt {}/{}): {}",
-                    attempt, attempts, err
-                );
-                tokio::time::sleep(delay).await;
-            }
-        }
-    }
-    Ok(())
-}
-
-fn build_transcription_prompt(config: &Config) -> Option<String> {
-    let mut phrases = Vec::new();
-    if !config.files.is_empty() {
-        let mut keys: Vec<_> = config.files.keys().cloned().collect();
-        keys.sort();
-        for key in keys {
-            phrases.push(format!("Open {}.", key));
-        }
-    }
-    if !config.applications.is_empty() {
-        let mut keys: Vec<_> = config.applications.keys().cloned().collect();
-        keys.sort();
-        for key in keys {
-            phrases.push(format!("Launch {}.", key));
-        }
-    }
-    let system = &config.system;
-    if system.volume_mute {
-        phrases.push("Mute volume.".to_string());
-    }
-    if system.volume_up {
-        phrases.push("Volume up.".to_string());
-    }
-    if system.volume_down {
-        phrases.push("Volume down.".to_string());
-    }
-    if system.volume_set {
-        phrases.push("Set volume to 50.".to_string());
-    }
-    if system.sleep {
-        phrases.push("Go to sleep.".to_string());
-    }
-    if system.restart {
-        phrases.push("Restart computer.".to_string());
-    }
-    if system.shutdown {
-        phrases.push("Shut down computer.".to_string());
-    }
-    if system.lock {
-        phrases.push("Lock computer.".to_string());
-    }
-    if phrases.is_empty() {
-        None
-    } else {
-        Some(phrases.join(" "))
-    }
-}
-
 unsafe extern "C" fn silent_whisper_log(
     _level: std::os::raw::c_int,
     _text: *const std::os::raw::c_char,
@@ -359,42 +631,21 @@ unsafe extern "C" fn silent_whisper_log(
 ) {
 }
 
-fn handle_intent(
-    executor: &CommandExecutor<'_>,
-    intent: Intent,
-    feedback: &mut FeedbackPlayer,
-) {
-    let confidence = intent.confidence();
-    match executor.execute(&intent) {
-        Ok(result) => match result {
-            ExecutionResult::Action(message) => {
-                println!("{} (confidence {:.2})", message, confidence);
-                feedback.success();
-            }
-            ExecutionResult::Answer(response) => {
-                println!("Speaking response...");
-                println!("Answer: {} (confidence {:.2})", response, confidence);
-                feedback.say(&response);
-            }
-        },
-        Err(err) => {
-            eprintln!("Action failed: {}", err);
-            if matches!(err, executor::ExecutionError::UnknownIntent) {
-                feedback.error("I don't know how to do that");
-            } else {
-                feedback.error("Command failed");
-            }
-        }
-    }
-}
-
 #[derive(Debug)]
 enum BuddyError {
     Config(config::ConfigError),
     Audio(audio::AudioError),
     Transcription(transcription::TranscriptionError),
     Intent(IntentError),
+    Pipeline(PipelineError),
     Hotkey(HotkeyError),
+    Ipc(IpcError),
+    Http(HttpError),
+    Audit(AuditError),
+    Rag(RagError),
+    Autostart(AutostartError),
+    Bench(BenchError),
+    BenchStt(BenchSttError),
     Join(tokio::task::JoinError),
 }
 
@@ -405,7 +656,15 @@ impl std::fmt::Display for BuddyError {
             Self::Audio(err) => write!(f, "audio error: {}", err),
             Self::Transcription(err) => write!(f, "transcription error: {}", err),
             Self::Intent(err) => write!(f, "intent error: {}", err),
+            Self::Pipeline(err) => write!(f, "{}", err),
             Self::Hotkey(err) => write!(f, "hotkey error: {}", err),
+            Self::Ipc(err) => write!(f, "ipc error: {}", err),
+            Self::Http(err) => write!(f, "http error: {}", err),
+            Self::Audit(err) => write!(f, "audit error: {}", err),
+            Self::Rag(err) => write!(f, "document index error: {}", err),
+            Self::Autostart(err) => write!(f, "autostart error: {}", err),
+            Self::Bench(err) => write!(f, "benchmark error: {}", err),
+            Self::BenchStt(err) => write!(f, "benchmark error: {}", err),
             Self::Join(err) => write!(f, "task failed: {}", err),
         }
     }
@@ -418,7 +677,15 @@ impl std::error::Error for BuddyError {
             Self::Audio(err) => Some(err),
             Self::Transcription(err) => Some(err),
             Self::Intent(err) => Some(err),
+            Self::Pipeline(err) => Some(err),
             Self::Hotkey(err) => Some(err),
+            Self::Ipc(err) => Some(err),
+            Self::Http(err) => Some(err),
+            Self::Audit(err) => Some(err),
+            Self::Rag(err) => Some(err),
+            Self::Autostart(err) => Some(err),
+            Self::Bench(err) => Some(err),
+            Self::BenchStt(err) => Some(err),
             Self::Join(err) => Some(err),
         }
     }
@@ -430,6 +697,12 @@ impl From<audio::AudioError> for BuddyError {
     }
 }
 
+impl From<AuditError> for BuddyError {
+    fn from(err: AuditError) -> Self {
+        Self::Audit(err)
+    }
+}
+
 impl From<config::ConfigError> for BuddyError {
     fn from(err: config::ConfigError) -> Self {
         Self::Config(err)
@@ -448,12 +721,54 @@ impl From<IntentError> for BuddyError {
     }
 }
 
+impl From<PipelineError> for BuddyError {
+    fn from(err: PipelineError) -> Self {
+        Self::Pipeline(err)
+    }
+}
+
 impl From<HotkeyError> for BuddyError {
     fn from(err: HotkeyError) -> Self {
         Self::Hotkey(err)
     }
 }
 
+impl From<IpcError> for BuddyError {
+    fn from(err: IpcError) -> Self {
+        Self::Ipc(err)
+    }
+}
+
+impl From<HttpError> for BuddyError {
+    fn from(err: HttpError) -> Self {
+        Self::Http(err)
+    }
+}
+
+impl From<RagError> for BuddyError {
+    fn from(err: RagError) -> Self {
+        Self::Rag(err)
+    }
+}
+
+impl From<AutostartError> for BuddyError {
+    fn from(err: AutostartError) -> Self {
+        Self::Autostart(err)
+    }
+}
+
+impl From<BenchError> for BuddyError {
+    fn from(err: BenchError) -> Self {
+        Self::Bench(err)
+    }
+}
+
+impl From<BenchSttError> for BuddyError {
+    fn from(err: BenchSttError) -> Self {
+        Self::BenchStt(err)
+    }
+}
+
 impl From<tokio::task::JoinError> for BuddyError {
     fn from(err: tokio::task::JoinError) -> Self {
         Self::Join(err)
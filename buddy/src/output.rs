@@ -0,0 +1,141 @@
+//! `--output json`: each pipeline event as one JSON object per line on
+//! stdout, so other programs can wrap Buddy. Everything else Buddy prints is
+//! moved to stderr, leaving stdout to the events. Every object has `event`
+//! and `time` (milliseconds since the epoch).
+
+use buddy_core::{intent::Intent, metrics::Timings, transcription::Transcript};
+use serde_json::{json, Value};
+use std::{
+    fmt,
+    fs::File,
+    io::{self, Write},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The original stdout, once JSON output is on.
+static EVENTS: Mutex<Option<File>> = Mutex::new(None);
+
+/// Turns JSON output on: stdout is kept for the events and what was printed
+/// to it goes to stderr from here on.
+pub fn use_json() -> io::Result<()> {
+    io::stdout().flush()?;
+    let events = platform::take_stdout()?;
+    *EVENTS.lock().unwrap_or_else(|err| err.into_inner()) = Some(events);
+    Ok(())
+}
+
+/// Writes `event` with the fields of `fields`, when JSON output is on.
+pub fn emit(event: &str, fields: Value) {
+    let mut events = EVENTS.lock().unwrap_or_else(|err| err.into_inner());
+    let Some(out) = events.as_mut() else {
+        return;
+    };
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis() as u64);
+    let mut line = json!({ "event": event, "time": time });
+    if let (Some(line), Value::Object(fields)) = (line.as_object_mut(), fields) {
+        line.extend(fields);
+    }
+    // A reader that went away shouldn't stop Buddy.
+    let _ = writeln!(out, "{}", line).and_then(|()| out.flush());
+}
+
+pub fn transcript(transcript: &Transcript) {
+    emit(
+        "transcript",
+        json!({
+            "text": transcript.text,
+            "original": transcript.original,
+            "language": transcript.language,
+            "confidence": transcript.confidence,
+        }),
+    );
+}
+
+/// `source` is what classified it: "model", "rules", "calculator", ...
+pub fn intent(intent: &Intent, source: &str) {
+    emit(
+        "intent",
+        json!({ "intent": intent.to_json(), "source": source }),
+    );
+}
+
+/// What came of running `intent`. `fields` are added to `action`, `target`
+/// and `succeeded: true`, and can override them.
+pub fn result(intent: &Intent, fields: Value) {
+    let meant = intent.to_json();
+    let mut event = json!({
+        "action": meant["action"],
+        "target": meant["target"],
+        "succeeded": true,
+    });
+    if let (Some(event), Value::Object(fields)) = (event.as_object_mut(), fields) {
+        event.extend(fields);
+    }
+    emit("result", event);
+}
+
+/// `stage` is where it failed: "capture", "transcribe", "intent", ...
+pub fn error(stage: &str, err: impl fmt::Display) {
+    emit(
+        "error",
+        json!({ "stage": stage, "message": err.to_string() }),
+    );
+}
+
+pub fn timings(timings: &Timings) {
+    let ms = |duration: std::time::Duration| duration.as_millis() as u64;
+    emit(
+        "timings",
+        json!({
+            "capture_ms": ms(timings.capture),
+            "transcribe_ms": ms(timings.transcribe),
+            "intent_ms": ms(timings.intent),
+            "execute_ms": ms(timings.execute),
+            "total_ms": ms(timings.total),
+        }),
+    );
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::*;
+    use std::os::windows::io::FromRawHandle;
+    use windows::Win32::System::Console::{
+        GetStdHandle, SetStdHandle, STD_ERROR_HANDLE, STD_OUTPUT_HANDLE,
+    };
+
+    /// Rust looks the standard handles up on every write, so pointing the
+    /// output handle at stderr moves `println!` there.
+    pub fn take_stdout() -> io::Result<File> {
+        unsafe {
+            let stdout = GetStdHandle(STD_OUTPUT_HANDLE).map_err(io::Error::other)?;
+            let stderr = GetStdHandle(STD_ERROR_HANDLE).map_err(io::Error::other)?;
+            SetStdHandle(STD_OUTPUT_HANDLE, stderr).map_err(io::Error::other)?;
+            Ok(File::from_raw_handle(stdout.0))
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod platform {
+    use super::*;
+    use std::os::fd::FromRawFd;
+
+    pub fn take_stdout() -> io::Result<File> {
+        unsafe {
+            let stdout = libc::dup(libc::STDOUT_FILENO);
+            if stdout == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::dup2(libc::STDERR_FILENO, libc::STDOUT_FILENO) == -1 {
+                let err = io::Error::last_os_error();
+                libc::close(stdout);
+                return Err(err);
+            }
+            Ok(File::from_raw_fd(stdout))
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use crate::ipc::{ControlCommand, ControlRequest};
+use buddy_core::audit::Source;
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Ctrl-C presses (or termination requests) seen so far. A signal handler
+/// can do little more than bump a counter, so a watcher thread does the rest.
+static REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Exit code for a second Ctrl-C, as a shell reports death by SIGINT.
+const INTERRUPTED: i32 = 130;
+
+/// Catches Ctrl-C (SIGINT and SIGTERM off Windows, console close events on
+/// it) and turns the first one into a quit request, after stopping any
+/// recording in progress through `cancel`. The main loop then shuts down as
+/// for `buddy-ctl quit`. A second Ctrl-C exits at once.
+pub fn install(cancel: Arc<AtomicBool>, tx: UnboundedSender<ControlRequest>) {
+    if let Err(err) = platform::install() {
+        eprintln!("Failed to install the Ctrl-C handler: {}", err);
+        return;
+    }
+    let spawned = thread::Builder::new()
+        .name("shutdown".into())
+        .spawn(move || {
+            while REQUESTS.load(Ordering::SeqCst) == 0 {
+                thread::sleep(POLL_INTERVAL);
+            }
+            println!("Shutting down... (press Ctrl-C again to exit immediately)");
+            cancel.store(true, Ordering::Relaxed);
+            let (request, _reply) = ControlRequest::new(ControlCommand::Quit, Source::Console);
+            let _ = tx.send(request);
+        });
+    if let Err(err) = spawned {
+        eprintln!("Failed to start the shutdown thread: {}", err);
+    }
+}
+
+/// Called from the signal handler: counts the request and exits on the second.
+fn request() {
+    if REQUESTS.fetch_add(1, Ordering::SeqCst) > 0 {
+        platform::exit_now();
+    }
+}
+
+#[cfg(unix)]
+mod platform {
+    extern "C" fn on_signal(_signal: libc::c_int) {
+        super::request();
+    }
+
+    pub fn install() -> Result<(), String> {
+        for signal in [libc::SIGINT, libc::SIGTERM] {
+            let handler = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            if unsafe { libc::signal(signal, handler) } == libc::SIG_ERR {
+                return Err(std::io::Error::last_os_error().to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Skips destructors and buffered output, which may be mid-update in the
+    /// interrupted thread.
+    pub fn exit_now() {
+        unsafe { libc::_exit(super::INTERRUPTED) };
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::{thread, time::Duration};
+    use windows::Win32::{
+        Foundation::BOOL,
+        System::Console::{SetConsoleCtrlHandler, CTRL_BREAK_EVENT, CTRL_CLOSE_EVENT, CTRL_C_EVENT},
+    };
+
+    /// Windows ends the process once the handler returns from a close event,
+    /// so it waits here for the main loop to say goodbye and save the session.
+    const CLOSE_GRACE: Duration = Duration::from_secs(4);
+
+    unsafe extern "system" fn on_ctrl(kind: u32) -> BOOL {
+        match kind {
+            CTRL_C_EVENT | CTRL_BREAK_EVENT => {
+                super::request();
+                BOOL::from(true)
+            }
+            CTRL_CLOSE_EVENT => {
+                super::request();
+                thread::sleep(CLOSE_GRACE);
+                BOOL::from(true)
+            }
+            _ => BOOL::from(false),
+        }
+    }
+
+    pub fn install() -> Result<(), String> {
+        unsafe { SetConsoleCtrlHandler(Some(on_ctrl), BOOL::from(true)) }
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn exit_now() {
+        std::process::exit(super::INTERRUPTED);
+    }
+}